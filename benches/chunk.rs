@@ -0,0 +1,47 @@
+//! Decoding and encoding the data of ChunkData, for a column of 24 sections.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use mc_protocol::testing::Rng;
+use mc_protocol::world::chunk::ChunkColumn;
+
+const MIN_SECTION: i32 = -4;
+const SECTIONS: usize = 24;
+
+/// Stone with ores below y 64, a few layers of dirt and grass, then air.
+fn terrain() -> ChunkColumn {
+    let mut rng = Rng::new(64);
+    let mut column = ChunkColumn::new(0, 0, MIN_SECTION, SECTIONS);
+    for y in MIN_SECTION * 16..68 {
+        for z in 0..16 {
+            for x in 0..16 {
+                let state = match y {
+                    64..=66 => 10,
+                    67 => 9,
+                    _ if rng.next_u64().is_multiple_of(20) => 70 + (rng.next_u64() % 8) as i32,
+                    _ => 1
+                };
+                column.set(x, y, z, state);
+            }
+        }
+    }
+    column
+}
+
+fn chunk(c: &mut Criterion) {
+    let column = terrain();
+    let (bitmask, data) = column.encode();
+    let mut group = c.benchmark_group("chunk");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("decode", |b| b.iter(|| {
+        ChunkColumn::decode(0, 0, MIN_SECTION, SECTIONS, &bitmask, &data).unwrap()
+    }));
+    group.bench_function("encode", |b| b.iter(|| column.encode()));
+    let mut out = Vec::with_capacity(data.len() + 5);
+    group.bench_function("write_into", |b| b.iter(|| {
+        out.clear();
+        column.write_into(&mut out).unwrap()
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, chunk);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+//! Writing and reading uncompressed frames of typical sizes.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mc_protocol::connection::frame::{self, FrameLimits};
+use mc_protocol::protocol::{Direction, RawPacket, State};
+use mc_protocol::testing::Rng;
+
+const SIZES: [usize; 3] = [16, 1024, 64 * 1024];
+
+fn packet(len: usize) -> RawPacket {
+    let mut body = vec![0u8; len];
+    Rng::new(len as u64).fill(&mut body);
+    RawPacket{ id: 0x22, state: State::Play, direction: Direction::ClientBound, body }
+}
+
+fn write_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("write_frame");
+    for len in SIZES {
+        let packet = packet(len);
+        let mut out = Vec::with_capacity(len + 8);
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &packet, |b, packet| b.iter(|| {
+            out.clear();
+            packet.write_frame(&mut out).unwrap();
+        }));
+    }
+    group.finish();
+}
+
+fn read_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_frame");
+    for len in SIZES {
+        let mut framed = Vec::new();
+        packet(len).write_frame(&mut framed).unwrap();
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("allocating", len), &framed, |b, framed| b.iter(|| {
+            frame::read_frame(&mut &framed[..], &FrameLimits::default(), State::Play).unwrap()
+        }));
+        let mut body = Vec::new();
+        group.bench_with_input(BenchmarkId::new("reused", len), &framed, |b, framed| b.iter(|| {
+            frame::read_frame_into(&mut &framed[..], &mut body, &FrameLimits::default(), State::Play).unwrap()
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, write_frame, read_frame);
+criterion_main!(benches);
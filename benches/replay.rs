@@ -0,0 +1,80 @@
+//! Replaying a whole session log: reading the records and decoding every
+//! chunk in them.
+//!
+//! Set `MC_PROTOCOL_BENCH_LOG` to a log written by `capture::recorder::Recorder`
+//! to replay a real session. Without it, a session of keep alives, movement
+//! and 200 chunks is generated.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use mc_protocol::capture::recorder::{Recorder, Replayer};
+use mc_protocol::protocol::{Direction, RawPacket, State};
+use mc_protocol::testing::Rng;
+use mc_protocol::world::chunk::ChunkColumn;
+use std::convert::TryInto;
+
+const CHUNK_DATA: i32 = 0x22;
+
+/// A chunk packet body: the section count, the bitmask and the data.
+fn chunk_body(x: i32, z: i32, rng: &mut Rng) -> Vec<u8> {
+    let mut column = ChunkColumn::new(x, z, 0, 16);
+    for y in 0..64 {
+        for i in 0..256 {
+            let state = if rng.next_u64().is_multiple_of(10) { 2 } else { 1 };
+            column.set(i % 16, y, i / 16, state);
+        }
+    }
+    let (bitmask, data) = column.encode();
+    let mut body = vec![bitmask.len() as u8];
+    for long in bitmask {
+        body.extend_from_slice(&long.to_be_bytes());
+    }
+    body.extend_from_slice(&data);
+    body
+}
+
+fn generated_log() -> Vec<u8> {
+    let mut rng = Rng::new(1);
+    let mut recorder = Recorder::new(Vec::new(), 755).unwrap();
+    recorder.set_state(State::Play);
+    for i in 0..200 {
+        let mut position = vec![0u8; 33];
+        rng.fill(&mut position);
+        recorder.record(&RawPacket{ id: 0x11, state: State::Play, direction: Direction::ServerBound, body: position }).unwrap();
+        recorder.record(&RawPacket{ id: 0x21, state: State::Play, direction: Direction::ClientBound, body: (i as i64).to_be_bytes().to_vec() }).unwrap();
+        let body = chunk_body(i % 15, i / 15, &mut rng);
+        recorder.record(&RawPacket{ id: CHUNK_DATA, state: State::Play, direction: Direction::ClientBound, body }).unwrap();
+    }
+    recorder.into_inner()
+}
+
+/// Decodes the chunks of a generated log, a real log is only read.
+fn replay(log: &[u8], decode_chunks: bool) -> usize {
+    let mut replayer = Replayer::new(log).unwrap();
+    let mut packets = 0;
+    while let Some(record) = replayer.next_record().unwrap() {
+        let packet = &record.envelope.packet;
+        if decode_chunks && packet.id == CHUNK_DATA {
+            let longs = packet.body[0] as usize;
+            let bitmask: Vec<i64> = packet.body[1..1 + longs * 8].chunks(8)
+                .map(|long| i64::from_be_bytes(long.try_into().unwrap()))
+                .collect();
+            ChunkColumn::decode(0, 0, 0, 16, &bitmask, &packet.body[1 + longs * 8..]).unwrap();
+        }
+        packets += 1;
+    }
+    packets
+}
+
+fn session(c: &mut Criterion) {
+    let (log, generated) = match std::env::var_os("MC_PROTOCOL_BENCH_LOG") {
+        Some(path) => (std::fs::read(path).expect("can not read MC_PROTOCOL_BENCH_LOG"), false),
+        None => (generated_log(), true)
+    };
+    let mut group = c.benchmark_group("replay");
+    group.throughput(Throughput::Bytes(log.len() as u64));
+    group.sample_size(20);
+    group.bench_function("session", |b| b.iter(|| replay(&log, generated)));
+    group.finish();
+}
+
+criterion_group!(benches, session);
+criterion_main!(benches);
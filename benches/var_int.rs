@@ -0,0 +1,49 @@
+//! VarInt and VarLong coding, for values of every encoded length.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mc_protocol::segment::implementation::var::{decode_var_int, decode_var_long, read_var_int, read_var_long, write_var_int, write_var_long};
+
+const INTS: [i32; 5] = [1, 300, 70_000, 10_000_000, -1];
+const LONGS: [i64; 3] = [1, 1 << 40, -1];
+
+fn var_int(c: &mut Criterion) {
+    let mut group = c.benchmark_group("var_int");
+    for value in INTS {
+        let mut encoded = Vec::new();
+        write_var_int(value, &mut encoded).unwrap();
+        group.bench_with_input(BenchmarkId::new("read", encoded.len()), &encoded, |b, encoded| b.iter(|| {
+            read_var_int(&mut &encoded[..]).unwrap()
+        }));
+        group.bench_with_input(BenchmarkId::new("decode", encoded.len()), &encoded, |b, encoded| b.iter(|| {
+            decode_var_int(black_box(encoded)).unwrap()
+        }));
+        let mut out = Vec::with_capacity(5);
+        group.bench_with_input(BenchmarkId::new("write", encoded.len()), &value, |b, value| b.iter(|| {
+            out.clear();
+            write_var_int(black_box(*value), &mut out).unwrap();
+        }));
+    }
+    group.finish();
+}
+
+fn var_long(c: &mut Criterion) {
+    let mut group = c.benchmark_group("var_long");
+    for value in LONGS {
+        let mut encoded = Vec::new();
+        write_var_long(value, &mut encoded).unwrap();
+        group.bench_with_input(BenchmarkId::new("read", encoded.len()), &encoded, |b, encoded| b.iter(|| {
+            read_var_long(&mut &encoded[..]).unwrap()
+        }));
+        group.bench_with_input(BenchmarkId::new("decode", encoded.len()), &encoded, |b, encoded| b.iter(|| {
+            decode_var_long(black_box(encoded)).unwrap()
+        }));
+        let mut out = Vec::with_capacity(10);
+        group.bench_with_input(BenchmarkId::new("write", encoded.len()), &value, |b, value| b.iter(|| {
+            out.clear();
+            write_var_long(black_box(*value), &mut out).unwrap();
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, var_int, var_long);
+criterion_main!(benches);
@@ -0,0 +1,321 @@
+//! A proxy with a tiny HTTP status page.
+//!
+//! ```text
+//! cargo run --example dashboard --features steven,metrics -- 127.0.0.1:8080 0.0.0.0:25566=127.0.0.1:25565 [...]
+//! ```
+//!
+//! The first argument is the address of the status page, every other argument
+//! is a `listen=backend` route. Every connection follows the protocol state
+//! from its handshake, packets are relayed through a `MiddlewareChain` with a
+//! `PluginChannelFilter`, and what the installed metrics see is counted per
+//! route. `/` lists the routes, `/metrics` serves the `Registry` in the
+//! Prometheus format.
+//!
+//! Packets are decoded as 1.17 and neither compression nor encryption is set
+//! up, so run the backend with `online-mode=false` and
+//! `network-compression-threshold=-1`.
+//!
+//! Tracing is left out: the crate emits no spans or events, what it reports
+//! goes through `Metrics`, which the page above is built on. A subscriber
+//! would only see the few errors the example prints itself, so those go to
+//! stderr instead: failed connections, dropped packets and status requests.
+use mc_protocol::connection::middleware::{relay, Action, MiddlewareChain};
+use mc_protocol::connection::state::StateMachine;
+use mc_protocol::connection::stream::PacketStream;
+use mc_protocol::metrics::{self, Decoded, Metrics, Registry};
+use mc_protocol::protocol::implementation::steven::v1_17::Proto_1_17;
+use mc_protocol::protocol::trailing::TrailingBytes;
+use mc_protocol::protocol::{Direction, Protocol, State};
+use mc_protocol::proxy::plugin_channel::{ChannelPolicy, PluginChannelFilter};
+use mc_protocol::segment::Segment;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct RouteStats{
+    connections: AtomicUsize,
+    /// Open connections by the state they are in.
+    states: Mutex<HashMap<State, usize>>,
+    /// Packets decoded, as reported to the installed metrics.
+    packets: AtomicUsize,
+    failures: AtomicUsize,
+    bytes: AtomicUsize,
+    /// Packets per second over the last sampling interval.
+    packet_rate: AtomicUsize,
+    channels: Mutex<PluginChannelFilter>
+}
+
+impl Default for RouteStats{
+    fn default() -> Self {
+        RouteStats{
+            connections: Default::default(),
+            states: Default::default(),
+            packets: Default::default(),
+            failures: Default::default(),
+            bytes: Default::default(),
+            packet_rate: Default::default(),
+            channels: Mutex::new(PluginChannelFilter::with_default(ChannelPolicy::Allow))
+        }
+    }
+}
+
+impl RouteStats{
+    fn switch(&self, from: Option<State>, to: Option<State>) {
+        let mut states = self.states.lock().unwrap();
+        if let Some(from) = from {
+            if let Some(count) = states.get_mut(&from) {
+                *count -= 1;
+            }
+        }
+        if let Some(to) = to {
+            *states.entry(to).or_insert(0) += 1;
+        }
+    }
+}
+
+type Routes = Arc<Mutex<BTreeMap<String, Arc<RouteStats>>>>;
+
+thread_local! {
+    /// The route the current thread relays packets for.
+    static ROUTE: RefCell<Option<Arc<RouteStats>>> = const { RefCell::new(None) };
+}
+
+fn on_route(f: impl FnOnce(&RouteStats)) {
+    ROUTE.with(|route| {
+        if let Some(route) = &*route.borrow() {
+            f(route)
+        }
+    });
+}
+
+/// The installed `Metrics`: everything goes to the registry, and is counted
+/// for the route of the reporting thread.
+#[derive(Default)]
+struct Dashboard{
+    registry: Registry
+}
+
+impl Metrics for Dashboard{
+    fn packet_decoded(&self, packet: &Decoded) {
+        self.registry.packet_decoded(packet);
+        on_route(|route| {
+            route.packets.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    fn decode_failed(&self, protocol: &'static str, state: State, direction: Direction, id: i32) {
+        self.registry.decode_failed(protocol, state, direction, id);
+        on_route(|route| {
+            route.failures.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    fn trailing_bytes(&self, protocol: &'static str, trailing: &TrailingBytes) {
+        self.registry.trailing_bytes(protocol, trailing);
+    }
+
+    fn bytes_read(&self, bytes: usize) {
+        self.registry.bytes_read(bytes);
+        on_route(|route| {
+            route.bytes.fetch_add(bytes, Ordering::Relaxed);
+        });
+    }
+
+    fn bytes_written(&self, bytes: usize) {
+        self.registry.bytes_written(bytes);
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let status_addr = args.next().expect("missing status page address");
+    let dashboard = Arc::new(Dashboard::default());
+    metrics::install(dashboard.clone())?;
+    let routes: Routes = Default::default();
+
+    for route in args {
+        let (listen, backend) = route.split_once('=').expect("routes are formatted as listen=backend");
+        let stats = Arc::new(RouteStats::default());
+        routes.lock().unwrap().insert(route.clone(), stats.clone());
+        let listener = TcpListener::bind(listen)?;
+        let backend = backend.to_string();
+        thread::spawn(move || accept_loop(listener, backend, stats));
+    }
+
+    let sampler = routes.clone();
+    thread::spawn(move || sample_rates(sampler));
+
+    let status = TcpListener::bind(status_addr)?;
+    for stream in status.incoming() {
+        if let Err(e) = serve_status(stream?, &routes, &dashboard.registry) {
+            eprintln!("status page: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn accept_loop(listener: TcpListener, backend: String, stats: Arc<RouteStats>) {
+    for client in listener.incoming() {
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => {
+                eprintln!("accept: {}", e);
+                continue;
+            }
+        };
+        let stats = stats.clone();
+        let backend = backend.clone();
+        thread::spawn(move || {
+            if let Err(e) = proxy(client, &backend, stats) {
+                eprintln!("{}: {}", backend, e);
+            }
+        });
+    }
+}
+
+fn proxy(client: TcpStream, backend: &str, stats: Arc<RouteStats>) -> std::io::Result<()> {
+    let server = TcpStream::connect(backend)?;
+    let machine = Arc::new(Mutex::new(StateMachine::new()));
+    stats.connections.fetch_add(1, Ordering::Relaxed);
+    stats.switch(None, Some(State::Handshaking));
+
+    let upstream = {
+        let (client, server) = (client.try_clone()?, server.try_clone()?);
+        let (machine, stats) = (machine.clone(), stats.clone());
+        thread::spawn(move || forward(client, server, Direction::ServerBound, &machine, stats))
+    };
+    forward(server, client, Direction::ClientBound, &machine, stats.clone());
+    let _ = upstream.join();
+
+    stats.switch(Some(machine.lock().unwrap().state()), None);
+    stats.connections.fetch_sub(1, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Relays the packets going in `direction` until either side hangs up.
+fn forward(from: TcpStream, to: TcpStream, direction: Direction, machine: &Arc<Mutex<StateMachine>>, stats: Arc<RouteStats>) {
+    ROUTE.with(|route| *route.borrow_mut() = Some(stats.clone()));
+    let mut chain = MiddlewareChain::new();
+    let channels = stats.clone();
+    chain.push(move |_, packet: &mut Proto_1_17| match channels.channels.lock().unwrap().filter_packet(packet.clone()) {
+        Some(filtered) => {
+            *packet = filtered;
+            Action::Forward
+        }
+        None => Action::Drop
+    });
+    let states = machine.clone();
+    chain.push(move |direction, packet: &mut Proto_1_17| follow_state(&states, &stats, direction, packet));
+
+    let mut from = PacketStream::new(from);
+    let mut to = PacketStream::new(to);
+    from.record_history(8);
+    let error = loop {
+        // The other direction may switch the state while this one waits, so it
+        // is only looked at once the next frame arrived.
+        match from.get_ref().peek(&mut [0]) {
+            Ok(0) => break None,
+            Ok(_) => {}
+            Err(e) => break Some(e)
+        }
+        let state = machine.lock().unwrap().state();
+        if let Err(e) = relay(&mut chain, &mut from, &mut to, state, direction) {
+            break Some(e);
+        }
+    };
+    match error {
+        Some(e) if e.kind() != ErrorKind::UnexpectedEof => eprintln!("{:?}: {}", direction, e),
+        _ => {}
+    }
+    let _ = from.get_ref().shutdown(Shutdown::Both);
+    let _ = to.get_ref().shutdown(Shutdown::Both);
+}
+
+/// Follows the switch the packet causes before it is forwarded, so the other
+/// direction already reads in the new state when the answer arrives. Packets
+/// 1.17 does not know are passed on as they are.
+fn follow_state(machine: &Mutex<StateMachine>, stats: &RouteStats, direction: Direction, packet: &mut Proto_1_17) -> Action<Proto_1_17> {
+    if packet.packet_name() == "Raw" {
+        return Action::Forward;
+    }
+    let mut machine = machine.lock().unwrap();
+    let before = machine.state();
+    let mut body = Vec::new();
+    let observed = packet.write_to_stream(&mut body)
+        .and_then(|_| machine.observe::<Proto_1_17>(direction, packet.id(), &body, ErrorKind::InvalidData));
+    match observed {
+        Ok(()) => {
+            if machine.state() != before {
+                stats.switch(Some(before), Some(machine.state()));
+            }
+            Action::Forward
+        }
+        Err(e) => {
+            eprintln!("{:?} {}: {}", direction, packet.packet_name(), e);
+            Action::Drop
+        }
+    }
+}
+
+fn sample_rates(routes: Routes) {
+    let mut previous: BTreeMap<String, usize> = BTreeMap::new();
+    let mut last = Instant::now();
+    loop {
+        thread::sleep(Duration::from_secs(1));
+        let elapsed = last.elapsed().as_secs_f64();
+        last = Instant::now();
+        for (name, stats) in routes.lock().unwrap().iter() {
+            let packets = stats.packets.load(Ordering::Relaxed);
+            let before = previous.insert(name.clone(), packets).unwrap_or(0);
+            let rate = (packets - before) as f64 / elapsed;
+            stats.packet_rate.store(rate as usize, Ordering::Relaxed);
+        }
+    }
+}
+
+fn serve_status(mut stream: TcpStream, routes: &Routes, registry: &Registry) -> std::io::Result<()> {
+    let mut request = [0u8; 1024];
+    let read = stream.read(&mut request)?;
+    let request = String::from_utf8_lossy(&request[..read]);
+
+    let body = match request.split_whitespace().nth(1) {
+        Some("/metrics") => registry.render(),
+        _ => route_table(routes)
+    };
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+fn route_table(routes: &Routes) -> String {
+    let mut body = String::from("route\tconnections\tstates\tpackets\tpackets/s\tfailures\tbytes\tplugin messages\n");
+    for (name, stats) in routes.lock().unwrap().iter() {
+        let mut states: Vec<String> = stats.states.lock().unwrap().iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(state, count)| format!("{:?}={}", state, count))
+            .collect();
+        states.sort();
+        let channels = stats.channels.lock().unwrap();
+        let plugin_messages = channels.unlisted_counters().forwarded + channels.all_counters().map(|(_, counters)| counters.forwarded).sum::<u64>();
+        body.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            name,
+            stats.connections.load(Ordering::Relaxed),
+            states.join(","),
+            stats.packets.load(Ordering::Relaxed),
+            stats.packet_rate.load(Ordering::Relaxed),
+            stats.failures.load(Ordering::Relaxed),
+            stats.bytes.load(Ordering::Relaxed),
+            plugin_messages
+        ));
+    }
+    body
+}
@@ -0,0 +1,11 @@
+//! Prints the Markdown documentation of the 1.17 protocol definition.
+//!
+//! ```text
+//! cargo run --example docgen --features steven > protocol-1.17.md
+//! ```
+use mc_protocol::protocol::docgen;
+use mc_protocol::protocol::implementation::steven::v1_17::Proto_1_17;
+
+fn main() {
+    print!("{}", docgen::markdown::<Proto_1_17>());
+}
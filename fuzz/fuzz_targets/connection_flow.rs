@@ -0,0 +1,84 @@
+//! Drives the server side of a connection through a whole flow, following
+//! the state like a server does with `PacketStream::read_tracked`: the
+//! handshake, status or login, configuration and play, and back to
+//! configuration.
+//!
+//! The first byte picks the protocol and what the server enables once the
+//! client started logging in: bit 0 compression with the threshold in bits 2
+//! to 6, bit 1 encryption, and bit 7 picks 1.17 instead of 1.21, which has no
+//! configuration state. The rest is what the client sends, read until it ends
+//! or the server would close the connection.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use mc_protocol::connection::state::StateMachine;
+use mc_protocol::connection::stream::PacketStream;
+use mc_protocol::protocol::implementation::steven::v1_17::Proto_1_17;
+use mc_protocol::protocol::implementation::steven::v1_21::Proto_1_21;
+use mc_protocol::protocol::{Direction, Protocol, State};
+use std::io::{Read, Write};
+
+/// What the client sent, and a sink for what the server answers.
+struct Client<'a>(&'a [u8]);
+
+impl Read for Client<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Client<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn run<P: Protocol + 'static>(flags: u8, input: &[u8]) {
+    let mut stream = PacketStream::new(Client(input));
+    stream.track_state(StateMachine::new(), Direction::ServerBound);
+    let mut logging_in = false;
+    loop {
+        let packet = match stream.read_tracked::<P>() {
+            Ok(packet) => packet,
+            // The server closes the connection on anything it can not read.
+            Err(_) => return
+        };
+        if logging_in || stream.state() != Some(State::Login) || packet.packet_name() != "LoginStart" {
+            continue;
+        }
+        logging_in = true;
+        if flags & 0b10 != 0 {
+            stream.enable_encryption(&[flags; 16]);
+        }
+        if flags & 0b1 != 0 {
+            let threshold = ((flags >> 2) & 0x1F) as usize * 64;
+            if stream.set_compression(Some(threshold)).is_err() {
+                return;
+            }
+        }
+        // Takes 1.17 to Play, 1.21 waits for the client to acknowledge it.
+        let success = P::descriptors().into_iter()
+            .find(|kind| kind.descriptor.name == "LoginSuccess")
+            .map(|kind| kind.create());
+        if let Some(success) = success {
+            if stream.write_tracked(&success).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (&flags, input) = match data.split_first() {
+        Some(split) => split,
+        None => return
+    };
+    if flags & 0x80 == 0 {
+        run::<Proto_1_21>(flags, input);
+    } else {
+        run::<Proto_1_17>(flags, input);
+    }
+});
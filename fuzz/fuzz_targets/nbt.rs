@@ -0,0 +1,11 @@
+//! Feeds arbitrary bytes to the NBT validation that guards the NBT decoder.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use mc_protocol::segment::implementation::nbt::{copy_named_tag, MAX_DEPTH};
+
+fuzz_target!(|data: &[u8]| {
+    let mut copied = Vec::new();
+    if copy_named_tag(&mut &data[..], &mut copied, MAX_DEPTH).is_ok() {
+        assert!(data.starts_with(&copied));
+    }
+});
@@ -0,0 +1,34 @@
+//! Decodes a single packet body in any state and direction.
+//!
+//! The first byte selects the state and direction, followed by the VarInt
+//! packet id and the body. Decoders must return an error rather than panic,
+//! so `packet_by_id` is called directly instead of through `decode_untrusted`,
+//! which would hide the panic.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use mc_protocol::protocol::implementation::steven::v1_17::Proto_1_17;
+use mc_protocol::protocol::{Direction, Protocol, State};
+use mc_protocol::segment::implementation::var::read_var_int;
+
+fuzz_target!(|data: &[u8]| {
+    let (&selector, mut input) = match data.split_first() {
+        Some(split) => split,
+        None => return
+    };
+    let state = match selector & 0b11 {
+        0 => State::Handshaking,
+        1 => State::Status,
+        2 => State::Login,
+        _ => State::Play
+    };
+    let direction = if selector & 0b100 == 0 {
+        Direction::ServerBound
+    } else {
+        Direction::ClientBound
+    };
+    let id = match read_var_int(&mut input) {
+        Ok(id) => id,
+        Err(_) => return
+    };
+    let _ = Proto_1_17::packet_by_id(state, direction, id, &mut &input[..]);
+});
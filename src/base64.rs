@@ -0,0 +1,44 @@
+//! Standard base64 with padding, for proxy credentials and favicons.
+use std::io::{Error, ErrorKind};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(value >> (18 - 6 * i) & 0x3F) as usize] as char);
+            }else{
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub fn decode(text: &str) -> std::io::Result<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return Err(Error::new(ErrorKind::InvalidData, "base64 length is not a multiple of 4"));
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for (i, chunk) in text.chunks(4).enumerate() {
+        let last = i == text.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return Err(Error::new(ErrorKind::InvalidData, "misplaced base64 padding"));
+        }
+        let mut value = 0u32;
+        for c in &chunk[..4 - padding] {
+            let digit = ALPHABET.iter().position(|a| a == c)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("invalid base64 character {:?}", *c as char)))?;
+            value = value << 6 | digit as u32;
+        }
+        value <<= 6 * padding;
+        out.extend_from_slice(&value.to_be_bytes()[1..4 - padding]);
+    }
+    Ok(out)
+}
@@ -0,0 +1,145 @@
+//! Self describing wrapper for packets that are stored rather than sent.
+//!
+//! Every record carries the protocol version, state, direction and time it
+//! was captured at, so a file can be interpreted without knowing where it came
+//! from. Layout of a record:
+//!
+//! ```text
+//! u8      envelope format version
+//! VarInt  protocol version
+//! u8      state
+//! u8      direction
+//! u64     milliseconds since the unix epoch
+//! VarInt  packet id
+//! VarInt  body length
+//! [u8]    body
+//! ```
+use crate::protocol::{Direction, Protocol, RawPacket, State};
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Format version written by this version of the crate.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Upper bound for the body of a single record, to not allocate whatever a
+/// corrupt length prefix asks for.
+pub const MAX_BODY_LEN: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Envelope{
+    pub protocol_version: i32,
+    pub timestamp: SystemTime,
+    pub packet: RawPacket
+}
+
+impl Envelope{
+    pub fn new(protocol_version: i32, packet: RawPacket) -> Self {
+        Envelope{
+            protocol_version,
+            timestamp: SystemTime::now(),
+            packet
+        }
+    }
+
+    pub fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let millis = self.timestamp.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        writer.write_u8(ENVELOPE_VERSION)?;
+        write_var_int(self.protocol_version, writer)?;
+        writer.write_u8(self.packet.state.id())?;
+        writer.write_u8(self.packet.direction.id())?;
+        writer.write_u64::<BigEndian>(millis)?;
+        write_var_int(self.packet.id, writer)?;
+        write_var_int(self.packet.body.len() as i32, writer)?;
+        writer.write_all(&self.packet.body)
+    }
+
+    /// Reads a record as it was written, without applying any upgrades.
+    pub fn read_from_stream<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let version = reader.read_u8()?;
+        if version != ENVELOPE_VERSION {
+            return Err(Error::new(ErrorKind::InvalidData, format!("unsupported envelope version {}", version)));
+        }
+        let protocol_version = read_var_int(reader)?;
+        let state = State::from_id(reader.read_u8()?)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid state"))?;
+        let direction = Direction::from_id(reader.read_u8()?)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid direction"))?;
+        let timestamp = UNIX_EPOCH + Duration::from_millis(reader.read_u64::<BigEndian>()?);
+        let id = read_var_int(reader)?;
+        let len = read_var_int(reader)?;
+        if len < 0 || len as usize > MAX_BODY_LEN {
+            return Err(Error::new(ErrorKind::InvalidData, format!("invalid body length {}", len)));
+        }
+        let mut body = vec![0u8; len as usize];
+        reader.read_exact(&mut body)?;
+        Ok(Envelope{
+            protocol_version,
+            timestamp,
+            packet: RawPacket{ id, state, direction, body }
+        })
+    }
+
+    /// Decodes the packet with the given protocol, which has to match the
+    /// protocol version of the envelope.
+    pub fn decode<P: Protocol>(&self) -> std::io::Result<Option<P>> {
+        if self.protocol_version != P::PROTOCOL {
+            return Err(Error::new(ErrorKind::InvalidInput, format!(
+                "envelope holds protocol {}, not {} ({})",
+                self.protocol_version, P::PROTOCOL, P::NAME
+            )));
+        }
+        P::packet_by_id(self.packet.state, self.packet.direction, self.packet.id, &mut &self.packet.body[..])
+    }
+}
+
+/// Rewrites an envelope of an older protocol version into a newer one.
+pub type UpgradeHook = Box<dyn Fn(Envelope) -> std::io::Result<Envelope> + Send + Sync>;
+
+/// Brings envelopes of old protocol versions up to date, so captures stay
+/// readable after protocol definitions move on.
+#[derive(Default)]
+pub struct Upgrades{
+    hooks: HashMap<i32, UpgradeHook>
+}
+
+impl Upgrades{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a hook for envelopes of `from_protocol`. The hook must return
+    /// an envelope of a higher protocol version, hooks are chained until no
+    /// hook is registered for the resulting version.
+    pub fn register<F>(&mut self, from_protocol: i32, hook: F)
+        where F: Fn(Envelope) -> std::io::Result<Envelope> + Send + Sync + 'static {
+        self.hooks.insert(from_protocol, Box::new(hook));
+    }
+
+    pub fn upgrade(&self, mut envelope: Envelope) -> std::io::Result<Envelope> {
+        while let Some(hook) = self.hooks.get(&envelope.protocol_version) {
+            let from = envelope.protocol_version;
+            envelope = hook(envelope)?;
+            if envelope.protocol_version <= from {
+                return Err(Error::other(format!(
+                    "upgrade hook for protocol {} did not move to a newer protocol", from
+                )));
+            }
+        }
+        Ok(envelope)
+    }
+
+    /// Reads a record and upgrades it.
+    pub fn read_from_stream<R: std::io::Read>(&self, reader: &mut R) -> std::io::Result<Envelope> {
+        self.upgrade(Envelope::read_from_stream(reader)?)
+    }
+}
+
+impl std::fmt::Debug for Upgrades{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Upgrades").field("from_protocols", &self.hooks.keys()).finish()
+    }
+}
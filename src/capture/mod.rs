@@ -0,0 +1,4 @@
+pub mod envelope;
+pub mod recorder;
+#[cfg(feature = "pcap")]
+pub mod pcap;
@@ -0,0 +1,598 @@
+//! Decoding Minecraft connections from Wireshark captures.
+//!
+//! Both pcap and pcapng files are supported, with Ethernet, Linux cooked,
+//! loopback and raw IP link types. TCP streams to or from the server port are
+//! reassembled, the connection state is followed through the handshake and
+//! login, and compressed frames are inflated. Encrypted connections (online
+//! mode) can not be decoded; decoding of such a connection stops at the
+//! EncryptionResponse and the connection is reported in `Capture::encrypted`.
+use crate::protocol::{Direction, Protocol, RawPacket, State};
+use crate::segment::implementation::var::read_var_int;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Error, ErrorKind, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_PORT: u16 = 25565;
+
+/// Frames larger than this are considered a decoding error rather than waited for.
+const MAX_FRAME_LEN: usize = 2 * 1024 * 1024;
+
+/// The two ends of a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Flow{
+    pub client: SocketAddr,
+    pub server: SocketAddr
+}
+
+#[derive(Debug, Clone)]
+pub struct CapturedPacket{
+    pub timestamp: SystemTime,
+    pub flow: Flow,
+    pub packet: RawPacket
+}
+
+impl CapturedPacket{
+    /// Decodes the packet with `P`, unknown packets are returned as `P::Raw`.
+    pub fn decode<P: Protocol>(&self) -> std::io::Result<P> {
+        P::packet_by_id_or_raw(self.packet.state, self.packet.direction, self.packet.id, &mut &self.packet.body[..])
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Capture{
+    /// Packets of all connections, in capture order.
+    pub packets: Vec<CapturedPacket>,
+    /// Connections that switched to encryption, after which nothing was decoded.
+    pub encrypted: Vec<Flow>,
+    /// Connections whose stream could not be followed, with the reason.
+    pub failed: Vec<(Flow, String)>
+}
+
+/// Reads a pcap or pcapng file and decodes the Minecraft connections on `port`.
+pub fn read_capture<R: Read>(mut reader: R, port: u16) -> std::io::Result<Capture> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let mut decoder = Decoder::new(port);
+    if data.len() >= 4 && data[..4] == [0x0A, 0x0D, 0x0D, 0x0A] {
+        read_pcapng(&data, &mut decoder)?;
+    }else{
+        read_pcap(&data, &mut decoder)?;
+    }
+    Ok(decoder.finish())
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+fn slice(data: &[u8], start: usize, len: usize) -> std::io::Result<&[u8]> {
+    data.get(start..start.checked_add(len).ok_or_else(|| invalid("length overflow"))?)
+        .ok_or_else(|| invalid("capture is truncated"))
+}
+
+fn read_pcap(data: &[u8], decoder: &mut Decoder) -> std::io::Result<()> {
+    let header = slice(data, 0, 24)?;
+    let (little_endian, nanos) = match header[..4] {
+        [0xD4, 0xC3, 0xB2, 0xA1] => (true, false),
+        [0xA1, 0xB2, 0xC3, 0xD4] => (false, false),
+        [0x4D, 0x3C, 0xB2, 0xA1] => (true, true),
+        [0xA1, 0xB2, 0x3C, 0x4D] => (false, true),
+        _ => return Err(invalid("not a pcap or pcapng file"))
+    };
+    let u32_at = |bytes: &[u8], offset: usize| if little_endian {
+        LittleEndian::read_u32(&bytes[offset..])
+    }else{
+        BigEndian::read_u32(&bytes[offset..])
+    };
+    let link_type = u32_at(header, 20);
+    let mut offset = 24;
+    while offset < data.len() {
+        let record = slice(data, offset, 16)?;
+        let seconds = u32_at(record, 0) as u64;
+        let fraction = u32_at(record, 4) as u64;
+        let captured = u32_at(record, 8) as usize;
+        let timestamp = UNIX_EPOCH + Duration::from_secs(seconds)
+            + if nanos { Duration::from_nanos(fraction) } else { Duration::from_micros(fraction) };
+        let frame = slice(data, offset + 16, captured)?;
+        decoder.link_frame(link_type, timestamp, frame);
+        offset += 16 + captured;
+    }
+    Ok(())
+}
+
+fn read_pcapng(data: &[u8], decoder: &mut Decoder) -> std::io::Result<()> {
+    let mut little_endian = true;
+    // Link type and timestamp resolution in nanoseconds per interface, reset by every section.
+    let mut interfaces: Vec<(u32, u64)> = Vec::new();
+    let mut offset = 0;
+    while offset + 12 <= data.len() {
+        let block_type_bytes = &data[offset..offset + 4];
+        if block_type_bytes == [0x0A, 0x0D, 0x0D, 0x0A] {
+            let magic = slice(data, offset + 8, 4)?;
+            little_endian = match magic {
+                [0x4D, 0x3C, 0x2B, 0x1A] => true,
+                [0x1A, 0x2B, 0x3C, 0x4D] => false,
+                _ => return Err(invalid("invalid pcapng byte order magic"))
+            };
+            interfaces.clear();
+        }
+        let u32_at = |bytes: &[u8], offset: usize| if little_endian {
+            LittleEndian::read_u32(&bytes[offset..])
+        }else{
+            BigEndian::read_u32(&bytes[offset..])
+        };
+        let block_type = u32_at(data, offset);
+        let block_len = u32_at(data, offset + 4) as usize;
+        if block_len < 12 || !block_len.is_multiple_of(4) {
+            return Err(invalid("invalid pcapng block length"));
+        }
+        let block = slice(data, offset, block_len)?;
+        let body = &block[8..block_len - 4];
+        let min_body_len = match block_type {
+            1 => 8,
+            3 => 4,
+            6 => 20,
+            _ => 0
+        };
+        if body.len() < min_body_len {
+            return Err(invalid("pcapng block is too short for its type"));
+        }
+        match block_type {
+            // Interface Description Block
+            1 => {
+                let link_type = if little_endian { LittleEndian::read_u16(body) } else { BigEndian::read_u16(body) } as u32;
+                interfaces.push((link_type, interface_resolution(&body[8..], little_endian)));
+            }
+            // Enhanced Packet Block
+            6 => {
+                let interface = u32_at(body, 0) as usize;
+                let ticks = ((u32_at(body, 4) as u64) << 32) | u32_at(body, 8) as u64;
+                let captured = u32_at(body, 12) as usize;
+                let frame = slice(body, 20, captured)?;
+                if let Some(&(link_type, resolution)) = interfaces.get(interface) {
+                    let timestamp = UNIX_EPOCH + Duration::from_nanos(ticks.saturating_mul(resolution));
+                    decoder.link_frame(link_type, timestamp, frame);
+                }
+            }
+            // Simple Packet Block, which has neither an interface nor a timestamp.
+            3 => {
+                if let Some(&(link_type, _)) = interfaces.first() {
+                    let captured = std::cmp::min(u32_at(body, 0) as usize, body.len() - 4);
+                    decoder.link_frame(link_type, UNIX_EPOCH, &body[4..4 + captured]);
+                }
+            }
+            _ => {}
+        }
+        offset += block_len;
+    }
+    Ok(())
+}
+
+/// Nanoseconds per timestamp unit, from the `if_tsresol` option.
+fn interface_resolution(mut options: &[u8], little_endian: bool) -> u64 {
+    while options.len() >= 4 {
+        let (code, len) = if little_endian {
+            (LittleEndian::read_u16(options), LittleEndian::read_u16(&options[2..]) as usize)
+        }else{
+            (BigEndian::read_u16(options), BigEndian::read_u16(&options[2..]) as usize)
+        };
+        if code == 0 {
+            break;
+        }
+        if code == 9 && len >= 1 && options.len() > 4 {
+            let value = options[4];
+            let exponent = (value & 0x7F) as u32;
+            let per_second: u64 = if value & 0x80 == 0 {
+                10u64.saturating_pow(exponent)
+            }else{
+                2u64.saturating_pow(exponent)
+            };
+            return std::cmp::max(1, 1_000_000_000 / std::cmp::max(1, per_second));
+        }
+        let padded = (len + 3) & !3;
+        options = &options[std::cmp::min(options.len(), 4 + padded)..];
+    }
+    // Microseconds unless specified otherwise.
+    1_000
+}
+
+/// Follows a single direction of a TCP stream.
+#[derive(Debug, Default)]
+struct TcpHalf{
+    next_seq: Option<u32>,
+    /// Segments that arrived ahead of `next_seq`.
+    pending: BTreeMap<u32, Vec<u8>>,
+    /// Reassembled bytes not yet consumed as frames.
+    buffer: Vec<u8>
+}
+
+impl TcpHalf{
+    fn segment(&mut self, seq: u32, syn: bool, payload: &[u8]) {
+        if syn {
+            self.next_seq = Some(seq.wrapping_add(1));
+            return;
+        }
+        let next = *self.next_seq.get_or_insert(seq);
+        if payload.is_empty() {
+            return;
+        }
+        let ahead = seq.wrapping_sub(next);
+        if ahead == 0 {
+            self.append(payload);
+        }else if ahead < u32::MAX / 2 {
+            self.pending.entry(seq).or_insert_with(|| payload.to_vec());
+        }else{
+            // Retransmission, possibly carrying some new bytes at the end.
+            let behind = next.wrapping_sub(seq) as usize;
+            if behind < payload.len() {
+                self.append(&payload[behind..]);
+            }
+        }
+    }
+
+    fn append(&mut self, payload: &[u8]) {
+        self.buffer.extend_from_slice(payload);
+        let mut next = self.next_seq.unwrap_or(0).wrapping_add(payload.len() as u32);
+        while let Some(segment) = self.pending.remove(&next) {
+            self.buffer.extend_from_slice(&segment);
+            next = next.wrapping_add(segment.len() as u32);
+        }
+        self.next_seq = Some(next);
+    }
+}
+
+#[derive(Debug)]
+struct Connection{
+    state: State,
+    /// The protocol version from the handshake.
+    protocol: i32,
+    compression: Option<i32>,
+    /// Set once the client sent its EncryptionResponse.
+    encrypted: bool,
+    failed: bool,
+    to_server: TcpHalf,
+    to_client: TcpHalf
+}
+
+struct Decoder{
+    port: u16,
+    connections: HashMap<Flow, Connection>,
+    capture: Capture
+}
+
+impl Decoder{
+    fn new(port: u16) -> Self {
+        Decoder{
+            port,
+            connections: HashMap::new(),
+            capture: Capture::default()
+        }
+    }
+
+    fn finish(self) -> Capture {
+        self.capture
+    }
+
+    /// Strips the link layer and passes IP packets on.
+    fn link_frame(&mut self, link_type: u32, timestamp: SystemTime, frame: &[u8]) {
+        let ip = match link_type {
+            // Ethernet, possibly with a VLAN tag
+            1 if frame.len() >= 14 => {
+                let mut ether_type = BigEndian::read_u16(&frame[12..]);
+                let mut offset = 14;
+                if ether_type == 0x8100 && frame.len() >= 18 {
+                    ether_type = BigEndian::read_u16(&frame[16..]);
+                    offset = 18;
+                }
+                match ether_type {
+                    0x0800 | 0x86DD => &frame[offset..],
+                    _ => return
+                }
+            }
+            // BSD loopback, the address family is in host byte order
+            0 | 108 if frame.len() >= 4 => &frame[4..],
+            // Raw IP
+            101 | 228 | 229 => frame,
+            // Linux cooked capture v1 and v2
+            113 if frame.len() >= 16 => &frame[16..],
+            276 if frame.len() >= 20 => &frame[20..],
+            _ => return
+        };
+        self.ip_packet(timestamp, ip);
+    }
+
+    fn ip_packet(&mut self, timestamp: SystemTime, ip: &[u8]) {
+        let (source, destination, tcp) = match ip.first().map(|b| b >> 4) {
+            Some(4) if ip.len() >= 20 => {
+                let header_len = ((ip[0] & 0x0F) as usize) * 4;
+                let total_len = std::cmp::min(BigEndian::read_u16(&ip[2..]) as usize, ip.len());
+                if ip[9] != 6 || header_len < 20 || total_len < header_len {
+                    return;
+                }
+                let source = IpAddr::V4(Ipv4Addr::new(ip[12], ip[13], ip[14], ip[15]));
+                let destination = IpAddr::V4(Ipv4Addr::new(ip[16], ip[17], ip[18], ip[19]));
+                (source, destination, &ip[header_len..total_len])
+            }
+            Some(6) if ip.len() >= 40 => {
+                // Extension headers are not followed.
+                if ip[6] != 6 {
+                    return;
+                }
+                let payload_len = std::cmp::min(BigEndian::read_u16(&ip[4..]) as usize, ip.len() - 40);
+                let mut source = [0u8; 16];
+                let mut destination = [0u8; 16];
+                source.copy_from_slice(&ip[8..24]);
+                destination.copy_from_slice(&ip[24..40]);
+                (IpAddr::V6(Ipv6Addr::from(source)), IpAddr::V6(Ipv6Addr::from(destination)), &ip[40..40 + payload_len])
+            }
+            _ => return
+        };
+        if tcp.len() < 20 {
+            return;
+        }
+        let source_port = BigEndian::read_u16(tcp);
+        let destination_port = BigEndian::read_u16(&tcp[2..]);
+        let seq = BigEndian::read_u32(&tcp[4..]);
+        let data_offset = ((tcp[12] >> 4) as usize) * 4;
+        let syn = tcp[13] & 0x02 != 0;
+        if data_offset < 20 || data_offset > tcp.len() {
+            return;
+        }
+        let payload = &tcp[data_offset..];
+        let (flow, direction) = if destination_port == self.port {
+            (Flow{ client: SocketAddr::new(source, source_port), server: SocketAddr::new(destination, destination_port) }, Direction::ServerBound)
+        }else if source_port == self.port {
+            (Flow{ client: SocketAddr::new(destination, destination_port), server: SocketAddr::new(source, source_port) }, Direction::ClientBound)
+        }else{
+            return;
+        };
+        self.segment(timestamp, flow, direction, seq, syn, payload);
+    }
+
+    fn segment(&mut self, timestamp: SystemTime, flow: Flow, direction: Direction, seq: u32, syn: bool, payload: &[u8]) {
+        let connection = self.connections.entry(flow).or_insert_with(|| Connection{
+            state: State::Handshaking,
+            protocol: 0,
+            compression: None,
+            encrypted: false,
+            failed: false,
+            to_server: TcpHalf::default(),
+            to_client: TcpHalf::default()
+        });
+        if connection.encrypted || connection.failed {
+            return;
+        }
+        // A new connection on a reused address and port.
+        if syn && direction == Direction::ServerBound && connection.to_server.next_seq.is_some() {
+            *connection = Connection{
+                state: State::Handshaking,
+                protocol: 0,
+                compression: None,
+                encrypted: false,
+                failed: false,
+                to_server: TcpHalf::default(),
+                to_client: TcpHalf::default()
+            };
+        }
+        match direction {
+            Direction::ServerBound => connection.to_server.segment(seq, syn, payload),
+            Direction::ClientBound => connection.to_client.segment(seq, syn, payload)
+        }
+        if let Err(e) = drain_frames(connection, direction, timestamp, flow, &mut self.capture.packets) {
+            connection.failed = true;
+            self.capture.failed.push((flow, e.to_string()));
+        }
+        if connection.encrypted {
+            self.capture.encrypted.push(flow);
+        }
+    }
+}
+
+/// Decodes all complete frames buffered for `direction`.
+fn drain_frames(connection: &mut Connection, direction: Direction, timestamp: SystemTime, flow: Flow, out: &mut Vec<CapturedPacket>) -> std::io::Result<()> {
+    loop {
+        let half = match direction {
+            Direction::ServerBound => &mut connection.to_server,
+            Direction::ClientBound => &mut connection.to_client
+        };
+        let mut cursor = &half.buffer[..];
+        let len = match read_var_int(&mut cursor) {
+            Ok(len) => len,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e)
+        };
+        if len < 1 || len as usize > MAX_FRAME_LEN {
+            return Err(invalid("invalid frame length"));
+        }
+        if cursor.len() < len as usize {
+            return Ok(());
+        }
+        let frame = cursor[..len as usize].to_vec();
+        let consumed = half.buffer.len() - cursor.len() + len as usize;
+        half.buffer.drain(..consumed);
+
+        let data = match connection.compression {
+            Some(_) => crate::connection::compression::decompress(&frame)?,
+            None => frame
+        };
+        let mut body = &data[..];
+        let id = read_var_int(&mut body)?;
+        let packet = RawPacket{ id, state: connection.state, direction, body: body.to_vec() };
+        follow_state(connection, &packet)?;
+        out.push(CapturedPacket{ timestamp, flow, packet });
+        if connection.encrypted {
+            return Ok(());
+        }
+    }
+}
+
+/// The first protocol version with the configuration state, 1.20.2.
+const CONFIGURATION_PROTOCOL: i32 = 764;
+
+/// The ids of the serverbound packets switching between Configuration and
+/// Play, which moved between versions: AcknowledgeFinishConfiguration of
+/// Configuration and ConfigurationAcknowledged of Play. `None` for protocols
+/// without Configuration and for those newer than listed here.
+fn configuration_switch_ids(protocol: i32) -> Option<(i32, i32)> {
+    match protocol {
+        // 1.20.2 to 1.20.4
+        764..=765 => Some((0x02, 0x0b)),
+        // 1.20.5 to 1.21.1
+        766..=767 => Some((0x03, 0x0c)),
+        // 1.21.2 to 1.21.4
+        768..=769 => Some((0x03, 0x0e)),
+        _ => None
+    }
+}
+
+/// Applies the state changes the vanilla client and server make.
+fn follow_state(connection: &mut Connection, packet: &RawPacket) -> std::io::Result<()> {
+    let mut body = &packet.body[..];
+    match (packet.state, packet.direction, packet.id) {
+        (State::Handshaking, Direction::ServerBound, 0x00) => {
+            connection.protocol = read_var_int(&mut body)?;
+            let host_len = read_var_int(&mut body)?;
+            if host_len < 0 || body.len() < host_len as usize + 2 {
+                return Err(invalid("truncated handshake"));
+            }
+            body = &body[host_len as usize + 2..];
+            connection.state = match read_var_int(&mut body)? {
+                1 => State::Status,
+                // 3 logs in after a Transfer, since 1.20.5.
+                2 | 3 => State::Login,
+                _ => return Err(invalid("handshake requests an unknown state"))
+            };
+        }
+        (State::Login, Direction::ServerBound, 0x01) => connection.encrypted = true,
+        (State::Login, Direction::ClientBound, 0x02) if connection.protocol < CONFIGURATION_PROTOCOL => connection.state = State::Play,
+        (State::Login, Direction::ServerBound, 0x03) if connection.protocol >= CONFIGURATION_PROTOCOL => {
+            if configuration_switch_ids(connection.protocol).is_none() {
+                return Err(Error::new(ErrorKind::InvalidData, format!(
+                    "the Configuration state of protocol {} can not be followed", connection.protocol
+                )));
+            }
+            connection.state = State::Configuration;
+        }
+        (State::Configuration, Direction::ServerBound, id) if configuration_switch_ids(connection.protocol).map(|ids| ids.0) == Some(id) => connection.state = State::Play,
+        (State::Play, Direction::ServerBound, id) if configuration_switch_ids(connection.protocol).map(|ids| ids.1) == Some(id) => connection.state = State::Configuration,
+        (State::Login, Direction::ClientBound, 0x03) => {
+            let threshold = read_var_int(&mut body)?;
+            connection.compression = if threshold >= 0 { Some(threshold) } else { None };
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let padded = (body.len() + 3) & !3;
+        let len = (12 + padded) as u32;
+        let mut block = Vec::new();
+        block.extend_from_slice(&block_type.to_le_bytes());
+        block.extend_from_slice(&len.to_le_bytes());
+        block.extend_from_slice(body);
+        block.resize(8 + padded, 0);
+        block.extend_from_slice(&len.to_le_bytes());
+        block
+    }
+
+    /// A little endian section header and a raw IP interface.
+    fn header() -> Vec<u8> {
+        let mut capture = block(0x0A0D0D0A, &[0x4D, 0x3C, 0x2B, 0x1A, 1, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        capture.extend(block(1, &[101, 0, 0, 0, 0, 0, 1, 0]));
+        capture
+    }
+
+    /// An Enhanced Packet Block with an IPv4 TCP segment from the client to the server.
+    fn client_segment(seq: u32, payload: &[u8]) -> Vec<u8> {
+        let mut ip = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, 6, 0, 0, 127, 0, 0, 1, 127, 0, 0, 1];
+        let total_len = (40 + payload.len()) as u16;
+        ip[2..4].copy_from_slice(&total_len.to_be_bytes());
+        ip.extend_from_slice(&50000u16.to_be_bytes());
+        ip.extend_from_slice(&DEFAULT_PORT.to_be_bytes());
+        ip.extend_from_slice(&seq.to_be_bytes());
+        ip.extend_from_slice(&[0, 0, 0, 0, 0x50, 0x18, 0xFF, 0xFF, 0, 0, 0, 0]);
+        ip.extend_from_slice(payload);
+        let mut body = vec![0; 12];
+        body.extend_from_slice(&(ip.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(ip.len() as u32).to_le_bytes());
+        body.extend_from_slice(&ip);
+        block(6, &body)
+    }
+
+    fn frame(id: u8, body: &[u8]) -> Vec<u8> {
+        let mut frame = vec![(body.len() + 1) as u8, id];
+        frame.extend_from_slice(body);
+        frame
+    }
+
+    fn truncated(block_type: u32, body: &[u8]) {
+        let mut capture = header();
+        capture.extend(block(block_type, body));
+        let error = read_capture(&capture[..], DEFAULT_PORT).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated_blocks_are_invalid() {
+        // Enhanced Packet Blocks without a body, short Interface Description
+        // Blocks and Simple Packet Blocks without their length.
+        truncated(6, &[]);
+        truncated(6, &[0; 16]);
+        truncated(1, &[101, 0]);
+        truncated(3, &[]);
+    }
+
+    #[test]
+    fn configuration_is_followed_with_the_ids_of_the_protocol() {
+        let mut handshake = vec![0xFF, 0x05, 9];
+        handshake.extend_from_slice(b"localhost");
+        handshake.extend_from_slice(&DEFAULT_PORT.to_be_bytes());
+        handshake.push(2);
+        let mut payload = frame(0x00, &handshake);
+        // LoginAcknowledged
+        payload.extend(frame(0x03, &[]));
+        // A plugin message, AcknowledgeFinishConfiguration of 1.20.4
+        payload.extend(frame(0x02, &[4, b't', b'e', b's', b't']));
+        // AcknowledgeFinishConfiguration
+        payload.extend(frame(0x03, &[]));
+        // ConfigurationAcknowledged
+        payload.extend(frame(0x0c, &[]));
+        payload.extend(frame(0x00, &[]));
+        let mut capture = header();
+        capture.extend(client_segment(1000, &payload));
+
+        let capture = read_capture(&capture[..], DEFAULT_PORT).unwrap();
+        assert!(capture.failed.is_empty(), "{:?}", capture.failed);
+        let states: Vec<(State, i32)> = capture.packets.iter().map(|packet| (packet.packet.state, packet.packet.id)).collect();
+        assert_eq!(states, vec![
+            (State::Handshaking, 0x00),
+            (State::Login, 0x03),
+            (State::Configuration, 0x02),
+            (State::Configuration, 0x03),
+            (State::Play, 0x0c),
+            (State::Configuration, 0x00)
+        ]);
+    }
+
+    #[test]
+    fn unknown_configuration_ids_fail_the_connection() {
+        let mut handshake = vec![0x80, 0x80, 0x80, 0x80, 0x04, 0];
+        handshake.extend_from_slice(&DEFAULT_PORT.to_be_bytes());
+        handshake.push(2);
+        let mut payload = frame(0x00, &handshake);
+        payload.extend(frame(0x03, &[]));
+        let mut capture = header();
+        capture.extend(client_segment(1000, &payload));
+
+        let capture = read_capture(&capture[..], DEFAULT_PORT).unwrap();
+        assert_eq!(capture.packets.len(), 1);
+        assert_eq!(capture.failed.len(), 1);
+    }
+}
@@ -0,0 +1,145 @@
+//! Binary session logs of framed packets, and replaying them.
+//!
+//! A log starts with `MAGIC` followed by records of:
+//!
+//! ```text
+//! VarInt  compression threshold in effect when the packet was captured, -1 if disabled
+//! ...     the packet as an `Envelope`
+//! ```
+//!
+//! Packets are always stored uncompressed, the threshold is only kept to
+//! be able to tell what the connection looked like on the wire.
+use crate::capture::envelope::{Envelope, Upgrades};
+use crate::connection::frame::{self, FrameLimits};
+use crate::protocol::{Direction, Protocol, RawPacket, State};
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::time::SystemTime;
+
+pub const MAGIC: &[u8; 6] = b"MCREC\x01";
+
+/// Writes the packets of a connection to a log.
+#[derive(Debug)]
+pub struct Recorder<W: Write>{
+    out: W,
+    protocol_version: i32,
+    state: State,
+    compression_threshold: Option<i32>
+}
+
+impl<W: Write> Recorder<W>{
+    pub fn new(mut out: W, protocol_version: i32) -> std::io::Result<Self> {
+        out.write_all(MAGIC)?;
+        Ok(Recorder{
+            out,
+            protocol_version,
+            state: State::Handshaking,
+            compression_threshold: None
+        })
+    }
+
+    /// The state packets read through `tap` are recorded in.
+    pub fn set_state(&mut self, state: State) {
+        self.state = state;
+    }
+
+    pub fn set_compression(&mut self, threshold: Option<i32>) {
+        self.compression_threshold = threshold;
+    }
+
+    /// Records a packet that was read or written elsewhere.
+    pub fn record(&mut self, packet: &RawPacket) -> std::io::Result<()> {
+        write_var_int(self.compression_threshold.unwrap_or(-1), &mut self.out)?;
+        Envelope{
+            protocol_version: self.protocol_version,
+            timestamp: SystemTime::now(),
+            packet: packet.clone()
+        }.write_to_stream(&mut self.out)
+    }
+
+    /// Reads an uncompressed frame from `reader`, records and returns it.
+    /// Frames over the vanilla `FrameLimits` of the state are refused.
+    pub fn tap<R: Read>(&mut self, direction: Direction, reader: &mut R) -> std::io::Result<RawPacket> {
+        let (id, body) = frame::read_frame(reader, &FrameLimits::default(), self.state)?;
+        let packet = RawPacket{ id, state: self.state, direction, body };
+        self.record(&packet)?;
+        Ok(packet)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Recorded{
+    pub compression_threshold: Option<i32>,
+    pub envelope: Envelope
+}
+
+/// Reads a log written by `Recorder`.
+#[derive(Debug)]
+pub struct Replayer<R: Read>{
+    input: R,
+    upgrades: Upgrades
+}
+
+impl<R: Read> Replayer<R>{
+    pub fn new(input: R) -> std::io::Result<Self> {
+        Self::with_upgrades(input, Upgrades::new())
+    }
+
+    /// Reads the log, upgrading records of older protocol versions on the way.
+    pub fn with_upgrades(mut input: R, upgrades: Upgrades) -> std::io::Result<Self> {
+        let mut magic = [0u8; 6];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "not a packet log"));
+        }
+        Ok(Replayer{ input, upgrades })
+    }
+
+    /// The next record, or `None` at the end of the log.
+    pub fn next_record(&mut self) -> std::io::Result<Option<Recorded>> {
+        let threshold = match read_var_int(&mut self.input) {
+            Ok(threshold) => threshold,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e)
+        };
+        let envelope = self.upgrades.read_from_stream(&mut self.input)?;
+        Ok(Some(Recorded{
+            compression_threshold: if threshold < 0 { None } else { Some(threshold) },
+            envelope
+        }))
+    }
+
+    /// Decodes the next record with `P`, which has to match the protocol the
+    /// record was captured with. Packets `P` does not know are returned as `P::Raw`.
+    pub fn next_packet<P: Protocol>(&mut self) -> std::io::Result<Option<(Recorded, P)>> {
+        let record = match self.next_record()? {
+            Some(record) => record,
+            None => return Ok(None)
+        };
+        if record.envelope.protocol_version != P::PROTOCOL {
+            return Err(Error::new(ErrorKind::InvalidInput, format!(
+                "record holds protocol {}, not {} ({})",
+                record.envelope.protocol_version, P::PROTOCOL, P::NAME
+            )));
+        }
+        let raw = &record.envelope.packet;
+        let packet = P::packet_by_id_or_raw(raw.state, raw.direction, raw.id, &mut &raw.body[..])?;
+        Ok(Some((record, packet)))
+    }
+}
+
+impl<R: Read> Iterator for Replayer<R>{
+    type Item = std::io::Result<Recorded>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
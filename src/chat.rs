@@ -0,0 +1,210 @@
+//! Chat components, the JSON text used for disconnect reasons, chat and the
+//! server list. Since 1.20.3 they are sent as NBT outside of login.
+//!
+//! This covers what servers send most, plain and translated text with a
+//! style. Anything else can still be sent as raw JSON.
+use crate::segment::implementation::nbt::Tag;
+use std::fmt::{Display, Formatter, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Content{
+    Text(String),
+    /// A key of the client's language file, `%s` in it replaced by `with`.
+    Translate{ key: String, with: Vec<Component> }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Component{
+    pub content: Content,
+    /// A color name like `gold`, or `#rrggbb` since 1.16.
+    pub color: Option<String>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    /// Components following this one, inheriting its style.
+    pub extra: Vec<Component>
+}
+
+impl Default for Component{
+    fn default() -> Self {
+        Component::text("")
+    }
+}
+
+impl Component{
+    pub fn text(text: impl Into<String>) -> Self {
+        Component{
+            content: Content::Text(text.into()),
+            color: None,
+            bold: None,
+            italic: None,
+            underlined: None,
+            strikethrough: None,
+            obfuscated: None,
+            extra: Vec::new()
+        }
+    }
+
+    pub fn translate(key: impl Into<String>, with: Vec<Component>) -> Self {
+        Component{ content: Content::Translate{ key: key.into(), with }, ..Component::text("") }
+    }
+
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.obfuscated = Some(obfuscated);
+        self
+    }
+
+    pub fn append(mut self, component: Component) -> Self {
+        self.extra.push(component);
+        self
+    }
+
+    /// The text of this component and its extras, without styles. Translated
+    /// components show their key.
+    pub fn plain_text(&self) -> String {
+        let mut text = String::new();
+        self.push_plain_text(&mut text);
+        text
+    }
+
+    fn push_plain_text(&self, text: &mut String) {
+        match &self.content {
+            Content::Text(content) => text.push_str(content),
+            Content::Translate{ key, .. } => text.push_str(key)
+        }
+        for extra in &self.extra {
+            extra.push_plain_text(text);
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_string()
+    }
+
+    /// The component as NBT, how 1.20.3 and later send it outside of login.
+    pub fn to_nbt(&self) -> Tag {
+        let mut entries = match &self.content {
+            Content::Text(text) => vec![("text".to_string(), Tag::String(text.clone()))],
+            Content::Translate{ key, with } => {
+                let mut entries = vec![("translate".to_string(), Tag::String(key.clone()))];
+                if !with.is_empty() {
+                    entries.push(("with".to_string(), Tag::List(with.iter().map(Component::to_nbt).collect())));
+                }
+                entries
+            }
+        };
+        if let Some(color) = &self.color {
+            entries.push(("color".to_string(), Tag::String(color.clone())));
+        }
+        for (name, value) in [("bold", self.bold), ("italic", self.italic), ("underlined", self.underlined), ("strikethrough", self.strikethrough), ("obfuscated", self.obfuscated)] {
+            if let Some(value) = value {
+                entries.push((name.to_string(), Tag::Byte(value as i8)));
+            }
+        }
+        if !self.extra.is_empty() {
+            entries.push(("extra".to_string(), Tag::List(self.extra.iter().map(Component::to_nbt).collect())));
+        }
+        Tag::Compound(entries)
+    }
+}
+
+impl From<&str> for Component{
+    fn from(text: &str) -> Self {
+        Component::text(text)
+    }
+}
+
+impl From<String> for Component{
+    fn from(text: String) -> Self {
+        Component::text(text)
+    }
+}
+
+/// Writes the component as JSON.
+impl Display for Component{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.content {
+            Content::Text(text) => {
+                f.write_str("{\"text\":")?;
+                write_json_string(f, text)?;
+            }
+            Content::Translate{ key, with } => {
+                f.write_str("{\"translate\":")?;
+                write_json_string(f, key)?;
+                if !with.is_empty() {
+                    write_list(f, "with", with)?;
+                }
+            }
+        }
+        if let Some(color) = &self.color {
+            f.write_str(",\"color\":")?;
+            write_json_string(f, color)?;
+        }
+        for (name, value) in [("bold", self.bold), ("italic", self.italic), ("underlined", self.underlined), ("strikethrough", self.strikethrough), ("obfuscated", self.obfuscated)] {
+            if let Some(value) = value {
+                write!(f, ",\"{}\":{}", name, value)?;
+            }
+        }
+        if !self.extra.is_empty() {
+            write_list(f, "extra", &self.extra)?;
+        }
+        f.write_char('}')
+    }
+}
+
+fn write_list(f: &mut Formatter<'_>, name: &str, components: &[Component]) -> std::fmt::Result {
+    write!(f, ",\"{}\":[", name)?;
+    for (i, component) in components.iter().enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        Display::fmt(component, f)?;
+    }
+    f.write_char(']')
+}
+
+/// Writes `value` as a quoted JSON string.
+pub fn write_json_string<W: Write>(out: &mut W, value: &str) -> std::fmt::Result {
+    out.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?
+        }
+    }
+    out.write_char('"')
+}
@@ -0,0 +1,333 @@
+//! A client that connects to a server and follows the connection through the
+//! handshake and login into play.
+//!
+//! The client sends the Handshake and LoginStart, switches to compression and
+//! encryption when the server asks for it, declines login plugin requests and
+//! answers keep-alives by itself, so the caller only deals with play packets.
+//!
+//! Online mode servers send an EncryptionRequest. Answering it requires RSA
+//! encryption with the public key of the server and a call to the Mojang
+//! session server, which are left to an `Authenticator`. With the
+//! `encryption` feature `MojangAuthenticator` does both for a logged in
+//! account. Without an authenticator only offline mode servers can be joined.
+//!
+//! The client speaks 1.17 (`Proto_1_17`) only. Joining servers of other
+//! versions needs a proxy that translates, like ViaVersion.
+use crate::connection::keep_alive::{KeepAliveTracker, Role};
+use crate::connection::stream::PacketStream;
+use crate::connection::transport::Transport;
+use crate::protocol::implementation::steven::v1_17::*;
+use crate::protocol::{Direction, Protocol, State};
+use crate::server::login::validate_username;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use steven_protocol::protocol::{LenPrefixedBytes, VarInt};
+
+/// What the server asks for in its EncryptionRequest.
+#[derive(Debug)]
+pub struct EncryptionChallenge<'a>{
+    pub server_id: &'a str,
+    /// The DER encoded RSA public key of the server.
+    pub public_key: &'a [u8],
+    pub verify_token: &'a [u8]
+}
+
+/// The answer to an `EncryptionChallenge`.
+#[derive(Debug, Clone)]
+pub struct EncryptionAnswer{
+    /// The key both sides encrypt the connection with from now on.
+    pub shared_secret: [u8; 16],
+    /// The shared secret, encrypted with the public key of the server (RSA, PKCS#1 v1.5).
+    pub encrypted_secret: Vec<u8>,
+    /// The verify token, encrypted the same way.
+    pub encrypted_verify_token: Vec<u8>
+}
+
+/// Handles the login to an online mode server.
+///
+/// An implementation generates a random shared secret, tells the session
+/// server that the player joins (`POST https://sessionserver.mojang.com/session/minecraft/join`
+/// with the access token, the profile id and the `server_hash` of the
+/// challenge) and encrypts the secret and token with the public key.
+pub trait Authenticator{
+    fn authenticate(&mut self, challenge: &EncryptionChallenge) -> std::io::Result<EncryptionAnswer>;
+}
+
+#[cfg(feature = "encryption")]
+pub use self::mojang::MojangAuthenticator;
+
+/// Formats a SHA-1 digest the way the session server expects the server hash:
+/// as a signed hexadecimal number without leading zeroes.
+///
+/// The digest is taken over the server id, the shared secret and the public
+/// key, in that order.
+pub fn server_hash(digest: [u8; 20]) -> String {
+    let negative = digest[0] & 0x80 != 0;
+    let mut magnitude = digest;
+    if negative {
+        // Two's complement
+        let mut carry = true;
+        for byte in magnitude.iter_mut().rev() {
+            *byte = !*byte;
+            if carry {
+                let (sum, overflow) = byte.overflowing_add(1);
+                *byte = sum;
+                carry = overflow;
+            }
+        }
+    }
+    let hex: String = magnitude.iter().map(|b| format!("{:02x}", b)).collect();
+    let hex = hex.trim_start_matches('0');
+    let hex = if hex.is_empty() { "0" } else { hex };
+    if negative {
+        format!("-{}", hex)
+    }else{
+        hex.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOptions{
+    pub username: String,
+    /// The address sent in the Handshake, which proxies use to pick a backend.
+    pub host: String,
+    pub port: u16,
+    /// Answer KeepAliveClientbound with KeepAliveServerbound automatically.
+    pub answer_keep_alives: bool
+}
+
+impl ClientOptions{
+    pub fn new(username: impl Into<String>, host: impl Into<String>, port: u16) -> Self {
+        ClientOptions{
+            username: username.into(),
+            host: host.into(),
+            port,
+            answer_keep_alives: true
+        }
+    }
+}
+
+/// The result of a server list ping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerStatus{
+    /// The JSON status of the server.
+    pub json: String,
+    pub latency: Duration
+}
+
+/// A connection in the play state.
+#[derive(Debug)]
+pub struct Client<S>{
+    stream: PacketStream<S>,
+    profile: LoginSuccess,
+    answer_keep_alives: bool,
+    keep_alive: KeepAliveTracker
+}
+
+impl<S: Read + Write> Client<S>{
+    /// Opens a connection with `transport` and logs in.
+    pub fn connect<T: Transport<Stream = S>>(transport: &T, options: &ClientOptions, authenticator: Option<&mut dyn Authenticator>) -> std::io::Result<Self> {
+        let stream = transport.connect(&options.host, options.port)?;
+        Self::login(stream, options, authenticator)
+    }
+
+    /// Logs in over an already connected stream.
+    pub fn login(stream: S, options: &ClientOptions, mut authenticator: Option<&mut dyn Authenticator>) -> std::io::Result<Self> {
+        validate_username(&options.username)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("username {}", e.description())))?;
+        let mut stream = PacketStream::new(stream);
+        stream.write_packet(&handshake(options, 2))?;
+        stream.write_packet(&LoginStart{ username: options.username.clone() })?;
+        loop {
+            match stream.read_packet::<Proto_1_17>(State::Login, Direction::ClientBound)? {
+                Proto_1_17::LoginDisconnect(disconnect) => {
+                    return Err(Error::new(ErrorKind::ConnectionRefused, format!("disconnected during login: {:?}", disconnect.reason)));
+                }
+                Proto_1_17::EncryptionRequest(request) => {
+                    let authenticator = authenticator.as_deref_mut()
+                        .ok_or_else(|| Error::new(ErrorKind::PermissionDenied, "the server is in online mode, which needs an Authenticator"))?;
+                    let answer = authenticator.authenticate(&EncryptionChallenge{
+                        server_id: &request.server_id,
+                        public_key: &request.public_key.data,
+                        verify_token: &request.verify_token.data
+                    })?;
+                    stream.write_packet(&EncryptionResponse{
+                        shared_secret: LenPrefixedBytes::new(answer.encrypted_secret),
+                        verify_token: LenPrefixedBytes::new(answer.encrypted_verify_token)
+                    })?;
+                    enable_encryption(&mut stream, &answer.shared_secret)?;
+                }
+                Proto_1_17::SetInitialCompression(compression) => {
+                    let threshold = compression.threshold.0;
+                    stream.set_compression(if threshold >= 0 { Some(threshold as usize) } else { None })?;
+                }
+                Proto_1_17::LoginPluginRequest(request) => {
+                    stream.write_packet(&LoginPluginResponse{ message_id: request.message_id, successful: false, data: Vec::new() })?;
+                }
+                Proto_1_17::LoginSuccess(profile) => {
+                    return Ok(Client{
+                        stream,
+                        profile: *profile,
+                        answer_keep_alives: options.answer_keep_alives,
+                        keep_alive: KeepAliveTracker::new(Role::Client, Instant::now())
+                    });
+                }
+                other => {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("unexpected packet 0x{:02X} during login", other.id())));
+                }
+            }
+        }
+    }
+
+    /// The profile the server assigned in LoginSuccess.
+    pub fn profile(&self) -> &LoginSuccess {
+        &self.profile
+    }
+
+    /// Reads the next play packet. Keep-alives are answered before they are returned.
+    pub fn next_packet(&mut self) -> std::io::Result<Proto_1_17> {
+        let packet = self.stream.read_packet::<Proto_1_17>(State::Play, Direction::ClientBound)?;
+        if self.answer_keep_alives {
+            if let Some(answer) = self.keep_alive.handle(&packet, Instant::now())? {
+                self.stream.write_packet(&answer)?;
+            }
+        }
+        Ok(packet)
+    }
+
+    /// Keep-alive state, e.g. to notice that the server went silent.
+    pub fn keep_alive(&mut self) -> &mut KeepAliveTracker {
+        &mut self.keep_alive
+    }
+
+    /// Iterates over play packets until the connection fails or closes.
+    pub fn packets(&mut self) -> Packets<'_, S> {
+        Packets{ client: self, done: false }
+    }
+
+    /// Sends a play packet. Clientbound packets and those of other states do
+    /// not compile, use `stream` to send anything else.
+    pub fn send<P: crate::protocol::Packet + crate::protocol::ServerBound + crate::protocol::PlayState>(&mut self, packet: &P) -> std::io::Result<()> {
+        self.stream.write_packet(packet)
+    }
+
+    pub fn stream(&mut self) -> &mut PacketStream<S> {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> PacketStream<S> {
+        self.stream
+    }
+}
+
+/// Pings a server like the server list does.
+pub fn status<S: Read + Write>(stream: S, host: &str, port: u16) -> std::io::Result<ServerStatus> {
+    let mut stream = PacketStream::new(stream);
+    let options = ClientOptions::new("", host, port);
+    stream.write_packet(&handshake(&options, 1))?;
+    stream.write_packet(&StatusRequest{})?;
+    let json = match stream.read_packet::<Proto_1_17>(State::Status, Direction::ClientBound)? {
+        Proto_1_17::StatusResponse(response) => response.status,
+        other => return Err(Error::new(ErrorKind::InvalidData, format!("expected StatusResponse, got 0x{:02X}", other.id())))
+    };
+    let payload = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or_default();
+    let sent = Instant::now();
+    stream.write_packet(&StatusPing{ ping: payload })?;
+    match stream.read_packet::<Proto_1_17>(State::Status, Direction::ClientBound)? {
+        Proto_1_17::StatusPong(pong) if pong.ping == payload => Ok(ServerStatus{ json, latency: sent.elapsed() }),
+        _ => Err(Error::new(ErrorKind::InvalidData, "server did not answer the ping"))
+    }
+}
+
+fn handshake(options: &ClientOptions, next: i32) -> Handshake {
+    Handshake{
+        protocol_version: VarInt(Proto_1_17::PROTOCOL),
+        host: options.host.clone(),
+        port: options.port,
+        next: VarInt(next)
+    }
+}
+
+#[cfg(feature = "encryption")]
+fn enable_encryption<S: Read + Write>(stream: &mut PacketStream<S>, shared_secret: &[u8; 16]) -> std::io::Result<()> {
+    stream.enable_encryption(shared_secret);
+    Ok(())
+}
+
+#[cfg(not(feature = "encryption"))]
+fn enable_encryption<S: Read + Write>(_stream: &mut PacketStream<S>, _shared_secret: &[u8; 16]) -> std::io::Result<()> {
+    Err(Error::new(ErrorKind::Unsupported, "the server is in online mode, which needs the `encryption` feature"))
+}
+
+#[cfg(feature = "encryption")]
+mod mojang{
+    use super::{Authenticator, EncryptionAnswer, EncryptionChallenge};
+    use rsa::pkcs8::DecodePublicKey;
+    use rsa::rand_core::{OsRng, RngCore};
+    use rsa::{Pkcs1v15Encrypt, RsaPublicKey};
+    use std::io::{Error, ErrorKind};
+    use steven_protocol::protocol::mojang::Profile;
+
+    /// Joins online mode servers with the access token of a Mojang account,
+    /// through `Profile::join_server` of the session server.
+    #[derive(Debug, Clone)]
+    pub struct MojangAuthenticator{
+        profile: Profile
+    }
+
+    impl MojangAuthenticator{
+        /// `profile` has to be logged in, e.g. with `Profile::login` or `Profile::refresh`.
+        pub fn new(profile: Profile) -> Self {
+            MojangAuthenticator{ profile }
+        }
+
+        pub fn profile(&self) -> &Profile {
+            &self.profile
+        }
+    }
+
+    impl Authenticator for MojangAuthenticator{
+        fn authenticate(&mut self, challenge: &EncryptionChallenge) -> std::io::Result<EncryptionAnswer> {
+            let key = RsaPublicKey::from_public_key_der(challenge.public_key)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("server public key is invalid: {}", e)))?;
+            let mut shared_secret = [0; 16];
+            OsRng.fill_bytes(&mut shared_secret);
+            let encrypt = |data: &[u8]| key.encrypt(&mut OsRng, Pkcs1v15Encrypt, data)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("could not encrypt for the server: {}", e)));
+            let encrypted_secret = encrypt(&shared_secret)?;
+            let encrypted_verify_token = encrypt(challenge.verify_token)?;
+            self.profile.join_server(challenge.server_id, &shared_secret, challenge.public_key)
+                .map_err(|e| Error::new(ErrorKind::PermissionDenied, format!("the session server refused the join: {}", e)))?;
+            Ok(EncryptionAnswer{ shared_secret, encrypted_secret, encrypted_verify_token })
+        }
+    }
+}
+
+/// Iterator over the play packets of a `Client`. Stops after the first error,
+/// which is returned as the last item.
+#[derive(Debug)]
+pub struct Packets<'a, S>{
+    client: &'a mut Client<S>,
+    done: bool
+}
+
+impl<S: Read + Write> Iterator for Packets<'_, S>{
+    type Item = std::io::Result<Proto_1_17>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.client.next_packet() {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+            Ok(packet) => Some(Ok(packet))
+        }
+    }
+}
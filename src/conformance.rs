@@ -0,0 +1,225 @@
+//! Golden files of packets, checked against the protocol definitions.
+//!
+//! Fixtures live in `fixtures/<protocol name>/` and are named
+//! `<state>-<direction>-0x<id>-<packet name>.bin`, e.g.
+//! `fixtures/1.17/handshaking-serverbound-0x00-Handshake.bin`. A `.bin` file
+//! holds the packet body without the frame length and packet id. Next to it
+//! an optional `.txt` file holds the expected `Debug` output of the decoded
+//! protocol enum.
+//!
+//! Every fixture has to decode as the named packet, consume the whole body,
+//! encode back to the same bytes and match its `.txt` file. A new protocol
+//! version is checked by adding its directory and calling `run` with it:
+//!
+//! ```ignore
+//! #[test]
+//! fn conformance() {
+//!     let report = mc_protocol::conformance::run::<Proto_1_17>().unwrap();
+//!     assert!(report.is_ok(), "{}", report);
+//! }
+//! ```
+use crate::protocol::{Direction, PacketDescriptor, Protocol, State};
+use std::fmt::{Display, Formatter};
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// The fixtures shipped with this crate.
+pub const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+#[derive(Debug, Default)]
+pub struct Report{
+    /// File names of the fixtures that passed.
+    pub passed: Vec<String>,
+    /// File names of the fixtures that failed, with the reason.
+    pub failures: Vec<(String, String)>,
+    /// Packets of the protocol without a fixture.
+    pub uncovered: Vec<&'static PacketDescriptor>
+}
+
+impl Report{
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl Display for Report{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} passed, {} failed, {} packets without fixture", self.passed.len(), self.failures.len(), self.uncovered.len())?;
+        for (file, reason) in &self.failures {
+            writeln!(f, "  {}: {}", file, reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the fixtures of `P` shipped with this crate.
+pub fn run<P: Protocol>() -> std::io::Result<Report> {
+    run_in::<P>(FIXTURES)
+}
+
+/// Checks the fixtures in `root/<P::NAME>/`. A missing directory results in
+/// a report where every packet is uncovered.
+pub fn run_in<P: Protocol>(root: impl AsRef<Path>) -> std::io::Result<Report> {
+    let directory = root.as_ref().join(P::NAME);
+    let mut report = Report::default();
+    let mut covered = Vec::new();
+    if directory.is_dir() {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<_>>()?;
+        files.sort();
+        for path in files.iter().filter(|path| path.extension().is_some_and(|extension| extension == "bin")) {
+            let file = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            match check::<P>(path) {
+                Ok(descriptor) => {
+                    covered.push(descriptor);
+                    report.passed.push(file);
+                }
+                Err(e) => report.failures.push((file, e.to_string()))
+            }
+        }
+    }
+    report.uncovered = P::packets().iter()
+        .filter(|descriptor| !covered.contains(descriptor))
+        .collect();
+    Ok(report)
+}
+
+/// Writes `packet` as a fixture in `root/<P::NAME>/`, along with its `Debug`
+/// output. Review the result before committing it.
+pub fn write_fixture<P: Protocol>(root: impl AsRef<Path>, state: State, direction: Direction, packet: &P) -> std::io::Result<PathBuf> {
+    let descriptor = P::packets().iter()
+        .find(|descriptor| descriptor.state == state && descriptor.direction == direction && descriptor.id == packet.id())
+        .ok_or_else(|| invalid(format!("packet 0x{:02X} is not part of {} {:?} {:?}", packet.id(), P::NAME, state, direction)))?;
+    let directory = root.as_ref().join(P::NAME);
+    std::fs::create_dir_all(&directory)?;
+    let path = directory.join(fixture_name(descriptor));
+    let mut body = Vec::new();
+    packet.write_to_stream(&mut body)?;
+    std::fs::write(&path, body)?;
+    std::fs::write(path.with_extension("txt"), format!("{:?}\n", packet))?;
+    Ok(path)
+}
+
+/// The file name of the fixture for a packet.
+pub fn fixture_name(descriptor: &PacketDescriptor) -> String {
+    format!("{}-{}-0x{:02X}-{}.bin", state_name(descriptor.state), direction_name(descriptor.direction), descriptor.id, descriptor.name)
+}
+
+fn check<P: Protocol>(path: &Path) -> std::io::Result<&'static PacketDescriptor> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let descriptor = P::packets().iter()
+        .find(|descriptor| fixture_name(descriptor) == name)
+        .ok_or_else(|| invalid(format!("no packet of {} matches the file name", P::NAME)))?;
+    let body = std::fs::read(path)?;
+    let mut reader = &body[..];
+    let packet = P::packet_by_id(descriptor.state, descriptor.direction, descriptor.id, &mut reader)?
+        .ok_or_else(|| invalid("packet is not decoded".to_string()))?;
+    if !reader.is_empty() {
+        return Err(invalid(format!("{} of {} bytes were not read", reader.len(), body.len())));
+    }
+    let mut written = Vec::new();
+    packet.write_to_stream(&mut written)?;
+    if written != body {
+        return Err(invalid(format!("encodes to {:?} instead of the fixture", written)));
+    }
+    let expected = path.with_extension("txt");
+    if expected.exists() {
+        let expected = std::fs::read_to_string(expected)?;
+        let actual = format!("{:?}", packet);
+        if actual.trim() != expected.trim() {
+            return Err(invalid(format!("decoded as {}, expected {}", actual, expected.trim())));
+        }
+    }
+    Ok(descriptor)
+}
+
+fn state_name(state: State) -> &'static str {
+    match state {
+        State::Handshaking => "handshaking",
+        State::Status => "status",
+        State::Login => "login",
+        State::Configuration => "configuration",
+        State::Play => "play"
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::ServerBound => "serverbound",
+        Direction::ClientBound => "clientbound"
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(all(test, feature = "steven_shared"))]
+mod tests {
+    use super::*;
+    use crate::protocol::implementation::steven::v1_7_10::Proto_1_7_10;
+    use crate::protocol::implementation::steven::v1_14_4::Proto_1_14_4;
+    use crate::protocol::implementation::steven::v1_16_5::Proto_1_16_5;
+    use crate::protocol::implementation::steven::v1_17::Proto_1_17;
+    use crate::protocol::implementation::steven::v1_20_4::Proto_1_20_4;
+    use crate::protocol::implementation::steven::v1_21::Proto_1_21;
+
+    fn assert_passes<P: Protocol>(expected: &[&str]) {
+        let report = run::<P>().unwrap();
+        assert!(report.is_ok(), "{}", report);
+        for file in expected {
+            assert!(report.passed.iter().any(|passed| passed == file), "{} did not run for {}", file, P::NAME);
+        }
+    }
+
+    #[test]
+    fn fixtures_1_7_10() {
+        assert_passes::<Proto_1_7_10>(&[
+            "handshaking-serverbound-0x00-Handshake.bin",
+            "login-serverbound-0x00-LoginStart.bin",
+            "play-clientbound-0x00-KeepAliveClientbound.bin"
+        ]);
+    }
+
+    #[test]
+    fn fixtures_1_14_4() {
+        assert_passes::<Proto_1_14_4>(&[
+            "handshaking-serverbound-0x00-Handshake.bin",
+            "login-clientbound-0x03-SetInitialCompression.bin",
+            "play-clientbound-0x20-KeepAliveClientbound.bin"
+        ]);
+    }
+
+    #[test]
+    fn fixtures_1_16_5() {
+        assert_passes::<Proto_1_16_5>(&[
+            "handshaking-serverbound-0x00-Handshake.bin",
+            "login-clientbound-0x03-SetInitialCompression.bin",
+            "play-clientbound-0x1F-KeepAliveClientbound.bin"
+        ]);
+    }
+
+    #[test]
+    fn fixtures_1_17() {
+        assert_passes::<Proto_1_17>(&["play-clientbound-0x66-Tags.bin"]);
+    }
+
+    #[test]
+    fn fixtures_1_20_4() {
+        assert_passes::<Proto_1_20_4>(&[
+            "login-serverbound-0x00-LoginStart.bin",
+            "configuration-clientbound-0x03-ConfigurationKeepAliveClientbound.bin",
+            "play-clientbound-0x24-KeepAliveClientbound.bin"
+        ]);
+    }
+
+    #[test]
+    fn fixtures_1_21() {
+        assert_passes::<Proto_1_21>(&[
+            "login-serverbound-0x00-LoginStart.bin",
+            "configuration-clientbound-0x04-ConfigurationKeepAliveClientbound.bin",
+            "play-clientbound-0x26-KeepAliveClientbound.bin"
+        ]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Write};
+
+/// How important an outbound packet is when the buffer of a connection runs full.
+///
+/// Purely cosmetic packets like particles and sounds should be sent as `Low`,
+/// so they are the first to go when a client can not keep up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Priority{
+    Low,
+    #[default]
+    Normal,
+    High
+}
+
+/// What an `OutboundBuffer` should do with a packet that does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow{
+    /// Discard the incoming packet, keep everything that is already buffered.
+    Drop,
+    /// Evict buffered packets with a priority up to and including the given
+    /// one (oldest first) until the incoming packet fits. If that is not
+    /// enough, the connection is disconnected.
+    Evict(Priority),
+    /// Give up on the connection.
+    Disconnect
+}
+
+/// Decides how a connection deals with a client that is reading slower than
+/// the server is writing.
+pub trait BackpressurePolicy{
+    /// Called when a packet of `incoming` priority would grow the buffer past `limit`.
+    fn on_overflow(&mut self, buffered: usize, limit: usize, incoming: Priority) -> Overflow;
+}
+
+/// Drops low priority packets first, both incoming and buffered ones. Anything
+/// that still does not fit disconnects the client.
+#[derive(Debug, Default, Clone)]
+pub struct DropLowPriority;
+
+impl BackpressurePolicy for DropLowPriority{
+    fn on_overflow(&mut self, _buffered: usize, _limit: usize, incoming: Priority) -> Overflow {
+        if incoming == Priority::Low {
+            Overflow::Drop
+        }else{
+            Overflow::Evict(Priority::Low)
+        }
+    }
+}
+
+/// Disconnects the client as soon as the buffer limit is exceeded.
+#[derive(Debug, Default, Clone)]
+pub struct DisconnectImmediately;
+
+impl BackpressurePolicy for DisconnectImmediately{
+    fn on_overflow(&mut self, _buffered: usize, _limit: usize, _incoming: Priority) -> Overflow {
+        Overflow::Disconnect
+    }
+}
+
+/// Per-connection queue of encoded packets with an upper bound on the amount
+/// of bytes it may hold.
+///
+/// Packets leave the buffer in the order they were pushed, regardless of
+/// their priority. The priority only matters when the buffer is full.
+#[derive(Debug)]
+pub struct OutboundBuffer<P: BackpressurePolicy = DropLowPriority>{
+    queue: VecDeque<(Priority, Vec<u8>)>,
+    /// Bytes of the front packet that have already been written.
+    written: usize,
+    buffered: usize,
+    limit: usize,
+    policy: P
+}
+
+impl OutboundBuffer<DropLowPriority>{
+    pub fn new(limit: usize) -> Self {
+        Self::with_policy(limit, DropLowPriority)
+    }
+}
+
+impl<P: BackpressurePolicy> OutboundBuffer<P>{
+    pub fn with_policy(limit: usize, policy: P) -> Self {
+        OutboundBuffer{
+            queue: VecDeque::new(),
+            written: 0,
+            buffered: 0,
+            limit,
+            policy
+        }
+    }
+
+    /// Amount of bytes waiting to be written.
+    pub fn buffered(&self) -> usize {
+        self.buffered
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Queues an encoded packet.
+    ///
+    /// Returns `Ok(true)` if the packet was queued and `Ok(false)` if the policy
+    /// decided to drop it. An error means the client should be disconnected.
+    pub fn push(&mut self, priority: Priority, packet: Vec<u8>) -> std::io::Result<bool> {
+        if self.buffered + packet.len() > self.limit {
+            match self.policy.on_overflow(self.buffered, self.limit, priority) {
+                Overflow::Drop => return Ok(false),
+                Overflow::Evict(evictable) => {
+                    self.evict(evictable, packet.len());
+                    if self.buffered + packet.len() > self.limit {
+                        return Err(overflowed());
+                    }
+                }
+                Overflow::Disconnect => return Err(overflowed())
+            }
+        }
+        self.buffered += packet.len();
+        self.queue.push_back((priority, packet));
+        Ok(true)
+    }
+
+    /// Removes buffered packets up to the given priority, oldest first, until
+    /// `needed` more bytes fit. A partially written packet is never evicted.
+    fn evict(&mut self, evictable: Priority, needed: usize) {
+        let limit = self.limit;
+        let mut buffered = self.buffered;
+        let mut index = 0;
+        let written = self.written;
+        self.queue.retain(|(priority, packet)| {
+            let in_flight = index == 0 && written > 0;
+            index += 1;
+            if in_flight || buffered + needed <= limit || *priority > evictable {
+                true
+            }else{
+                buffered -= packet.len();
+                false
+            }
+        });
+        self.buffered = buffered;
+    }
+
+    /// Writes as much of the buffer as the writer accepts.
+    ///
+    /// A writer returning `ErrorKind::WouldBlock` simply stops the flush; the
+    /// rest of the buffer is kept for the next call.
+    pub fn flush_to<W: Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        while let Some((_, packet)) = self.queue.front() {
+            match writer.write(&packet[self.written..]) {
+                Ok(0) => return Err(Error::new(ErrorKind::WriteZero, "failed to write buffered packet")),
+                Ok(n) => {
+                    self.written += n;
+                    if self.written == packet.len() {
+                        self.buffered -= packet.len();
+                        self.written = 0;
+                        self.queue.pop_front();
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e)
+            }
+        }
+        writer.flush()
+    }
+}
+
+fn overflowed() -> Error {
+    Error::other("outbound buffer limit exceeded")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accepts `budget` more bytes, then blocks.
+    struct Throttled{
+        out: Vec<u8>,
+        budget: usize
+    }
+
+    impl Write for Throttled{
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.budget == 0 {
+                return Err(ErrorKind::WouldBlock.into());
+            }
+            let n = std::cmp::min(self.budget, buf.len());
+            self.out.extend_from_slice(&buf[..n]);
+            self.budget -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn low_priority_packets_are_dropped_first() {
+        let mut buffer = OutboundBuffer::new(6);
+        assert!(buffer.push(Priority::Low, vec![1; 2]).unwrap());
+        assert!(buffer.push(Priority::Normal, vec![2; 2]).unwrap());
+        assert!(buffer.push(Priority::Low, vec![3; 2]).unwrap());
+        // A low packet that does not fit is dropped itself.
+        assert!(!buffer.push(Priority::Low, vec![4; 2]).unwrap());
+        // A more important one evicts the oldest low packet.
+        assert!(buffer.push(Priority::High, vec![5; 2]).unwrap());
+        assert_eq!(buffer.buffered(), 6);
+        let mut writer = Throttled{ out: Vec::new(), budget: usize::MAX };
+        buffer.flush_to(&mut writer).unwrap();
+        assert_eq!(writer.out, [2, 2, 3, 3, 5, 5]);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn packets_that_still_do_not_fit_disconnect() {
+        let mut buffer = OutboundBuffer::new(4);
+        buffer.push(Priority::Normal, vec![0; 3]).unwrap();
+        buffer.push(Priority::Low, vec![0; 1]).unwrap();
+        assert!(buffer.push(Priority::Normal, vec![0; 2]).is_err());
+        let mut buffer = OutboundBuffer::with_policy(4, DisconnectImmediately);
+        buffer.push(Priority::Low, vec![0; 4]).unwrap();
+        assert!(buffer.push(Priority::High, vec![0; 1]).is_err());
+    }
+
+    #[test]
+    fn partial_writes_resume_where_they_stopped() {
+        let mut buffer = OutboundBuffer::new(8);
+        buffer.push(Priority::Low, vec![1, 2, 3]).unwrap();
+        buffer.push(Priority::Normal, vec![4, 5]).unwrap();
+        let mut writer = Throttled{ out: Vec::new(), budget: 2 };
+        buffer.flush_to(&mut writer).unwrap();
+        assert_eq!(buffer.buffered(), 5);
+        // The packet being written is never evicted, even if it is low priority.
+        buffer.push(Priority::High, vec![6, 7, 8, 9]).unwrap_err();
+        writer.budget = usize::MAX;
+        buffer.flush_to(&mut writer).unwrap();
+        assert_eq!(writer.out, [1, 2, 3, 4, 5]);
+        assert_eq!(buffer.buffered(), 0);
+    }
+}
@@ -0,0 +1,165 @@
+//! Bundles of 1.19.4 and later: clientbound packets between two
+//! BundleDelimiter packets, which the client handles within the same tick.
+//!
+//! Servers bundle e.g. spawning an entity with its metadata and equipment.
+//! Handling the packets of a bundle one by one lets others interleave, which
+//! trackers can not make sense of. A `Bundler` collects them instead and hands
+//! the whole bundle over at once, `PacketStream::read_bundled` reads that way.
+use crate::protocol::Protocol;
+use std::io::{Error, ErrorKind};
+
+/// The name of the delimiter in every protocol definition that has it.
+pub const DELIMITER: &str = "BundleDelimiter";
+
+/// The most packets vanilla clients accept in one bundle.
+pub const MAX_BUNDLE_LEN: usize = 4096;
+
+/// A bundle of more than `MAX_BUNDLE_LEN` packets. Holds the packets read
+/// since its opening delimiter, the refused one included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleTooLong<P>{
+    pub packets: Vec<P>
+}
+
+impl<P> std::fmt::Display for BundleTooLong<P>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bundle of more than {} packets", MAX_BUNDLE_LEN)
+    }
+}
+
+impl<P: std::fmt::Debug> std::error::Error for BundleTooLong<P>{}
+
+impl<P> From<BundleTooLong<P>> for Error{
+    /// Drops the packets, keep them by handling `BundleTooLong` first.
+    fn from(error: BundleTooLong<P>) -> Self {
+        Error::new(ErrorKind::InvalidData, error.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bundled<P>{
+    Single(P),
+    /// The packets of a bundle, without its delimiters.
+    Bundle(Vec<P>)
+}
+
+impl<P: Protocol> Bundled<P>{
+    /// The packets as they are sent, delimiters included. A bundle of a
+    /// protocol without BundleDelimiter is returned without delimiters.
+    pub fn into_packets(self) -> Vec<P> where P: 'static {
+        match self {
+            Bundled::Single(packet) => vec![packet],
+            Bundled::Bundle(mut packets) => match (delimiter::<P>(), delimiter::<P>()) {
+                (Some(open), Some(close)) => {
+                    packets.insert(0, open);
+                    packets.push(close);
+                    packets
+                }
+                _ => packets
+            }
+        }
+    }
+}
+
+/// Whether the packet is a BundleDelimiter.
+pub fn is_delimiter<P: Protocol>(packet: &P) -> bool {
+    packet.packet_name() == DELIMITER
+}
+
+/// A new BundleDelimiter, if the protocol has one.
+pub fn delimiter<P: Protocol + 'static>() -> Option<P> {
+    P::descriptors().into_iter().find(|kind| kind.descriptor.name == DELIMITER).map(|kind| kind.create())
+}
+
+/// Groups the packets of bundles, see the module documentation.
+#[derive(Debug)]
+pub struct Bundler<P>{
+    open: Option<Vec<P>>
+}
+
+impl<P> Default for Bundler<P>{
+    fn default() -> Self {
+        Bundler{ open: None }
+    }
+}
+
+impl<P: Protocol> Bundler<P>{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the next packet. Returns it right away unless a bundle is open,
+    /// and the bundle once it is closed. Bundles of more than `MAX_BUNDLE_LEN`
+    /// packets are refused like vanilla clients refuse them, the error holds
+    /// the packets of the refused bundle.
+    pub fn push(&mut self, packet: P) -> Result<Option<Bundled<P>>, BundleTooLong<P>> {
+        if is_delimiter(&packet) {
+            return Ok(match self.open.take() {
+                Some(packets) => Some(Bundled::Bundle(packets)),
+                None => {
+                    self.open = Some(Vec::new());
+                    None
+                }
+            });
+        }
+        match &mut self.open {
+            Some(packets) if packets.len() >= MAX_BUNDLE_LEN => {
+                let mut packets = self.open.take().unwrap_or_default();
+                packets.push(packet);
+                Err(BundleTooLong{ packets })
+            }
+            Some(packets) => {
+                packets.push(packet);
+                Ok(None)
+            }
+            None => Ok(Some(Bundled::Single(packet)))
+        }
+    }
+
+    /// Whether a bundle was opened and not closed yet.
+    pub fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// The packets of the open bundle, e.g. when the connection closes before
+    /// the bundle does.
+    pub fn take_open(&mut self) -> Option<Vec<P>> {
+        self.open.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::test_protocol::TestProto;
+
+    fn flag() -> TestProto {
+        TestProto::descriptors().into_iter().find(|kind| kind.descriptor.name == "Flag").unwrap().create()
+    }
+
+    #[test]
+    fn packets_between_delimiters_are_bundled() {
+        let mut bundler = Bundler::<TestProto>::new();
+        assert!(matches!(bundler.push(flag()), Ok(Some(Bundled::Single(_)))));
+        assert!(bundler.push(delimiter().unwrap()).unwrap().is_none());
+        assert!(bundler.push(flag()).unwrap().is_none());
+        assert!(bundler.push(flag()).unwrap().is_none());
+        let bundled = bundler.push(delimiter().unwrap()).unwrap().unwrap();
+        assert!(matches!(&bundled, Bundled::Bundle(packets) if packets.len() == 2));
+        assert_eq!(bundled.into_packets().len(), 4);
+        assert!(!bundler.is_open());
+    }
+
+    #[test]
+    fn too_long_bundle_keeps_its_packets() {
+        let mut bundler = Bundler::<TestProto>::new();
+        bundler.push(delimiter().unwrap()).unwrap();
+        for _ in 0..MAX_BUNDLE_LEN {
+            assert!(bundler.push(flag()).unwrap().is_none());
+        }
+        let error = bundler.push(flag()).unwrap_err();
+        assert_eq!(error.packets.len(), MAX_BUNDLE_LEN + 1);
+        assert!(!bundler.is_open());
+        assert_eq!(Error::from(error).kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,304 @@
+//! A `tokio_util` codec reading and writing whole packets. Like
+//! `PacketStream` it frames, compresses and encrypts them, following the
+//! switches made during login through `Framed::codec_mut`:
+//!
+//! ```ignore
+//! let mut framed = Framed::new(socket, PacketCodec::<Proto_1_17>::new(State::Handshaking, Direction::ServerBound));
+//! while let Some(packet) = framed.next().await {
+//!     if let Proto_1_17::Handshake(handshake) = packet? {
+//!         framed.codec_mut().set_state(State::Login);
+//!     }
+//! }
+//! ```
+use crate::connection::frame::FrameLimits;
+use crate::connection::pool;
+use crate::connection::stream::{compress_data, Adaptive};
+use crate::io::borrowed::read_var_int;
+use crate::protocol::quarantine::{decode_or_quarantine, DecodeFailure};
+use crate::protocol::{Direction, Protocol, State};
+use crate::segment::implementation::var::{encode_var_int, var_int_len, write_var_int};
+use bytes::BytesMut;
+use std::io::{Error, ErrorKind};
+use std::marker::PhantomData;
+use tokio_util::codec::{Decoder, Encoder, FramedRead};
+
+/// Decodes packets of `P` travelling in `direction` and encodes packets for
+/// the other direction. Packets unknown to `P` are decoded as `P::Raw`.
+#[derive(Debug)]
+pub struct PacketCodec<P>{
+    state: State,
+    direction: Direction,
+    compression: Option<usize>,
+    #[cfg(feature = "encryption")]
+    encryption: Option<(crate::connection::encryption::Cfb8, crate::connection::encryption::Cfb8)>,
+    /// Bytes at the front of the read buffer that were decrypted already.
+    decrypted: usize,
+    limits: FrameLimits,
+    /// Statistics of the packets encoded, see `track_compression`.
+    adaptive: Adaptive,
+    protocol: PhantomData<fn() -> P>
+}
+
+pub type McCodec<P> = PacketCodec<P>;
+
+/// The packets read from `io` as a `Stream`, the async counterpart of
+/// `PacketStream::packets`. Switches are made through `FramedRead::decoder_mut`.
+pub fn packets<P: Protocol, T>(io: T, state: State, direction: Direction) -> FramedRead<T, PacketCodec<P>> {
+    FramedRead::new(io, PacketCodec::new(state, direction))
+}
+
+impl<P: Protocol> PacketCodec<P>{
+    pub fn new(state: State, direction: Direction) -> Self {
+        PacketCodec{
+            state,
+            direction,
+            compression: None,
+            #[cfg(feature = "encryption")]
+            encryption: None,
+            decrypted: 0,
+            limits: FrameLimits::default(),
+            adaptive: Default::default(),
+            protocol: PhantomData
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: State) {
+        self.state = state;
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Refuses frames longer than `limits` allow in the current state from now
+    /// on, instead of the vanilla limits.
+    pub fn set_frame_limits(&mut self, limits: FrameLimits) {
+        self.limits = limits;
+    }
+
+    pub fn frame_limits(&self) -> FrameLimits {
+        self.limits
+    }
+
+    /// Compresses packets of at least `threshold` bytes from now on, `None` turns compression off.
+    /// Fails if the crate was built without the `compression` feature.
+    pub fn set_compression(&mut self, threshold: Option<usize>) -> std::io::Result<()> {
+        if threshold.is_some() && cfg!(not(feature = "compression")) {
+            return Err(Error::new(ErrorKind::Unsupported, "the peer enabled compression, which needs the `compression` feature"));
+        }
+        self.compression = threshold;
+        Ok(())
+    }
+
+    pub fn compression(&self) -> Option<usize> {
+        self.compression
+    }
+
+    /// Counts what compression does to the packets encoded from now on, see
+    /// `compression_stats`. With `adaptive` the threshold packets are
+    /// compressed with is raised to what the counts suggest, see
+    /// `compression::AdaptiveCompression`.
+    #[cfg(feature = "compression")]
+    pub fn track_compression(&mut self, adaptive: bool) {
+        self.adaptive = Some(crate::connection::compression::AdaptiveCompression::new(adaptive));
+    }
+
+    #[cfg(feature = "compression")]
+    pub fn compression_stats(&self) -> Option<&crate::connection::compression::CompressionStats> {
+        self.adaptive.as_ref().map(|adaptive| adaptive.stats())
+    }
+
+    /// The threshold packets are encoded with, which an adaptive codec may
+    /// have raised above `compression`.
+    #[cfg(feature = "compression")]
+    pub fn send_threshold(&self) -> Option<usize> {
+        let threshold = self.compression?;
+        Some(self.adaptive.as_ref().map_or(threshold, |adaptive| adaptive.threshold(threshold)))
+    }
+
+    /// Encrypts everything decoded and encoded from now on, including bytes
+    /// already buffered but not decoded yet.
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) {
+        use crate::connection::encryption::Cfb8;
+        self.encryption = Some((Cfb8::new(shared_secret), Cfb8::new(shared_secret)));
+        self.decrypted = 0;
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        return self.encryption.is_some();
+        #[cfg(not(feature = "encryption"))]
+        return false;
+    }
+
+    fn decode_packet(&self, mut data: &[u8]) -> std::io::Result<P> {
+        let id = read_var_int(&mut data)?;
+        P::decode_body(self.state, self.direction, id, data)
+    }
+}
+
+impl<P: Protocol> PacketCodec<P>{
+    /// Takes the next whole frame off `src` and hands its packet id and body
+    /// to `decode`, decompressed.
+    fn decode_frame<T>(&mut self, src: &mut BytesMut, decode: impl FnOnce(&Self, &[u8]) -> std::io::Result<T>) -> std::io::Result<Option<T>> {
+        #[cfg(feature = "encryption")]
+        if let Some((_, decryptor)) = &mut self.encryption {
+            decryptor.decrypt(&mut src[self.decrypted..]);
+        }
+        self.decrypted = src.len();
+        let mut cursor = &src[..];
+        let len = match read_var_int(&mut cursor) {
+            Ok(len) => len,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e)
+        };
+        self.limits.check(self.state, self.compression.is_some(), len)?;
+        let frame_len = var_int_len(len) + len as usize;
+        if src.len() < frame_len {
+            src.reserve(frame_len - src.len());
+            return Ok(None);
+        }
+        let frame = src.split_to(frame_len);
+        self.decrypted -= frame_len;
+        crate::metrics::bytes_read(frame_len);
+        let body = &frame[var_int_len(len)..];
+        match self.compression {
+            #[cfg(feature = "compression")]
+            Some(_) => {
+                let data = crate::connection::compression::decompress(body)?;
+                let packet = decode(self, &data);
+                pool::recycle(data);
+                packet.map(Some)
+            }
+            _ => decode(self, body).map(Some)
+        }
+    }
+}
+
+impl<P: Protocol> Decoder for PacketCodec<P>{
+    type Item = P;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> std::io::Result<Option<P>> {
+        self.decode_frame(src, Self::decode_packet)
+    }
+}
+
+/// A `PacketCodec` that decodes packets failing to decode as `DecodeFailure`s
+/// instead of failing the stream, see `protocol::quarantine`. Switches are
+/// made on the inner codec.
+#[derive(Debug)]
+pub struct QuarantineCodec<P>(pub PacketCodec<P>);
+
+impl<P: Protocol> Decoder for QuarantineCodec<P>{
+    type Item = Result<P, DecodeFailure>;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> std::io::Result<Option<Self::Item>> {
+        self.0.decode_frame(src, |codec, mut data| {
+            let id = read_var_int(&mut data)?;
+            Ok(decode_or_quarantine(codec.state, codec.direction, id, data))
+        })
+    }
+}
+
+impl<P: Protocol> Encoder<P> for QuarantineCodec<P>{
+    type Error = Error;
+
+    fn encode(&mut self, packet: P, dst: &mut BytesMut) -> std::io::Result<()> {
+        Encoder::<&P>::encode(&mut self.0, &packet, dst)
+    }
+}
+
+impl<P: Protocol> Encoder<&P> for QuarantineCodec<P>{
+    type Error = Error;
+
+    fn encode(&mut self, packet: &P, dst: &mut BytesMut) -> std::io::Result<()> {
+        Encoder::<&P>::encode(&mut self.0, packet, dst)
+    }
+}
+
+impl<P: Protocol> Encoder<P> for PacketCodec<P>{
+    type Error = Error;
+
+    fn encode(&mut self, packet: P, dst: &mut BytesMut) -> std::io::Result<()> {
+        Encoder::<&P>::encode(self, &packet, dst)
+    }
+}
+
+impl<P: Protocol> Encoder<&P> for PacketCodec<P>{
+    type Error = Error;
+
+    fn encode(&mut self, packet: &P, dst: &mut BytesMut) -> std::io::Result<()> {
+        let mut data = pool::take_packet(packet.id());
+        write_var_int(packet.id(), &mut data)?;
+        packet.write_to_stream(&mut data)?;
+        let body = compress_data(data, self.compression, &mut self.adaptive)?;
+        let start = dst.len();
+        let (header, header_len) = encode_var_int(body.len() as i32);
+        dst.extend_from_slice(&header[..header_len]);
+        dst.extend_from_slice(&body);
+        pool::recycle(body);
+        #[cfg(feature = "encryption")]
+        if let Some((encryptor, _)) = &mut self.encryption {
+            encryptor.encrypt(&mut dst[start..]);
+        }
+        crate::metrics::bytes_written(dst.len() - start);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::frame::FrameTooLarge;
+    use crate::protocol::test_protocol::TestProto;
+
+    #[test]
+    fn oversized_frame_is_refused_before_reserving() {
+        let mut codec = PacketCodec::<TestProto>::new(State::Login, Direction::ServerBound);
+        // A frame of 8193 bytes, over the limit of Login before compression.
+        let mut src = BytesMut::from(&[0x81, 0x40][..]);
+        let error = codec.decode(&mut src).unwrap_err();
+        assert_eq!(FrameTooLarge::of(&error).map(|e| e.len), Some(8193));
+        assert!(src.capacity() < 8193);
+    }
+
+    #[test]
+    fn incomplete_frame_waits_for_more() {
+        let mut codec = PacketCodec::<TestProto>::new(State::Play, Direction::ClientBound);
+        let mut src = BytesMut::from(&[0x02, 0x01][..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&[0x01]);
+        assert!(matches!(codec.decode(&mut src).unwrap(), Some(TestProto::Flag(flag)) if flag.value));
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn encode_then_decode() {
+        let mut codec = PacketCodec::<TestProto>::new(State::Play, Direction::ClientBound);
+        let packet = codec.decode(&mut BytesMut::from(&[0x02, 0x01, 0x01][..])).unwrap().unwrap();
+        let mut dst = BytesMut::new();
+        Encoder::<TestProto>::encode(&mut codec, packet, &mut dst).unwrap();
+        assert_eq!(&dst[..], &[0x02, 0x01, 0x01]);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn compressed_encode_then_decode() {
+        let mut codec = PacketCodec::<TestProto>::new(State::Play, Direction::ClientBound);
+        let packet = codec.decode(&mut BytesMut::from(&[0x02, 0x01, 0x01][..])).unwrap().unwrap();
+        codec.set_compression(Some(0)).unwrap();
+        let mut dst = BytesMut::new();
+        Encoder::<TestProto>::encode(&mut codec, packet, &mut dst).unwrap();
+        // Length, the uncompressed length and a zlib stream.
+        assert_eq!(&dst[1..2], &[0x02]);
+        assert!(matches!(codec.decode(&mut dst).unwrap(), Some(TestProto::Flag(flag)) if flag.value));
+        assert!(dst.is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+//! Compressed framing, used once the server sent SetCompression.
+//!
+//! The frame body becomes `[VarInt data length][data]`, where the data is the
+//! zlib compressed packet id and packet, or the uncompressed ones with a data
+//! length of 0 when the packet is smaller than the threshold.
+//!
+//! `CompressionStats` counts what compression does to the packets sent, and
+//! `AdaptiveCompression` raises the threshold past the sizes where it makes
+//! packets larger instead of smaller.
+use crate::connection::pool;
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// The largest uncompressed packet the vanilla client accepts.
+pub const MAX_DATA_LEN: usize = 8_388_608;
+
+/// Turns an uncompressed frame body into a compressed one.
+pub fn compress(data: &[u8], threshold: usize, out: &mut Vec<u8>) -> std::io::Result<()> {
+    if data.len() < threshold {
+        write_var_int(0, out)?;
+        out.extend_from_slice(data);
+        return Ok(());
+    }
+    write_var_int(data.len() as i32, out)?;
+    let mut encoder = ZlibEncoder::new(out, Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Turns a compressed frame body back into the packet id and packet, in a
+/// buffer taken from the pool.
+pub fn decompress(frame: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut cursor = frame;
+    let data_len = read_var_int(&mut cursor)?;
+    if data_len == 0 {
+        let mut data = pool::take(cursor.len());
+        data.extend_from_slice(cursor);
+        return Ok(data);
+    }
+    if data_len < 0 || data_len as usize > MAX_DATA_LEN {
+        return Err(Error::new(ErrorKind::InvalidData, "invalid uncompressed length"));
+    }
+    let mut data = pool::take(std::cmp::min(data_len as usize, 64 * 1024));
+    let read = ZlibDecoder::new(cursor).take(data_len as u64).read_to_end(&mut data);
+    if read.is_err() || data.len() != data_len as usize {
+        pool::recycle(data);
+        read?;
+        return Err(Error::new(ErrorKind::InvalidData, "compressed packet is shorter than announced"));
+    }
+    Ok(data)
+}
+
+/// Sizes are grouped by powers of two, up to `MAX_DATA_LEN`.
+const SIZE_CLASSES: usize = 24;
+
+/// Packets between two adjustments of an `AdaptiveCompression`.
+pub const ADAPT_INTERVAL: u64 = 256;
+
+/// Packets before and after compression.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionCounts{
+    pub packets: u64,
+    /// Packets at or over the threshold, which were compressed.
+    pub compressed: u64,
+    /// Compressed packets that came out larger than they went in.
+    pub inflated: u64,
+    /// The packet ids and packets.
+    pub data_bytes: u64,
+    /// The frame bodies as sent, data lengths included.
+    pub body_bytes: u64
+}
+
+impl CompressionCounts{
+    fn add(&mut self, data_len: usize, body_len: usize, compressed: bool) {
+        self.packets += 1;
+        self.data_bytes += data_len as u64;
+        self.body_bytes += body_len as u64;
+        if compressed {
+            self.compressed += 1;
+            if body_len > data_len {
+                self.inflated += 1;
+            }
+        }
+    }
+
+    fn halve(&mut self) {
+        self.packets /= 2;
+        self.compressed /= 2;
+        self.inflated /= 2;
+        self.data_bytes /= 2;
+        self.body_bytes /= 2;
+    }
+
+    /// Bytes sent per byte of data, below 1 when compression pays off.
+    pub fn ratio(&self) -> f64 {
+        if self.data_bytes == 0 {
+            return 1.0;
+        }
+        self.body_bytes as f64 / self.data_bytes as f64
+    }
+}
+
+/// Compression results by packet id and in total.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompressionStats{
+    by_id: BTreeMap<i32, CompressionCounts>,
+    /// Compressed packets only, by the power of two below their data length.
+    /// Halved by `decay`.
+    by_size: [CompressionCounts; SIZE_CLASSES],
+    total: CompressionCounts
+}
+
+impl CompressionStats{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a packet of `data_len` bytes that was sent as a frame body of
+    /// `body_len` bytes.
+    pub fn record(&mut self, id: i32, data_len: usize, body_len: usize, compressed: bool) {
+        self.by_id.entry(id).or_default().add(data_len, body_len, compressed);
+        self.total.add(data_len, body_len, compressed);
+        if compressed {
+            self.by_size[size_class(data_len)].add(data_len, body_len, compressed);
+        }
+    }
+
+    pub fn total(&self) -> CompressionCounts {
+        self.total
+    }
+
+    pub fn get(&self, id: i32) -> Option<CompressionCounts> {
+        self.by_id.get(&id).copied()
+    }
+
+    /// The counts of every packet id sent, by id.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, CompressionCounts)> + '_ {
+        self.by_id.iter().map(|(&id, &counts)| (id, counts))
+    }
+
+    /// The power of two that sends the fewest bytes as threshold, never
+    /// below `negotiated`: compressing the sizes under it cost more bytes
+    /// than it saved, summed over all of them. A few packets of a size that
+    /// grew can not outweigh the bytes saved on the sizes below them. None
+    /// until packets were compressed.
+    pub fn suggest_threshold(&self, negotiated: usize) -> Option<usize> {
+        if self.by_size.iter().all(|counts| counts.compressed == 0) {
+            return None;
+        }
+        let mut saved = 0i64;
+        let mut best = (0i64, 0usize);
+        for (class, counts) in self.by_size.iter().enumerate() {
+            saved += counts.data_bytes as i64 - counts.body_bytes as i64;
+            if saved < best.0 {
+                best = (saved, class + 1);
+            }
+        }
+        Some(match best.1 {
+            0 => negotiated,
+            classes => (1 << classes).max(negotiated)
+        })
+    }
+
+    /// Halves the counts by size that `suggest_threshold` weighs, so its
+    /// suggestion follows the packets sent lately. The counts by id and the
+    /// total are kept.
+    pub fn decay(&mut self) {
+        for counts in &mut self.by_size {
+            counts.halve();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn size_class(data_len: usize) -> usize {
+    let class = usize::BITS - data_len.max(1).leading_zeros() - 1;
+    (class as usize).min(SIZE_CLASSES - 1)
+}
+
+/// Counts the packets sent and, when adaptive, raises the threshold every
+/// `ADAPT_INTERVAL` packets to the one `CompressionStats::suggest_threshold`
+/// suggests. Peers refuse compressed packets under the threshold they sent,
+/// so the threshold is only ever raised above that one. The counts by size
+/// decay at every adjustment, so a threshold raised for packets sent long
+/// ago falls back and the sizes under it are tried again.
+#[derive(Debug, Clone, Default)]
+pub struct AdaptiveCompression{
+    stats: CompressionStats,
+    adaptive: bool,
+    raised: Option<usize>,
+    /// Packets since the last adjustment.
+    since_adjusted: u64
+}
+
+impl AdaptiveCompression{
+    /// Only counts packets unless `adaptive`.
+    pub fn new(adaptive: bool) -> Self {
+        AdaptiveCompression{ stats: CompressionStats::new(), adaptive, raised: None, since_adjusted: 0 }
+    }
+
+    pub fn stats(&self) -> &CompressionStats {
+        &self.stats
+    }
+
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    /// The threshold to compress with, given the one the peer was sent.
+    pub fn threshold(&self, negotiated: usize) -> usize {
+        self.raised.map_or(negotiated, |raised| raised.max(negotiated))
+    }
+
+    /// Compresses like `compress` with `threshold(negotiated)` and counts the result.
+    pub fn compress(&mut self, id: i32, data: &[u8], negotiated: usize, out: &mut Vec<u8>) -> std::io::Result<()> {
+        let threshold = self.threshold(negotiated);
+        let start = out.len();
+        compress(data, threshold, out)?;
+        self.stats.record(id, data.len(), out.len() - start, data.len() >= threshold);
+        self.since_adjusted += 1;
+        if self.adaptive && self.since_adjusted >= ADAPT_INTERVAL {
+            self.since_adjusted = 0;
+            self.raised = self.stats.suggest_threshold(0);
+            self.stats.decay();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(stats: &mut CompressionStats, packets: usize, data_len: usize, body_len: usize) {
+        for _ in 0..packets {
+            stats.record(0x22, data_len, body_len, true);
+        }
+    }
+
+    #[test]
+    fn no_suggestion_before_compressing() {
+        let mut stats = CompressionStats::new();
+        stats.record(0x22, 10, 11, false);
+        assert_eq!(stats.suggest_threshold(256), None);
+    }
+
+    #[test]
+    fn losing_sizes_are_weighed_by_bytes() {
+        let mut stats = CompressionStats::new();
+        record(&mut stats, 10, 40, 45);
+        record(&mut stats, 10, 1024, 200);
+        // A single packet above the winning sizes that grew by a byte.
+        record(&mut stats, 1, 4096, 4097);
+        assert_eq!(stats.suggest_threshold(0), Some(64));
+        assert_eq!(stats.suggest_threshold(256), Some(256));
+    }
+
+    #[test]
+    fn winning_sizes_keep_the_negotiated_threshold() {
+        let mut stats = CompressionStats::new();
+        record(&mut stats, 10, 40, 30);
+        record(&mut stats, 10, 100, 50);
+        assert_eq!(stats.suggest_threshold(32), Some(32));
+    }
+
+    #[test]
+    fn counts_by_size_decay() {
+        let mut stats = CompressionStats::new();
+        record(&mut stats, 100, 40, 45);
+        assert_eq!(stats.suggest_threshold(0), Some(64));
+        for _ in 0..16 {
+            stats.decay();
+        }
+        assert_eq!(stats.suggest_threshold(0), None);
+        assert_eq!(stats.total().packets, 100);
+    }
+
+    #[test]
+    fn compress_round_trip() {
+        let data = vec![7u8; 1000];
+        let mut body = Vec::new();
+        compress(&data, 256, &mut body).unwrap();
+        assert!(body.len() < data.len());
+        assert_eq!(decompress(&body).unwrap(), data);
+        let mut body = Vec::new();
+        compress(&data[..10], 256, &mut body).unwrap();
+        assert_eq!(body[0], 0);
+        assert_eq!(decompress(&body).unwrap(), &data[..10]);
+    }
+}
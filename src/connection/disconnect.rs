@@ -0,0 +1,76 @@
+//! Disconnecting a client with a reason it gets to see.
+//!
+//! Login, configuration and play each have their own disconnect packet, and sending the one
+//! of the wrong state leaves the client with a decoding error instead of the
+//! reason. `PacketStream::disconnect` looks the right one up in the protocol,
+//! sends it and closes the sending half of the connection, so the client reads
+//! the packet before it sees the connection end.
+use crate::chat::Component;
+use crate::connection::stream::PacketStream;
+use crate::protocol::{Direction, PacketDescriptor, Protocol, State};
+use crate::segment::implementation::var::write_var_int;
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+
+/// Name of the packet carrying a disconnect reason in login.
+pub const LOGIN_DISCONNECT: &str = "LoginDisconnect";
+
+/// Name of the packet carrying a disconnect reason in configuration.
+pub const CONFIGURATION_DISCONNECT: &str = "ConfigurationDisconnect";
+
+/// Name of the packet carrying a disconnect reason in play.
+pub const PLAY_DISCONNECT: &str = "Disconnect";
+
+/// The first protocol version sending disconnect reasons outside of login as NBT, 1.20.3.
+pub const NBT_REASON_PROTOCOL: i32 = 765;
+
+/// Streams that can stop sending while still being able to read.
+pub trait HalfClose{
+    fn close_write(&mut self) -> std::io::Result<()>;
+}
+
+impl HalfClose for TcpStream{
+    fn close_write(&mut self) -> std::io::Result<()> {
+        self.shutdown(Shutdown::Write)
+    }
+}
+
+impl<S: HalfClose + ?Sized> HalfClose for &mut S{
+    fn close_write(&mut self) -> std::io::Result<()> {
+        (**self).close_write()
+    }
+}
+
+/// The packet of `P` that carries a disconnect reason in `state`. Handshaking
+/// and status have none, the connection is just closed there.
+pub fn disconnect_packet<P: Protocol>(state: State) -> Option<&'static PacketDescriptor> {
+    let name = match state {
+        State::Login => LOGIN_DISCONNECT,
+        State::Configuration => CONFIGURATION_DISCONNECT,
+        State::Play => PLAY_DISCONNECT,
+        State::Handshaking | State::Status => return None
+    };
+    P::packets().iter()
+        .find(|packet| packet.state == state && packet.direction == Direction::ClientBound && packet.name == name)
+}
+
+impl<S: Read + Write + HalfClose> PacketStream<S>{
+    /// Sends the disconnect packet of `state` with `reason`, then closes the
+    /// sending half of the connection. Reading can continue until the client
+    /// closes its side.
+    pub fn disconnect<P: Protocol>(&mut self, state: State, reason: &Component) -> std::io::Result<()> {
+        if let Some(packet) = disconnect_packet::<P>(state) {
+            let mut body = Vec::new();
+            if state != State::Login && P::PROTOCOL >= NBT_REASON_PROTOCOL {
+                reason.to_nbt().write_network(&mut body)?;
+            }else{
+                let reason = reason.to_json();
+                write_var_int(reason.len() as i32, &mut body)?;
+                body.extend_from_slice(reason.as_bytes());
+            }
+            self.write_raw(packet.id, &body)?;
+        }
+        self.get_mut().flush()?;
+        self.get_mut().close_write()
+    }
+}
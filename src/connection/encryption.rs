@@ -0,0 +1,54 @@
+//! The AES/CFB8 stream cipher connections switch to after the encryption
+//! handshake. The shared secret is used as both the key and the IV.
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+
+/// One direction of an encrypted connection.
+#[derive(Clone)]
+pub struct Cfb8{
+    cipher: Aes128,
+    register: [u8; 16]
+}
+
+impl std::fmt::Debug for Cfb8{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Cfb8")
+    }
+}
+
+impl Cfb8{
+    pub fn new(shared_secret: &[u8; 16]) -> Self {
+        Cfb8{
+            cipher: Aes128::new(GenericArray::from_slice(shared_secret)),
+            register: *shared_secret
+        }
+    }
+
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte ^= self.keystream();
+            self.shift(*byte);
+        }
+    }
+
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data {
+            let ciphertext = *byte;
+            *byte ^= self.keystream();
+            self.shift(ciphertext);
+        }
+    }
+
+    fn keystream(&self) -> u8 {
+        let mut block = GenericArray::clone_from_slice(&self.register);
+        self.cipher.encrypt_block(&mut block);
+        block[0]
+    }
+
+    /// Feeds a byte of ciphertext back into the register.
+    fn shift(&mut self, ciphertext: u8) {
+        self.register.copy_within(1.., 0);
+        self.register[15] = ciphertext;
+    }
+}
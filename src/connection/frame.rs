@@ -0,0 +1,233 @@
+//! Uncompressed packet framing: `[VarInt length][VarInt packet id][body]`.
+use crate::connection::pool;
+use crate::protocol::{Packet, State};
+use crate::segment::implementation::var::{read_var_int, var_int_len, write_var_int};
+use std::io::{Error, ErrorKind, Read};
+
+/// The largest frame the vanilla server accepts, the length has to fit in a three byte VarInt.
+pub const MAX_FRAME_LEN: i32 = 2_097_151;
+
+/// The longest Login frame before compression is enabled. Nothing sent then
+/// comes close, so peers announcing more are refused early.
+pub const MAX_UNCOMPRESSED_LOGIN_FRAME_LEN: i32 = 8 * 1024;
+
+/// The longest frame accepted in every state, checked against the length
+/// prefix before anything is allocated for the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLimits{
+    pub handshaking: i32,
+    pub status: i32,
+    pub login: i32,
+    /// Login before compression is enabled.
+    pub uncompressed_login: i32,
+    pub configuration: i32,
+    pub play: i32
+}
+
+impl Default for FrameLimits{
+    /// The limits of vanilla.
+    fn default() -> Self {
+        FrameLimits{
+            handshaking: MAX_FRAME_LEN,
+            status: MAX_FRAME_LEN,
+            login: MAX_FRAME_LEN,
+            uncompressed_login: MAX_UNCOMPRESSED_LOGIN_FRAME_LEN,
+            configuration: MAX_FRAME_LEN,
+            play: MAX_FRAME_LEN
+        }
+    }
+}
+
+impl FrameLimits{
+    /// Replaces the limit of `state`, and of Login before compression when `state` is Login.
+    pub fn with(mut self, state: State, max: i32) -> Self {
+        match state {
+            State::Handshaking => self.handshaking = max,
+            State::Status => self.status = max,
+            State::Login => {
+                self.login = max;
+                self.uncompressed_login = max;
+            }
+            State::Configuration => self.configuration = max,
+            State::Play => self.play = max
+        }
+        self
+    }
+
+    /// The longest frame accepted in `state`.
+    pub fn max(&self, state: State, compressed: bool) -> i32 {
+        match state {
+            State::Handshaking => self.handshaking,
+            State::Status => self.status,
+            State::Login if compressed => self.login,
+            State::Login => self.uncompressed_login,
+            State::Configuration => self.configuration,
+            State::Play => self.play
+        }
+    }
+
+    /// Checks the length prefix of a frame, failing with `FrameTooLarge`
+    /// inside an `InvalidData` error.
+    pub fn check(&self, state: State, compressed: bool, len: i32) -> std::io::Result<()> {
+        if len < 1 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("invalid frame length {}", len)));
+        }
+        let max = self.max(state, compressed);
+        if len > max {
+            return Err(Error::new(ErrorKind::InvalidData, FrameTooLarge{ state, len, max }));
+        }
+        Ok(())
+    }
+}
+
+/// A frame longer than its `FrameLimits` allow, carried inside the
+/// `std::io::Error`. Use `FrameTooLarge::of` to get at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTooLarge{
+    pub state: State,
+    /// The announced length.
+    pub len: i32,
+    pub max: i32
+}
+
+impl FrameTooLarge{
+    pub fn of(error: &Error) -> Option<&FrameTooLarge> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl std::fmt::Display for FrameTooLarge{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "frame of {} bytes exceeds the {} bytes allowed in the {:?} state", self.len, self.max, self.state)
+    }
+}
+
+impl std::error::Error for FrameTooLarge{}
+
+/// Bytes reserved up front when reading a frame, the rest is allocated as it arrives.
+const INITIAL_CAPACITY: usize = 64 * 1024;
+
+/// Length of the frame body, which is the packet id plus the packet itself.
+/// Fails if the packet can not be written.
+#[inline]
+pub fn body_len<P: Packet>(packet: &P) -> std::io::Result<usize> {
+    Ok(var_int_len(packet.packet_id()) + packet.try_serialized_len()?)
+}
+
+/// Total amount of bytes `write_frame` produces for this packet.
+#[inline]
+pub fn frame_len<P: Packet>(packet: &P) -> std::io::Result<usize> {
+    let body = body_len(packet)?;
+    Ok(var_int_len(body as i32) + body)
+}
+
+pub fn write_frame<P: Packet, W: std::io::Write>(packet: &P, writer: &mut W) -> std::io::Result<()> {
+    let body = body_len(packet)?;
+    write_var_int(body as i32, writer)?;
+    write_var_int(packet.packet_id(), writer)?;
+    packet.write_to_stream(writer)?;
+    crate::metrics::bytes_written(var_int_len(body as i32) + body);
+    Ok(())
+}
+
+/// Reads a single frame of `state`, returning the packet id and the
+/// remaining body. Frames longer than `limits` allow are rejected before
+/// anything is allocated for them. The body is taken from the buffer pool,
+/// see `connection::recycle_buffer`.
+pub fn read_frame<R: Read>(reader: &mut R, limits: &FrameLimits, state: State) -> std::io::Result<(i32, Vec<u8>)> {
+    let mut body = pool::take(0);
+    match read_frame_into(reader, &mut body, limits, state) {
+        Ok(id) => Ok((id, body)),
+        Err(e) => {
+            pool::recycle(body);
+            Err(e)
+        }
+    }
+}
+
+/// Like `read_frame`, but reads the body into `body`, replacing its contents.
+/// Reusing the buffer saves an allocation per packet.
+pub fn read_frame_into<R: Read>(reader: &mut R, body: &mut Vec<u8>, limits: &FrameLimits, state: State) -> std::io::Result<i32> {
+    let len = read_var_int(reader)?;
+    // These frames are never compressed.
+    limits.check(state, false, len)?;
+    let mut frame = reader.take(len as u64);
+    let id = read_var_int(&mut frame)?;
+    // What is left of the frame is the body, however many bytes the id was sent in.
+    let body_len = frame.limit() as usize;
+    body.clear();
+    body.reserve(std::cmp::min(body_len, INITIAL_CAPACITY));
+    frame.read_to_end(body)?;
+    if body.len() != body_len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "packet ended prematurely"));
+    }
+    crate::metrics::bytes_read(var_int_len(len) + len as usize);
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Login frame of 8193 bytes, one over `MAX_UNCOMPRESSED_LOGIN_FRAME_LEN`, without its body.
+    const LONG_LOGIN_PREFIX: [u8; 2] = [0x81, 0x40];
+
+    #[test]
+    fn oversized_frame_is_refused_before_its_body() {
+        let mut reader = &LONG_LOGIN_PREFIX[..];
+        let mut body = Vec::new();
+        let error = read_frame_into(&mut reader, &mut body, &FrameLimits::default(), State::Login).unwrap_err();
+        assert_eq!(FrameTooLarge::of(&error), Some(&FrameTooLarge{ state: State::Login, len: 8193, max: MAX_UNCOMPRESSED_LOGIN_FRAME_LEN }));
+        assert_eq!(body.capacity(), 0);
+    }
+
+    #[test]
+    fn limits_follow_the_state() {
+        let error = read_frame(&mut &LONG_LOGIN_PREFIX[..], &FrameLimits::default(), State::Play).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        let limits = FrameLimits::default().with(State::Play, 4);
+        let error = read_frame(&mut &[0x05, 0x00, 0, 0, 0, 0][..], &limits, State::Play).unwrap_err();
+        assert_eq!(FrameTooLarge::of(&error).map(|e| e.max), Some(4));
+    }
+
+    #[test]
+    fn empty_frame_is_invalid() {
+        let error = read_frame(&mut &[0x00][..], &FrameLimits::default(), State::Play).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn frame_round_trip() {
+        let limits = FrameLimits::default();
+        let (id, body) = read_frame(&mut &[0x03, 0x22, 0x01, 0x02][..], &limits, State::Play).unwrap();
+        assert_eq!((id, &body[..]), (0x22, &[0x01, 0x02][..]));
+    }
+
+    #[test]
+    fn overlong_id_leaves_the_body_intact() {
+        // Id 0 sent in two bytes.
+        let (id, body) = read_frame(&mut &[0x03, 0x80, 0x00, 0xAA][..], &FrameLimits::default(), State::Play).unwrap();
+        assert_eq!((id, &body[..]), (0, &[0xAA][..]));
+    }
+
+    #[test]
+    fn failing_condition_fails_the_write() {
+        use crate::protocol::test_protocol::Optional;
+        use crate::protocol::FieldError;
+        let packet = Optional{ present: 2, value: 7 };
+        let error = body_len(&packet).unwrap_err();
+        let field = error.get_ref().and_then(|e| e.downcast_ref::<FieldError>()).unwrap();
+        assert_eq!((field.packet, field.field), ("Optional", "value"));
+
+        let mut frame = Vec::new();
+        assert!(write_frame(&packet, &mut frame).is_err());
+        assert!(frame.is_empty());
+        assert_eq!(frame_len(&Optional{ present: 1, value: 7 }).unwrap(), 11);
+    }
+
+    #[test]
+    fn id_past_the_frame_is_an_error() {
+        let error = read_frame(&mut &[0x01, 0x80, 0x00][..], &FrameLimits::default(), State::Play).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::protocol::Direction;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryEntry<P>{
+    pub timestamp: SystemTime,
+    pub direction: Direction,
+    pub packet: P
+}
+
+/// The last few packets of a connection, kept for the error report when the
+/// connection dies.
+///
+/// Only the newest `capacity` packets are kept. A capacity of zero records
+/// nothing, which makes it cheap to leave in place when disabled.
+#[derive(Debug, Clone)]
+pub struct PacketHistory<P>{
+    capacity: usize,
+    entries: VecDeque<HistoryEntry<P>>
+}
+
+impl<P: Debug> PacketHistory<P>{
+    pub fn new(capacity: usize) -> Self {
+        PacketHistory{
+            capacity,
+            entries: VecDeque::with_capacity(capacity)
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn record(&mut self, direction: Direction, packet: P) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry{
+            timestamp: SystemTime::now(),
+            direction,
+            packet
+        });
+    }
+
+    /// Oldest entries first.
+    pub fn iter(&self) -> impl Iterator<Item = &HistoryEntry<P>> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// One line per packet, oldest first: milliseconds since the unix epoch,
+    /// direction and the packet's `Debug` output.
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        for entry in &self.entries {
+            let millis = entry.timestamp.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+            let arrow = match entry.direction {
+                Direction::ClientBound => "S->C",
+                Direction::ServerBound => "C->S"
+            };
+            let _ = writeln!(out, "{} {} {:?}", millis, arrow, entry.packet);
+        }
+        out
+    }
+
+    /// Attaches the dump to an error, so whoever logs the error gets the
+    /// packets that led up to it.
+    pub fn annotate<T>(&self, result: std::io::Result<T>) -> std::io::Result<T> {
+        result.map_err(|e| {
+            if self.entries.is_empty() {
+                e
+            }else{
+                std::io::Error::new(e.kind(), format!("{}\nlast {} packets:\n{}", e, self.entries.len(), self.dump()))
+            }
+        })
+    }
+}
@@ -0,0 +1,142 @@
+//! Keep-alive bookkeeping for both ends of a connection.
+//!
+//! The server sends a keep-alive with a fresh id every `interval` and
+//! disconnects the client if it has not echoed the id within `timeout`. The
+//! client echoes every keep-alive right away. The tracker does no I/O itself,
+//! it tells the caller what to send and when the peer timed out.
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role{
+    Client,
+    Server
+}
+
+#[derive(Debug, Clone)]
+pub struct KeepAliveTracker{
+    role: Role,
+    interval: Duration,
+    timeout: Duration,
+    /// The id sent by the server and when, until the client answers it.
+    pending: Option<(i64, Instant)>,
+    last_sent: Option<Instant>,
+    last_received: Instant,
+    latency: Option<Duration>,
+    next_id: i64
+}
+
+impl KeepAliveTracker{
+    /// Uses the vanilla timings: a keep-alive every 15 seconds, which has to
+    /// be answered within 30 seconds.
+    pub fn new(role: Role, now: Instant) -> Self {
+        Self::with_timings(role, Duration::from_secs(15), Duration::from_secs(30), now)
+    }
+
+    pub fn with_timings(role: Role, interval: Duration, timeout: Duration, now: Instant) -> Self {
+        KeepAliveTracker{
+            role,
+            interval,
+            timeout,
+            pending: None,
+            last_sent: None,
+            last_received: now,
+            latency: None,
+            next_id: 1
+        }
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// The round trip time of the last answered keep-alive. Only known to the server.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// When the last keep-alive was received from the peer.
+    pub fn last_received(&self) -> Instant {
+        self.last_received
+    }
+
+    /// Call regularly on the server. Returns the id of a keep-alive to send
+    /// when one is due, or a `TimedOut` error when the client took too long.
+    /// Clients fail once the server has been silent for `timeout`.
+    pub fn poll(&mut self, now: Instant) -> std::io::Result<Option<i64>> {
+        match self.role {
+            Role::Client => {
+                if now.saturating_duration_since(self.last_received) >= self.timeout {
+                    return Err(timed_out());
+                }
+                Ok(None)
+            }
+            Role::Server => {
+                if let Some((_, sent)) = self.pending {
+                    if now.saturating_duration_since(sent) >= self.timeout {
+                        return Err(timed_out());
+                    }
+                    return Ok(None);
+                }
+                if self.last_sent.is_some_and(|last| now.saturating_duration_since(last) < self.interval) {
+                    return Ok(None);
+                }
+                let id = self.next_id;
+                self.next_id = self.next_id.wrapping_add(1);
+                self.pending = Some((id, now));
+                self.last_sent = Some(now);
+                Ok(Some(id))
+            }
+        }
+    }
+
+    /// Handles a keep-alive from the peer. The client gets the id to echo
+    /// back, the server checks the id and records the latency.
+    pub fn received(&mut self, id: i64, now: Instant) -> std::io::Result<Option<i64>> {
+        self.last_received = now;
+        match self.role {
+            Role::Client => Ok(Some(id)),
+            Role::Server => match self.pending {
+                Some((pending, sent)) if pending == id => {
+                    self.pending = None;
+                    self.latency = Some(now.saturating_duration_since(sent));
+                    Ok(None)
+                }
+                _ => Err(Error::new(ErrorKind::InvalidData, "keep-alive answered with an unexpected id"))
+            }
+        }
+    }
+}
+
+fn timed_out() -> Error {
+    Error::new(ErrorKind::TimedOut, "keep-alive timed out")
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::{KeepAliveTracker, Role};
+    use crate::protocol::implementation::steven::v1_17::{KeepAliveClientbound, KeepAliveServerbound, Proto_1_17};
+    use std::time::Instant;
+
+    impl KeepAliveTracker{
+        /// `poll` for servers, producing the packet to send.
+        pub fn poll_packet(&mut self, now: Instant) -> std::io::Result<Option<KeepAliveClientbound>> {
+            Ok(self.poll(now)?.map(|id| KeepAliveClientbound{ id }))
+        }
+
+        /// Passes keep-alives from the peer to `received`, returning the answer
+        /// a client has to send. Other packets are ignored.
+        pub fn handle(&mut self, packet: &Proto_1_17, now: Instant) -> std::io::Result<Option<KeepAliveServerbound>> {
+            match (self.role, packet) {
+                (Role::Client, Proto_1_17::KeepAliveClientbound(keep_alive)) => {
+                    Ok(self.received(keep_alive.id, now)?.map(|id| KeepAliveServerbound{ id }))
+                }
+                (Role::Server, Proto_1_17::KeepAliveServerbound(keep_alive)) => {
+                    self.received(keep_alive.id, now)?;
+                    Ok(None)
+                }
+                _ => Ok(None)
+            }
+        }
+    }
+}
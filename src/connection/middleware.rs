@@ -0,0 +1,115 @@
+//! Plug-ins that look at, change, drop or replace packets passing through a
+//! proxy, like chat filters and command interceptors.
+//!
+//! A `MiddlewareChain` runs its middlewares in the order they were added.
+//! Every middleware sees the packets the one before it let through, packets
+//! that replace another one included. `relay` reads a packet from one side,
+//! runs the chain and writes the result to the other side:
+//!
+//! ```ignore
+//! let mut chain = MiddlewareChain::new();
+//! chain.push(|_, packet: &mut Proto_1_17| match packet {
+//!     Proto_1_17::ChatMessageServerbound(chat) if chat.message.starts_with("/secret") => Action::Drop,
+//!     _ => Action::Forward
+//! });
+//! loop {
+//!     relay(&mut chain, &mut client, &mut server, State::Play, Direction::ServerBound)?;
+//! }
+//! ```
+use crate::connection::stream::PacketStream;
+use crate::protocol::{Direction, Protocol, State};
+use std::io::{Read, Write};
+
+#[derive(Debug)]
+pub enum Action<P>{
+    /// Pass the packet on, including changes made to it.
+    Forward,
+    Drop,
+    /// Send these packets instead, none at all is the same as `Drop`.
+    Replace(Vec<P>)
+}
+
+pub trait Middleware<P>{
+    /// Called for every packet going in `direction`, `ServerBound` being from
+    /// the client to the server.
+    fn on_packet(&mut self, direction: Direction, packet: &mut P) -> Action<P>;
+}
+
+impl<P, F: FnMut(Direction, &mut P) -> Action<P>> Middleware<P> for F{
+    fn on_packet(&mut self, direction: Direction, packet: &mut P) -> Action<P> {
+        self(direction, packet)
+    }
+}
+
+pub struct MiddlewareChain<P>{
+    middlewares: Vec<Box<dyn Middleware<P> + Send>>
+}
+
+impl<P> std::fmt::Debug for MiddlewareChain<P>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiddlewareChain").field("middlewares", &self.middlewares.len()).finish()
+    }
+}
+
+impl<P> Default for MiddlewareChain<P>{
+    fn default() -> Self {
+        MiddlewareChain{ middlewares: Vec::new() }
+    }
+}
+
+impl<P> MiddlewareChain<P>{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a middleware that runs after the ones added before.
+    pub fn push<M: Middleware<P> + Send + 'static>(&mut self, middleware: M) -> &mut Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.middlewares.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.is_empty()
+    }
+
+    /// Runs the packet through every middleware, returning the packets to send.
+    pub fn process(&mut self, direction: Direction, packet: P) -> Vec<P> {
+        let mut packets = vec![packet];
+        for middleware in &mut self.middlewares {
+            let mut passed = Vec::with_capacity(packets.len());
+            for mut packet in packets {
+                match middleware.on_packet(direction, &mut packet) {
+                    Action::Forward => passed.push(packet),
+                    Action::Drop => {}
+                    Action::Replace(replacements) => passed.extend(replacements)
+                }
+            }
+            if passed.is_empty() {
+                return passed;
+            }
+            packets = passed;
+        }
+        packets
+    }
+}
+
+/// Reads a packet from `from`, runs it through the chain and writes what is
+/// left of it to `to`. Returns the amount of packets written.
+pub fn relay<P: Protocol, A: Read + Write, B: Read + Write>(
+    chain: &mut MiddlewareChain<P>,
+    from: &mut PacketStream<A>,
+    to: &mut PacketStream<B>,
+    state: State,
+    direction: Direction
+) -> std::io::Result<usize> {
+    let packet = from.read_packet::<P>(state, direction)?;
+    let packets = chain.process(direction, packet);
+    for packet in &packets {
+        to.write_protocol(packet)?;
+    }
+    Ok(packets.len())
+}
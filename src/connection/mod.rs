@@ -0,0 +1,26 @@
+pub mod backpressure;
+pub mod bundle;
+#[cfg(feature = "async")]
+pub mod codec;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod disconnect;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod frame;
+pub mod history;
+pub mod keep_alive;
+pub mod middleware;
+pub mod outbound;
+pub mod ping;
+pub(crate) mod pool;
+pub mod rate_limit;
+pub mod split;
+pub mod state;
+pub mod stream;
+pub mod timeout;
+pub mod writer;
+pub mod transport;
+pub mod websocket;
+
+pub use pool::{config as pool_config, configure as configure_pool, pooled as pooled_buffers, recycle as recycle_buffer, PoolConfig};
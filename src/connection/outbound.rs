@@ -0,0 +1,251 @@
+//! An outbound queue sending urgent packets first, for clients reading slower
+//! than the server writes.
+//!
+//! Unlike `OutboundBuffer`, which keeps the order packets were pushed in, the
+//! queue keeps one FIFO per `PacketClass` and always writes from the highest
+//! class with packets waiting. A keep-alive or teleport does not wait behind
+//! megabytes of chunk data, at the price of the classes no longer being
+//! ordered among each other. Packets depending on each other, like spawning
+//! an entity and moving it, have to share a class.
+//!
+//! As the backlog grows past the `Thresholds`, packets pushed with a
+//! coalescing key replace the queued packet with the same key, and new
+//! packets of the lower classes are dropped.
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Write};
+
+/// How urgent an outbound packet is, from least to most.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PacketClass{
+    /// Chunks and light, large and only needed eventually.
+    Bulk,
+    #[default]
+    Normal,
+    Chat,
+    /// Keep-alives and teleports, which time out or desync the client when late.
+    Critical
+}
+
+impl PacketClass{
+    pub const ALL: [PacketClass; 4] = [PacketClass::Bulk, PacketClass::Normal, PacketClass::Chat, PacketClass::Critical];
+
+    /// The class of a packet by its name, see `Protocol::packet_name`.
+    pub fn classify(packet_name: &str) -> PacketClass {
+        match packet_name {
+            "KeepAliveClientbound" | "TeleportPlayer" | "PlayPing" | "Disconnect" | "ChunkBatchStart" | "ChunkBatchFinished" => PacketClass::Critical,
+            "ServerMessage" | "PlayerChat" | "SystemChat" | "DisguisedChat" | "ActionBar" | "TabCompleteReply" => PacketClass::Chat,
+            "ChunkData" | "ChunkDataAndUpdateLight" | "UpdateLight" | "ChunkUnload" | "MultiBlockChange" | "ChunkBiomes" => PacketClass::Bulk,
+            _ => PacketClass::Normal
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Backlog sizes in bytes at which the queue starts shedding load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Thresholds{
+    /// Packets with a coalescing key replace the queued one with the same key.
+    pub coalesce: usize,
+    /// New `Bulk` packets are dropped.
+    pub drop_bulk: usize,
+    /// New `Normal` packets are dropped.
+    pub drop_normal: usize,
+    /// Packets that would grow the backlog past this fail, the client should
+    /// be disconnected.
+    pub limit: usize
+}
+
+impl Default for Thresholds{
+    fn default() -> Self {
+        Thresholds{ coalesce: 0, drop_bulk: 2 * 1024 * 1024, drop_normal: 6 * 1024 * 1024, limit: 8 * 1024 * 1024 }
+    }
+}
+
+/// What happened to a pushed packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pushed{
+    Queued,
+    /// The packet replaced a queued one with the same key.
+    Coalesced,
+    Dropped
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueueStats{
+    pub queued: u64,
+    pub coalesced: u64,
+    /// Dropped packets per class, indexed like `PacketClass::ALL`.
+    pub dropped: [u64; 4],
+    pub written_bytes: u64
+}
+
+#[derive(Debug)]
+struct Queued{
+    key: Option<u64>,
+    packet: Vec<u8>
+}
+
+#[derive(Debug)]
+pub struct OutboundQueue{
+    classes: [VecDeque<Queued>; 4],
+    /// The packet being written and how much of it was, which has to finish
+    /// before anything else goes out.
+    current: Option<(Vec<u8>, usize)>,
+    backlog: usize,
+    thresholds: Thresholds,
+    stats: QueueStats
+}
+
+impl Default for OutboundQueue{
+    fn default() -> Self {
+        Self::new(Thresholds::default())
+    }
+}
+
+impl OutboundQueue{
+    pub fn new(thresholds: Thresholds) -> Self {
+        OutboundQueue{
+            classes: Default::default(),
+            current: None,
+            backlog: 0,
+            thresholds,
+            stats: QueueStats::default()
+        }
+    }
+
+    pub fn thresholds(&self) -> &Thresholds {
+        &self.thresholds
+    }
+
+    pub fn set_thresholds(&mut self, thresholds: Thresholds) {
+        self.thresholds = thresholds;
+    }
+
+    /// Bytes waiting to be written.
+    pub fn backlog(&self) -> usize {
+        self.backlog
+    }
+
+    /// Packets waiting in a class.
+    pub fn len(&self, class: PacketClass) -> usize {
+        self.classes[class.index()].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current.is_none() && self.classes.iter().all(VecDeque::is_empty)
+    }
+
+    pub fn stats(&self) -> &QueueStats {
+        &self.stats
+    }
+
+    /// Queues an encoded packet. An error means the backlog limit was hit and
+    /// the client should be disconnected.
+    pub fn push(&mut self, class: PacketClass, packet: Vec<u8>) -> std::io::Result<Pushed> {
+        self.push_keyed(class, None, packet)
+    }
+
+    /// Queues an encoded packet that supersedes earlier ones with the same
+    /// `key` in its class, like the position of an entity or the data of a
+    /// chunk. The earlier packet is replaced in place once the backlog
+    /// reaches `Thresholds::coalesce`.
+    pub fn push_coalescing(&mut self, class: PacketClass, key: u64, packet: Vec<u8>) -> std::io::Result<Pushed> {
+        self.push_keyed(class, Some(key), packet)
+    }
+
+    fn push_keyed(&mut self, class: PacketClass, key: Option<u64>, packet: Vec<u8>) -> std::io::Result<Pushed> {
+        if key.is_some() && self.backlog >= self.thresholds.coalesce {
+            let queue = &mut self.classes[class.index()];
+            if let Some(queued) = queue.iter_mut().find(|queued| queued.key == key) {
+                if self.backlog - queued.packet.len() + packet.len() > self.thresholds.limit {
+                    return Err(overflowed());
+                }
+                self.backlog = self.backlog - queued.packet.len() + packet.len();
+                queued.packet = packet;
+                self.stats.coalesced += 1;
+                return Ok(Pushed::Coalesced);
+            }
+        }
+        let drop_above = match class {
+            PacketClass::Bulk => self.thresholds.drop_bulk,
+            PacketClass::Normal => self.thresholds.drop_normal,
+            PacketClass::Chat | PacketClass::Critical => usize::MAX
+        };
+        if self.backlog + packet.len() > drop_above {
+            self.stats.dropped[class.index()] += 1;
+            return Ok(Pushed::Dropped);
+        }
+        if self.backlog + packet.len() > self.thresholds.limit {
+            return Err(overflowed());
+        }
+        self.backlog += packet.len();
+        self.stats.queued += 1;
+        self.classes[class.index()].push_back(Queued{ key, packet });
+        Ok(Pushed::Queued)
+    }
+
+    /// Takes the next packet to send, from the highest class with packets waiting.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        if let Some((packet, written)) = self.current.take() {
+            self.backlog -= packet.len() - written;
+            return Some(packet[written..].to_vec());
+        }
+        let queued = self.classes.iter_mut().rev().find_map(VecDeque::pop_front)?;
+        self.backlog -= queued.packet.len();
+        Some(queued.packet)
+    }
+
+    /// Drops the queued packets of a class, e.g. the chunks of a world the
+    /// player left.
+    pub fn clear(&mut self, class: PacketClass) {
+        for queued in self.classes[class.index()].drain(..) {
+            self.backlog -= queued.packet.len();
+        }
+    }
+
+    /// Writes as much of the queue as the writer accepts, urgent packets
+    /// first. A writer returning `ErrorKind::WouldBlock` stops the flush, the
+    /// rest is kept for the next call.
+    pub fn flush_to<W: Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        loop {
+            let (packet, mut written) = match self.current.take() {
+                Some(current) => current,
+                None => match self.classes.iter_mut().rev().find_map(VecDeque::pop_front) {
+                    Some(queued) => (queued.packet, 0),
+                    None => break
+                }
+            };
+            while written < packet.len() {
+                match writer.write(&packet[written..]) {
+                    Ok(0) => {
+                        self.current = Some((packet, written));
+                        return Err(Error::new(ErrorKind::WriteZero, "failed to write queued packet"));
+                    }
+                    Ok(n) => {
+                        written += n;
+                        self.backlog -= n;
+                        self.stats.written_bytes += n as u64;
+                    }
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) => {
+                        self.current = Some((packet, written));
+                        return match e.kind() {
+                            ErrorKind::WouldBlock => Ok(()),
+                            _ => Err(e)
+                        };
+                    }
+                }
+            }
+        }
+        writer.flush()
+    }
+}
+
+fn overflowed() -> Error {
+    Error::other("outbound queue limit exceeded")
+}
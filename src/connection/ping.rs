@@ -0,0 +1,163 @@
+//! Measuring the round trip time to a peer with StatusPing in the Status state
+//! or PlayPing in Play, as server lists and proxies showing ping do.
+//!
+//! Samples are taken with `Instant`, so they do not jump with the wall clock.
+//! Next to the last sample and the extremes of a rolling window, the `Pinger`
+//! keeps a smoothed latency and its jitter like TCP does for its RTT, so a
+//! single slow answer does not make the shown ping jump. Like
+//! `KeepAliveTracker` it does no I/O itself.
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, Instant};
+
+/// Pings that may wait for their answer at the same time. Older ones are
+/// forgotten, their answers are refused.
+pub const MAX_PENDING: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PingStats{
+    pub last: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// The mean of the window.
+    pub average: Duration,
+    /// An exponential average weighting every sample by 1/8.
+    pub smoothed: Duration,
+    /// The smoothed deviation of the samples from `smoothed`.
+    pub jitter: Duration,
+    /// The amount of samples in the window.
+    pub samples: usize
+}
+
+#[derive(Debug, Clone)]
+pub struct Pinger{
+    next_id: i32,
+    pending: VecDeque<(i32, Instant)>,
+    window: usize,
+    samples: VecDeque<Duration>,
+    smoothed: Option<(Duration, Duration)>
+}
+
+impl Default for Pinger{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pinger{
+    /// Keeps the last 16 samples.
+    pub fn new() -> Self {
+        Self::with_window(16)
+    }
+
+    /// Keeps the last `window` samples for the rolling stats, at least one.
+    pub fn with_window(window: usize) -> Self {
+        Pinger{
+            next_id: 1,
+            pending: VecDeque::new(),
+            window: window.max(1),
+            samples: VecDeque::new(),
+            smoothed: None
+        }
+    }
+
+    /// Starts a ping, returning the id to send.
+    pub fn ping(&mut self, now: Instant) -> i32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        if self.pending.len() >= MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back((id, now));
+        id
+    }
+
+    /// Handles the answer to a ping, returning its round trip time. Ids that
+    /// were not sent, or were answered already, are refused.
+    pub fn pong(&mut self, id: i32, now: Instant) -> std::io::Result<Duration> {
+        let index = self.pending.iter().position(|&(pending, _)| pending == id)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("pong {} does not answer a pending ping", id)))?;
+        let (_, sent) = self.pending.remove(index).unwrap_or((id, now));
+        let sample = now.saturating_duration_since(sent);
+        self.record(sample);
+        Ok(sample)
+    }
+
+    /// Adds a sample measured elsewhere, e.g. with keep-alives.
+    pub fn record(&mut self, sample: Duration) {
+        if self.samples.len() >= self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        self.smoothed = Some(match self.smoothed {
+            None => (sample, sample / 2),
+            Some((smoothed, jitter)) => {
+                (smoothed * 7 / 8 + sample / 8, jitter * 3 / 4 + sample.abs_diff(smoothed) / 4)
+            }
+        });
+    }
+
+    /// Whether a ping waits for its answer.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// The stats of the window, None before the first answer.
+    pub fn stats(&self) -> Option<PingStats> {
+        let (smoothed, jitter) = self.smoothed?;
+        Some(PingStats{
+            last: *self.samples.back()?,
+            min: *self.samples.iter().min()?,
+            max: *self.samples.iter().max()?,
+            average: self.samples.iter().sum::<Duration>() / self.samples.len() as u32,
+            smoothed,
+            jitter,
+            samples: self.samples.len()
+        })
+    }
+
+    /// The smoothed latency, the one to show to players.
+    pub fn latency(&self) -> Option<Duration> {
+        self.smoothed.map(|(smoothed, _)| smoothed)
+    }
+
+    /// Forgets pending pings and samples, e.g. after a reconnect.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.samples.clear();
+        self.smoothed = None;
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::Pinger;
+    use crate::protocol::implementation::steven::v1_17::{PlayPing, Proto_1_17, StatusPing};
+    use std::io::{Error, ErrorKind};
+    use std::time::{Duration, Instant};
+
+    impl Pinger{
+        /// `ping` for the Status state, producing the packet to send.
+        pub fn status_ping(&mut self, now: Instant) -> StatusPing {
+            StatusPing{ ping: self.ping(now) as i64 }
+        }
+
+        /// `ping` for servers in the Play state, producing the packet to send.
+        pub fn play_ping(&mut self, now: Instant) -> PlayPing {
+            PlayPing{ id: self.ping(now) }
+        }
+
+        /// Passes StatusPong and PlayPong to `pong`, returning the round trip
+        /// time. Other packets are ignored.
+        pub fn handle(&mut self, packet: &Proto_1_17, now: Instant) -> std::io::Result<Option<Duration>> {
+            match packet {
+                Proto_1_17::StatusPong(pong) => match std::convert::TryFrom::try_from(pong.ping) {
+                    Ok(id) => self.pong(id, now).map(Some),
+                    Err(_) => Err(Error::new(ErrorKind::InvalidData, format!("pong {} does not answer a pending ping", pong.ping)))
+                },
+                Proto_1_17::PlayPong(pong) => self.pong(pong.id, now).map(Some),
+                _ => Ok(None)
+            }
+        }
+    }
+}
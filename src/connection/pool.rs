@@ -0,0 +1,98 @@
+//! Recycles the buffers packets are read into and written from.
+//!
+//! Framing, compression and `PacketStream` take their buffers from here and
+//! hand them back once a packet is done with, so a busy connection stops
+//! allocating per packet. Every thread has a pool of its own, which keeps it
+//! free of locking; a buffer recycled on another thread than the one that took
+//! it simply moves to that pool.
+//!
+//! Bodies returned to the caller, like the one of `frame::read_frame`, come
+//! from the pool too. Pass them to `connection::recycle_buffer` when done to
+//! make them available again, dropping them is fine as well. The pool itself
+//! is internal, `connection` re-exports what callers configure it with.
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolConfig{
+    /// Buffers kept per thread, 0 disables pooling.
+    pub max_buffers: usize,
+    /// Buffers with a larger capacity are freed instead of kept, so a single
+    /// large packet does not hold on to its memory.
+    pub max_buffer_size: usize
+}
+
+impl Default for PoolConfig{
+    fn default() -> Self {
+        PoolConfig{
+            max_buffers: 32,
+            max_buffer_size: 64 * 1024
+        }
+    }
+}
+
+static MAX_BUFFERS: AtomicUsize = AtomicUsize::new(32);
+static MAX_BUFFER_SIZE: AtomicUsize = AtomicUsize::new(64 * 1024);
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Applies to all threads. Pools that hold more buffers than allowed shrink
+/// as buffers are taken.
+pub fn configure(config: PoolConfig) {
+    MAX_BUFFERS.store(config.max_buffers, Ordering::Relaxed);
+    MAX_BUFFER_SIZE.store(config.max_buffer_size, Ordering::Relaxed);
+}
+
+pub fn config() -> PoolConfig {
+    PoolConfig{
+        max_buffers: MAX_BUFFERS.load(Ordering::Relaxed),
+        max_buffer_size: MAX_BUFFER_SIZE.load(Ordering::Relaxed)
+    }
+}
+
+/// An empty buffer with room for at least `capacity` bytes.
+pub fn take(capacity: usize) -> Vec<u8> {
+    let mut buffer = POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let max_buffers = MAX_BUFFERS.load(Ordering::Relaxed);
+        if pool.len() > max_buffers {
+            pool.truncate(max_buffers);
+        }
+        pool.pop()
+    }).unwrap_or_default();
+    buffer.reserve(capacity);
+    buffer
+}
+
+/// A buffer to serialize a packet into. It is not sized by `serialized_len`,
+/// which serializes segments of unknown size like chat components a second
+/// time. Pooled buffers keep the capacity of the packets before.
+pub fn take_packet(id: i32) -> Vec<u8> {
+    take(crate::segment::implementation::var::var_int_len(id) + PACKET_CAPACITY)
+}
+
+/// Room for the body of most packets, so cold buffers rarely grow.
+const PACKET_CAPACITY: usize = 256;
+
+/// Returns a buffer to the pool of this thread, or frees it if it is too
+/// large or the pool is full.
+pub fn recycle(mut buffer: Vec<u8>) {
+    if buffer.capacity() == 0 || buffer.capacity() > MAX_BUFFER_SIZE.load(Ordering::Relaxed) {
+        return;
+    }
+    buffer.clear();
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.len() < MAX_BUFFERS.load(Ordering::Relaxed) {
+            pool.push(buffer);
+        }
+    });
+}
+
+/// The amount of buffers in the pool of this thread.
+pub fn pooled() -> usize {
+    POOL.with(|pool| pool.borrow().len())
+}
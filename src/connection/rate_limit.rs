@@ -0,0 +1,202 @@
+//! Limits on how many packets and bytes a peer may send.
+//!
+//! Every state has its own limits, enforced with token buckets: a peer may
+//! send `burst` seconds worth of traffic at once, after which it is held to
+//! the configured rate. What happens to traffic over the limit is up to the
+//! `Action` of the limit.
+use crate::protocol::State;
+use std::time::{Duration, Instant};
+
+/// What to do with a packet that exceeds a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action{
+    /// Discard the packet.
+    Drop,
+    /// Process the packet, but only after waiting until it is within the rate again.
+    Delay,
+    /// Close the connection.
+    Disconnect
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit{
+    pub packets_per_second: Option<f64>,
+    pub bytes_per_second: Option<f64>,
+    /// How many seconds of traffic may arrive at once.
+    pub burst: f64,
+    pub action: Action
+}
+
+impl Limit{
+    pub fn new(packets_per_second: Option<f64>, bytes_per_second: Option<f64>, action: Action) -> Self {
+        Limit{ packets_per_second, bytes_per_second, burst: 1.0, action }
+    }
+
+    pub fn with_burst(mut self, burst: f64) -> Self {
+        self.burst = burst;
+        self
+    }
+}
+
+/// The outcome of `RateLimiter::check`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict{
+    Allow,
+    Drop,
+    /// Wait this long before processing the packet.
+    Delay(Duration),
+    Disconnect
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RateCounters{
+    pub packets: u64,
+    pub bytes: u64,
+    pub dropped: u64,
+    pub delayed: u64,
+    pub disconnects: u64
+}
+
+#[derive(Debug, Clone)]
+struct Bucket{
+    limit: Limit,
+    packets: f64,
+    bytes: f64,
+    updated: Instant
+}
+
+impl Bucket{
+    fn new(limit: Limit, now: Instant) -> Self {
+        Bucket{
+            limit,
+            packets: limit.packets_per_second.unwrap_or(0.0) * limit.burst,
+            bytes: limit.bytes_per_second.unwrap_or(0.0) * limit.burst,
+            updated: now
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.updated = now;
+        if let Some(rate) = self.limit.packets_per_second {
+            self.packets = (self.packets + rate * elapsed).min(rate * self.limit.burst);
+        }
+        if let Some(rate) = self.limit.bytes_per_second {
+            self.bytes = (self.bytes + rate * elapsed).min(rate * self.limit.burst);
+        }
+    }
+
+    /// How long until the packet fits, zero if it does right now.
+    fn wait(&self, bytes: f64) -> Duration {
+        let wait_for = |available: f64, needed: f64, rate: Option<f64>| match rate {
+            Some(rate) if available < needed && rate > 0.0 => (needed - available) / rate,
+            Some(_) if available < needed => f64::INFINITY,
+            _ => 0.0
+        };
+        let seconds = wait_for(self.packets, 1.0, self.limit.packets_per_second)
+            .max(wait_for(self.bytes, bytes, self.limit.bytes_per_second));
+        if seconds.is_finite() { Duration::from_secs_f64(seconds) } else { Duration::MAX }
+    }
+
+    fn take(&mut self, bytes: f64) {
+        if self.limit.packets_per_second.is_some() {
+            self.packets -= 1.0;
+        }
+        if self.limit.bytes_per_second.is_some() {
+            self.bytes -= bytes;
+        }
+    }
+}
+
+/// Rate limits for the packets of a single connection.
+#[derive(Debug, Clone)]
+pub struct RateLimiter{
+    buckets: [Option<Bucket>; 5],
+    counters: [RateCounters; 5]
+}
+
+impl Default for RateLimiter{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiter{
+    /// A limiter without any limits, only counting.
+    pub fn new() -> Self {
+        RateLimiter{
+            buckets: [None, None, None, None, None],
+            counters: [RateCounters::default(); 5]
+        }
+    }
+
+    /// Limits suited to a public server: the states before play allow only a
+    /// handful of small packets, play allows well above what a vanilla client sends.
+    pub fn recommended(now: Instant) -> Self {
+        let mut limiter = Self::new();
+        let before_play = Limit::new(Some(10.0), Some(8192.0), Action::Disconnect).with_burst(2.0);
+        limiter.set_limit(State::Handshaking, Some(before_play), now);
+        limiter.set_limit(State::Status, Some(before_play), now);
+        limiter.set_limit(State::Login, Some(before_play), now);
+        limiter.set_limit(State::Configuration, Some(before_play), now);
+        limiter.set_limit(State::Play, Some(Limit::new(Some(500.0), Some(1_048_576.0), Action::Disconnect).with_burst(2.0)), now);
+        limiter
+    }
+
+    pub fn set_limit(&mut self, state: State, limit: Option<Limit>, now: Instant) {
+        self.buckets[state_index(state)] = limit.map(|limit| Bucket::new(limit, now));
+    }
+
+    pub fn limit(&self, state: State) -> Option<&Limit> {
+        self.buckets[state_index(state)].as_ref().map(|bucket| &bucket.limit)
+    }
+
+    /// Counts a packet of `bytes` received in `state` and decides what to do with it.
+    pub fn check(&mut self, state: State, bytes: usize, now: Instant) -> Verdict {
+        let index = state_index(state);
+        let counters = &mut self.counters[index];
+        counters.packets += 1;
+        counters.bytes += bytes as u64;
+        let bucket = match &mut self.buckets[index] {
+            Some(bucket) => bucket,
+            None => return Verdict::Allow
+        };
+        bucket.refill(now);
+        let bytes = bytes as f64;
+        let wait = bucket.wait(bytes);
+        if wait.is_zero() {
+            bucket.take(bytes);
+            return Verdict::Allow;
+        }
+        match bucket.limit.action {
+            Action::Drop => {
+                counters.dropped += 1;
+                Verdict::Drop
+            }
+            Action::Delay if wait != Duration::MAX => {
+                // Taken now, so the packets after this one wait their turn as well.
+                bucket.take(bytes);
+                counters.delayed += 1;
+                Verdict::Delay(wait)
+            }
+            _ => {
+                counters.disconnects += 1;
+                Verdict::Disconnect
+            }
+        }
+    }
+
+    pub fn counters(&self, state: State) -> &RateCounters {
+        &self.counters[state_index(state)]
+    }
+}
+
+fn state_index(state: State) -> usize {
+    match state {
+        State::Handshaking => 0,
+        State::Status => 1,
+        State::Login => 2,
+        State::Play => 3,
+        State::Configuration => 4
+    }
+}
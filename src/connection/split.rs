@@ -0,0 +1,421 @@
+//! A `PacketStream` split into a reading and a writing half, so receiving and
+//! sending can run on separate threads without a lock around the connection.
+//!
+//! Each half keeps the cipher of its direction. The compression threshold is
+//! shared, as either side of the login may see SetCompression first:
+//!
+//! ```ignore
+//! let (mut reader, mut writer) = stream.split()?;
+//! std::thread::spawn(move || while let Ok(packet) = reader.read_packet::<Proto_1_17>(State::Play, Direction::ClientBound) { ... });
+//! writer.write_protocol(&packet)?;
+//! ```
+use crate::connection::frame::FrameLimits;
+use crate::connection::stream::{end_of_stream, next_frame, write_data, Adaptive, Cipher, PacketStream, Parts};
+use crate::connection::pool;
+use crate::connection::timeout::{timed_out, Armed, Deadline, SocketTimeouts, TimeoutKind, Timeouts};
+use crate::protocol::{Direction, Packet, Protocol, State};
+use crate::segment::implementation::var::{var_int_len, write_var_int};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Streams that can be split into independently owned halves.
+pub trait Split{
+    type Read: Read;
+    type Write: Write;
+
+    fn split(self) -> std::io::Result<(Self::Read, Self::Write)>;
+
+    /// How to set the read timeout of the read half, which a split stream
+    /// with `Timeouts` needs. `None` for read halves without timeouts.
+    fn read_timeout() -> Option<fn(&Self::Read, Option<Duration>) -> std::io::Result<()>> {
+        None
+    }
+}
+
+impl Split for std::net::TcpStream{
+    type Read = std::net::TcpStream;
+    type Write = std::net::TcpStream;
+
+    fn split(self) -> std::io::Result<(Self::Read, Self::Write)> {
+        Ok((self.try_clone()?, self))
+    }
+
+    fn read_timeout() -> Option<fn(&Self::Read, Option<Duration>) -> std::io::Result<()>> {
+        Some(<std::net::TcpStream as SocketTimeouts>::set_read_timeout)
+    }
+}
+
+#[cfg(unix)]
+impl Split for std::os::unix::net::UnixStream{
+    type Read = std::os::unix::net::UnixStream;
+    type Write = std::os::unix::net::UnixStream;
+
+    fn split(self) -> std::io::Result<(Self::Read, Self::Write)> {
+        Ok((self.try_clone()?, self))
+    }
+
+    fn read_timeout() -> Option<fn(&Self::Read, Option<Duration>) -> std::io::Result<()>> {
+        Some(<std::os::unix::net::UnixStream as SocketTimeouts>::set_read_timeout)
+    }
+}
+
+/// The halves of a `PacketStream<S>`.
+pub type Halves<S> = (ReadHalf<<S as Split>::Read>, WriteHalf<<S as Split>::Write>);
+
+/// The compression threshold shared by both halves.
+#[derive(Debug, Clone)]
+struct Compression(Arc<AtomicUsize>);
+
+impl Compression{
+    const OFF: usize = usize::MAX;
+
+    fn new(threshold: Option<usize>) -> Self {
+        Compression(Arc::new(AtomicUsize::new(threshold.unwrap_or(Self::OFF))))
+    }
+
+    fn get(&self) -> Option<usize> {
+        match self.0.load(Ordering::Acquire) {
+            Self::OFF => None,
+            threshold => Some(threshold)
+        }
+    }
+
+    fn set(&self, threshold: Option<usize>) -> std::io::Result<()> {
+        if threshold.is_some() && cfg!(not(feature = "compression")) {
+            return Err(Error::new(ErrorKind::Unsupported, "the peer enabled compression, which needs the `compression` feature"));
+        }
+        self.0.store(threshold.unwrap_or(Self::OFF), Ordering::Release);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ReadHalf<R>{
+    inner: R,
+    compression: Compression,
+    decryptor: Cipher,
+    limits: FrameLimits,
+    timeouts: Option<Armed<R>>
+}
+
+#[derive(Debug)]
+pub struct WriteHalf<W>{
+    inner: W,
+    compression: Compression,
+    adaptive: Adaptive,
+    encryptor: Cipher,
+    frame: Vec<u8>,
+    /// The write timeout of the socket, to report a write running into it.
+    write_timeout: Option<Duration>
+}
+
+/// Halves of a connection whose streams were split already, without
+/// compression or encryption.
+pub fn halves<R: Read, W: Write>(reader: R, writer: W) -> (ReadHalf<R>, WriteHalf<W>) {
+    let compression = Compression::new(None);
+    (
+        ReadHalf{ inner: reader, compression: compression.clone(), decryptor: Default::default(), limits: FrameLimits::default(), timeouts: None },
+        WriteHalf{ inner: writer, compression, adaptive: Default::default(), encryptor: Default::default(), frame: Vec::new(), write_timeout: None }
+    )
+}
+
+impl<S: Read + Write + Split> PacketStream<S>{
+    /// Splits the connection, keeping its compression and its statistics,
+    /// encryption, frame limits and timeouts. Fails with `ErrorKind::Unsupported` if timeouts
+    /// are set but `S::read_timeout` gives no way to enforce them.
+    pub fn split(self) -> std::io::Result<Halves<S>> {
+        let limits = self.frame_limits();
+        let Parts{ inner, compression, adaptive, encryptor, decryptor, timeouts } = self.into_parts()?;
+        let armed = match (timeouts, S::read_timeout()) {
+            (None, _) => None,
+            (Some((timeouts, handshaken)), Some(set_read_timeout)) => {
+                let mut armed = Armed::new(timeouts, set_read_timeout);
+                armed.handshaken = handshaken;
+                Some(armed)
+            }
+            (Some(_), None) => return Err(Error::new(ErrorKind::Unsupported, "the read half of this stream can not enforce timeouts"))
+        };
+        let write_timeout = armed.as_ref().and_then(|armed| armed.timeouts.write);
+        let (reader, writer) = inner.split()?;
+        let compression = Compression::new(compression);
+        Ok((
+            ReadHalf{ inner: reader, compression: compression.clone(), decryptor, limits, timeouts: armed },
+            WriteHalf{ inner: writer, compression, adaptive, encryptor, frame: Vec::new(), write_timeout }
+        ))
+    }
+}
+
+impl<R: Read> ReadHalf<R>{
+    /// Compresses packets of at least `threshold` bytes from now on, for both halves.
+    pub fn set_compression(&self, threshold: Option<usize>) -> std::io::Result<()> {
+        self.compression.set(threshold)
+    }
+
+    pub fn compression(&self) -> Option<usize> {
+        self.compression.get()
+    }
+
+    /// Decrypts everything read from now on. The writer is switched separately.
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) {
+        self.decryptor = Some(crate::connection::encryption::Cfb8::new(shared_secret));
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        return self.decryptor.is_some();
+        #[cfg(not(feature = "encryption"))]
+        return false;
+    }
+
+    /// See `PacketStream::set_frame_limits`. The halves do not track the
+    /// state, the limit of the state passed to each read applies.
+    pub fn set_frame_limits(&mut self, limits: FrameLimits) {
+        self.limits = limits;
+    }
+
+    /// See `PacketStream::read_raw`, with the frame limit of `state`.
+    pub fn read_raw(&mut self, state: State) -> std::io::Result<(i32, Vec<u8>)> {
+        let compression = self.compression.get();
+        let frame = match &mut self.timeouts {
+            Some(armed) => {
+                let frame = next_frame(&mut Deadline::new(&mut self.inner, armed), &mut self.decryptor, compression, &self.limits, state);
+                armed.handshaken |= matches!(frame, Ok(Some(_)));
+                frame
+            }
+            None => next_frame(&mut self.inner, &mut self.decryptor, compression, &self.limits, state)
+        };
+        frame?.ok_or_else(end_of_stream)
+    }
+
+    /// Reads the next packet, packets unknown to `P` are returned as `P::Raw`.
+    pub fn read_packet<P: Protocol>(&mut self, state: State, direction: Direction) -> std::io::Result<P> {
+        let (id, body) = self.read_raw(state)?;
+        let packet = P::decode_body(state, direction, id, &body);
+        pool::recycle(body);
+        packet
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + SocketTimeouts> ReadHalf<R>{
+    /// See `PacketStream::set_timeouts`, of which the read half enforces all
+    /// but `write`.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) -> std::io::Result<()> {
+        self.inner.set_read_timeout(None)?;
+        self.timeouts = Some(Armed::new(timeouts, R::set_read_timeout));
+        Ok(())
+    }
+}
+
+impl<W: Write> WriteHalf<W>{
+    /// Compresses packets of at least `threshold` bytes from now on, for both halves.
+    pub fn set_compression(&self, threshold: Option<usize>) -> std::io::Result<()> {
+        self.compression.set(threshold)
+    }
+
+    pub fn compression(&self) -> Option<usize> {
+        self.compression.get()
+    }
+
+    /// See `PacketStream::track_compression`.
+    #[cfg(feature = "compression")]
+    pub fn track_compression(&mut self, adaptive: bool) {
+        self.adaptive = Some(crate::connection::compression::AdaptiveCompression::new(adaptive));
+    }
+
+    #[cfg(feature = "compression")]
+    pub fn compression_stats(&self) -> Option<&crate::connection::compression::CompressionStats> {
+        self.adaptive.as_ref().map(|adaptive| adaptive.stats())
+    }
+
+    /// See `PacketStream::send_threshold`.
+    #[cfg(feature = "compression")]
+    pub fn send_threshold(&self) -> Option<usize> {
+        let threshold = self.compression.get()?;
+        Some(self.adaptive.as_ref().map_or(threshold, |adaptive| adaptive.threshold(threshold)))
+    }
+
+    /// Encrypts everything written from now on. The reader is switched separately.
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) {
+        self.encryptor = Some(crate::connection::encryption::Cfb8::new(shared_secret));
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        return self.encryptor.is_some();
+        #[cfg(not(feature = "encryption"))]
+        return false;
+    }
+
+    /// Writes a frame with the packet id and body.
+    pub fn write_raw(&mut self, id: i32, body: &[u8]) -> std::io::Result<()> {
+        let mut data = pool::take(var_int_len(id) + body.len());
+        write_var_int(id, &mut data)?;
+        data.extend_from_slice(body);
+        self.write_data(data)
+    }
+
+    pub fn write_packet<P: Packet>(&mut self, packet: &P) -> std::io::Result<()> {
+        let mut data = pool::take_packet(packet.packet_id());
+        write_var_int(packet.packet_id(), &mut data)?;
+        packet.write_to_stream(&mut data)?;
+        self.write_data(data)
+    }
+
+    /// Writes any packet of a protocol.
+    pub fn write_protocol<P: Protocol>(&mut self, packet: &P) -> std::io::Result<()> {
+        let mut data = pool::take_packet(packet.id());
+        write_var_int(packet.id(), &mut data)?;
+        packet.write_to_stream(&mut data)?;
+        self.write_data(data)
+    }
+
+    fn write_data(&mut self, data: Vec<u8>) -> std::io::Result<()> {
+        let compression = self.compression.get();
+        let write = self.write_timeout;
+        write_data(&mut self.inner, &mut self.frame, &mut self.encryptor, compression, &mut self.adaptive, data)
+            .map_err(|e| timed_out(e, write.map(|write| (TimeoutKind::Write, write))))
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write + SocketTimeouts> WriteHalf<W>{
+    /// Sets the write timeout of the socket, see `Timeouts::write`.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+        self.inner.set_write_timeout(timeout)?;
+        self.write_timeout = timeout;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::timeout::tests::Stalling;
+    use crate::connection::frame::FrameTooLarge;
+    use crate::connection::timeout::TimedOut;
+    use crate::protocol::test_protocol::TestProto;
+
+    impl Split for Stalling{
+        type Read = Stalling;
+        type Write = Stalling;
+
+        fn split(self) -> std::io::Result<(Self::Read, Self::Write)> {
+            Ok((self.clone(), self))
+        }
+
+        fn read_timeout() -> Option<fn(&Self::Read, Option<Duration>) -> std::io::Result<()>> {
+            Some(<Stalling as SocketTimeouts>::set_read_timeout)
+        }
+    }
+
+    fn kind(error: &Error) -> Option<TimeoutKind> {
+        TimedOut::of(error).map(|timed_out| timed_out.kind)
+    }
+
+    #[test]
+    fn split_keeps_the_timeouts() {
+        let timeouts = Timeouts::default()
+            .with_handshake(Duration::from_secs(1))
+            .with_frame(Duration::from_secs(10))
+            .with_write(Duration::from_secs(1));
+        let socket = Stalling::new(&[0x01, 0x00, 0x02]);
+        let mut stream = PacketStream::new(socket.clone());
+        stream.set_timeouts(timeouts).unwrap();
+        stream.read_raw().unwrap();
+        let (mut reader, mut writer) = stream.split().unwrap();
+        // Nothing set for the frame read before the split stays on the socket.
+        assert_eq!(socket.read_timeouts.borrow().last(), Some(&None));
+        let set_before = socket.read_timeouts.borrow().len();
+
+        let error = reader.read_raw(State::Play).unwrap_err();
+        assert_eq!(kind(&error), Some(TimeoutKind::Frame));
+        // The handshake was read before the split, so its timeout is not set again.
+        assert!(!socket.read_timeouts.borrow()[set_before..].contains(&Some(Duration::from_secs(1))));
+
+        let error = writer.write_raw(0x00, &[]).unwrap_err();
+        assert_eq!(kind(&error), Some(TimeoutKind::Write));
+    }
+
+    #[test]
+    fn split_without_timeouts_reports_would_block() {
+        let (_, mut writer) = PacketStream::new(Stalling::new(&[])).split().unwrap();
+        assert_eq!(writer.write_raw(0x00, &[]).unwrap_err().kind(), ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn read_half_timeouts() {
+        let (mut reader, _) = halves(Stalling::new(&[]), Stalling::default());
+        reader.set_timeouts(Timeouts::default().with_read(Duration::from_secs(1))).unwrap();
+        assert_eq!(kind(&reader.read_raw(State::Play).unwrap_err()), Some(TimeoutKind::Read));
+    }
+
+    #[test]
+    fn read_half_uses_the_limit_of_the_state() {
+        // A frame of 8193 bytes, over the limit of Login before compression only.
+        let (mut reader, _) = halves(&[0x81, 0x40, 0x81, 0x40][..], Vec::new());
+        let error = reader.read_raw(State::Login).unwrap_err();
+        assert_eq!(FrameTooLarge::of(&error).map(|e| e.state), Some(State::Login));
+        assert_eq!(reader.read_raw(State::Play).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn write_half_counts_compression() {
+        let (reader, mut writer) = halves(std::io::empty(), Vec::new());
+        reader.set_compression(Some(0)).unwrap();
+        writer.track_compression(false);
+        writer.write_raw(0x01, &[0x01]).unwrap();
+        assert_eq!(writer.compression_stats().map(|stats| stats.total().compressed), Some(1));
+    }
+
+    fn write_then_read(compression: Option<usize>) {
+        let (_, mut writer) = halves(std::io::empty(), Vec::new());
+        writer.set_compression(compression).unwrap();
+        writer.write_raw(0x01, &[0x01]).unwrap();
+        writer.write_raw(0x00, &[0; 8]).unwrap();
+        let written = writer.into_inner();
+        let (mut reader, _) = halves(&written[..], std::io::sink());
+        reader.set_compression(compression).unwrap();
+        assert!(matches!(reader.read_packet(State::Play, Direction::ClientBound).unwrap(), TestProto::Flag(flag) if flag.value));
+        assert!(matches!(reader.read_packet(State::Play, Direction::ClientBound).unwrap(), TestProto::KeepAlive(keep_alive) if keep_alive.id == 0));
+        assert_eq!(reader.read_raw(State::Play).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn written_packets_read_back() {
+        write_then_read(None);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn compressed_packets_read_back() {
+        write_then_read(Some(0));
+    }
+}
@@ -0,0 +1,127 @@
+//! Following the protocol state of a connection: Handshake picks Status or
+//! Login, login ends in Play, or in Configuration for protocols that have it,
+//! and Configuration ends in Play once the client acknowledged its end.
+//!
+//! Switches happen on the packets that vanilla switches on, found by their
+//! name so they hold for every protocol definition:
+//!
+//! * `Handshake`, to the state of its `next` field, with 3 (a transfer) meaning Login.
+//! * `LoginSuccess`, to Play if the protocol has no Configuration state.
+//! * `LoginAcknowledged` and `ConfigurationAcknowledged`, to Configuration.
+//! * `AcknowledgeFinishConfiguration`, to Play.
+//!
+//! Packets that are not part of the current state are refused with a
+//! `WrongState` error, see `PacketStream::track_state`.
+use crate::io::borrowed::Handshake;
+use crate::protocol::{Direction, PacketDescriptor, Protocol, State};
+use std::io::{Error, ErrorKind};
+
+/// A packet that does not belong to the state of the connection, carried
+/// inside the `std::io::Error`. Use `WrongState::of` to get at it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrongState{
+    /// The name of the packet, or its id if the protocol does not know it.
+    pub packet: String,
+    pub direction: Direction,
+    pub state: State,
+    /// The state the packet belongs to, if known.
+    pub expected: Option<State>
+}
+
+impl WrongState{
+    /// The `WrongState` inside an error returned by a tracked connection.
+    pub fn of(error: &Error) -> Option<&WrongState> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl std::fmt::Display for WrongState{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {} is not allowed in the {:?} state", self.direction, self.packet, self.state)?;
+        if let Some(expected) = self.expected {
+            write!(f, ", it belongs to {:?}", expected)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for WrongState{}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateMachine{
+    state: State
+}
+
+impl Default for StateMachine{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateMachine{
+    /// A machine for a new connection, in the Handshaking state.
+    pub fn new() -> Self {
+        Self::at(State::Handshaking)
+    }
+
+    /// A machine for a connection that is already in `state`.
+    pub fn at(state: State) -> Self {
+        StateMachine{ state }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Checks that the packet with `id` is part of the current state and
+    /// follows the switch it causes, if any. Ids left out of `P` as gaps are
+    /// allowed, unknown ids are refused with `kind`.
+    pub fn observe<P: Protocol>(&mut self, direction: Direction, id: i32, body: &[u8], kind: ErrorKind) -> std::io::Result<()> {
+        let state = self.state;
+        let descriptor = P::packets().iter().find(|descriptor| descriptor.state == state && descriptor.direction == direction && descriptor.id == id);
+        let descriptor = match descriptor {
+            Some(descriptor) => descriptor,
+            None if P::id_gaps().contains(&(state, direction, id)) => return Ok(()),
+            None => return Err(Error::new(kind, WrongState{ packet: format!("packet 0x{:02x}", id), direction, state, expected: None }))
+        };
+        self.state = match (state, direction, descriptor.name) {
+            (State::Handshaking, Direction::ServerBound, "Handshake") => match Handshake::decode(body)?.next {
+                1 => State::Status,
+                2 | 3 => State::Login,
+                next => return Err(Error::new(ErrorKind::InvalidData, format!("handshake asks for unknown state {}", next)))
+            },
+            (State::Login, Direction::ClientBound, "LoginSuccess") if !has_configuration::<P>() => State::Play,
+            (State::Login, Direction::ServerBound, "LoginAcknowledged") => State::Configuration,
+            (State::Play, Direction::ServerBound, "ConfigurationAcknowledged") => State::Configuration,
+            (State::Configuration, Direction::ServerBound, "AcknowledgeFinishConfiguration") => State::Play,
+            _ => state
+        };
+        Ok(())
+    }
+
+    /// Checks that a packet about to be sent is part of the current state.
+    /// Raw packets are not checked.
+    pub fn check<P: Protocol>(&self, packet: &P, direction: Direction) -> std::io::Result<()> {
+        let name = packet.packet_name();
+        let descriptor = P::packets().iter().find(|descriptor| descriptor.name == name && descriptor.direction == direction);
+        match descriptor {
+            Some(PacketDescriptor{ state, .. }) if *state != self.state => Err(Error::new(ErrorKind::InvalidInput, WrongState{
+                packet: name.to_string(),
+                direction,
+                state: self.state,
+                expected: Some(*state)
+            })),
+            None if name != "Raw" => Err(Error::new(ErrorKind::InvalidInput, WrongState{
+                packet: name.to_string(),
+                direction,
+                state: self.state,
+                expected: None
+            })),
+            _ => Ok(())
+        }
+    }
+}
+
+fn has_configuration<P: Protocol>() -> bool {
+    P::packets().iter().any(|descriptor| descriptor.state == State::Configuration)
+}
@@ -0,0 +1,654 @@
+//! A connection that reads and writes whole packets, following the switches
+//! to compression and encryption during login. With `track_state` it also
+//! follows the protocol state, see `connection::state`. With `set_timeouts`
+//! it stops waiting on peers that are too slow, see `connection::timeout`.
+//! With `record_history` it keeps the last packets for the errors it returns,
+//! see `connection::history`.
+use crate::connection::bundle::{Bundled, Bundler};
+use crate::connection::frame::FrameLimits;
+use crate::connection::history::PacketHistory;
+use crate::connection::pool;
+use crate::connection::state::StateMachine;
+use crate::connection::timeout::{timed_out, Armed, Deadline, SocketTimeouts, TimeoutKind, Timeouts};
+use crate::protocol::quarantine::{decode_or_quarantine, DecodeFailure};
+use crate::protocol::{Direction, Packet, Protocol, State};
+use crate::segment::implementation::var::{read_var_int, var_int_len, write_var_int};
+use std::fmt::Debug;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+
+/// The cipher of one direction, nothing without the `encryption` feature.
+#[cfg(feature = "encryption")]
+pub(crate) type Cipher = Option<crate::connection::encryption::Cfb8>;
+#[cfg(not(feature = "encryption"))]
+pub(crate) type Cipher = ();
+
+/// What compression does to the packets written, see `track_compression`,
+/// nothing without the `compression` feature.
+#[cfg(feature = "compression")]
+pub(crate) type Adaptive = Option<crate::connection::compression::AdaptiveCompression>;
+#[cfg(not(feature = "compression"))]
+pub(crate) type Adaptive = ();
+
+/// A `PacketStream` taken apart, see `into_parts`.
+pub(crate) struct Parts<S>{
+    pub inner: S,
+    pub compression: Option<usize>,
+    pub adaptive: Adaptive,
+    pub encryptor: Cipher,
+    pub decryptor: Cipher,
+    /// The timeouts, and whether the first frame was read.
+    pub timeouts: Option<(Timeouts, bool)>
+}
+
+#[derive(Debug)]
+pub struct PacketStream<S>{
+    inner: S,
+    compression: Option<usize>,
+    adaptive: Adaptive,
+    encryptor: Cipher,
+    decryptor: Cipher,
+    frame: Vec<u8>,
+    /// The state machine and the direction of the packets read.
+    states: Option<(StateMachine, Direction)>,
+    timeouts: Option<Armed<S>>,
+    limits: FrameLimits,
+    /// The `Debug` output of the last packets, empty unless `record_history` was called.
+    history: PacketHistory<String>,
+    /// The direction of the last packet read, which tells the one of the packets written.
+    incoming: Option<Direction>
+}
+
+impl<S: Read + Write> PacketStream<S>{
+    pub fn new(inner: S) -> Self {
+        PacketStream{
+            inner,
+            compression: None,
+            adaptive: Default::default(),
+            encryptor: Default::default(),
+            decryptor: Default::default(),
+            frame: Vec::new(),
+            states: None,
+            timeouts: None,
+            limits: FrameLimits::default(),
+            history: PacketHistory::new(0),
+            incoming: None
+        }
+    }
+
+    /// Follows the protocol state from now on, for `read_tracked` and
+    /// `write_tracked`. `incoming` is the direction of the packets read, so
+    /// `ServerBound` for a server.
+    pub fn track_state(&mut self, machine: StateMachine, incoming: Direction) {
+        self.states = Some((machine, incoming));
+    }
+
+    /// The current state, if it is tracked.
+    pub fn state(&self) -> Option<State> {
+        self.states.map(|(machine, _)| machine.state())
+    }
+
+    /// Compresses packets of at least `threshold` bytes from now on, `None` turns compression off.
+    /// Fails if the crate was built without the `compression` feature.
+    pub fn set_compression(&mut self, threshold: Option<usize>) -> std::io::Result<()> {
+        if threshold.is_some() && cfg!(not(feature = "compression")) {
+            return Err(Error::new(ErrorKind::Unsupported, "the peer enabled compression, which needs the `compression` feature"));
+        }
+        self.compression = threshold;
+        Ok(())
+    }
+
+    pub fn compression(&self) -> Option<usize> {
+        self.compression
+    }
+
+    /// Counts what compression does to the packets written from now on, see
+    /// `compression_stats`. With `adaptive` the threshold packets are
+    /// compressed with is raised to what the counts suggest, see
+    /// `compression::AdaptiveCompression`.
+    #[cfg(feature = "compression")]
+    pub fn track_compression(&mut self, adaptive: bool) {
+        self.adaptive = Some(crate::connection::compression::AdaptiveCompression::new(adaptive));
+    }
+
+    #[cfg(feature = "compression")]
+    pub fn compression_stats(&self) -> Option<&crate::connection::compression::CompressionStats> {
+        self.adaptive.as_ref().map(|adaptive| adaptive.stats())
+    }
+
+    /// The threshold packets are written with, which an adaptive stream may
+    /// have raised above `compression`.
+    #[cfg(feature = "compression")]
+    pub fn send_threshold(&self) -> Option<usize> {
+        let threshold = self.compression?;
+        Some(self.adaptive.as_ref().map_or(threshold, |adaptive| adaptive.threshold(threshold)))
+    }
+
+    /// Encrypts everything read and written from now on.
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) {
+        use crate::connection::encryption::Cfb8;
+        self.encryptor = Some(Cfb8::new(shared_secret));
+        self.decryptor = Some(Cfb8::new(shared_secret));
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        return self.encryptor.is_some();
+        #[cfg(not(feature = "encryption"))]
+        return false;
+    }
+
+    /// Reads the next frame, returning the packet id and body. The body is
+    /// taken from the buffer pool, see `connection::recycle_buffer`. The
+    /// frame limit of the tracked state applies, or that of Play.
+    pub fn read_raw(&mut self) -> std::io::Result<(i32, Vec<u8>)> {
+        self.next_frame(None)?.ok_or_else(end_of_stream)
+    }
+
+    /// Like `read_raw`, with the frame limit of `state`.
+    fn read_raw_in(&mut self, state: State) -> std::io::Result<(i32, Vec<u8>)> {
+        self.next_frame(Some(state))?.ok_or_else(end_of_stream)
+    }
+
+    /// Reads the next frame of `state`, `None` if the stream ended before its
+    /// first byte. Without a state the tracked one is used, and without
+    /// tracking Play stands in for all of them.
+    fn next_frame(&mut self, state: Option<State>) -> std::io::Result<Option<(i32, Vec<u8>)>> {
+        let state = state.or_else(|| self.state()).unwrap_or(State::Play);
+        let frame = match &mut self.timeouts {
+            Some(armed) => {
+                let frame = next_frame(&mut Deadline::new(&mut self.inner, armed), &mut self.decryptor, self.compression, &self.limits, state);
+                armed.handshaken |= matches!(frame, Ok(Some(_)));
+                frame
+            }
+            None => next_frame(&mut self.inner, &mut self.decryptor, self.compression, &self.limits, state)
+        };
+        self.history.annotate(frame)
+    }
+
+    /// Keeps the last `capacity` packets read and written from now on, and
+    /// appends them to the message of every error the stream returns, see
+    /// `PacketHistory::annotate`. Zero stops recording.
+    pub fn record_history(&mut self, capacity: usize) {
+        self.history = PacketHistory::new(capacity);
+    }
+
+    /// The packets recorded since `record_history`, oldest first.
+    pub fn history(&self) -> &PacketHistory<String> {
+        &self.history
+    }
+
+    fn record(&mut self, direction: Direction, packet: &dyn Debug) {
+        if self.history.capacity() > 0 {
+            self.history.record(direction, format!("{:?}", packet));
+        }
+    }
+
+    /// The direction of the packets written: the opposite of the packets read.
+    /// Before anything was read it is `ServerBound`, as a server reads first.
+    fn outgoing(&self) -> Direction {
+        match self.states.as_ref().map(|(_, incoming)| *incoming).or(self.incoming) {
+            Some(Direction::ServerBound) => Direction::ClientBound,
+            Some(Direction::ClientBound) | None => Direction::ServerBound
+        }
+    }
+
+    /// Refuses frames longer than `limits` allow from now on, instead of the
+    /// vanilla limits. The limit of the state packets are read in applies,
+    /// which for `read_raw` is the tracked state or Play.
+    pub fn set_frame_limits(&mut self, limits: FrameLimits) {
+        self.limits = limits;
+    }
+
+    pub fn frame_limits(&self) -> FrameLimits {
+        self.limits
+    }
+
+    /// The timeouts set with `set_timeouts`.
+    pub fn timeouts(&self) -> Option<Timeouts> {
+        self.timeouts.as_ref().map(|armed| armed.timeouts)
+    }
+
+    /// Reads the next packet, packets unknown to `P` are returned as `P::Raw`.
+    pub fn read_packet<P: Protocol>(&mut self, state: State, direction: Direction) -> std::io::Result<P> {
+        let (id, body) = self.read_raw_in(state)?;
+        self.decode(Some((state, direction)), id, body)
+    }
+
+    /// Like `read_packet`, but a packet that fails to decode is returned as a
+    /// `DecodeFailure` and the stream can be read on. Only failing to read the
+    /// frame itself is an error.
+    pub fn read_quarantined<P: Protocol>(&mut self, state: State, direction: Direction) -> std::io::Result<Result<P, DecodeFailure>> {
+        let (id, body) = self.read_raw_in(state)?;
+        let packet = decode_or_quarantine(state, direction, id, &body);
+        pool::recycle(body);
+        self.incoming = Some(direction);
+        match &packet {
+            Ok(packet) => self.record(direction, packet),
+            Err(failure) => self.record(direction, failure)
+        }
+        Ok(packet)
+    }
+
+    /// Reads the next packet in the tracked state and follows the switch it
+    /// causes. Packets of other states fail with `state::WrongState`.
+    pub fn read_tracked<P: Protocol>(&mut self) -> std::io::Result<P> {
+        self.states.ok_or_else(untracked)?;
+        let (id, body) = self.read_raw()?;
+        self.decode(None, id, body)
+    }
+
+    /// Decodes a frame in `state` and `direction`, or in the tracked state
+    /// when they are `None`. The body goes back to the buffer pool.
+    fn decode<P: Protocol>(&mut self, state: Option<(State, Direction)>, id: i32, body: Vec<u8>) -> std::io::Result<P> {
+        let (packet, direction) = match (state, &mut self.states) {
+            (Some((state, direction)), _) => (P::decode_body(state, direction, id, &body), direction),
+            (None, Some((machine, direction))) => {
+                let state = machine.state();
+                let packet = machine.observe::<P>(*direction, id, &body, ErrorKind::InvalidData)
+                    .and_then(|_| P::decode_body(state, *direction, id, &body));
+                (packet, *direction)
+            }
+            (None, None) => {
+                pool::recycle(body);
+                return Err(untracked());
+            }
+        };
+        self.incoming = Some(direction);
+        match &packet {
+            Ok(packet) => self.record(direction, packet),
+            Err(_) => self.record(direction, &format_args!("undecodable packet 0x{:02X} of {} bytes", id, body.len()))
+        }
+        pool::recycle(body);
+        self.history.annotate(packet)
+    }
+
+    /// Writes a packet after checking that it is part of the tracked state,
+    /// then follows the switch it causes.
+    pub fn write_tracked<P: Protocol>(&mut self, packet: &P) -> std::io::Result<()> {
+        let (machine, incoming) = self.states.as_mut().ok_or_else(untracked)?;
+        let direction = match incoming {
+            Direction::ServerBound => Direction::ClientBound,
+            Direction::ClientBound => Direction::ServerBound
+        };
+        machine.check(packet, direction)?;
+        let id_len = var_int_len(packet.id());
+        let mut data = pool::take_packet(packet.id());
+        write_var_int(packet.id(), &mut data)?;
+        packet.write_to_stream(&mut data)?;
+        if let Err(e) = machine.observe::<P>(direction, packet.id(), &data[id_len..], ErrorKind::InvalidInput) {
+            pool::recycle(data);
+            return Err(e);
+        }
+        self.record(direction, packet);
+        self.write_data(data)
+    }
+
+    /// Reads the next packet, or all packets of the bundle it opens. See
+    /// `connection::bundle`.
+    pub fn read_bundled<P: Protocol>(&mut self, state: State, direction: Direction) -> std::io::Result<Bundled<P>> {
+        let mut bundler = Bundler::new();
+        loop {
+            let packet = self.read_packet(state, direction)?;
+            if let Some(bundled) = bundler.push(packet)? {
+                return Ok(bundled);
+            }
+        }
+    }
+
+    /// Iterates over the packets of `state` and `direction`:
+    ///
+    /// ```ignore
+    /// for packet in stream.packets::<Proto_1_17>(State::Play, Direction::ClientBound) {
+    ///     println!("{:?}", packet?);
+    /// }
+    /// ```
+    pub fn packets<P: Protocol>(&mut self, state: State, direction: Direction) -> PacketIter<'_, P, S> {
+        PacketIter{ stream: self, state: Some((state, direction)), done: false, protocol: PhantomData }
+    }
+
+    /// Iterates over the packets of the tracked state, following its switches
+    /// like `read_tracked`.
+    pub fn tracked_packets<P: Protocol>(&mut self) -> PacketIter<'_, P, S> {
+        PacketIter{ stream: self, state: None, done: false, protocol: PhantomData }
+    }
+
+    /// Writes a frame with the packet id and body.
+    pub fn write_raw(&mut self, id: i32, body: &[u8]) -> std::io::Result<()> {
+        let mut data = pool::take(var_int_len(id) + body.len());
+        write_var_int(id, &mut data)?;
+        data.extend_from_slice(body);
+        self.record(self.outgoing(), &format_args!("packet 0x{:02X} of {} bytes", id, body.len()));
+        self.write_data(data)
+    }
+
+    pub fn write_packet<P: Packet>(&mut self, packet: &P) -> std::io::Result<()> {
+        let mut data = pool::take_packet(packet.packet_id());
+        write_var_int(packet.packet_id(), &mut data)?;
+        packet.write_to_stream(&mut data)?;
+        self.record(self.outgoing(), packet);
+        self.write_data(data)
+    }
+
+    /// Writes any packet of a protocol.
+    pub fn write_protocol<P: Protocol>(&mut self, packet: &P) -> std::io::Result<()> {
+        let mut data = pool::take_packet(packet.id());
+        write_var_int(packet.id(), &mut data)?;
+        packet.write_to_stream(&mut data)?;
+        self.record(self.outgoing(), packet);
+        self.write_data(data)
+    }
+
+    fn write_data(&mut self, data: Vec<u8>) -> std::io::Result<()> {
+        let write = self.timeouts.as_ref().and_then(|armed| armed.timeouts.write);
+        let written = write_data(&mut self.inner, &mut self.frame, &mut self.encryptor, self.compression, &mut self.adaptive, data)
+            .map_err(|e| timed_out(e, write.map(|write| (TimeoutKind::Write, write))));
+        self.history.annotate(written)
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The parts the halves of a split are made of. The read timeout set for
+    /// a frame is taken off the stream, the timeouts are returned instead.
+    pub(crate) fn into_parts(mut self) -> std::io::Result<Parts<S>> {
+        let timeouts = match &mut self.timeouts {
+            Some(armed) => {
+                armed.disarm(&self.inner)?;
+                Some((armed.timeouts, armed.handshaken))
+            }
+            None => None
+        };
+        Ok(Parts{
+            inner: self.inner,
+            compression: self.compression,
+            adaptive: self.adaptive,
+            encryptor: self.encryptor,
+            decryptor: self.decryptor,
+            timeouts
+        })
+    }
+}
+
+impl<S: Read + Write + SocketTimeouts> PacketStream<S>{
+    /// Enforces `timeouts` on the reads and writes from now on. Reads and
+    /// writes that run out of time fail with a `timeout::TimedOut`, after
+    /// which the connection should be closed: a frame may have been cut off.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) -> std::io::Result<()> {
+        self.inner.set_write_timeout(timeouts.write)?;
+        self.inner.set_read_timeout(None)?;
+        self.timeouts = Some(Armed::new(timeouts, S::set_read_timeout));
+        Ok(())
+    }
+}
+
+/// Iterator over the packets of a `PacketStream`, see `PacketStream::packets`.
+/// Ends when the stream ends between two frames, or after the first error,
+/// which is returned as the last item. A frame cut off is such an error.
+#[derive(Debug)]
+pub struct PacketIter<'a, P, S>{
+    stream: &'a mut PacketStream<S>,
+    /// None to follow the tracked state.
+    state: Option<(State, Direction)>,
+    done: bool,
+    protocol: PhantomData<fn() -> P>
+}
+
+impl<P: Protocol, S: Read + Write> Iterator for PacketIter<'_, P, S>{
+    type Item = std::io::Result<P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = match (self.state, self.stream.states) {
+            (None, None) => Err(untracked()),
+            (state, _) => self.stream.next_frame(state.map(|(state, _)| state))
+        };
+        // Only a stream ending between frames ends the iteration, one ending
+        // within a frame or a body too short for its packet is an error.
+        let packet = match frame {
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Ok(Some((id, body))) => self.stream.decode(self.state, id, body),
+            Err(e) => Err(e)
+        };
+        self.done = packet.is_err();
+        Some(packet)
+    }
+}
+
+pub(crate) fn end_of_stream() -> Error {
+    Error::new(ErrorKind::UnexpectedEof, "the stream ended")
+}
+
+fn untracked() -> Error {
+    Error::new(ErrorKind::InvalidInput, "the state is not tracked, see PacketStream::track_state")
+}
+
+/// Reads the next frame of `state`, see `PacketStream::read_raw`, or `None`
+/// when the stream ends before the first byte of the frame, which is a clean
+/// end rather than a frame cut off. Frames over the limit are refused before
+/// their buffer is taken.
+pub(crate) fn next_frame<R: Read>(inner: &mut R, decryptor: &mut Cipher, compression: Option<usize>, limits: &FrameLimits, state: State) -> std::io::Result<Option<(i32, Vec<u8>)>> {
+    let mut first = [0u8; 1];
+    loop {
+        match Decrypting(inner, decryptor).read(&mut first) {
+            Ok(0) => return Ok(None),
+            Ok(_) => break,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        }
+    }
+    let len = read_var_int(&mut (&first[..]).chain(Decrypting(inner, decryptor)))?;
+    limits.check(state, compression.is_some(), len)?;
+    let mut frame = pool::take(len as usize);
+    frame.resize(len as usize, 0);
+    if let Err(e) = Decrypting(inner, decryptor).read_exact(&mut frame) {
+        pool::recycle(frame);
+        return Err(e);
+    }
+    crate::metrics::bytes_read(var_int_len(len) + len as usize);
+    let data = match compression {
+        #[cfg(feature = "compression")]
+        Some(_) => {
+            let data = crate::connection::compression::decompress(&frame);
+            pool::recycle(frame);
+            data?
+        }
+        _ => frame
+    };
+    let mut body = &data[..];
+    let id = read_var_int(&mut body);
+    let packet = id.map(|id| {
+        let mut packet = pool::take(body.len());
+        packet.extend_from_slice(body);
+        Some((id, packet))
+    });
+    pool::recycle(data);
+    packet
+}
+
+/// Turns the packet id and packet into a frame body, compressed if
+/// `compression` is enabled and counted by `adaptive` if it is tracked.
+/// `data` goes back to the buffer pool.
+pub(crate) fn compress_data(data: Vec<u8>, compression: Option<usize>, adaptive: &mut Adaptive) -> std::io::Result<Vec<u8>> {
+    match compression {
+        #[cfg(feature = "compression")]
+        Some(threshold) => {
+            let mut body = pool::take(data.len() / 2);
+            let compressed = match adaptive {
+                Some(adaptive) => {
+                    let id = crate::segment::implementation::var::decode_var_int(&data).map_or(-1, |(id, _)| id);
+                    adaptive.compress(id, &data, threshold, &mut body)
+                }
+                None => crate::connection::compression::compress(&data, threshold, &mut body)
+            };
+            pool::recycle(data);
+            match compressed {
+                Ok(()) => Ok(body),
+                Err(e) => {
+                    pool::recycle(body);
+                    Err(e)
+                }
+            }
+        }
+        _ => {
+            let _ = adaptive;
+            Ok(data)
+        }
+    }
+}
+
+/// Frames the packet id and packet, then compresses and encrypts them as
+/// needed. `data` goes back to the buffer pool, `frame` is scratch space.
+pub(crate) fn write_data<W: Write>(inner: &mut W, frame: &mut Vec<u8>, encryptor: &mut Cipher, compression: Option<usize>, adaptive: &mut Adaptive, data: Vec<u8>) -> std::io::Result<()> {
+    let body = compress_data(data, compression, adaptive)?;
+    frame.clear();
+    write_var_int(body.len() as i32, frame)?;
+    frame.extend_from_slice(&body);
+    pool::recycle(body);
+    #[cfg(feature = "encryption")]
+    if let Some(encryptor) = encryptor {
+        encryptor.encrypt(frame);
+    }
+    #[cfg(not(feature = "encryption"))]
+    let _ = encryptor;
+    inner.write_all(frame)?;
+    crate::metrics::bytes_written(frame.len());
+    inner.flush()
+}
+
+/// Reads from the inner stream, decrypting if encryption is enabled.
+struct Decrypting<'a, R>(&'a mut R, &'a mut Cipher);
+
+impl<R: Read> Read for Decrypting<'_, R>{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.0.read(buf)?;
+        #[cfg(feature = "encryption")]
+        if let Some(decryptor) = &mut self.1 {
+            decryptor.decrypt(&mut buf[..read]);
+        }
+        #[cfg(not(feature = "encryption"))]
+        let _ = &self.1;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::test_protocol::TestProto;
+    use std::io::Cursor;
+
+    fn stream(bytes: &[u8]) -> PacketStream<Cursor<Vec<u8>>> {
+        PacketStream::new(Cursor::new(bytes.to_vec()))
+    }
+
+    fn packets(bytes: &[u8]) -> Vec<std::io::Result<TestProto>> {
+        stream(bytes).packets::<TestProto>(State::Play, Direction::ClientBound).collect()
+    }
+
+    #[test]
+    fn packets_end_with_the_stream() {
+        assert!(packets(&[]).is_empty());
+        let read = packets(&[0x02, 0x01, 0x01]);
+        assert_eq!(read.len(), 1);
+        assert!(matches!(read[0], Ok(TestProto::Flag(ref flag)) if flag.value));
+    }
+
+    #[test]
+    fn truncated_frame_is_an_error() {
+        let packets = packets(&[0x02, 0x01, 0x01, 0x05, 0x00, 0x01]);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[1].as_ref().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn truncated_length_prefix_is_an_error() {
+        let packets = packets(&[0x80]);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].as_ref().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn short_body_is_an_error() {
+        // KeepAlive with two of the eight bytes of its id.
+        let packets = packets(&[0x03, 0x00, 0x01, 0x02]);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].as_ref().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn oversized_frame_is_refused_before_its_body() {
+        let mut stream = stream(&[0x11]);
+        stream.set_frame_limits(FrameLimits::default().with(State::Play, 16));
+        let error = stream.read_raw().unwrap_err();
+        assert_eq!(crate::connection::frame::FrameTooLarge::of(&error).map(|e| (e.len, e.max)), Some((17, 16)));
+    }
+
+    #[test]
+    fn untracked_reads_use_the_limit_of_their_state() {
+        // A Login frame of 8193 bytes, over the limit before compression.
+        let error = stream(&[0x81, 0x40]).read_packet::<TestProto>(State::Login, Direction::ServerBound).unwrap_err();
+        let too_large = crate::connection::frame::FrameTooLarge::of(&error).copied();
+        assert_eq!(too_large.map(|e| (e.state, e.max)), Some((State::Login, crate::connection::frame::MAX_UNCOMPRESSED_LOGIN_FRAME_LEN)));
+        let error = stream(&[0x81, 0x40]).read_quarantined::<TestProto>(State::Login, Direction::ServerBound).unwrap_err();
+        assert!(crate::connection::frame::FrameTooLarge::of(&error).is_some());
+        // The same frame is allowed in Play, where it is cut off.
+        let error = stream(&[0x81, 0x40]).read_packet::<TestProto>(State::Play, Direction::ClientBound).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn errors_carry_the_recorded_history() {
+        // Flag, then a KeepAlive with two of the eight bytes of its id.
+        let mut stream = stream(&[0x02, 0x01, 0x01, 0x03, 0x00, 0x01, 0x02]);
+        stream.record_history(4);
+        assert!(matches!(stream.read_packet::<TestProto>(State::Play, Direction::ClientBound), Ok(TestProto::Flag(_))));
+        let error = stream.read_packet::<TestProto>(State::Play, Direction::ClientBound).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        let message = error.to_string();
+        assert!(message.contains("last 2 packets"), "{}", message);
+        assert!(message.contains("S->C Flag(Flag { value: true })"), "{}", message);
+        assert!(message.contains("undecodable packet 0x00 of 2 bytes"), "{}", message);
+    }
+
+    #[test]
+    fn written_packets_are_recorded() {
+        let mut stream = stream(&[]);
+        stream.write_raw(0x01, &[0x01]).unwrap();
+        assert_eq!(stream.history().iter().count(), 0);
+        stream.record_history(1);
+        stream.write_raw(0x01, &[0x01]).unwrap();
+        stream.write_raw(0x02, &[]).unwrap();
+        let entries: Vec<_> = stream.history().iter().map(|entry| (entry.direction, entry.packet.as_str())).collect();
+        assert_eq!(entries, vec![(Direction::ServerBound, "packet 0x02 of 0 bytes")]);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn written_packets_are_counted() {
+        let mut stream = stream(&[]);
+        stream.set_compression(Some(0)).unwrap();
+        stream.track_compression(true);
+        for _ in 0..3 {
+            stream.write_raw(0x01, &[0x01]).unwrap();
+        }
+        let stats = stream.compression_stats().unwrap();
+        assert_eq!(stats.get(0x01).map(|counts| counts.compressed), Some(3));
+        assert_eq!(stream.send_threshold(), Some(0));
+    }
+}
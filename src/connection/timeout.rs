@@ -0,0 +1,359 @@
+//! Timeouts protecting a connection from peers that send nothing, or send a
+//! frame a byte at a time to hold on to it as cheaply as possible.
+//!
+//! `Timeouts` bounds the wait for the first byte of a frame, the time to read
+//! a whole frame once it started, the wait for the Handshake of a new
+//! connection and every write. Set them with `PacketStream::set_timeouts` on
+//! streams with socket timeouts, see `SocketTimeouts`, and `split` keeps them
+//! for both halves. A timeout fails the read or write with
+//! `ErrorKind::TimedOut` carrying a `TimedOut`.
+//!
+//! The async codec has no timeouts of its own, wrap its futures in those of
+//! the runtime instead.
+use std::io::{Error, ErrorKind, Read};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+/// Streams whose reads and writes can be given a timeout.
+pub trait SocketTimeouts{
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()>;
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()>;
+}
+
+impl SocketTimeouts for TcpStream{
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_write_timeout(self, timeout)
+    }
+}
+
+#[cfg(unix)]
+impl SocketTimeouts for std::os::unix::net::UnixStream{
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        std::os::unix::net::UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        std::os::unix::net::UnixStream::set_write_timeout(self, timeout)
+    }
+}
+
+impl<S: SocketTimeouts + ?Sized> SocketTimeouts for &mut S{
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        (**self).set_read_timeout(timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        (**self).set_write_timeout(timeout)
+    }
+}
+
+/// The timeouts of a connection, `None` waits forever.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timeouts{
+    /// The longest wait for the next byte.
+    pub read: Option<Duration>,
+    pub write: Option<Duration>,
+    /// The longest time a single frame may take from its first byte to its last.
+    pub frame: Option<Duration>,
+    /// The longest wait for the first frame, the Handshake when the timeouts
+    /// are set on a new connection. Falls back to `read`.
+    pub handshake: Option<Duration>
+}
+
+impl Timeouts{
+    /// Timeouts for servers: vanilla's 30 seconds of silence, 10 seconds for
+    /// a frame and 5 seconds to send the Handshake.
+    pub fn server() -> Self {
+        Timeouts{
+            read: Some(Duration::from_secs(30)),
+            write: Some(Duration::from_secs(30)),
+            frame: Some(Duration::from_secs(10)),
+            handshake: Some(Duration::from_secs(5))
+        }
+    }
+
+    pub fn with_read(mut self, read: Duration) -> Self {
+        self.read = Some(read);
+        self
+    }
+
+    pub fn with_write(mut self, write: Duration) -> Self {
+        self.write = Some(write);
+        self
+    }
+
+    pub fn with_frame(mut self, frame: Duration) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+
+    pub fn with_handshake(mut self, handshake: Duration) -> Self {
+        self.handshake = Some(handshake);
+        self
+    }
+}
+
+/// Which of the `Timeouts` ran out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeoutKind{
+    Read,
+    Write,
+    Frame,
+    Handshake
+}
+
+/// A timeout that ran out, carried inside the `std::io::Error`. Use
+/// `TimedOut::of` to get at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut{
+    pub kind: TimeoutKind,
+    /// The configured timeout.
+    pub after: Duration
+}
+
+impl TimedOut{
+    /// The `TimedOut` inside an error returned by a stream with timeouts.
+    pub fn of(error: &Error) -> Option<&TimedOut> {
+        error.get_ref()?.downcast_ref()
+    }
+
+    pub(crate) fn error(kind: TimeoutKind, after: Duration) -> Error {
+        Error::new(ErrorKind::TimedOut, TimedOut{ kind, after })
+    }
+}
+
+impl std::fmt::Display for TimedOut{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.kind {
+            TimeoutKind::Read => "nothing was received",
+            TimeoutKind::Write => "a write did not complete",
+            TimeoutKind::Frame => "a frame was not completed",
+            TimeoutKind::Handshake => "no handshake was received"
+        };
+        write!(f, "{} within {:?}", what, self.after)
+    }
+}
+
+impl std::error::Error for TimedOut{}
+
+/// Turns the error of a read or write that ran into the socket timeout set
+/// for `armed` into a `TimedOut`. Unix reports the timeout as `WouldBlock`,
+/// Windows as `TimedOut`. Without a timeout set by this module `WouldBlock`
+/// is a non-blocking socket that is not ready, and returned as is.
+pub(crate) fn timed_out(error: Error, armed: Option<(TimeoutKind, Duration)>) -> Error {
+    match armed {
+        Some((kind, after)) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) && TimedOut::of(&error).is_none() => {
+            TimedOut::error(kind, after)
+        }
+        _ => error
+    }
+}
+
+/// The timeouts of a `PacketStream` or `split::ReadHalf`, with how to set
+/// the read timeout of its stream, which changes while a frame is read.
+#[derive(Debug)]
+pub(crate) struct Armed<S>{
+    pub timeouts: Timeouts,
+    pub set_read_timeout: fn(&S, Option<Duration>) -> std::io::Result<()>,
+    /// The read timeout the stream has now.
+    pub current: Option<Duration>,
+    /// Whether the first frame was read, after which `handshake` no longer applies.
+    pub handshaken: bool
+}
+
+/// Reads a single frame from the stream, moving its read timeout along so no
+/// read waits past the deadline of the frame.
+pub(crate) struct Deadline<'a, S>{
+    inner: &'a mut S,
+    armed: &'a mut Armed<S>,
+    started: Option<Instant>
+}
+
+impl<S> Armed<S>{
+    pub fn new(timeouts: Timeouts, set_read_timeout: fn(&S, Option<Duration>) -> std::io::Result<()>) -> Self {
+        Armed{ timeouts, set_read_timeout, current: None, handshaken: false }
+    }
+
+    /// Takes the read timeout off the stream, so nothing this module set for a
+    /// single frame stays behind when the stream is used without it.
+    pub fn disarm(&mut self, stream: &S) -> std::io::Result<()> {
+        if self.current.is_some() {
+            (self.set_read_timeout)(stream, None)?;
+            self.current = None;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, S> Deadline<'a, S>{
+    pub fn new(inner: &'a mut S, armed: &'a mut Armed<S>) -> Self {
+        Deadline{ inner, armed, started: None }
+    }
+
+    /// The read timeout for the next read, with the timeout to report when it runs out.
+    fn next_timeout(&self) -> std::io::Result<Option<(TimeoutKind, Duration, Duration)>> {
+        let timeouts = self.armed.timeouts;
+        let started = match self.started {
+            Some(started) => started,
+            None if !self.armed.handshaken && timeouts.handshake.is_some() => {
+                return Ok(timeouts.handshake.map(|handshake| (TimeoutKind::Handshake, handshake, handshake)))
+            }
+            None => return Ok(timeouts.read.map(|read| (TimeoutKind::Read, read, read)))
+        };
+        let frame = match timeouts.frame {
+            Some(frame) => frame,
+            None => return Ok(timeouts.read.map(|read| (TimeoutKind::Read, read, read)))
+        };
+        let remaining = frame.saturating_sub(started.elapsed());
+        if remaining.is_zero() {
+            return Err(TimedOut::error(TimeoutKind::Frame, frame));
+        }
+        Ok(Some(match timeouts.read {
+            Some(read) if read < remaining => (TimeoutKind::Read, read, read),
+            _ => (TimeoutKind::Frame, remaining, frame)
+        }))
+    }
+}
+
+impl<S: Read> Read for Deadline<'_, S>{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let next = self.next_timeout()?;
+        let timeout = next.map(|(_, timeout, _)| timeout);
+        if timeout != self.armed.current {
+            (self.armed.set_read_timeout)(&*self.inner, timeout)?;
+            self.armed.current = timeout;
+        }
+        match self.inner.read(buf) {
+            Ok(read) => {
+                if read > 0 && self.started.is_none() {
+                    self.started = Some(Instant::now());
+                }
+                Ok(read)
+            }
+            Err(e) => Err(timed_out(e, next.map(|(kind, _, after)| (kind, after))))
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    /// A socket whose peer sends `input` a byte at a time and then nothing,
+    /// and never reads. Once its input ran out it fails reads like a socket
+    /// whose timeout ran out, or ends when no read timeout is set. Writes
+    /// fail with `WouldBlock`. Clones share the socket.
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct Stalling{
+        pub input: Rc<RefCell<VecDeque<u8>>>,
+        /// Every read timeout set, in order.
+        pub read_timeouts: Rc<RefCell<Vec<Option<Duration>>>>
+    }
+
+    impl Stalling{
+        pub fn new(input: &[u8]) -> Self {
+            Stalling{ input: Rc::new(RefCell::new(input.iter().copied().collect())), ..Default::default() }
+        }
+
+        fn current_read_timeout(&self) -> Option<Duration> {
+            self.read_timeouts.borrow().last().copied().flatten()
+        }
+    }
+
+    impl Read for Stalling{
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.input.borrow_mut().pop_front() {
+                Some(byte) => {
+                    buf[0] = byte;
+                    Ok(1)
+                }
+                None if self.current_read_timeout().is_some() => Err(Error::new(ErrorKind::WouldBlock, "resource temporarily unavailable")),
+                None => Ok(0)
+            }
+        }
+    }
+
+    impl std::io::Write for Stalling{
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(Error::new(ErrorKind::WouldBlock, "resource temporarily unavailable"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SocketTimeouts for Stalling{
+        fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+            self.read_timeouts.borrow_mut().push(timeout);
+            Ok(())
+        }
+
+        fn set_write_timeout(&self, _: Option<Duration>) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn kind(error: &Error) -> Option<TimeoutKind> {
+        TimedOut::of(error).map(|timed_out| timed_out.kind)
+    }
+
+    #[test]
+    fn would_block_is_a_timeout_only_when_armed() {
+        let would_block = || Error::new(ErrorKind::WouldBlock, "not ready");
+        assert_eq!(timed_out(would_block(), None).kind(), ErrorKind::WouldBlock);
+        let error = timed_out(would_block(), Some((TimeoutKind::Read, Duration::from_secs(1))));
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+        assert_eq!(kind(&error), Some(TimeoutKind::Read));
+        let other = timed_out(Error::new(ErrorKind::ConnectionReset, "reset"), Some((TimeoutKind::Read, Duration::from_secs(1))));
+        assert_eq!(other.kind(), ErrorKind::ConnectionReset);
+    }
+
+    #[test]
+    fn handshake_then_frame_timeouts() {
+        let timeouts = Timeouts::default().with_handshake(Duration::from_secs(1)).with_frame(Duration::from_secs(10));
+        let mut stream = Stalling::new(&[]);
+        let mut armed = Armed::new(timeouts, Stalling::set_read_timeout);
+        let error = Deadline::new(&mut stream, &mut armed).read(&mut [0]).unwrap_err();
+        assert_eq!(kind(&error), Some(TimeoutKind::Handshake));
+
+        stream.input.borrow_mut().push_back(1);
+        armed.handshaken = true;
+        let mut deadline = Deadline::new(&mut stream, &mut armed);
+        assert_eq!(deadline.read(&mut [0]).unwrap(), 1);
+        let error = deadline.read(&mut [0]).unwrap_err();
+        assert_eq!(kind(&error), Some(TimeoutKind::Frame));
+    }
+
+    #[test]
+    fn no_timeout_between_frames_without_read() {
+        let timeouts = Timeouts::default().with_frame(Duration::from_secs(10));
+        let mut stream = Stalling::new(&[1]);
+        let mut armed = Armed::new(timeouts, Stalling::set_read_timeout);
+        let mut deadline = Deadline::new(&mut stream, &mut armed);
+        assert_eq!(deadline.read(&mut [0]).unwrap(), 1);
+        assert!(deadline.read(&mut [0]).is_err());
+        // The next frame waits forever for its first byte, so the frame timeout is taken off.
+        assert_eq!(Deadline::new(&mut stream, &mut armed).read(&mut [0]).unwrap(), 0);
+        assert_eq!(stream.current_read_timeout(), None);
+    }
+
+    #[test]
+    fn disarm_clears_the_read_timeout() {
+        let mut stream = Stalling::new(&[1]);
+        let mut armed = Armed::new(Timeouts::default().with_read(Duration::from_secs(1)), Stalling::set_read_timeout);
+        Deadline::new(&mut stream, &mut armed).read(&mut [0]).unwrap();
+        assert_eq!(stream.current_read_timeout(), Some(Duration::from_secs(1)));
+        armed.disarm(&stream).unwrap();
+        assert_eq!(stream.current_read_timeout(), None);
+        assert_eq!(armed.current, None);
+    }
+}
@@ -0,0 +1,262 @@
+//! Ways of opening the connection to a server.
+//!
+//! A `Transport` hands back a stream that is connected to the server, ready
+//! for the Handshake. Besides connecting directly, connections can be made
+//! through a SOCKS5 or HTTP CONNECT proxy. Other tunnels implement the trait
+//! themselves, and can use `socks5_connect` and `http_connect` to talk to a
+//! proxy over a stream they opened.
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+pub trait Transport{
+    type Stream: Read + Write;
+
+    fn connect(&self, host: &str, port: u16) -> std::io::Result<Self::Stream>;
+}
+
+/// A user name and password for a proxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials{
+    pub username: String,
+    pub password: String
+}
+
+impl Credentials{
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Credentials{ username: username.into(), password: password.into() }
+    }
+}
+
+/// Connects over plain TCP.
+#[derive(Debug, Clone, Default)]
+pub struct Direct{
+    pub timeout: Option<Duration>
+}
+
+impl Transport for Direct{
+    type Stream = TcpStream;
+
+    fn connect(&self, host: &str, port: u16) -> std::io::Result<TcpStream> {
+        tcp_connect((host, port), self.timeout)
+    }
+}
+
+/// Connects through a SOCKS5 proxy. The proxy resolves the host name.
+#[derive(Debug, Clone)]
+pub struct Socks5{
+    pub proxy: String,
+    pub credentials: Option<Credentials>,
+    pub timeout: Option<Duration>
+}
+
+impl Socks5{
+    /// `proxy` is the `host:port` of the proxy.
+    pub fn new(proxy: impl Into<String>) -> Self {
+        Socks5{ proxy: proxy.into(), credentials: None, timeout: None }
+    }
+
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+}
+
+impl Transport for Socks5{
+    type Stream = TcpStream;
+
+    fn connect(&self, host: &str, port: u16) -> std::io::Result<TcpStream> {
+        let mut stream = tcp_connect(self.proxy.as_str(), self.timeout)?;
+        socks5_connect(&mut stream, host, port, self.credentials.as_ref())?;
+        Ok(stream)
+    }
+}
+
+/// Connects through an HTTP proxy using the CONNECT method.
+#[derive(Debug, Clone)]
+pub struct HttpConnect{
+    pub proxy: String,
+    pub credentials: Option<Credentials>,
+    pub timeout: Option<Duration>
+}
+
+impl HttpConnect{
+    /// `proxy` is the `host:port` of the proxy.
+    pub fn new(proxy: impl Into<String>) -> Self {
+        HttpConnect{ proxy: proxy.into(), credentials: None, timeout: None }
+    }
+
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+}
+
+impl Transport for HttpConnect{
+    type Stream = TcpStream;
+
+    fn connect(&self, host: &str, port: u16) -> std::io::Result<TcpStream> {
+        let mut stream = tcp_connect(self.proxy.as_str(), self.timeout)?;
+        http_connect(&mut stream, host, port, self.credentials.as_ref())?;
+        Ok(stream)
+    }
+}
+
+fn tcp_connect<A: ToSocketAddrs>(address: A, timeout: Option<Duration>) -> std::io::Result<TcpStream> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return TcpStream::connect(address)
+    };
+    let mut last_error = Error::new(ErrorKind::InvalidInput, "address resolved to nothing");
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e
+        }
+    }
+    Err(last_error)
+}
+
+const SOCKS_VERSION: u8 = 5;
+const SOCKS_NO_AUTH: u8 = 0;
+const SOCKS_PASSWORD_AUTH: u8 = 2;
+const SOCKS_NO_ACCEPTABLE_METHOD: u8 = 0xFF;
+const SOCKS_CONNECT: u8 = 1;
+const SOCKS_IPV4: u8 = 1;
+const SOCKS_DOMAIN: u8 = 3;
+const SOCKS_IPV6: u8 = 4;
+
+/// Asks a SOCKS5 proxy on the other end of `stream` to connect to `host`.
+pub fn socks5_connect<S: Read + Write>(stream: &mut S, host: &str, port: u16, credentials: Option<&Credentials>) -> std::io::Result<()> {
+    if credentials.is_some() {
+        stream.write_all(&[SOCKS_VERSION, 2, SOCKS_NO_AUTH, SOCKS_PASSWORD_AUTH])?;
+    }else{
+        stream.write_all(&[SOCKS_VERSION, 1, SOCKS_NO_AUTH])?;
+    }
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply)?;
+    if reply[0] != SOCKS_VERSION {
+        return Err(proxy_error("proxy does not speak SOCKS5"));
+    }
+    match (reply[1], credentials) {
+        (SOCKS_NO_AUTH, _) => {}
+        (SOCKS_PASSWORD_AUTH, Some(credentials)) => {
+            let username = credentials.username.as_bytes();
+            let password = credentials.password.as_bytes();
+            if username.len() > 255 || password.len() > 255 {
+                return Err(Error::new(ErrorKind::InvalidInput, "SOCKS5 credentials are limited to 255 bytes"));
+            }
+            let mut request = vec![1, username.len() as u8];
+            request.extend_from_slice(username);
+            request.push(password.len() as u8);
+            request.extend_from_slice(password);
+            stream.write_all(&request)?;
+            stream.read_exact(&mut reply)?;
+            if reply[1] != 0 {
+                return Err(Error::new(ErrorKind::PermissionDenied, "SOCKS5 proxy rejected the credentials"));
+            }
+        }
+        (SOCKS_NO_ACCEPTABLE_METHOD, _) => return Err(Error::new(ErrorKind::PermissionDenied, "SOCKS5 proxy requires authentication")),
+        _ => return Err(proxy_error("SOCKS5 proxy chose an unsupported authentication method"))
+    }
+
+    let mut request = vec![SOCKS_VERSION, SOCKS_CONNECT, 0];
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(SOCKS_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(SOCKS_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > 255 {
+                return Err(Error::new(ErrorKind::InvalidInput, "host name is too long for SOCKS5"));
+            }
+            request.push(SOCKS_DOMAIN);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[1] != 0 {
+        let reason = match reply[1] {
+            1 => "general failure",
+            2 => "connection not allowed by ruleset",
+            3 => "network unreachable",
+            4 => "host unreachable",
+            5 => "connection refused",
+            6 => "TTL expired",
+            7 => "command not supported",
+            8 => "address type not supported",
+            _ => "unknown error"
+        };
+        return Err(Error::new(ErrorKind::ConnectionRefused, format!("SOCKS5 proxy could not connect: {}", reason)));
+    }
+    // The address the proxy bound to is of no use to us.
+    let address_len = match reply[3] {
+        SOCKS_IPV4 => 4,
+        SOCKS_IPV6 => 16,
+        SOCKS_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len)?;
+            len[0] as usize
+        }
+        _ => return Err(proxy_error("SOCKS5 proxy replied with an unknown address type"))
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound)
+}
+
+/// The longest response header accepted from an HTTP proxy.
+const MAX_HTTP_HEADER_LEN: usize = 8192;
+
+/// Asks an HTTP proxy on the other end of `stream` to connect to `host`.
+pub fn http_connect<S: Read + Write>(stream: &mut S, host: &str, port: u16, credentials: Option<&Credentials>) -> std::io::Result<()> {
+    let authority = if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    }else{
+        format!("{}:{}", host, port)
+    };
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if let Some(credentials) = credentials {
+        let token = crate::base64::encode(format!("{}:{}", credentials.username, credentials.password).as_bytes());
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+    stream.flush()?;
+
+    // Read byte by byte, anything after the header already belongs to the server.
+    let mut header = Vec::new();
+    let mut byte = [0u8; 1];
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() >= MAX_HTTP_HEADER_LEN {
+            return Err(proxy_error("HTTP proxy response header is too long"));
+        }
+        stream.read_exact(&mut byte)?;
+        header.push(byte[0]);
+    }
+    let header = String::from_utf8_lossy(&header);
+    let status_line = header.lines().next().unwrap_or_default();
+    let mut parts = status_line.splitn(3, ' ');
+    let version = parts.next().unwrap_or_default();
+    let status = parts.next().unwrap_or_default();
+    if !version.starts_with("HTTP/") {
+        return Err(proxy_error("proxy does not speak HTTP"));
+    }
+    match status {
+        "200" => Ok(()),
+        "407" => Err(Error::new(ErrorKind::PermissionDenied, "HTTP proxy requires authentication")),
+        _ => Err(Error::new(ErrorKind::ConnectionRefused, format!("HTTP proxy refused to connect: {}", status_line)))
+    }
+}
+
+fn proxy_error(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
@@ -0,0 +1,272 @@
+//! Connections carried in WebSocket messages, for browsers that can only
+//! reach a server through a WebSocket to TCP bridge like websockify.
+//!
+//! `MessageStream` turns a message based connection into the `Read + Write`
+//! stream `PacketStream` expects. Everything written until a flush is sent as
+//! a single binary message, which `PacketStream` does once per packet.
+//! Received messages are pushed into the `Inbox` of the stream, usually from
+//! the callback of the socket. Reading from an empty inbox fails with
+//! `WouldBlock` instead of waiting, as nothing can wait in a browser; use
+//! `Inbox::has_frame` to only read packets that have fully arrived. That does
+//! not work once encryption is enabled, so stick to offline mode servers.
+//!
+//! With the `wasm` feature and on `wasm32`, `WebSocketTransport` opens such a
+//! stream with the WebSocket of the browser.
+use crate::io::borrowed;
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::sync::{Arc, Mutex};
+
+/// Sends a single binary message.
+pub trait MessageSink{
+    fn send(&mut self, message: &[u8]) -> std::io::Result<()>;
+}
+
+impl<F: FnMut(&[u8]) -> std::io::Result<()>> MessageSink for F{
+    fn send(&mut self, message: &[u8]) -> std::io::Result<()> {
+        self(message)
+    }
+}
+
+#[derive(Debug, Default)]
+struct InboxState{
+    bytes: VecDeque<u8>,
+    closed: bool,
+    error: Option<String>
+}
+
+/// The receiving end of a `MessageStream`, cheap to clone and to move into
+/// the callback of a socket.
+#[derive(Debug, Clone, Default)]
+pub struct Inbox(Arc<Mutex<InboxState>>);
+
+impl Inbox{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the payload of a received binary message.
+    pub fn push(&self, message: &[u8]) {
+        self.lock().bytes.extend(message);
+    }
+
+    /// Marks the connection as closed. Reads return the rest of the received
+    /// bytes, then end of file.
+    pub fn close(&self) {
+        self.lock().closed = true;
+    }
+
+    /// Closes the connection with an error, which reads return once the
+    /// received bytes are used up.
+    pub fn fail(&self, error: impl Into<String>) {
+        let mut state = self.lock();
+        state.closed = true;
+        state.error = Some(error.into());
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// The amount of received bytes that were not read yet.
+    pub fn available(&self) -> usize {
+        self.lock().bytes.len()
+    }
+
+    /// Whether a whole uncompressed or compressed frame was received, so
+    /// `PacketStream::read_raw` will not run out of bytes halfway. Unreliable
+    /// once encryption is enabled, as the frame length is encrypted as well.
+    pub fn has_frame(&self) -> std::io::Result<bool> {
+        let mut state = self.lock();
+        let mut buffer: &[u8] = state.bytes.make_contiguous();
+        Ok(borrowed::read_frame(&mut buffer)?.is_some())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, InboxState> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A `Read + Write` stream over a message based connection, see the module
+/// documentation.
+#[derive(Debug)]
+pub struct MessageStream<S: MessageSink>{
+    sink: S,
+    inbox: Inbox,
+    outgoing: Vec<u8>
+}
+
+impl<S: MessageSink> MessageStream<S>{
+    pub fn new(sink: S, inbox: Inbox) -> Self {
+        MessageStream{ sink, inbox, outgoing: Vec::new() }
+    }
+
+    pub fn inbox(&self) -> &Inbox {
+        &self.inbox
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+}
+
+impl<S: MessageSink> Read for MessageStream<S>{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut state = self.inbox.lock();
+        if state.bytes.is_empty() {
+            return match (&state.error, state.closed) {
+                (Some(error), _) => Err(Error::new(ErrorKind::ConnectionAborted, error.clone())),
+                (None, true) => Ok(0),
+                (None, false) => Err(Error::new(ErrorKind::WouldBlock, "no message received yet"))
+            };
+        }
+        let len = std::cmp::min(buf.len(), state.bytes.len());
+        for (target, byte) in buf.iter_mut().zip(state.bytes.drain(..len)) {
+            *target = byte;
+        }
+        Ok(len)
+    }
+}
+
+impl<S: MessageSink> Write for MessageStream<S>{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.inbox.is_closed() {
+            return Err(Error::new(ErrorKind::NotConnected, "the connection is closed"));
+        }
+        self.outgoing.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// Sends everything written since the last flush as one message.
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.outgoing.is_empty() {
+            return Ok(());
+        }
+        self.sink.send(&self.outgoing)?;
+        self.outgoing.clear();
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use browser::{BrowserSocket, WebSocketTransport};
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod browser {
+    use super::{Inbox, MessageSink, MessageStream};
+    use crate::connection::transport::Transport;
+    use std::cell::RefCell;
+    use std::io::{Error, ErrorKind};
+    use std::rc::Rc;
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+    use web_sys::{BinaryType, CloseEvent, Event, MessageEvent, WebSocket};
+
+    /// Connects through a WebSocket to TCP bridge.
+    #[derive(Debug, Clone)]
+    pub struct WebSocketTransport{
+        /// The URL of the bridge, `{host}` and `{port}` are replaced by the
+        /// server to connect to, for bridges that take it from the URL.
+        pub url: String
+    }
+
+    impl WebSocketTransport{
+        pub fn new(url: impl Into<String>) -> Self {
+            WebSocketTransport{ url: url.into() }
+        }
+    }
+
+    impl Transport for WebSocketTransport{
+        type Stream = MessageStream<BrowserSocket>;
+
+        /// Returns right away, messages written before the socket opened are
+        /// sent once it did.
+        fn connect(&self, host: &str, port: u16) -> std::io::Result<Self::Stream> {
+            let url = self.url.replace("{host}", host).replace("{port}", &port.to_string());
+            let socket = WebSocket::new(&url).map_err(|e| js_error("can not open the WebSocket", e))?;
+            socket.set_binary_type(BinaryType::Arraybuffer);
+            let inbox = Inbox::new();
+            let pending = Rc::new(RefCell::new(Vec::<Vec<u8>>::new()));
+
+            let on_message = {
+                let inbox = inbox.clone();
+                Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                    if let Ok(buffer) = event.data().dyn_into::<js_sys::ArrayBuffer>() {
+                        inbox.push(&js_sys::Uint8Array::new(&buffer).to_vec());
+                    }
+                })
+            };
+            let on_open = {
+                let (socket, pending) = (socket.clone(), pending.clone());
+                Closure::<dyn FnMut(Event)>::new(move |_: Event| {
+                    for message in pending.borrow_mut().drain(..) {
+                        let _ = socket.send_with_u8_array(&message);
+                    }
+                })
+            };
+            let on_close = {
+                let inbox = inbox.clone();
+                Closure::<dyn FnMut(CloseEvent)>::new(move |event: CloseEvent| {
+                    if event.was_clean() {
+                        inbox.close();
+                    }else{
+                        inbox.fail(format!("WebSocket closed with code {}", event.code()));
+                    }
+                })
+            };
+            socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+            socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+            let sink = BrowserSocket{ socket, pending, _callbacks: (on_message, on_open, on_close) };
+            Ok(MessageStream::new(sink, inbox))
+        }
+    }
+
+    /// The WebSocket of a `MessageStream`, closed when dropped.
+    pub struct BrowserSocket{
+        socket: WebSocket,
+        pending: Rc<RefCell<Vec<Vec<u8>>>>,
+        _callbacks: (Closure<dyn FnMut(MessageEvent)>, Closure<dyn FnMut(Event)>, Closure<dyn FnMut(CloseEvent)>)
+    }
+
+    impl BrowserSocket{
+        pub fn socket(&self) -> &WebSocket {
+            &self.socket
+        }
+    }
+
+    impl std::fmt::Debug for BrowserSocket{
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("BrowserSocket").field("url", &self.socket.url()).finish()
+        }
+    }
+
+    impl MessageSink for BrowserSocket{
+        fn send(&mut self, message: &[u8]) -> std::io::Result<()> {
+            match self.socket.ready_state() {
+                WebSocket::CONNECTING => {
+                    self.pending.borrow_mut().push(message.to_vec());
+                    Ok(())
+                }
+                WebSocket::OPEN => self.socket.send_with_u8_array(message).map_err(|e| js_error("can not send", e)),
+                _ => Err(Error::new(ErrorKind::NotConnected, "the WebSocket is closed"))
+            }
+        }
+    }
+
+    impl Drop for BrowserSocket{
+        fn drop(&mut self) {
+            self.socket.set_onmessage(None);
+            self.socket.set_onopen(None);
+            self.socket.set_onclose(None);
+            let _ = self.socket.close();
+        }
+    }
+
+    fn js_error(message: &str, error: wasm_bindgen::JsValue) -> Error {
+        Error::other(format!("{}: {:?}", message, error))
+    }
+}
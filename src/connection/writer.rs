@@ -0,0 +1,109 @@
+use crate::connection::{frame, pool};
+use crate::protocol::Packet;
+use std::io::{Error, ErrorKind, IoSlice, Write};
+
+/// Batches framed packets and hands them to the underlying writer in a single
+/// `write_vectored` call on `flush()`.
+///
+/// Nothing is written until `flush()` is called, so make sure to call it after
+/// queueing a batch of packets.
+#[derive(Debug)]
+pub struct PacketWriter<W: Write>{
+    inner: W,
+    frames: Vec<Vec<u8>>,
+    /// Cleared frames kept around to reuse their allocation.
+    spare: Vec<Vec<u8>>,
+    buffered: usize
+}
+
+impl<W: Write> PacketWriter<W>{
+    pub fn new(inner: W) -> Self {
+        PacketWriter{
+            inner,
+            frames: Vec::new(),
+            spare: Vec::new(),
+            buffered: 0
+        }
+    }
+
+    /// Frames the packet and queues it for the next flush.
+    pub fn send<P: Packet>(&mut self, packet: &P) -> std::io::Result<()> {
+        let len = frame::frame_len(packet)?;
+        let mut buffer = self.spare.pop().unwrap_or_else(|| pool::take(0));
+        buffer.reserve_exact(len);
+        frame::write_frame(packet, &mut buffer)?;
+        self.buffered += buffer.len();
+        self.frames.push(buffer);
+        Ok(())
+    }
+
+    /// Queues an already framed packet.
+    pub fn send_raw(&mut self, frame: Vec<u8>) {
+        self.buffered += frame.len();
+        self.frames.push(frame);
+    }
+
+    /// Amount of bytes waiting for the next flush.
+    pub fn buffered(&self) -> usize {
+        self.buffered
+    }
+
+    /// Writes all queued packets to the underlying writer and flushes it.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        let mut frame_index = 0;
+        let mut offset = 0;
+        while frame_index < self.frames.len() {
+            let written = {
+                let mut slices = Vec::with_capacity(self.frames.len() - frame_index);
+                slices.push(IoSlice::new(&self.frames[frame_index][offset..]));
+                slices.extend(self.frames[frame_index + 1..].iter().map(|f| IoSlice::new(f)));
+                match self.inner.write_vectored(&slices) {
+                    Ok(0) => {
+                        self.discard_written(frame_index, offset);
+                        return Err(Error::new(ErrorKind::WriteZero, "failed to write queued packets"));
+                    }
+                    Ok(n) => n,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        self.discard_written(frame_index, offset);
+                        return Err(e);
+                    }
+                }
+            };
+            offset += written;
+            while frame_index < self.frames.len() && offset >= self.frames[frame_index].len() {
+                offset -= self.frames[frame_index].len();
+                frame_index += 1;
+            }
+        }
+        self.discard_written(frame_index, 0);
+        self.inner.flush()
+    }
+
+    /// Drops everything that made it to the underlying writer, so a failed
+    /// flush can be retried without sending anything twice.
+    fn discard_written(&mut self, frame_index: usize, offset: usize) {
+        for mut frame in self.frames.drain(..frame_index) {
+            self.buffered -= frame.len();
+            frame.clear();
+            self.spare.push(frame);
+        }
+        if offset > 0 {
+            self.frames[0].drain(..offset);
+            self.buffered -= offset;
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the underlying writer. Queued packets that were not flushed are lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
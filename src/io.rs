@@ -0,0 +1,169 @@
+//! The `Read` and `Write` the `Segment` layer is built on.
+//!
+//! With the `std` feature, the default, these are the ones of `std::io`. Without
+//! it, the crate is `no_std` and only has `segment` with the numeric, VarInt,
+//! bounded and enum segments, and `borrowed` for the handshake and status
+//! packets. Those then use the minimal versions below, which are implemented
+//! for byte slices and `Vec<u8>`. Implement them for other sources and sinks,
+//! like a UART or a WebSocket frame.
+pub mod borrowed;
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Read, Result, Write};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{Error, ErrorKind, Read, Result, Write};
+
+/// Reads exactly `N` bytes, for the fixed size numbers.
+#[inline]
+pub(crate) fn read_array<const N: usize, R: Read + ?Sized>(reader: &mut R) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use alloc::borrow::Cow;
+    use alloc::vec::Vec;
+    use core::fmt::{Display, Formatter};
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// The kinds of `std::io::ErrorKind` the segments use.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum ErrorKind{
+        InvalidData,
+        InvalidInput,
+        UnexpectedEof,
+        WriteZero,
+        Unsupported,
+        Other
+    }
+
+    #[derive(Debug)]
+    pub struct Error{
+        kind: ErrorKind,
+        message: Cow<'static, str>
+    }
+
+    impl Error{
+        pub fn new<M: Into<Cow<'static, str>>>(kind: ErrorKind, message: M) -> Self {
+            Error{ kind, message: message.into() }
+        }
+
+        pub fn other<M: Into<Cow<'static, str>>>(message: M) -> Self {
+            Self::new(ErrorKind::Other, message)
+        }
+
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl Display for Error{
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            f.write_str(&self.message)
+        }
+    }
+
+    impl From<ErrorKind> for Error{
+        fn from(kind: ErrorKind) -> Self {
+            Error::new(kind, "")
+        }
+    }
+
+    pub trait Read{
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+        fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.read(buf)? {
+                    0 => return Err(Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer")),
+                    n => buf = &mut buf[n..]
+                }
+            }
+            Ok(())
+        }
+
+        fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+            let start = buf.len();
+            let mut chunk = [0u8; 256];
+            loop {
+                match self.read(&mut chunk)? {
+                    0 => return Ok(buf.len() - start),
+                    n => buf.extend_from_slice(&chunk[..n])
+                }
+            }
+        }
+    }
+
+    pub trait Write{
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        fn flush(&mut self) -> Result<()>;
+
+        fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.write(buf)? {
+                    0 => return Err(Error::new(ErrorKind::WriteZero, "failed to write whole buffer")),
+                    n => buf = &buf[n..]
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl Read for &[u8]{
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let len = core::cmp::min(buf.len(), self.len());
+            let (read, rest) = self.split_at(len);
+            buf[..len].copy_from_slice(read);
+            *self = rest;
+            Ok(len)
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for &mut R{
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+
+    impl Write for Vec<u8>{
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Writes into the front of the slice and advances it, failing with
+    /// `WriteZero` once it is full.
+    impl Write for &mut [u8]{
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            let len = core::cmp::min(buf.len(), self.len());
+            let (written, rest) = core::mem::take(self).split_at_mut(len);
+            written.copy_from_slice(&buf[..len]);
+            *self = rest;
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W{
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            (**self).write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            (**self).flush()
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Allocation free encoding and decoding of the handshake and status packets.
+//!
+//! Variable length parts borrow from the caller's buffer instead of being
+//! copied into a `String` or `Vec`, and errors are created from a bare
+//! `ErrorKind`, so none of these functions touch the heap. That is enough to
+//! query a server list ping on targets with little or no allocator, like a
+//! hardware MOTD display.
+//!
+//! The module is built on `crate::io` and also available without the `std`
+//! feature. VarInts go through `var::decode_var_int`, so inside
+//! `var::canonical` overlong encodings are rejected here as well.
+use crate::io::{Error, ErrorKind, Write};
+use crate::segment::implementation::var::{var_int_len, write_var_int};
+
+pub const HANDSHAKE_ID: i32 = 0x00;
+pub const STATUS_REQUEST_ID: i32 = 0x00;
+pub const STATUS_RESPONSE_ID: i32 = 0x00;
+pub const PING_ID: i32 = 0x01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handshake<'a>{
+    pub protocol_version: i32,
+    pub host: &'a str,
+    pub port: u16,
+    /// 1 for status, 2 for login.
+    pub next: i32
+}
+
+/// Reads a VarInt from the front of the slice and advances it.
+#[inline]
+pub fn read_var_int(buffer: &mut &[u8]) -> crate::io::Result<i32> {
+    let (value, len) = crate::segment::implementation::var::decode_var_int(buffer)?;
+    *buffer = &buffer[len..];
+    Ok(value)
+}
+
+/// Reads a length prefixed string, borrowing it from the buffer.
+pub fn read_str<'a>(buffer: &mut &'a [u8]) -> crate::io::Result<&'a str> {
+    let len = read_var_int(buffer)?;
+    if len < 0 || len as usize > buffer.len() {
+        return Err(Error::from(ErrorKind::InvalidData));
+    }
+    let (bytes, rest) = buffer.split_at(len as usize);
+    *buffer = rest;
+    core::str::from_utf8(bytes).map_err(|_| Error::from(ErrorKind::InvalidData))
+}
+
+fn read_array<const N: usize>(buffer: &mut &[u8]) -> crate::io::Result<[u8; N]> {
+    if buffer.len() < N {
+        return Err(Error::from(ErrorKind::UnexpectedEof));
+    }
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(&buffer[..N]);
+    *buffer = &buffer[N..];
+    Ok(bytes)
+}
+
+/// Splits a complete frame off the front of the buffer.
+///
+/// Returns the packet id and body, or `None` if the buffer does not hold a
+/// whole frame yet. The buffer is only advanced when a frame is returned.
+pub fn read_frame<'a>(buffer: &mut &'a [u8]) -> crate::io::Result<Option<(i32, &'a [u8])>> {
+    let mut cursor = *buffer;
+    let len = match read_var_int(&mut cursor) {
+        Ok(len) => len,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e)
+    };
+    if len < 1 {
+        return Err(Error::from(ErrorKind::InvalidData));
+    }
+    if cursor.len() < len as usize {
+        return Ok(None);
+    }
+    let (mut frame, rest) = cursor.split_at(len as usize);
+    let id = read_var_int(&mut frame)?;
+    *buffer = rest;
+    Ok(Some((id, frame)))
+}
+
+/// Writes a frame into `out`, returning the amount of bytes used. The body is
+/// produced by `body`, which must write exactly `body_len` bytes.
+fn write_frame<F>(out: &mut [u8], id: i32, body_len: usize, body: F) -> crate::io::Result<usize>
+    where F: FnOnce(&mut &mut [u8]) -> crate::io::Result<()> {
+    let capacity = out.len();
+    let mut cursor = out;
+    write_var_int((var_int_len(id) + body_len) as i32, &mut cursor)?;
+    write_var_int(id, &mut cursor)?;
+    body(&mut cursor)?;
+    Ok(capacity - cursor.len())
+}
+
+impl<'a> Handshake<'a>{
+    pub fn decode(mut body: &'a [u8]) -> crate::io::Result<Self> {
+        let protocol_version = read_var_int(&mut body)?;
+        let host = read_str(&mut body)?;
+        let port = u16::from_be_bytes(read_array(&mut body)?);
+        let next = read_var_int(&mut body)?;
+        Ok(Handshake{ protocol_version, host, port, next })
+    }
+
+    /// Writes the framed handshake into `out` and returns the amount of bytes used.
+    pub fn encode(&self, out: &mut [u8]) -> crate::io::Result<usize> {
+        let body_len = var_int_len(self.protocol_version)
+            + var_int_len(self.host.len() as i32) + self.host.len()
+            + 2
+            + var_int_len(self.next);
+        write_frame(out, HANDSHAKE_ID, body_len, |out| {
+            write_var_int(self.protocol_version, out)?;
+            write_var_int(self.host.len() as i32, out)?;
+            out.write_all(self.host.as_bytes())?;
+            out.write_all(&self.port.to_be_bytes())?;
+            write_var_int(self.next, out)
+        })
+    }
+}
+
+/// Writes a framed StatusRequest into `out` and returns the amount of bytes used.
+pub fn encode_status_request(out: &mut [u8]) -> crate::io::Result<usize> {
+    write_frame(out, STATUS_REQUEST_ID, 0, |_| Ok(()))
+}
+
+/// Writes a framed StatusPing into `out` and returns the amount of bytes used.
+pub fn encode_ping(payload: i64, out: &mut [u8]) -> crate::io::Result<usize> {
+    write_frame(out, PING_ID, 8, |out| out.write_all(&payload.to_be_bytes()))
+}
+
+/// Borrows the JSON of a StatusResponse body.
+pub fn decode_status_response(mut body: &[u8]) -> crate::io::Result<&str> {
+    read_str(&mut body)
+}
+
+/// Writes a framed StatusResponse into `out` and returns the amount of bytes used.
+pub fn encode_status_response(json: &str, out: &mut [u8]) -> crate::io::Result<usize> {
+    write_frame(out, STATUS_RESPONSE_ID, var_int_len(json.len() as i32) + json.len(), |out| {
+        write_var_int(json.len() as i32, out)?;
+        out.write_all(json.as_bytes())
+    })
+}
+
+/// Reads the payload of a StatusPing or StatusPong body.
+pub fn decode_ping(mut body: &[u8]) -> crate::io::Result<i64> {
+    Ok(i64::from_be_bytes(read_array(&mut body)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_round_trip() {
+        let handshake = Handshake{ protocol_version: 756, host: "mc.example.com", port: 25565, next: 1 };
+        let mut out = [0u8; 64];
+        let len = handshake.encode(&mut out).unwrap();
+        let mut buffer = &out[..len];
+        let (id, body) = read_frame(&mut buffer).unwrap().unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(id, HANDSHAKE_ID);
+        assert_eq!(Handshake::decode(body).unwrap(), handshake);
+    }
+
+    #[test]
+    fn partial_frames_leave_the_buffer_alone() {
+        let mut out = [0u8; 32];
+        let len = encode_ping(0x0102_0304_0506_0708, &mut out).unwrap();
+        for end in 0..len {
+            let mut buffer = &out[..end];
+            assert!(read_frame(&mut buffer).unwrap().is_none());
+            assert_eq!(buffer.len(), end);
+        }
+        let mut buffer = &out[..len];
+        let (id, body) = read_frame(&mut buffer).unwrap().unwrap();
+        assert_eq!(id, PING_ID);
+        assert_eq!(decode_ping(body).unwrap(), 0x0102_0304_0506_0708);
+    }
+
+    #[test]
+    fn status_response_round_trip() {
+        let json = r#"{"description":{"text":"A Minecraft Server"}}"#;
+        let mut out = [0u8; 64];
+        let len = encode_status_response(json, &mut out).unwrap();
+        let mut buffer = &out[..len];
+        let (id, body) = read_frame(&mut buffer).unwrap().unwrap();
+        assert_eq!(id, STATUS_RESPONSE_ID);
+        assert_eq!(decode_status_response(body).unwrap(), json);
+        // Frames that do not fit are an error rather than cut off.
+        assert!(encode_status_response(json, &mut out[..len - 1]).is_err());
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        assert_eq!(read_frame(&mut &[0x00, 0x00][..]).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(read_str(&mut &[0x05, b'a'][..]).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(read_str(&mut &[0x01, 0xFF][..]).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(decode_ping(&[0; 7]).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn overlong_var_ints_are_rejected_in_canonical_mode() {
+        let overlong = [0x80, 0x00];
+        assert_eq!(read_var_int(&mut &overlong[..]).unwrap(), 0);
+        let error = crate::segment::implementation::var::canonical(|| read_var_int(&mut &overlong[..])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,131 @@
+//! "Open to LAN" discovery.
+//!
+//! Servers announce themselves by sending `[MOTD]motd[/MOTD][AD]port[/AD]` to
+//! the multicast group 224.0.2.60:4445 every 1.5 seconds. Clients listen on
+//! that group and connect to the address the announcement came from, using the
+//! announced port.
+use std::io::{Error, ErrorKind};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+pub const MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 2, 60);
+pub const MULTICAST_PORT: u16 = 4445;
+/// How often the vanilla server repeats its announcement.
+pub const INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Announcements are small, anything longer is cut off.
+const MAX_DATAGRAM_LEN: usize = 1024;
+
+/// A server found on the local network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanAnnouncement{
+    pub motd: String,
+    /// The address the announcement came from with the announced port.
+    pub address: SocketAddr
+}
+
+/// Formats an announcement.
+pub fn encode(motd: &str, port: u16) -> String {
+    format!("[MOTD]{}[/MOTD][AD]{}[/AD]", motd, port)
+}
+
+/// Parses an announcement into the MOTD and the port.
+pub fn decode(message: &str) -> Option<(&str, u16)> {
+    let motd = between(message, "[MOTD]", "[/MOTD]")?;
+    let port = between(message, "[AD]", "[/AD]")?.trim().parse().ok()?;
+    Some((motd, port))
+}
+
+fn between<'a>(message: &'a str, open: &str, close: &str) -> Option<&'a str> {
+    let start = message.find(open)? + open.len();
+    let len = message[start..].find(close)?;
+    Some(&message[start..start + len])
+}
+
+/// Announces a server to the local network.
+#[derive(Debug)]
+pub struct LanBroadcaster{
+    socket: UdpSocket,
+    message: String
+}
+
+impl LanBroadcaster{
+    pub fn new(motd: &str, port: u16) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.set_multicast_ttl_v4(1)?;
+        Ok(LanBroadcaster{ socket, message: encode(motd, port) })
+    }
+
+    pub fn set_motd(&mut self, motd: &str, port: u16) {
+        self.message = encode(motd, port);
+    }
+
+    /// Sends the announcement once.
+    pub fn announce(&self) -> std::io::Result<()> {
+        self.socket.send_to(self.message.as_bytes(), SocketAddrV4::new(MULTICAST_ADDR, MULTICAST_PORT))?;
+        Ok(())
+    }
+
+    /// Announces every `INTERVAL` until `stop` is set, meant to be run on its own thread.
+    pub fn run(&self, stop: &AtomicBool) -> std::io::Result<()> {
+        while !stop.load(Ordering::Relaxed) {
+            self.announce()?;
+            std::thread::sleep(INTERVAL);
+        }
+        Ok(())
+    }
+}
+
+/// Receives announcements from the local network.
+///
+/// The socket is non-blocking, so `poll` can be called from a game or UI loop
+/// without a thread of its own. `recv_timeout` waits instead.
+#[derive(Debug)]
+pub struct LanListener{
+    socket: UdpSocket,
+    buffer: Vec<u8>
+}
+
+impl LanListener{
+    pub fn bind() -> std::io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, MULTICAST_PORT))?;
+        socket.join_multicast_v4(&MULTICAST_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+        socket.set_nonblocking(true)?;
+        Ok(LanListener{ socket, buffer: vec![0; MAX_DATAGRAM_LEN] })
+    }
+
+    /// Returns the next announcement if one has arrived. Datagrams that are
+    /// not announcements are skipped.
+    pub fn poll(&mut self) -> std::io::Result<Option<LanAnnouncement>> {
+        loop {
+            let (len, source) = match self.socket.recv_from(&mut self.buffer) {
+                Ok(received) => received,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e)
+            };
+            let message = String::from_utf8_lossy(&self.buffer[..len]);
+            if let Some((motd, port)) = decode(&message) {
+                return Ok(Some(LanAnnouncement{
+                    motd: motd.to_string(),
+                    address: SocketAddr::new(source.ip(), port)
+                }));
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for an announcement.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> std::io::Result<LanAnnouncement> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(announcement) = self.poll()? {
+                return Ok(announcement);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::new(ErrorKind::TimedOut, "no LAN announcement received"));
+            }
+            std::thread::sleep(std::cmp::min(deadline - now, Duration::from_millis(50)));
+        }
+    }
+}
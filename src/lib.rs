@@ -1,3 +1,6 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
 #[allow(dead_code)]
 
 #[cfg(feature = "steven_protocol")]
@@ -6,7 +9,67 @@ extern crate steven_protocol;
 extern crate steven_shared;
 #[cfg(feature = "serde_json")]
 extern crate serde_json;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "flate2")]
+extern crate flate2;
+#[cfg(feature = "aes")]
+extern crate aes;
+#[cfg(feature = "hmac")]
+extern crate hmac;
+#[cfg(feature = "sha1")]
+extern crate sha1;
+#[cfg(feature = "sha2")]
+extern crate sha2;
+#[cfg(feature = "rsa")]
+extern crate rsa;
+#[cfg(feature = "bytes")]
+extern crate bytes;
+#[cfg(feature = "tokio-util")]
+extern crate tokio_util;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 
+pub mod io;
+#[cfg(feature = "std")]
 #[macro_use]
 pub mod protocol;
-pub mod segment;
\ No newline at end of file
+pub mod segment;
+pub mod prelude;
+#[cfg(feature = "std")]
+mod base64;
+#[cfg(feature = "std")]
+pub mod chat;
+#[cfg(feature = "std")]
+pub mod connection;
+#[cfg(feature = "std")]
+pub mod world;
+#[cfg(feature = "std")]
+pub mod test_vectors;
+#[cfg(feature = "std")]
+pub mod capture;
+#[cfg(feature = "std")]
+pub mod server;
+#[cfg(feature = "std")]
+pub mod proxy;
+#[cfg(feature = "std")]
+pub mod rcon;
+#[cfg(feature = "std")]
+pub mod lan;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod play;
+#[cfg(feature = "steven_shared")]
+pub mod client;
+#[cfg(feature = "test-utils")]
+pub mod testing;
+#[cfg(feature = "test-utils")]
+pub mod conformance;
+
+/// What the exported macros need from `alloc`, which crates without `std` may
+/// not have declared themselves.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::format;
+}
@@ -0,0 +1,322 @@
+//! Counters and histograms for running proxies and servers in production.
+//!
+//! With the `metrics` feature, `install` sets a `Metrics` implementation that
+//! framing and `Protocol::packet_by_id` report to. `Registry` keeps per-packet
+//! counters and byte and duration histograms, rendered in the Prometheus text
+//! format:
+//!
+//! ```ignore
+//! let registry = Arc::new(Registry::new());
+//! metrics::install(registry.clone())?;
+//! // serve `registry.render()` on /metrics
+//! ```
+//!
+//! Without the feature, or before anything is installed, the hooks do nothing.
+#[cfg(feature = "metrics")]
+use crate::protocol::trailing::TrailingBytes;
+#[cfg(feature = "metrics")]
+use crate::protocol::{Direction, State};
+#[cfg(feature = "metrics")]
+use std::collections::HashMap;
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "metrics")]
+use std::sync::{Arc, Mutex, OnceLock};
+#[cfg(feature = "metrics")]
+use std::time::{Duration, Instant};
+
+/// A packet read by `Protocol::packet_by_id`.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy)]
+pub struct Decoded{
+    pub protocol: &'static str,
+    pub state: State,
+    pub direction: Direction,
+    pub packet: &'static str,
+    /// Serialized length of the packet, without the packet id.
+    pub len: usize,
+    pub duration: Duration
+}
+
+/// Receives the events of every connection. Calls come from whichever thread
+/// is reading or writing, so implementations should be cheap.
+#[cfg(feature = "metrics")]
+pub trait Metrics: Send + Sync{
+    fn packet_decoded(&self, _packet: &Decoded) {}
+
+    /// A known packet id whose body could not be read.
+    fn decode_failed(&self, _protocol: &'static str, _state: State, _direction: Direction, _id: i32) {}
+
+    /// A packet that decoded with bytes left unread, under `TrailingBytesPolicy::Warn`.
+    fn trailing_bytes(&self, _protocol: &'static str, _trailing: &TrailingBytes) {}
+
+    /// Bytes of a frame read off the wire, including the length prefix.
+    fn bytes_read(&self, _bytes: usize) {}
+
+    /// Bytes of a frame written to the wire, including the length prefix.
+    fn bytes_written(&self, _bytes: usize) {}
+}
+
+#[cfg(feature = "metrics")]
+impl<M: Metrics + ?Sized> Metrics for Arc<M>{
+    fn packet_decoded(&self, packet: &Decoded) {
+        (**self).packet_decoded(packet)
+    }
+
+    fn decode_failed(&self, protocol: &'static str, state: State, direction: Direction, id: i32) {
+        (**self).decode_failed(protocol, state, direction, id)
+    }
+
+    fn trailing_bytes(&self, protocol: &'static str, trailing: &TrailingBytes) {
+        (**self).trailing_bytes(protocol, trailing)
+    }
+
+    fn bytes_read(&self, bytes: usize) {
+        (**self).bytes_read(bytes)
+    }
+
+    fn bytes_written(&self, bytes: usize) {
+        (**self).bytes_written(bytes)
+    }
+}
+
+#[cfg(feature = "metrics")]
+static INSTALLED: OnceLock<Box<dyn Metrics>> = OnceLock::new();
+
+/// Sets where events are reported to. This can be done once per process.
+#[cfg(feature = "metrics")]
+pub fn install<M: Metrics + 'static>(metrics: M) -> std::io::Result<()> {
+    INSTALLED.set(Box::new(metrics))
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::AlreadyExists, "metrics were installed already"))
+}
+
+#[cfg(feature = "metrics")]
+pub fn installed() -> Option<&'static dyn Metrics> {
+    INSTALLED.get().map(|metrics| &**metrics)
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn bytes_read(_bytes: usize) {
+    #[cfg(feature = "metrics")]
+    if let Some(metrics) = installed() {
+        metrics.bytes_read(_bytes);
+    }
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn bytes_written(_bytes: usize) {
+    #[cfg(feature = "metrics")]
+    if let Some(metrics) = installed() {
+        metrics.bytes_written(_bytes);
+    }
+}
+
+/// Times a single `packet_by_id`, used by `define_protocol!`.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Decoding{
+    #[cfg(feature = "metrics")]
+    started: Option<(Instant, &'static str, State, Direction, i32)>
+}
+
+impl Decoding{
+    #[inline]
+    pub fn start(_protocol: &'static str, _state: crate::protocol::State, _direction: crate::protocol::Direction, _id: i32) -> Self {
+        Decoding{
+            #[cfg(feature = "metrics")]
+            started: installed().map(|_| (Instant::now(), _protocol, _state, _direction, _id))
+        }
+    }
+
+    #[inline]
+    pub fn finish<P: crate::segment::Segment>(self, _name: &'static str, _packet: &P, _result: &std::io::Result<()>) {
+        #[cfg(feature = "metrics")]
+        if let (Some((started, protocol, state, direction, id)), Some(metrics)) = (self.started, installed()) {
+            match _result {
+                Ok(()) => metrics.packet_decoded(&Decoded{
+                    protocol,
+                    state,
+                    direction,
+                    packet: _name,
+                    len: _packet.serialized_len(),
+                    duration: started.elapsed()
+                }),
+                Err(_) => metrics.decode_failed(protocol, state, direction, id)
+            }
+        }
+    }
+}
+
+/// Upper bounds of the packet size histogram, in bytes.
+#[cfg(feature = "metrics")]
+const BYTE_BUCKETS: &[f64] = &[16.0, 64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0];
+
+/// Upper bounds of the decode duration histogram, in seconds.
+#[cfg(feature = "metrics")]
+const DURATION_BUCKETS: &[f64] = &[0.000_001, 0.000_005, 0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01];
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone)]
+struct Histogram{
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64
+}
+
+#[cfg(feature = "metrics")]
+impl Histogram{
+    fn new(bounds: &'static [f64]) -> Self {
+        Histogram{ bounds, counts: vec![0; bounds.len()], sum: 0.0, count: 0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        if let Some(bucket) = self.bounds.iter().position(|bound| value <= *bound) {
+            self.counts[bucket] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, name: &str, labels: &str, out: &mut String) {
+        use std::fmt::Write;
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bound, cumulative);
+        }
+        let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, self.count);
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone)]
+struct PacketStats{
+    bytes: Histogram,
+    duration: Histogram
+}
+
+#[cfg(feature = "metrics")]
+type PacketKey = (&'static str, State, Direction, &'static str);
+
+#[cfg(feature = "metrics")]
+type FailureKey = (&'static str, State, Direction, i32);
+
+/// Keeps what is reported to it and renders it for a Prometheus scrape.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+pub struct Registry{
+    packets: Mutex<HashMap<PacketKey, PacketStats>>,
+    failures: Mutex<HashMap<FailureKey, u64>>,
+    /// Packets that left bytes unread, see `Metrics::trailing_bytes`.
+    trailing: Mutex<HashMap<PacketKey, u64>>,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64
+}
+
+#[cfg(feature = "metrics")]
+impl Registry{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Amount of packets decoded with this name, of any protocol.
+    pub fn decoded(&self, packet: &str) -> u64 {
+        self.packets.lock().unwrap().iter()
+            .filter(|((_, _, _, name), _)| *name == packet)
+            .map(|(_, stats)| stats.bytes.count)
+            .sum()
+    }
+
+    pub fn total_bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    pub fn total_bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+
+    /// Everything collected so far, in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        use std::fmt::Write;
+        let mut out = String::new();
+        let mut packets: Vec<_> = self.packets.lock().unwrap().iter().map(|(key, stats)| (*key, stats.clone())).collect();
+        packets.sort_by_key(|((protocol, state, direction, name), _)| (*protocol, state.id(), direction.id(), *name));
+        let label = |(protocol, state, direction, name): PacketKey| {
+            format!("protocol=\"{}\",state=\"{:?}\",direction=\"{:?}\",packet=\"{}\"", protocol, state, direction, name)
+        };
+
+        out.push_str("# HELP mc_protocol_packets_decoded_total Packets decoded by packet_by_id.\n");
+        out.push_str("# TYPE mc_protocol_packets_decoded_total counter\n");
+        for (key, stats) in &packets {
+            let _ = writeln!(out, "mc_protocol_packets_decoded_total{{{}}} {}", label(*key), stats.bytes.count);
+        }
+
+        out.push_str("# HELP mc_protocol_packet_bytes Serialized size of decoded packets.\n");
+        out.push_str("# TYPE mc_protocol_packet_bytes histogram\n");
+        for (key, stats) in &packets {
+            stats.bytes.render("mc_protocol_packet_bytes", &label(*key), &mut out);
+        }
+
+        out.push_str("# HELP mc_protocol_decode_seconds Time spent decoding packets.\n");
+        out.push_str("# TYPE mc_protocol_decode_seconds histogram\n");
+        for (key, stats) in &packets {
+            stats.duration.render("mc_protocol_decode_seconds", &label(*key), &mut out);
+        }
+
+        let mut failures: Vec<_> = self.failures.lock().unwrap().iter().map(|(key, count)| (*key, *count)).collect();
+        failures.sort_by_key(|((protocol, state, direction, id), _)| (*protocol, state.id(), direction.id(), *id));
+        out.push_str("# HELP mc_protocol_decode_failures_total Known packets whose body could not be read.\n");
+        out.push_str("# TYPE mc_protocol_decode_failures_total counter\n");
+        for ((protocol, state, direction, id), count) in failures {
+            let _ = writeln!(out, "mc_protocol_decode_failures_total{{protocol=\"{}\",state=\"{:?}\",direction=\"{:?}\",id=\"{:#04x}\"}} {}", protocol, state, direction, id, count);
+        }
+
+        let mut trailing: Vec<_> = self.trailing.lock().unwrap().iter().map(|(key, count)| (*key, *count)).collect();
+        trailing.sort_by_key(|((protocol, state, direction, name), _)| (*protocol, state.id(), direction.id(), *name));
+        out.push_str("# HELP mc_protocol_trailing_bytes_total Packets that decoded with bytes left unread.\n");
+        out.push_str("# TYPE mc_protocol_trailing_bytes_total counter\n");
+        for (key, count) in trailing {
+            let _ = writeln!(out, "mc_protocol_trailing_bytes_total{{{}}} {}", label(key), count);
+        }
+
+        out.push_str("# HELP mc_protocol_read_bytes_total Bytes of frames read.\n");
+        out.push_str("# TYPE mc_protocol_read_bytes_total counter\n");
+        let _ = writeln!(out, "mc_protocol_read_bytes_total {}", self.total_bytes_read());
+        out.push_str("# HELP mc_protocol_written_bytes_total Bytes of frames written.\n");
+        out.push_str("# TYPE mc_protocol_written_bytes_total counter\n");
+        let _ = writeln!(out, "mc_protocol_written_bytes_total {}", self.total_bytes_written());
+        out
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Metrics for Registry{
+    fn packet_decoded(&self, packet: &Decoded) {
+        let mut packets = self.packets.lock().unwrap();
+        let stats = packets.entry((packet.protocol, packet.state, packet.direction, packet.packet))
+            .or_insert_with(|| PacketStats{ bytes: Histogram::new(BYTE_BUCKETS), duration: Histogram::new(DURATION_BUCKETS) });
+        stats.bytes.observe(packet.len as f64);
+        stats.duration.observe(packet.duration.as_secs_f64());
+    }
+
+    fn decode_failed(&self, protocol: &'static str, state: State, direction: Direction, id: i32) {
+        *self.failures.lock().unwrap().entry((protocol, state, direction, id)).or_insert(0) += 1;
+    }
+
+    fn trailing_bytes(&self, protocol: &'static str, trailing: &TrailingBytes) {
+        *self.trailing.lock().unwrap().entry((protocol, trailing.state, trailing.direction, trailing.packet)).or_insert(0) += 1;
+    }
+
+    fn bytes_read(&self, bytes: usize) {
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn bytes_written(&self, bytes: usize) {
+        self.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
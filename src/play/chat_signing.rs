@@ -0,0 +1,667 @@
+//! Signed chat as done since 1.19.3.
+//!
+//! Each player chat message is signed by the sender with the key of its chat
+//! session, which the client announces with PlayerSession. The signature
+//! covers the message, a link to the previous message of the same session and
+//! the signatures of the last messages the sender saw, which it acknowledges
+//! with a `LastSeenUpdate` of the 20 most recent messages it received.
+//!
+//! A server checks incoming messages with a `SignedMessageChain` per session
+//! and the acknowledgements with a `LastSeenValidator`, clients keep track
+//! with a `LastSeenTracker`. Clients that are kicked for invalid chat usually
+//! got an acknowledgement the server did not expect.
+//!
+//! The signed data is `[i32 1][UUID sender][UUID session][i32 index]`
+//! followed by the body `[i64 salt][i64 timestamp in seconds][i32 length][message][i32 count][signatures]`,
+//! signed with SHA256withRSA.
+use crate::chat::Component;
+use crate::io::borrowed::{read_str, read_var_int};
+use crate::segment::implementation::var::write_var_int;
+use crate::segment::Segment;
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+
+pub use crate::segment::implementation::mojang::profile_key::ProfilePublicKey;
+
+pub const SIGNATURE_LEN: usize = 256;
+
+/// Amount of messages a client acknowledges at most.
+pub const LAST_SEEN_WINDOW: usize = 20;
+
+/// Bytes of the acknowledged bit set of a `LastSeenUpdate`.
+const ACKNOWLEDGED_LEN: usize = LAST_SEEN_WINDOW.div_ceil(8);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageSignature(pub [u8; SIGNATURE_LEN]);
+
+impl std::fmt::Debug for MessageSignature{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MessageSignature(")?;
+        for byte in &self.0[..8] {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, "..)")
+    }
+}
+
+impl MessageSignature{
+    pub fn from_slice(bytes: &[u8]) -> std::io::Result<Self> {
+        let mut signature = [0; SIGNATURE_LEN];
+        if bytes.len() != SIGNATURE_LEN {
+            return Err(Error::new(ErrorKind::InvalidData, format!("message signature of {} bytes rather than {}", bytes.len(), SIGNATURE_LEN)));
+        }
+        signature.copy_from_slice(bytes);
+        Ok(MessageSignature(signature))
+    }
+
+    pub fn decode(buffer: &mut &[u8]) -> std::io::Result<Self> {
+        if buffer.len() < SIGNATURE_LEN {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "message signature is truncated"));
+        }
+        let signature = MessageSignature::from_slice(&buffer[..SIGNATURE_LEN])?;
+        *buffer = &buffer[SIGNATURE_LEN..];
+        Ok(signature)
+    }
+}
+
+/// Where a message sits in the chain of its chat session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageLink{
+    pub sender: u128,
+    pub session_id: u128,
+    /// Counts up from 0 for every message sent in the session.
+    pub index: i32
+}
+
+/// The signed part of a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageBody{
+    pub content: String,
+    /// Milliseconds since the Unix epoch, the signature only covers the seconds.
+    pub timestamp: i64,
+    pub salt: i64,
+    /// Signatures of the messages the sender acknowledged with this message.
+    pub last_seen: Vec<MessageSignature>
+}
+
+/// The bytes that are signed for a message.
+pub fn signed_data(link: &MessageLink, body: &MessageBody) -> Vec<u8> {
+    let mut data = Vec::with_capacity(64 + body.content.len() + body.last_seen.len() * SIGNATURE_LEN);
+    data.extend_from_slice(&1i32.to_be_bytes());
+    data.extend_from_slice(&link.sender.to_be_bytes());
+    data.extend_from_slice(&link.session_id.to_be_bytes());
+    data.extend_from_slice(&link.index.to_be_bytes());
+    data.extend_from_slice(&body.salt.to_be_bytes());
+    data.extend_from_slice(&body.timestamp.div_euclid(1000).to_be_bytes());
+    data.extend_from_slice(&(body.content.len() as i32).to_be_bytes());
+    data.extend_from_slice(body.content.as_bytes());
+    data.extend_from_slice(&(body.last_seen.len() as i32).to_be_bytes());
+    for signature in &body.last_seen {
+        data.extend_from_slice(&signature.0);
+    }
+    data
+}
+
+/// Signs a message the way the client does, e.g. for bots.
+pub fn sign(key: &RsaPrivateKey, link: &MessageLink, body: &MessageBody) -> std::io::Result<MessageSignature> {
+    let hashed = Sha256::digest(signed_data(link, body));
+    let signature = key.sign(Pkcs1v15Sign::new::<Sha256>(), &hashed)
+        .map_err(|e| Error::other(format!("could not sign message: {}", e)))?;
+    MessageSignature::from_slice(&signature)
+}
+
+pub fn verify(key: &RsaPublicKey, link: &MessageLink, body: &MessageBody, signature: &MessageSignature) -> bool {
+    let hashed = Sha256::digest(signed_data(link, body));
+    key.verify(Pkcs1v15Sign::new::<Sha256>(), &hashed, &signature.0).is_ok()
+}
+
+/// The body of PlayerSession, the chat session a client starts after login.
+/// Check its key with `ProfilePublicKey::verify` before trusting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerSession{
+    pub session_id: u128,
+    pub public_key: ProfilePublicKey
+}
+
+impl PlayerSession{
+    pub fn decode(buffer: &mut &[u8]) -> std::io::Result<Self> {
+        let session_id = read_uuid(buffer)?;
+        let mut public_key = ProfilePublicKey::default();
+        public_key.read_from_stream(buffer)?;
+        Ok(PlayerSession{ session_id, public_key })
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.session_id.to_be_bytes());
+        let _ = self.public_key.write_to_stream(out);
+    }
+}
+
+/// Checks the messages of one chat session, in the order they arrive.
+///
+/// Once a message fails the check the chain is broken and every later message
+/// of the session is refused, as the vanilla server does.
+#[derive(Debug)]
+pub struct SignedMessageChain{
+    sender: u128,
+    session_id: u128,
+    expires_at: i64,
+    key: RsaPublicKey,
+    next_index: Option<i32>,
+    last_timestamp: i64
+}
+
+impl SignedMessageChain{
+    pub fn new(sender: u128, session: &PlayerSession) -> std::io::Result<Self> {
+        Ok(SignedMessageChain{
+            sender,
+            session_id: session.session_id,
+            expires_at: session.public_key.expires_at,
+            key: session.public_key.rsa_key()?,
+            next_index: Some(0),
+            last_timestamp: i64::MIN
+        })
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.next_index.is_none()
+    }
+
+    /// Checks the message with `index`, `now` being milliseconds since the
+    /// Unix epoch. Returns the link the message was signed with.
+    pub fn validate(&mut self, index: i32, body: &MessageBody, signature: &MessageSignature, now: i64) -> std::io::Result<MessageLink> {
+        let next_index = self.next_index
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "chat chain is broken"))?;
+        let result = self.check(next_index, index, body, signature, now);
+        match &result {
+            Ok(link) => {
+                // Nothing can follow the last index, so the chain ends there.
+                self.next_index = link.index.checked_add(1);
+                self.last_timestamp = body.timestamp;
+            }
+            Err(_) => self.next_index = None
+        }
+        result
+    }
+
+    fn check(&self, next_index: i32, index: i32, body: &MessageBody, signature: &MessageSignature, now: i64) -> std::io::Result<MessageLink> {
+        if now > self.expires_at {
+            return Err(Error::new(ErrorKind::PermissionDenied, "chat session key expired"));
+        }
+        if index < next_index || body.timestamp < self.last_timestamp {
+            return Err(Error::new(ErrorKind::InvalidData, "out of order chat message"));
+        }
+        if body.last_seen.len() > LAST_SEEN_WINDOW {
+            return Err(Error::new(ErrorKind::InvalidData, format!("chat message acknowledges {} messages, more than {}", body.last_seen.len(), LAST_SEEN_WINDOW)));
+        }
+        let link = MessageLink{ sender: self.sender, session_id: self.session_id, index };
+        if !verify(&self.key, &link, body, signature) {
+            return Err(Error::new(ErrorKind::InvalidData, "invalid chat message signature"));
+        }
+        Ok(link)
+    }
+}
+
+/// What a client acknowledges with a chat message or MessageAcknowledgment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LastSeenUpdate{
+    /// Messages received since the previous update.
+    pub offset: i32,
+    /// Bit `i` is set when the `i`th message of the window was seen.
+    pub acknowledged: [u8; ACKNOWLEDGED_LEN]
+}
+
+impl LastSeenUpdate{
+    pub fn is_acknowledged(&self, i: usize) -> bool {
+        self.acknowledged[i / 8] & (1 << (i % 8)) != 0
+    }
+
+    pub fn set_acknowledged(&mut self, i: usize) {
+        self.acknowledged[i / 8] |= 1 << (i % 8);
+    }
+
+    pub fn decode(buffer: &mut &[u8]) -> std::io::Result<Self> {
+        let offset = read_var_int(buffer)?;
+        if buffer.len() < ACKNOWLEDGED_LEN {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "last seen update is truncated"));
+        }
+        let mut acknowledged = [0; ACKNOWLEDGED_LEN];
+        acknowledged.copy_from_slice(&buffer[..ACKNOWLEDGED_LEN]);
+        *buffer = &buffer[ACKNOWLEDGED_LEN..];
+        Ok(LastSeenUpdate{ offset, acknowledged })
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let _ = write_var_int(self.offset, out);
+        out.extend_from_slice(&self.acknowledged);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TrackedMessage{
+    signature: MessageSignature,
+    pending: bool
+}
+
+/// The server side of acknowledgements: remembers the signed messages sent
+/// to a client and checks the updates it answers with.
+#[derive(Debug, Clone)]
+pub struct LastSeenValidator{
+    tracked: Vec<Option<TrackedMessage>>,
+    last_pending: Option<MessageSignature>
+}
+
+impl Default for LastSeenValidator{
+    fn default() -> Self {
+        LastSeenValidator{ tracked: vec![None; LAST_SEEN_WINDOW], last_pending: None }
+    }
+}
+
+impl LastSeenValidator{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Call for every signed message sent to the client.
+    pub fn add_pending(&mut self, signature: MessageSignature) {
+        if self.last_pending != Some(signature) {
+            self.tracked.push(Some(TrackedMessage{ signature, pending: true }));
+            self.last_pending = Some(signature);
+        }
+    }
+
+    /// Messages sent to the client that it did not acknowledge or ignore yet.
+    pub fn pending(&self) -> usize {
+        self.tracked.len() - LAST_SEEN_WINDOW
+    }
+
+    /// Applies the update of a client, returning the signatures it says it
+    /// saw. These have to match `MessageBody::last_seen` of its message.
+    pub fn apply_update(&mut self, update: &LastSeenUpdate) -> std::io::Result<Vec<MessageSignature>> {
+        let offset = usize::try_from(update.offset).ok()
+            .filter(|offset| *offset <= self.pending())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("last seen offset {} is out of bounds", update.offset)))?;
+        self.tracked.drain(..offset);
+        let mut last_seen = Vec::new();
+        for i in 0..LAST_SEEN_WINDOW {
+            let tracked = &mut self.tracked[i];
+            if update.is_acknowledged(i) {
+                let message = tracked.as_mut()
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "last seen update acknowledged an unknown or ignored message"))?;
+                message.pending = false;
+                last_seen.push(message.signature);
+            }else{
+                if tracked.is_some_and(|message| !message.pending) {
+                    return Err(Error::new(ErrorKind::InvalidData, "last seen update ignored a previously acknowledged message"));
+                }
+                *tracked = None;
+            }
+        }
+        Ok(last_seen)
+    }
+}
+
+/// The client side of acknowledgements: collects the signatures of received
+/// messages and produces the update to send with the next message.
+#[derive(Debug, Clone)]
+pub struct LastSeenTracker{
+    tracked: [Option<TrackedMessage>; LAST_SEEN_WINDOW],
+    tail: usize,
+    offset: i32,
+    last_tracked: Option<MessageSignature>
+}
+
+impl Default for LastSeenTracker{
+    fn default() -> Self {
+        LastSeenTracker{ tracked: [None; LAST_SEEN_WINDOW], tail: 0, offset: 0, last_tracked: None }
+    }
+}
+
+impl LastSeenTracker{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Call for every signed message received. `shown` is false when the
+    /// message was hidden, e.g. from a blocked player.
+    pub fn add_pending(&mut self, signature: MessageSignature, shown: bool) -> bool {
+        if self.last_tracked == Some(signature) {
+            return false;
+        }
+        self.last_tracked = Some(signature);
+        self.tracked[self.tail] = if shown { Some(TrackedMessage{ signature, pending: true }) } else { None };
+        self.tail = (self.tail + 1) % LAST_SEEN_WINDOW;
+        self.offset += 1;
+        true
+    }
+
+    /// Messages received since the last update, sent as MessageAcknowledgment
+    /// when it grows large without the player chatting.
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    /// The signatures to sign the next message with and the update to send along.
+    pub fn update(&mut self) -> (Vec<MessageSignature>, LastSeenUpdate) {
+        let mut update = LastSeenUpdate{ offset: std::mem::take(&mut self.offset), ..Default::default() };
+        let mut last_seen = Vec::new();
+        for i in 0..LAST_SEEN_WINDOW {
+            if let Some(message) = &mut self.tracked[(self.tail + i) % LAST_SEEN_WINDOW] {
+                update.set_acknowledged(i);
+                last_seen.push(message.signature);
+                message.pending = false;
+            }
+        }
+        (last_seen, update)
+    }
+}
+
+/// A message acknowledged in PlayerChat, by its id in the client's
+/// signature cache or in full.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviousMessage{
+    Cached(i32),
+    Full(Box<MessageSignature>)
+}
+
+/// Which parts of a message the server filtered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterMask{
+    PassThrough,
+    FullyFiltered,
+    /// Bit set of the filtered characters.
+    PartiallyFiltered(Vec<u64>)
+}
+
+/// Builder of the body of PlayerChat, laid out as in 1.19.3 up to 1.20.2
+/// where chat components are sent as JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerChatMessage{
+    pub sender: u128,
+    pub index: i32,
+    pub signature: Option<MessageSignature>,
+    pub content: String,
+    pub timestamp: i64,
+    pub salt: i64,
+    pub previous: Vec<PreviousMessage>,
+    /// Shown instead of `content`, e.g. after the server decorated it.
+    pub unsigned_content: Option<Component>,
+    pub filter: FilterMask,
+    /// Id of the chat type in the registry sent with JoinGame.
+    pub chat_type: i32,
+    pub sender_name: Component,
+    pub target_name: Option<Component>
+}
+
+impl PlayerChatMessage{
+    /// An unsigned message of chat type 0, `minecraft:chat`.
+    pub fn new(sender: u128, sender_name: impl Into<Component>, content: impl Into<String>, timestamp: i64) -> Self {
+        PlayerChatMessage{
+            sender,
+            index: 0,
+            signature: None,
+            content: content.into(),
+            timestamp,
+            salt: 0,
+            previous: Vec::new(),
+            unsigned_content: None,
+            filter: FilterMask::PassThrough,
+            chat_type: 0,
+            sender_name: sender_name.into(),
+            target_name: None
+        }
+    }
+
+    /// Forwards a message checked by `SignedMessageChain`. The last seen
+    /// signatures are sent in full.
+    pub fn signed(link: &MessageLink, body: &MessageBody, signature: MessageSignature, sender_name: impl Into<Component>) -> Self {
+        PlayerChatMessage{
+            index: link.index,
+            signature: Some(signature),
+            salt: body.salt,
+            previous: body.last_seen.iter().map(|signature| PreviousMessage::Full(Box::new(*signature))).collect(),
+            ..PlayerChatMessage::new(link.sender, sender_name, body.content.clone(), body.timestamp)
+        }
+    }
+
+    pub fn unsigned_content(mut self, content: impl Into<Component>) -> Self {
+        self.unsigned_content = Some(content.into());
+        self
+    }
+
+    pub fn chat_type(mut self, chat_type: i32) -> Self {
+        self.chat_type = chat_type;
+        self
+    }
+
+    pub fn target_name(mut self, target_name: impl Into<Component>) -> Self {
+        self.target_name = Some(target_name.into());
+        self
+    }
+
+    pub fn filter(mut self, filter: FilterMask) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.sender.to_be_bytes());
+        let _ = write_var_int(self.index, out);
+        out.push(self.signature.is_some() as u8);
+        if let Some(signature) = &self.signature {
+            out.extend_from_slice(&signature.0);
+        }
+        write_str(&self.content, out);
+        out.extend_from_slice(&self.timestamp.to_be_bytes());
+        out.extend_from_slice(&self.salt.to_be_bytes());
+        let _ = write_var_int(self.previous.len() as i32, out);
+        for previous in &self.previous {
+            match previous {
+                PreviousMessage::Cached(id) => {
+                    let _ = write_var_int(id + 1, out);
+                }
+                PreviousMessage::Full(signature) => {
+                    let _ = write_var_int(0, out);
+                    out.extend_from_slice(&signature.0);
+                }
+            }
+        }
+        write_optional_component(self.unsigned_content.as_ref(), out);
+        match &self.filter {
+            FilterMask::PassThrough => {
+                let _ = write_var_int(0, out);
+            }
+            FilterMask::FullyFiltered => {
+                let _ = write_var_int(1, out);
+            }
+            FilterMask::PartiallyFiltered(bits) => {
+                let _ = write_var_int(2, out);
+                let _ = write_var_int(bits.len() as i32, out);
+                for word in bits {
+                    out.extend_from_slice(&word.to_be_bytes());
+                }
+            }
+        }
+        let _ = write_var_int(self.chat_type, out);
+        write_str(&self.sender_name.to_json(), out);
+        write_optional_component(self.target_name.as_ref(), out);
+    }
+}
+
+fn write_optional_component(component: Option<&Component>, out: &mut Vec<u8>) {
+    out.push(component.is_some() as u8);
+    if let Some(component) = component {
+        write_str(&component.to_json(), out);
+    }
+}
+
+fn write_str(value: &str, out: &mut Vec<u8>) {
+    let _ = write_var_int(value.len() as i32, out);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn read_i64(buffer: &mut &[u8]) -> std::io::Result<i64> {
+    if buffer.len() < 8 {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "chat session is truncated"));
+    }
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&buffer[..8]);
+    *buffer = &buffer[8..];
+    Ok(i64::from_be_bytes(bytes))
+}
+
+fn read_uuid(buffer: &mut &[u8]) -> std::io::Result<u128> {
+    if buffer.len() < 16 {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "chat session is truncated"));
+    }
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&buffer[..16]);
+    *buffer = &buffer[16..];
+    Ok(u128::from_be_bytes(bytes))
+}
+
+/// The body of the serverbound ChatMessage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage{
+    pub content: String,
+    pub timestamp: i64,
+    pub salt: i64,
+    pub signature: Option<MessageSignature>,
+    pub last_seen: LastSeenUpdate
+}
+
+impl ChatMessage{
+    pub fn decode(buffer: &mut &[u8]) -> std::io::Result<Self> {
+        let content = read_str(buffer)?.to_string();
+        let timestamp = read_i64(buffer)?;
+        let salt = read_i64(buffer)?;
+        let signed = buffer.first().copied()
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "chat message is truncated"))?;
+        *buffer = &buffer[1..];
+        let signature = if signed != 0 { Some(MessageSignature::decode(buffer)?) } else { None };
+        Ok(ChatMessage{ content, timestamp, salt, signature, last_seen: LastSeenUpdate::decode(buffer)? })
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        write_str(&self.content, out);
+        out.extend_from_slice(&self.timestamp.to_be_bytes());
+        out.extend_from_slice(&self.salt.to_be_bytes());
+        out.push(self.signature.is_some() as u8);
+        if let Some(signature) = &self.signature {
+            out.extend_from_slice(&signature.0);
+        }
+        self.last_seen.encode(out);
+    }
+
+    /// The signed body, with the signatures `LastSeenValidator::apply_update`
+    /// returned for `last_seen`.
+    pub fn body(&self, last_seen: Vec<MessageSignature>) -> MessageBody {
+        MessageBody{ content: self.content.clone(), timestamp: self.timestamp, salt: self.salt, last_seen }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::pkcs8::DecodePrivateKey;
+
+    /// A chat session and two serverbound ChatMessage bodies of it, signed with
+    /// an independent SHA256withRSA implementation over vanilla's layout. The
+    /// second message acknowledges the first.
+    const PRIVATE_KEY: &[u8] = include_bytes!("../../fixtures/chat_signing/private_key.der");
+    const PLAYER_SESSION: &[u8] = include_bytes!("../../fixtures/chat_signing/player_session.bin");
+    const CHAT_MESSAGES: [&[u8]; 2] = [
+        include_bytes!("../../fixtures/chat_signing/chat_message_0.bin"),
+        include_bytes!("../../fixtures/chat_signing/chat_message_1.bin")
+    ];
+    const SIGNED_DATA: [&[u8]; 2] = [
+        include_bytes!("../../fixtures/chat_signing/signed_data_0.bin"),
+        include_bytes!("../../fixtures/chat_signing/signed_data_1.bin")
+    ];
+    const SENDER: u128 = 0x069a79f4_44e9_4726_a5be_fca90e38aaf5;
+    const NOW: i64 = 1_700_000_010_000;
+
+    fn decode_all<T>(bytes: &[u8], decode: fn(&mut &[u8]) -> std::io::Result<T>) -> T {
+        let mut buffer = bytes;
+        let value = decode(&mut buffer).unwrap();
+        assert!(buffer.is_empty(), "{} bytes were not read", buffer.len());
+        value
+    }
+
+    #[test]
+    fn known_answer() {
+        let session = decode_all(PLAYER_SESSION, PlayerSession::decode);
+        assert_eq!(session.session_id, 0x5f4dcc3b_5aa7_65d6_1d83_27deb882cf99);
+        assert_eq!(session.public_key.expires_at, 1_735_689_600_000);
+        let mut chain = SignedMessageChain::new(SENDER, &session).unwrap();
+        let mut validator = LastSeenValidator::new();
+
+        let first = decode_all(CHAT_MESSAGES[0], ChatMessage::decode);
+        assert_eq!((first.content.as_str(), first.timestamp, first.salt), ("Hello, world!", 1_700_000_000_123, 0x0123_4567_89ab_cdef));
+        let body = first.body(validator.apply_update(&first.last_seen).unwrap());
+        let signature = first.signature.unwrap();
+        let link = chain.validate(0, &body, &signature, NOW).unwrap();
+        assert_eq!(signed_data(&link, &body), SIGNED_DATA[0]);
+        validator.add_pending(signature);
+
+        let second = decode_all(CHAT_MESSAGES[1], ChatMessage::decode);
+        assert_eq!(second.content, "Ça va?");
+        let body = second.body(validator.apply_update(&second.last_seen).unwrap());
+        assert_eq!(body.last_seen, vec![signature]);
+        let link = chain.validate(1, &body, &second.signature.unwrap(), NOW).unwrap();
+        assert_eq!(signed_data(&link, &body), SIGNED_DATA[1]);
+        assert!(!chain.is_broken());
+    }
+
+    #[test]
+    fn signing_reproduces_the_vector() {
+        let key = RsaPrivateKey::from_pkcs8_der(PRIVATE_KEY).unwrap();
+        let session = decode_all(PLAYER_SESSION, PlayerSession::decode);
+        let message = decode_all(CHAT_MESSAGES[0], ChatMessage::decode);
+        let link = MessageLink{ sender: SENDER, session_id: session.session_id, index: 0 };
+        let body = message.body(Vec::new());
+        assert_eq!(sign(&key, &link, &body).unwrap(), message.signature.unwrap());
+        let mut encoded = Vec::new();
+        message.encode(&mut encoded);
+        assert_eq!(encoded, CHAT_MESSAGES[0]);
+    }
+
+    #[test]
+    fn tampered_messages_break_the_chain() {
+        let session = decode_all(PLAYER_SESSION, PlayerSession::decode);
+        let mut chain = SignedMessageChain::new(SENDER, &session).unwrap();
+        let message = decode_all(CHAT_MESSAGES[0], ChatMessage::decode);
+        let mut body = message.body(Vec::new());
+        body.content.push('!');
+        assert_eq!(chain.validate(0, &body, &message.signature.unwrap(), NOW).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(chain.is_broken());
+        let body = message.body(Vec::new());
+        assert!(chain.validate(0, &body, &message.signature.unwrap(), NOW).is_err());
+    }
+
+    #[test]
+    fn the_chain_ends_at_the_last_index() {
+        let key = RsaPrivateKey::from_pkcs8_der(PRIVATE_KEY).unwrap();
+        let session = decode_all(PLAYER_SESSION, PlayerSession::decode);
+        let mut chain = SignedMessageChain::new(SENDER, &session).unwrap();
+        let message = decode_all(CHAT_MESSAGES[0], ChatMessage::decode);
+        let body = message.body(Vec::new());
+        let last = MessageLink{ sender: SENDER, session_id: session.session_id, index: i32::MAX };
+        chain.validate(i32::MAX, &body, &sign(&key, &last, &body).unwrap(), NOW).unwrap();
+        // A replay of an earlier message must not wrap around to be accepted.
+        assert!(chain.validate(0, &body, &message.signature.unwrap(), NOW).is_err());
+        assert!(chain.is_broken());
+    }
+
+    #[test]
+    fn expired_sessions_are_refused() {
+        let session = decode_all(PLAYER_SESSION, PlayerSession::decode);
+        let mut chain = SignedMessageChain::new(SENDER, &session).unwrap();
+        let message = decode_all(CHAT_MESSAGES[0], ChatMessage::decode);
+        let expired = session.public_key.expires_at + 1;
+        let error = chain.validate(0, &message.body(Vec::new()), &message.signature.unwrap(), expired).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+    }
+}
@@ -0,0 +1,102 @@
+//! Cookies of 1.20.5 and later: small payloads a server stores on the client
+//! with StoreCookie and reads back with CookieRequest, e.g. to hand a session
+//! over to the server it transfers the client to.
+//!
+//! The client keeps its cookies across transfers and drops them when it
+//! disconnects otherwise, so one store belongs to one session, not to one
+//! connection.
+use crate::segment::implementation::mojang::identifier::Identifier;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+/// The largest payload vanilla clients store or send back.
+pub const MAX_COOKIE_LEN: usize = 5120;
+
+/// The cookies of a client, as kept by the client or by a proxy in its place.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieStore{
+    cookies: HashMap<Identifier, Vec<u8>>
+}
+
+impl CookieStore{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores a cookie, replacing the one under the same key. Payloads over
+    /// `MAX_COOKIE_LEN` are refused like vanilla clients refuse them.
+    pub fn store(&mut self, key: Identifier, payload: Vec<u8>) -> std::io::Result<()> {
+        if payload.len() > MAX_COOKIE_LEN {
+            return Err(Error::new(ErrorKind::InvalidData, format!("cookie {} of {} bytes exceeds {} bytes", key, payload.len(), MAX_COOKIE_LEN)));
+        }
+        self.cookies.insert(key, payload);
+        Ok(())
+    }
+
+    pub fn get(&self, key: &Identifier) -> Option<&[u8]> {
+        self.cookies.get(key).map(Vec::as_slice)
+    }
+
+    pub fn remove(&mut self, key: &Identifier) -> Option<Vec<u8>> {
+        self.cookies.remove(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Identifier> {
+        self.cookies.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_21{
+    use super::CookieStore;
+    use crate::protocol::implementation::steven::v1_21::{ConfigurationCookieResponse, CookieResponse, LoginCookieResponse, Proto_1_21};
+    use crate::segment::implementation::mojang::identifier::Identifier;
+    use steven_protocol::protocol::{LenPrefixedBytes, VarInt};
+
+    impl CookieStore{
+        /// Stores the cookies of StoreCookie and answers CookieRequest in
+        /// every state, returning the answer to send. Other packets are ignored.
+        pub fn handle(&mut self, packet: &Proto_1_21) -> std::io::Result<Option<Proto_1_21>> {
+            Ok(match packet {
+                Proto_1_21::ConfigurationStoreCookie(cookie) => {
+                    self.store(cookie.key.clone(), cookie.payload.data.clone())?;
+                    None
+                }
+                Proto_1_21::StoreCookie(cookie) => {
+                    self.store(cookie.key.clone(), cookie.payload.data.clone())?;
+                    None
+                }
+                Proto_1_21::LoginCookieRequest(request) => {
+                    let (key, has_payload, payload) = self.answer(&request.key);
+                    Some(Proto_1_21::LoginCookieResponse(Box::new(LoginCookieResponse{ key, has_payload, payload })))
+                }
+                Proto_1_21::ConfigurationCookieRequest(request) => {
+                    let (key, has_payload, payload) = self.answer(&request.key);
+                    Some(Proto_1_21::ConfigurationCookieResponse(Box::new(ConfigurationCookieResponse{ key, has_payload, payload })))
+                }
+                Proto_1_21::CookieRequest(request) => {
+                    let (key, has_payload, payload) = self.answer(&request.key);
+                    Some(Proto_1_21::CookieResponse(Box::new(CookieResponse{ key, has_payload, payload })))
+                }
+                _ => None
+            })
+        }
+
+        fn answer(&self, key: &Identifier) -> (Identifier, bool, LenPrefixedBytes<VarInt>) {
+            let payload = self.get(key);
+            (key.clone(), payload.is_some(), LenPrefixedBytes::new(payload.unwrap_or_default().to_vec()))
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//! Tracks the entities a client can see, from their spawn packet up to
+//! EntityDestroy.
+//!
+//! Relative moves are applied on top of the last known position, and the
+//! position a client renders is interpolated from the previous one over a few
+//! ticks like the vanilla client does. Metadata is kept per index as the raw
+//! `[VarInt type][value]` bytes of the latest update.
+use crate::segment::implementation::nbt::{copy_named_tag, MAX_DEPTH};
+use crate::segment::implementation::var::read_var_int;
+use byteorder::ReadBytesExt;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Error, ErrorKind, Read};
+use std::time::{Duration, Instant};
+
+/// The vanilla client moves entities to a new position over three ticks.
+pub const INTERPOLATION: Duration = Duration::from_millis(150);
+
+const METADATA_END: u8 = 0xff;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Vec3{
+    pub x: f64,
+    pub y: f64,
+    pub z: f64
+}
+
+impl Vec3{
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Vec3{ x, y, z }
+    }
+
+    fn lerp(self, to: Vec3, t: f64) -> Vec3 {
+        Vec3{
+            x: self.x + (to.x - self.x) * t,
+            y: self.y + (to.y - self.y) * t,
+            z: self.z + (to.z - self.z) * t
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind{
+    /// Non living entities like items, arrows and minecarts, with the data
+    /// field of SpawnObject.
+    Object{ ty: i32, data: i32 },
+    Mob{ ty: i32 },
+    Player,
+    ExperienceOrb{ count: i16 },
+    Painting{ motive: i32, direction: u8 }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackedEntity{
+    pub id: i32,
+    /// Zero for experience orbs, which are spawned without one.
+    pub uuid: u128,
+    pub kind: EntityKind,
+    pub position: Vec3,
+    /// Where the entity was before the last move, for interpolation.
+    pub previous_position: Vec3,
+    pub moved_at: Instant,
+    /// Degrees.
+    pub yaw: f32,
+    pub pitch: f32,
+    pub head_yaw: f32,
+    /// Blocks per tick.
+    pub velocity: Vec3,
+    pub on_ground: bool,
+    pub metadata: BTreeMap<u8, Vec<u8>>
+}
+
+impl TrackedEntity{
+    /// The position a client shows at `now`, moving from the previous to the
+    /// current position over `INTERPOLATION`.
+    pub fn interpolated_position(&self, now: Instant) -> Vec3 {
+        let elapsed = now.saturating_duration_since(self.moved_at).as_secs_f64();
+        let t = (elapsed / INTERPOLATION.as_secs_f64()).min(1.0);
+        self.previous_position.lerp(self.position, t)
+    }
+
+    /// Sets yaw and pitch, turning the head along with the body.
+    pub fn set_rotation(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = yaw;
+        self.head_yaw = yaw;
+        self.pitch = pitch;
+    }
+
+    fn move_to(&mut self, target: Vec3, now: Instant) {
+        // Start from where the client currently shows the entity, so moves
+        // arriving mid interpolation do not make it jump.
+        self.previous_position = self.interpolated_position(now);
+        self.position = target;
+        self.moved_at = now;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EntityTracker{
+    entities: HashMap<i32, TrackedEntity>
+}
+
+impl EntityTracker{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entity, replacing one with the same id. Its rotation and
+    /// velocity start out zero and can be set on the returned entity.
+    pub fn spawn(&mut self, id: i32, uuid: u128, kind: EntityKind, position: Vec3, now: Instant) -> &mut TrackedEntity {
+        let entity = TrackedEntity{
+            id,
+            uuid,
+            kind,
+            position,
+            previous_position: position,
+            moved_at: now,
+            yaw: 0.0,
+            pitch: 0.0,
+            head_yaw: 0.0,
+            velocity: Vec3::default(),
+            on_ground: false,
+            metadata: BTreeMap::new()
+        };
+        self.entities.insert(id, entity);
+        self.entities.get_mut(&id).expect("inserted above")
+    }
+
+    /// Applies a relative move. Returns `false` if the entity is unknown.
+    pub fn move_by(&mut self, id: i32, delta: Vec3, on_ground: bool, now: Instant) -> bool {
+        self.update(id, |entity| {
+            let target = Vec3::new(entity.position.x + delta.x, entity.position.y + delta.y, entity.position.z + delta.z);
+            entity.move_to(target, now);
+            entity.on_ground = on_ground;
+        })
+    }
+
+    pub fn look(&mut self, id: i32, yaw: f32, pitch: f32, on_ground: bool) -> bool {
+        self.update(id, |entity| {
+            entity.yaw = yaw;
+            entity.pitch = pitch;
+            entity.on_ground = on_ground;
+        })
+    }
+
+    pub fn head_look(&mut self, id: i32, head_yaw: f32) -> bool {
+        self.update(id, |entity| entity.head_yaw = head_yaw)
+    }
+
+    pub fn teleport(&mut self, id: i32, position: Vec3, yaw: f32, pitch: f32, on_ground: bool, now: Instant) -> bool {
+        self.update(id, |entity| {
+            entity.move_to(position, now);
+            entity.yaw = yaw;
+            entity.pitch = pitch;
+            entity.on_ground = on_ground;
+        })
+    }
+
+    pub fn set_velocity(&mut self, id: i32, velocity: Vec3) -> bool {
+        self.update(id, |entity| entity.velocity = velocity)
+    }
+
+    /// Merges the body of an EntityMetadata packet, without the entity id,
+    /// into the metadata of the entity. Returns `Ok(false)` if it is unknown.
+    pub fn update_metadata(&mut self, id: i32, metadata: &[u8]) -> std::io::Result<bool> {
+        let entries = split_metadata(metadata)?;
+        Ok(self.update(id, |entity| {
+            for (index, value) in entries {
+                entity.metadata.insert(index, value.to_vec());
+            }
+        }))
+    }
+
+    pub fn destroy(&mut self, id: i32) -> Option<TrackedEntity> {
+        self.entities.remove(&id)
+    }
+
+    /// Forgets all entities, as a client does on Respawn.
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    pub fn get(&self, id: i32) -> Option<&TrackedEntity> {
+        self.entities.get(&id)
+    }
+
+    pub fn by_uuid(&self, uuid: u128) -> Option<&TrackedEntity> {
+        self.entities.values().find(|entity| entity.uuid == uuid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TrackedEntity> {
+        self.entities.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// The entities within `radius` blocks of `center`, using their current
+    /// (not interpolated) position.
+    pub fn within(&self, center: Vec3, radius: f64) -> impl Iterator<Item = &TrackedEntity> {
+        self.entities.values().filter(move |entity| {
+            let (dx, dy, dz) = (entity.position.x - center.x, entity.position.y - center.y, entity.position.z - center.z);
+            dx * dx + dy * dy + dz * dz <= radius * radius
+        })
+    }
+
+    fn update<F: FnOnce(&mut TrackedEntity)>(&mut self, id: i32, f: F) -> bool {
+        match self.entities.get_mut(&id) {
+            Some(entity) => {
+                f(entity);
+                true
+            }
+            None => false
+        }
+    }
+}
+
+/// Converts a protocol angle, 1/256th of a turn, to degrees.
+pub fn angle(value: i8) -> f32 {
+    value as u8 as f32 * 360.0 / 256.0
+}
+
+/// Converts a protocol velocity, 1/8000th of a block per tick, to blocks per tick.
+pub fn velocity(value: i16) -> f64 {
+    value as f64 / 8000.0
+}
+
+/// Converts the delta of a relative move, 1/4096th of a block, to blocks.
+pub fn move_delta(value: i16) -> f64 {
+    value as f64 / 4096.0
+}
+
+/// Splits entity metadata into `(index, [VarInt type][value])` entries, using
+/// the 1.17 metadata types.
+pub fn split_metadata(metadata: &[u8]) -> std::io::Result<Vec<(u8, &[u8])>> {
+    let mut entries = Vec::new();
+    let mut reader = metadata;
+    loop {
+        let index = reader.read_u8()?;
+        if index == METADATA_END {
+            break;
+        }
+        let start = reader;
+        skip_metadata_value(&mut reader)?;
+        entries.push((index, &start[..start.len() - reader.len()]));
+    }
+    if !reader.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, "entity metadata has trailing bytes"));
+    }
+    Ok(entries)
+}
+
+fn skip_metadata_value(reader: &mut &[u8]) -> std::io::Result<()> {
+    match read_var_int(reader)? {
+        // Byte, boolean
+        0 | 7 => skip(reader, 1),
+        // VarInt, direction, optional block state, optional VarInt, pose
+        1 | 11 | 13 | 17 | 18 => read_var_int(reader).map(drop),
+        // Float
+        2 => skip(reader, 4),
+        // String, chat
+        3 | 4 => skip_string(reader),
+        // Optional chat
+        5 => if reader.read_u8()? != 0 { skip_string(reader) }else{ Ok(()) },
+        6 => skip_slot(reader),
+        // Rotation
+        8 => skip(reader, 12),
+        // Position
+        9 => skip(reader, 8),
+        10 => if reader.read_u8()? != 0 { skip(reader, 8) }else{ Ok(()) },
+        // Optional UUID
+        12 => if reader.read_u8()? != 0 { skip(reader, 16) }else{ Ok(()) },
+        14 => skip_nbt(reader),
+        15 => skip_particle(reader),
+        // Villager data
+        16 => {
+            for _ in 0..3 {
+                read_var_int(reader)?;
+            }
+            Ok(())
+        }
+        ty => Err(Error::new(ErrorKind::InvalidData, format!("unknown entity metadata type {}", ty)))
+    }
+}
+
+fn skip_particle(reader: &mut &[u8]) -> std::io::Result<()> {
+    match read_var_int(reader)? {
+        // Block, falling dust
+        4 | 25 => read_var_int(reader).map(drop),
+        // Dust: rgb and scale
+        15 => skip(reader, 16),
+        // Dust color transition: two colors and scale
+        16 => skip(reader, 28),
+        // Item
+        36 => skip_slot(reader),
+        // Vibration: origin, destination type and destination, ticks
+        37 => {
+            skip(reader, 24)?;
+            let mut destination = String::new();
+            read_string(reader, &mut destination)?;
+            match destination.as_str() {
+                "minecraft:block" => skip(reader, 8)?,
+                "minecraft:entity" => read_var_int(reader).map(drop)?,
+                other => return Err(Error::new(ErrorKind::InvalidData, format!("unknown vibration destination type {}", other)))
+            }
+            read_var_int(reader).map(drop)
+        }
+        _ => Ok(())
+    }
+}
+
+fn skip_slot(reader: &mut &[u8]) -> std::io::Result<()> {
+    if reader.read_u8()? == 0 {
+        return Ok(());
+    }
+    read_var_int(reader)?;
+    skip(reader, 1)?;
+    skip_nbt(reader)
+}
+
+fn skip_nbt(reader: &mut &[u8]) -> std::io::Result<()> {
+    copy_named_tag(reader, &mut Vec::new(), MAX_DEPTH)
+}
+
+fn skip_string(reader: &mut &[u8]) -> std::io::Result<()> {
+    let len = read_var_int(reader)?;
+    if len < 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "negative string length"));
+    }
+    skip(reader, len as usize)
+}
+
+fn read_string(reader: &mut &[u8], out: &mut String) -> std::io::Result<()> {
+    let len = read_var_int(reader)?;
+    if len < 0 || len as usize > reader.len() {
+        return Err(Error::new(ErrorKind::InvalidData, "invalid string length"));
+    }
+    reader.take(len as u64).read_to_string(out).map(drop)
+}
+
+fn skip(reader: &mut &[u8], len: usize) -> std::io::Result<()> {
+    if reader.len() < len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "entity metadata is truncated"));
+    }
+    *reader = &reader[len..];
+    Ok(())
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::{angle, move_delta, velocity, EntityKind, EntityTracker, Vec3};
+    use crate::protocol::implementation::steven::v1_17::Proto_1_17;
+    use crate::segment::Segment;
+    use std::time::Instant;
+    use steven_protocol::protocol::{FixedPoint12, UUID};
+
+    impl EntityTracker{
+        /// Applies spawn, movement, velocity, metadata and destroy packets.
+        /// Returns whether the packet was one of them.
+        pub fn handle(&mut self, packet: &Proto_1_17, now: Instant) -> std::io::Result<bool> {
+            match packet {
+                Proto_1_17::SpawnObject(p) => {
+                    let entity = self.spawn(p.entity_id.0, uuid(&p.uuid)?, EntityKind::Object{ ty: p.ty.0, data: p.data }, Vec3::new(p.x, p.y, p.z), now);
+                    entity.set_rotation(angle(p.yaw), angle(p.pitch));
+                    entity.velocity = Vec3::new(velocity(p.velocity_x), velocity(p.velocity_y), velocity(p.velocity_z));
+                }
+                Proto_1_17::SpawnExperienceOrb(p) => {
+                    self.spawn(p.entity_id.0, 0, EntityKind::ExperienceOrb{ count: p.count }, Vec3::new(p.x, p.y, p.z), now);
+                }
+                Proto_1_17::SpawnMob(p) => {
+                    let entity = self.spawn(p.entity_id.0, uuid(&p.uuid)?, EntityKind::Mob{ ty: p.ty.0 }, Vec3::new(p.x, p.y, p.z), now);
+                    entity.set_rotation(angle(p.yaw), angle(p.pitch));
+                    entity.head_yaw = angle(p.head_pitch);
+                    entity.velocity = Vec3::new(velocity(p.velocity_x), velocity(p.velocity_y), velocity(p.velocity_z));
+                }
+                Proto_1_17::SpawnPainting(p) => {
+                    let position = Vec3::new(p.location.x as f64, p.location.y as f64, p.location.z as f64);
+                    self.spawn(p.entity_id.0, uuid(&p.uuid)?, EntityKind::Painting{ motive: p.motive.0, direction: p.direction }, position, now);
+                }
+                Proto_1_17::SpawnPlayer(p) => {
+                    self.spawn(p.entity_id.0, uuid(&p.uuid)?, EntityKind::Player, Vec3::new(p.x, p.y, p.z), now)
+                        .set_rotation(angle(p.yaw), angle(p.pitch));
+                }
+                Proto_1_17::EntityMove(p) => {
+                    let delta = Vec3::new(fixed_point(&p.delta_x)?, fixed_point(&p.delta_y)?, fixed_point(&p.delta_z)?);
+                    self.move_by(p.entity_id.0, delta, p.on_ground, now);
+                }
+                Proto_1_17::EntityLookAndMove(p) => {
+                    let delta = Vec3::new(fixed_point(&p.delta_x)?, fixed_point(&p.delta_y)?, fixed_point(&p.delta_z)?);
+                    self.move_by(p.entity_id.0, delta, p.on_ground, now);
+                    self.look(p.entity_id.0, angle(p.yaw), angle(p.pitch), p.on_ground);
+                }
+                Proto_1_17::EntityLook(p) => {
+                    self.look(p.entity_id.0, angle(p.yaw), angle(p.pitch), p.on_ground);
+                }
+                Proto_1_17::EntityHeadLook(p) => {
+                    self.head_look(p.entity_id.0, angle(p.head_yaw));
+                }
+                Proto_1_17::EntityTeleport(p) => {
+                    self.teleport(p.entity_id.0, Vec3::new(p.x, p.y, p.z), angle(p.yaw), angle(p.pitch), p.on_ground, now);
+                }
+                Proto_1_17::EntityVelocity(p) => {
+                    self.set_velocity(p.entity_id.0, Vec3::new(velocity(p.velocity_x), velocity(p.velocity_y), velocity(p.velocity_z)));
+                }
+                Proto_1_17::EntityMetadata(p) => {
+                    let mut metadata = Vec::with_capacity(p.metadata.serialized_len());
+                    p.metadata.write_to_stream(&mut metadata)?;
+                    self.update_metadata(p.entity_id.0, &metadata)?;
+                }
+                Proto_1_17::EntityDestroy(p) => {
+                    self.destroy(p.entity_id.0);
+                }
+                Proto_1_17::Respawn(_) => self.clear(),
+                _ => return Ok(false)
+            }
+            Ok(true)
+        }
+    }
+
+    fn uuid(uuid: &UUID) -> std::io::Result<u128> {
+        let mut bytes = Vec::with_capacity(16);
+        uuid.write_to_stream(&mut bytes)?;
+        let mut array = [0; 16];
+        array.copy_from_slice(&bytes);
+        Ok(u128::from_be_bytes(array))
+    }
+
+    fn fixed_point(value: &FixedPoint12<i16>) -> std::io::Result<f64> {
+        let mut bytes = Vec::with_capacity(2);
+        value.write_to_stream(&mut bytes)?;
+        Ok(move_delta(i16::from_be_bytes([bytes[0], bytes[1]])))
+    }
+}
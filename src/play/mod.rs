@@ -0,0 +1,18 @@
+//! Helpers for the play state.
+#[cfg(feature = "chat-signing")]
+pub mod chat_signing;
+pub mod cookies;
+pub mod entities;
+pub mod plugin_channels;
+pub mod resource_pack;
+pub mod scoreboard;
+pub mod tab_list;
+pub mod transactions;
+
+pub use self::cookies::CookieStore;
+pub use self::entities::EntityTracker;
+pub use self::plugin_channels::PluginChannels;
+pub use self::resource_pack::ResourcePackManager;
+pub use self::scoreboard::Scoreboard;
+pub use self::tab_list::TabList;
+pub use self::transactions::Transactions;
@@ -0,0 +1,404 @@
+//! Plugin messages by channel: keeping track of the channels the other side
+//! registered, and decoding the messages of a channel for its handler.
+//!
+//! `PluginChannels` reads `minecraft:register` and `minecraft:unregister`
+//! itself. Other channels get a `ChannelCodec` and a handler, and
+//! `dispatch` runs the handler for every PluginMessage of that channel:
+//!
+//! ```ignore
+//! let mut channels = PluginChannels::new();
+//! channels.register(Identifier::parse(BRAND)?, BrandCodec, |_, brand| println!("client brand {}", brand));
+//! channels.dispatch(Direction::ServerBound, &message.channel, &message.data)?;
+//! ```
+use crate::protocol::Direction;
+use crate::segment::implementation::mojang::identifier::Identifier;
+use crate::segment::implementation::var::write_var_int;
+use std::collections::{BTreeSet, HashMap};
+use std::io::{Error, ErrorKind};
+
+pub const REGISTER: &str = "minecraft:register";
+pub const UNREGISTER: &str = "minecraft:unregister";
+pub const BRAND: &str = "minecraft:brand";
+pub const BUNGEECORD: &str = "bungeecord:main";
+
+/// Names of the channels before 1.13, which were not namespaced.
+pub const LEGACY_REGISTER: &str = "REGISTER";
+pub const LEGACY_UNREGISTER: &str = "UNREGISTER";
+pub const LEGACY_BRAND: &str = "MC|Brand";
+pub const LEGACY_BUNGEECORD: &str = "BungeeCord";
+
+/// Turns the payload of a channel into messages and back. `direction` is the
+/// direction the message travels in, for channels whose requests and answers
+/// look alike.
+pub trait ChannelCodec{
+    type Message;
+
+    fn decode(&self, direction: Direction, data: &[u8]) -> std::io::Result<Self::Message>;
+
+    fn encode(&self, message: &Self::Message) -> Vec<u8>;
+}
+
+/// `minecraft:brand`, the name of the client or server software as String.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BrandCodec;
+
+impl ChannelCodec for BrandCodec{
+    type Message = String;
+
+    fn decode(&self, _: Direction, mut data: &[u8]) -> std::io::Result<String> {
+        Ok(crate::io::borrowed::read_str(&mut data)?.to_string())
+    }
+
+    fn encode(&self, brand: &String) -> Vec<u8> {
+        let mut data = Vec::with_capacity(brand.len() + 1);
+        let _ = write_var_int(brand.len() as i32, &mut data);
+        data.extend_from_slice(brand.as_bytes());
+        data
+    }
+}
+
+/// `minecraft:register` and `minecraft:unregister`, channel names separated by NUL.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChannelListCodec;
+
+impl ChannelCodec for ChannelListCodec{
+    type Message = Vec<String>;
+
+    fn decode(&self, _: Direction, data: &[u8]) -> std::io::Result<Vec<String>> {
+        let text = std::str::from_utf8(data)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "channel list is not UTF-8"))?;
+        Ok(text.split('\0').filter(|channel| !channel.is_empty()).map(str::to_string).collect())
+    }
+
+    fn encode(&self, channels: &Vec<String>) -> Vec<u8> {
+        channels.join("\0").into_bytes()
+    }
+}
+
+/// A message a backend server sends to BungeeCord on `bungeecord:main`, as
+/// a clientbound plugin message on a player's connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BungeeRequest{
+    Connect{ server: String },
+    ConnectOther{ player: String, server: String },
+    Ip,
+    IpOther{ player: String },
+    /// `ALL` counts the players of every server.
+    PlayerCount{ server: String },
+    PlayerList{ server: String },
+    GetServers,
+    Message{ player: String, message: String },
+    MessageRaw{ player: String, json: String },
+    GetServer,
+    /// `ALL` and `ONLINE` forward to every server, or every server with players.
+    Forward{ server: String, channel: String, data: Vec<u8> },
+    ForwardToPlayer{ player: String, channel: String, data: Vec<u8> },
+    Uuid,
+    UuidOther{ player: String },
+    ServerIp{ server: String },
+    KickPlayer{ player: String, reason: String },
+    Other{ subchannel: String, data: Vec<u8> }
+}
+
+/// A message BungeeCord answers with, as a serverbound plugin message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BungeeResponse{
+    Ip{ ip: String, port: i32 },
+    IpOther{ player: String, ip: String, port: i32 },
+    PlayerCount{ server: String, count: i32 },
+    PlayerList{ server: String, players: Vec<String> },
+    GetServers{ servers: Vec<String> },
+    GetServer{ server: String },
+    /// A message another server sent with `Forward` or `ForwardToPlayer`.
+    Forward{ channel: String, data: Vec<u8> },
+    Uuid{ uuid: String },
+    UuidOther{ player: String, uuid: String },
+    ServerIp{ server: String, ip: String, port: u16 },
+    Other{ subchannel: String, data: Vec<u8> }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BungeeMessage{
+    Request(BungeeRequest),
+    Response(BungeeResponse)
+}
+
+/// `bungeecord:main`. Strings are written by Java's `DataOutput.writeUTF`,
+/// `[u16 length][UTF-8]`, lists are joined with `, `.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BungeeCordCodec;
+
+impl ChannelCodec for BungeeCordCodec{
+    type Message = BungeeMessage;
+
+    fn decode(&self, direction: Direction, mut data: &[u8]) -> std::io::Result<BungeeMessage> {
+        let buffer = &mut data;
+        let subchannel = read_utf(buffer)?;
+        Ok(match direction {
+            Direction::ClientBound => BungeeMessage::Request(match subchannel.as_str() {
+                "Connect" => BungeeRequest::Connect{ server: read_utf(buffer)? },
+                "ConnectOther" => BungeeRequest::ConnectOther{ player: read_utf(buffer)?, server: read_utf(buffer)? },
+                "IP" => BungeeRequest::Ip,
+                "IPOther" => BungeeRequest::IpOther{ player: read_utf(buffer)? },
+                "PlayerCount" => BungeeRequest::PlayerCount{ server: read_utf(buffer)? },
+                "PlayerList" => BungeeRequest::PlayerList{ server: read_utf(buffer)? },
+                "GetServers" => BungeeRequest::GetServers,
+                "Message" => BungeeRequest::Message{ player: read_utf(buffer)?, message: read_utf(buffer)? },
+                "MessageRaw" => BungeeRequest::MessageRaw{ player: read_utf(buffer)?, json: read_utf(buffer)? },
+                "GetServer" => BungeeRequest::GetServer,
+                "Forward" => BungeeRequest::Forward{ server: read_utf(buffer)?, channel: read_utf(buffer)?, data: read_short_bytes(buffer)? },
+                "ForwardToPlayer" => BungeeRequest::ForwardToPlayer{ player: read_utf(buffer)?, channel: read_utf(buffer)?, data: read_short_bytes(buffer)? },
+                "UUID" => BungeeRequest::Uuid,
+                "UUIDOther" => BungeeRequest::UuidOther{ player: read_utf(buffer)? },
+                "ServerIP" => BungeeRequest::ServerIp{ server: read_utf(buffer)? },
+                "KickPlayer" => BungeeRequest::KickPlayer{ player: read_utf(buffer)?, reason: read_utf(buffer)? },
+                _ => BungeeRequest::Other{ subchannel, data: buffer.to_vec() }
+            }),
+            Direction::ServerBound => BungeeMessage::Response(match subchannel.as_str() {
+                "IP" => BungeeResponse::Ip{ ip: read_utf(buffer)?, port: read_i32(buffer)? },
+                "IPOther" => BungeeResponse::IpOther{ player: read_utf(buffer)?, ip: read_utf(buffer)?, port: read_i32(buffer)? },
+                "PlayerCount" => BungeeResponse::PlayerCount{ server: read_utf(buffer)?, count: read_i32(buffer)? },
+                "PlayerList" => BungeeResponse::PlayerList{ server: read_utf(buffer)?, players: split_list(&read_utf(buffer)?) },
+                "GetServers" => BungeeResponse::GetServers{ servers: split_list(&read_utf(buffer)?) },
+                "GetServer" => BungeeResponse::GetServer{ server: read_utf(buffer)? },
+                "UUID" => BungeeResponse::Uuid{ uuid: read_utf(buffer)? },
+                "UUIDOther" => BungeeResponse::UuidOther{ player: read_utf(buffer)?, uuid: read_utf(buffer)? },
+                "ServerIP" => BungeeResponse::ServerIp{ server: read_utf(buffer)?, ip: read_utf(buffer)?, port: read_u16(buffer)? },
+                _ => {
+                    // Forwarded messages carry the channel they were sent on as subchannel.
+                    match read_short_bytes(&mut &buffer[..]) {
+                        Ok(data) if data.len() + 2 == buffer.len() => BungeeResponse::Forward{ channel: subchannel, data },
+                        _ => BungeeResponse::Other{ subchannel, data: buffer.to_vec() }
+                    }
+                }
+            })
+        })
+    }
+
+    fn encode(&self, message: &BungeeMessage) -> Vec<u8> {
+        let mut out = Vec::new();
+        match message {
+            BungeeMessage::Request(request) => match request {
+                BungeeRequest::Connect{ server } => write_utfs(&mut out, &["Connect", server]),
+                BungeeRequest::ConnectOther{ player, server } => write_utfs(&mut out, &["ConnectOther", player, server]),
+                BungeeRequest::Ip => write_utfs(&mut out, &["IP"]),
+                BungeeRequest::IpOther{ player } => write_utfs(&mut out, &["IPOther", player]),
+                BungeeRequest::PlayerCount{ server } => write_utfs(&mut out, &["PlayerCount", server]),
+                BungeeRequest::PlayerList{ server } => write_utfs(&mut out, &["PlayerList", server]),
+                BungeeRequest::GetServers => write_utfs(&mut out, &["GetServers"]),
+                BungeeRequest::Message{ player, message } => write_utfs(&mut out, &["Message", player, message]),
+                BungeeRequest::MessageRaw{ player, json } => write_utfs(&mut out, &["MessageRaw", player, json]),
+                BungeeRequest::GetServer => write_utfs(&mut out, &["GetServer"]),
+                BungeeRequest::Forward{ server, channel, data } => {
+                    write_utfs(&mut out, &["Forward", server, channel]);
+                    write_short_bytes(&mut out, data);
+                }
+                BungeeRequest::ForwardToPlayer{ player, channel, data } => {
+                    write_utfs(&mut out, &["ForwardToPlayer", player, channel]);
+                    write_short_bytes(&mut out, data);
+                }
+                BungeeRequest::Uuid => write_utfs(&mut out, &["UUID"]),
+                BungeeRequest::UuidOther{ player } => write_utfs(&mut out, &["UUIDOther", player]),
+                BungeeRequest::ServerIp{ server } => write_utfs(&mut out, &["ServerIP", server]),
+                BungeeRequest::KickPlayer{ player, reason } => write_utfs(&mut out, &["KickPlayer", player, reason]),
+                BungeeRequest::Other{ subchannel, data } => {
+                    write_utfs(&mut out, &[subchannel]);
+                    out.extend_from_slice(data);
+                }
+            },
+            BungeeMessage::Response(response) => match response {
+                BungeeResponse::Ip{ ip, port } => {
+                    write_utfs(&mut out, &["IP", ip]);
+                    out.extend_from_slice(&port.to_be_bytes());
+                }
+                BungeeResponse::IpOther{ player, ip, port } => {
+                    write_utfs(&mut out, &["IPOther", player, ip]);
+                    out.extend_from_slice(&port.to_be_bytes());
+                }
+                BungeeResponse::PlayerCount{ server, count } => {
+                    write_utfs(&mut out, &["PlayerCount", server]);
+                    out.extend_from_slice(&count.to_be_bytes());
+                }
+                BungeeResponse::PlayerList{ server, players } => write_utfs(&mut out, &["PlayerList", server, &players.join(", ")]),
+                BungeeResponse::GetServers{ servers } => write_utfs(&mut out, &["GetServers", &servers.join(", ")]),
+                BungeeResponse::GetServer{ server } => write_utfs(&mut out, &["GetServer", server]),
+                BungeeResponse::Forward{ channel, data } => {
+                    write_utfs(&mut out, &[channel]);
+                    write_short_bytes(&mut out, data);
+                }
+                BungeeResponse::Uuid{ uuid } => write_utfs(&mut out, &["UUID", uuid]),
+                BungeeResponse::UuidOther{ player, uuid } => write_utfs(&mut out, &["UUIDOther", player, uuid]),
+                BungeeResponse::ServerIp{ server, ip, port } => {
+                    write_utfs(&mut out, &["ServerIP", server, ip]);
+                    out.extend_from_slice(&port.to_be_bytes());
+                }
+                BungeeResponse::Other{ subchannel, data } => {
+                    write_utfs(&mut out, &[subchannel]);
+                    out.extend_from_slice(data);
+                }
+            }
+        }
+        out
+    }
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(", ").filter(|entry| !entry.is_empty()).map(str::to_string).collect()
+}
+
+fn take<'a>(buffer: &mut &'a [u8], len: usize) -> std::io::Result<&'a [u8]> {
+    if buffer.len() < len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "plugin message is truncated"));
+    }
+    let (bytes, rest) = buffer.split_at(len);
+    *buffer = rest;
+    Ok(bytes)
+}
+
+fn read_u16(buffer: &mut &[u8]) -> std::io::Result<u16> {
+    let bytes = take(buffer, 2)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_i32(buffer: &mut &[u8]) -> std::io::Result<i32> {
+    let bytes = take(buffer, 4)?;
+    Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_utf(buffer: &mut &[u8]) -> std::io::Result<String> {
+    let len = read_u16(buffer)? as usize;
+    String::from_utf8(take(buffer, len)?.to_vec())
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "plugin message string is not UTF-8"))
+}
+
+fn read_short_bytes(buffer: &mut &[u8]) -> std::io::Result<Vec<u8>> {
+    let len = read_u16(buffer)? as usize;
+    Ok(take(buffer, len)?.to_vec())
+}
+
+fn write_utfs(out: &mut Vec<u8>, values: &[&str]) {
+    for value in values {
+        write_short_bytes(out, value.as_bytes());
+    }
+}
+
+fn write_short_bytes(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+type Handler = Box<dyn FnMut(Direction, &[u8]) -> std::io::Result<()> + Send>;
+
+/// Channels with handlers, and the channels the client and server registered.
+#[derive(Default)]
+pub struct PluginChannels{
+    handlers: HashMap<String, Handler>,
+    client_channels: BTreeSet<String>,
+    server_channels: BTreeSet<String>
+}
+
+impl std::fmt::Debug for PluginChannels{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginChannels")
+            .field("handlers", &self.handlers.keys().collect::<Vec<_>>())
+            .field("client_channels", &self.client_channels)
+            .field("server_channels", &self.server_channels)
+            .finish()
+    }
+}
+
+impl PluginChannels{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Runs `handler` for every message on `channel`, decoded by `codec`.
+    /// Replaces the handler registered for the channel before.
+    pub fn register<C, F>(&mut self, channel: Identifier, codec: C, mut handler: F) -> &mut Self
+        where C: ChannelCodec + Send + 'static, F: FnMut(Direction, C::Message) + Send + 'static {
+        self.handlers.insert(channel.to_string(), Box::new(move |direction, data| {
+            handler(direction, codec.decode(direction, data)?);
+            Ok(())
+        }));
+        self
+    }
+
+    pub fn unregister(&mut self, channel: &Identifier) -> &mut Self {
+        self.handlers.remove(&channel.to_string());
+        self
+    }
+
+    /// Channels with a handler.
+    pub fn channels(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    /// The `minecraft:register` payload announcing the channels with a handler.
+    pub fn register_payload(&self) -> Vec<u8> {
+        let mut channels: Vec<String> = self.handlers.keys().cloned().collect();
+        channels.sort();
+        ChannelListCodec.encode(&channels)
+    }
+
+    /// The channels registered by the side sending in `direction`, the client
+    /// for `ServerBound`.
+    pub fn registered(&self, direction: Direction) -> &BTreeSet<String> {
+        match direction {
+            Direction::ServerBound => &self.client_channels,
+            Direction::ClientBound => &self.server_channels
+        }
+    }
+
+    /// Whether the side receiving in `direction` listens on `channel`, so
+    /// messages to it are not wasted.
+    pub fn is_listening(&self, direction: Direction, channel: &str) -> bool {
+        let receiver = match direction {
+            Direction::ServerBound => Direction::ClientBound,
+            Direction::ClientBound => Direction::ServerBound
+        };
+        self.registered(receiver).contains(channel)
+    }
+
+    /// Handles a plugin message travelling in `direction`. Returns whether a
+    /// handler or the channel registration took it.
+    pub fn dispatch(&mut self, direction: Direction, channel: &str, data: &[u8]) -> std::io::Result<bool> {
+        let registered = match direction {
+            Direction::ServerBound => &mut self.client_channels,
+            Direction::ClientBound => &mut self.server_channels
+        };
+        match channel {
+            REGISTER | LEGACY_REGISTER => {
+                registered.extend(ChannelListCodec.decode(direction, data)?);
+                Ok(true)
+            }
+            UNREGISTER | LEGACY_UNREGISTER => {
+                for channel in ChannelListCodec.decode(direction, data)? {
+                    registered.remove(&channel);
+                }
+                Ok(true)
+            }
+            _ => match self.handlers.get_mut(channel) {
+                Some(handler) => handler(direction, data).map(|_| true),
+                None => Ok(false)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::PluginChannels;
+    use crate::protocol::implementation::steven::v1_17::Proto_1_17;
+    use crate::protocol::Direction;
+
+    impl PluginChannels{
+        /// Dispatches the packet if it is a plugin message, see `dispatch`.
+        pub fn dispatch_packet(&mut self, packet: &Proto_1_17) -> std::io::Result<bool> {
+            match packet {
+                Proto_1_17::PluginMessageServerbound(message) => self.dispatch(Direction::ServerBound, &message.channel, &message.data),
+                Proto_1_17::PluginMessageClientbound(message) => self.dispatch(Direction::ClientBound, &message.channel, &message.data),
+                _ => Ok(false)
+            }
+        }
+    }
+}
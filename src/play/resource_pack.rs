@@ -0,0 +1,234 @@
+//! Pushing resource packs to players and following their answers.
+//!
+//! A server sends ResourcePackSend and the client answers with one or more
+//! ResourcePackStatus, e.g. accepted, downloaded and finally loaded. Before
+//! 1.20.3 a client holds a single server pack and the answers carry no id.
+//! Since 1.20.3 packs are pushed with ResourcePackPush under a UUID, several
+//! at once, removed with ResourcePackPop and answered per UUID.
+//!
+//! Pack hashes are the lower case hex SHA-1 of the pack file, which
+//! `pack_hash` and `hash_reader` compute with the `sha1` feature.
+use crate::chat::Component;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The `result` of ResourcePackStatus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PackStatus{
+    SuccessfullyLoaded,
+    Declined,
+    FailedDownload,
+    Accepted,
+    /// Since 1.20.3, like the statuses below.
+    Downloaded,
+    InvalidUrl,
+    FailedReload,
+    /// The client dropped the pack, e.g. after a ResourcePackPop.
+    Discarded
+}
+
+impl PackStatus{
+    pub fn id(&self) -> i32 {
+        *self as i32
+    }
+
+    pub fn from_id(id: i32) -> Option<PackStatus> {
+        Some(match id {
+            0 => PackStatus::SuccessfullyLoaded,
+            1 => PackStatus::Declined,
+            2 => PackStatus::FailedDownload,
+            3 => PackStatus::Accepted,
+            4 => PackStatus::Downloaded,
+            5 => PackStatus::InvalidUrl,
+            6 => PackStatus::FailedReload,
+            7 => PackStatus::Discarded,
+            _ => return None
+        })
+    }
+
+    /// Whether no further status follows for the pack.
+    pub fn is_final(&self) -> bool {
+        !matches!(self, PackStatus::Accepted | PackStatus::Downloaded)
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, PackStatus::FailedDownload | PackStatus::InvalidUrl | PackStatus::FailedReload)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourcePack{
+    /// The UUID of the pack since 1.20.3, `None` before.
+    pub id: Option<u128>,
+    pub url: String,
+    /// Lower case hex SHA-1 of the pack, empty to skip the check.
+    pub hash: String,
+    /// Kick players declining the pack, since 1.17.
+    pub forced: bool,
+    /// Shown in the prompt, since 1.17.
+    pub prompt: Option<Component>
+}
+
+impl ResourcePack{
+    pub fn new(url: impl Into<String>, hash: impl Into<String>) -> Self {
+        ResourcePack{ id: None, url: url.into(), hash: hash.into(), forced: false, prompt: None }
+    }
+
+    /// Sends the pack with ResourcePackPush under `id`.
+    pub fn with_id(mut self, id: u128) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn forced(mut self, forced: bool) -> Self {
+        self.forced = forced;
+        self
+    }
+
+    pub fn prompt(mut self, prompt: impl Into<Component>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+}
+
+/// Whether `hash` can be sent: 40 hex digits, or empty.
+pub fn is_valid_hash(hash: &str) -> bool {
+    hash.is_empty() || (hash.len() == 40 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// The hash of a pack file.
+#[cfg(feature = "sha1")]
+pub fn pack_hash(data: &[u8]) -> String {
+    use sha1::{Digest, Sha1};
+    hex(&Sha1::digest(data))
+}
+
+/// The hash of a pack file read from `reader`, without loading it whole.
+#[cfg(feature = "sha1")]
+pub fn hash_reader<R: std::io::Read>(mut reader: R) -> std::io::Result<String> {
+    use sha1::{Digest, Sha1};
+    let mut hasher = Sha1::new();
+    let mut buffer = [0; 8192];
+    loop {
+        match reader.read(&mut buffer)? {
+            0 => return Ok(hex(&hasher.finalize())),
+            read => hasher.update(&buffer[..read])
+        }
+    }
+}
+
+#[cfg(feature = "sha1")]
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourcePackError{
+    UnknownPlayer(u128),
+    /// The player answered for a pack it was not sent.
+    UnknownPack(u128, Option<u128>)
+}
+
+impl ResourcePackError{
+    pub fn description(&self) -> String {
+        match self {
+            ResourcePackError::UnknownPlayer(player) => format!("no resource packs were sent to {:032x}", player),
+            ResourcePackError::UnknownPack(player, Some(pack)) => format!("resource pack {:032x} was not sent to {:032x}", pack, player),
+            ResourcePackError::UnknownPack(player, None) => format!("no resource pack was sent to {:032x}", player)
+        }
+    }
+}
+
+/// A pack sent to a player, with the last status it answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentPack{
+    pub pack: ResourcePack,
+    pub sent: Instant,
+    pub status: Option<PackStatus>
+}
+
+impl SentPack{
+    pub fn is_done(&self) -> bool {
+        self.status.is_some_and(|status| status.is_final())
+    }
+}
+
+/// What to do about a ResourcePackStatus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response{
+    pub pack: ResourcePack,
+    pub status: PackStatus,
+    /// The pack is forced and the player declined it, vanilla kicks with
+    /// `multiplayer.requiredTexturePrompt.disconnect`.
+    pub kick: bool
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResourcePackManager{
+    players: HashMap<u128, Vec<SentPack>>
+}
+
+impl ResourcePackManager{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Records `pack` as sent to `player`. A pack without id replaces the one
+    /// sent before, as does a pack with the same id.
+    pub fn push(&mut self, player: u128, pack: ResourcePack, now: Instant) {
+        let packs = self.players.entry(player).or_default();
+        packs.retain(|sent| sent.pack.id != pack.id);
+        packs.push(SentPack{ pack, sent: now, status: None });
+    }
+
+    /// Records a ResourcePackPop, `None` removing every pack.
+    pub fn pop(&mut self, player: u128, id: Option<u128>) {
+        if let Some(packs) = self.players.get_mut(&player) {
+            packs.retain(|sent| id.is_some() && sent.pack.id != id);
+        }
+    }
+
+    /// Handles a ResourcePackStatus, `id` being `None` before 1.20.3.
+    pub fn handle_status(&mut self, player: u128, id: Option<u128>, status: PackStatus) -> Result<Response, ResourcePackError> {
+        let packs = self.players.get_mut(&player)
+            .ok_or(ResourcePackError::UnknownPlayer(player))?;
+        let index = packs.iter().position(|sent| sent.pack.id == id)
+            .ok_or(ResourcePackError::UnknownPack(player, id))?;
+        let sent = &mut packs[index];
+        sent.status = Some(status);
+        let response = Response{ pack: sent.pack.clone(), status, kick: sent.pack.forced && status == PackStatus::Declined };
+        if status == PackStatus::Discarded {
+            packs.remove(index);
+        }
+        Ok(response)
+    }
+
+    pub fn packs(&self, player: u128) -> &[SentPack] {
+        self.players.get(&player).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn status(&self, player: u128, id: Option<u128>) -> Option<PackStatus> {
+        self.packs(player).iter().find(|sent| sent.pack.id == id).and_then(|sent| sent.status)
+    }
+
+    /// Whether every pack sent to the player finished loading.
+    pub fn all_loaded(&self, player: u128) -> bool {
+        self.packs(player).iter().all(|sent| sent.status == Some(PackStatus::SuccessfullyLoaded))
+    }
+
+    /// Players with packs that got no final status within `timeout` of being sent.
+    pub fn expired(&self, now: Instant, timeout: Duration) -> Vec<(u128, &ResourcePack)> {
+        self.players.iter()
+            .flat_map(|(player, packs)| packs.iter().map(move |sent| (*player, sent)))
+            .filter(|(_, sent)| !sent.is_done() && now.saturating_duration_since(sent.sent) >= timeout)
+            .map(|(player, sent)| (player, &sent.pack))
+            .collect()
+    }
+
+    /// Forgets a player that left.
+    pub fn remove_player(&mut self, player: u128) {
+        self.players.remove(&player);
+    }
+}
@@ -0,0 +1,484 @@
+//! Objectives, scores and teams, as set by ScoreboardObjective, UpdateScore,
+//! ScoreboardDisplay and Teams.
+//!
+//! `Scoreboard::apply` follows what the server sends, for clients and
+//! proxies. Servers change the scoreboard through the builder methods
+//! instead, which update the state and return the updates that bring a client
+//! along, in the order the client expects them.
+use std::collections::{BTreeSet, HashMap};
+
+/// Display position of the player list.
+pub const DISPLAY_LIST: u8 = 0;
+pub const DISPLAY_SIDEBAR: u8 = 1;
+pub const DISPLAY_BELOW_NAME: u8 = 2;
+/// The sidebar only shown to members of teams with the given color, up to 15
+/// positions after this one.
+pub const DISPLAY_TEAM_SIDEBAR: u8 = 3;
+
+/// Team flag allowing members to hurt each other.
+pub const FRIENDLY_FIRE: u8 = 0x01;
+/// Team flag showing invisible members as translucent to their team.
+pub const SEE_INVISIBLE: u8 = 0x02;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Objective{
+    pub name: String,
+    /// JSON chat component.
+    pub display_name: String,
+    /// How the scores are rendered, `integer` or `hearts`.
+    pub render_type: String
+}
+
+/// The properties of a team, everything but its members.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamInfo{
+    /// JSON chat component.
+    pub display_name: String,
+    pub flags: u8,
+    /// `always`, `hideForOtherTeams`, `hideForOwnTeam` or `never`.
+    pub name_tag_visibility: String,
+    /// `always`, `pushOtherTeams`, `pushOwnTeam` or `never`.
+    pub collision_rule: String,
+    /// Chat color of the member names.
+    pub formatting: i32,
+    /// JSON chat component shown in front of member names.
+    pub prefix: String,
+    pub suffix: String
+}
+
+impl Default for TeamInfo{
+    fn default() -> Self {
+        TeamInfo{
+            display_name: String::from("{\"text\":\"\"}"),
+            flags: 0,
+            name_tag_visibility: String::from("always"),
+            collision_rule: String::from("always"),
+            // Reset, which leaves the names uncolored.
+            formatting: 21,
+            prefix: String::from("{\"text\":\"\"}"),
+            suffix: String::from("{\"text\":\"\"}")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Team{
+    pub name: String,
+    pub info: TeamInfo,
+    /// Player names and entity UUIDs on the team.
+    pub members: BTreeSet<String>
+}
+
+/// A single scoreboard packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScoreboardUpdate{
+    CreateObjective(Objective),
+    RemoveObjective(String),
+    UpdateObjective(Objective),
+    SetScore{ entity: String, objective: String, value: i32 },
+    /// Removes a score. An empty objective removes the entity from all of them.
+    RemoveScore{ entity: String, objective: String },
+    /// Shows an objective at a display position, an empty name clears it.
+    Display{ position: u8, objective: String },
+    CreateTeam(Team),
+    RemoveTeam(String),
+    UpdateTeam{ team: String, info: TeamInfo },
+    AddMembers{ team: String, members: Vec<String> },
+    RemoveMembers{ team: String, members: Vec<String> }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scoreboard{
+    objectives: HashMap<String, Objective>,
+    /// Scores per objective, then per entity.
+    scores: HashMap<String, HashMap<String, i32>>,
+    displays: HashMap<u8, String>,
+    teams: HashMap<String, Team>,
+    team_of: HashMap<String, String>
+}
+
+impl Scoreboard{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies an update received from the server. Updates referring to
+    /// objectives or teams that do not exist are ignored.
+    pub fn apply(&mut self, update: ScoreboardUpdate) {
+        match update {
+            ScoreboardUpdate::CreateObjective(objective) | ScoreboardUpdate::UpdateObjective(objective) => {
+                self.objectives.insert(objective.name.clone(), objective);
+            }
+            ScoreboardUpdate::RemoveObjective(name) => {
+                self.objectives.remove(&name);
+                self.scores.remove(&name);
+                self.displays.retain(|_, displayed| *displayed != name);
+            }
+            ScoreboardUpdate::SetScore{ entity, objective, value } => {
+                if self.objectives.contains_key(&objective) {
+                    self.scores.entry(objective).or_default().insert(entity, value);
+                }
+            }
+            ScoreboardUpdate::RemoveScore{ entity, objective } => {
+                if objective.is_empty() {
+                    for scores in self.scores.values_mut() {
+                        scores.remove(&entity);
+                    }
+                }else if let Some(scores) = self.scores.get_mut(&objective) {
+                    scores.remove(&entity);
+                }
+            }
+            ScoreboardUpdate::Display{ position, objective } => {
+                if objective.is_empty() {
+                    self.displays.remove(&position);
+                }else if self.objectives.contains_key(&objective) {
+                    self.displays.insert(position, objective);
+                }
+            }
+            ScoreboardUpdate::CreateTeam(team) => {
+                self.drop_team(&team.name);
+                for member in &team.members {
+                    self.move_member(member, &team.name);
+                }
+                self.teams.insert(team.name.clone(), team);
+            }
+            ScoreboardUpdate::RemoveTeam(name) => self.drop_team(&name),
+            ScoreboardUpdate::UpdateTeam{ team, info } => {
+                if let Some(team) = self.teams.get_mut(&team) {
+                    team.info = info;
+                }
+            }
+            ScoreboardUpdate::AddMembers{ team, members } => {
+                if self.teams.contains_key(&team) {
+                    for member in members {
+                        self.move_member(&member, &team);
+                        self.teams.get_mut(&team).expect("checked above").members.insert(member);
+                    }
+                }
+            }
+            ScoreboardUpdate::RemoveMembers{ team, members } => {
+                if let Some(entry) = self.teams.get_mut(&team) {
+                    for member in members {
+                        if entry.members.remove(&member) {
+                            self.team_of.remove(&member);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Takes `member` out of its current team and records it as part of `team`.
+    fn move_member(&mut self, member: &str, team: &str) {
+        if let Some(previous) = self.team_of.insert(member.to_string(), team.to_string()) {
+            if previous != team {
+                if let Some(previous) = self.teams.get_mut(&previous) {
+                    previous.members.remove(member);
+                }
+            }
+        }
+    }
+
+    fn drop_team(&mut self, name: &str) {
+        if let Some(team) = self.teams.remove(name) {
+            for member in team.members {
+                self.team_of.remove(&member);
+            }
+        }
+    }
+
+    pub fn objective(&self, name: &str) -> Option<&Objective> {
+        self.objectives.get(name)
+    }
+
+    pub fn objectives(&self) -> impl Iterator<Item = &Objective> {
+        self.objectives.values()
+    }
+
+    pub fn score(&self, objective: &str, entity: &str) -> Option<i32> {
+        self.scores.get(objective)?.get(entity).copied()
+    }
+
+    /// The scores of an objective in sidebar order: highest first, ties by name.
+    pub fn scores(&self, objective: &str) -> Vec<(&str, i32)> {
+        let mut scores: Vec<(&str, i32)> = self.scores.get(objective)
+            .map(|scores| scores.iter().map(|(entity, value)| (entity.as_str(), *value)).collect())
+            .unwrap_or_default();
+        scores.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        scores
+    }
+
+    /// The objective shown at a display position.
+    pub fn displayed(&self, position: u8) -> Option<&Objective> {
+        self.objectives.get(self.displays.get(&position)?)
+    }
+
+    pub fn team(&self, name: &str) -> Option<&Team> {
+        self.teams.get(name)
+    }
+
+    pub fn teams(&self) -> impl Iterator<Item = &Team> {
+        self.teams.values()
+    }
+
+    /// The team a player name or entity UUID is on.
+    pub fn team_of(&self, member: &str) -> Option<&Team> {
+        self.teams.get(self.team_of.get(member)?)
+    }
+
+    /// Creates or replaces an objective. Replacing only updates its display
+    /// name and render type, scores and display positions are kept.
+    pub fn set_objective(&mut self, objective: Objective) -> Option<ScoreboardUpdate> {
+        let update = match self.objectives.get(&objective.name) {
+            Some(existing) if *existing == objective => return None,
+            Some(_) => ScoreboardUpdate::UpdateObjective(objective),
+            None => ScoreboardUpdate::CreateObjective(objective)
+        };
+        self.apply(update.clone());
+        Some(update)
+    }
+
+    /// Removes an objective along with its scores and display positions,
+    /// which the client drops on its own.
+    pub fn remove_objective(&mut self, name: &str) -> Option<ScoreboardUpdate> {
+        if !self.objectives.contains_key(name) {
+            return None;
+        }
+        let update = ScoreboardUpdate::RemoveObjective(name.to_string());
+        self.apply(update.clone());
+        Some(update)
+    }
+
+    /// Sets a score, returning `None` if it did not change or the objective does not exist.
+    pub fn set_score(&mut self, objective: &str, entity: &str, value: i32) -> Option<ScoreboardUpdate> {
+        if !self.objectives.contains_key(objective) || self.score(objective, entity) == Some(value) {
+            return None;
+        }
+        let update = ScoreboardUpdate::SetScore{ entity: entity.to_string(), objective: objective.to_string(), value };
+        self.apply(update.clone());
+        Some(update)
+    }
+
+    pub fn remove_score(&mut self, objective: &str, entity: &str) -> Option<ScoreboardUpdate> {
+        self.score(objective, entity)?;
+        let update = ScoreboardUpdate::RemoveScore{ entity: entity.to_string(), objective: objective.to_string() };
+        self.apply(update.clone());
+        Some(update)
+    }
+
+    /// Shows an objective at a display position, or clears it with `None`.
+    pub fn set_display(&mut self, position: u8, objective: Option<&str>) -> Option<ScoreboardUpdate> {
+        let objective = objective.unwrap_or_default();
+        if self.displays.get(&position).map_or("", String::as_str) == objective
+            || (!objective.is_empty() && !self.objectives.contains_key(objective)) {
+            return None;
+        }
+        let update = ScoreboardUpdate::Display{ position, objective: objective.to_string() };
+        self.apply(update.clone());
+        Some(update)
+    }
+
+    /// Creates a team or updates its info. A new team starts out empty.
+    pub fn set_team(&mut self, name: &str, info: TeamInfo) -> Option<ScoreboardUpdate> {
+        let update = match self.teams.get(name) {
+            Some(team) if team.info == info => return None,
+            Some(_) => ScoreboardUpdate::UpdateTeam{ team: name.to_string(), info },
+            None => ScoreboardUpdate::CreateTeam(Team{ name: name.to_string(), info, members: BTreeSet::new() })
+        };
+        self.apply(update.clone());
+        Some(update)
+    }
+
+    pub fn remove_team(&mut self, name: &str) -> Option<ScoreboardUpdate> {
+        if !self.teams.contains_key(name) {
+            return None;
+        }
+        let update = ScoreboardUpdate::RemoveTeam(name.to_string());
+        self.apply(update.clone());
+        Some(update)
+    }
+
+    /// Puts a member on a team. A member can only be on one team, so it is
+    /// removed from its previous team first, like the vanilla server does.
+    pub fn join_team(&mut self, team: &str, member: &str) -> Vec<ScoreboardUpdate> {
+        let mut updates = Vec::new();
+        if !self.teams.contains_key(team) {
+            return updates;
+        }
+        match self.team_of.get(member) {
+            Some(current) if current == team => return updates,
+            Some(current) => updates.push(ScoreboardUpdate::RemoveMembers{ team: current.clone(), members: vec![member.to_string()] }),
+            None => {}
+        }
+        updates.push(ScoreboardUpdate::AddMembers{ team: team.to_string(), members: vec![member.to_string()] });
+        for update in &updates {
+            self.apply(update.clone());
+        }
+        updates
+    }
+
+    pub fn leave_team(&mut self, member: &str) -> Option<ScoreboardUpdate> {
+        let team = self.team_of.get(member)?.clone();
+        let update = ScoreboardUpdate::RemoveMembers{ team, members: vec![member.to_string()] };
+        self.apply(update.clone());
+        Some(update)
+    }
+
+    /// The updates that recreate this scoreboard on a client that has none,
+    /// objectives before the scores and display positions referring to them.
+    pub fn sync(&self) -> Vec<ScoreboardUpdate> {
+        let mut updates: Vec<ScoreboardUpdate> = self.objectives.values().cloned()
+            .map(ScoreboardUpdate::CreateObjective)
+            .collect();
+        for (objective, scores) in &self.scores {
+            for (entity, value) in scores {
+                updates.push(ScoreboardUpdate::SetScore{ entity: entity.clone(), objective: objective.clone(), value: *value });
+            }
+        }
+        for (position, objective) in &self.displays {
+            updates.push(ScoreboardUpdate::Display{ position: *position, objective: objective.clone() });
+        }
+        updates.extend(self.teams.values().cloned().map(ScoreboardUpdate::CreateTeam));
+        updates
+    }
+
+    /// The updates that clear this scoreboard on a client, for example before
+    /// a proxy switches the player to another server.
+    pub fn clear(&self) -> Vec<ScoreboardUpdate> {
+        self.objectives.keys().cloned().map(ScoreboardUpdate::RemoveObjective)
+            .chain(self.teams.keys().cloned().map(ScoreboardUpdate::RemoveTeam))
+            .collect()
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::{Objective, Scoreboard, ScoreboardUpdate, Team, TeamInfo};
+    use crate::protocol::implementation::steven::v1_17::{Proto_1_17, ScoreboardDisplay, ScoreboardObjective, Teams, TeamsAction, UpdateScore};
+    use steven_protocol::protocol::{LenPrefixed, VarInt};
+
+    impl ScoreboardUpdate{
+        /// Reads the update out of a scoreboard packet, `None` for any other packet.
+        pub fn from_packet(packet: &Proto_1_17) -> Option<Self> {
+            Some(match packet {
+                Proto_1_17::ScoreboardObjective(objective) => {
+                    let value = || Objective{
+                        name: objective.name.clone(),
+                        display_name: objective.value.clone(),
+                        render_type: objective.ty.clone()
+                    };
+                    match objective.mode {
+                        0 => ScoreboardUpdate::CreateObjective(value()),
+                        1 => ScoreboardUpdate::RemoveObjective(objective.name.clone()),
+                        _ => ScoreboardUpdate::UpdateObjective(value())
+                    }
+                }
+                Proto_1_17::UpdateScore(score) => match (score.action, &score.value) {
+                    (0, Some(value)) => ScoreboardUpdate::SetScore{ entity: score.name.clone(), objective: score.object_name.clone(), value: value.0 },
+                    _ => ScoreboardUpdate::RemoveScore{ entity: score.name.clone(), objective: score.object_name.clone() }
+                },
+                Proto_1_17::ScoreboardDisplay(display) => ScoreboardUpdate::Display{ position: display.position, objective: display.name.clone() },
+                Proto_1_17::Teams(teams) => {
+                    let team = teams.name.clone();
+                    match &teams.mode {
+                        TeamsAction::Create{ display_name, flags, name_tag_visibility, collision_rule, formatting, prefix, suffix, players } => ScoreboardUpdate::CreateTeam(Team{
+                            name: team,
+                            info: TeamInfo{
+                                display_name: display_name.clone(),
+                                flags: *flags,
+                                name_tag_visibility: name_tag_visibility.clone(),
+                                collision_rule: collision_rule.clone(),
+                                formatting: formatting.0,
+                                prefix: prefix.clone(),
+                                suffix: suffix.clone()
+                            },
+                            members: players.data.iter().cloned().collect()
+                        }),
+                        TeamsAction::Remove => ScoreboardUpdate::RemoveTeam(team),
+                        TeamsAction::UpdateInfo{ display_name, flags, name_tag_visibility, collision_rule, formatting, prefix, suffix } => ScoreboardUpdate::UpdateTeam{
+                            team,
+                            info: TeamInfo{
+                                display_name: display_name.clone(),
+                                flags: *flags,
+                                name_tag_visibility: name_tag_visibility.clone(),
+                                collision_rule: collision_rule.clone(),
+                                formatting: formatting.0,
+                                prefix: prefix.clone(),
+                                suffix: suffix.clone()
+                            }
+                        },
+                        TeamsAction::AddPlayers{ players } => ScoreboardUpdate::AddMembers{ team, members: players.data.clone() },
+                        TeamsAction::RemovePlayers{ players } => ScoreboardUpdate::RemoveMembers{ team, members: players.data.clone() }
+                    }
+                }
+                _ => return None
+            })
+        }
+
+        pub fn to_packet(&self) -> Proto_1_17 {
+            let objective = |name: &str, mode: u8, display_name: &str, render_type: &str| Proto_1_17::ScoreboardObjective(Box::new(ScoreboardObjective{
+                name: name.to_string(),
+                mode,
+                value: display_name.to_string(),
+                ty: render_type.to_string()
+            }));
+            let teams = |name: &str, mode: TeamsAction| Proto_1_17::Teams(Box::new(Teams{ name: name.to_string(), mode }));
+            match self {
+                ScoreboardUpdate::CreateObjective(o) => objective(&o.name, 0, &o.display_name, &o.render_type),
+                ScoreboardUpdate::RemoveObjective(name) => objective(name, 1, "", ""),
+                ScoreboardUpdate::UpdateObjective(o) => objective(&o.name, 2, &o.display_name, &o.render_type),
+                ScoreboardUpdate::SetScore{ entity, objective, value } => Proto_1_17::UpdateScore(Box::new(UpdateScore{
+                    name: entity.clone(),
+                    action: 0,
+                    object_name: objective.clone(),
+                    value: Some(VarInt(*value))
+                })),
+                ScoreboardUpdate::RemoveScore{ entity, objective } => Proto_1_17::UpdateScore(Box::new(UpdateScore{
+                    name: entity.clone(),
+                    action: 1,
+                    object_name: objective.clone(),
+                    value: None
+                })),
+                ScoreboardUpdate::Display{ position, objective } => Proto_1_17::ScoreboardDisplay(Box::new(ScoreboardDisplay{
+                    position: *position,
+                    name: objective.clone()
+                })),
+                ScoreboardUpdate::CreateTeam(team) => teams(&team.name, TeamsAction::Create{
+                    display_name: team.info.display_name.clone(),
+                    flags: team.info.flags,
+                    name_tag_visibility: team.info.name_tag_visibility.clone(),
+                    collision_rule: team.info.collision_rule.clone(),
+                    formatting: VarInt(team.info.formatting),
+                    prefix: team.info.prefix.clone(),
+                    suffix: team.info.suffix.clone(),
+                    players: LenPrefixed::new(team.members.iter().cloned().collect())
+                }),
+                ScoreboardUpdate::RemoveTeam(name) => teams(name, TeamsAction::Remove),
+                ScoreboardUpdate::UpdateTeam{ team, info } => teams(team, TeamsAction::UpdateInfo{
+                    display_name: info.display_name.clone(),
+                    flags: info.flags,
+                    name_tag_visibility: info.name_tag_visibility.clone(),
+                    collision_rule: info.collision_rule.clone(),
+                    formatting: VarInt(info.formatting),
+                    prefix: info.prefix.clone(),
+                    suffix: info.suffix.clone()
+                }),
+                ScoreboardUpdate::AddMembers{ team, members } => teams(team, TeamsAction::AddPlayers{ players: LenPrefixed::new(members.clone()) }),
+                ScoreboardUpdate::RemoveMembers{ team, members } => teams(team, TeamsAction::RemovePlayers{ players: LenPrefixed::new(members.clone()) })
+            }
+        }
+    }
+
+    impl Scoreboard{
+        /// Applies a scoreboard packet, returning whether the packet was one.
+        pub fn handle(&mut self, packet: &Proto_1_17) -> bool {
+            match ScoreboardUpdate::from_packet(packet) {
+                Some(update) => {
+                    self.apply(update);
+                    true
+                }
+                None => false
+            }
+        }
+    }
+}
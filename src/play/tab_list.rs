@@ -0,0 +1,405 @@
+//! The player list shown when holding tab, as maintained by PlayerInfo.
+//!
+//! A PlayerInfo body is `[VarInt action][VarInt count]` followed by `count`
+//! entries of `[UUID]` and the fields of the action:
+//!
+//! * 0, add: `[String name][VarInt property count][properties][VarInt gamemode][VarInt latency][bool has display name][String display name]`
+//! * 1, update gamemode: `[VarInt gamemode]`
+//! * 2, update latency: `[VarInt latency]`
+//! * 3, update display name: `[bool has display name][String display name]`
+//! * 4, remove: nothing
+//!
+//! Properties are encoded as `[String name][String value][bool signed][String signature if signed]`.
+//! Display names are chat components and kept as their JSON.
+//!
+//! `PlayerInfoAction` is the field of the PlayerInfo packets of 1.14.4 to 1.17.1.
+use crate::io::borrowed::{read_str, read_var_int};
+use crate::proxy::bungeecord::ProfileProperty;
+use crate::segment::implementation::var::write_var_int;
+use crate::segment::Segment;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TabEntry{
+    pub uuid: u128,
+    pub name: String,
+    pub properties: Vec<ProfileProperty>,
+    pub gamemode: i32,
+    /// Latency in milliseconds, which the client shows as signal bars.
+    pub latency: i32,
+    /// JSON chat component shown instead of the name.
+    pub display_name: Option<String>
+}
+
+/// The decoded body of a PlayerInfo packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlayerInfoAction{
+    Add(Vec<TabEntry>),
+    UpdateGamemode(Vec<(u128, i32)>),
+    UpdateLatency(Vec<(u128, i32)>),
+    UpdateDisplayName(Vec<(u128, Option<String>)>),
+    Remove(Vec<u128>)
+}
+
+impl PlayerInfoAction{
+    pub fn decode(mut body: &[u8]) -> std::io::Result<Self> {
+        let buffer = &mut body;
+        let action = read_var_int(buffer)?;
+        let count = read_var_int(buffer)?;
+        if count < 0 {
+            return Err(invalid("negative player info count"));
+        }
+        // Every entry takes at least its UUID, which bounds the allocation.
+        let capacity = std::cmp::min(count as usize, buffer.len() / 16);
+        let decoded = match action {
+            0 => {
+                let mut entries = Vec::with_capacity(capacity);
+                for _ in 0..count {
+                    let uuid = read_uuid(buffer)?;
+                    let name = read_str(buffer)?.to_string();
+                    let properties = read_properties(buffer)?;
+                    let gamemode = read_var_int(buffer)?;
+                    let latency = read_var_int(buffer)?;
+                    let display_name = read_optional_str(buffer)?;
+                    entries.push(TabEntry{ uuid, name, properties, gamemode, latency, display_name });
+                }
+                PlayerInfoAction::Add(entries)
+            }
+            1 | 2 => {
+                let mut updates = Vec::with_capacity(capacity);
+                for _ in 0..count {
+                    updates.push((read_uuid(buffer)?, read_var_int(buffer)?));
+                }
+                if action == 1 {
+                    PlayerInfoAction::UpdateGamemode(updates)
+                }else{
+                    PlayerInfoAction::UpdateLatency(updates)
+                }
+            }
+            3 => {
+                let mut updates = Vec::with_capacity(capacity);
+                for _ in 0..count {
+                    updates.push((read_uuid(buffer)?, read_optional_str(buffer)?));
+                }
+                PlayerInfoAction::UpdateDisplayName(updates)
+            }
+            4 => {
+                let mut removed = Vec::with_capacity(capacity);
+                for _ in 0..count {
+                    removed.push(read_uuid(buffer)?);
+                }
+                PlayerInfoAction::Remove(removed)
+            }
+            action => return Err(invalid(&format!("unknown player info action {}", action)))
+        };
+        if !buffer.is_empty() {
+            return Err(invalid("player info has trailing bytes"));
+        }
+        Ok(decoded)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        let (action, count) = match self {
+            PlayerInfoAction::Add(entries) => (0, entries.len()),
+            PlayerInfoAction::UpdateGamemode(updates) => (1, updates.len()),
+            PlayerInfoAction::UpdateLatency(updates) => (2, updates.len()),
+            PlayerInfoAction::UpdateDisplayName(updates) => (3, updates.len()),
+            PlayerInfoAction::Remove(removed) => (4, removed.len())
+        };
+        let _ = write_var_int(action, &mut body);
+        let _ = write_var_int(count as i32, &mut body);
+        match self {
+            PlayerInfoAction::Add(entries) => for entry in entries {
+                body.extend_from_slice(&entry.uuid.to_be_bytes());
+                write_str(&entry.name, &mut body);
+                let _ = write_var_int(entry.properties.len() as i32, &mut body);
+                for property in &entry.properties {
+                    write_str(&property.name, &mut body);
+                    write_str(&property.value, &mut body);
+                    write_optional_str(property.signature.as_deref(), &mut body);
+                }
+                let _ = write_var_int(entry.gamemode, &mut body);
+                let _ = write_var_int(entry.latency, &mut body);
+                write_optional_str(entry.display_name.as_deref(), &mut body);
+            }
+            PlayerInfoAction::UpdateGamemode(updates) | PlayerInfoAction::UpdateLatency(updates) => for (uuid, value) in updates {
+                body.extend_from_slice(&uuid.to_be_bytes());
+                let _ = write_var_int(*value, &mut body);
+            }
+            PlayerInfoAction::UpdateDisplayName(updates) => for (uuid, display_name) in updates {
+                body.extend_from_slice(&uuid.to_be_bytes());
+                write_optional_str(display_name.as_deref(), &mut body);
+            }
+            PlayerInfoAction::Remove(removed) => for uuid in removed {
+                body.extend_from_slice(&uuid.to_be_bytes());
+            }
+        }
+        body
+    }
+}
+
+/// An empty add.
+impl Default for PlayerInfoAction{
+    fn default() -> Self {
+        PlayerInfoAction::Add(Vec::new())
+    }
+}
+
+/// The action is the whole body of PlayerInfo, so reading takes the rest of the stream.
+impl Segment for PlayerInfoAction{
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()> {
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        *self = Self::decode(&body)?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.encode())
+    }
+}
+
+/// The player list as the client sees it, keyed by UUID.
+///
+/// Updates for players that were never added are ignored, like the vanilla
+/// client does.
+#[derive(Debug, Clone, Default)]
+pub struct TabList{
+    entries: HashMap<u128, TabEntry>,
+    header: String,
+    footer: String
+}
+
+impl TabList{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&mut self, action: PlayerInfoAction) {
+        match action {
+            PlayerInfoAction::Add(entries) => for entry in entries {
+                self.entries.insert(entry.uuid, entry);
+            }
+            PlayerInfoAction::UpdateGamemode(updates) => for (uuid, gamemode) in updates {
+                if let Some(entry) = self.entries.get_mut(&uuid) {
+                    entry.gamemode = gamemode;
+                }
+            }
+            PlayerInfoAction::UpdateLatency(updates) => for (uuid, latency) in updates {
+                if let Some(entry) = self.entries.get_mut(&uuid) {
+                    entry.latency = latency;
+                }
+            }
+            PlayerInfoAction::UpdateDisplayName(updates) => for (uuid, display_name) in updates {
+                if let Some(entry) = self.entries.get_mut(&uuid) {
+                    entry.display_name = display_name;
+                }
+            }
+            PlayerInfoAction::Remove(removed) => for uuid in removed {
+                self.entries.remove(&uuid);
+            }
+        }
+    }
+
+    /// Decodes and applies the body of a PlayerInfo packet.
+    pub fn apply_body(&mut self, body: &[u8]) -> std::io::Result<()> {
+        self.apply(PlayerInfoAction::decode(body)?);
+        Ok(())
+    }
+
+    pub fn get(&self, uuid: u128) -> Option<&TabEntry> {
+        self.entries.get(&uuid)
+    }
+
+    /// Looks a player up by name, which is case sensitive like on the wire.
+    pub fn by_name(&self, name: &str) -> Option<&TabEntry> {
+        self.entries.values().find(|entry| entry.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TabEntry> {
+        self.entries.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The header as a JSON chat component, empty if none was sent.
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    pub fn footer(&self) -> &str {
+        &self.footer
+    }
+
+    pub fn set_header_footer(&mut self, header: String, footer: String) {
+        self.header = header;
+        self.footer = footer;
+    }
+
+    /// The actions that rebuild this list on a client that has an empty one,
+    /// for example after a proxy switched the player to another server.
+    pub fn snapshot(&self) -> PlayerInfoAction {
+        PlayerInfoAction::Add(self.entries.values().cloned().collect())
+    }
+
+    /// The action that clears this list on the client.
+    pub fn clear_action(&self) -> PlayerInfoAction {
+        PlayerInfoAction::Remove(self.entries.keys().copied().collect())
+    }
+}
+
+fn read_uuid(buffer: &mut &[u8]) -> std::io::Result<u128> {
+    if buffer.len() < 16 {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "player info is truncated"));
+    }
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&buffer[..16]);
+    *buffer = &buffer[16..];
+    Ok(u128::from_be_bytes(bytes))
+}
+
+fn read_bool(buffer: &mut &[u8]) -> std::io::Result<bool> {
+    let (&byte, rest) = buffer.split_first()
+        .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "player info is truncated"))?;
+    *buffer = rest;
+    Ok(byte != 0)
+}
+
+fn read_optional_str(buffer: &mut &[u8]) -> std::io::Result<Option<String>> {
+    Ok(if read_bool(buffer)? {
+        Some(read_str(buffer)?.to_string())
+    }else{
+        None
+    })
+}
+
+fn read_properties(buffer: &mut &[u8]) -> std::io::Result<Vec<ProfileProperty>> {
+    let count = read_var_int(buffer)?;
+    if count < 0 {
+        return Err(invalid("negative property count"));
+    }
+    let mut properties = Vec::new();
+    for _ in 0..count {
+        let name = read_str(buffer)?.to_string();
+        let value = read_str(buffer)?.to_string();
+        let signature = read_optional_str(buffer)?;
+        properties.push(ProfileProperty{ name, value, signature });
+    }
+    Ok(properties)
+}
+
+fn write_str(value: &str, out: &mut Vec<u8>) {
+    let _ = write_var_int(value.len() as i32, out);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn write_optional_str(value: Option<&str>, out: &mut Vec<u8>) {
+    match value {
+        Some(value) => {
+            out.push(1);
+            write_str(value, out);
+        }
+        None => out.push(0)
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::TabList;
+    use crate::io::borrowed::read_str;
+    use crate::protocol::implementation::steven::v1_17::Proto_1_17;
+    use crate::segment::Segment;
+
+    impl TabList{
+        /// Applies PlayerInfo and PlayerListHeaderFooter, returning whether the
+        /// packet was one of them.
+        pub fn handle(&mut self, packet: &Proto_1_17) -> std::io::Result<bool> {
+            match packet {
+                Proto_1_17::PlayerInfo(info) => {
+                    self.apply(info.action.clone());
+                    Ok(true)
+                }
+                Proto_1_17::PlayerListHeaderFooter(texts) => {
+                    let mut body = Vec::with_capacity(texts.serialized_len());
+                    texts.write_to_stream(&mut body)?;
+                    let buffer = &mut &body[..];
+                    let header = read_str(buffer)?.to_string();
+                    let footer = read_str(buffer)?.to_string();
+                    self.set_header_footer(header, footer);
+                    Ok(true)
+                }
+                _ => Ok(false)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(uuid: u128, name: &str) -> TabEntry {
+        TabEntry{
+            uuid,
+            name: name.to_string(),
+            properties: vec![ProfileProperty{ name: "textures".to_string(), value: "e30=".to_string(), signature: None }],
+            gamemode: 1,
+            latency: 42,
+            display_name: Some(r#"{"text":"Admin"}"#.to_string())
+        }
+    }
+
+    #[test]
+    fn actions_round_trip_as_segments() {
+        let actions = vec![
+            PlayerInfoAction::Add(vec![entry(1, "Notch"), entry(2, "jeb_")]),
+            PlayerInfoAction::UpdateGamemode(vec![(1, 3)]),
+            PlayerInfoAction::UpdateLatency(vec![(2, 150)]),
+            PlayerInfoAction::UpdateDisplayName(vec![(1, None)]),
+            PlayerInfoAction::Remove(vec![1, 2])
+        ];
+        for action in actions {
+            let mut body = Vec::new();
+            action.write_to_stream(&mut body).unwrap();
+            assert_eq!(body.len(), action.serialized_len());
+            let mut read = PlayerInfoAction::default();
+            read.read_from_stream(&mut &body[..]).unwrap();
+            assert_eq!(read, action);
+        }
+    }
+
+    #[test]
+    fn malformed_bodies_are_errors() {
+        assert_eq!(PlayerInfoAction::decode(&[5, 0]).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(PlayerInfoAction::decode(&[4, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F]).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(PlayerInfoAction::decode(&[4, 1, 0]).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(PlayerInfoAction::decode(&[4, 0, 0]).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn updates_apply_to_added_players_only() {
+        let mut list = TabList::new();
+        list.apply(PlayerInfoAction::Add(vec![entry(1, "Notch")]));
+        list.apply(PlayerInfoAction::UpdateLatency(vec![(1, 7), (2, 9)]));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.by_name("Notch").unwrap().latency, 7);
+        assert!(list.get(2).is_none());
+        let clear = list.clear_action();
+        list.apply(clear);
+        assert!(list.is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+//! Bookkeeping for the exchanges in play where the client has to echo an id:
+//! TeleportPlayer answered by TeleportConfirm, and PlayPing answered by PlayPong.
+//!
+//! The server side hands out ids, matches the answers and reports exchanges
+//! that were not answered in time. A new teleport supersedes the ones still
+//! pending, like in vanilla, while pongs have to arrive in the order the
+//! pings were sent.
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind{
+    Teleport,
+    Ping
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionError{
+    /// No exchange with this id is pending.
+    Unknown(Kind, i32),
+    /// A pong for a later ping arrived before the pong of `expected`.
+    OutOfOrder{ expected: i32, received: i32 }
+}
+
+impl TransactionError{
+    pub fn description(&self) -> String {
+        match self {
+            TransactionError::Unknown(kind, id) => format!("no {:?} with id {} is pending", kind, id),
+            TransactionError::OutOfOrder{ expected, received } => format!("expected pong {}, received {}", expected, received)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transactions{
+    timeout: Duration,
+    next_teleport: i32,
+    next_ping: i32,
+    teleports: VecDeque<(i32, Instant)>,
+    pings: VecDeque<(i32, Instant)>
+}
+
+impl Default for Transactions{
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30))
+    }
+}
+
+impl Transactions{
+    /// Exchanges not answered within `timeout` are reported by `expired`.
+    pub fn new(timeout: Duration) -> Self {
+        Transactions{
+            timeout,
+            next_teleport: 1,
+            next_ping: 1,
+            teleports: VecDeque::new(),
+            pings: VecDeque::new()
+        }
+    }
+
+    /// The id for a TeleportPlayer about to be sent.
+    pub fn teleport(&mut self, now: Instant) -> i32 {
+        let id = next(&mut self.next_teleport);
+        self.teleports.push_back((id, now));
+        id
+    }
+
+    /// The id for a PlayPing about to be sent.
+    pub fn ping(&mut self, now: Instant) -> i32 {
+        let id = next(&mut self.next_ping);
+        self.pings.push_back((id, now));
+        id
+    }
+
+    /// Handles a TeleportConfirm, returning how long the client took. Teleports
+    /// sent before the confirmed one are dropped.
+    pub fn confirm_teleport(&mut self, id: i32, now: Instant) -> Result<Duration, TransactionError> {
+        let position = self.teleports.iter().position(|(pending, _)| *pending == id)
+            .ok_or(TransactionError::Unknown(Kind::Teleport, id))?;
+        let (_, sent) = self.teleports.drain(..=position).next_back().expect("drained at least one teleport");
+        Ok(now.saturating_duration_since(sent))
+    }
+
+    /// Handles a PlayPong, returning the round trip time.
+    pub fn pong(&mut self, id: i32, now: Instant) -> Result<Duration, TransactionError> {
+        match self.pings.front() {
+            Some((expected, sent)) if *expected == id => {
+                let sent = *sent;
+                self.pings.pop_front();
+                Ok(now.saturating_duration_since(sent))
+            }
+            Some((expected, _)) if self.pings.iter().any(|(pending, _)| *pending == id) => {
+                Err(TransactionError::OutOfOrder{ expected: *expected, received: id })
+            }
+            _ => Err(TransactionError::Unknown(Kind::Ping, id))
+        }
+    }
+
+    /// Whether the client still has to confirm a teleport. Vanilla ignores
+    /// movement from the client until it did.
+    pub fn awaiting_teleport(&self) -> bool {
+        !self.teleports.is_empty()
+    }
+
+    pub fn pending(&self, kind: Kind) -> usize {
+        match kind {
+            Kind::Teleport => self.teleports.len(),
+            Kind::Ping => self.pings.len()
+        }
+    }
+
+    /// Removes and returns the exchanges that were not answered within the timeout.
+    pub fn expired(&mut self, now: Instant) -> Vec<(Kind, i32)> {
+        let mut expired = Vec::new();
+        for (kind, queue) in [(Kind::Teleport, &mut self.teleports), (Kind::Ping, &mut self.pings)] {
+            while let Some(&(id, sent)) = queue.front() {
+                if now.saturating_duration_since(sent) < self.timeout {
+                    break;
+                }
+                queue.pop_front();
+                expired.push((kind, id));
+            }
+        }
+        expired
+    }
+}
+
+fn next(counter: &mut i32) -> i32 {
+    let id = *counter;
+    *counter = counter.checked_add(1).unwrap_or(1);
+    id
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::{Transactions, TransactionError};
+    use crate::protocol::implementation::steven::v1_17::{PlayPing, PlayPong, Proto_1_17, TeleportConfirm};
+    use std::time::{Duration, Instant};
+    use steven_protocol::protocol::VarInt;
+
+    impl Transactions{
+        /// Server side: matches TeleportConfirm and PlayPong against the pending
+        /// exchanges, returning the time they took. Other packets give `Ok(None)`.
+        pub fn handle(&mut self, packet: &Proto_1_17, now: Instant) -> Result<Option<Duration>, TransactionError> {
+            match packet {
+                Proto_1_17::TeleportConfirm(confirm) => self.confirm_teleport(confirm.teleport_id.0, now).map(Some),
+                Proto_1_17::PlayPong(pong) => self.pong(pong.id, now).map(Some),
+                _ => Ok(None)
+            }
+        }
+
+        pub fn ping_packet(&mut self, now: Instant) -> PlayPing {
+            PlayPing{ id: self.ping(now) }
+        }
+    }
+
+    /// Client side: the answer a client owes for TeleportPlayer and PlayPing.
+    pub fn answer(packet: &Proto_1_17) -> Option<Proto_1_17> {
+        match packet {
+            Proto_1_17::TeleportPlayer(teleport) => Some(Proto_1_17::TeleportConfirm(Box::new(TeleportConfirm{ teleport_id: VarInt(teleport.teleport_id.0) }))),
+            Proto_1_17::PlayPing(ping) => Some(Proto_1_17::PlayPong(Box::new(PlayPong{ id: ping.id }))),
+            _ => None
+        }
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+pub use self::v1_17::answer;
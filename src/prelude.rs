@@ -0,0 +1,24 @@
+//! The items most users of the crate need, under paths that stay the same
+//! across releases:
+//!
+//! ```ignore
+//! use mc_protocol::prelude::*;
+//! ```
+//!
+//! `Latest` is the protocol of the newest supported version and changes with
+//! the releases that add one. Code that has to keep speaking one version names
+//! it through `protocol::implementation::steven` instead.
+pub use crate::segment::{Segment, SegmentEnum};
+
+#[cfg(feature = "std")]
+pub use crate::protocol::{ClientBound, Direction, Packet, PacketOf, PlayState, Protocol, RawPacket, ServerBound, State};
+#[cfg(feature = "std")]
+pub use crate::segment::{Identifier, NetworkNbt, Slot, Tag};
+#[cfg(feature = "std")]
+pub use crate::connection::stream::PacketStream;
+
+#[cfg(feature = "steven_protocol")]
+pub use crate::segment::{LenPrefixed, LenPrefixedBytes, VarInt, VarLong};
+
+#[cfg(feature = "steven_shared")]
+pub use crate::protocol::implementation::steven::latest::Proto_1_21 as Latest;
@@ -0,0 +1,231 @@
+//! Generates `define_protocol!` invocations from the `protocol.json` files of
+//! PrismarineJS minecraft-data, meant to be run from a build script:
+//!
+//! ```ignore
+//! // build.rs, with mc-protocol as build dependency with the `codegen` feature
+//! let json = std::fs::read_to_string("minecraft-data/data/pc/1.17/protocol.json")?;
+//! let code = Generator::new("Proto_1_17", "1.17", 755).generate(&json)?;
+//! std::fs::write(Path::new(&std::env::var("OUT_DIR")?).join("proto_1_17.rs"), code)?;
+//!
+//! // in the crate itself, with State, Direction and the mapped types imported
+//! include!(concat!(env!("OUT_DIR"), "/proto_1_17.rs"));
+//! ```
+//!
+//! Types are mapped by their minecraft-data name, by default to the
+//! stevenarella types the hand-written definitions use. Varint prefixed arrays
+//! and buffers of mapped types become `LenPrefixed` and `LenPrefixedBytes`,
+//! and options become a `has_` flag followed by the conditional field.
+//! Anything else, like switches and nested containers, can not be expressed:
+//! the packet then ends with `rest: Vec<u8>` holding the remaining bytes,
+//! documented with the type it stands in for.
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::io::{Error, ErrorKind};
+
+const STATES: [(&str, &str); 5] = [
+    ("handshaking", "State::Handshaking"),
+    ("status", "State::Status"),
+    ("login", "State::Login"),
+    ("configuration", "State::Configuration"),
+    ("play", "State::Play")
+];
+const DIRECTIONS: [(&str, &str, &str); 2] = [
+    ("toServer", "Direction::ServerBound", "Serverbound"),
+    ("toClient", "Direction::ClientBound", "Clientbound")
+];
+const KEYWORDS: [&str; 20] = [
+    "as", "async", "await", "box", "const", "crate", "dyn", "enum", "fn", "impl",
+    "in", "let", "loop", "match", "mod", "move", "ref", "self", "use", "where"
+];
+
+#[derive(Debug, Clone)]
+pub struct Generator{
+    enum_name: String,
+    version_name: String,
+    protocol_version: i32,
+    macro_path: String,
+    types: BTreeMap<String, String>
+}
+
+impl Generator{
+    pub fn new(enum_name: &str, version_name: &str, protocol_version: i32) -> Self {
+        let types = [
+            ("bool", "bool"), ("i8", "i8"), ("u8", "u8"), ("i16", "i16"), ("u16", "u16"),
+            ("i32", "i32"), ("i64", "i64"), ("u64", "u64"), ("f32", "f32"), ("f64", "f64"),
+            ("varint", "VarInt"), ("varlong", "VarLong"), ("string", "String"), ("UUID", "UUID"),
+            ("position", "Position"), ("restBuffer", "Vec<u8>"), ("slot", "Option<item::Stack>"),
+            ("nbt", "Option<nbt::NamedTag>"), ("optionalNbt", "Option<nbt::NamedTag>"),
+            ("entityMetadata", "types::Metadata")
+        ].iter().map(|(name, ty)| (name.to_string(), ty.to_string())).collect();
+        Generator{
+            enum_name: enum_name.to_string(),
+            version_name: version_name.to_string(),
+            protocol_version,
+            macro_path: "mc_protocol::define_protocol".to_string(),
+            types
+        }
+    }
+
+    /// Maps a minecraft-data type, replacing the default mapping if there is one.
+    pub fn with_type(mut self, name: &str, rust_type: &str) -> Self {
+        self.types.insert(name.to_string(), rust_type.to_string());
+        self
+    }
+
+    /// The path the macro is invoked by, `crate::define_protocol` within this crate.
+    pub fn with_macro_path(mut self, path: &str) -> Self {
+        self.macro_path = path.to_string();
+        self
+    }
+
+    pub fn generate(&self, protocol_json: &str) -> std::io::Result<String> {
+        let protocol: Value = serde_json::from_str(protocol_json)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut names = HashSet::new();
+        let mut out = String::new();
+        let _ = writeln!(out, "// Generated from minecraft-data, do not edit.");
+        let _ = writeln!(out, "{}!(pub {}, {:?}, {} {{", self.macro_path, self.enum_name, self.version_name, self.protocol_version);
+        for (state_key, state) in STATES.iter() {
+            let mut directions = String::new();
+            for (direction_key, direction, suffix) in DIRECTIONS.iter() {
+                let types = match protocol.get(state_key).and_then(|state| state.get(direction_key)).and_then(|direction| direction.get("types")) {
+                    Some(types) => types,
+                    None => continue
+                };
+                let mut packets = String::new();
+                for (id, packet) in packet_ids(types)? {
+                    let mut name = camel_case(&packet);
+                    if !names.insert(name.clone()) {
+                        name.push_str(suffix);
+                        names.insert(name.clone());
+                    }
+                    let definition = types.get(format!("packet_{}", packet)).ok_or_else(|| invalid(format!("packet_{} is not defined", packet)))?;
+                    let _ = writeln!(packets, "            0x{:02x} => {} {{", id, name);
+                    packets.push_str(&self.fields(definition));
+                    let _ = writeln!(packets, "            }},");
+                }
+                if !packets.is_empty() {
+                    let _ = writeln!(directions, "        {} => {{\n{}        }},", direction, packets);
+                }
+            }
+            if !directions.is_empty() {
+                let _ = writeln!(out, "    {} => {{\n{}    }},", state, directions);
+            }
+        }
+        let _ = writeln!(out, "}});");
+        Ok(out)
+    }
+
+    fn fields(&self, definition: &Value) -> String {
+        let mut out = String::new();
+        let fields = match container(definition) {
+            Some(fields) => fields,
+            None => {
+                rest(&mut out, definition);
+                return out;
+            }
+        };
+        for field in fields {
+            let name = match field.get("name").and_then(Value::as_str) {
+                Some(name) => field_name(name),
+                None => {
+                    rest(&mut out, field);
+                    break;
+                }
+            };
+            let ty = &field["type"];
+            if let Some(rust_type) = self.rust_type(ty) {
+                let _ = writeln!(out, "                {}: {},", name, rust_type);
+            }else if let Some(rust_type) = option(ty).and_then(|inner| self.rust_type(inner)) {
+                let _ = writeln!(out, "                has_{}: bool,", name);
+                let _ = writeln!(out, "                {}: Option<{}> where |p| {{p.has_{}}},", name, rust_type, name);
+            }else{
+                rest(&mut out, field);
+                break;
+            }
+        }
+        out
+    }
+
+    fn rust_type(&self, ty: &Value) -> Option<String> {
+        if let Some(name) = ty.as_str() {
+            return self.types.get(name).cloned();
+        }
+        let (kind, options) = (ty.get(0)?.as_str()?, ty.get(1)?);
+        if options.get("countType").and_then(Value::as_str) != Some("varint") {
+            return None;
+        }
+        match kind {
+            "array" => Some(format!("LenPrefixed<VarInt, {}>", self.rust_type(options.get("type")?)?)),
+            "buffer" => Some("LenPrefixedBytes<VarInt>".to_string()),
+            _ => None
+        }
+    }
+}
+
+/// The packet ids and names of a direction, from the mapper of its `packet` type.
+fn packet_ids(types: &Value) -> std::io::Result<Vec<(i32, String)>> {
+    let fields = types.get("packet").and_then(container).ok_or_else(|| invalid("packet is not a container".to_string()))?;
+    let mappings = fields.iter()
+        .find(|field| field.get("name").and_then(Value::as_str) == Some("name"))
+        .and_then(|field| field.get("type")?.get(1)?.get("mappings")?.as_object())
+        .ok_or_else(|| invalid("packet has no name mappings".to_string()))?;
+    let mut ids = Vec::with_capacity(mappings.len());
+    for (id, name) in mappings {
+        let parsed = i32::from_str_radix(id.trim_start_matches("0x"), 16).map_err(|_| invalid(format!("{} is not a packet id", id)))?;
+        let name = name.as_str().ok_or_else(|| invalid(format!("packet 0x{:02x} has no name", parsed)))?;
+        ids.push((parsed, name.to_string()));
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+fn container(ty: &Value) -> Option<&Vec<Value>> {
+    if ty.get(0)?.as_str()? != "container" {
+        return None;
+    }
+    ty.get(1)?.as_array()
+}
+
+fn option(ty: &Value) -> Option<&Value> {
+    if ty.get(0)?.as_str()? != "option" {
+        return None;
+    }
+    ty.get(1)
+}
+
+fn rest(out: &mut String, ty: &Value) {
+    let _ = writeln!(out, "                /// The remaining bytes, minecraft-data type: `{}`", ty);
+    let _ = writeln!(out, "                rest: Vec<u8>,");
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('_').filter(|part| !part.is_empty()).map(|part| {
+        let mut chars = part.chars();
+        chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+    }).collect()
+}
+
+fn field_name(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    let mut previous_lowercase = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && previous_lowercase {
+            snake.push('_');
+        }
+        previous_lowercase = c.is_ascii_lowercase() || c.is_ascii_digit();
+        snake.push(c.to_ascii_lowercase());
+    }
+    if snake == "type" {
+        "ty".to_string()
+    }else if KEYWORDS.contains(&snake.as_str()) {
+        snake + "_"
+    }else{
+        snake
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,196 @@
+//! Compares two protocol definitions by their packet descriptors.
+//!
+//! Packets are matched by state, direction and name, so a packet that is
+//! renamed shows up as removed and added. Fields are matched by name; their
+//! type, wire type, condition and version bounds are compared as written in
+//! the definitions, ignoring whitespace.
+use crate::protocol::{Direction, FieldDescriptor, PacketDescriptor, Protocol, State};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldChange{
+    Added(FieldDescriptor),
+    Removed(FieldDescriptor),
+    /// The field is defined differently, see the module documentation.
+    Changed{
+        old: FieldDescriptor,
+        new: FieldDescriptor
+    },
+    /// The field moved relative to the fields both versions have.
+    Moved{
+        name: &'static str,
+        old_index: usize,
+        new_index: usize
+    }
+}
+
+/// A packet that differs. Added packets have no old id, removed ones no new id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacketDiff{
+    pub name: &'static str,
+    pub state: State,
+    pub direction: Direction,
+    pub old_id: Option<i32>,
+    pub new_id: Option<i32>,
+    /// Empty for added and removed packets.
+    pub fields: Vec<FieldChange>
+}
+
+impl PacketDiff{
+    pub fn is_added(&self) -> bool {
+        self.old_id.is_none()
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.new_id.is_none()
+    }
+
+    pub fn is_renumbered(&self) -> bool {
+        matches!((self.old_id, self.new_id), (Some(old), Some(new)) if old != new)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolDiff{
+    pub old: (&'static str, i32),
+    pub new: (&'static str, i32),
+    /// Sorted by state, direction and id.
+    pub packets: Vec<PacketDiff>
+}
+
+impl ProtocolDiff{
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    pub fn added(&self) -> impl Iterator<Item = &PacketDiff> {
+        self.packets.iter().filter(|packet| packet.is_added())
+    }
+
+    pub fn removed(&self) -> impl Iterator<Item = &PacketDiff> {
+        self.packets.iter().filter(|packet| packet.is_removed())
+    }
+
+    pub fn renumbered(&self) -> impl Iterator<Item = &PacketDiff> {
+        self.packets.iter().filter(|packet| packet.is_renumbered())
+    }
+
+    /// Packets both versions have with fields that changed.
+    pub fn changed(&self) -> impl Iterator<Item = &PacketDiff> {
+        self.packets.iter().filter(|packet| !packet.fields.is_empty())
+    }
+}
+
+/// Compares the packets of `A` to the ones of `B`.
+pub fn diff<A: Protocol, B: Protocol>() -> ProtocolDiff {
+    let old = A::packets();
+    let new = B::packets();
+    let mut packets = Vec::new();
+    for packet in old {
+        match find(new, packet) {
+            Some(other) => {
+                let fields = diff_fields(packet.fields, other.fields);
+                if packet.id != other.id || !fields.is_empty() {
+                    packets.push(PacketDiff{ name: packet.name, state: packet.state, direction: packet.direction, old_id: Some(packet.id), new_id: Some(other.id), fields });
+                }
+            }
+            None => packets.push(PacketDiff{ name: packet.name, state: packet.state, direction: packet.direction, old_id: Some(packet.id), new_id: None, fields: Vec::new() })
+        }
+    }
+    for packet in new.iter().filter(|packet| find(old, packet).is_none()) {
+        packets.push(PacketDiff{ name: packet.name, state: packet.state, direction: packet.direction, old_id: None, new_id: Some(packet.id), fields: Vec::new() });
+    }
+    packets.sort_by_key(|packet| (packet.state.id(), packet.direction.id(), packet.new_id.or(packet.old_id)));
+    ProtocolDiff{ old: (A::NAME, A::PROTOCOL), new: (B::NAME, B::PROTOCOL), packets }
+}
+
+fn find<'a>(packets: &'a [PacketDescriptor], packet: &PacketDescriptor) -> Option<&'a PacketDescriptor> {
+    packets.iter().find(|other| other.state == packet.state && other.direction == packet.direction && other.name == packet.name)
+}
+
+pub(crate) fn diff_fields(old: &[FieldDescriptor], new: &[FieldDescriptor]) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let common_old: Vec<&FieldDescriptor> = old.iter().filter(|field| new.iter().any(|other| other.name == field.name)).collect();
+    let common_new: Vec<&FieldDescriptor> = new.iter().filter(|field| old.iter().any(|other| other.name == field.name)).collect();
+    for field in old.iter().filter(|field| !new.iter().any(|other| other.name == field.name)) {
+        changes.push(FieldChange::Removed(*field));
+    }
+    for field in new.iter().filter(|field| !old.iter().any(|other| other.name == field.name)) {
+        changes.push(FieldChange::Added(*field));
+    }
+    for (old_index, field) in common_old.iter().enumerate() {
+        let new_index = common_new.iter().position(|other| other.name == field.name).expect("field is in both");
+        let other = common_new[new_index];
+        if !same_definition(field, other) {
+            changes.push(FieldChange::Changed{ old: **field, new: *other });
+        }
+        if old_index != new_index {
+            changes.push(FieldChange::Moved{ name: field.name, old_index, new_index });
+        }
+    }
+    changes
+}
+
+fn same_definition(a: &FieldDescriptor, b: &FieldDescriptor) -> bool {
+    normalize(a.value_type) == normalize(b.value_type)
+        && a.wire_type.map(normalize) == b.wire_type.map(normalize)
+        && a.condition.map(normalize) == b.condition.map(normalize)
+        && a.custom == b.custom
+        && a.since == b.since
+        && a.until == b.until
+}
+
+fn normalize(tokens: &str) -> String {
+    tokens.split_whitespace().collect()
+}
+
+impl Display for FieldChange{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldChange::Added(field) => write!(f, "+ {}: {}", field.name, field.value_type),
+            FieldChange::Removed(field) => write!(f, "- {}: {}", field.name, field.value_type),
+            FieldChange::Changed{ old, new } => write!(f, "~ {}: {} -> {}", old.name, describe(old), describe(new)),
+            FieldChange::Moved{ name, old_index, new_index } => write!(f, "~ {}: moved from {} to {}", name, old_index, new_index)
+        }
+    }
+}
+
+fn describe(field: &FieldDescriptor) -> String {
+    let mut description = field.value_type.to_string();
+    if let Some(wire_type) = field.wire_type {
+        description += &format!(" as {}", wire_type);
+    }
+    if let Some(since) = field.since {
+        description += &format!(" since {}", since);
+    }
+    if let Some(until) = field.until {
+        description += &format!(" until {}", until);
+    }
+    if field.custom {
+        description += " (custom codec)";
+    }
+    if let Some(condition) = field.condition {
+        description += &format!(" where {}", condition);
+    }
+    description
+}
+
+/// One line per packet, followed by its field changes.
+impl Display for ProtocolDiff{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} ({}) -> {} ({})", self.old.0, self.old.1, self.new.0, self.new.1)?;
+        for packet in &self.packets {
+            let prefix = format!("{:?} {:?} {}", packet.state, packet.direction, packet.name);
+            match (packet.old_id, packet.new_id) {
+                (None, Some(id)) => writeln!(f, "+ {} 0x{:02X}", prefix, id)?,
+                (Some(id), None) => writeln!(f, "- {} 0x{:02X}", prefix, id)?,
+                (Some(old), Some(new)) if old != new => writeln!(f, "~ {} 0x{:02X} -> 0x{:02X}", prefix, old, new)?,
+                (_, id) => writeln!(f, "~ {} 0x{:02X}", prefix, id.unwrap_or_default())?
+            }
+            for field in &packet.fields {
+                writeln!(f, "    {}", field)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+//! Renders protocol definitions as wiki.vg style Markdown.
+use crate::protocol::{Direction, PacketDescriptor, Protocol, State};
+use std::fmt::Write;
+
+const STATES: [State; 5] = [State::Handshaking, State::Status, State::Login, State::Configuration, State::Play];
+const DIRECTIONS: [Direction; 2] = [Direction::ServerBound, Direction::ClientBound];
+
+/// A Markdown document with an overview table per state and direction,
+/// followed by a section per packet listing its fields.
+pub fn markdown<P: Protocol>() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {} (protocol {})\n", P::NAME, crate::protocol::version::ProtocolVersion(P::PROTOCOL));
+    for state in STATES.iter() {
+        for direction in DIRECTIONS.iter() {
+            let mut packets: Vec<&PacketDescriptor> = P::packets().iter()
+                .filter(|p| p.state == *state && p.direction == *direction)
+                .collect();
+            if packets.is_empty() {
+                continue;
+            }
+            packets.sort_by_key(|p| p.id);
+            let _ = writeln!(out, "## {:?} {:?}\n", state, direction);
+            let _ = writeln!(out, "| ID | Packet | Summary |");
+            let _ = writeln!(out, "|---|---|---|");
+            for packet in &packets {
+                let summary = packet.doc().into_iter().next().unwrap_or_default();
+                let _ = writeln!(out, "| 0x{:02X} | [{}](#{}) | {} |", packet.id, packet.name, anchor(packet), escape(&summary));
+            }
+            out.push('\n');
+            for packet in &packets {
+                write_packet(&mut out, packet);
+            }
+        }
+    }
+    out
+}
+
+fn write_packet(out: &mut String, packet: &PacketDescriptor) {
+    let _ = writeln!(out, "### <a name=\"{}\"></a>{} (0x{:02X})\n", anchor(packet), packet.name, packet.id);
+    let doc = packet.doc();
+    if !doc.is_empty() {
+        let _ = writeln!(out, "{}\n", doc.join("\n"));
+    }
+    if packet.fields.is_empty() {
+        let _ = writeln!(out, "*No fields.*\n");
+        return;
+    }
+    let _ = writeln!(out, "| Field | Type | Condition | Notes |");
+    let _ = writeln!(out, "|---|---|---|---|");
+    for field in packet.fields {
+        let value_type = match (field.wire_type, field.custom) {
+            (Some(wire_type), _) => format!("`{}` as `{}`", field.value_type, wire_type),
+            (None, true) => format!("`{}` (custom codec)", field.value_type),
+            (None, false) => format!("`{}`", field.value_type)
+        };
+        let mut conditions: Vec<String> = field.condition
+            .map(|condition| format!("`{}`", condition.trim_start_matches('{').trim_end_matches('}').trim()))
+            .into_iter()
+            .collect();
+        if let Some(since) = field.since {
+            conditions.push(format!("since {}", since));
+        }
+        if let Some(until) = field.until {
+            conditions.push(format!("until {}", until));
+        }
+        let condition = conditions.join(", ");
+        let _ = writeln!(out, "| {} | {} | {} | {} |", field.name, escape(&value_type), escape(&condition), escape(&field.doc().join(" ")));
+    }
+    out.push('\n');
+}
+
+fn anchor(packet: &PacketDescriptor) -> String {
+    format!("{:?}-{:?}-{}", packet.state, packet.direction, packet.name).to_lowercase()
+}
+
+/// Keeps table cells intact.
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
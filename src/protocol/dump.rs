@@ -0,0 +1,73 @@
+//! Annotated hex dumps of packets, listing the bytes of every field next to
+//! its decoded value in the style of the wiki.vg examples.
+use crate::protocol::{Packet, Protocol};
+use crate::segment::implementation::var::write_var_int;
+use std::fmt::Write;
+
+/// Bytes shown per line, longer fields continue on the next line.
+const BYTES_PER_LINE: usize = 16;
+/// Longer values are cut off.
+const MAX_VALUE_LEN: usize = 96;
+
+/// A single field as it is sent on the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpField{
+    pub name: &'static str,
+    /// Empty for fields whose condition did not hold.
+    pub bytes: Vec<u8>,
+    /// The `Debug` representation of the value.
+    pub value: String
+}
+
+/// Dumps a single packet, starting with its id.
+pub fn packet<T: Packet>(packet: &T) -> std::io::Result<String> {
+    let name = std::any::type_name::<T>().rsplit("::").next().unwrap_or_default();
+    Ok(format(name, packet.packet_id(), &packet.dump_fields()?))
+}
+
+/// Dumps any packet of a protocol, unknown packets are dumped as their body.
+pub fn protocol<P: Protocol>(packet: &P) -> std::io::Result<String> {
+    Ok(format(packet.packet_name(), packet.id(), &packet.dump_fields()?))
+}
+
+/// Dumps a packet body without knowing its fields.
+pub fn raw(id: i32, body: &[u8]) -> String {
+    format("Raw", id, &[DumpField{ name: "body", bytes: body.to_vec(), value: format!("{} bytes", body.len()) }])
+}
+
+fn format(name: &str, id: i32, fields: &[DumpField]) -> String {
+    let mut id_bytes = Vec::new();
+    let _ = write_var_int(id, &mut id_bytes);
+    let id_field = DumpField{ name: "packet id", bytes: id_bytes, value: format!("0x{:02X}", id) };
+    let rows: Vec<&DumpField> = std::iter::once(&id_field).chain(fields.iter()).collect();
+
+    let total: usize = rows.iter().map(|field| field.bytes.len()).sum();
+    let name_width = rows.iter().map(|field| field.name.len()).max().unwrap_or(0);
+    let bytes_width = BYTES_PER_LINE * 3 - 1;
+    let mut out = String::new();
+    let _ = writeln!(out, "{} (0x{:02X}, {} bytes)", name, id, total);
+    let _ = writeln!(out, "{:<6}  {:<bytes_width$}  {:<name_width$}  value", "offset", "bytes", "field", bytes_width = bytes_width, name_width = name_width);
+    let mut offset = 0;
+    for field in rows {
+        let value = truncate(&field.value);
+        if field.bytes.is_empty() {
+            let _ = writeln!(out, "{:<6}  {:<bytes_width$}  {:<name_width$}  {}", "", "(absent)", field.name, value, bytes_width = bytes_width, name_width = name_width);
+            continue;
+        }
+        for (i, chunk) in field.bytes.chunks(BYTES_PER_LINE).enumerate() {
+            let hex = chunk.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
+            let (name, value) = if i == 0 { (field.name, value.as_str()) } else { ("", "") };
+            let _ = writeln!(out, "0x{:04X}  {:<bytes_width$}  {:<name_width$}  {}", offset, hex, name, value, bytes_width = bytes_width, name_width = name_width);
+            offset += chunk.len();
+        }
+    }
+    out
+}
+
+fn truncate(value: &str) -> String {
+    let value = value.replace('\n', " ");
+    match value.char_indices().nth(MAX_VALUE_LEN) {
+        Some((end, _)) => format!("{}...", &value[..end]),
+        None => value
+    }
+}
@@ -1,2 +1,16 @@
 #[cfg(feature = "steven_shared")]
-pub mod v1_17;
\ No newline at end of file
+pub mod v1_7_10;
+#[cfg(feature = "steven_shared")]
+pub mod v1_14_4;
+#[cfg(feature = "steven_shared")]
+pub mod v1_16_5;
+#[cfg(feature = "steven_shared")]
+pub mod v1_17;
+#[cfg(feature = "steven_shared")]
+pub mod v1_20_4;
+#[cfg(feature = "steven_shared")]
+pub mod v1_21;
+
+/// The newest protocol version, which moves along with new releases.
+#[cfg(feature = "steven_shared")]
+pub use self::v1_21 as latest;
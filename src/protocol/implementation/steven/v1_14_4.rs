@@ -0,0 +1,1258 @@
+//! Protocol 498, 1.14.4, the village and pillage layout many minigame
+//! networks still target.
+//!
+//! Compared to 1.16.5 the world has no dimension codec and sends its biomes
+//! inside the chunk data, entities spawn with their metadata, chat carries
+//! no sender and the login sends the uuid as a string.
+use crate::protocol::State;
+use crate::protocol::Direction;
+use crate::protocol::implementation::steven::v1_16_5::{read_light_arrays, write_light_arrays, WorldBorderAction};
+use crate::protocol::implementation::steven::v1_17::{BossBarAction, TeamsAction};
+use steven_protocol::protocol::{LenPrefixedBytes, UUID, LenPrefixed, FixedPoint12};
+use steven_protocol::format;
+use steven_protocol::item;
+use steven_protocol::nbt;
+use steven_protocol::types;
+use steven_protocol::protocol::packet;
+use steven_protocol::protocol::{VarInt, VarLong};
+use steven_shared::Position;
+use crate::segment::implementation::bounded::Bounded;
+use crate::segment::implementation::mojang::advancement::{self, Advancement, AdvancementProgress};
+use crate::play::tab_list::PlayerInfoAction;
+use crate::segment::implementation::mojang::recipe::Recipe;
+use crate::segment::implementation::mojang::tags::TagSet;
+use crate::segment::implementation::types::{ChatPosition, ClientStatusAction, Difficulty, DiggingStatus, Direction6, GameMode, Hand};
+use crate::segment::implementation::types::{AbilityFlags, CommandBlockFlags, RelativeTeleportFlags, SkinParts, SteerFlags, StopSoundFlags, StructureBlockFlags};
+
+fn read_sky_light<R: std::io::Read>(packet: &UpdateLight, reader: &mut R) -> std::io::Result<Vec<Vec<u8>>> {
+    read_light_arrays(packet.sky_light_mask.0, reader)
+}
+
+fn read_block_light<R: std::io::Read>(packet: &UpdateLight, reader: &mut R) -> std::io::Result<Vec<Vec<u8>>> {
+    read_light_arrays(packet.block_light_mask.0, reader)
+}
+
+fn write_light<W: std::io::Write>(_packet: &UpdateLight, arrays: &[Vec<u8>], writer: &mut W) -> std::io::Result<()> {
+    write_light_arrays(arrays, writer)
+}
+
+fn read_recipes<R: std::io::Read>(_packet: &DeclareRecipes, reader: &mut R) -> std::io::Result<Vec<Recipe>> {
+    crate::segment::implementation::mojang::recipe::read_recipes(reader)
+}
+
+fn write_recipes<W: std::io::Write>(_packet: &DeclareRecipes, recipes: &[Recipe], writer: &mut W) -> std::io::Result<()> {
+    crate::segment::implementation::mojang::recipe::write_recipes(recipes, writer)
+}
+
+fn read_tags<R: std::io::Read>(_packet: &Tags, reader: &mut R) -> std::io::Result<TagSet> {
+    TagSet::read_fixed(reader)
+}
+
+fn write_tags<W: std::io::Write>(_packet: &Tags, tags: &TagSet, writer: &mut W) -> std::io::Result<()> {
+    tags.write_fixed(writer)
+}
+
+fn read_advancements<R: std::io::Read>(_packet: &Advancements, reader: &mut R) -> std::io::Result<Vec<(String, Advancement)>> {
+    advancement::read_keyed(reader)
+}
+
+fn write_advancements<W: std::io::Write>(_packet: &Advancements, mapping: &[(String, Advancement)], writer: &mut W) -> std::io::Result<()> {
+    advancement::write_keyed(mapping, writer)
+}
+
+fn read_advancement_progress<R: std::io::Read>(_packet: &Advancements, reader: &mut R) -> std::io::Result<Vec<(String, AdvancementProgress)>> {
+    advancement::read_keyed(reader)
+}
+
+fn write_advancement_progress<W: std::io::Write>(_packet: &Advancements, progress: &[(String, AdvancementProgress)], writer: &mut W) -> std::io::Result<()> {
+    advancement::write_keyed(progress, writer)
+}
+
+crate::define_protocol!(pub Proto_1_14_4, "1.14.4", 498 {
+    State::Handshaking => {
+        Direction::ServerBound => {
+            /// Handshake is the first packet sent in the protocol.
+            /// Its used for deciding if the request is a client
+            /// is requesting status information about the server
+            /// (MOTD, players etc) or trying to login to the server.
+            ///
+            /// The host and port fields are not used by the vanilla
+            /// server but are there for virtual server hosting to
+            /// be able to redirect a client to a target server with
+            /// a single address + port.
+            ///
+            /// Some modified servers/proxies use the handshake field
+            /// differently, packing information into the field other
+            /// than the hostname due to the protocol not providing
+            /// any system for custom information to be transfered
+            /// by the client to the server until after login.
+            0x00 => Handshake {
+                /// The protocol version of the connecting client
+                protocol_version: VarInt,
+                /// The hostname the client connected to
+                host: String,
+                /// The port the client connected to
+                port: u16,
+                /// The next protocol state the client wants
+                next: VarInt,
+            }
+        }
+    },
+    State::Status => {
+        Direction::ServerBound => {
+            /// StatusRequest is sent by the client instantly after
+            /// switching to the Status protocol state and is used
+            /// to signal the server to send a StatusResponse to the
+            /// client
+            0x00 => StatusRequest,
+            /// StatusPing is sent by the client after recieving a
+            /// StatusResponse. The client uses the time from sending
+            /// the ping until the time of recieving a pong to measure
+            /// the latency between the client and the server.
+            0x01 => StatusPing{
+                ping: i64,
+            },
+        },
+        Direction::ClientBound => {
+            /// StatusResponse is sent as a reply to a StatusRequest.
+            /// The Status should contain a json encoded structure with
+            /// version information, a player sample, a description/MOTD
+            /// and optionally a favicon.
+            ///
+            /// The structure is as follows
+            ///
+            /// ```json
+            /// {
+            ///     "version": {
+            ///         "name": "1.8.3",
+            ///         "protocol": 47,
+            ///     },
+            ///     "players": {
+            ///         "max": 20,
+            ///         "online": 1,
+            ///         "sample": [
+            ///            packet  {"name": "Thinkofdeath", "id": "4566e69f-c907-48ee-8d71-d7ba5aa00d20"}
+            ///         ]
+            ///     },
+            ///     "description": "Hello world",
+            ///     "favicon": "data:image/png;base64,<data>"
+            /// }
+            /// ```
+            0x00 => StatusResponse{
+                status: String,
+            },
+            /// StatusPong is sent as a reply to a StatusPing.
+            /// The Time field should be exactly the same as the
+            /// one sent by the client.
+            0x01 => StatusPong{
+                ping: i64
+            },
+        }
+    },
+    State::Login => {
+        Direction::ServerBound => {
+            /// LoginStart is sent immeditately after switching into the login
+            /// state. The passed username is used by the server to authenticate
+            /// the player in online mode.
+            0x00 => LoginStart{
+                username: String
+            },
+            /// EncryptionResponse is sent as a reply to EncryptionRequest. All
+            /// packets following this one must be encrypted with AES/CFB8
+            /// encryption.
+            0x01 => EncryptionResponse{
+                /// The key for the AES/CFB8 cipher encrypted with the
+                /// public key
+                shared_secret: LenPrefixedBytes<VarInt>,
+                /// The verify token from the request encrypted with the
+                /// public key
+                verify_token: LenPrefixedBytes<VarInt>,
+            },
+            0x02 => LoginPluginResponse{
+                message_id: VarInt,
+                successful: bool,
+                data: Vec<u8>,
+            }
+        },
+        Direction::ClientBound => {
+            /// LoginDisconnect is sent by the server if there was any issues
+            /// authenticating the player during login or the general server
+            /// issues (e.g. too many players).
+            0x00 => LoginDisconnect{
+                reason: format::Component,
+            },
+            /// EncryptionRequest is sent by the server if the server is in
+            /// online mode. If it is not sent then its assumed the server is
+            /// in offline mode.
+            0x01 => EncryptionRequest{
+                /// Generally empty, left in from legacy auth
+                /// but is still used by the client if provided
+                server_id: String,
+                /// A RSA Public key serialized in x.509 PRIX format
+                public_key: LenPrefixedBytes<VarInt>,
+                /// Token used by the server to verify encryption is working
+                /// correctly
+                verify_token: LenPrefixedBytes<VarInt>,
+            },
+            0x02 => LoginSuccess{
+                /// The uuid with hyphens
+                uuid: String,
+                username: String,
+            },
+            /// SetInitialCompression sets the compression threshold during the
+            /// login state.
+            0x03 => SetInitialCompression{
+                /// Threshold where a packet should be sent compressed
+                threshold: VarInt,
+            },
+            0x04 => LoginPluginRequest{
+                message_id: VarInt,
+                channel: String,
+                data: Vec<u8>,
+            },
+        }
+    },
+    State::Play => {
+        Direction::ServerBound => {
+            /// TeleportConfirm is sent by the client as a reply to a telport from
+            /// the server.
+            0x00 => TeleportConfirm{
+                teleport_id: VarInt,
+            },
+            0x01 => QueryBlockNBT{
+                transaction_id: VarInt,
+                location: Position,
+            },
+            0x02 => SetDifficulty{
+                new_difficulty: Difficulty,
+            },
+            /// ChatMessage is sent by the client when it sends a chat message or
+            /// executes a command (prefixed by '/').
+            0x03 => ChatMessage {
+                message: String,
+            },
+            /// ClientStatus is sent to update the client's status
+            0x04 => ClientStatus{
+                action_id: ClientStatusAction,
+            },
+            /// ClientSettings is sent by the client to update its current settings.
+            0x05 => ClientSettings {
+                locale: String,
+                view_distance: Bounded<u8, 2, 32>,
+                chat_mode: VarInt,
+                chat_colors: bool,
+                displayed_skin_parts: SkinParts,
+                main_hand: VarInt,
+            },
+            /// TabComplete is sent by the client when the client presses tab in
+            /// the chat box.
+            0x06 => TabComplete {
+                transaction_id: VarInt,
+                text: String,
+            },
+            /// ConfirmTransactionServerbound answers a rejected ConfirmTransaction,
+            /// replaced by PlayPong in 1.17.
+            0x07 => ConfirmTransactionServerbound {
+                id: u8,
+                action_number: i16,
+                accepted: bool,
+            },
+            /// ClickWindowButton is used for clicking an enchantment, lectern, stonecutter, or loom.
+            0x08 => ClickWindowButton {
+                id: u8,
+                button: u8,
+            },
+            /// ClickWindow is sent when the client clicks in a window.
+            0x09 => ClickWindow {
+                id: u8,
+                slot: i16,
+                button: u8,
+                action_number: u16,
+                mode: VarInt,
+                clicked_item: Option<item::Stack>,
+            },
+            /// CloseWindow is sent when the client closes a window.
+            0x0a => CloseWindow {
+                id: u8,
+            },
+            /// PluginMessageServerbound is used for custom messages between the client
+            /// and server. This is mainly for plugins/mods but vanilla has a few channels
+            /// registered too.
+            0x0b => PluginMessageServerbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            0x0c => EditBook {
+                new_book: Option<item::Stack>,
+                is_signing: bool,
+                hand: Hand,
+            },
+            0x0d => QueryEntityNBT {
+                transaction_id: VarInt,
+                entity_id: VarInt,
+            },
+            /// UseEntity is sent when the user interacts (right clicks) or attacks
+            /// (left clicks) an entity.
+            0x0e => UseEntity {
+                target_id: VarInt,
+                ty: VarInt,
+                target_x: f32 where |p| {p.ty.0 == 2},
+                target_y: f32 where |p| {p.ty.0 == 2},
+                target_z: f32 where |p| {p.ty.0 == 2},
+                hand: Hand where |p| {p.ty.0 == 0 || p.ty.0 == 2},
+            },
+            /// KeepAliveServerbound is sent by a client as a response to a
+            /// KeepAliveClientbound. If the client doesn't reply the server
+            /// may disconnect the client.
+            0x0f => KeepAliveServerbound {
+                id: i64,
+            },
+            0x10 => LockDifficulty {
+                locked: bool,
+            },
+            /// PlayerPosition is used to update the player's position.
+            0x11 => PlayerPosition {
+                x: f64,
+                y: f64,
+                z: f64,
+                on_ground: bool,
+            },
+            /// PlayerPositionLook is a combination of PlayerPosition and
+            /// PlayerLook.
+            0x12 => PlayerPositionLook {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// PlayerLook is used to update the player's rotation.
+            0x13 => PlayerLook {
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// Player is used to update whether the player is on the ground or not.
+            0x14 => Player {
+                on_ground: bool,
+            },
+            /// Sent by the client when in a vehicle instead of the normal move packet.
+            0x15 => VehicleMove {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+            },
+            /// SteerBoat is used to visually update the boat paddles.
+            0x16 => SteerBoat {
+                left_paddle_turning: bool,
+                right_paddle_turning: bool,
+            },
+            0x17 => PickItem {
+                slot_to_use: VarInt,
+            },
+            /// CraftRecipeRequest is sent when player clicks a recipe in the crafting book.
+            0x18 => CraftRecipeRequest {
+                window_id: u8,
+                recipe: String,
+                make_all: bool,
+            },
+            0x19 => ClientAbilities {
+                flags: AbilityFlags,
+                flying_speed: f32,
+                walking_speed: f32,
+            },
+            /// PlayerDigging is sent when the client starts/stops digging a block.
+            /// It also can be sent for droppping items and eating/shooting.
+            0x1a => PlayerDigging {
+                status: DiggingStatus,
+                location: Position,
+                face: Direction6 as u8,
+            },
+            /// PlayerAction is sent when a player preforms various actions.
+            0x1b => PlayerAction{
+                entity_id: VarInt,
+                action_id: VarInt,
+                jump_boost: VarInt,
+            },
+            /// SteerVehicle is sent by the client when steers or preforms an action
+            /// on a vehicle.
+            0x1c => SteerVehicle {
+                sideways: f32,
+                forward: f32,
+                flags: SteerFlags,
+            },
+            /// CraftingBookData reports the recipe the player looked at (type 0) or
+            /// the state of the recipe books (type 1), split in 1.16.2.
+            0x1d => CraftingBookData {
+                action: VarInt,
+                recipe_id: Option<String> where |p| {
+                    p.action.0 == 0
+                },
+                crafting_book_open: Option<bool> where |p| {
+                    p.action.0 == 1
+                },
+                crafting_filter: Option<bool> where |p| {
+                    p.action.0 == 1
+                },
+                smelting_book_open: Option<bool> where |p| {
+                    p.action.0 == 1
+                },
+                smelting_filter: Option<bool> where |p| {
+                    p.action.0 == 1
+                },
+                blast_furnace_open: Option<bool> where |p| {
+                    p.action.0 == 1
+                },
+                blast_furnace_filter: Option<bool> where |p| {
+                    p.action.0 == 1
+                },
+                smoker_open: Option<bool> where |p| {
+                    p.action.0 == 1
+                },
+                smoker_filter: Option<bool> where |p| {
+                    p.action.0 == 1
+                },
+            },
+            0x1e => NameItem {
+                item_name: String,
+            },
+            /// ResourcePackStatus informs the server of the client's current progress
+            /// in activating the requested resource pack
+            0x1f => ResourcePackStatus {
+                result: VarInt,
+            },
+            0x20 => AdvancementTab {
+                action: VarInt,
+                tab_id: String where |p| {p.action.0 == 0},
+            },
+            0x21 => SelectTrade {
+                selected_slot: VarInt,
+            },
+            0x22 => SetBeaconEffect {
+                primary_effect: VarInt,
+                secondary_effect: VarInt,
+            },
+            /// HeldItemChange is sent when the player changes the currently active
+            /// hotbar slot.
+            0x23 => HeldItemChange {
+                slot: Bounded<i16, 0, 8>,
+            },
+            0x24 => UpdateCommandBlock {
+                location: Position,
+                command: String,
+                mode: VarInt,
+                flags: CommandBlockFlags,
+            },
+            0x25 => UpdateCommandBlockMinecart {
+                entity_id: VarInt,
+                command: String,
+                track_output: bool,
+            },
+            /// CreativeInventoryAction is sent when the client clicks in the creative
+            /// inventory. This is used to spawn items in creative.
+            0x26 => CreativeInventoryAction {
+                /// -1 drops the item.
+                slot: Bounded<i16, -1, 45>,
+                clicked_item: Option<item::Stack>,
+            },
+            0x27 => UpdateJigsawBlock {
+                location: Position,
+                attachment_type: String,
+                target_pool: String,
+                final_state: String,
+            },
+            0x28 => UpdateStructureBlock {
+                location: Position,
+                action: VarInt,
+                mode: VarInt,
+                name: String,
+                offset_x: i8,
+                offset_y: i8,
+                offset_z: i8,
+                size_x: i8,
+                size_y: i8,
+                size_z: i8,
+                mirror: VarInt,
+                rotation: VarInt,
+                metadata: String,
+                integrity: f32,
+                seed: VarLong,
+                flags: StructureBlockFlags,
+            },
+            /// SetSign sets the text on a sign after placing it.
+            0x29 => SetSign {
+                location: Position,
+                line1: String,
+                line2: String,
+                line3: String,
+                line4: String,
+            },
+            /// ArmSwing is sent by the client when the player left clicks
+            /// (to swing their arm).
+            0x2a => ArmSwing {
+                hand: Hand,
+            },
+            /// SpectateTeleport is sent by clients in spectator mode to teleport to a player.
+            0x2b => SpectateTeleport {
+                target: UUID,
+            },
+            0x2c => PlayerBlockPlacement {
+                hand: Hand,
+                location: Position,
+                face: Direction6,
+                cursor_x: f32,
+                cursor_y: f32,
+                cursor_z: f32,
+                inside_block: bool,
+            },
+            /// UseItem is sent when the client tries to use an item.
+            0x2d => UseItem {
+                hand: Hand,
+            },
+        },
+        Direction::ClientBound => {
+            0x00 => SpawnObject {
+                entity_id: VarInt,
+                uuid: UUID,
+                ty: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                pitch: i8,
+                yaw: i8,
+                data: i32,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            },
+            /// SpawnExperienceOrb spawns a single experience orb into the world when
+            /// it is in range of the client. The count controls the amount of experience
+            /// gained when collected.
+            0x01 => SpawnExperienceOrb {
+                entity_id: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                count: i16,
+            },
+            /// SpawnGlobalEntity spawns an entity which is visible from anywhere in the
+            /// world. Currently only used for lightning.
+            0x02 => SpawnGlobalEntity {
+                entity_id: VarInt,
+                ty: u8,
+                x: f64,
+                y: f64,
+                z: f64,
+            },
+            /// SpawnMob is used to spawn a living entity into the world when it is in
+            /// range of the client.
+            0x03 => SpawnMob {
+                entity_id: VarInt,
+                uuid: UUID,
+                ty: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: i8,
+                pitch: i8,
+                head_pitch: i8,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+                metadata: types::Metadata,
+            },
+            /// SpawnPainting spawns a painting into the world when it is in range of
+            /// the client. The title effects the size and the texture of the painting.
+            0x04 => SpawnPainting {
+                entity_id: VarInt,
+                uuid: UUID,
+                motive: VarInt,
+                location: Position,
+                direction: u8,
+            },
+            /// SpawnPlayer is used to spawn a player when they are in range of the client.
+            /// This alone isn't enough to display the player as the skin and username
+            /// information is in the player information packet.
+            0x05 => SpawnPlayer {
+                entity_id: VarInt,
+                uuid: UUID,
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: i8,
+                pitch: i8,
+                metadata: types::Metadata,
+            },
+            /// Animation is sent by the server to play an animation on a specific entity.
+            0x06 => Animation {
+                entity_id: VarInt,
+                animation_id: u8,
+            },
+            /// Statistics is used to update the statistics screen for the client.
+            0x07 => Statistics {
+                statistices: LenPrefixed<VarInt, packet::Statistic>,
+            },
+            /// BlockBreakAnimation is used to create and update the block breaking
+            /// animation played when a player starts digging a block.
+            0x08 => BlockBreakAnimation {
+                entity_id: VarInt,
+                location: Position,
+                stage: i8,
+            },
+            /// UpdateBlockEntity updates the nbt tag of a block entity in the
+            /// world.
+            0x09 => UpdateBlockEntity {
+                location: Position,
+                action: u8,
+                nbt: Option<nbt::NamedTag>,
+            },
+            /// BlockAction triggers different actions depending on the target block.
+            0x0a => BlockAction {
+                location: Position,
+                byte1: u8,
+                byte2: u8,
+                block_type: VarInt,
+            },
+            /// BlockChange is used to update a single block on the client.
+            0x0b => BlockChange {
+                location: Position,
+                block_id: VarInt,
+            },
+            /// BossBar displays and/or changes a boss bar that is displayed on the
+            /// top of the client's screen. This is normally used for bosses such as
+            /// the ender dragon or the wither.
+            0x0c => BossBar {
+                uuid: UUID,
+                action: BossBarAction,
+            },
+            /// ServerDifficulty changes the displayed difficulty in the client's menu
+            /// as well as some ui changes for hardcore.
+            0x0d => ServerDifficulty {
+                difficulty: Difficulty,
+                locked: bool,
+            },
+            /// ServerMessage is a message sent by the server. It could be from a player
+            /// or just a system message. The Type controls the location the
+            /// message is displayed at and when the message is displayed.
+            0x0e => ServerMessage {
+                message: serde_json::Value,
+                position: ChatPosition,
+            },
+            /// MultiBlockChange is used to update a batch of blocks in a single packet.
+            0x0f => MultiBlockChange {
+                chunk_x: i32,
+                chunk_z: i32,
+                records: LenPrefixed<VarInt, packet::BlockChangeRecord>,
+            },
+            /// TabCompleteReply is sent as a reply to a tab completion request.
+            /// The matches should be possible completions for the command/chat the
+            /// player sent.
+            0x10 => TabCompleteReply {
+                matches: LenPrefixed<VarInt, String>,
+            },
+            0x11 => DeclareCommands {
+                nodes: LenPrefixed<VarInt, packet::CommandNode>,
+                root_index: VarInt,
+            },
+            /// ConfirmTransaction tells the client whether a window click was accepted,
+            /// replaced by PlayPing in 1.17.
+            0x12 => ConfirmTransaction {
+                id: u8,
+                action_number: i16,
+                accepted: bool,
+            },
+            /// WindowClose forces the client to close the window with the given id,
+            /// e.g. a chest getting destroyed.
+            0x13 => WindowClose {
+                id: u8,
+            },
+            /// WindowItems sets every item in a window.
+            0x14 => WindowItems {
+                id: u8,
+                items: LenPrefixed<i16, Option<item::Stack>>,
+            },
+            /// WindowProperty changes the value of a property of a window. Properties
+            /// vary depending on the window type.
+            0x15 => WindowProperty {
+                id: u8,
+                property: i16,
+                value: i16,
+            },
+            /// WindowSetSlot changes an itemstack in one of the slots in a window.
+            0x16 => WindowSetSlot {
+                id: u8,
+                property: i16,
+                item: Option<item::Stack>,
+            },
+            /// SetCooldown disables a set item (by id) for the set number of ticks
+            0x17 => SetCooldown {
+                item_id: VarInt,
+                ticks: VarInt,
+            },
+            /// PluginMessageClientbound is used for custom messages between the client
+            /// and server. This is mainly for plugins/mods but vanilla has a few channels
+            /// registered too.
+            0x18 => PluginMessageClientbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            /// Plays a sound by name on the client
+            0x19 => NamedSoundEffect {
+                name: String,
+                category: VarInt,
+                x: i32,
+                y: i32,
+                z: i32,
+                volume: f32,
+                pitch: f32,
+            },
+            /// Disconnect causes the client to disconnect displaying the passed reason.
+            0x1a => Disconnect {
+                reason: format::Component,
+            },
+            /// EntityAction causes an entity to preform an action based on the passed
+            /// id.
+            0x1b => EntityAction {
+                entity_id: i32,
+                action_id: u8,
+            },
+            /// Explosion is sent when an explosion is triggered (tnt, creeper etc).
+            /// This plays the effect and removes the effected blocks.
+            0x1c => Explosion {
+                x: f32,
+                y: f32,
+                z: f32,
+                radius: f32,
+                records: LenPrefixed<i32, packet::ExplosionRecord>,
+                velocity_x: f32,
+                velocity_y: f32,
+                velocity_z: f32,
+            },
+            /// ChunkUnload tells the client to unload the chunk at the specified
+            /// position.
+            0x1d => ChunkUnload {
+                x: i32,
+                z: i32,
+            },
+            /// ChangeGameState is used to modify the game's state like gamemode or
+            /// weather.
+            0x1e => ChangeGameState {
+                reason: u8,
+                value: f32,
+            },
+            0x1f => WindowOpenHorse {
+                window_id: u8,
+                number_of_slots: VarInt,
+                entity_id: i32,
+            },
+            /// KeepAliveClientbound is sent by a server to check if the
+            /// client is still responding and keep the connection open.
+            /// The client should reply with the KeepAliveServerbound
+            /// setting ID to the same as this one.
+            0x20 => KeepAliveClientbound {
+                id: i64,
+            },
+            /// ChunkData sends a whole column when `full_chunk` is set, with the biomes
+            /// at the end of `data`, otherwise only the sections in `bitmask`.
+            0x21 => ChunkData {
+                chunk_x: i32,
+                chunk_z: i32,
+                full_chunk: bool,
+                bitmask: VarInt,
+                heightmaps: Option<nbt::NamedTag>,
+                data: LenPrefixedBytes<VarInt>,
+                block_entities: LenPrefixed<VarInt, Option<nbt::NamedTag>>,
+            },
+            /// Effect plays a sound effect or particle at the target location with the
+            /// volume (of sounds) being relative to the player's position unless
+            /// DisableRelative is set to true.
+            0x22 => Effect {
+                effect_id: i32,
+                location: Position,
+                data: i32,
+                disable_relative: bool,
+            },
+            /// Particle spawns particles at the target location with the various
+            /// modifiers.
+            0x23 => Particle {
+                particle_id: i32,
+                long_distance: bool,
+                x: f64,
+                y: f64,
+                z: f64,
+                offset_x: f32,
+                offset_y: f32,
+                offset_z: f32,
+                speed: f32,
+                count: i32,
+                block_state: VarInt where |p| {
+                    p.particle_id == 3 || p.particle_id == 23
+                },
+                red: f32 where |p| {
+                    p.particle_id == 14
+                },
+                green: f32 where |p| {
+                    p.particle_id == 14
+                },
+                blue: f32 where |p| {
+                    p.particle_id == 14
+                },
+                scale: f32 where |p| {
+                    p.particle_id == 14
+                },
+                item: Option<nbt::NamedTag> where |p| {
+                    p.particle_id == 32
+                },
+            },
+            /// UpdateLight sends one array for every bit set in the matching mask,
+            /// without a count.
+            0x24 => UpdateLight {
+                chunk_x: VarInt,
+                chunk_z: VarInt,
+                sky_light_mask: VarInt,
+                block_light_mask: VarInt,
+                empty_sky_light_mask: VarInt,
+                empty_block_light_mask: VarInt,
+                sky_light: Vec<Vec<u8>> => with(
+                    read = read_sky_light,
+                    write = write_light
+                ),
+                block_light: Vec<Vec<u8>> => with(
+                    read = read_block_light,
+                    write = write_light
+                ),
+            },
+            /// JoinGame is sent after completing the login process. This
+            /// sets the initial state for the client.
+            0x25 => JoinGame {
+                /// The entity id the client will be referenced by
+                entity_id: i32,
+                /// The starting gamemode of the client, with bit 3 set in hardcore mode
+                gamemode: u8,
+                /// The dimension the client is starting in, -1 for the nether and 1 for the end
+                dimension: i32,
+                /// Ignored by the client
+                max_players: u8,
+                /// The generator of the world, like "default" or "flat"
+                level_type: String,
+                /// The render distance (2-32)
+                view_distance: Bounded<VarInt, 2, 32>,
+                /// Whether the client should reduce the amount of debug
+                /// information it displays in F3 mode
+                reduced_debug_info: bool,
+            },
+            /// Maps updates a single map's contents
+            0x26 => Maps {
+                item_damage: VarInt,
+                scale: i8,
+                tracking_position: bool,
+                locked: bool,
+                icons: LenPrefixed<VarInt, packet::MapIcon>,
+                columns: u8,
+                rows: Option<u8> where |p| {
+                    p.columns > 0
+                },
+                x: Option<u8> where |p| {
+                    p.columns > 0
+                },
+                z: Option<u8> where |p| {
+                    p.columns > 0
+                },
+                data: Option<LenPrefixedBytes<VarInt>> where |p| {
+                    p.columns > 0
+                },
+            },
+            0x27 => TradeList {
+                id: VarInt,
+                trades: LenPrefixed<u8, packet::Trade>,
+                villager_level: VarInt,
+                experience: VarInt,
+                is_regular_villager: bool,
+                can_restock: bool,
+            },
+            /// EntityMove moves the entity with the id by the offsets provided.
+            0x28 => EntityMove {
+                entity_id: VarInt,
+                delta_x: FixedPoint12<i16>,
+                delta_y: FixedPoint12<i16>,
+                delta_z: FixedPoint12<i16>,
+                on_ground: bool,
+            },
+            /// EntityLookAndMove is a combination of EntityMove and EntityLook.
+            0x29 => EntityLookAndMove {
+                entity_id: VarInt,
+                delta_x: FixedPoint12<i16>,
+                delta_y: FixedPoint12<i16>,
+                delta_z: FixedPoint12<i16>,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            /// EntityLook rotates the entity to the new angles provided.
+            0x2a => EntityLook {
+                entity_id: VarInt,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            /// EntityUpdate keeps the entity alive on the client without moving it.
+            0x2b => EntityUpdate {
+                entity_id: VarInt,
+            },
+            /// Teleports the player's vehicle
+            0x2c => VehicleTeleport {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+            },
+            /// Opens the book GUI.
+            0x2d => OpenBook {
+                hand: Hand,
+            },
+            /// WindowOpen tells the client to open the inventory window of the given
+            /// type. The ID is used to reference the instance of the window in
+            /// other packets.
+            0x2e => WindowOpen {
+                id: VarInt,
+                ty: VarInt,
+                title: format::Component,
+            },
+            /// SignEditorOpen causes the client to open the editor for a sign so that
+            /// it can write to it. Only sent in vanilla when the player places a sign.
+            0x2f => SignEditorOpen {
+                location: Position,
+            },
+            /// CraftRecipeResponse is a response to CraftRecipeRequest, notifies the UI.
+            0x30 => CraftRecipeResponse {
+                window_id: u8,
+                recipe: String,
+            },
+            /// PlayerAbilities is used to modify the players current abilities. Flying,
+            /// creative, god mode etc.
+            0x31 => PlayerAbilities {
+                flags: AbilityFlags,
+                flying_speed: f32,
+                walking_speed: f32,
+            },
+            /// CombatEvent enters combat (event 0), ends it (1) or reports the death of
+            /// the player (2), split into a packet per event in 1.17.
+            0x32 => CombatEvent {
+                event: VarInt,
+                duration: Option<VarInt> where |p| {
+                    p.event.0 == 1
+                },
+                player_id: Option<VarInt> where |p| {
+                    p.event.0 == 2
+                },
+                entity_id: Option<i32> where |p| {
+                    p.event.0 == 1 || p.event.0 == 2
+                },
+                message: Option<format::Component> where |p| {
+                    p.event.0 == 2
+                },
+            },
+            /// PlayerInfo is sent by the server for every player connected to the server
+            /// to provide skin and username information as well as ping and gamemode info.
+            0x33 => PlayerInfo {
+                action: PlayerInfoAction,
+            },
+            0x34 => FacePlayer {
+                feet_eyes: VarInt,
+                target_x: f64,
+                target_y: f64,
+                target_z: f64,
+                is_entity: bool,
+                entity_id: Option<VarInt> where |p| {p.is_entity},
+                entity_feet_eyes: Option<VarInt> where |p| {p.is_entity},
+            },
+            0x35 => TeleportPlayer {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                flags: RelativeTeleportFlags,
+                teleport_id: VarInt,
+            },
+            0x36 => UnlockRecipes{
+                action: VarInt,
+                crafting_book_open: bool,
+                filtering_craftable: bool,
+                smelting_book_open: bool,
+                filtering_smeltable: bool,
+                recipe_ids: LenPrefixed<VarInt, String>,
+                recipe_ids2: LenPrefixed<VarInt, String> where |p| {
+                    p.action.0 == 0
+                }
+            },
+            /// EntityDestroy removes a list of entities, 1.17 sends one per packet.
+            0x37 => EntityDestroy {
+                entity_ids: LenPrefixed<VarInt, VarInt>,
+            },
+            /// EntityRemoveEffect removes an effect from an entity.
+            0x38 => EntityRemoveEffect {
+                entity_id: VarInt,
+                effect_id: i8,
+            },
+            /// ResourcePackSend causes the client to check its cache for the requested
+            /// resource and download it if its missing. Once the resource pack
+            /// is obtained the client will use it.
+            0x39 => ResourcePackSend {
+                url: String,
+                hash: String,
+            },
+            /// Respawn is sent to respawn the player after death or when they move worlds.
+            0x3a => Respawn {
+                dimension: i32,
+                gamemode: GameMode,
+                level_type: String,
+            },
+            /// EntityHeadLook rotates an entity's head to the new angle.
+            0x3b => EntityHeadLook {
+                entity_id: VarInt,
+                head_yaw: i8,
+            },
+            /// SelectAdvancementTab indicates the client should switch the advancement tab.
+            0x3c => SelectAdvancementTab {
+                has_id: bool,
+                tab_id: String where |p| {p.has_id},
+            },
+            /// WorldBorder updates the world border, split into a packet per action in 1.17.
+            0x3d => WorldBorder {
+                action: WorldBorderAction,
+            },
+            /// Camera causes the client to spectate the entity with the passed id.
+            /// Use the player's id to de-spectate.
+            0x3e => Camera {
+                target_id: VarInt,
+            },
+            /// SetCurrentHotbarSlot changes the player's currently selected hotbar item.
+            0x3f => SetCurrentHotbarSlot {
+                slot: Bounded<u8, 0, 8>,
+            },
+            /// UpdateViewPosition is used to determine what chunks should be remain loaded.
+            0x40 => UpdateViewPosition {
+                chunk_x: VarInt,
+                chunk_z: VarInt,
+            },
+            /// UpdateViewDistance is sent by the integrated server when changing render distance.
+            0x41 => UpdateViewDistance {
+                view_distance: Bounded<VarInt, 2, 32>,
+            },
+            /// ScoreboardDisplay is used to set the display position of a scoreboard.
+            0x42 => ScoreboardDisplay {
+                position: u8,
+                name: String,
+            },
+            /// EntityMetadata updates the metadata for an entity.
+            0x43 => EntityMetadata {
+                entity_id: VarInt,
+                metadata: types::Metadata,
+            },
+            /// EntityAttach attaches to entities together, either by mounting or leashing.
+            /// -1 can be used at the EntityID to deattach.
+            0x44 => EntityAttach {
+                entity_id: i32,
+                vehicle: i32,
+            },
+            /// EntityVelocity sets the velocity of an entity in 1/8000 of a block
+            /// per a tick.
+            0x45 => EntityVelocity {
+                entity_id: VarInt,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            },
+            /// EntityEquipment is sent to display an item on an entity, like a sword
+            /// or armor. Slot 0 is the held item and slots 1 to 4 are boots, leggings
+            /// chestplate and helmet respectively.
+            0x46 => EntityEquipment {
+                entity_id: VarInt,
+                slot: VarInt,
+                item: Option<item::Stack>,
+            },
+            /// SetExperience updates the experience bar on the client.
+            0x47 => SetExperience {
+                experience_bar: f32,
+                level: VarInt,
+                total_experience: VarInt,
+            },
+            /// UpdateHealth is sent by the server to update the player's health and food.
+            0x48 => UpdateHealth {
+                health: f32,
+                food: VarInt,
+                food_saturation: f32,
+            },
+            /// ScoreboardObjective creates/updates a scoreboard objective.
+            0x49 => ScoreboardObjective {
+                name: String,
+                mode: u8,
+                value: String where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                ty: VarInt where |p| {
+                    p.mode == 0 || p.mode == 2
+                }
+            },
+            /// SetPassengers mounts entities to an entity
+            0x4a => SetPassengers {
+                entity_id: VarInt,
+                passengers: LenPrefixed<VarInt, VarInt>,
+            },
+            /// Teams creates and updates teams
+            0x4b => Teams {
+                name: String,
+                mode: TeamsAction,
+            },
+            /// UpdateScore is used to update or remove an item from a scoreboard
+            /// objective.
+            0x4c => UpdateScore {
+                name: String,
+                action: u8,
+                object_name: String,
+                value: Option<VarInt> where |p| {
+                    p.action != 1
+                },
+            },
+            /// SpawnPosition is sent to change the player's current spawn point. Currently
+            /// only used by the client for the compass.
+            0x4d => SpawnPosition {
+                location: Position,
+            },
+            /// TimeUpdate is sent to sync the world's time to the client, the client
+            /// will manually tick the time itself so this doesn't need to sent repeatedly
+            /// but if the server or client has issues keeping up this can fall out of sync
+            /// so it is a good idea to send this now and again
+            0x4e => TimeUpdate {
+                world_age: i64,
+                time_of_day: i64,
+            },
+            /// Title configures an on-screen title.
+            0x4f => Title {
+                action: VarInt,
+                title: Option<format::Component> where |p| {
+                    p.action.0 == 0
+                },
+                sub_title: Option<format::Component> where |p| {
+                    p.action.0 == 1
+                },
+                action_bar_text: Option<String> where |p| {
+                    p.action.0 == 2
+                },
+                fade_in: Option<i32> where |p| {
+                    p.action.0 == 3
+                },
+                fade_stay: Option<i32> where |p| {
+                    p.action.0 == 3
+                },
+                fade_out: Option<i32> where |p| {
+                    p.action.0 == 3
+                }
+            },
+            /// Plays a sound effect from an entity.
+            0x50 => EntitySoundEffect {
+                sound_id: VarInt,
+                sound_category: VarInt,
+                entity_id: VarInt,
+                volume: f32,
+                pitch: f32,
+            },
+            /// SoundEffect plays the named sound at the target location.
+            0x51 => SoundEffect {
+                name: VarInt,
+                category: VarInt,
+                x: i32,
+                y: i32,
+                z: i32,
+                volume: f32,
+                pitch: f32,
+            },
+            0x52 => StopSound {
+                flags: StopSoundFlags,
+                source: Option<VarInt> where |p| {
+                    p.flags.contains(StopSoundFlags::SOURCE)
+                },
+                sound: Option<String> where |p| {
+                    p.flags.contains(StopSoundFlags::SOUND)
+                }
+            },
+            /// PlayerListHeaderFooter updates the header/footer of the player list.
+            0x53 => PlayerListHeaderFooter {
+                header: format::Component,
+                footer: format::Component,
+            },
+            0x54 => NBTQueryResponse {
+                transaction_id: VarInt,
+                nbt: Option<nbt::NamedTag>,
+            },
+            /// CollectItem causes the collected item to fly towards the collector. This
+            /// does not destroy the entity.
+            0x55 => CollectItem {
+                collected_entity_id: VarInt,
+                collector_entity_id: VarInt,
+                number_of_items: VarInt,
+            },
+            /// EntityTeleport teleports the entity to the target location. This is
+            /// sent if the entity moves further than EntityMove allows.
+            0x56 => EntityTeleport {
+                entity_id: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            0x57 => Advancements {
+                reset_clear: bool,
+                mapping: Vec<(String, Advancement)> => with(
+                    read = read_advancements,
+                    write = write_advancements
+                ),
+                /// Advancements to remove.
+                identifiers: LenPrefixed<VarInt, String>,
+                progress: Vec<(String, AdvancementProgress)> => with(
+                    read = read_advancement_progress,
+                    write = write_advancement_progress
+                ),
+            },
+            /// EntityProperties updates the properties for an entity.
+            0x58 => EntityProperties{
+                entity_id: VarInt,
+                properties: LenPrefixed<i32, packet::EntityProperty>,
+            },
+            /// EntityEffect applies a status effect to an entity for a given duration.
+            0x59 => EntityEffect {
+                entity_id: VarInt,
+                effect_id: i8,
+                amplifier: i8,
+                duration: VarInt,
+                flags: u8,
+            },
+            0x5a => DeclareRecipes {
+                recipes: Vec<Recipe> => with(
+                    read = read_recipes,
+                    write = write_recipes
+                ),
+            },
+            0x5b => Tags {
+                /// Block, item, fluid and entity type tags.
+                tags: TagSet => with(
+                    read = read_tags,
+                    write = write_tags
+                ),
+            },
+            0x5c => AcknowledgePlayerDigging {
+                location: Position,
+                block: VarInt,
+                status: DiggingStatus,
+                successful: bool,
+            },
+        }
+    }
+});
@@ -8,6 +8,141 @@ use steven_protocol::types;
 use steven_protocol::protocol::packet;
 use steven_protocol::protocol::{VarInt, VarLong};
 use steven_shared::Position;
+use crate::segment::implementation::bounded::Bounded;
+use crate::segment::implementation::mojang::advancement::{self, Advancement, AdvancementProgress};
+use crate::play::tab_list::PlayerInfoAction;
+use crate::segment::implementation::mojang::recipe::Recipe;
+use crate::segment::implementation::mojang::tags::TagSet;
+use crate::segment::implementation::types::{ChatPosition, ClientStatusAction, Difficulty, DiggingStatus, Direction6, GameMode, Hand};
+use crate::segment::implementation::types::{AbilityFlags, BossBarFlags, CommandBlockFlags, RelativeTeleportFlags, SkinParts, SteerFlags, StopSoundFlags, StructureBlockFlags};
+
+crate::segment_enum!{
+    /// The recipe book a recipe book packet applies to.
+    pub enum RecipeBookType {
+        Crafting = 0,
+        Furnace = 1,
+        BlastFurnace = 2,
+        Smoker = 3,
+    }
+}
+
+crate::tagged_union!{
+    /// The action of a BossBar packet along with its payload.
+    pub enum BossBarAction: VarInt {
+        0 => Add {
+            title: format::Component,
+            health: f32,
+            color: VarInt,
+            style: VarInt,
+            flags: BossBarFlags,
+        },
+        1 => Remove,
+        2 => UpdateHealth {
+            health: f32,
+        },
+        3 => UpdateTitle {
+            title: format::Component,
+        },
+        4 => UpdateStyle {
+            color: VarInt,
+            style: VarInt,
+        },
+        5 => UpdateFlags {
+            flags: BossBarFlags,
+        },
+    }
+}
+
+crate::tagged_union!{
+    /// The mode of a Teams packet along with its payload.
+    pub enum TeamsAction: u8 {
+        0 => Create {
+            display_name: String,
+            flags: u8,
+            name_tag_visibility: String,
+            collision_rule: String,
+            formatting: VarInt,
+            prefix: String,
+            suffix: String,
+            players: LenPrefixed<VarInt, String>,
+        },
+        1 => Remove,
+        2 => UpdateInfo {
+            display_name: String,
+            flags: u8,
+            name_tag_visibility: String,
+            collision_rule: String,
+            formatting: VarInt,
+            prefix: String,
+            suffix: String,
+        },
+        3 => AddPlayers {
+            players: LenPrefixed<VarInt, String>,
+        },
+        4 => RemovePlayers {
+            players: LenPrefixed<VarInt, String>,
+        },
+    }
+}
+
+/// Where a sculk vibration travels to, depending on `SculkVibrationSignal::destination_id`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VibrationDestination{
+    Block(Position),
+    Entity(VarInt)
+}
+
+fn read_vibration_destination<R: std::io::Read>(packet: &SculkVibrationSignal, reader: &mut R) -> std::io::Result<Option<VibrationDestination>> {
+    match packet.destination_id.as_str() {
+        "minecraft:block" => {
+            let mut position = Position::default();
+            crate::segment::Segment::read_from_stream(&mut position, reader)?;
+            Ok(Some(VibrationDestination::Block(position)))
+        }
+        "minecraft:entity" => {
+            let mut entity_id = VarInt::default();
+            crate::segment::Segment::read_from_stream(&mut entity_id, reader)?;
+            Ok(Some(VibrationDestination::Entity(entity_id)))
+        }
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("unknown vibration destination type {}", other)
+        ))
+    }
+}
+
+fn write_vibration_destination<W: std::io::Write>(_packet: &SculkVibrationSignal, destination: &Option<VibrationDestination>, writer: &mut W) -> std::io::Result<()> {
+    match destination {
+        Some(VibrationDestination::Block(position)) => crate::segment::Segment::write_to_stream(position, writer),
+        Some(VibrationDestination::Entity(entity_id)) => crate::segment::Segment::write_to_stream(entity_id, writer),
+        None => Ok(())
+    }
+}
+
+fn read_recipes<R: std::io::Read>(_packet: &DeclareRecipes, reader: &mut R) -> std::io::Result<Vec<Recipe>> {
+    crate::segment::implementation::mojang::recipe::read_recipes(reader)
+}
+
+fn write_recipes<W: std::io::Write>(_packet: &DeclareRecipes, recipes: &[Recipe], writer: &mut W) -> std::io::Result<()> {
+    crate::segment::implementation::mojang::recipe::write_recipes(recipes, writer)
+}
+
+fn read_advancements<R: std::io::Read>(_packet: &Advancements, reader: &mut R) -> std::io::Result<Vec<(String, Advancement)>> {
+    advancement::read_keyed(reader)
+}
+
+fn write_advancements<W: std::io::Write>(_packet: &Advancements, mapping: &[(String, Advancement)], writer: &mut W) -> std::io::Result<()> {
+    advancement::write_keyed(mapping, writer)
+}
+
+fn read_advancement_progress<R: std::io::Read>(_packet: &Advancements, reader: &mut R) -> std::io::Result<Vec<(String, AdvancementProgress)>> {
+    advancement::read_keyed(reader)
+}
+
+fn write_advancement_progress<W: std::io::Write>(_packet: &Advancements, progress: &[(String, AdvancementProgress)], writer: &mut W) -> std::io::Result<()> {
+    advancement::write_keyed(progress, writer)
+}
 
 crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
     State::Handshaking => {
@@ -164,7 +299,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 location: Position,
             },
             0x02 => SetDifficulty{
-                new_difficulty: u8,
+                new_difficulty: Difficulty,
             },
             /// ChatMessage is sent by the client when it sends a chat message or
             /// executes a command (prefixed by '/').
@@ -173,15 +308,15 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             },
             /// ClientStatus is sent to update the client's status
             0x04 => ClientStatus{
-                action_id: VarInt,
+                action_id: ClientStatusAction,
             },
             /// ClientSettings is sent by the client to update its current settings.
             0x05 => ClientSettings {
                 locale: String,
-                view_distance: u8,
+                view_distance: Bounded<u8, 2, 32>,
                 chat_mode: VarInt,
                 chat_colors: bool,
-                displayed_skin_parts: u8,
+                displayed_skin_parts: SkinParts,
                 main_hand: VarInt,
             },
             /// TabComplete is sent by the client when the client presses tab in
@@ -220,7 +355,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             0x0b => EditBook {
                 new_book: Option<item::Stack>,
                 is_signing: bool,
-                hand: VarInt,
+                hand: Hand,
             },
             0x0c => QueryEntityNBT {
                 transaction_id: VarInt,
@@ -234,7 +369,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 target_x: f32 where |p| {p.ty.0 == 2},
                 target_y: f32 where |p| {p.ty.0 == 2},
                 target_z: f32 where |p| {p.ty.0 == 2},
-                hand: VarInt where |p| {p.ty.0 == 0 || p.ty.0 == 2},
+                hand: Hand where |p| {p.ty.0 == 0 || p.ty.0 == 2},
                 sneaking: bool,
             },
             /// Sent when Generate is pressed on the Jigsaw Block interface.
@@ -302,14 +437,14 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 make_all: bool,
             },
             0x19 => ClientAbilities {
-                flags: u8,
+                flags: AbilityFlags,
             },
             /// PlayerDigging is sent when the client starts/stops digging a block.
             /// It also can be sent for droppping items and eating/shooting.
             0x1a => PlayerDigging {
-                status: VarInt,
+                status: DiggingStatus,
                 location: Position,
-                face: u8,
+                face: Direction6 as u8,
             },
             /// PlayerAction is sent when a player preforms various actions.
             0x1b => PlayerAction{
@@ -322,7 +457,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             0x1c => SteerVehicle {
                 sideways: f32,
                 forward: f32,
-                flags: u8,
+                flags: SteerFlags,
             },
             0x1d => PlayPong {
                 id: i32,
@@ -333,7 +468,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             },
             /// SetRecipeBookState replaces CraftingBookData, type 1.
             0x1f => SetRecipeBookState {
-                book_id: VarInt, // TO DO: enum, 0: crafting, 1: furnace, 2: blast furnace, 3: smoker
+                book_id: RecipeBookType as VarInt,
                 book_open: bool,
                 filter_active: bool,
             },
@@ -359,13 +494,13 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             /// HeldItemChange is sent when the player changes the currently active
             /// hotbar slot.
             0x25 => HeldItemChange {
-                slot: i16,
+                slot: Bounded<i16, 0, 8>,
             },
             0x26 => UpdateCommandBlock {
                 location: Position,
                 command: String,
                 mode: VarInt,
-                flags: u8,
+                flags: CommandBlockFlags,
             },
             0x27 => UpdateCommandBlockMinecart {
                 entity_id: VarInt,
@@ -375,7 +510,8 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             /// CreativeInventoryAction is sent when the client clicks in the creative
             /// inventory. This is used to spawn items in creative.
             0x28 => CreativeInventoryAction {
-                slot: i16,
+                /// -1 drops the item.
+                slot: Bounded<i16, -1, 45>,
                 clicked_item: Option<item::Stack>,
             },
             0x29 => UpdateJigsawBlock {
@@ -402,7 +538,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 metadata: String,
                 integrity: f32,
                 seed: VarLong,
-                flags: i8,
+                flags: StructureBlockFlags,
             },
             /// SetSign sets the text on a sign after placing it.
             0x2b => SetSign {
@@ -415,16 +551,16 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             /// ArmSwing is sent by the client when the player left clicks
             /// (to swing their arm).
             0x2c => ArmSwing {
-                hand: VarInt,
+                hand: Hand,
             },
             /// SpectateTeleport is sent by clients in spectator mode to teleport to a player.
             0x2d => SpectateTeleport {
                 target: UUID,
             },
             0x2e => PlayerBlockPlacement {
-                hand: VarInt,
+                hand: Hand,
                 location: Position,
-                face: VarInt,
+                face: Direction6,
                 cursor_x: f32,
                 cursor_y: f32,
                 cursor_z: f32,
@@ -432,7 +568,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             },
             /// UseItem is sent when the client tries to use an item.
             0x2f => UseItem {
-                hand: VarInt,
+                hand: Hand,
             },
         },
         Direction::ClientBound => {
@@ -500,9 +636,10 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             0x05 => SculkVibrationSignal {
                 source: Position,
                 destination_id: String,
-                destination_pos: Option<Position> where |p| {
-                    unimplemented!("Not enough info to tell if Position or VarInt with entity ID")
-                },
+                destination: Option<VibrationDestination> => with(
+                    read = read_vibration_destination,
+                    write = write_vibration_destination
+                ),
                 arrival_ticks: VarInt,
             },
             /// Animation is sent by the server to play an animation on a specific entity.
@@ -517,7 +654,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             0x08 => AcknowledgePlayerDigging {
                 location: Position,
                 block: VarInt,
-                status: VarInt,
+                status: DiggingStatus,
                 successful: bool,
             },
             /// BlockBreakAnimation is used to create and update the block breaking
@@ -551,27 +688,12 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             /// the ender dragon or the wither.
             0x0d => BossBar {
                 uuid: UUID,
-                action: VarInt,
-                title: format::Component where |p| {
-                    p.action.0 == 0 || p.action.0 == 3
-                },
-                health: f32 where |p| {
-                    p.action.0 == 0 || p.action.0 == 2
-                },
-                color: VarInt where |p| {
-                    p.action.0 == 0 || p.action.0 == 4
-                },
-                style: VarInt where |p| {
-                    p.action.0 == 0 || p.action.0 == 4
-                },
-                flags: u8 where |p| {
-                    p.action.0 == 0 || p.action.0 == 5
-                }
+                action: BossBarAction,
             },
             /// ServerDifficulty changes the displayed difficulty in the client's menu
             /// as well as some ui changes for hardcore.
             0x0e => ServerDifficulty {
-                difficulty: u8,
+                difficulty: Difficulty,
                 locked: bool,
             },
             /// ServerMessage is a message sent by the server. It could be from a player
@@ -579,8 +701,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             /// message is displayed at and when the message is displayed.
             0x0f => ServerMessage {
                 message: serde_json::Value,
-                /// 0 - Chat message, 1 - System message, 2 - Action bar message
-                position: u8,
+                position: ChatPosition,
                 sender: UUID,
             },
             /// Clear the client's current title information
@@ -767,8 +888,8 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 /// Whether hardcore mode is enabled
                 is_hardcore: bool,
                 /// The starting gamemode of the client
-                gamemode: u8,
-                /// The previous gamemode of the client
+                gamemode: GameMode,
+                /// The previous gamemode of the client, 255 if there is none
                 previous_gamemode: u8,
                 /// Identifiers for all worlds on the server
                 world_names: LenPrefixed<VarInt, String>,
@@ -783,7 +904,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 /// The max number of players on the server
                 max_players: VarInt,
                 /// The render distance (2-32)
-                view_distance: VarInt,
+                view_distance: Bounded<VarInt, 2, 32>,
                 /// Whether the client should reduce the amount of debug
                 /// information it displays in F3 mode
                 reduced_debug_info: bool,
@@ -858,7 +979,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             },
             /// Opens the book GUI.
             0x2d => OpenBook {
-                hand: VarInt,
+                hand: Hand,
             },
             /// WindowOpen tells the client to open the inventory window of the given
             /// type. The ID is used to reference the instance of the window in
@@ -888,7 +1009,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             /// PlayerAbilities is used to modify the players current abilities. Flying,
             /// creative, god mode etc.
             0x32 => PlayerAbilities {
-                flags: u8,
+                flags: AbilityFlags,
                 flying_speed: f32,
                 walking_speed: f32,
             },
@@ -916,7 +1037,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             /// PlayerInfo is sent by the server for every player connected to the server
             /// to provide skin and username information as well as ping and gamemode info.
             0x36 => PlayerInfo {
-                inner: packet::PlayerInfoData,
+                action: PlayerInfoAction,
             },
             0x37 => FacePlayer {
                 feet_eyes: VarInt,
@@ -933,7 +1054,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 z: f64,
                 yaw: f32,
                 pitch: f32,
-                flags: u8,
+                flags: RelativeTeleportFlags,
                 teleport_id: VarInt,
                 dismount: bool,
             },
@@ -972,7 +1093,8 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 dimension: Option<nbt::NamedTag>,
                 world_name: String,
                 hashed_seed: i64,
-                gamemode: u8,
+                gamemode: GameMode,
+                /// 255 if there is none
                 previous_gamemode: u8,
                 is_debug: bool,
                 is_flat: bool,
@@ -1022,7 +1144,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             },
             /// SetCurrentHotbarSlot changes the player's currently selected hotbar item.
             0x48 => SetCurrentHotbarSlot {
-                slot: u8,
+                slot: Bounded<u8, 0, 8>,
             },
             /// UpdateViewPosition is used to determine what chunks should be remain loaded.
             0x49 => UpdateViewPosition {
@@ -1031,7 +1153,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             },
             /// UpdateViewDistance is sent by the integrated server when changing render distance.
             0x4a => UpdateViewDistance {
-                view_distance: VarInt,
+                view_distance: Bounded<VarInt, 2, 32>,
             },
             /// SpawnPosition is sent to change the player's current spawn point. Currently
             /// only used by the client for the compass.
@@ -1100,27 +1222,7 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
             /// Teams creates and updates teams
             0x55 => Teams {
                 name: String,
-                mode: u8,
-                display_name: Option<String> where |p| {p.mode == 0 || p.mode == 2},
-                flags: Option<u8> where |p| {p.mode == 0 || p.mode == 2},
-                name_tag_visibility: Option<String> where |p| {
-                    p.mode == 0 || p.mode == 2
-                },
-                collision_rule: Option<String> where |p| {
-                    p.mode == 0 || p.mode == 2
-                },
-                formatting: Option<VarInt> where |p| {
-                    p.mode == 0 || p.mode == 2
-                },
-                prefix: Option<String> where |p| {
-                    p.mode == 0 || p.mode == 2
-                },
-                suffix: Option<String> where |p| {
-                    p.mode == 0 || p.mode == 2
-                },
-                players: Option<LenPrefixed<VarInt, String>> where |p| {
-                    p.mode == 0 || p.mode == 3 || p.mode == 4
-                },
+                mode: TeamsAction,
             },
             /// UpdateScore is used to update or remove an item from a scoreboard
             /// objective.
@@ -1189,12 +1291,12 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 pitch: f32,
             },
             0x5d => StopSound {
-                flags: u8,
+                flags: StopSoundFlags,
                 source: Option<VarInt> where |p| {
-                    p.flags & 0x01 != 0
+                    p.flags.contains(StopSoundFlags::SOURCE)
                 },
                 sound: Option<String> where |p| {
-                    p.flags & 0x02 != 0
+                    p.flags.contains(StopSoundFlags::SOUND)
                 }
             },
             /// PlayerListHeaderFooter updates the header/footer of the player list.
@@ -1225,14 +1327,17 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 on_ground: bool,
             },
             0x62 => Advancements {
-                data: Vec<u8>,
-                /* TODO: fix parsing modded advancements 1.12.2 (e.g. SevTech Ages)
-                 * see https://github.com/iceiix/stevenarella/issues/148
                 reset_clear: bool,
-                mapping: LenPrefixed<VarInt, packet::Advancement>,
+                mapping: Vec<(String, Advancement)> => with(
+                    read = read_advancements,
+                    write = write_advancements
+                ),
+                /// Advancements to remove.
                 identifiers: LenPrefixed<VarInt, String>,
-                progress: LenPrefixed<VarInt, packet::AdvancementProgress>,
-                */
+                progress: Vec<(String, AdvancementProgress)> => with(
+                    read = read_advancement_progress,
+                    write = write_advancement_progress
+                ),
             },
             /// EntityProperties updates the properties for an entity.
             0x63 => EntityProperties{
@@ -1248,13 +1353,14 @@ crate::define_protocol!(pub Proto_1_17, "1.17", 755 {
                 hide_particles: bool,
             },
             0x65 => DeclareRecipes {
-                recipes: LenPrefixed<VarInt, packet::Recipe>,
+                recipes: Vec<Recipe> => with(
+                    read = read_recipes,
+                    write = write_recipes
+                ),
             },
             0x66 => Tags {
-                block_tags: LenPrefixed<VarInt, packet::Tags>,
-                item_tags: LenPrefixed<VarInt, packet::Tags>,
-                fluid_tags: LenPrefixed<VarInt, packet::Tags>,
-                entity_tags: LenPrefixed<VarInt, packet::Tags>,
+                /// Tags per registry, which since 1.17 include game events.
+                tags: TagSet,
             },
         }
     }
@@ -0,0 +1,1650 @@
+//! Protocol 765, 1.20.3 and 1.20.4.
+//!
+//! Since 1.20.2 login is followed by the configuration state, where the server
+//! sends registries, feature flags and resource packs before play starts, and
+//! chunks are sent in batches the client acknowledges with its chunk rate.
+//! Since 1.20.3 text components are NBT instead of JSON outside of login and
+//! resource packs are pushed and popped by UUID.
+//!
+//! Packets whose layout is too involved for a field list, like commands,
+//! entity metadata, recipes and the player info update, keep their body as
+//! bytes. Packets that match 1.17 use its names.
+use crate::protocol::State;
+use crate::protocol::Direction;
+use crate::protocol::implementation::steven::v1_17::RecipeBookType;
+use crate::proxy::bungeecord::ProfileProperty;
+use steven_protocol::protocol::{LenPrefixedBytes, UUID, LenPrefixed, FixedPoint12};
+use steven_protocol::format;
+use steven_protocol::protocol::packet;
+use steven_protocol::protocol::{VarInt, VarLong};
+use steven_shared::Position;
+use crate::segment::implementation::bounded::Bounded;
+use crate::segment::implementation::mojang::{self, read_string, write_string};
+use crate::segment::implementation::mojang::block_entity::ChunkBlockEntity;
+use crate::segment::implementation::mojang::map::MapIcon;
+use crate::segment::implementation::mojang::slot::NetworkSlot;
+use crate::segment::implementation::mojang::tags::TagSet;
+use crate::segment::implementation::nbt::NetworkNbt;
+use crate::segment::implementation::types::{ClientStatusAction, Difficulty, DiggingStatus, Direction6, GameMode, Hand};
+use crate::segment::implementation::types::{AbilityFlags, BossBarFlags, CommandBlockFlags, RelativeTeleportFlags, SkinParts, SteerFlags, StopSoundFlags, StructureBlockFlags};
+use crate::segment::implementation::var::write_var_int;
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+/// The length of a message signature.
+const SIGNATURE_LEN: usize = 256;
+
+crate::tagged_union!{
+    /// The action of a BossBar packet along with its payload.
+    pub enum BossBarAction: VarInt {
+        0 => Add {
+            title: NetworkNbt,
+            health: f32,
+            color: VarInt,
+            style: VarInt,
+            flags: BossBarFlags,
+        },
+        1 => Remove,
+        2 => UpdateHealth {
+            health: f32,
+        },
+        3 => UpdateTitle {
+            title: NetworkNbt,
+        },
+        4 => UpdateStyle {
+            color: VarInt,
+            style: VarInt,
+        },
+        5 => UpdateFlags {
+            flags: BossBarFlags,
+        },
+    }
+}
+
+crate::tagged_union!{
+    /// The mode of a Teams packet along with its payload.
+    pub enum TeamsAction: u8 {
+        0 => Create {
+            display_name: NetworkNbt,
+            flags: u8,
+            name_tag_visibility: String,
+            collision_rule: String,
+            formatting: VarInt,
+            prefix: NetworkNbt,
+            suffix: NetworkNbt,
+            players: LenPrefixed<VarInt, String>,
+        },
+        1 => Remove,
+        2 => UpdateInfo {
+            display_name: NetworkNbt,
+            flags: u8,
+            name_tag_visibility: String,
+            collision_rule: String,
+            formatting: VarInt,
+            prefix: NetworkNbt,
+            suffix: NetworkNbt,
+        },
+        3 => AddPlayers {
+            players: LenPrefixed<VarInt, String>,
+        },
+        4 => RemovePlayers {
+            players: LenPrefixed<VarInt, String>,
+        },
+    }
+}
+
+pub(crate) fn read_properties<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Vec<ProfileProperty>> {
+    let count = mojang::read_count(reader)?;
+    (0..count).map(|_| {
+        let name = read_string(reader)?;
+        let value = read_string(reader)?;
+        let signature = match reader.read_u8()? != 0 {
+            true => Some(read_string(reader)?),
+            false => None
+        };
+        Ok(ProfileProperty{ name, value, signature })
+    }).collect()
+}
+
+pub(crate) fn write_properties<P, W: std::io::Write>(_packet: &P, properties: &[ProfileProperty], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(properties.len() as i32, writer)?;
+    for property in properties {
+        write_string(&property.name, writer)?;
+        write_string(&property.value, writer)?;
+        match &property.signature {
+            Some(signature) => {
+                writer.write_u8(1)?;
+                write_string(signature, writer)?;
+            }
+            None => writer.write_u8(0)?
+        }
+    }
+    Ok(())
+}
+
+/// Reads the signature of a message, if `present`.
+pub(crate) fn read_signature<R: std::io::Read>(present: bool, reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let mut signature = Vec::new();
+    if present {
+        signature.resize(SIGNATURE_LEN, 0);
+        reader.read_exact(&mut signature)?;
+    }
+    Ok(signature)
+}
+
+pub(crate) fn write_signature<W: std::io::Write>(signature: &[u8], writer: &mut W) -> std::io::Result<()> {
+    if !signature.is_empty() && signature.len() != SIGNATURE_LEN {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("message signature of {} bytes", signature.len())));
+    }
+    writer.write_all(signature)
+}
+
+fn read_chat_signature<R: std::io::Read>(packet: &ChatMessage, reader: &mut R) -> std::io::Result<Vec<u8>> {
+    read_signature(packet.has_signature, reader)
+}
+
+fn write_chat_signature<W: std::io::Write>(_packet: &ChatMessage, signature: &[u8], writer: &mut W) -> std::io::Result<()> {
+    write_signature(signature, writer)
+}
+
+fn read_player_chat_signature<R: std::io::Read>(packet: &PlayerChat, reader: &mut R) -> std::io::Result<Vec<u8>> {
+    read_signature(packet.has_signature, reader)
+}
+
+fn write_player_chat_signature<W: std::io::Write>(_packet: &PlayerChat, signature: &[u8], writer: &mut W) -> std::io::Result<()> {
+    write_signature(signature, writer)
+}
+
+fn read_changed_slots<R: std::io::Read>(_packet: &ClickWindow, reader: &mut R) -> std::io::Result<Vec<(i16, NetworkSlot)>> {
+    let count = mojang::read_count(reader)?;
+    (0..count).map(|_| {
+        let slot = reader.read_i16::<BigEndian>()?;
+        let mut item = NetworkSlot::default();
+        item.read_from_stream(reader)?;
+        Ok((slot, item))
+    }).collect()
+}
+
+fn write_changed_slots<W: std::io::Write>(_packet: &ClickWindow, slots: &[(i16, NetworkSlot)], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(slots.len() as i32, writer)?;
+    for (slot, item) in slots {
+        writer.write_i16::<BigEndian>(*slot)?;
+        item.write_to_stream(writer)?;
+    }
+    Ok(())
+}
+
+fn read_window_items<R: std::io::Read>(_packet: &WindowItems, reader: &mut R) -> std::io::Result<Vec<NetworkSlot>> {
+    mojang::read_var_list(reader)
+}
+
+fn write_window_items<W: std::io::Write>(_packet: &WindowItems, items: &[NetworkSlot], writer: &mut W) -> std::io::Result<()> {
+    mojang::write_var_list(items, writer)
+}
+
+pub(crate) fn read_block_entities<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Vec<ChunkBlockEntity>> {
+    mojang::read_var_list(reader)
+}
+
+pub(crate) fn write_block_entities<P, W: std::io::Write>(_packet: &P, block_entities: &[ChunkBlockEntity], writer: &mut W) -> std::io::Result<()> {
+    mojang::write_var_list(block_entities, writer)
+}
+
+pub(crate) fn read_chunk_biomes<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Vec<(i32, i32, Vec<u8>)>> {
+    let count = mojang::read_count(reader)?;
+    (0..count).map(|_| {
+        let chunk_z = reader.read_i32::<BigEndian>()?;
+        let chunk_x = reader.read_i32::<BigEndian>()?;
+        let mut data = vec![0; mojang::read_count(reader)?];
+        reader.read_exact(&mut data)?;
+        Ok((chunk_x, chunk_z, data))
+    }).collect()
+}
+
+pub(crate) fn write_chunk_biomes<P, W: std::io::Write>(_packet: &P, chunks: &[(i32, i32, Vec<u8>)], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(chunks.len() as i32, writer)?;
+    for (chunk_x, chunk_z, data) in chunks {
+        writer.write_i32::<BigEndian>(*chunk_z)?;
+        writer.write_i32::<BigEndian>(*chunk_x)?;
+        write_var_int(data.len() as i32, writer)?;
+        writer.write_all(data)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_suggestions<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Vec<(String, NetworkNbt)>> {
+    let count = mojang::read_count(reader)?;
+    (0..count).map(|_| {
+        let text = read_string(reader)?;
+        let mut tooltip = NetworkNbt::default();
+        if reader.read_u8()? != 0 {
+            tooltip.read_from_stream(reader)?;
+        }
+        Ok((text, tooltip))
+    }).collect()
+}
+
+pub(crate) fn write_suggestions<P, W: std::io::Write>(_packet: &P, suggestions: &[(String, NetworkNbt)], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(suggestions.len() as i32, writer)?;
+    for (text, tooltip) in suggestions {
+        write_string(text, writer)?;
+        match &tooltip.0 {
+            Some(_) => {
+                writer.write_u8(1)?;
+                tooltip.write_to_stream(writer)?;
+            }
+            None => writer.write_u8(0)?
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_map_icons<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Vec<MapIcon>> {
+    mojang::read_var_list(reader)
+}
+
+pub(crate) fn write_map_icons<P, W: std::io::Write>(_packet: &P, icons: &[MapIcon], writer: &mut W) -> std::io::Result<()> {
+    mojang::write_var_list(icons, writer)
+}
+
+/// Reads equipment until an entry without the continuation bit, `0x80` of the slot.
+fn read_equipment<R: std::io::Read>(_packet: &EntityEquipment, reader: &mut R) -> std::io::Result<Vec<(u8, NetworkSlot)>> {
+    let mut equipment = Vec::new();
+    loop {
+        let slot = reader.read_u8()?;
+        let mut item = NetworkSlot::default();
+        item.read_from_stream(reader)?;
+        equipment.push((slot & 0x7f, item));
+        if slot & 0x80 == 0 {
+            return Ok(equipment);
+        }
+    }
+}
+
+fn write_equipment<W: std::io::Write>(_packet: &EntityEquipment, equipment: &[(u8, NetworkSlot)], writer: &mut W) -> std::io::Result<()> {
+    if equipment.is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "EntityEquipment needs at least one slot"));
+    }
+    for (i, (slot, item)) in equipment.iter().enumerate() {
+        let more = if i + 1 < equipment.len() { 0x80 } else { 0 };
+        writer.write_u8(slot & 0x7f | more)?;
+        item.write_to_stream(writer)?;
+    }
+    Ok(())
+}
+
+crate::define_protocol!(pub Proto_1_20_4, "1.20.4", 765 {
+    State::Handshaking => {
+        Direction::ServerBound => {
+            /// Handshake is the first packet sent in the protocol.
+            /// Its used for deciding if the request is a client
+            /// is requesting status information about the server
+            /// (MOTD, players etc) or trying to login to the server.
+            ///
+            /// The host and port fields are not used by the vanilla
+            /// server but are there for virtual server hosting to
+            /// be able to redirect a client to a target server with
+            /// a single address + port.
+            ///
+            /// Some modified servers/proxies use the handshake field
+            /// differently, packing information into the field other
+            /// than the hostname due to the protocol not providing
+            /// any system for custom information to be transfered
+            /// by the client to the server until after login.
+            0x00 => Handshake {
+                /// The protocol version of the connecting client
+                protocol_version: VarInt,
+                /// The hostname the client connected to
+                host: String,
+                /// The port the client connected to
+                port: u16,
+                /// The next protocol state the client wants
+                next: VarInt,
+            }
+        }
+    },
+    State::Status => {
+        Direction::ServerBound => {
+            /// StatusRequest is sent by the client instantly after
+            /// switching to the Status protocol state and is used
+            /// to signal the server to send a StatusResponse to the
+            /// client
+            0x00 => StatusRequest,
+            /// StatusPing is sent by the client after recieving a
+            /// StatusResponse. The client uses the time from sending
+            /// the ping until the time of recieving a pong to measure
+            /// the latency between the client and the server.
+            0x01 => StatusPing{
+                ping: i64,
+            },
+        },
+        Direction::ClientBound => {
+            /// StatusResponse is sent as a reply to a StatusRequest.
+            /// The Status should contain a json encoded structure with
+            /// version information, a player sample, a description/MOTD
+            /// and optionally a favicon.
+            0x00 => StatusResponse{
+                status: String,
+            },
+            /// StatusPong is sent as a reply to a StatusPing.
+            /// The Time field should be exactly the same as the
+            /// one sent by the client.
+            0x01 => StatusPong{
+                ping: i64
+            },
+        }
+    },
+    State::Login => {
+        Direction::ServerBound => {
+            /// LoginStart is sent immeditately after switching into the login
+            /// state. The passed username is used by the server to authenticate
+            /// the player in online mode.
+            0x00 => LoginStart{
+                username: String,
+                /// The UUID of the player's account, unused by the vanilla server.
+                uuid: UUID,
+            },
+            /// EncryptionResponse is sent as a reply to EncryptionRequest. All
+            /// packets following this one must be encrypted with AES/CFB8
+            /// encryption.
+            0x01 => EncryptionResponse{
+                /// The key for the AES/CFB8 cipher encrypted with the
+                /// public key
+                shared_secret: LenPrefixedBytes<VarInt>,
+                /// The verify token from the request encrypted with the
+                /// public key
+                verify_token: LenPrefixedBytes<VarInt>,
+            },
+            0x02 => LoginPluginResponse{
+                message_id: VarInt,
+                successful: bool,
+                data: Vec<u8>,
+            },
+            /// LoginAcknowledged answers LoginSuccess and switches both sides to
+            /// the configuration state.
+            0x03 => LoginAcknowledged,
+        },
+        Direction::ClientBound => {
+            /// LoginDisconnect is sent by the server if there was any issues
+            /// authenticating the player during login or the general server
+            /// issues (e.g. too many players). Still JSON, unlike later states.
+            0x00 => LoginDisconnect{
+                reason: format::Component,
+            },
+            /// EncryptionRequest is sent by the server if the server is in
+            /// online mode. If it is not sent then its assumed the server is
+            /// in offline mode.
+            0x01 => EncryptionRequest{
+                /// Generally empty, left in from legacy auth
+                /// but is still used by the client if provided
+                server_id: String,
+                /// A RSA Public key serialized in x.509 PRIX format
+                public_key: LenPrefixedBytes<VarInt>,
+                /// Token used by the server to verify encryption is working
+                /// correctly
+                verify_token: LenPrefixedBytes<VarInt>,
+            },
+            /// LoginSuccess ends login once the client sent LoginAcknowledged.
+            0x02 => LoginSuccess{
+                uuid: UUID,
+                username: String,
+                properties: Vec<ProfileProperty> => with(
+                    read = read_properties,
+                    write = write_properties
+                ),
+            },
+            /// SetInitialCompression sets the compression threshold during the
+            /// login state.
+            0x03 => SetInitialCompression{
+                /// Threshold where a packet should be sent compressed
+                threshold: VarInt,
+            },
+            0x04 => LoginPluginRequest{
+                message_id: VarInt,
+                channel: String,
+                data: Vec<u8>,
+            },
+        }
+    },
+    State::Configuration => {
+        Direction::ServerBound => {
+            /// ConfigurationClientSettings is ClientSettings during configuration.
+            0x00 => ConfigurationClientSettings {
+                locale: String,
+                view_distance: Bounded<u8, 2, 32>,
+                chat_mode: VarInt,
+                chat_colors: bool,
+                displayed_skin_parts: SkinParts,
+                main_hand: VarInt,
+                enable_text_filtering: bool,
+                allow_server_listings: bool,
+            },
+            0x01 => ConfigurationPluginMessageServerbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            /// AcknowledgeFinishConfiguration answers FinishConfiguration and
+            /// switches both sides to play.
+            0x02 => AcknowledgeFinishConfiguration,
+            0x03 => ConfigurationKeepAliveServerbound {
+                id: i64,
+            },
+            0x04 => ConfigurationPong {
+                id: i32,
+            },
+            0x05 => ConfigurationResourcePackStatus {
+                uuid: UUID,
+                result: VarInt,
+            },
+        },
+        Direction::ClientBound => {
+            0x00 => ConfigurationPluginMessageClientbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            0x01 => ConfigurationDisconnect {
+                reason: NetworkNbt,
+            },
+            /// FinishConfiguration tells the client the server sent everything
+            /// it needs to enter play.
+            0x02 => FinishConfiguration,
+            0x03 => ConfigurationKeepAliveClientbound {
+                id: i64,
+            },
+            0x04 => ConfigurationPing {
+                id: i32,
+            },
+            /// RegistryData sends every synchronized registry, like dimension
+            /// types and biomes, as one compound.
+            0x05 => RegistryData {
+                codec: NetworkNbt,
+            },
+            /// ConfigurationResourcePackPop removes the pack with the UUID, or every pack.
+            0x06 => ConfigurationResourcePackPop {
+                has_uuid: bool,
+                uuid: UUID where |p| {p.has_uuid},
+            },
+            0x07 => ConfigurationResourcePackPush {
+                uuid: UUID,
+                url: String,
+                hash: String,
+                forced: bool,
+                has_prompt: bool,
+                prompt: NetworkNbt where |p| {p.has_prompt},
+            },
+            0x08 => FeatureFlags {
+                flags: LenPrefixed<VarInt, String>,
+            },
+            0x09 => ConfigurationTags {
+                tags: TagSet,
+            },
+        }
+    },
+    State::Play => {
+        Direction::ServerBound => {
+            /// TeleportConfirm is sent by the client as a reply to a telport from
+            /// the server.
+            0x00 => TeleportConfirm{
+                teleport_id: VarInt,
+            },
+            0x01 => QueryBlockNBT{
+                transaction_id: VarInt,
+                location: Position,
+            },
+            0x02 => SetDifficulty{
+                new_difficulty: Difficulty,
+            },
+            /// MessageAcknowledgment acknowledges messages without sending one,
+            /// once too many are pending.
+            0x03 => MessageAcknowledgment {
+                message_count: VarInt,
+            },
+            /// ChatCommand is sent when the client executes a command, without
+            /// the leading '/'.
+            0x04 => ChatCommand {
+                command: String,
+                timestamp: i64,
+                salt: i64,
+                /// The signed arguments followed by a `LastSeenUpdate`, see
+                /// `play::chat_signing`.
+                signatures: Vec<u8>,
+            },
+            /// ChatMessage is sent by the client when it sends a chat message.
+            0x05 => ChatMessage {
+                message: String,
+                timestamp: i64,
+                salt: i64,
+                has_signature: bool,
+                signature: Vec<u8> => with(
+                    read = read_chat_signature,
+                    write = write_chat_signature
+                ),
+                /// A `LastSeenUpdate`, see `play::chat_signing`.
+                last_seen: Vec<u8>,
+            },
+            /// PlayerSession announces the key the client signs its messages with.
+            0x06 => PlayerSession {
+                session_id: UUID,
+                expires_at: i64,
+                public_key: LenPrefixedBytes<VarInt>,
+                key_signature: LenPrefixedBytes<VarInt>,
+            },
+            /// ChunkBatchReceived answers ChunkBatchFinished with the chunks
+            /// per tick the client wants to receive.
+            0x07 => ChunkBatchReceived {
+                chunks_per_tick: f32,
+            },
+            /// ClientStatus is sent to update the client's status
+            0x08 => ClientStatus{
+                action_id: ClientStatusAction,
+            },
+            /// ClientSettings is sent by the client to update its current settings.
+            0x09 => ClientSettings {
+                locale: String,
+                view_distance: Bounded<u8, 2, 32>,
+                chat_mode: VarInt,
+                chat_colors: bool,
+                displayed_skin_parts: SkinParts,
+                main_hand: VarInt,
+                enable_text_filtering: bool,
+                allow_server_listings: bool,
+            },
+            /// TabComplete is sent by the client when the client presses tab in
+            /// the chat box.
+            0x0a => TabComplete {
+                transaction_id: VarInt,
+                text: String,
+            },
+            /// ConfigurationAcknowledged answers StartConfiguration and switches
+            /// both sides back to configuration.
+            0x0b => ConfigurationAcknowledged,
+            /// ClickWindowButton is used for clicking an enchantment, lectern, stonecutter, or loom.
+            0x0c => ClickWindowButton {
+                id: u8,
+                button: u8,
+            },
+            /// ClickWindow is sent when the client clicks in a window.
+            0x0d => ClickWindow {
+                id: u8,
+                state_id: VarInt,
+                slot: i16,
+                button: u8,
+                mode: VarInt,
+                changed_slots: Vec<(i16, NetworkSlot)> => with(
+                    read = read_changed_slots,
+                    write = write_changed_slots
+                ),
+                carried_item: NetworkSlot,
+            },
+            /// CloseWindow is sent when the client closes a window.
+            0x0e => CloseWindow {
+                id: u8,
+            },
+            /// ContainerSlotStateChanged toggles a slot of a crafter.
+            0x0f => ContainerSlotStateChanged {
+                slot: VarInt,
+                window_id: VarInt,
+                enabled: bool,
+            },
+            /// PluginMessageServerbound is used for custom messages between the client
+            /// and server. This is mainly for plugins/mods but vanilla has a few channels
+            /// registered too.
+            0x10 => PluginMessageServerbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            0x11 => EditBook {
+                slot: VarInt,
+                entries: LenPrefixed<VarInt, String>,
+                has_title: bool,
+                title: String where |p| {p.has_title},
+            },
+            0x12 => QueryEntityNBT {
+                transaction_id: VarInt,
+                entity_id: VarInt,
+            },
+            /// UseEntity is sent when the user interacts (right clicks) or attacks
+            /// (left clicks) an entity.
+            0x13 => UseEntity {
+                target_id: VarInt,
+                ty: VarInt,
+                target_x: f32 where |p| {p.ty.0 == 2},
+                target_y: f32 where |p| {p.ty.0 == 2},
+                target_z: f32 where |p| {p.ty.0 == 2},
+                hand: Hand where |p| {p.ty.0 == 0 || p.ty.0 == 2},
+                sneaking: bool,
+            },
+            /// Sent when Generate is pressed on the Jigsaw Block interface.
+            0x14 => GenerateStructure {
+                location: Position,
+                levels: VarInt,
+                keep_jigsaws: bool,
+            },
+            /// KeepAliveServerbound is sent by a client as a response to a
+            /// KeepAliveClientbound. If the client doesn't reply the server
+            /// may disconnect the client.
+            0x15 => KeepAliveServerbound {
+                id: i64,
+            },
+            0x16 => LockDifficulty {
+                locked: bool,
+            },
+            /// PlayerPosition is used to update the player's position.
+            0x17 => PlayerPosition {
+                x: f64,
+                y: f64,
+                z: f64,
+                on_ground: bool,
+            },
+            /// PlayerPositionLook is a combination of PlayerPosition and
+            /// PlayerLook.
+            0x18 => PlayerPositionLook {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// PlayerLook is used to update the player's rotation.
+            0x19 => PlayerLook {
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// Player is used to update whether the player is on the ground or not.
+            0x1a => Player {
+                on_ground: bool,
+            },
+            /// Sent by the client when in a vehicle instead of the normal move packet.
+            0x1b => VehicleMove {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+            },
+            /// SteerBoat is used to visually update the boat paddles.
+            0x1c => SteerBoat {
+                left_paddle_turning: bool,
+                right_paddle_turning: bool,
+            },
+            0x1d => PickItem {
+                slot_to_use: VarInt,
+            },
+            /// PingRequest asks for a PingResponse, used by the debug ping chart.
+            0x1e => PingRequest {
+                payload: i64,
+            },
+            /// CraftRecipeRequest is sent when player clicks a recipe in the crafting book.
+            0x1f => CraftRecipeRequest {
+                window_id: u8,
+                recipe: String,
+                make_all: bool,
+            },
+            0x20 => ClientAbilities {
+                flags: AbilityFlags,
+            },
+            /// PlayerDigging is sent when the client starts/stops digging a block.
+            /// It also can be sent for droppping items and eating/shooting.
+            0x21 => PlayerDigging {
+                status: DiggingStatus,
+                location: Position,
+                face: Direction6 as u8,
+                /// Answered by AcknowledgeBlockChange.
+                sequence: VarInt,
+            },
+            /// PlayerAction is sent when a player preforms various actions.
+            0x22 => PlayerAction{
+                entity_id: VarInt,
+                action_id: VarInt,
+                jump_boost: VarInt,
+            },
+            /// SteerVehicle is sent by the client when steers or preforms an action
+            /// on a vehicle.
+            0x23 => SteerVehicle {
+                sideways: f32,
+                forward: f32,
+                flags: SteerFlags,
+            },
+            0x24 => PlayPong {
+                id: i32,
+            },
+            0x25 => SetRecipeBookState {
+                book_id: RecipeBookType as VarInt,
+                book_open: bool,
+                filter_active: bool,
+            },
+            0x26 => SetDisplayedRecipe {
+                recipe_id: String,
+            },
+            0x27 => NameItem {
+                item_name: String,
+            },
+            /// ResourcePackStatus informs the server of the client's progress with
+            /// the pack pushed under `uuid`.
+            0x28 => ResourcePackStatus {
+                uuid: UUID,
+                result: VarInt,
+            },
+            0x29 => AdvancementTab {
+                action: VarInt,
+                tab_id: String where |p| {p.action.0 == 0},
+            },
+            0x2a => SelectTrade {
+                selected_slot: VarInt,
+            },
+            0x2b => SetBeaconEffect {
+                has_primary_effect: bool,
+                primary_effect: VarInt where |p| {p.has_primary_effect},
+                has_secondary_effect: bool,
+                secondary_effect: VarInt where |p| {p.has_secondary_effect},
+            },
+            /// HeldItemChange is sent when the player changes the currently active
+            /// hotbar slot.
+            0x2c => HeldItemChange {
+                slot: Bounded<i16, 0, 8>,
+            },
+            0x2d => UpdateCommandBlock {
+                location: Position,
+                command: String,
+                mode: VarInt,
+                flags: CommandBlockFlags,
+            },
+            0x2e => UpdateCommandBlockMinecart {
+                entity_id: VarInt,
+                command: String,
+                track_output: bool,
+            },
+            /// CreativeInventoryAction is sent when the client clicks in the creative
+            /// inventory. This is used to spawn items in creative.
+            0x2f => CreativeInventoryAction {
+                /// -1 drops the item.
+                slot: Bounded<i16, -1, 45>,
+                clicked_item: NetworkSlot,
+            },
+            0x30 => UpdateJigsawBlock {
+                location: Position,
+                name: String,
+                target: String,
+                pool: String,
+                final_state: String,
+                joint_type: String,
+                selection_priority: VarInt,
+                placement_priority: VarInt,
+            },
+            0x31 => UpdateStructureBlock {
+                location: Position,
+                action: VarInt,
+                mode: VarInt,
+                name: String,
+                offset_x: i8,
+                offset_y: i8,
+                offset_z: i8,
+                size_x: i8,
+                size_y: i8,
+                size_z: i8,
+                mirror: VarInt,
+                rotation: VarInt,
+                metadata: String,
+                integrity: f32,
+                seed: VarLong,
+                flags: StructureBlockFlags,
+            },
+            /// SetSign sets the text on a side of a sign.
+            0x32 => SetSign {
+                location: Position,
+                is_front_text: bool,
+                line1: String,
+                line2: String,
+                line3: String,
+                line4: String,
+            },
+            /// ArmSwing is sent by the client when the player left clicks
+            /// (to swing their arm).
+            0x33 => ArmSwing {
+                hand: Hand,
+            },
+            /// SpectateTeleport is sent by clients in spectator mode to teleport to a player.
+            0x34 => SpectateTeleport {
+                target: UUID,
+            },
+            0x35 => PlayerBlockPlacement {
+                hand: Hand,
+                location: Position,
+                face: Direction6,
+                cursor_x: f32,
+                cursor_y: f32,
+                cursor_z: f32,
+                inside_block: bool,
+                sequence: VarInt,
+            },
+            /// UseItem is sent when the client tries to use an item.
+            0x36 => UseItem {
+                hand: Hand,
+                sequence: VarInt,
+            },
+        },
+        Direction::ClientBound => {
+            /// BundleDelimiter starts and ends a bundle of packets the client
+            /// handles within the same tick.
+            0x00 => BundleDelimiter,
+            /// SpawnEntity spawns any entity, players and mobs included.
+            0x01 => SpawnEntity {
+                entity_id: VarInt,
+                uuid: UUID,
+                ty: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                pitch: i8,
+                yaw: i8,
+                head_yaw: i8,
+                data: VarInt,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            },
+            /// SpawnExperienceOrb spawns a single experience orb into the world when
+            /// it is in range of the client. The count controls the amount of experience
+            /// gained when collected.
+            0x02 => SpawnExperienceOrb {
+                entity_id: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                count: i16,
+            },
+            /// Animation is sent by the server to play an animation on a specific entity.
+            0x03 => Animation {
+                entity_id: VarInt,
+                animation_id: u8,
+            },
+            /// Statistics is used to update the statistics screen for the client.
+            0x04 => Statistics {
+                statistices: LenPrefixed<VarInt, packet::Statistic>,
+            },
+            /// AcknowledgeBlockChange confirms the block changes of the client up to `sequence`.
+            0x05 => AcknowledgeBlockChange {
+                sequence: VarInt,
+            },
+            /// BlockBreakAnimation is used to create and update the block breaking
+            /// animation played when a player starts digging a block.
+            0x06 => BlockBreakAnimation {
+                entity_id: VarInt,
+                location: Position,
+                stage: i8,
+            },
+            /// UpdateBlockEntity updates the nbt tag of a block entity in the
+            /// world.
+            0x07 => UpdateBlockEntity {
+                location: Position,
+                ty: VarInt,
+                nbt: NetworkNbt,
+            },
+            /// BlockAction triggers different actions depending on the target block.
+            0x08 => BlockAction {
+                location: Position,
+                byte1: u8,
+                byte2: u8,
+                block_type: VarInt,
+            },
+            /// BlockChange is used to update a single block on the client.
+            0x09 => BlockChange {
+                location: Position,
+                block_id: VarInt,
+            },
+            /// BossBar displays and/or changes a boss bar that is displayed on the
+            /// top of the client's screen. This is normally used for bosses such as
+            /// the ender dragon or the wither.
+            0x0a => BossBar {
+                uuid: UUID,
+                action: BossBarAction,
+            },
+            /// ServerDifficulty changes the displayed difficulty in the client's menu
+            /// as well as some ui changes for hardcore.
+            0x0b => ServerDifficulty {
+                difficulty: Difficulty,
+                locked: bool,
+            },
+            /// ChunkBatchFinished ends a batch of `batch_size` chunks, the client
+            /// answers with ChunkBatchReceived.
+            0x0c => ChunkBatchFinished {
+                batch_size: VarInt,
+            },
+            /// ChunkBatchStart starts a batch of chunks, the client measures how
+            /// long it takes to receive it.
+            0x0d => ChunkBatchStart,
+            /// ChunkBiomes replaces the biomes of loaded chunks, `(x, z, data)`.
+            0x0e => ChunkBiomes {
+                chunks: Vec<(i32, i32, Vec<u8>)> => with(
+                    read = read_chunk_biomes,
+                    write = write_chunk_biomes
+                ),
+            },
+            0x0f => ClearTitles {
+                reset: bool,
+            },
+            /// TabCompleteReply is sent as a reply to a tab completion request.
+            /// The matches should be possible completions for the command/chat the
+            /// player sent.
+            0x10 => TabCompleteReply {
+                transaction_id: VarInt,
+                start: VarInt,
+                length: VarInt,
+                /// Each match with its tooltip, if any.
+                matches: Vec<(String, NetworkNbt)> => with(
+                    read = read_suggestions,
+                    write = write_suggestions
+                ),
+            },
+            /// DeclareCommands sends the command graph, kept as bytes.
+            0x11 => DeclareCommands {
+                data: Vec<u8>,
+            },
+            /// WindowClose forces the client to close the window with the given id,
+            /// e.g. a chest getting destroyed.
+            0x12 => WindowClose {
+                id: u8,
+            },
+            /// WindowItems sets every slot of a window at once.
+            0x13 => WindowItems {
+                id: u8,
+                state_id: VarInt,
+                items: Vec<NetworkSlot> => with(
+                    read = read_window_items,
+                    write = write_window_items
+                ),
+                carried_item: NetworkSlot,
+            },
+            /// WindowProperty changes the value of a property of a window.
+            0x14 => WindowProperty {
+                id: u8,
+                property: i16,
+                value: i16,
+            },
+            /// WindowSetSlot changes an itemstack in one of the slots in a window.
+            0x15 => WindowSetSlot {
+                id: i8,
+                state_id: VarInt,
+                slot: i16,
+                item: NetworkSlot,
+            },
+            /// SetCooldown disables a set item (by id) for the set number of ticks.
+            0x16 => SetCooldown {
+                item_id: VarInt,
+                ticks: VarInt,
+            },
+            /// ChatSuggestions adds, removes or sets custom chat completions.
+            0x17 => ChatSuggestions {
+                action: VarInt,
+                entries: LenPrefixed<VarInt, String>,
+            },
+            /// PluginMessageClientbound is used for custom messages between the client
+            /// and server. This is mainly for plugins/mods but vanilla has a few channels
+            /// registered too.
+            0x18 => PluginMessageClientbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            0x19 => DamageEvent {
+                entity_id: VarInt,
+                source_type_id: VarInt,
+                /// The entity id of the cause plus one, 0 if there is none.
+                source_cause_id: VarInt,
+                source_direct_id: VarInt,
+                has_source_position: bool,
+                source_x: f64 where |p| {p.has_source_position},
+                source_y: f64 where |p| {p.has_source_position},
+                source_z: f64 where |p| {p.has_source_position},
+            },
+            /// DeleteMessage hides a message from the chat.
+            0x1a => DeleteMessage {
+                /// The id of the message in the client's cache plus one, or 0
+                /// followed by its signature.
+                message_id: VarInt,
+                signature: Vec<u8>,
+            },
+            /// Disconnect causes the client to disconnect displaying the passed reason.
+            0x1b => Disconnect {
+                reason: NetworkNbt,
+            },
+            /// DisguisedChat is chat that is not signed by a player, like /say
+            /// from the console.
+            0x1c => DisguisedChat {
+                message: NetworkNbt,
+                chat_type: VarInt,
+                sender_name: NetworkNbt,
+                has_target_name: bool,
+                target_name: NetworkNbt where |p| {p.has_target_name},
+            },
+            /// EntityAction causes an entity to preform an action based on the passed
+            /// id.
+            0x1d => EntityAction {
+                entity_id: i32,
+                action_id: u8,
+            },
+            /// Explosion is sent when an explosion is triggered (tnt, creeper etc).
+            /// This plays the effect and removes the effected blocks.
+            0x1e => Explosion {
+                x: f64,
+                y: f64,
+                z: f64,
+                radius: f32,
+                records: LenPrefixed<VarInt, packet::ExplosionRecord>,
+                velocity_x: f32,
+                velocity_y: f32,
+                velocity_z: f32,
+                block_interaction: VarInt,
+                /// The small and large particles and the sound, kept as bytes.
+                effects: Vec<u8>,
+            },
+            /// ChunkUnload tells the client to unload the chunk at the specified
+            /// position, z first since 1.20.2.
+            0x1f => ChunkUnload {
+                z: i32,
+                x: i32,
+            },
+            /// ChangeGameState is used to modify the game's state like gamemode or
+            /// weather.
+            0x20 => ChangeGameState {
+                reason: u8,
+                value: f32,
+            },
+            0x21 => WindowOpenHorse {
+                window_id: u8,
+                number_of_slots: VarInt,
+                entity_id: i32,
+            },
+            0x22 => HurtAnimation {
+                entity_id: VarInt,
+                yaw: f32,
+            },
+            0x23 => InitializeWorldBorder{
+                x: f64,
+                z: f64,
+                old_diameter: f64,
+                new_diameter: f64,
+                speed: VarLong,
+                portal_tp_boundary: VarInt,
+                warning_blocks: VarInt,
+                warning_time: VarInt,
+            },
+            /// KeepAliveClientbound is sent by a server to check if the
+            /// client is still responding and keep the connection open.
+            /// The client should reply with the KeepAliveServerbound
+            /// setting ID to the same as this one.
+            0x24 => KeepAliveClientbound {
+                id: i64,
+            },
+            /// ChunkDataAndUpdateLight sends a whole column along with its light.
+            0x25 => ChunkDataAndUpdateLight {
+                chunk_x: i32,
+                chunk_z: i32,
+                heightmaps: NetworkNbt,
+                data: LenPrefixedBytes<VarInt>,
+                block_entities: Vec<ChunkBlockEntity> => with(
+                    read = read_block_entities,
+                    write = write_block_entities
+                ),
+                sky_light_mask: LenPrefixed<VarInt, i64>,
+                block_light_mask: LenPrefixed<VarInt, i64>,
+                empty_sky_light_mask: LenPrefixed<VarInt, i64>,
+                empty_block_light_mask: LenPrefixed<VarInt, i64>,
+                sky_light: LenPrefixed<VarInt, LenPrefixed<VarInt, u8>>,
+                block_light: LenPrefixed<VarInt, LenPrefixed<VarInt, u8>>,
+            },
+            /// Effect plays a sound effect or particle at the target location with the
+            /// volume (of sounds) being relative to the player's position unless
+            /// DisableRelative is set to true.
+            0x26 => Effect {
+                effect_id: i32,
+                location: Position,
+                data: i32,
+                disable_relative: bool,
+            },
+            /// Particle spawns particles at the target location with the various
+            /// modifiers.
+            0x27 => Particle {
+                particle_id: VarInt,
+                long_distance: bool,
+                x: f64,
+                y: f64,
+                z: f64,
+                offset_x: f32,
+                offset_y: f32,
+                offset_z: f32,
+                speed: f32,
+                count: i32,
+                /// Depends on the particle, kept as bytes.
+                data: Vec<u8>,
+            },
+            0x28 => UpdateLight {
+                chunk_x: VarInt,
+                chunk_z: VarInt,
+                sky_light_mask: LenPrefixed<VarInt, i64>,
+                block_light_mask: LenPrefixed<VarInt, i64>,
+                empty_sky_light_mask: LenPrefixed<VarInt, i64>,
+                empty_block_light_mask: LenPrefixed<VarInt, i64>,
+                sky_light: LenPrefixed<VarInt, LenPrefixed<VarInt, u8>>,
+                block_light: LenPrefixed<VarInt, LenPrefixed<VarInt, u8>>,
+            },
+            /// JoinGame is sent after completing configuration. The registries
+            /// were sent in configuration, the dimension is referenced by name.
+            0x29 => JoinGame {
+                /// The entity id the client will be referenced by
+                entity_id: i32,
+                /// Whether hardcore mode is enabled
+                is_hardcore: bool,
+                /// Identifiers for all worlds on the server
+                world_names: LenPrefixed<VarInt, String>,
+                /// The max number of players on the server
+                max_players: VarInt,
+                /// The render distance (2-32)
+                view_distance: Bounded<VarInt, 2, 32>,
+                simulation_distance: VarInt,
+                /// Whether the client should reduce the amount of debug
+                /// information it displays in F3 mode
+                reduced_debug_info: bool,
+                /// Whether to prompt or immediately respawn
+                enable_respawn_screen: bool,
+                /// Whether only unlocked recipes can be crafted
+                do_limited_crafting: bool,
+                dimension_type: String,
+                /// The world being spawned into
+                world_name: String,
+                /// Truncated SHA-256 hash of world's seed
+                hashed_seed: i64,
+                /// The starting gamemode of the client
+                gamemode: GameMode,
+                /// The previous gamemode of the client, -1 if there is none
+                previous_gamemode: i8,
+                /// Whether the world is in debug mode
+                is_debug: bool,
+                /// Whether the world is a superflat world
+                is_flat: bool,
+                has_death_location: bool,
+                death_dimension: String where |p| {p.has_death_location},
+                death_location: Position where |p| {p.has_death_location},
+                portal_cooldown: VarInt,
+            },
+            /// Maps updates a single map's contents
+            0x2a => Maps {
+                map_id: VarInt,
+                scale: i8,
+                locked: bool,
+                has_icons: bool,
+                icons: Vec<MapIcon> => with(
+                    read = read_map_icons,
+                    write = write_map_icons
+                ) where |p| {p.has_icons},
+                columns: u8,
+                rows: u8 where |p| {p.columns > 0},
+                x: u8 where |p| {p.columns > 0},
+                z: u8 where |p| {p.columns > 0},
+                data: LenPrefixedBytes<VarInt> where |p| {p.columns > 0},
+            },
+            /// TradeList lists the trades of a villager, kept as bytes after the window id.
+            0x2b => TradeList {
+                id: VarInt,
+                data: Vec<u8>,
+            },
+            /// EntityMove moves the entity with the id by the offsets provided.
+            0x2c => EntityMove {
+                entity_id: VarInt,
+                delta_x: FixedPoint12<i16>,
+                delta_y: FixedPoint12<i16>,
+                delta_z: FixedPoint12<i16>,
+                on_ground: bool,
+            },
+            /// EntityLookAndMove is a combination of EntityMove and EntityLook.
+            0x2d => EntityLookAndMove {
+                entity_id: VarInt,
+                delta_x: FixedPoint12<i16>,
+                delta_y: FixedPoint12<i16>,
+                delta_z: FixedPoint12<i16>,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            /// EntityLook rotates the entity to the new angles provided.
+            0x2e => EntityLook {
+                entity_id: VarInt,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            /// Teleports the player's vehicle
+            0x2f => VehicleTeleport {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+            },
+            /// Opens the book GUI.
+            0x30 => OpenBook {
+                hand: Hand,
+            },
+            /// WindowOpen tells the client to open the inventory window of the given
+            /// type. The ID is used to reference the instance of the window in
+            /// other packets.
+            0x31 => WindowOpen {
+                id: VarInt,
+                ty: VarInt,
+                title: NetworkNbt,
+            },
+            /// SignEditorOpen causes the client to open the editor for a side of a sign.
+            0x32 => SignEditorOpen {
+                location: Position,
+                is_front_text: bool,
+            },
+            0x33 => PlayPing {
+                id: i32,
+            },
+            /// PingResponse answers PingRequest with its payload.
+            0x34 => PingResponse {
+                payload: i64,
+            },
+            /// CraftRecipeResponse is a response to CraftRecipeRequest, notifies the UI.
+            0x35 => CraftRecipeResponse {
+                window_id: u8,
+                recipe: String,
+            },
+            /// PlayerAbilities is used to modify the players current abilities. Flying,
+            /// creative, god mode etc.
+            0x36 => PlayerAbilities {
+                flags: AbilityFlags,
+                flying_speed: f32,
+                walking_speed: f32,
+            },
+            /// PlayerChat is a message signed by a player.
+            0x37 => PlayerChat {
+                sender: UUID,
+                index: VarInt,
+                has_signature: bool,
+                signature: Vec<u8> => with(
+                    read = read_player_chat_signature,
+                    write = write_player_chat_signature
+                ),
+                /// The body, previous messages, unsigned content, filter and chat
+                /// type, see `play::chat_signing`.
+                data: Vec<u8>,
+            },
+            0x38 => EndCombatEvent {
+                duration: VarInt,
+            },
+            0x39 => EnterCombatEvent,
+            0x3a => DeathCombatEvent {
+                player_id: VarInt,
+                message: NetworkNbt,
+            },
+            /// PlayerInfoRemove removes players from the player list.
+            0x3b => PlayerInfoRemove {
+                uuids: LenPrefixed<VarInt, UUID>,
+            },
+            /// PlayerInfoUpdate adds and updates players of the player list, see
+            /// `play::tab_list`.
+            0x3c => PlayerInfoUpdate {
+                actions: u8,
+                data: Vec<u8>,
+            },
+            0x3d => FacePlayer {
+                feet_eyes: VarInt,
+                target_x: f64,
+                target_y: f64,
+                target_z: f64,
+                is_entity: bool,
+                entity_id: VarInt where |p| {p.is_entity},
+                entity_feet_eyes: VarInt where |p| {p.is_entity},
+            },
+            0x3e => TeleportPlayer {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                flags: RelativeTeleportFlags,
+                teleport_id: VarInt,
+            },
+            0x3f => UnlockRecipes{
+                action: VarInt,
+                crafting_book_open: bool,
+                filtering_craftable: bool,
+                smelting_book_open: bool,
+                filtering_smeltable: bool,
+                blast_furnace_open: bool,
+                filtering_blast_furnace: bool,
+                smoker_open: bool,
+                filtering_smoker: bool,
+                recipe_ids: LenPrefixed<VarInt, String>,
+                recipe_ids2: LenPrefixed<VarInt, String> where |p| {
+                    p.action.0 == 0
+                }
+            },
+            0x40 => EntityDestroy{
+                entity_ids: LenPrefixed<VarInt, VarInt>,
+            },
+            /// EntityRemoveEffect removes an effect from an entity.
+            0x41 => EntityRemoveEffect {
+                entity_id: VarInt,
+                effect_id: VarInt,
+            },
+            /// ResetScore removes the score of an entity from one or every objective.
+            0x42 => ResetScore {
+                entity_name: String,
+                has_objective: bool,
+                objective: String where |p| {p.has_objective},
+            },
+            /// ResourcePackPop removes the pack with the UUID, or every pack.
+            0x43 => ResourcePackPop {
+                has_uuid: bool,
+                uuid: UUID where |p| {p.has_uuid},
+            },
+            /// ResourcePackPush causes the client to check its cache for the requested
+            /// resource and download it if its missing. Once the resource pack
+            /// is obtained the client will use it along with the packs it has.
+            0x44 => ResourcePackPush {
+                uuid: UUID,
+                url: String,
+                hash: String,
+                forced: bool,
+                has_prompt: bool,
+                prompt: NetworkNbt where |p| {p.has_prompt},
+            },
+            /// Respawn is sent to respawn the player after death or when they move worlds.
+            0x45 => Respawn {
+                dimension_type: String,
+                world_name: String,
+                hashed_seed: i64,
+                gamemode: GameMode,
+                /// -1 if there is none
+                previous_gamemode: i8,
+                is_debug: bool,
+                is_flat: bool,
+                has_death_location: bool,
+                death_dimension: String where |p| {p.has_death_location},
+                death_location: Position where |p| {p.has_death_location},
+                portal_cooldown: VarInt,
+                /// Bit 0 keeps attributes, bit 1 keeps metadata.
+                data_kept: u8,
+            },
+            /// EntityHeadLook rotates an entity's head to the new angle.
+            0x46 => EntityHeadLook {
+                entity_id: VarInt,
+                head_yaw: i8,
+            },
+            /// MultiBlockChange is used to update a batch of blocks in a single packet.
+            0x47 => MultiBlockChange {
+                chunk_section_pos: u64,
+                records: LenPrefixed<VarInt, VarLong>,
+            },
+            /// SelectAdvancementTab indicates the client should switch the advancement tab.
+            0x48 => SelectAdvancementTab {
+                has_id: bool,
+                tab_id: String where |p| {p.has_id},
+            },
+            /// ServerData sends the MOTD and icon shown in the server list.
+            0x49 => ServerData {
+                motd: NetworkNbt,
+                has_icon: bool,
+                icon: LenPrefixedBytes<VarInt> where |p| {p.has_icon},
+                enforces_secure_chat: bool,
+            },
+            0x4a => ActionBar {
+                message: NetworkNbt,
+            },
+            0x4b => WorldBorderCenter {
+                x: f64,
+                z: f64,
+            },
+            0x4c => WorldBorderResize {
+                old_diameter: f64,
+                new_diameter: f64,
+                speed: VarLong,
+            },
+            0x4d => WorldBorderSize {
+                diameter: f64,
+            },
+            0x4e => WorldBorderWarningTime {
+                warning_time: VarInt,
+            },
+            0x4f => WorldBorderWarningDistance {
+                warning_blocks: VarInt,
+            },
+            /// Camera causes the client to spectate the entity with the passed id.
+            /// Use the player's id to de-spectate.
+            0x50 => Camera {
+                target_id: VarInt,
+            },
+            /// SetCurrentHotbarSlot changes the player's currently selected hotbar item.
+            0x51 => SetCurrentHotbarSlot {
+                slot: Bounded<u8, 0, 8>,
+            },
+            /// UpdateViewPosition is used to determine what chunks should be remain loaded.
+            0x52 => UpdateViewPosition {
+                chunk_x: VarInt,
+                chunk_z: VarInt,
+            },
+            /// UpdateViewDistance is sent by the integrated server when changing render distance.
+            0x53 => UpdateViewDistance {
+                view_distance: Bounded<VarInt, 2, 32>,
+            },
+            /// SpawnPosition is sent to change the player's current spawn point. Currently
+            /// only used by the client for the compass.
+            0x54 => SpawnPosition {
+                location: Position,
+                angle: f32,
+            },
+            /// ScoreboardDisplay is used to set the display position of a scoreboard.
+            0x55 => ScoreboardDisplay {
+                position: VarInt,
+                name: String,
+            },
+            /// EntityMetadata updates the metadata for an entity, kept as bytes.
+            0x56 => EntityMetadata {
+                entity_id: VarInt,
+                metadata: Vec<u8>,
+            },
+            /// EntityAttach attaches to entities together, either by mounting or leashing.
+            /// -1 can be used at the EntityID to deattach.
+            0x57 => EntityAttach {
+                entity_id: i32,
+                vehicle: i32,
+            },
+            /// EntityVelocity sets the velocity of an entity in 1/8000 of a block
+            /// per a tick.
+            0x58 => EntityVelocity {
+                entity_id: VarInt,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            },
+            /// EntityEquipment is sent to display items on an entity, like a sword
+            /// or armor, as pairs of slot and item.
+            0x59 => EntityEquipment {
+                entity_id: VarInt,
+                equipment: Vec<(u8, NetworkSlot)> => with(
+                    read = read_equipment,
+                    write = write_equipment
+                ),
+            },
+            /// SetExperience updates the experience bar on the client.
+            0x5a => SetExperience {
+                experience_bar: f32,
+                level: VarInt,
+                total_experience: VarInt,
+            },
+            /// UpdateHealth is sent by the server to update the player's health and food.
+            0x5b => UpdateHealth {
+                health: f32,
+                food: VarInt,
+                food_saturation: f32,
+            },
+            /// ScoreboardObjective creates/updates a scoreboard objective.
+            0x5c => ScoreboardObjective {
+                name: String,
+                mode: u8,
+                value: NetworkNbt where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                ty: VarInt where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                /// The optional number format, kept as bytes.
+                number_format: Vec<u8>,
+            },
+            /// SetPassengers mounts entities to an entity
+            0x5d => SetPassengers {
+                entity_id: VarInt,
+                passengers: LenPrefixed<VarInt, VarInt>,
+            },
+            /// Teams creates and updates teams
+            0x5e => Teams {
+                name: String,
+                mode: TeamsAction,
+            },
+            /// UpdateScore sets the score of an entity, ResetScore removes it.
+            0x5f => UpdateScore {
+                name: String,
+                object_name: String,
+                value: VarInt,
+                has_display_name: bool,
+                display_name: NetworkNbt where |p| {p.has_display_name},
+                /// The optional number format, kept as bytes.
+                number_format: Vec<u8>,
+            },
+            0x60 => SetSimulationDistance {
+                simulation_distance: VarInt,
+            },
+            0x61 => SetTitleSubtitle {
+                subtitle: NetworkNbt,
+            },
+            /// TimeUpdate is sent to sync the world's time to the client, the client
+            /// will manually tick the time itself so this doesn't need to sent repeatedly
+            /// but if the server or client has issues keeping up this can fall out of sync
+            /// so it is a good idea to send this now and again
+            0x62 => TimeUpdate {
+                world_age: i64,
+                time_of_day: i64,
+            },
+            /// Title sets the text of the on-screen title.
+            0x63 => Title {
+                title: NetworkNbt,
+            },
+            0x64 => SetTitleTimes {
+                fade_in: i32,
+                stay: i32,
+                fade_out: i32,
+            },
+            /// Plays a sound effect from an entity. A `sound_id` of 0 is followed
+            /// by the sound inline, otherwise it is the id plus one.
+            0x65 => EntitySoundEffect {
+                sound_id: VarInt,
+                sound_name: String where |p| {p.sound_id.0 == 0},
+                has_fixed_range: bool where |p| {p.sound_id.0 == 0},
+                fixed_range: f32 where |p| {p.sound_id.0 == 0 && p.has_fixed_range},
+                sound_category: VarInt,
+                entity_id: VarInt,
+                volume: f32,
+                pitch: f32,
+                seed: i64,
+            },
+            /// SoundEffect plays a sound at the target location, with `sound_id`
+            /// like EntitySoundEffect.
+            0x66 => SoundEffect {
+                sound_id: VarInt,
+                sound_name: String where |p| {p.sound_id.0 == 0},
+                has_fixed_range: bool where |p| {p.sound_id.0 == 0},
+                fixed_range: f32 where |p| {p.sound_id.0 == 0 && p.has_fixed_range},
+                category: VarInt,
+                x: i32,
+                y: i32,
+                z: i32,
+                volume: f32,
+                pitch: f32,
+                seed: i64,
+            },
+            /// StartConfiguration sends the client back to configuration, it
+            /// answers with ConfigurationAcknowledged.
+            0x67 => StartConfiguration,
+            0x68 => StopSound {
+                flags: StopSoundFlags,
+                source: VarInt where |p| {
+                    p.flags.contains(StopSoundFlags::SOURCE)
+                },
+                sound: String where |p| {
+                    p.flags.contains(StopSoundFlags::SOUND)
+                }
+            },
+            /// SystemChat is a message that is not sent by a player.
+            0x69 => SystemChat {
+                content: NetworkNbt,
+                /// Shown above the hotbar instead of in the chat.
+                overlay: bool,
+            },
+            /// PlayerListHeaderFooter updates the header/footer of the player list.
+            0x6a => PlayerListHeaderFooter {
+                header: NetworkNbt,
+                footer: NetworkNbt,
+            },
+            0x6b => NBTQueryResponse {
+                transaction_id: VarInt,
+                nbt: NetworkNbt,
+            },
+            /// CollectItem causes the collected item to fly towards the collector. This
+            /// does not destroy the entity.
+            0x6c => CollectItem {
+                collected_entity_id: VarInt,
+                collector_entity_id: VarInt,
+                number_of_items: VarInt,
+            },
+            /// EntityTeleport teleports the entity to the target location. This is
+            /// sent if the entity moves further than EntityMove allows.
+            0x6d => EntityTeleport {
+                entity_id: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            /// TickingState sets the tick rate of the world and whether it is frozen.
+            0x6e => TickingState {
+                tick_rate: f32,
+                is_frozen: bool,
+            },
+            /// StepTick advances a frozen world by some ticks.
+            0x6f => StepTick {
+                tick_steps: VarInt,
+            },
+            /// Advancements adds, removes and updates advancements, kept as bytes.
+            0x70 => Advancements {
+                data: Vec<u8>,
+            },
+            /// EntityProperties updates the properties for an entity.
+            0x71 => EntityProperties{
+                entity_id: VarInt,
+                properties: LenPrefixed<VarInt, packet::EntityProperty>,
+            },
+            /// EntityEffect applies a status effect to an entity for a given duration.
+            0x72 => EntityEffect {
+                entity_id: VarInt,
+                effect_id: VarInt,
+                amplifier: i8,
+                duration: VarInt,
+                flags: u8,
+                has_factor_data: bool,
+                factor_data: NetworkNbt where |p| {p.has_factor_data},
+            },
+            /// DeclareRecipes sends every recipe, kept as bytes.
+            0x73 => DeclareRecipes {
+                data: Vec<u8>,
+            },
+            0x74 => Tags {
+                tags: TagSet,
+            },
+        }
+    }
+});
@@ -0,0 +1,1704 @@
+//! Protocol 767, 1.21 and 1.21.1.
+//!
+//! Since 1.20.5 registries are sent one per RegistryData packet, see
+//! `world::registry::RegistryStore`, and entries of data packs both sides
+//! know, see KnownPacksClientbound, come without their data. Items carry data
+//! components instead of NBT, see `mojang::component`. Slots are read with
+//! this version also without a `DecodeContext`, other packets carrying items
+//! keep them as bytes.
+//!
+//! 1.20.5 also added cookies, which a server stores on the client to read them
+//! back after transferring it to another server, see `play::cookies`. 1.21
+//! adds server links and custom report details. Packets that match 1.20.4 use
+//! its names.
+use crate::protocol::State;
+use crate::protocol::Direction;
+use crate::protocol::implementation::steven::v1_17::RecipeBookType;
+use crate::protocol::implementation::steven::v1_20_4::{self, BossBarAction, TeamsAction};
+use crate::proxy::bungeecord::ProfileProperty;
+use steven_protocol::protocol::{LenPrefixedBytes, UUID, LenPrefixed, FixedPoint12};
+use steven_protocol::format;
+use steven_protocol::protocol::packet;
+use steven_protocol::protocol::{VarInt, VarLong};
+use steven_shared::Position;
+use crate::segment::context;
+use crate::segment::implementation::bounded::Bounded;
+use crate::segment::implementation::mojang::{self, read_string, write_string};
+use crate::segment::implementation::mojang::block_entity::ChunkBlockEntity;
+use crate::segment::implementation::mojang::identifier::Identifier;
+use crate::segment::implementation::mojang::map::MapIcon;
+use crate::segment::implementation::mojang::registry::RegistryDataEntry;
+use crate::segment::implementation::mojang::slot::Slot;
+use crate::segment::implementation::mojang::tags::TagSet;
+use crate::segment::implementation::nbt::NetworkNbt;
+use crate::segment::implementation::types::{ClientStatusAction, Difficulty, DiggingStatus, Direction6, GameMode, Hand};
+use crate::segment::implementation::types::{AbilityFlags, CommandBlockFlags, RelativeTeleportFlags, SkinParts, SteerFlags, StopSoundFlags, StructureBlockFlags};
+use crate::segment::implementation::var::write_var_int;
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use v1_20_4::{read_block_entities, read_chunk_biomes, read_map_icons, read_properties, read_suggestions};
+use v1_20_4::{write_block_entities, write_chunk_biomes, write_map_icons, write_properties, write_suggestions};
+
+/// A data pack, like `minecraft:core` of version `1.21`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KnownPack{
+    pub namespace: String,
+    pub id: String,
+    pub version: String
+}
+
+fn read_known_packs<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Vec<KnownPack>> {
+    let count = mojang::read_count(reader)?;
+    (0..count).map(|_| Ok(KnownPack{
+        namespace: read_string(reader)?,
+        id: read_string(reader)?,
+        version: read_string(reader)?
+    })).collect()
+}
+
+fn write_known_packs<P, W: std::io::Write>(_packet: &P, packs: &[KnownPack], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(packs.len() as i32, writer)?;
+    for pack in packs {
+        write_string(&pack.namespace, writer)?;
+        write_string(&pack.id, writer)?;
+        write_string(&pack.version, writer)?;
+    }
+    Ok(())
+}
+
+fn read_report_details<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Vec<(String, String)>> {
+    let count = mojang::read_count(reader)?;
+    (0..count).map(|_| Ok((read_string(reader)?, read_string(reader)?))).collect()
+}
+
+fn write_report_details<P, W: std::io::Write>(_packet: &P, details: &[(String, String)], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(details.len() as i32, writer)?;
+    for (title, description) in details {
+        write_string(title, writer)?;
+        write_string(description, writer)?;
+    }
+    Ok(())
+}
+
+fn read_registry_entries<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Vec<RegistryDataEntry>> {
+    mojang::read_var_list(reader)
+}
+
+fn write_registry_entries<P, W: std::io::Write>(_packet: &P, entries: &[RegistryDataEntry], writer: &mut W) -> std::io::Result<()> {
+    mojang::write_var_list(entries, writer)
+}
+
+/// Runs `f` with the version of this definition, which decides the layout of `Slot`.
+fn this_version<T>(f: impl FnOnce() -> T) -> T {
+    context::versioned(767).enter(f)
+}
+
+fn read_slot<P, R: std::io::Read>(_packet: &P, reader: &mut R) -> std::io::Result<Slot> {
+    let mut slot = Slot::default();
+    this_version(|| slot.read_from_stream(reader))?;
+    Ok(slot)
+}
+
+fn write_slot<P, W: std::io::Write>(_packet: &P, slot: &Slot, writer: &mut W) -> std::io::Result<()> {
+    this_version(|| slot.write_to_stream(writer))
+}
+
+fn read_changed_slots<R: std::io::Read>(_packet: &ClickWindow, reader: &mut R) -> std::io::Result<Vec<(i16, Slot)>> {
+    this_version(|| {
+        let count = mojang::read_count(reader)?;
+        (0..count).map(|_| {
+            let slot = reader.read_i16::<BigEndian>()?;
+            let mut item = Slot::default();
+            item.read_from_stream(reader)?;
+            Ok((slot, item))
+        }).collect()
+    })
+}
+
+fn write_changed_slots<W: std::io::Write>(_packet: &ClickWindow, slots: &[(i16, Slot)], writer: &mut W) -> std::io::Result<()> {
+    this_version(|| {
+        write_var_int(slots.len() as i32, writer)?;
+        for (slot, item) in slots {
+            writer.write_i16::<BigEndian>(*slot)?;
+            item.write_to_stream(writer)?;
+        }
+        Ok(())
+    })
+}
+
+fn read_window_items<R: std::io::Read>(_packet: &WindowItems, reader: &mut R) -> std::io::Result<Vec<Slot>> {
+    this_version(|| mojang::read_var_list(reader))
+}
+
+fn write_window_items<W: std::io::Write>(_packet: &WindowItems, items: &[Slot], writer: &mut W) -> std::io::Result<()> {
+    this_version(|| mojang::write_var_list(items, writer))
+}
+
+fn read_equipment<R: std::io::Read>(_packet: &EntityEquipment, reader: &mut R) -> std::io::Result<Vec<(u8, Slot)>> {
+    this_version(|| {
+        let mut equipment = Vec::new();
+        loop {
+            let slot = reader.read_u8()?;
+            let mut item = Slot::default();
+            item.read_from_stream(reader)?;
+            equipment.push((slot & 0x7f, item));
+            if slot & 0x80 == 0 {
+                return Ok(equipment);
+            }
+        }
+    })
+}
+
+fn write_equipment<W: std::io::Write>(_packet: &EntityEquipment, equipment: &[(u8, Slot)], writer: &mut W) -> std::io::Result<()> {
+    if equipment.is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "EntityEquipment needs at least one slot"));
+    }
+    this_version(|| {
+        for (i, (slot, item)) in equipment.iter().enumerate() {
+            let more = if i + 1 < equipment.len() { 0x80 } else { 0 };
+            writer.write_u8(slot & 0x7f | more)?;
+            item.write_to_stream(writer)?;
+        }
+        Ok(())
+    })
+}
+
+fn read_chat_signature<R: std::io::Read>(packet: &ChatMessage, reader: &mut R) -> std::io::Result<Vec<u8>> {
+    v1_20_4::read_signature(packet.has_signature, reader)
+}
+
+fn write_chat_signature<W: std::io::Write>(_packet: &ChatMessage, signature: &[u8], writer: &mut W) -> std::io::Result<()> {
+    v1_20_4::write_signature(signature, writer)
+}
+
+fn read_player_chat_signature<R: std::io::Read>(packet: &PlayerChat, reader: &mut R) -> std::io::Result<Vec<u8>> {
+    v1_20_4::read_signature(packet.has_signature, reader)
+}
+
+fn write_player_chat_signature<W: std::io::Write>(_packet: &PlayerChat, signature: &[u8], writer: &mut W) -> std::io::Result<()> {
+    v1_20_4::write_signature(signature, writer)
+}
+
+crate::define_protocol!(pub Proto_1_21, "1.21", 767 {
+    State::Handshaking => {
+        Direction::ServerBound => {
+            /// Handshake is the first packet sent in the protocol.
+            /// Its used for deciding if the request is a client
+            /// is requesting status information about the server
+            /// (MOTD, players etc) or trying to login to the server.
+            ///
+            /// The host and port fields are not used by the vanilla
+            /// server but are there for virtual server hosting to
+            /// be able to redirect a client to a target server with
+            /// a single address + port.
+            ///
+            /// Some modified servers/proxies use the handshake field
+            /// differently, packing information into the field other
+            /// than the hostname due to the protocol not providing
+            /// any system for custom information to be transfered
+            /// by the client to the server until after login.
+            0x00 => Handshake {
+                /// The protocol version of the connecting client
+                protocol_version: VarInt,
+                /// The hostname the client connected to
+                host: String,
+                /// The port the client connected to
+                port: u16,
+                /// The next protocol state the client wants, 3 to log in after a
+                /// Transfer
+                next: VarInt,
+            }
+        }
+    },
+    State::Status => {
+        Direction::ServerBound => {
+            /// StatusRequest is sent by the client instantly after
+            /// switching to the Status protocol state and is used
+            /// to signal the server to send a StatusResponse to the
+            /// client
+            0x00 => StatusRequest,
+            /// StatusPing is sent by the client after recieving a
+            /// StatusResponse. The client uses the time from sending
+            /// the ping until the time of recieving a pong to measure
+            /// the latency between the client and the server.
+            0x01 => StatusPing{
+                ping: i64,
+            }
+        },
+        Direction::ClientBound => {
+            /// StatusResponse is sent as a reply to a StatusRequest.
+            /// The Status should contain a json encoded structure with
+            /// version information, a player sample, a description/MOTD
+            /// and optionally a favicon.
+            0x00 => StatusResponse{
+                status: String,
+            },
+            /// StatusPong is sent as a reply to a StatusPing.
+            /// The Time field should be exactly the same as the
+            /// one sent by the client.
+            0x01 => StatusPong{
+                ping: i64
+            }
+        }
+    },
+    State::Login => {
+        Direction::ServerBound => {
+            /// LoginStart is sent immeditately after switching into the login
+            /// state. The passed username is used by the server to authenticate
+            /// the player in online mode.
+            0x00 => LoginStart{
+                username: String,
+                /// The UUID of the player's account, unused by the vanilla server.
+                uuid: UUID,
+            },
+            /// EncryptionResponse is sent as a reply to EncryptionRequest. All
+            /// packets following this one must be encrypted with AES/CFB8
+            /// encryption.
+            0x01 => EncryptionResponse{
+                /// The key for the AES/CFB8 cipher encrypted with the
+                /// public key
+                shared_secret: LenPrefixedBytes<VarInt>,
+                /// The verify token from the request encrypted with the
+                /// public key
+                verify_token: LenPrefixedBytes<VarInt>,
+            },
+            0x02 => LoginPluginResponse{
+                message_id: VarInt,
+                successful: bool,
+                data: Vec<u8>,
+            },
+            /// LoginAcknowledged answers LoginSuccess and switches both sides to
+            /// the configuration state.
+            0x03 => LoginAcknowledged,
+            /// LoginCookieResponse answers LoginCookieRequest with the cookie stored
+            /// under the key, if any.
+            0x04 => LoginCookieResponse {
+                key: Identifier,
+                has_payload: bool,
+                /// At most 5120 bytes.
+                payload: LenPrefixedBytes<VarInt> where |p| {p.has_payload},
+            }
+        },
+        Direction::ClientBound => {
+            /// LoginDisconnect is sent by the server if there was any issues
+            /// authenticating the player during login or the general server
+            /// issues (e.g. too many players). Still JSON, unlike later states.
+            0x00 => LoginDisconnect{
+                reason: format::Component,
+            },
+            /// EncryptionRequest is sent by the server if the server is in
+            /// online mode. If it is not sent then its assumed the server is
+            /// in offline mode.
+            0x01 => EncryptionRequest{
+                /// Generally empty, left in from legacy auth
+                /// but is still used by the client if provided
+                server_id: String,
+                /// A RSA Public key serialized in x.509 PRIX format
+                public_key: LenPrefixedBytes<VarInt>,
+                /// Token used by the server to verify encryption is working
+                /// correctly
+                verify_token: LenPrefixedBytes<VarInt>,
+                /// Whether the client has to authenticate with Mojang, set by
+                /// online mode servers.
+                should_authenticate: bool,
+            },
+            /// LoginSuccess ends login once the client sent LoginAcknowledged.
+            0x02 => LoginSuccess{
+                uuid: UUID,
+                username: String,
+                properties: Vec<ProfileProperty> => with(
+                    read = read_properties,
+                    write = write_properties
+                ),
+                /// Whether the client disconnects on packets it fails to decode.
+                strict_error_handling: bool,
+            },
+            /// SetInitialCompression sets the compression threshold during the
+            /// login state.
+            0x03 => SetInitialCompression{
+                /// Threshold where a packet should be sent compressed
+                threshold: VarInt,
+            },
+            0x04 => LoginPluginRequest{
+                message_id: VarInt,
+                channel: String,
+                data: Vec<u8>,
+            },
+            /// LoginCookieRequest asks the client for the cookie stored under the
+            /// key, e.g. one stored before the client was transferred.
+            0x05 => LoginCookieRequest {
+                key: Identifier,
+            }
+        }
+    },
+    State::Configuration => {
+        Direction::ServerBound => {
+            /// ConfigurationClientSettings is ClientSettings during configuration.
+            0x00 => ConfigurationClientSettings {
+                locale: String,
+                view_distance: Bounded<u8, 2, 32>,
+                chat_mode: VarInt,
+                chat_colors: bool,
+                displayed_skin_parts: SkinParts,
+                main_hand: VarInt,
+                enable_text_filtering: bool,
+                allow_server_listings: bool,
+            },
+            /// ConfigurationCookieResponse answers ConfigurationCookieRequest with the cookie stored
+            /// under the key, if any.
+            0x01 => ConfigurationCookieResponse {
+                key: Identifier,
+                has_payload: bool,
+                /// At most 5120 bytes.
+                payload: LenPrefixedBytes<VarInt> where |p| {p.has_payload},
+            },
+            0x02 => ConfigurationPluginMessageServerbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            /// AcknowledgeFinishConfiguration answers FinishConfiguration and
+            /// switches both sides to play.
+            0x03 => AcknowledgeFinishConfiguration,
+            0x04 => ConfigurationKeepAliveServerbound {
+                id: i64,
+            },
+            0x05 => ConfigurationPong {
+                id: i32,
+            },
+            0x06 => ConfigurationResourcePackStatus {
+                uuid: UUID,
+                result: VarInt,
+            },
+            /// KnownPacksServerbound answers with the packs the client has too.
+            0x07 => KnownPacksServerbound {
+                packs: Vec<KnownPack> => with(
+                    read = read_known_packs,
+                    write = write_known_packs
+                ),
+            }
+        },
+        Direction::ClientBound => {
+            /// ConfigurationCookieRequest is CookieRequest during configuration.
+            0x00 => ConfigurationCookieRequest {
+                key: Identifier,
+            },
+            0x01 => ConfigurationPluginMessageClientbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            0x02 => ConfigurationDisconnect {
+                reason: NetworkNbt,
+            },
+            /// FinishConfiguration tells the client the server sent everything
+            /// it needs to enter play.
+            0x03 => FinishConfiguration,
+            0x04 => ConfigurationKeepAliveClientbound {
+                id: i64,
+            },
+            0x05 => ConfigurationPing {
+                id: i32,
+            },
+            /// ResetChat clears the chat history and signature chain of the client.
+            0x06 => ResetChat,
+            /// RegistryData sends one synchronized registry, like dimension types or
+            /// biomes. Entries of known packs come without their data.
+            0x07 => RegistryData {
+                registry_id: Identifier,
+                entries: Vec<RegistryDataEntry> => with(
+                    read = read_registry_entries,
+                    write = write_registry_entries
+                ),
+            },
+            /// ConfigurationResourcePackPop removes the pack with the UUID, or every pack.
+            0x08 => ConfigurationResourcePackPop {
+                has_uuid: bool,
+                uuid: UUID where |p| {p.has_uuid},
+            },
+            0x09 => ConfigurationResourcePackPush {
+                uuid: UUID,
+                url: String,
+                hash: String,
+                forced: bool,
+                has_prompt: bool,
+                prompt: NetworkNbt where |p| {p.has_prompt},
+            },
+            /// ConfigurationStoreCookie is StoreCookie during configuration.
+            0x0a => ConfigurationStoreCookie {
+                key: Identifier,
+                /// At most 5120 bytes.
+                payload: LenPrefixedBytes<VarInt>,
+            },
+            /// ConfigurationTransfer is Transfer during configuration.
+            0x0b => ConfigurationTransfer {
+                host: String,
+                port: VarInt,
+            },
+            0x0c => FeatureFlags {
+                flags: LenPrefixed<VarInt, String>,
+            },
+            0x0d => ConfigurationTags {
+                tags: TagSet,
+            },
+            /// KnownPacksClientbound lists the data packs of the server whose registry
+            /// entries the client may already know.
+            0x0e => KnownPacksClientbound {
+                packs: Vec<KnownPack> => with(
+                    read = read_known_packs,
+                    write = write_known_packs
+                ),
+            },
+            0x0f => ConfigurationCustomReportDetails {
+                details: Vec<(String, String)> => with(
+                    read = read_report_details,
+                    write = write_report_details
+                ),
+            },
+            0x10 => ConfigurationServerLinks {
+                /// The links, kept as bytes.
+                links: Vec<u8>,
+            }
+        }
+    },
+    State::Play => {
+        Direction::ServerBound => {
+            /// TeleportConfirm is sent by the client as a reply to a telport from
+            /// the server.
+            0x00 => TeleportConfirm{
+                teleport_id: VarInt,
+            },
+            0x01 => QueryBlockNBT{
+                transaction_id: VarInt,
+                location: Position,
+            },
+            0x02 => SetDifficulty{
+                new_difficulty: Difficulty,
+            },
+            /// MessageAcknowledgment acknowledges messages without sending one,
+            /// once too many are pending.
+            0x03 => MessageAcknowledgment {
+                message_count: VarInt,
+            },
+            /// ChatCommand is sent when the client executes a command without signed
+            /// arguments, without the leading '/'.
+            0x04 => ChatCommand {
+                command: String,
+            },
+            /// SignedChatCommand is sent when the client executes a command with
+            /// signed arguments.
+            0x05 => SignedChatCommand {
+                command: String,
+                timestamp: i64,
+                salt: i64,
+                /// The signed arguments followed by a `LastSeenUpdate`, see
+                /// `play::chat_signing`.
+                signatures: Vec<u8>,
+            },
+            /// ChatMessage is sent by the client when it sends a chat message.
+            0x06 => ChatMessage {
+                message: String,
+                timestamp: i64,
+                salt: i64,
+                has_signature: bool,
+                signature: Vec<u8> => with(
+                    read = read_chat_signature,
+                    write = write_chat_signature
+                ),
+                /// A `LastSeenUpdate`, see `play::chat_signing`.
+                last_seen: Vec<u8>,
+            },
+            /// PlayerSession announces the key the client signs its messages with.
+            0x07 => PlayerSession {
+                session_id: UUID,
+                expires_at: i64,
+                public_key: LenPrefixedBytes<VarInt>,
+                key_signature: LenPrefixedBytes<VarInt>,
+            },
+            /// ChunkBatchReceived answers ChunkBatchFinished with the chunks
+            /// per tick the client wants to receive.
+            0x08 => ChunkBatchReceived {
+                chunks_per_tick: f32,
+            },
+            /// ClientStatus is sent to update the client's status
+            0x09 => ClientStatus{
+                action_id: ClientStatusAction,
+            },
+            /// ClientSettings is sent by the client to update its current settings.
+            0x0a => ClientSettings {
+                locale: String,
+                view_distance: Bounded<u8, 2, 32>,
+                chat_mode: VarInt,
+                chat_colors: bool,
+                displayed_skin_parts: SkinParts,
+                main_hand: VarInt,
+                enable_text_filtering: bool,
+                allow_server_listings: bool,
+            },
+            /// TabComplete is sent by the client when the client presses tab in
+            /// the chat box.
+            0x0b => TabComplete {
+                transaction_id: VarInt,
+                text: String,
+            },
+            /// ConfigurationAcknowledged answers StartConfiguration and switches
+            /// both sides back to configuration.
+            0x0c => ConfigurationAcknowledged,
+            /// ClickWindowButton is used for clicking an enchantment, lectern, stonecutter, or loom.
+            0x0d => ClickWindowButton {
+                id: u8,
+                button: u8,
+            },
+            /// ClickWindow is sent when the client clicks in a window.
+            0x0e => ClickWindow {
+                id: u8,
+                state_id: VarInt,
+                slot: i16,
+                button: u8,
+                mode: VarInt,
+                changed_slots: Vec<(i16, Slot)> => with(
+                    read = read_changed_slots,
+                    write = write_changed_slots
+                ),
+                carried_item: Slot => with(
+                    read = read_slot,
+                    write = write_slot
+                ),
+            },
+            /// CloseWindow is sent when the client closes a window.
+            0x0f => CloseWindow {
+                id: u8,
+            },
+            /// ContainerSlotStateChanged toggles a slot of a crafter.
+            0x10 => ContainerSlotStateChanged {
+                slot: VarInt,
+                window_id: VarInt,
+                enabled: bool,
+            },
+            /// CookieResponse answers CookieRequest with the cookie stored
+            /// under the key, if any.
+            0x11 => CookieResponse {
+                key: Identifier,
+                has_payload: bool,
+                /// At most 5120 bytes.
+                payload: LenPrefixedBytes<VarInt> where |p| {p.has_payload},
+            },
+            /// PluginMessageServerbound is used for custom messages between the client
+            /// and server. This is mainly for plugins/mods but vanilla has a few channels
+            /// registered too.
+            0x12 => PluginMessageServerbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            /// DebugSampleSubscription asks for DebugSample packets for a while, sent
+            /// by operators with the tick chart open.
+            0x13 => DebugSampleSubscription {
+                sample_type: VarInt,
+            },
+            0x14 => EditBook {
+                slot: VarInt,
+                entries: LenPrefixed<VarInt, String>,
+                has_title: bool,
+                title: String where |p| {p.has_title},
+            },
+            0x15 => QueryEntityNBT {
+                transaction_id: VarInt,
+                entity_id: VarInt,
+            },
+            /// UseEntity is sent when the user interacts (right clicks) or attacks
+            /// (left clicks) an entity.
+            0x16 => UseEntity {
+                target_id: VarInt,
+                ty: VarInt,
+                target_x: f32 where |p| {p.ty.0 == 2},
+                target_y: f32 where |p| {p.ty.0 == 2},
+                target_z: f32 where |p| {p.ty.0 == 2},
+                hand: Hand where |p| {p.ty.0 == 0 || p.ty.0 == 2},
+                sneaking: bool,
+            },
+            /// Sent when Generate is pressed on the Jigsaw Block interface.
+            0x17 => GenerateStructure {
+                location: Position,
+                levels: VarInt,
+                keep_jigsaws: bool,
+            },
+            /// KeepAliveServerbound is sent by a client as a response to a
+            /// KeepAliveClientbound. If the client doesn't reply the server
+            /// may disconnect the client.
+            0x18 => KeepAliveServerbound {
+                id: i64,
+            },
+            0x19 => LockDifficulty {
+                locked: bool,
+            },
+            /// PlayerPosition is used to update the player's position.
+            0x1a => PlayerPosition {
+                x: f64,
+                y: f64,
+                z: f64,
+                on_ground: bool,
+            },
+            /// PlayerPositionLook is a combination of PlayerPosition and
+            /// PlayerLook.
+            0x1b => PlayerPositionLook {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// PlayerLook is used to update the player's rotation.
+            0x1c => PlayerLook {
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// Player is used to update whether the player is on the ground or not.
+            0x1d => Player {
+                on_ground: bool,
+            },
+            /// Sent by the client when in a vehicle instead of the normal move packet.
+            0x1e => VehicleMove {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+            },
+            /// SteerBoat is used to visually update the boat paddles.
+            0x1f => SteerBoat {
+                left_paddle_turning: bool,
+                right_paddle_turning: bool,
+            },
+            0x20 => PickItem {
+                slot_to_use: VarInt,
+            },
+            /// PingRequest asks for a PingResponse, used by the debug ping chart.
+            0x21 => PingRequest {
+                payload: i64,
+            },
+            /// CraftRecipeRequest is sent when player clicks a recipe in the crafting book.
+            0x22 => CraftRecipeRequest {
+                window_id: u8,
+                recipe: String,
+                make_all: bool,
+            },
+            0x23 => ClientAbilities {
+                flags: AbilityFlags,
+            },
+            /// PlayerDigging is sent when the client starts/stops digging a block.
+            /// It also can be sent for droppping items and eating/shooting.
+            0x24 => PlayerDigging {
+                status: DiggingStatus,
+                location: Position,
+                face: Direction6 as u8,
+                /// Answered by AcknowledgeBlockChange.
+                sequence: VarInt,
+            },
+            /// PlayerAction is sent when a player preforms various actions.
+            0x25 => PlayerAction{
+                entity_id: VarInt,
+                action_id: VarInt,
+                jump_boost: VarInt,
+            },
+            /// SteerVehicle is sent by the client when steers or preforms an action
+            /// on a vehicle.
+            0x26 => SteerVehicle {
+                sideways: f32,
+                forward: f32,
+                flags: SteerFlags,
+            },
+            0x27 => PlayPong {
+                id: i32,
+            },
+            0x28 => SetRecipeBookState {
+                book_id: RecipeBookType as VarInt,
+                book_open: bool,
+                filter_active: bool,
+            },
+            0x29 => SetDisplayedRecipe {
+                recipe_id: String,
+            },
+            0x2a => NameItem {
+                item_name: String,
+            },
+            /// ResourcePackStatus informs the server of the client's progress with
+            /// the pack pushed under `uuid`.
+            0x2b => ResourcePackStatus {
+                uuid: UUID,
+                result: VarInt,
+            },
+            0x2c => AdvancementTab {
+                action: VarInt,
+                tab_id: String where |p| {p.action.0 == 0},
+            },
+            0x2d => SelectTrade {
+                selected_slot: VarInt,
+            },
+            0x2e => SetBeaconEffect {
+                has_primary_effect: bool,
+                primary_effect: VarInt where |p| {p.has_primary_effect},
+                has_secondary_effect: bool,
+                secondary_effect: VarInt where |p| {p.has_secondary_effect},
+            },
+            /// HeldItemChange is sent when the player changes the currently active
+            /// hotbar slot.
+            0x2f => HeldItemChange {
+                slot: Bounded<i16, 0, 8>,
+            },
+            0x30 => UpdateCommandBlock {
+                location: Position,
+                command: String,
+                mode: VarInt,
+                flags: CommandBlockFlags,
+            },
+            0x31 => UpdateCommandBlockMinecart {
+                entity_id: VarInt,
+                command: String,
+                track_output: bool,
+            },
+            /// CreativeInventoryAction is sent when the client clicks in the creative
+            /// inventory. This is used to spawn items in creative.
+            0x32 => CreativeInventoryAction {
+                /// -1 drops the item.
+                slot: Bounded<i16, -1, 45>,
+                clicked_item: Slot => with(
+                    read = read_slot,
+                    write = write_slot
+                ),
+            },
+            0x33 => UpdateJigsawBlock {
+                location: Position,
+                name: String,
+                target: String,
+                pool: String,
+                final_state: String,
+                joint_type: String,
+                selection_priority: VarInt,
+                placement_priority: VarInt,
+            },
+            0x34 => UpdateStructureBlock {
+                location: Position,
+                action: VarInt,
+                mode: VarInt,
+                name: String,
+                offset_x: i8,
+                offset_y: i8,
+                offset_z: i8,
+                size_x: i8,
+                size_y: i8,
+                size_z: i8,
+                mirror: VarInt,
+                rotation: VarInt,
+                metadata: String,
+                integrity: f32,
+                seed: VarLong,
+                flags: StructureBlockFlags,
+            },
+            /// SetSign sets the text on a side of a sign.
+            0x35 => SetSign {
+                location: Position,
+                is_front_text: bool,
+                line1: String,
+                line2: String,
+                line3: String,
+                line4: String,
+            },
+            /// ArmSwing is sent by the client when the player left clicks
+            /// (to swing their arm).
+            0x36 => ArmSwing {
+                hand: Hand,
+            },
+            /// SpectateTeleport is sent by clients in spectator mode to teleport to a player.
+            0x37 => SpectateTeleport {
+                target: UUID,
+            },
+            0x38 => PlayerBlockPlacement {
+                hand: Hand,
+                location: Position,
+                face: Direction6,
+                cursor_x: f32,
+                cursor_y: f32,
+                cursor_z: f32,
+                inside_block: bool,
+                sequence: VarInt,
+            },
+            /// UseItem is sent when the client tries to use an item.
+            0x39 => UseItem {
+                hand: Hand,
+                sequence: VarInt,
+                yaw: f32,
+                pitch: f32,
+            }
+        },
+        Direction::ClientBound => {
+            /// BundleDelimiter starts and ends a bundle of packets the client
+            /// handles within the same tick.
+            0x00 => BundleDelimiter,
+            /// SpawnEntity spawns any entity, players and mobs included.
+            0x01 => SpawnEntity {
+                entity_id: VarInt,
+                uuid: UUID,
+                ty: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                pitch: i8,
+                yaw: i8,
+                head_yaw: i8,
+                data: VarInt,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            },
+            /// SpawnExperienceOrb spawns a single experience orb into the world when
+            /// it is in range of the client. The count controls the amount of experience
+            /// gained when collected.
+            0x02 => SpawnExperienceOrb {
+                entity_id: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                count: i16,
+            },
+            /// Animation is sent by the server to play an animation on a specific entity.
+            0x03 => Animation {
+                entity_id: VarInt,
+                animation_id: u8,
+            },
+            /// Statistics is used to update the statistics screen for the client.
+            0x04 => Statistics {
+                statistices: LenPrefixed<VarInt, packet::Statistic>,
+            },
+            /// AcknowledgeBlockChange confirms the block changes of the client up to `sequence`.
+            0x05 => AcknowledgeBlockChange {
+                sequence: VarInt,
+            },
+            /// BlockBreakAnimation is used to create and update the block breaking
+            /// animation played when a player starts digging a block.
+            0x06 => BlockBreakAnimation {
+                entity_id: VarInt,
+                location: Position,
+                stage: i8,
+            },
+            /// UpdateBlockEntity updates the nbt tag of a block entity in the
+            /// world.
+            0x07 => UpdateBlockEntity {
+                location: Position,
+                ty: VarInt,
+                nbt: NetworkNbt,
+            },
+            /// BlockAction triggers different actions depending on the target block.
+            0x08 => BlockAction {
+                location: Position,
+                byte1: u8,
+                byte2: u8,
+                block_type: VarInt,
+            },
+            /// BlockChange is used to update a single block on the client.
+            0x09 => BlockChange {
+                location: Position,
+                block_id: VarInt,
+            },
+            /// BossBar displays and/or changes a boss bar that is displayed on the
+            /// top of the client's screen. This is normally used for bosses such as
+            /// the ender dragon or the wither.
+            0x0a => BossBar {
+                uuid: UUID,
+                action: BossBarAction,
+            },
+            /// ServerDifficulty changes the displayed difficulty in the client's menu
+            /// as well as some ui changes for hardcore.
+            0x0b => ServerDifficulty {
+                difficulty: Difficulty,
+                locked: bool,
+            },
+            /// ChunkBatchFinished ends a batch of `batch_size` chunks, the client
+            /// answers with ChunkBatchReceived.
+            0x0c => ChunkBatchFinished {
+                batch_size: VarInt,
+            },
+            /// ChunkBatchStart starts a batch of chunks, the client measures how
+            /// long it takes to receive it.
+            0x0d => ChunkBatchStart,
+            /// ChunkBiomes replaces the biomes of loaded chunks, `(x, z, data)`.
+            0x0e => ChunkBiomes {
+                chunks: Vec<(i32, i32, Vec<u8>)> => with(
+                    read = read_chunk_biomes,
+                    write = write_chunk_biomes
+                ),
+            },
+            0x0f => ClearTitles {
+                reset: bool,
+            },
+            /// TabCompleteReply is sent as a reply to a tab completion request.
+            /// The matches should be possible completions for the command/chat the
+            /// player sent.
+            0x10 => TabCompleteReply {
+                transaction_id: VarInt,
+                start: VarInt,
+                length: VarInt,
+                /// Each match with its tooltip, if any.
+                matches: Vec<(String, NetworkNbt)> => with(
+                    read = read_suggestions,
+                    write = write_suggestions
+                ),
+            },
+            /// DeclareCommands sends the command graph, kept as bytes.
+            0x11 => DeclareCommands {
+                data: Vec<u8>,
+            },
+            /// WindowClose forces the client to close the window with the given id,
+            /// e.g. a chest getting destroyed.
+            0x12 => WindowClose {
+                id: u8,
+            },
+            /// WindowItems sets every slot of a window at once.
+            0x13 => WindowItems {
+                id: u8,
+                state_id: VarInt,
+                items: Vec<Slot> => with(
+                    read = read_window_items,
+                    write = write_window_items
+                ),
+                carried_item: Slot => with(
+                    read = read_slot,
+                    write = write_slot
+                ),
+            },
+            /// WindowProperty changes the value of a property of a window.
+            0x14 => WindowProperty {
+                id: u8,
+                property: i16,
+                value: i16,
+            },
+            /// WindowSetSlot changes an itemstack in one of the slots in a window.
+            0x15 => WindowSetSlot {
+                id: i8,
+                state_id: VarInt,
+                slot: i16,
+                item: Slot => with(
+                    read = read_slot,
+                    write = write_slot
+                ),
+            },
+            /// CookieRequest asks the client for the cookie stored under the key.
+            0x16 => CookieRequest {
+                key: Identifier,
+            },
+            /// SetCooldown disables a set item (by id) for the set number of ticks.
+            0x17 => SetCooldown {
+                item_id: VarInt,
+                ticks: VarInt,
+            },
+            /// ChatSuggestions adds, removes or sets custom chat completions.
+            0x18 => ChatSuggestions {
+                action: VarInt,
+                entries: LenPrefixed<VarInt, String>,
+            },
+            /// PluginMessageClientbound is used for custom messages between the client
+            /// and server. This is mainly for plugins/mods but vanilla has a few channels
+            /// registered too.
+            0x19 => PluginMessageClientbound {
+                channel: String,
+                data: Vec<u8>,
+            },
+            0x1a => DamageEvent {
+                entity_id: VarInt,
+                source_type_id: VarInt,
+                /// The entity id of the cause plus one, 0 if there is none.
+                source_cause_id: VarInt,
+                source_direct_id: VarInt,
+                has_source_position: bool,
+                source_x: f64 where |p| {p.has_source_position},
+                source_y: f64 where |p| {p.has_source_position},
+                source_z: f64 where |p| {p.has_source_position},
+            },
+            /// DebugSample sends the durations of a tick to subscribed clients.
+            0x1b => DebugSample {
+                sample: LenPrefixed<VarInt, i64>,
+                sample_type: VarInt,
+            },
+            /// DeleteMessage hides a message from the chat.
+            0x1c => DeleteMessage {
+                /// The id of the message in the client's cache plus one, or 0
+                /// followed by its signature.
+                message_id: VarInt,
+                signature: Vec<u8>,
+            },
+            /// Disconnect causes the client to disconnect displaying the passed reason.
+            0x1d => Disconnect {
+                reason: NetworkNbt,
+            },
+            /// DisguisedChat is chat that is not signed by a player, like /say
+            /// from the console.
+            0x1e => DisguisedChat {
+                message: NetworkNbt,
+                chat_type: VarInt,
+                sender_name: NetworkNbt,
+                has_target_name: bool,
+                target_name: NetworkNbt where |p| {p.has_target_name},
+            },
+            /// EntityAction causes an entity to preform an action based on the passed
+            /// id.
+            0x1f => EntityAction {
+                entity_id: i32,
+                action_id: u8,
+            },
+            /// Explosion is sent when an explosion is triggered (tnt, creeper etc).
+            /// This plays the effect and removes the effected blocks.
+            0x20 => Explosion {
+                x: f64,
+                y: f64,
+                z: f64,
+                radius: f32,
+                records: LenPrefixed<VarInt, packet::ExplosionRecord>,
+                velocity_x: f32,
+                velocity_y: f32,
+                velocity_z: f32,
+                block_interaction: VarInt,
+                /// The small and large particles and the sound, kept as bytes.
+                effects: Vec<u8>,
+            },
+            /// ChunkUnload tells the client to unload the chunk at the specified
+            /// position, z first since 1.20.2.
+            0x21 => ChunkUnload {
+                z: i32,
+                x: i32,
+            },
+            /// ChangeGameState is used to modify the game's state like gamemode or
+            /// weather.
+            0x22 => ChangeGameState {
+                reason: u8,
+                value: f32,
+            },
+            0x23 => WindowOpenHorse {
+                window_id: u8,
+                number_of_slots: VarInt,
+                entity_id: i32,
+            },
+            0x24 => HurtAnimation {
+                entity_id: VarInt,
+                yaw: f32,
+            },
+            0x25 => InitializeWorldBorder{
+                x: f64,
+                z: f64,
+                old_diameter: f64,
+                new_diameter: f64,
+                speed: VarLong,
+                portal_tp_boundary: VarInt,
+                warning_blocks: VarInt,
+                warning_time: VarInt,
+            },
+            /// KeepAliveClientbound is sent by a server to check if the
+            /// client is still responding and keep the connection open.
+            /// The client should reply with the KeepAliveServerbound
+            /// setting ID to the same as this one.
+            0x26 => KeepAliveClientbound {
+                id: i64,
+            },
+            /// ChunkDataAndUpdateLight sends a whole column along with its light.
+            0x27 => ChunkDataAndUpdateLight {
+                chunk_x: i32,
+                chunk_z: i32,
+                heightmaps: NetworkNbt,
+                data: LenPrefixedBytes<VarInt>,
+                block_entities: Vec<ChunkBlockEntity> => with(
+                    read = read_block_entities,
+                    write = write_block_entities
+                ),
+                sky_light_mask: LenPrefixed<VarInt, i64>,
+                block_light_mask: LenPrefixed<VarInt, i64>,
+                empty_sky_light_mask: LenPrefixed<VarInt, i64>,
+                empty_block_light_mask: LenPrefixed<VarInt, i64>,
+                sky_light: LenPrefixed<VarInt, LenPrefixed<VarInt, u8>>,
+                block_light: LenPrefixed<VarInt, LenPrefixed<VarInt, u8>>,
+            },
+            /// Effect plays a sound effect or particle at the target location with the
+            /// volume (of sounds) being relative to the player's position unless
+            /// DisableRelative is set to true.
+            0x28 => Effect {
+                effect_id: i32,
+                location: Position,
+                data: i32,
+                disable_relative: bool,
+            },
+            /// Particle spawns particles at the target location with the various
+            /// modifiers.
+            0x29 => Particle {
+                long_distance: bool,
+                x: f64,
+                y: f64,
+                z: f64,
+                offset_x: f32,
+                offset_y: f32,
+                offset_z: f32,
+                speed: f32,
+                count: i32,
+                particle_id: VarInt,
+                /// Depends on the particle, kept as bytes.
+                data: Vec<u8>,
+            },
+            0x2a => UpdateLight {
+                chunk_x: VarInt,
+                chunk_z: VarInt,
+                sky_light_mask: LenPrefixed<VarInt, i64>,
+                block_light_mask: LenPrefixed<VarInt, i64>,
+                empty_sky_light_mask: LenPrefixed<VarInt, i64>,
+                empty_block_light_mask: LenPrefixed<VarInt, i64>,
+                sky_light: LenPrefixed<VarInt, LenPrefixed<VarInt, u8>>,
+                block_light: LenPrefixed<VarInt, LenPrefixed<VarInt, u8>>,
+            },
+            /// JoinGame is sent after completing configuration. The registries
+            /// were sent in configuration, the dimension is referenced by name.
+            0x2b => JoinGame {
+                /// The entity id the client will be referenced by
+                entity_id: i32,
+                /// Whether hardcore mode is enabled
+                is_hardcore: bool,
+                /// Identifiers for all worlds on the server
+                world_names: LenPrefixed<VarInt, String>,
+                /// The max number of players on the server
+                max_players: VarInt,
+                /// The render distance (2-32)
+                view_distance: Bounded<VarInt, 2, 32>,
+                simulation_distance: VarInt,
+                /// Whether the client should reduce the amount of debug
+                /// information it displays in F3 mode
+                reduced_debug_info: bool,
+                /// Whether to prompt or immediately respawn
+                enable_respawn_screen: bool,
+                /// Whether only unlocked recipes can be crafted
+                do_limited_crafting: bool,
+                dimension_type: VarInt,
+                /// The world being spawned into
+                world_name: String,
+                /// Truncated SHA-256 hash of world's seed
+                hashed_seed: i64,
+                /// The starting gamemode of the client
+                gamemode: GameMode,
+                /// The previous gamemode of the client, -1 if there is none
+                previous_gamemode: i8,
+                /// Whether the world is in debug mode
+                is_debug: bool,
+                /// Whether the world is a superflat world
+                is_flat: bool,
+                has_death_location: bool,
+                death_dimension: String where |p| {p.has_death_location},
+                death_location: Position where |p| {p.has_death_location},
+                portal_cooldown: VarInt,
+                enforces_secure_chat: bool,
+            },
+            /// Maps updates a single map's contents
+            0x2c => Maps {
+                map_id: VarInt,
+                scale: i8,
+                locked: bool,
+                has_icons: bool,
+                icons: Vec<MapIcon> => with(
+                    read = read_map_icons,
+                    write = write_map_icons
+                ) where |p| {p.has_icons},
+                columns: u8,
+                rows: u8 where |p| {p.columns > 0},
+                x: u8 where |p| {p.columns > 0},
+                z: u8 where |p| {p.columns > 0},
+                data: LenPrefixedBytes<VarInt> where |p| {p.columns > 0},
+            },
+            /// TradeList lists the trades of a villager, kept as bytes after the window id.
+            0x2d => TradeList {
+                id: VarInt,
+                data: Vec<u8>,
+            },
+            /// EntityMove moves the entity with the id by the offsets provided.
+            0x2e => EntityMove {
+                entity_id: VarInt,
+                delta_x: FixedPoint12<i16>,
+                delta_y: FixedPoint12<i16>,
+                delta_z: FixedPoint12<i16>,
+                on_ground: bool,
+            },
+            /// EntityLookAndMove is a combination of EntityMove and EntityLook.
+            0x2f => EntityLookAndMove {
+                entity_id: VarInt,
+                delta_x: FixedPoint12<i16>,
+                delta_y: FixedPoint12<i16>,
+                delta_z: FixedPoint12<i16>,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            /// EntityLook rotates the entity to the new angles provided.
+            0x30 => EntityLook {
+                entity_id: VarInt,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            /// Teleports the player's vehicle
+            0x31 => VehicleTeleport {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+            },
+            /// Opens the book GUI.
+            0x32 => OpenBook {
+                hand: Hand,
+            },
+            /// WindowOpen tells the client to open the inventory window of the given
+            /// type. The ID is used to reference the instance of the window in
+            /// other packets.
+            0x33 => WindowOpen {
+                id: VarInt,
+                ty: VarInt,
+                title: NetworkNbt,
+            },
+            /// SignEditorOpen causes the client to open the editor for a side of a sign.
+            0x34 => SignEditorOpen {
+                location: Position,
+                is_front_text: bool,
+            },
+            0x35 => PlayPing {
+                id: i32,
+            },
+            /// PingResponse answers PingRequest with its payload.
+            0x36 => PingResponse {
+                payload: i64,
+            },
+            /// CraftRecipeResponse is a response to CraftRecipeRequest, notifies the UI.
+            0x37 => CraftRecipeResponse {
+                window_id: u8,
+                recipe: String,
+            },
+            /// PlayerAbilities is used to modify the players current abilities. Flying,
+            /// creative, god mode etc.
+            0x38 => PlayerAbilities {
+                flags: AbilityFlags,
+                flying_speed: f32,
+                walking_speed: f32,
+            },
+            /// PlayerChat is a message signed by a player.
+            0x39 => PlayerChat {
+                sender: UUID,
+                index: VarInt,
+                has_signature: bool,
+                signature: Vec<u8> => with(
+                    read = read_player_chat_signature,
+                    write = write_player_chat_signature
+                ),
+                /// The body, previous messages, unsigned content, filter and chat
+                /// type, see `play::chat_signing`.
+                data: Vec<u8>,
+            },
+            0x3a => EndCombatEvent {
+                duration: VarInt,
+            },
+            0x3b => EnterCombatEvent,
+            0x3c => DeathCombatEvent {
+                player_id: VarInt,
+                message: NetworkNbt,
+            },
+            /// PlayerInfoRemove removes players from the player list.
+            0x3d => PlayerInfoRemove {
+                uuids: LenPrefixed<VarInt, UUID>,
+            },
+            /// PlayerInfoUpdate adds and updates players of the player list, see
+            /// `play::tab_list`.
+            0x3e => PlayerInfoUpdate {
+                actions: u8,
+                data: Vec<u8>,
+            },
+            0x3f => FacePlayer {
+                feet_eyes: VarInt,
+                target_x: f64,
+                target_y: f64,
+                target_z: f64,
+                is_entity: bool,
+                entity_id: VarInt where |p| {p.is_entity},
+                entity_feet_eyes: VarInt where |p| {p.is_entity},
+            },
+            0x40 => TeleportPlayer {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                flags: RelativeTeleportFlags,
+                teleport_id: VarInt,
+            },
+            0x41 => UnlockRecipes{
+                action: VarInt,
+                crafting_book_open: bool,
+                filtering_craftable: bool,
+                smelting_book_open: bool,
+                filtering_smeltable: bool,
+                blast_furnace_open: bool,
+                filtering_blast_furnace: bool,
+                smoker_open: bool,
+                filtering_smoker: bool,
+                recipe_ids: LenPrefixed<VarInt, String>,
+                recipe_ids2: LenPrefixed<VarInt, String> where |p| {
+                    p.action.0 == 0
+                }
+            },
+            0x42 => EntityDestroy{
+                entity_ids: LenPrefixed<VarInt, VarInt>,
+            },
+            /// EntityRemoveEffect removes an effect from an entity.
+            0x43 => EntityRemoveEffect {
+                entity_id: VarInt,
+                effect_id: VarInt,
+            },
+            /// ResetScore removes the score of an entity from one or every objective.
+            0x44 => ResetScore {
+                entity_name: String,
+                has_objective: bool,
+                objective: String where |p| {p.has_objective},
+            },
+            /// ResourcePackPop removes the pack with the UUID, or every pack.
+            0x45 => ResourcePackPop {
+                has_uuid: bool,
+                uuid: UUID where |p| {p.has_uuid},
+            },
+            /// ResourcePackPush causes the client to check its cache for the requested
+            /// resource and download it if its missing. Once the resource pack
+            /// is obtained the client will use it along with the packs it has.
+            0x46 => ResourcePackPush {
+                uuid: UUID,
+                url: String,
+                hash: String,
+                forced: bool,
+                has_prompt: bool,
+                prompt: NetworkNbt where |p| {p.has_prompt},
+            },
+            /// Respawn is sent to respawn the player after death or when they move worlds.
+            0x47 => Respawn {
+                dimension_type: VarInt,
+                world_name: String,
+                hashed_seed: i64,
+                gamemode: GameMode,
+                /// -1 if there is none
+                previous_gamemode: i8,
+                is_debug: bool,
+                is_flat: bool,
+                has_death_location: bool,
+                death_dimension: String where |p| {p.has_death_location},
+                death_location: Position where |p| {p.has_death_location},
+                portal_cooldown: VarInt,
+                /// Bit 0 keeps attributes, bit 1 keeps metadata.
+                data_kept: u8,
+            },
+            /// EntityHeadLook rotates an entity's head to the new angle.
+            0x48 => EntityHeadLook {
+                entity_id: VarInt,
+                head_yaw: i8,
+            },
+            /// MultiBlockChange is used to update a batch of blocks in a single packet.
+            0x49 => MultiBlockChange {
+                chunk_section_pos: u64,
+                records: LenPrefixed<VarInt, VarLong>,
+            },
+            /// SelectAdvancementTab indicates the client should switch the advancement tab.
+            0x4a => SelectAdvancementTab {
+                has_id: bool,
+                tab_id: String where |p| {p.has_id},
+            },
+            /// ServerData sends the MOTD and icon shown in the server list.
+            0x4b => ServerData {
+                motd: NetworkNbt,
+                has_icon: bool,
+                icon: LenPrefixedBytes<VarInt> where |p| {p.has_icon},
+            },
+            0x4c => ActionBar {
+                message: NetworkNbt,
+            },
+            0x4d => WorldBorderCenter {
+                x: f64,
+                z: f64,
+            },
+            0x4e => WorldBorderResize {
+                old_diameter: f64,
+                new_diameter: f64,
+                speed: VarLong,
+            },
+            0x4f => WorldBorderSize {
+                diameter: f64,
+            },
+            0x50 => WorldBorderWarningTime {
+                warning_time: VarInt,
+            },
+            0x51 => WorldBorderWarningDistance {
+                warning_blocks: VarInt,
+            },
+            /// Camera causes the client to spectate the entity with the passed id.
+            /// Use the player's id to de-spectate.
+            0x52 => Camera {
+                target_id: VarInt,
+            },
+            /// SetCurrentHotbarSlot changes the player's currently selected hotbar item.
+            0x53 => SetCurrentHotbarSlot {
+                slot: Bounded<u8, 0, 8>,
+            },
+            /// UpdateViewPosition is used to determine what chunks should be remain loaded.
+            0x54 => UpdateViewPosition {
+                chunk_x: VarInt,
+                chunk_z: VarInt,
+            },
+            /// UpdateViewDistance is sent by the integrated server when changing render distance.
+            0x55 => UpdateViewDistance {
+                view_distance: Bounded<VarInt, 2, 32>,
+            },
+            /// SpawnPosition is sent to change the player's current spawn point. Currently
+            /// only used by the client for the compass.
+            0x56 => SpawnPosition {
+                location: Position,
+                angle: f32,
+            },
+            /// ScoreboardDisplay is used to set the display position of a scoreboard.
+            0x57 => ScoreboardDisplay {
+                position: VarInt,
+                name: String,
+            },
+            /// EntityMetadata updates the metadata for an entity, kept as bytes.
+            0x58 => EntityMetadata {
+                entity_id: VarInt,
+                metadata: Vec<u8>,
+            },
+            /// EntityAttach attaches to entities together, either by mounting or leashing.
+            /// -1 can be used at the EntityID to deattach.
+            0x59 => EntityAttach {
+                entity_id: i32,
+                vehicle: i32,
+            },
+            /// EntityVelocity sets the velocity of an entity in 1/8000 of a block
+            /// per a tick.
+            0x5a => EntityVelocity {
+                entity_id: VarInt,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            },
+            /// EntityEquipment is sent to display items on an entity, like a sword
+            /// or armor, as pairs of slot and item.
+            0x5b => EntityEquipment {
+                entity_id: VarInt,
+                /// Pairs of slot and item, the slot's top bit set if another
+                /// pair follows.
+                equipment: Vec<(u8, Slot)> => with(
+                    read = read_equipment,
+                    write = write_equipment
+                ),
+            },
+            /// SetExperience updates the experience bar on the client.
+            0x5c => SetExperience {
+                experience_bar: f32,
+                level: VarInt,
+                total_experience: VarInt,
+            },
+            /// UpdateHealth is sent by the server to update the player's health and food.
+            0x5d => UpdateHealth {
+                health: f32,
+                food: VarInt,
+                food_saturation: f32,
+            },
+            /// ScoreboardObjective creates/updates a scoreboard objective.
+            0x5e => ScoreboardObjective {
+                name: String,
+                mode: u8,
+                value: NetworkNbt where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                ty: VarInt where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                /// The optional number format, kept as bytes.
+                number_format: Vec<u8>,
+            },
+            /// SetPassengers mounts entities to an entity
+            0x5f => SetPassengers {
+                entity_id: VarInt,
+                passengers: LenPrefixed<VarInt, VarInt>,
+            },
+            /// Teams creates and updates teams
+            0x60 => Teams {
+                name: String,
+                mode: TeamsAction,
+            },
+            /// UpdateScore sets the score of an entity, ResetScore removes it.
+            0x61 => UpdateScore {
+                name: String,
+                object_name: String,
+                value: VarInt,
+                has_display_name: bool,
+                display_name: NetworkNbt where |p| {p.has_display_name},
+                /// The optional number format, kept as bytes.
+                number_format: Vec<u8>,
+            },
+            0x62 => SetSimulationDistance {
+                simulation_distance: VarInt,
+            },
+            0x63 => SetTitleSubtitle {
+                subtitle: NetworkNbt,
+            },
+            /// TimeUpdate is sent to sync the world's time to the client, the client
+            /// will manually tick the time itself so this doesn't need to sent repeatedly
+            /// but if the server or client has issues keeping up this can fall out of sync
+            /// so it is a good idea to send this now and again
+            0x64 => TimeUpdate {
+                world_age: i64,
+                time_of_day: i64,
+            },
+            /// Title sets the text of the on-screen title.
+            0x65 => Title {
+                title: NetworkNbt,
+            },
+            0x66 => SetTitleTimes {
+                fade_in: i32,
+                stay: i32,
+                fade_out: i32,
+            },
+            /// Plays a sound effect from an entity. A `sound_id` of 0 is followed
+            /// by the sound inline, otherwise it is the id plus one.
+            0x67 => EntitySoundEffect {
+                sound_id: VarInt,
+                sound_name: String where |p| {p.sound_id.0 == 0},
+                has_fixed_range: bool where |p| {p.sound_id.0 == 0},
+                fixed_range: f32 where |p| {p.sound_id.0 == 0 && p.has_fixed_range},
+                sound_category: VarInt,
+                entity_id: VarInt,
+                volume: f32,
+                pitch: f32,
+                seed: i64,
+            },
+            /// SoundEffect plays a sound at the target location, with `sound_id`
+            /// like EntitySoundEffect.
+            0x68 => SoundEffect {
+                sound_id: VarInt,
+                sound_name: String where |p| {p.sound_id.0 == 0},
+                has_fixed_range: bool where |p| {p.sound_id.0 == 0},
+                fixed_range: f32 where |p| {p.sound_id.0 == 0 && p.has_fixed_range},
+                category: VarInt,
+                x: i32,
+                y: i32,
+                z: i32,
+                volume: f32,
+                pitch: f32,
+                seed: i64,
+            },
+            /// StartConfiguration sends the client back to configuration, it
+            /// answers with ConfigurationAcknowledged.
+            0x69 => StartConfiguration,
+            0x6a => StopSound {
+                flags: StopSoundFlags,
+                source: VarInt where |p| {
+                    p.flags.contains(StopSoundFlags::SOURCE)
+                },
+                sound: String where |p| {
+                    p.flags.contains(StopSoundFlags::SOUND)
+                }
+            },
+            /// StoreCookie stores a cookie on the client, which keeps it across
+            /// transfers until it disconnects.
+            0x6b => StoreCookie {
+                key: Identifier,
+                /// At most 5120 bytes.
+                payload: LenPrefixedBytes<VarInt>,
+            },
+            /// SystemChat is a message that is not sent by a player.
+            0x6c => SystemChat {
+                content: NetworkNbt,
+                /// Shown above the hotbar instead of in the chat.
+                overlay: bool,
+            },
+            /// PlayerListHeaderFooter updates the header/footer of the player list.
+            0x6d => PlayerListHeaderFooter {
+                header: NetworkNbt,
+                footer: NetworkNbt,
+            },
+            0x6e => NBTQueryResponse {
+                transaction_id: VarInt,
+                nbt: NetworkNbt,
+            },
+            /// CollectItem causes the collected item to fly towards the collector. This
+            /// does not destroy the entity.
+            0x6f => CollectItem {
+                collected_entity_id: VarInt,
+                collector_entity_id: VarInt,
+                number_of_items: VarInt,
+            },
+            /// EntityTeleport teleports the entity to the target location. This is
+            /// sent if the entity moves further than EntityMove allows.
+            0x70 => EntityTeleport {
+                entity_id: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: i8,
+                pitch: i8,
+                on_ground: bool,
+            },
+            /// TickingState sets the tick rate of the world and whether it is frozen.
+            0x71 => TickingState {
+                tick_rate: f32,
+                is_frozen: bool,
+            },
+            /// StepTick advances a frozen world by some ticks.
+            0x72 => StepTick {
+                tick_steps: VarInt,
+            },
+            /// Transfer makes the client connect to another server, with 3 as
+            /// the next state of its Handshake.
+            0x73 => Transfer {
+                host: String,
+                port: VarInt,
+            },
+            /// Advancements adds, removes and updates advancements, kept as bytes.
+            0x74 => Advancements {
+                data: Vec<u8>,
+            },
+            /// EntityProperties updates the properties for an entity.
+            0x75 => EntityProperties{
+                entity_id: VarInt,
+                /// Kept as bytes.
+                properties: Vec<u8>,
+            },
+            /// EntityEffect applies a status effect to an entity for a given duration.
+            0x76 => EntityEffect {
+                entity_id: VarInt,
+                effect_id: VarInt,
+                amplifier: VarInt,
+                duration: VarInt,
+                flags: u8,
+            },
+            /// DeclareRecipes sends every recipe, kept as bytes.
+            0x77 => DeclareRecipes {
+                data: Vec<u8>,
+            },
+            0x78 => Tags {
+                tags: TagSet,
+            },
+            /// ProjectilePower sets the acceleration of a wind charge or fireball.
+            0x79 => ProjectilePower {
+                entity_id: VarInt,
+                power: f64,
+            },
+            /// CustomReportDetails adds details to crash reports of the client.
+            0x7a => CustomReportDetails {
+                details: Vec<(String, String)> => with(
+                    read = read_report_details,
+                    write = write_report_details
+                ),
+            },
+            /// ServerLinks lists links the pause menu shows, like a website or a bug
+            /// tracker.
+            0x7b => ServerLinks {
+                /// The links, kept as bytes.
+                links: Vec<u8>,
+            }
+        }
+    }
+});
+
@@ -0,0 +1,928 @@
+//! Protocol 5, 1.7.10, which many old servers still run.
+//!
+//! Login has no compression phase, slots use the old
+//! `[i16 item id][i8 count][i16 damage][gzipped NBT]` layout, and many lists
+//! and byte arrays are prefixed with fixed size integers. Strings with a
+//! vanilla limit use `JavaString`, which counts characters like Java does.
+use crate::protocol::State;
+use crate::protocol::Direction;
+use steven_protocol::protocol::{LenPrefixedBytes, LenPrefixed};
+use steven_protocol::format;
+use steven_protocol::protocol::packet;
+use steven_protocol::protocol::VarInt;
+use crate::segment::implementation::mojang;
+use crate::segment::implementation::mojang::legacy::{ChunkMeta, JavaString, LegacyAttribute, LegacyNbt, LegacyProperty, LegacySlot, LegacyStatistic};
+use crate::segment::implementation::types::{AbilityFlags, Difficulty, GameMode};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind};
+
+fn read_properties<R: std::io::Read>(_packet: &SpawnPlayer, reader: &mut R) -> std::io::Result<Vec<LegacyProperty>> {
+    mojang::read_var_list(reader)
+}
+
+fn write_properties<W: std::io::Write>(_packet: &SpawnPlayer, properties: &[LegacyProperty], writer: &mut W) -> std::io::Result<()> {
+    mojang::write_var_list(properties, writer)
+}
+
+fn read_statistics<R: std::io::Read>(_packet: &Statistics, reader: &mut R) -> std::io::Result<Vec<LegacyStatistic>> {
+    mojang::read_var_list(reader)
+}
+
+fn write_statistics<W: std::io::Write>(_packet: &Statistics, statistics: &[LegacyStatistic], writer: &mut W) -> std::io::Result<()> {
+    mojang::write_var_list(statistics, writer)
+}
+
+fn read_attributes<R: std::io::Read>(_packet: &EntityProperties, reader: &mut R) -> std::io::Result<Vec<LegacyAttribute>> {
+    let count = reader.read_i32::<BigEndian>()?;
+    mojang::read_segments(checked_count(count as i64)?, reader)
+}
+
+fn write_attributes<W: std::io::Write>(_packet: &EntityProperties, attributes: &[LegacyAttribute], writer: &mut W) -> std::io::Result<()> {
+    writer.write_i32::<BigEndian>(attributes.len() as i32)?;
+    mojang::write_segments(attributes, writer)
+}
+
+fn read_window_items<R: std::io::Read>(_packet: &WindowItems, reader: &mut R) -> std::io::Result<Vec<LegacySlot>> {
+    let count = reader.read_i16::<BigEndian>()?;
+    mojang::read_segments(checked_count(count as i64)?, reader)
+}
+
+fn write_window_items<W: std::io::Write>(_packet: &WindowItems, items: &[LegacySlot], writer: &mut W) -> std::io::Result<()> {
+    writer.write_i16::<BigEndian>(items.len() as i16)?;
+    mojang::write_segments(items, writer)
+}
+
+fn read_bulk_data<R: std::io::Read>(packet: &ChunkDataBulk, reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let mut data = vec![0; checked_count(packet.data_length as i64)?];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn write_bulk_data<W: std::io::Write>(_packet: &ChunkDataBulk, data: &[u8], writer: &mut W) -> std::io::Result<()> {
+    writer.write_all(data)
+}
+
+fn read_chunk_meta<R: std::io::Read>(packet: &ChunkDataBulk, reader: &mut R) -> std::io::Result<Vec<ChunkMeta>> {
+    mojang::read_segments(checked_count(packet.column_count as i64)?, reader)
+}
+
+fn write_chunk_meta<W: std::io::Write>(_packet: &ChunkDataBulk, meta: &[ChunkMeta], writer: &mut W) -> std::io::Result<()> {
+    mojang::write_segments(meta, writer)
+}
+
+fn checked_count(count: i64) -> std::io::Result<usize> {
+    match count < 0 {
+        true => Err(Error::new(ErrorKind::InvalidData, "negative element count")),
+        false => Ok(count as usize)
+    }
+}
+
+crate::define_protocol!(pub Proto_1_7_10, "1.7.10", 5 {
+    State::Handshaking => {
+        Direction::ServerBound => {
+            /// Handshake is the first packet sent in the protocol.
+            /// Its used for deciding if the request is a client
+            /// is requesting status information about the server
+            /// (MOTD, players etc) or trying to login to the server.
+            ///
+            /// The host and port fields are not used by the vanilla
+            /// server but are there for virtual server hosting to
+            /// be able to redirect a client to a target server with
+            /// a single address + port.
+            ///
+            /// Some modified servers/proxies use the handshake field
+            /// differently, packing information into the field other
+            /// than the hostname due to the protocol not providing
+            /// any system for custom information to be transfered
+            /// by the client to the server until after login.
+            0x00 => Handshake {
+                /// The protocol version of the connecting client
+                protocol_version: VarInt,
+                /// The hostname the client connected to
+                host: String,
+                /// The port the client connected to
+                port: u16,
+                /// The next protocol state the client wants
+                next: VarInt,
+            }
+        }
+    },
+    State::Status => {
+        Direction::ServerBound => {
+            /// StatusRequest is sent by the client instantly after
+            /// switching to the Status protocol state and is used
+            /// to signal the server to send a StatusResponse to the
+            /// client
+            0x00 => StatusRequest,
+            /// StatusPing is sent by the client after recieving a
+            /// StatusResponse. The client uses the time from sending
+            /// the ping until the time of recieving a pong to measure
+            /// the latency between the client and the server.
+            0x01 => StatusPing{
+                ping: i64,
+            },
+        },
+        Direction::ClientBound => {
+            /// StatusResponse is sent as a reply to a StatusRequest.
+            /// The Status should contain a json encoded structure with
+            /// version information, a player sample, a description/MOTD
+            /// and optionally a favicon.
+            ///
+            /// The structure is as follows
+            ///
+            /// ```json
+            /// {
+            ///     "version": {
+            ///         "name": "1.8.3",
+            ///         "protocol": 47,
+            ///     },
+            ///     "players": {
+            ///         "max": 20,
+            ///         "online": 1,
+            ///         "sample": [
+            ///            packet  {"name": "Thinkofdeath", "id": "4566e69f-c907-48ee-8d71-d7ba5aa00d20"}
+            ///         ]
+            ///     },
+            ///     "description": "Hello world",
+            ///     "favicon": "data:image/png;base64,<data>"
+            /// }
+            /// ```
+            0x00 => StatusResponse{
+                status: String,
+            },
+            /// StatusPong is sent as a reply to a StatusPing.
+            /// The Time field should be exactly the same as the
+            /// one sent by the client.
+            0x01 => StatusPong{
+                ping: i64
+            },
+        }
+    },
+    State::Login => {
+        Direction::ServerBound => {
+            /// LoginStart is sent immeditately after switching into the login
+            /// state. The passed username is used by the server to authenticate
+            /// the player in online mode.
+            0x00 => LoginStart{
+                username: JavaString<16>
+            },
+            /// EncryptionResponse is sent as a reply to EncryptionRequest. All
+            /// packets following this one must be encrypted with AES/CFB8
+            /// encryption.
+            0x01 => EncryptionResponse{
+                /// The key for the AES/CFB8 cipher encrypted with the
+                /// public key
+                shared_secret: LenPrefixedBytes<i16>,
+                /// The verify token from the request encrypted with the
+                /// public key
+                verify_token: LenPrefixedBytes<i16>,
+            }
+        },
+        Direction::ClientBound => {
+            /// LoginDisconnect is sent by the server if there was any issues
+            /// authenticating the player during login or the general server
+            /// issues (e.g. too many players).
+            0x00 => LoginDisconnect{
+                reason: format::Component,
+            },
+            /// EncryptionRequest is sent by the server if the server is in
+            /// online mode. If it is not sent then its assumed the server is
+            /// in offline mode.
+            0x01 => EncryptionRequest{
+                server_id: String,
+                /// A RSA Public key serialized in x.509 PRIX format
+                public_key: LenPrefixedBytes<i16>,
+                /// Token used by the server to verify encryption is working
+                /// correctly
+                verify_token: LenPrefixedBytes<i16>,
+            },
+            /// LoginSuccess ends the login, there is no compression to set up
+            /// before it in 1.7.
+            0x02 => LoginSuccess{
+                /// The uuid with hyphens
+                uuid: String,
+                username: JavaString<16>,
+            },
+        }
+    },
+    State::Play => {
+        Direction::ServerBound => {
+            /// KeepAliveServerbound is sent by a client as a response to a
+            /// KeepAliveClientbound. If the client doesn't reply the server
+            /// may disconnect the client.
+            0x00 => KeepAliveServerbound {
+                id: i32,
+            },
+            /// ChatMessage is sent by the client when it sends a chat message or
+            /// executes a command (prefixed by '/').
+            0x01 => ChatMessage {
+                message: JavaString<100>,
+            },
+            /// UseEntity is sent when the user interacts (right clicks) or attacks
+            /// (left clicks) an entity.
+            0x02 => UseEntity {
+                target_id: i32,
+                /// 0 to interact, 1 to attack
+                ty: i8,
+            },
+            /// Player is used to update whether the player is on the ground or not.
+            0x03 => Player {
+                on_ground: bool,
+            },
+            /// PlayerPosition is used to update the player's position.
+            0x04 => PlayerPosition {
+                x: f64,
+                /// The y of the feet
+                y: f64,
+                /// The y of the head
+                stance: f64,
+                z: f64,
+                on_ground: bool,
+            },
+            /// PlayerLook is used to update the player's rotation.
+            0x05 => PlayerLook {
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// PlayerPositionLook is a combination of PlayerPosition and
+            /// PlayerLook.
+            0x06 => PlayerPositionLook {
+                x: f64,
+                y: f64,
+                stance: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// PlayerDigging is sent when the client starts/stops digging a block.
+            /// It also can be sent for droppping items and eating/shooting.
+            0x07 => PlayerDigging {
+                status: i8,
+                x: i32,
+                y: u8,
+                z: i32,
+                face: i8,
+            },
+            /// PlayerBlockPlacement is sent when the client tries to place a block.
+            0x08 => PlayerBlockPlacement {
+                x: i32,
+                y: u8,
+                z: i32,
+                face: i8,
+                held_item: LegacySlot,
+                cursor_x: i8,
+                cursor_y: i8,
+                cursor_z: i8,
+            },
+            /// HeldItemChange is sent when the player changes the currently active
+            /// hotbar slot.
+            0x09 => HeldItemChange {
+                slot: i16,
+            },
+            /// ArmSwing is sent by the client when the player left clicks (to swing their
+            /// arm).
+            0x0a => ArmSwing {
+                entity_id: i32,
+                animation: i8,
+            },
+            /// PlayerAction is sent when a player preforms various actions.
+            0x0b => PlayerAction {
+                entity_id: i32,
+                action_id: i8,
+                jump_boost: i32,
+            },
+            /// SteerVehicle is sent by the client when steers or preforms an action
+            /// on a vehicle.
+            0x0c => SteerVehicle {
+                sideways: f32,
+                forward: f32,
+                jump: bool,
+                unmount: bool,
+            },
+            /// CloseWindow is sent when the client closes a window.
+            0x0d => CloseWindow {
+                id: u8,
+            },
+            /// ClickWindow is sent when the client clicks in a window.
+            0x0e => ClickWindow {
+                id: u8,
+                slot: i16,
+                button: u8,
+                action_number: i16,
+                mode: i8,
+                clicked_item: LegacySlot,
+            },
+            /// ConfirmTransactionServerbound answers a rejected ConfirmTransaction.
+            0x0f => ConfirmTransactionServerbound {
+                id: u8,
+                action_number: i16,
+                accepted: bool,
+            },
+            /// CreativeInventoryAction is sent when a client clicks in the creative
+            /// inventory. This is used to spawn items in creative.
+            0x10 => CreativeInventoryAction {
+                slot: i16,
+                clicked_item: LegacySlot,
+            },
+            /// EnchantItem is sent when the client enchants an item.
+            0x11 => EnchantItem {
+                id: u8,
+                enchantment: i8,
+            },
+            /// SetSign sets the text on a sign after placing it.
+            0x12 => SetSign {
+                x: i32,
+                y: i16,
+                z: i32,
+                line1: JavaString<15>,
+                line2: JavaString<15>,
+                line3: JavaString<15>,
+                line4: JavaString<15>,
+            },
+            /// ClientAbilities is used to modify the players current abilities.
+            /// Currently flying is the only one
+            0x13 => ClientAbilities {
+                flags: AbilityFlags,
+                flying_speed: f32,
+                walking_speed: f32,
+            },
+            /// TabComplete is sent by the client when the client presses tab in
+            /// the chat box.
+            0x14 => TabComplete {
+                text: String,
+            },
+            /// ClientSettings is sent by the client to update its current settings.
+            0x15 => ClientSettings {
+                locale: JavaString<7>,
+                view_distance: i8,
+                chat_flags: i8,
+                chat_colors: bool,
+                difficulty: u8,
+                show_cape: bool,
+            },
+            /// ClientStatus is sent to update the client's status
+            0x16 => ClientStatus {
+                /// 0 to respawn, 1 to request the statistics and 2 to open the
+                /// inventory achievement
+                action_id: i8,
+            },
+            /// PluginMessageServerbound is used for custom messages between the client
+            /// and server. This is mainly for plugins/mods but vanilla has a few channels
+            /// registered too.
+            0x17 => PluginMessageServerbound {
+                channel: JavaString<20>,
+                data: LenPrefixedBytes<i16>,
+            },
+        },
+        Direction::ClientBound => {
+            /// KeepAliveClientbound is sent by a server to check if the
+            /// client is still responding and keep the connection open.
+            /// The client should reply with the KeepAliveServerbound
+            /// setting ID to the same as this one.
+            0x00 => KeepAliveClientbound {
+                id: i32,
+            },
+            /// JoinGame is sent after completing the login process. This
+            /// sets the initial state for the client.
+            0x01 => JoinGame {
+                /// The entity id the client will be referenced by
+                entity_id: i32,
+                /// The starting gamemode of the client, with bit 3 set in hardcore mode
+                gamemode: u8,
+                /// The dimension the client is starting in, -1 for the nether and 1 for the end
+                dimension: i8,
+                /// The difficuilty setting for the server
+                difficulty: Difficulty,
+                /// Used by the client to draw the player list
+                max_players: u8,
+                /// The generator of the world, like "default" or "flat"
+                level_type: String,
+            },
+            /// ServerMessage is a message sent by the server. It could be from a player
+            /// or just a system message.
+            0x02 => ServerMessage {
+                message: format::Component,
+            },
+            /// TimeUpdate is sent to sync the world's time to the client, the client
+            /// will manually tick the time itself so this doesn't need to sent repeatedly
+            /// but if the server or client has issues keeping up this can fall out of sync
+            /// so it is a good idea to send this now and again
+            0x03 => TimeUpdate {
+                world_age: i64,
+                time_of_day: i64,
+            },
+            /// EntityEquipment is sent to display an item on an entity, like a sword
+            /// or armor. Slot 0 is the held item and slots 1 to 4 are boots, leggings
+            /// chestplate and helmet respectively.
+            0x04 => EntityEquipment {
+                entity_id: i32,
+                slot: i16,
+                item: LegacySlot,
+            },
+            /// SpawnPosition is sent to change the player's current spawn point. Currently
+            /// only used by the client for the compass.
+            0x05 => SpawnPosition {
+                x: i32,
+                y: i32,
+                z: i32,
+            },
+            /// UpdateHealth is sent by the server to update the player's health and food.
+            0x06 => UpdateHealth {
+                health: f32,
+                food: i16,
+                food_saturation: f32,
+            },
+            /// Respawn is sent to respawn the player after death or when they move worlds.
+            0x07 => Respawn {
+                dimension: i32,
+                difficulty: Difficulty,
+                gamemode: GameMode,
+                level_type: String,
+            },
+            /// TeleportPlayer is sent to change the player's position. The client is expected
+            /// to reply to the server with the same positions as contained in this packet
+            /// otherwise will reject future packets.
+            0x08 => TeleportPlayer {
+                x: f64,
+                /// The y of the head
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                on_ground: bool,
+            },
+            /// SetCurrentHotbarSlot changes the player's currently selected hotbar item.
+            0x09 => SetCurrentHotbarSlot {
+                slot: u8,
+            },
+            /// EntityUsedBed is sent by the server when a player goes to bed.
+            0x0a => EntityUsedBed {
+                entity_id: i32,
+                x: i32,
+                y: u8,
+                z: i32,
+            },
+            /// Animation is sent by the server to play an animation on a specific entity.
+            0x0b => Animation {
+                entity_id: VarInt,
+                animation_id: u8,
+            },
+            /// SpawnPlayer is used to spawn a player when they are in range of the client.
+            /// The position is in 1/32 of a block.
+            0x0c => SpawnPlayer {
+                entity_id: VarInt,
+                /// The uuid with hyphens
+                uuid: String,
+                name: String,
+                properties: Vec<LegacyProperty> => with(
+                    read = read_properties,
+                    write = write_properties
+                ),
+                x: i32,
+                y: i32,
+                z: i32,
+                yaw: i8,
+                pitch: i8,
+                current_item: i16,
+                /// The 1.7 metadata encoding, up to and including the 0x7f end marker
+                metadata: Vec<u8>,
+            },
+            /// CollectItem causes the collected item to fly towards the collector. This
+            /// does not destroy the entity.
+            0x0d => CollectItem {
+                collected_entity_id: i32,
+                collector_entity_id: i32,
+            },
+            /// SpawnObject is used to spawn an object or vehicle into the world when it
+            /// is in range of the client. The position is in 1/32 of a block.
+            0x0e => SpawnObject {
+                entity_id: VarInt,
+                ty: u8,
+                x: i32,
+                y: i32,
+                z: i32,
+                pitch: i8,
+                yaw: i8,
+                data: i32,
+                velocity_x: i16 where |p| {p.data != 0},
+                velocity_y: i16 where |p| {p.data != 0},
+                velocity_z: i16 where |p| {p.data != 0},
+            },
+            /// SpawnMob is used to spawn a living entity into the world when it is in
+            /// range of the client. The position is in 1/32 of a block.
+            0x0f => SpawnMob {
+                entity_id: VarInt,
+                ty: u8,
+                x: i32,
+                y: i32,
+                z: i32,
+                yaw: i8,
+                pitch: i8,
+                head_pitch: i8,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+                /// The 1.7 metadata encoding, up to and including the 0x7f end marker
+                metadata: Vec<u8>,
+            },
+            /// SpawnPainting spawns a painting into the world when it is in range of
+            /// the client. The title effects the size and the texture of the painting.
+            0x10 => SpawnPainting {
+                entity_id: VarInt,
+                title: String,
+                x: i32,
+                y: i32,
+                z: i32,
+                direction: i32,
+            },
+            /// SpawnExperienceOrb spawns a single experience orb into the world when
+            /// it is in range of the client. The count controls the amount of experience
+            /// gained when collected.
+            0x11 => SpawnExperienceOrb {
+                entity_id: VarInt,
+                x: i32,
+                y: i32,
+                z: i32,
+                count: i16,
+            },
+            /// EntityVelocity sets the velocity of an entity in 1/8000 of a block
+            /// per a tick.
+            0x12 => EntityVelocity {
+                entity_id: i32,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            },
+            /// EntityDestroy destroys the entities with the ids in the provided slice.
+            0x13 => EntityDestroy {
+                entity_ids: LenPrefixed<u8, i32>,
+            },
+            /// EntityUpdate keeps the entity alive on the client without moving it.
+            0x14 => EntityUpdate {
+                entity_id: i32,
+            },
+            /// EntityMove moves the entity with the id by the offsets provided,
+            /// in 1/32 of a block.
+            0x15 => EntityMove {
+                entity_id: i32,
+                delta_x: i8,
+                delta_y: i8,
+                delta_z: i8,
+            },
+            /// EntityLook rotates the entity to the new angles provided.
+            0x16 => EntityLook {
+                entity_id: i32,
+                yaw: i8,
+                pitch: i8,
+            },
+            /// EntityLookAndMove is a combination of EntityMove and EntityLook.
+            0x17 => EntityLookAndMove {
+                entity_id: i32,
+                delta_x: i8,
+                delta_y: i8,
+                delta_z: i8,
+                yaw: i8,
+                pitch: i8,
+            },
+            /// EntityTeleport teleports the entity to the target location, in 1/32
+            /// of a block. This is sent if the entity moves further than EntityMove
+            /// allows.
+            0x18 => EntityTeleport {
+                entity_id: i32,
+                x: i32,
+                y: i32,
+                z: i32,
+                yaw: i8,
+                pitch: i8,
+            },
+            /// EntityHeadLook rotates an entity's head to the new angle.
+            0x19 => EntityHeadLook {
+                entity_id: i32,
+                head_yaw: i8,
+            },
+            /// EntityAction causes an entity to preform an action based on the passed
+            /// id.
+            0x1a => EntityAction {
+                entity_id: i32,
+                action_id: u8,
+            },
+            /// EntityAttach attaches to entities together, either by mounting or leashing.
+            /// -1 can be used at the EntityID to deattach.
+            0x1b => EntityAttach {
+                entity_id: i32,
+                vehicle: i32,
+                leash: bool,
+            },
+            /// EntityMetadata updates the metadata for an entity.
+            0x1c => EntityMetadata {
+                entity_id: i32,
+                /// The 1.7 metadata encoding, up to and including the 0x7f end marker
+                metadata: Vec<u8>,
+            },
+            /// EntityEffect applies a status effect to an entity for a given duration.
+            0x1d => EntityEffect {
+                entity_id: i32,
+                effect_id: i8,
+                amplifier: i8,
+                duration: i16,
+            },
+            /// EntityRemoveEffect removes an effect from an entity.
+            0x1e => EntityRemoveEffect {
+                entity_id: i32,
+                effect_id: i8,
+            },
+            /// SetExperience updates the experience bar on the client.
+            0x1f => SetExperience {
+                experience_bar: f32,
+                level: i16,
+                total_experience: i16,
+            },
+            /// EntityProperties updates the properties for an entity.
+            0x20 => EntityProperties {
+                entity_id: i32,
+                properties: Vec<LegacyAttribute> => with(
+                    read = read_attributes,
+                    write = write_attributes
+                ),
+            },
+            /// ChunkData sends a chunk column, zlib compressed.
+            0x21 => ChunkData {
+                chunk_x: i32,
+                chunk_z: i32,
+                /// Whether the whole column with its biomes is sent
+                full_chunk: bool,
+                bitmask: u16,
+                /// The sections with block ids above 255
+                add_bitmask: u16,
+                data: LenPrefixedBytes<i32>,
+            },
+            /// MultiBlockChange is used to update a batch of blocks in a single packet.
+            0x22 => MultiBlockChange {
+                chunk_x: i32,
+                chunk_z: i32,
+                record_count: i16,
+                /// An i32 for every record, `record_count * 4` bytes
+                records: LenPrefixedBytes<i32>,
+            },
+            /// BlockChange is used to update a single block on the client.
+            0x23 => BlockChange {
+                x: i32,
+                y: u8,
+                z: i32,
+                block_id: VarInt,
+                block_metadata: u8,
+            },
+            /// BlockAction triggers different actions depending on the target block.
+            0x24 => BlockAction {
+                x: i32,
+                y: i16,
+                z: i32,
+                byte1: u8,
+                byte2: u8,
+                block_type: VarInt,
+            },
+            /// BlockBreakAnimation is used to create and update the block breaking
+            /// animation played when a player starts digging a block.
+            0x25 => BlockBreakAnimation {
+                entity_id: VarInt,
+                x: i32,
+                y: i32,
+                z: i32,
+                stage: i8,
+            },
+            /// ChunkDataBulk sends several chunk columns in one packet, zlib
+            /// compressed.
+            0x26 => ChunkDataBulk {
+                column_count: i16,
+                data_length: i32,
+                sky_light_sent: bool,
+                data: Vec<u8> => with(
+                    read = read_bulk_data,
+                    write = write_bulk_data
+                ),
+                chunk_meta: Vec<ChunkMeta> => with(
+                    read = read_chunk_meta,
+                    write = write_chunk_meta
+                ),
+            },
+            /// Explosion is sent when an explosion is triggered (tnt, creeper etc).
+            /// This plays the effect and removes the effected blocks.
+            0x27 => Explosion {
+                x: f32,
+                y: f32,
+                z: f32,
+                radius: f32,
+                records: LenPrefixed<i32, packet::ExplosionRecord>,
+                velocity_x: f32,
+                velocity_y: f32,
+                velocity_z: f32,
+            },
+            /// Effect plays a sound effect or particle at the target location with the
+            /// volume (of sounds) being relative to the player's position unless
+            /// DisableRelative is set to true.
+            0x28 => Effect {
+                effect_id: i32,
+                x: i32,
+                y: i8,
+                z: i32,
+                data: i32,
+                disable_relative: bool,
+            },
+            /// NamedSoundEffect is used to play a sound effect on the client.
+            0x29 => NamedSoundEffect {
+                name: String,
+                x: i32,
+                y: i32,
+                z: i32,
+                volume: f32,
+                pitch: u8,
+            },
+            /// Particle spawns particles at the target location with the various
+            /// modifiers.
+            0x2a => Particle {
+                name: String,
+                x: f32,
+                y: f32,
+                z: f32,
+                offset_x: f32,
+                offset_y: f32,
+                offset_z: f32,
+                speed: f32,
+                count: i32,
+            },
+            /// ChangeGameState is used to modify the game's state like gamemode or
+            /// weather.
+            0x2b => ChangeGameState {
+                reason: u8,
+                value: f32,
+            },
+            /// SpawnGlobalEntity spawns an entity which is visible from anywhere in the
+            /// world. Currently only used for lightning.
+            0x2c => SpawnGlobalEntity {
+                entity_id: VarInt,
+                ty: u8,
+                x: i32,
+                y: i32,
+                z: i32,
+            },
+            /// WindowOpen tells the client to open the inventory window of the given
+            /// type. The ID is used to reference the instance of the window in
+            /// other packets.
+            0x2d => WindowOpen {
+                id: u8,
+                ty: u8,
+                title: String,
+                slot_count: u8,
+                use_provided_title: bool,
+                entity_id: i32 where |p| {
+                    p.ty == 11
+                },
+            },
+            /// WindowClose forces the client to close the window with the given id,
+            /// e.g. a chest getting destroyed.
+            0x2e => WindowClose {
+                id: u8,
+            },
+            /// WindowSetSlot changes an itemstack in one of the slots in a window.
+            0x2f => WindowSetSlot {
+                id: u8,
+                property: i16,
+                item: LegacySlot,
+            },
+            /// WindowItems sets every item in a window.
+            0x30 => WindowItems {
+                id: u8,
+                items: Vec<LegacySlot> => with(
+                    read = read_window_items,
+                    write = write_window_items
+                ),
+            },
+            /// WindowProperty changes the value of a property of a window. Properties
+            /// vary depending on the window type.
+            0x31 => WindowProperty {
+                id: u8,
+                property: i16,
+                value: i16,
+            },
+            /// ConfirmTransaction tells the client whether a window click was accepted.
+            0x32 => ConfirmTransaction {
+                id: u8,
+                action_number: i16,
+                accepted: bool,
+            },
+            /// UpdateSign sets or changes the text on a sign.
+            0x33 => UpdateSign {
+                x: i32,
+                y: i16,
+                z: i32,
+                line1: String,
+                line2: String,
+                line3: String,
+                line4: String,
+            },
+            /// Maps updates a single map's contents
+            0x34 => Maps {
+                item_damage: VarInt,
+                data: LenPrefixedBytes<i16>,
+            },
+            /// UpdateBlockEntity updates the nbt tag of a block entity in the
+            /// world.
+            0x35 => UpdateBlockEntity {
+                x: i32,
+                y: i16,
+                z: i32,
+                action: u8,
+                data: LegacyNbt,
+            },
+            /// SignEditorOpen causes the client to open the editor for a sign so that
+            /// it can write to it. Only sent in vanilla when the player places a sign.
+            0x36 => SignEditorOpen {
+                x: i32,
+                y: i32,
+                z: i32,
+            },
+            /// Statistics is used to update the statistics screen for the client.
+            0x37 => Statistics {
+                statistics: Vec<LegacyStatistic> => with(
+                    read = read_statistics,
+                    write = write_statistics
+                ),
+            },
+            /// PlayerInfo adds, updates or removes a name of the player list.
+            0x38 => PlayerInfo {
+                name: String,
+                online: bool,
+                ping: i16,
+            },
+            /// PlayerAbilities is used to modify the players current abilities. Flying,
+            /// creative, god mode etc.
+            0x39 => PlayerAbilities {
+                flags: AbilityFlags,
+                flying_speed: f32,
+                walking_speed: f32,
+            },
+            /// TabCompleteReply is sent as a reply to a tab completion request.
+            /// The matches should be possible completions for the command/chat the
+            /// player sent.
+            0x3a => TabCompleteReply {
+                matches: LenPrefixed<VarInt, String>,
+            },
+            /// ScoreboardObjective creates/updates a scoreboard objective.
+            0x3b => ScoreboardObjective {
+                name: String,
+                value: String,
+                mode: u8,
+            },
+            /// UpdateScore is used to update or remove an item from a scoreboard
+            /// objective.
+            0x3c => UpdateScore {
+                name: String,
+                action: u8,
+                object_name: String where |p| {
+                    p.action != 1
+                },
+                value: i32 where |p| {
+                    p.action != 1
+                },
+            },
+            /// ScoreboardDisplay is used to set the display position of a scoreboard.
+            0x3d => ScoreboardDisplay {
+                position: u8,
+                name: String,
+            },
+            /// Teams creates and updates teams
+            0x3e => Teams {
+                name: String,
+                mode: u8,
+                display_name: String where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                prefix: String where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                suffix: String where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                flags: u8 where |p| {
+                    p.mode == 0 || p.mode == 2
+                },
+                players: LenPrefixed<i16, String> where |p| {
+                    p.mode == 0 || p.mode == 3 || p.mode == 4
+                },
+            },
+            /// PluginMessageClientbound is used for custom messages between the client
+            /// and server. This is mainly for plugins/mods but vanilla has a few channels
+            /// registered too.
+            0x3f => PluginMessageClientbound {
+                channel: String,
+                data: LenPrefixedBytes<i16>,
+            },
+            /// Disconnect causes the client to disconnect displaying the passed reason.
+            0x40 => Disconnect {
+                reason: format::Component,
+            },
+        }
+    }
+});
@@ -0,0 +1,75 @@
+use crate::connection::frame::{self, FrameLimits};
+use crate::protocol::{Direction, Packet, Protocol, RawPacket, State};
+use std::marker::PhantomData;
+
+/// A framed packet whose body is only parsed when asked for.
+///
+/// Useful for proxies that only care about a few packet types, as forwarding
+/// the rest does not require decoding them at all.
+#[derive(Debug, Clone)]
+pub struct LazyPacket<P: Protocol>{
+    raw: RawPacket,
+    _protocol: PhantomData<P>
+}
+
+impl<P: Protocol> LazyPacket<P>{
+    pub fn new(raw: RawPacket) -> Self {
+        LazyPacket{
+            raw,
+            _protocol: PhantomData
+        }
+    }
+
+    /// Reads a single uncompressed frame without decoding its body. Frames
+    /// over the vanilla `FrameLimits` of `state` are refused.
+    pub fn read_frame<R: std::io::Read>(state: State, direction: Direction, reader: &mut R) -> std::io::Result<Self> {
+        let (id, body) = frame::read_frame(reader, &FrameLimits::default(), state)?;
+        Ok(Self::new(RawPacket{ id, state, direction, body }))
+    }
+
+    pub fn id(&self) -> i32 {
+        self.raw.id
+    }
+
+    pub fn state(&self) -> &State {
+        &self.raw.state
+    }
+
+    pub fn direction(&self) -> &Direction {
+        &self.raw.direction
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.raw.body
+    }
+
+    /// Whether this is a `T`, judging by the packet id alone. Packet ids are
+    /// only unique within a state and direction, so check those as well.
+    pub fn is<T: Packet>(&self) -> bool {
+        self.raw.id == T::PACKET_ID
+    }
+
+    /// Parses the body. Returns `Ok(None)` if the protocol does not know this packet.
+    pub fn decode(&self) -> std::io::Result<Option<P>> {
+        P::packet_by_id(self.raw.state, self.raw.direction, self.raw.id, &mut &self.raw.body[..])
+    }
+
+    /// Parses the body as `T`, or returns `Ok(None)` if the packet id does not match.
+    pub fn decode_as<T: Packet>(&self) -> std::io::Result<Option<T>> {
+        if !self.is::<T>() {
+            return Ok(None);
+        }
+        let mut packet = T::default();
+        packet.read_from_stream(&mut &self.raw.body[..])?;
+        Ok(Some(packet))
+    }
+
+    /// Writes the packet as it was received, without encoding it again.
+    pub fn write_frame<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.raw.write_frame(writer)
+    }
+
+    pub fn into_raw(self) -> RawPacket {
+        self.raw
+    }
+}
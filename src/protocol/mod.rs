@@ -2,21 +2,82 @@ use crate::segment::Segment;
 use std::fmt::Debug;
 
 pub mod implementation;
+pub mod lazy;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+pub mod diff;
+pub mod docgen;
+pub mod dump;
+pub mod quarantine;
+#[cfg(test)]
+pub(crate) mod test_protocol;
+pub mod trailing;
+pub mod untrusted;
+pub mod translate;
+pub mod version;
 
-#[derive(Debug, Clone)]
+pub use diff::diff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State{
     Handshaking,
     Status,
     Login,
+    /// Between login and play since 1.20.2, for registries, resource packs and feature flags.
+    Configuration,
     Play
 }
 
-#[derive(Debug, Clone)]
+impl State{
+    /// Numeric id of the state, matching the `next` field of the handshake for Status and Login.
+    /// Configuration was added after Play and keeps the ids of the others.
+    pub const fn id(&self) -> u8 {
+        match self {
+            State::Handshaking => 0,
+            State::Status => 1,
+            State::Login => 2,
+            State::Play => 3,
+            State::Configuration => 4
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<State> {
+        match id {
+            0 => Some(State::Handshaking),
+            1 => Some(State::Status),
+            2 => Some(State::Login),
+            3 => Some(State::Play),
+            4 => Some(State::Configuration),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction{
     ClientBound,
     ServerBound
 }
 
+impl Direction{
+    pub const fn id(&self) -> u8 {
+        match self {
+            Direction::ClientBound => 0,
+            Direction::ServerBound => 1
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Direction> {
+        match id {
+            0 => Some(Direction::ClientBound),
+            1 => Some(Direction::ServerBound),
+            _ => None
+        }
+    }
+}
+
 pub trait Protocol: Sized + Debug{
     const NAME: &'static str;
     const PROTOCOL: i32;
@@ -31,8 +92,345 @@ pub trait Protocol: Sized + Debug{
         Self::PROTOCOL
     }
 
+    /// Whether this is the protocol of a snapshot, see `version`.
+    fn is_snapshot() -> bool {
+        version::ProtocolVersion(Self::PROTOCOL).is_snapshot()
+    }
+
     #[allow(unused)]
     fn packet_by_id<R: std::io::Read>(state: State, direction: Direction, id: i32, reader: &mut R) -> std::io::Result<Option<Self>>;
+
+    /// Wraps a packet this protocol does not know about.
+    fn from_raw(raw: RawPacket) -> Self;
+
+    /// Every packet defined by this protocol.
+    fn packets() -> &'static [PacketDescriptor];
+
+    /// A constructor for every packet of `packets()`, in the same order. The
+    /// packets are created with default fields.
+    fn constructors() -> &'static [fn() -> Self] where Self: 'static;
+
+    /// Every packet along with its constructor, so each kind of packet can be
+    /// created generically, e.g. by benchmarks and fuzzers.
+    fn descriptors() -> Vec<PacketKind<Self>> where Self: 'static {
+        Self::packets().iter()
+            .zip(Self::constructors())
+            .map(|(descriptor, construct)| PacketKind{ descriptor, construct: *construct })
+            .collect()
+    }
+
+    /// Packet ids that are deliberately left out, declared with `gaps [...];`.
+    fn id_gaps() -> &'static [(State, Direction, i32)] {
+        &[]
+    }
+
+    /// The id of the contained packet.
+    fn id(&self) -> i32;
+
+    /// Writes the contained packet without framing, like `Segment::write_to_stream`.
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()>;
+
+    /// The exact amount of bytes `write_to_stream` produces.
+    fn serialized_len(&self) -> usize;
+
+    /// The name of the contained packet, `Raw` for unknown packets.
+    fn packet_name(&self) -> &'static str;
+
+    /// The fields of the contained packet, see `Packet::dump_fields`.
+    fn dump_fields(&self) -> std::io::Result<Vec<dump::DumpField>>;
+
+    /// Like `packet_by_id`, but unknown packets are returned as `Self::Raw`
+    /// with their body instead of being dropped. The reader must be limited to
+    /// the body of a single packet, as the remainder of it is taken as the body.
+    #[allow(unused)]
+    fn packet_by_id_or_raw<R: std::io::Read>(state: State, direction: Direction, id: i32, reader: &mut R) -> std::io::Result<Self> {
+        match Self::packet_by_id(state, direction, id, reader)? {
+            Some(packet) => Ok(packet),
+            None => {
+                let mut body = Vec::new();
+                reader.read_to_end(&mut body)?;
+                Ok(Self::from_raw(RawPacket{ id, state, direction, body }))
+            }
+        }
+    }
+
+    /// Decodes the body of a single packet with `packet_by_id_or_raw`, then
+    /// handles bytes it left unread by the `trailing::TrailingBytesPolicy` of
+    /// the active `DecodeContext`.
+    fn decode_body(state: State, direction: Direction, id: i32, body: &[u8]) -> std::io::Result<Self> {
+        let mut reader = body;
+        let packet = Self::packet_by_id_or_raw(state, direction, id, &mut reader)?;
+        trailing::check(Self::NAME, trailing::TrailingBytes{ packet: packet.packet_name(), state, direction, remaining: reader.len() })?;
+        Ok(packet)
+    }
+}
+
+/// A packet of protocol `P`, see `Protocol::descriptors`.
+pub struct PacketKind<P: Protocol>{
+    pub descriptor: &'static PacketDescriptor,
+    pub construct: fn() -> P
+}
+
+impl<P: Protocol> PacketKind<P>{
+    /// The packet with default fields.
+    pub fn create(&self) -> P {
+        (self.construct)()
+    }
+
+    /// Reads a packet of this kind from its body.
+    pub fn read<R: std::io::Read>(&self, reader: &mut R) -> std::io::Result<P> {
+        P::packet_by_id(self.descriptor.state, self.descriptor.direction, self.descriptor.id, reader)?
+            .ok_or_else(|| std::io::Error::other(format!("{} is not part of {}", self.descriptor.name, P::NAME)))
+    }
+}
+
+impl<P: Protocol> Clone for PacketKind<P>{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Protocol> Copy for PacketKind<P>{}
+
+impl<P: Protocol> Debug for PacketKind<P>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PacketKind").field(&self.descriptor.name).finish()
+    }
+}
+
+/// Static information about a packet of a protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketDescriptor{
+    pub name: &'static str,
+    pub state: State,
+    pub direction: Direction,
+    pub id: i32,
+    /// The attributes of the packet as written in the definition, doc comments included.
+    pub attributes: &'static str,
+    pub fields: &'static [FieldDescriptor]
+}
+
+impl PacketDescriptor{
+    /// The doc comment of the packet, one entry per line.
+    pub fn doc(&self) -> Vec<String> {
+        doc_lines(self.attributes)
+    }
+}
+
+/// Static information about a field of a packet, as written in the definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldDescriptor{
+    pub name: &'static str,
+    pub value_type: &'static str,
+    /// The type the field is sent as, for `SegmentEnum` fields.
+    pub wire_type: Option<&'static str>,
+    /// The body of the `where` condition.
+    pub condition: Option<&'static str>,
+    /// The first protocol version the field is sent in.
+    pub since: Option<i32>,
+    /// The last protocol version the field is sent in.
+    pub until: Option<i32>,
+    /// Whether the field is read and written by custom functions.
+    pub custom: bool,
+    pub attributes: &'static str
+}
+
+impl FieldDescriptor{
+    pub fn doc(&self) -> Vec<String> {
+        doc_lines(self.attributes)
+    }
+}
+
+/// The protocol version fields with `since` or `until` are read and written
+/// for: the one of the active `DecodeContext`, or `default` if it has none.
+pub fn active_version(default: i32) -> i32 {
+    crate::segment::context::current(|context| context.protocol_version).unwrap_or(default)
+}
+
+/// Whether `version` is within the optional inclusive bounds. Snapshot numbers
+/// are larger than every release, which holds for the snapshots of the next
+/// release but not older ones.
+pub fn in_versions(version: i32, since: Option<i32>, until: Option<i32>) -> bool {
+    since.is_none_or(|since| version >= since) && until.is_none_or(|until| version <= until)
+}
+
+/// How many packets of the state and direction have the id. `define_protocol!`
+/// checks at compile time that this is 1 for every packet, as a packet with
+/// the id of an earlier one would never be read.
+pub const fn packet_id_count(packets: &[PacketDescriptor], state: State, direction: Direction, id: i32) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < packets.len() {
+        let packet = &packets[i];
+        if packet.id == id && packet.state.id() == state.id() && packet.direction.id() == direction.id() {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Checks that the packet ids of every state and direction run from 0 to the
+/// highest one, except for the declared gaps. `define_protocol!` generates a
+/// test calling this for every protocol.
+pub fn check_ids<P: Protocol>() -> std::io::Result<()> {
+    let packets = P::packets();
+    let gaps = P::id_gaps();
+    let mut sections: Vec<(State, Direction)> = packets.iter().map(|packet| (packet.state, packet.direction)).collect();
+    sections.extend(gaps.iter().map(|(state, direction, _)| (*state, *direction)));
+    sections.sort_by_key(|(state, direction)| (state.id(), direction.id()));
+    sections.dedup();
+    let mut problems = Vec::new();
+    for (state, direction) in sections {
+        let ids: Vec<i32> = packets.iter().filter(|packet| packet.state == state && packet.direction == direction).map(|packet| packet.id).collect();
+        let declared: Vec<i32> = gaps.iter().filter(|gap| gap.0 == state && gap.1 == direction).map(|gap| gap.2).collect();
+        for gap in declared.iter().filter(|gap| ids.contains(gap)) {
+            problems.push(format!("{:?} {:?} declares 0x{:02X} as gap, but it is used", state, direction, gap));
+        }
+        let highest = ids.iter().copied().max().unwrap_or(-1);
+        let missing: Vec<String> = (0..=highest)
+            .filter(|id| !ids.contains(id) && !declared.contains(id))
+            .map(|id| format!("0x{:02X}", id))
+            .collect();
+        if !missing.is_empty() {
+            problems.push(format!("{:?} {:?} is missing packet ids {}", state, direction, missing.join(", ")));
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    }else{
+        Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!(
+            "{}: {}; declare ids that are left out on purpose with `gaps [...];`", P::NAME, problems.join("; ")
+        )))
+    }
+}
+
+/// Extracts the doc comment lines from stringified attributes. Depending on
+/// the compiler, doc comments are stringified as plain or raw string literals.
+fn doc_lines(attributes: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut rest = attributes;
+    while let Some(start) = rest.find("doc") {
+        rest = &rest[start + 3..];
+        let literal = match rest.trim_start().strip_prefix('=') {
+            Some(literal) => literal.trim_start(),
+            None => continue
+        };
+        let (line, remainder) = if let Some(raw) = literal.strip_prefix('r') {
+            let hashes = raw.len() - raw.trim_start_matches('#').len();
+            let body = &raw[hashes..];
+            let body = match body.strip_prefix('"') {
+                Some(body) => body,
+                None => continue
+            };
+            let terminator = format!("\"{}", "#".repeat(hashes));
+            match body.find(&terminator) {
+                Some(end) => (body[..end].to_string(), &body[end + terminator.len()..]),
+                None => (body.to_string(), "")
+            }
+        }else if let Some(body) = literal.strip_prefix('"') {
+            let mut line = String::new();
+            let mut end = body.len();
+            let mut chars = body.char_indices();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => match chars.next() {
+                        Some((_, 'n')) => line.push('\n'),
+                        Some((_, 't')) => line.push('\t'),
+                        Some((_, escaped)) => line.push(escaped),
+                        None => {}
+                    },
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    c => line.push(c)
+                }
+            }
+            (line, &body[end.min(body.len())..])
+        }else{
+            continue;
+        };
+        lines.push(line.strip_prefix(' ').map(str::to_string).unwrap_or(line));
+        rest = remainder;
+    }
+    lines
+}
+
+/// The result of a `where` condition in `define_protocol!`.
+///
+/// Conditions usually evaluate to a `bool`, but may return a
+/// `std::io::Result<bool>` when the packet itself is malformed to the point
+/// where it can not be decided whether the field is present.
+pub trait Condition{
+    fn evaluate(self) -> std::io::Result<bool>;
+}
+
+impl Condition for bool{
+    #[inline]
+    fn evaluate(self) -> std::io::Result<bool> {
+        Ok(self)
+    }
+}
+
+impl Condition for std::io::Result<bool>{
+    #[inline]
+    fn evaluate(self) -> std::io::Result<bool> {
+        self
+    }
+}
+
+/// Error of a field condition, carried inside the `std::io::Error` returned by
+/// the packet. Use `std::io::Error::get_ref` and downcast to get at it.
+#[derive(Debug)]
+pub struct FieldError{
+    pub packet: &'static str,
+    pub field: &'static str,
+    pub source: std::io::Error
+}
+
+impl FieldError{
+    pub fn wrap(packet: &'static str, field: &'static str, source: std::io::Error) -> std::io::Error {
+        std::io::Error::new(source.kind(), FieldError{ packet, field, source })
+    }
+}
+
+impl std::fmt::Display for FieldError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}: {}", self.packet, self.field, self.source)
+    }
+}
+
+impl std::error::Error for FieldError{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// A packet that was not parsed, either because the protocol does not define
+/// it or because the caller is not interested in its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawPacket{
+    pub id: i32,
+    pub state: State,
+    pub direction: Direction,
+    pub body: Vec<u8>
+}
+
+impl RawPacket{
+    /// Writes the body as-is, the counterpart of `Segment::write_to_stream`.
+    pub fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.body)
+    }
+
+    /// Writes the packet as an uncompressed frame.
+    pub fn write_frame<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        use crate::segment::implementation::var::{var_int_len, write_var_int};
+        write_var_int((var_int_len(self.id) + self.body.len()) as i32, writer)?;
+        write_var_int(self.id, writer)?;
+        self.write_to_stream(writer)
+    }
 }
 
 pub trait Packet: Segment + Sized + Debug{
@@ -41,69 +439,274 @@ pub trait Packet: Segment + Sized + Debug{
     fn packet_id(&self) -> i32 {
         Self::PACKET_ID
     }
+
+    /// Every field with the bytes it is written as, for `dump`. By default the
+    /// whole packet is returned as a single field.
+    fn dump_fields(&self) -> std::io::Result<Vec<dump::DumpField>> {
+        let mut bytes = Vec::new();
+        self.write_to_stream(&mut bytes)?;
+        Ok(vec![dump::DumpField{ name: "body", bytes, value: format!("{:?}", self) }])
+    }
 }
 
+/// A packet defined by the protocol `P`, implemented by `define_protocol!`.
+///
+/// Bounds on it make sending a packet of another protocol a compile error, and
+/// the consts let `id_in` check the state and direction in const contexts.
+pub trait PacketOf<P: Protocol>: Packet{
+    const STATE: State;
+    const DIRECTION: Direction;
+}
+
+/// Implemented by `define_protocol!` with the `Direction::id` of every packet,
+/// the markers `ClientBound` and `ServerBound` follow from it.
+pub trait PacketDirection<const ID: u8>{}
+
+/// Implemented by `define_protocol!` with the `State::id` of every packet, the
+/// markers like `PlayState` follow from it.
+pub trait PacketState<const ID: u8>{}
+
+/// A packet sent by the server. Bound APIs by it, and by the state markers
+/// below, to refuse packets of the wrong side or state at compile time.
+pub trait ClientBound{}
+/// A packet sent by the client.
+pub trait ServerBound{}
+impl<P: PacketDirection<0>> ClientBound for P{}
+impl<P: PacketDirection<1>> ServerBound for P{}
+
+pub trait HandshakeState{}
+pub trait StatusState{}
+pub trait LoginState{}
+pub trait PlayState{}
+pub trait ConfigurationState{}
+impl<P: PacketState<0>> HandshakeState for P{}
+impl<P: PacketState<1>> StatusState for P{}
+impl<P: PacketState<2>> LoginState for P{}
+impl<P: PacketState<3>> PlayState for P{}
+impl<P: PacketState<4>> ConfigurationState for P{}
+
+/// Defines the packets of a protocol version along with an enum holding any of them.
+///
+/// Fields are written as `name: Type` and may be followed by:
+///
+/// * `as WireType` to send a `SegmentEnum` as a number, e.g. `book_id: RecipeBookType as VarInt`.
+/// * `=> since 755` and/or `=> until 754` to only send the field from or up to a protocol
+///   version, so one definition can cover a range of versions. The version is the one of the
+///   active `DecodeContext`, falling back to the version of the definition; see
+///   `Segment::read_versioned`. Fields that are not sent keep their default value.
+/// * `=> with(read = read_fn, write = write_fn)` for fields that need custom logic. The
+///   functions have the signatures `fn(&Packet, &mut impl Read) -> std::io::Result<Type>`
+///   and `fn(&Packet, &Type, &mut impl Write) -> std::io::Result<()>`, and can look at the
+///   fields that were read before.
+/// * `where |p| {condition}` to only send the field when the condition holds. The condition
+///   evaluates to either a `bool` or a `std::io::Result<bool>`, see `Condition`.
+///
+/// Packet ids have to be unique within a state and direction, which is checked at compile
+/// time. The ids are also expected to run from 0 without holes, ids that are left out on
+/// purpose are declared at the start of the direction with `gaps [0x05, 0x07];`. A test
+/// checking this is generated for every protocol, see `check_ids`.
+///
+/// Every packet implements `PacketOf` for the protocol, and the markers of its
+/// direction and state like `ClientBound` and `PlayState`.
+///
+/// Packets and the protocol enum derive `Debug`, `Clone` and `PartialEq`, so all field types
+/// have to implement those. Attributes after the protocol version, like `#[derive(Eq)]`, are
+/// put on every packet, and all field types have to support them too. Attributes before the
+/// name end up on the protocol enum. With the `serde` feature of this crate, the packets and the protocol
+/// enum derive `serde::Serialize` and `serde::Deserialize`, so their field types have to
+/// implement those and crates defining a protocol need `serde` as a dependency.
 #[macro_export]
 macro_rules! define_protocol {
-    ($(#[$enum_meta:meta])* $struct_vis:vis $struct_name:ident, $protocol_name:literal, $protocol_version:literal $(, #[$global_packet_meta:meta])*{$($state:path =>{$($direction:path =>{$($(#[$packet_meta:meta])* $id:literal => $packet:ident$({$( $(#[$field_doc:meta])* $field:ident: $value_type:ty $(where |$acceptor:ident|$condition:block)?),*$(,)?})?),+$(,)?}),+$(,)?}),+$(,)?}) => {
-
-        $(#[$global_packet_meta])*
+    (@impl $global_packet_meta:tt $(#[$enum_meta:meta])* $struct_vis:vis $struct_name:ident, $protocol_name:literal, $protocol_version:literal {$($state:path =>{$($direction:path =>{$(gaps [$($gap:literal),*$(,)?];)? $($(#[$packet_meta:meta])* $id:literal => $packet:ident$({$( $(#[$field_doc:meta])* $field:ident: $value_type:ty $(as $wire_type:ty)? $(=> since $since:literal)? $(=> until $until:literal)? $(=> with(read = $read_fn:path, write = $write_fn:path))? $(where |$acceptor:ident|$condition:block)?),*$(,)?})?),+$(,)?}),+$(,)?}),+$(,)?}) => {
         $($($(
-        #[allow(unused)]
-        #[derive(Default, Debug)]
-        $(#[$packet_meta])*
-        $struct_vis struct $packet {
-            $($(
-                $(#[$field_doc])*
-                pub $field: $value_type
-            ),*)*
+        $crate::__serde_item!{$global_packet_meta
+            #[allow(unused)]
+            #[derive(Default, Debug, Clone, PartialEq)]
+            $(#[$packet_meta])*
+            $struct_vis struct $packet {
+                $($(
+                    $(#[$field_doc])*
+                    pub $field: $value_type
+                ),*)*
+            }
         }
 
-        impl crate::protocol::Packet for $packet{
+        impl $crate::protocol::Packet for $packet{
             const PACKET_ID: i32 = $id;
+
+            #[allow(unused)]
+            fn dump_fields(&self) -> std::io::Result<Vec<$crate::protocol::dump::DumpField>> {
+                let mut fields = Vec::new();
+                $($({
+                    let mut bytes = Vec::new();
+                    let present = $crate::__versioned!($protocol_version $(, since $since)? $(, until $until)?) $(&& $crate::__condition!(self, $packet, $field, |$acceptor| $condition)?)?;
+                    if present {
+                        let writer = &mut bytes;
+                        $crate::__write_field!(self, self.$field, writer $(, $wire_type)? $(, write = $write_fn)?);
+                    }
+                    fields.push($crate::protocol::dump::DumpField{ name: stringify!($field), bytes, value: format!("{:?}", self.$field) });
+                })*)*
+                Ok(fields)
+            }
         }
 
-        impl crate::segment::Segment for $packet {
+        impl $crate::protocol::PacketOf<$struct_name> for $packet{
+            const STATE: $crate::protocol::State = $state;
+            const DIRECTION: $crate::protocol::Direction = $direction;
+        }
+
+        impl $crate::protocol::PacketDirection<{ $direction.id() }> for $packet{}
+        impl $crate::protocol::PacketState<{ $state.id() }> for $packet{}
+
+        impl $crate::segment::Segment for $packet {
             #[allow(unused)]
             fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
                 $($(self.$field = {
                     let mut field: $value_type = Default::default();
-                    $(if (|$acceptor: &Self|$condition)(self))?
-                       {crate::segment::Segment::read_from_stream(&mut field, reader)?;}
+                    if $crate::__versioned!($protocol_version $(, since $since)? $(, until $until)?) $(&& $crate::__condition!(self, $packet, $field, |$acceptor| $condition)?)?
+                       {$crate::__read_field!(self, field, reader $(, $wire_type)? $(, read = $read_fn)?);}
                     field
                 };)*)*
                 Ok(())
             }
             #[allow(unused)]
             fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()>{
-                $($($(if (|$acceptor: &Self|$condition)(self))?
-                   { crate::segment::Segment::write_to_stream(&self.$field, writer)?; }
+                $($(if $crate::__versioned!($protocol_version $(, since $since)? $(, until $until)?) $(&& $crate::__condition!(self, $packet, $field, |$acceptor| $condition)?)?
+                   { $crate::__write_field!(self, self.$field, writer $(, $wire_type)? $(, write = $write_fn)?); }
                 )*)*
                 Ok(())
             }
-        })+)+)+
+            #[allow(unused)]
+            fn try_serialized_len(&self) -> std::io::Result<usize> {
+                let mut len = 0;
+                $($(if $crate::__versioned!($protocol_version $(, since $since)? $(, until $until)?) $(&& $crate::__condition!(self, $packet, $field, |$acceptor| $condition)?)?
+                   { len += $crate::__field_len!(self, self.$field $(, $wire_type)? $(, write = $write_fn)?)?; }
+                )*)*
+                Ok(len)
+            }
+        }
 
-        #[allow(unused, non_camel_case_types)]
-        #[derive(Debug)]
-        $(#[$enum_meta])*
-        $struct_vis enum $struct_name {
-            $($($($packet(Box<$packet>)),+),+),+
+        $crate::__test_utils!{$packet})+)+)+
+
+        $crate::__serde_item!{[]
+            #[allow(unused, non_camel_case_types)]
+            #[derive(Debug, Clone, PartialEq)]
+            $(#[$enum_meta])*
+            $struct_vis enum $struct_name {
+                $($($($packet(Box<$packet>)),+),+),+,
+                /// A packet that is not part of this protocol definition.
+                Raw(Box<$crate::protocol::RawPacket>)
+            }
         }
 
-        impl crate::protocol::Protocol for $struct_name {
+        #[allow(unused)]
+        impl $struct_name {
+            /// The id of packet `P`, which has to be a packet of this protocol.
+            pub const fn id_of<P: $crate::protocol::PacketOf<Self>>() -> i32 {
+                P::PACKET_ID
+            }
+
+            /// The id of packet `P`, asserting that it belongs to `state` and
+            /// `direction`. Evaluated in a const, a packet of another state or
+            /// direction fails to compile.
+            pub const fn id_in<P: $crate::protocol::PacketOf<Self>>(state: $crate::protocol::State, direction: $crate::protocol::Direction) -> i32 {
+                assert!(
+                    P::STATE.id() == state.id() && P::DIRECTION.id() == direction.id(),
+                    "the packet does not belong to the given state and direction"
+                );
+                P::PACKET_ID
+            }
+        }
+
+        impl $crate::protocol::Protocol for $struct_name {
             const NAME: &'static str = $protocol_name;
             const PROTOCOL: i32 = $protocol_version;
 
+            fn from_raw(raw: $crate::protocol::RawPacket) -> Self {
+                Self::Raw(Box::new(raw))
+            }
+
+            fn packets() -> &'static [$crate::protocol::PacketDescriptor] {
+                const PACKETS: &[$crate::protocol::PacketDescriptor] = &[$($($($crate::protocol::PacketDescriptor{
+                    name: stringify!($packet),
+                    state: $state,
+                    direction: $direction,
+                    id: $id,
+                    attributes: stringify!($(#[$packet_meta])*),
+                    fields: &[$($($crate::protocol::FieldDescriptor{
+                        name: stringify!($field),
+                        value_type: stringify!($value_type),
+                        wire_type: $crate::__optional_str!($($wire_type)?),
+                        condition: $crate::__optional_str!($($condition)?),
+                        since: $crate::__optional_version!($($since)?),
+                        until: $crate::__optional_version!($($until)?),
+                        custom: $crate::__optional_str!($($read_fn)?).is_some(),
+                        attributes: stringify!($(#[$field_doc])*)
+                    }),*)*]
+                }),+),+),+];
+                $($($(const _: () = assert!(
+                    $crate::protocol::packet_id_count(PACKETS, $state, $direction, $id) == 1,
+                    concat!(stringify!($packet), " has the same packet id as another packet of its state and direction")
+                );)+)+)+
+                PACKETS
+            }
+
+            fn constructors() -> &'static [fn() -> Self] {
+                &[$($($(|| $struct_name::$packet(Box::default())),+),+),+]
+            }
+
+            fn id_gaps() -> &'static [($crate::protocol::State, $crate::protocol::Direction, i32)] {
+                &[$($($($(($state, $direction, $gap),)*)?)+)+]
+            }
+
+            fn id(&self) -> i32 {
+                match self {
+                    $($($(Self::$packet(_) => $id,)+)+)+
+                    Self::Raw(raw) => raw.id
+                }
+            }
+
+            fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                match self {
+                    $($($(Self::$packet(packet) => $crate::segment::Segment::write_to_stream(packet, writer),)+)+)+
+                    Self::Raw(raw) => raw.write_to_stream(writer)
+                }
+            }
+
+            fn serialized_len(&self) -> usize {
+                match self {
+                    $($($(Self::$packet(packet) => $crate::segment::Segment::serialized_len(packet),)+)+)+
+                    Self::Raw(raw) => raw.body.len()
+                }
+            }
+
+            fn packet_name(&self) -> &'static str {
+                match self {
+                    $($($(Self::$packet(_) => stringify!($packet),)+)+)+
+                    Self::Raw(_) => "Raw"
+                }
+            }
+
+            fn dump_fields(&self) -> std::io::Result<Vec<$crate::protocol::dump::DumpField>> {
+                match self {
+                    $($($(Self::$packet(packet) => $crate::protocol::Packet::dump_fields(&**packet),)+)+)+
+                    Self::Raw(raw) => Ok(vec![$crate::protocol::dump::DumpField{ name: "body", bytes: raw.body.clone(), value: format!("{} bytes", raw.body.len()) }])
+                }
+            }
+
             #[allow(unreachable_patterns)]
-            fn packet_by_id<R: std::io::Read>(state: State, direction: crate::protocol::Direction, id: i32, reader: &mut R) -> std::io::Result<Option<Self>> {
+            fn packet_by_id<R: std::io::Read>(state: $crate::protocol::State, direction: $crate::protocol::Direction, id: i32, reader: &mut R) -> std::io::Result<Option<Self>> {
                 match state {
                     $($state => {
                         match direction {
                             $($direction => {
                                 match id {
                                     $($id => {
+                                        let decoding = $crate::metrics::Decoding::start($protocol_name, state, direction, id);
                                         let mut p: Box<$packet> = Box::new(Default::default());
-                                        if let Err(e) = crate::segment::Segment::read_from_stream(&mut p, reader){
+                                        let result = $crate::segment::Segment::read_from_stream(&mut p, reader);
+                                        decoding.finish(stringify!($packet), &p, &result);
+                                        if let Err(e) = result {
                                             Err(e)
                                         }else{
                                             Ok(Some(Self::$packet(p)))
@@ -119,5 +722,134 @@ macro_rules! define_protocol {
                 }
             }
         }
+
+        #[cfg(test)]
+        #[test]
+        #[allow(non_snake_case)]
+        fn $struct_name() {
+            if let Err(e) = $crate::protocol::check_ids::<$struct_name>() {
+                panic!("{}", e);
+            }
+        }
+    };
+    ($(#[$enum_meta:meta])* $struct_vis:vis $struct_name:ident, $protocol_name:literal, $protocol_version:literal $(, #[$global_packet_meta:meta])* {$($body:tt)*}) => {
+        $crate::define_protocol!(@impl [$(#[$global_packet_meta])*] $(#[$enum_meta])* $struct_vis $struct_name, $protocol_name, $protocol_version {$($body)*});
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __optional_str {
+    () => {
+        Option::<&'static str>::None
+    };
+    ($($tokens:tt)+) => {
+        Some(stringify!($($tokens)+))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __optional_version {
+    () => {
+        Option::<i32>::None
+    };
+    ($version:literal) => {
+        Some($version)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __versioned {
+    ($default:literal) => {
+        true
+    };
+    ($default:literal $(, since $since:literal)? $(, until $until:literal)?) => {
+        $crate::protocol::in_versions(
+            $crate::protocol::active_version($default),
+            $crate::__optional_version!($($since)?),
+            $crate::__optional_version!($($until)?)
+        )
+    };
+}
+
+/// The `arbitrary` and `roundtrip` helpers of a packet. Defined per feature, as
+/// a `cfg` inside `define_protocol!` would check the features of the calling crate.
+#[cfg(feature = "test-utils")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __test_utils {
+    ($packet:ident) => {
+        impl $packet {
+            /// Decodes the packet from arbitrary bytes, see `testing::arbitrary`.
+            pub fn arbitrary(data: &[u8]) -> std::io::Result<Self> {
+                $crate::testing::arbitrary(data)
+            }
+
+            /// Checks that the packet survives being written and read again.
+            pub fn roundtrip(&self) -> std::io::Result<()> {
+                $crate::testing::roundtrip(self)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "test-utils"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __test_utils {
+    ($packet:ident) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __condition {
+    ($packet:expr, $packet_type:ident, $field:ident, |$acceptor:ident| $condition:block) => {
+        $crate::protocol::Condition::evaluate((|$acceptor: &$packet_type| $condition)(&*$packet))
+            .map_err(|e| $crate::protocol::FieldError::wrap(stringify!($packet_type), stringify!($field), e))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __read_field {
+    ($packet:expr, $field:ident, $reader:ident) => {
+        $crate::segment::Segment::read_from_stream(&mut $field, $reader)?;
+    };
+    ($packet:expr, $field:ident, $reader:ident, read = $read_fn:path) => {
+        $field = $read_fn(&*$packet, $reader)?;
+    };
+    ($packet:expr, $field:ident, $reader:ident, $wire_type:ty) => {
+        $field = $crate::segment::read_enum::<_, $wire_type, _>($reader)?;
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __write_field {
+    ($packet:expr, $field:expr, $writer:ident) => {
+        $crate::segment::Segment::write_to_stream(&$field, $writer)?;
+    };
+    ($packet:expr, $field:expr, $writer:ident, write = $write_fn:path) => {
+        $write_fn($packet, &$field, $writer)?;
+    };
+    ($packet:expr, $field:expr, $writer:ident, $wire_type:ty) => {
+        $crate::segment::write_enum::<_, $wire_type, _>(&$field, $writer)?;
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_len {
+    ($packet:expr, $field:expr) => {
+        $crate::segment::Segment::try_serialized_len(&$field)
+    };
+    ($packet:expr, $field:expr, write = $write_fn:path) => {{
+        let mut counter = $crate::segment::ByteCounter::default();
+        $write_fn($packet, &$field, &mut counter).map(|_| counter.0)
+    }};
+    ($packet:expr, $field:expr, $wire_type:ty) => {
+        $crate::segment::try_enum_len::<_, $wire_type>(&$field)
     };
 }
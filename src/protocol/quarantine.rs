@@ -0,0 +1,95 @@
+//! Decoding that keeps going when a packet does not decode.
+//!
+//! A packet the protocol definition gets wrong fails to decode, which
+//! normally ends the connection. Proxies only need to forward such a packet,
+//! so here the failure is returned as a `DecodeFailure` holding the body as
+//! received, and the next packet is read as usual. The frame is read whole
+//! before it is decoded, so a failure does not leave the stream mid-frame.
+use crate::protocol::{Direction, Protocol, RawPacket, State};
+use std::io::Error;
+
+/// A packet that failed to decode, with its body so it can be forwarded anyway.
+#[derive(Debug)]
+pub struct DecodeFailure{
+    /// The name of the packet in the protocol.
+    pub packet_name: &'static str,
+    pub id: i32,
+    pub state: State,
+    pub direction: Direction,
+    pub error: Error,
+    /// The body after the packet id, as received.
+    pub raw: Vec<u8>
+}
+
+impl DecodeFailure{
+    /// The packet as a `RawPacket`, to be forwarded unchanged.
+    pub fn into_raw(self) -> RawPacket {
+        RawPacket{ id: self.id, state: self.state, direction: self.direction, body: self.raw }
+    }
+
+    /// The packet as `P::Raw`, for passing it on with the packets that did decode.
+    pub fn into_protocol<P: Protocol>(self) -> P {
+        P::from_raw(self.into_raw())
+    }
+}
+
+impl std::fmt::Display for DecodeFailure{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {} (0x{:02x}) in {:?} failed to decode: {}", self.direction, self.packet_name, self.id, self.state, self.error)
+    }
+}
+
+impl std::error::Error for DecodeFailure{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Decodes a packet body like `Protocol::packet_by_id_or_raw`, returning a
+/// `DecodeFailure` with a copy of the body when it fails.
+pub fn decode_or_quarantine<P: Protocol>(state: State, direction: Direction, id: i32, body: &[u8]) -> Result<P, DecodeFailure> {
+    P::decode_body(state, direction, id, body).map_err(|error| DecodeFailure{
+        packet_name: packet_name::<P>(state, direction, id),
+        id,
+        state,
+        direction,
+        error,
+        raw: body.to_vec()
+    })
+}
+
+/// The name of the packet with `id`, "Raw" for packets `P` does not know.
+fn packet_name<P: Protocol>(state: State, direction: Direction, id: i32) -> &'static str {
+    P::packets().iter()
+        .find(|packet| packet.state == state && packet.direction == direction && packet.id == id)
+        .map_or("Raw", |packet| packet.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::test_protocol::TestProto;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn packets_that_decode_are_returned() {
+        let packet = decode_or_quarantine::<TestProto>(State::Play, Direction::ClientBound, 0x01, &[0x01]).unwrap();
+        assert!(matches!(packet, TestProto::Flag(ref flag) if flag.value));
+        // Ids the protocol does not know are not failures.
+        let packet = decode_or_quarantine::<TestProto>(State::Play, Direction::ClientBound, 0x7F, &[0x01, 0x02]).unwrap();
+        assert!(matches!(packet, TestProto::Raw(ref raw) if raw.id == 0x7F && raw.body == [0x01, 0x02]));
+    }
+
+    #[test]
+    fn failures_keep_the_body() {
+        // KeepAlive with three of the eight bytes of its id.
+        let failure = decode_or_quarantine::<TestProto>(State::Play, Direction::ClientBound, 0x00, &[0x01, 0x02, 0x03]).unwrap_err();
+        assert_eq!((failure.packet_name, failure.id, failure.state, failure.direction), ("KeepAlive", 0x00, State::Play, Direction::ClientBound));
+        assert_eq!(failure.error.kind(), ErrorKind::UnexpectedEof);
+        assert!(failure.to_string().starts_with("ClientBound KeepAlive (0x00) in Play failed to decode"));
+        match failure.into_protocol::<TestProto>() {
+            TestProto::Raw(raw) => assert_eq!(*raw, RawPacket{ id: 0x00, state: State::Play, direction: Direction::ClientBound, body: vec![0x01, 0x02, 0x03] }),
+            other => panic!("expected a raw packet, got {:?}", other)
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! A small protocol of crate-native segments for the unit tests, which can
+//! not use the stevenarella definitions without the `steven` feature.
+use crate::protocol::{Direction, State};
+
+crate::define_protocol!(pub TestProto, "test", 755 {
+    State::Login => {
+        Direction::ServerBound => {
+            0x00 => LoginStart {
+                id: i64,
+            }
+        }
+    },
+    State::Play => {
+        Direction::ClientBound => {
+            0x00 => KeepAlive {
+                id: i64,
+            },
+            0x01 => Flag {
+                value: bool,
+            },
+            0x02 => BundleDelimiter
+        },
+        Direction::ServerBound => {
+            0x00 => Optional {
+                present: i8,
+                value: i64 where |p| {present(p.present)},
+            }
+        }
+    }
+});
+
+/// A condition that fails for anything but 0 and 1.
+fn present(flag: i8) -> std::io::Result<bool> {
+    match flag {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "present is neither 0 nor 1"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_can_be_cloned_and_compared() {
+        let packet = TestProto::KeepAlive(Box::new(KeepAlive{ id: 7 }));
+        assert_eq!(packet.clone(), packet);
+        assert_ne!(packet, TestProto::KeepAlive(Box::new(KeepAlive{ id: 8 })));
+        assert_ne!(packet, TestProto::Flag(Box::default()));
+    }
+
+    #[cfg(all(feature = "serde", feature = "serde_json"))]
+    #[test]
+    fn packets_and_the_protocol_enum_are_serializable() {
+        let packet = TestProto::KeepAlive(Box::new(KeepAlive{ id: 7 }));
+        let json = serde_json::to_string(&packet).unwrap();
+        assert_eq!(json, r#"{"KeepAlive":{"id":7}}"#);
+        match serde_json::from_str::<TestProto>(&json).unwrap() {
+            TestProto::KeepAlive(keep_alive) => assert_eq!(keep_alive.id, 7),
+            other => panic!("deserialized {:?}", other)
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Noticing packets that decode without reading all of their frame.
+//!
+//! Vanilla discards whatever a packet leaves unread, and so does
+//! `Protocol::packet_by_id`. Bytes left over usually mean the protocol
+//! definition misses a field, which then goes unnoticed until a field in the
+//! middle of a packet is wrong. `Protocol::decode_body` checks for them with
+//! the `TrailingBytesPolicy` of the active `DecodeContext`:
+//!
+//! ```ignore
+//! let context = DecodeContext::for_protocol::<Proto_1_17>().with_trailing_bytes(TrailingBytesPolicy::Reject);
+//! let packet = context.enter(|| stream.read_packet::<Proto_1_17>(State::Play, Direction::ClientBound))?;
+//! ```
+use crate::protocol::{Direction, State};
+use crate::segment::context;
+use std::io::{Error, ErrorKind};
+
+/// What to do with a packet that decoded with bytes left in its frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingBytesPolicy{
+    /// Discard them, like vanilla.
+    #[default]
+    Ignore,
+    /// Keep the packet and report them to the installed `metrics::Metrics`
+    /// and the `trailing_bytes_hook` of the context. With neither of them
+    /// this is the same as `Ignore`.
+    Warn,
+    /// Fail the decode with `TrailingBytes` inside an `InvalidData` error.
+    Reject
+}
+
+/// Bytes a packet left unread, carried inside the `std::io::Error` with
+/// `TrailingBytesPolicy::Reject`. Use `TrailingBytes::of` to get at it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrailingBytes{
+    /// The name of the packet.
+    pub packet: &'static str,
+    pub state: State,
+    pub direction: Direction,
+    /// Amount of bytes left unread.
+    pub remaining: usize
+}
+
+impl TrailingBytes{
+    pub fn of(error: &Error) -> Option<&TrailingBytes> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl std::fmt::Display for TrailingBytes{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {} in {:?} left {} bytes unread", self.direction, self.packet, self.state, self.remaining)
+    }
+}
+
+impl std::error::Error for TrailingBytes{}
+
+/// Applies the policy of the active context to a packet that left
+/// `remaining` bytes of its body unread.
+pub(crate) fn check(protocol: &'static str, trailing: TrailingBytes) -> std::io::Result<()> {
+    if trailing.remaining == 0 {
+        return Ok(());
+    }
+    match context::current(|context| context.trailing_bytes) {
+        TrailingBytesPolicy::Ignore => Ok(()),
+        TrailingBytesPolicy::Warn => {
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = crate::metrics::installed() {
+                metrics.trailing_bytes(protocol, &trailing);
+            }
+            if let Some(hook) = context::current(|context| context.trailing_bytes_hook.clone()) {
+                hook(protocol, &trailing);
+            }
+            Ok(())
+        }
+        TrailingBytesPolicy::Reject => Err(Error::new(ErrorKind::InvalidData, trailing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::test_protocol::TestProto;
+    use crate::protocol::Protocol;
+    use crate::segment::context::DecodeContext;
+    use std::sync::{Arc, Mutex};
+
+    /// A Flag with a byte after its value.
+    fn decode(context: DecodeContext) -> std::io::Result<TestProto> {
+        context.enter(|| TestProto::decode_body(State::Play, Direction::ClientBound, 0x01, &[0x01, 0xFF]))
+    }
+
+    #[test]
+    fn ignore_keeps_the_packet() {
+        assert!(matches!(decode(DecodeContext::default()), Ok(TestProto::Flag(_))));
+    }
+
+    #[test]
+    fn warn_keeps_the_packet_and_reports() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let context = DecodeContext::default()
+            .with_trailing_bytes(TrailingBytesPolicy::Warn)
+            .with_trailing_bytes_hook(move |protocol, trailing| sink.lock().unwrap().push((protocol, trailing.clone())));
+        assert!(matches!(decode(context), Ok(TestProto::Flag(_))));
+        let expected = TrailingBytes{ packet: "Flag", state: State::Play, direction: Direction::ClientBound, remaining: 1 };
+        assert_eq!(*reports.lock().unwrap(), vec![("test", expected)]);
+    }
+
+    #[test]
+    fn warn_without_a_hook_keeps_the_packet() {
+        assert!(matches!(decode(DecodeContext::default().with_trailing_bytes(TrailingBytesPolicy::Warn)), Ok(TestProto::Flag(_))));
+    }
+
+    #[test]
+    fn reject_fails_the_decode() {
+        let error = decode(DecodeContext::default().with_trailing_bytes(TrailingBytesPolicy::Reject)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(TrailingBytes::of(&error).map(|trailing| (trailing.packet, trailing.remaining)), Some(("Flag", 1)));
+        // Packets that read their whole body pass.
+        let context = DecodeContext::default().with_trailing_bytes(TrailingBytesPolicy::Reject);
+        assert!(context.enter(|| TestProto::decode_body(State::Play, Direction::ClientBound, 0x01, &[0x01])).is_ok());
+    }
+}
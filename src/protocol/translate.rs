@@ -0,0 +1,154 @@
+//! Translates packets of one protocol version into another, the building
+//! block of a proxy that lets clients join servers of another version.
+//!
+//! A `Translation` holds a translator per packet of the source protocol.
+//! Packets without one are passed through if the target protocol defines a
+//! packet with the same name, state, direction and fields, by writing it and
+//! reading it back with the id of the target, or forwarding the bytes as a
+//! raw packet if that fails. Fields with version bounds are written and read
+//! for their own protocol version. Everything else goes to the fallback,
+//! which drops the packet unless another one is set:
+//!
+//! ```ignore
+//! let mut translation = Translation::<Proto_1_16, Proto_1_17>::new();
+//! translation.register("ChatMessage", |packet| match packet {
+//!     Proto_1_16::ChatMessage(chat) => vec![Proto_1_17::ChatMessage(Box::new(ChatMessage{ message: chat.message }))],
+//!     _ => unreachable!()
+//! });
+//! let translated = translation.translate(packet);
+//! ```
+use crate::protocol::diff::diff_fields;
+use crate::protocol::{Direction, Protocol, RawPacket, State};
+use crate::segment::context;
+use std::collections::HashMap;
+
+/// Turns a packet of `A` into any number of packets of `B`.
+pub trait Translator<A: Protocol, B: Protocol>{
+    fn translate(&self, packet: A) -> Vec<B>;
+}
+
+impl<A: Protocol, B: Protocol, F: Fn(A) -> Vec<B>> Translator<A, B> for F{
+    fn translate(&self, packet: A) -> Vec<B> {
+        self(packet)
+    }
+}
+
+/// Where a packet that can be passed through ends up in the target protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Passthrough{
+    state: State,
+    direction: Direction,
+    id: i32
+}
+
+pub struct Translation<A: Protocol, B: Protocol>{
+    translators: HashMap<&'static str, Box<dyn Translator<A, B> + Send + Sync>>,
+    passthrough: HashMap<&'static str, Passthrough>,
+    fallback: Box<dyn Translator<A, B> + Send + Sync>
+}
+
+impl<A: Protocol, B: Protocol> std::fmt::Debug for Translation<A, B>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut translators: Vec<_> = self.translators.keys().collect();
+        translators.sort();
+        let mut passthrough: Vec<_> = self.passthrough.keys().collect();
+        passthrough.sort();
+        f.debug_struct("Translation")
+            .field("from", &A::NAME)
+            .field("to", &B::NAME)
+            .field("translators", &translators)
+            .field("passthrough", &passthrough)
+            .finish()
+    }
+}
+
+impl<A: Protocol, B: Protocol> Default for Translation<A, B>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Protocol, B: Protocol> Translation<A, B>{
+    /// Passes identical packets through and drops everything else.
+    pub fn new() -> Self {
+        let targets = B::packets();
+        let passthrough = A::packets().iter().filter_map(|packet| {
+            let target = targets.iter().find(|target| target.state == packet.state && target.direction == packet.direction && target.name == packet.name)?;
+            if !diff_fields(packet.fields, target.fields).is_empty() {
+                return None;
+            }
+            Some((packet.name, Passthrough{ state: target.state, direction: target.direction, id: target.id }))
+        }).collect();
+        Translation{
+            translators: HashMap::new(),
+            passthrough,
+            fallback: Box::new(|_| Vec::new())
+        }
+    }
+
+    /// Translates the packet of `A` named `packet` with `translator`, also if
+    /// it could be passed through.
+    pub fn register<T>(&mut self, packet: &'static str, translator: T) -> &mut Self
+        where T: Translator<A, B> + Send + Sync + 'static {
+        self.translators.insert(packet, Box::new(translator));
+        self
+    }
+
+    /// Drops the packet of `A` named `packet`, also if it could be passed through.
+    pub fn drop_packet(&mut self, packet: &'static str) -> &mut Self {
+        self.register(packet, |_| Vec::new())
+    }
+
+    /// Handles packets that have no translator and can not be passed through,
+    /// including packets `A` does not define.
+    pub fn set_fallback<T>(&mut self, fallback: T) -> &mut Self
+        where T: Translator<A, B> + Send + Sync + 'static {
+        self.fallback = Box::new(fallback);
+        self
+    }
+
+    /// Whether packets of `A` named `packet` are passed through when no
+    /// translator is registered for them.
+    pub fn is_passthrough(&self, packet: &str) -> bool {
+        self.passthrough.contains_key(packet)
+    }
+
+    /// The packets of `A` that can neither be translated nor passed through,
+    /// the ones that still need a translator.
+    pub fn untranslated(&self) -> Vec<&'static str> {
+        A::packets().iter()
+            .map(|packet| packet.name)
+            .filter(|name| !self.translators.contains_key(name) && !self.passthrough.contains_key(name))
+            .collect()
+    }
+}
+
+impl<A: Protocol, B: Protocol> Translator<A, B> for Translation<A, B>{
+    fn translate(&self, packet: A) -> Vec<B> {
+        let name = packet.packet_name();
+        if let Some(translator) = self.translators.get(name) {
+            return translator.translate(packet);
+        }
+        match self.passthrough.get(name).and_then(|target| pass(&packet, *target)) {
+            Some(translated) => vec![translated],
+            None => self.fallback.translate(packet)
+        }
+    }
+}
+
+/// Re-reads the packet as `B`. If that fails, the packet is kept as a raw
+/// packet of `B`, so the bytes are forwarded as they are. Returns `None` if
+/// the packet can not be written.
+fn pass<A: Protocol, B: Protocol>(packet: &A, target: Passthrough) -> Option<B> {
+    let mut body = Vec::with_capacity(packet.serialized_len());
+    context::versioned(A::PROTOCOL).enter(|| packet.write_to_stream(&mut body)).ok()?;
+    let read = context::versioned(B::PROTOCOL).enter(|| {
+        let mut reader = &body[..];
+        let packet = B::packet_by_id(target.state, target.direction, target.id, &mut reader)?;
+        Ok::<_, std::io::Error>(packet.filter(|_| reader.is_empty()))
+    });
+    match read {
+        Ok(Some(packet)) => Some(packet),
+        _ => Some(B::from_raw(RawPacket{ id: target.id, state: target.state, direction: target.direction, body }))
+    }
+}
@@ -0,0 +1,85 @@
+//! Decoding packets sent by peers that may be hostile.
+//!
+//! `decode_untrusted` is the entry point servers should use for anything a
+//! client sends. Compared to `Protocol::packet_by_id` it:
+//!
+//! * rejects bodies larger than `DecodeLimits::max_body_len` before decoding,
+//! * only reads from the given body, so length prefixes can not make the
+//!   decoders wait for more data than was received. Strings, NBT and the
+//!   `LenPrefixed` and `LenPrefixedBytes` fields of packets only allocate
+//!   as their data arrives, and strings are capped at `max_string_len`,
+//! * limits the nesting of NBT to `segment::implementation::nbt::MAX_DEPTH`,
+//! * decodes within a `DecodeContext`, which can reject overlong VarInts
+//!   and VarLongs with `Strictness::Strict` and packets leaving bytes unread
+//!   with `TrailingBytesPolicy::Reject`,
+//! * turns a panic in any decoder into an `InvalidData` error.
+//!
+//! Values nested inside other stevenarella types, like item stacks, are decoded
+//! by stevenarella itself. Their NBT is not covered by the depth limit, their
+//! VarInts not by the canonical encoding check, and their length prefixes are
+//! reserved before the data arrives. A panic is caught, but failing to
+//! allocate aborts the process, which only `max_body_len` bounds.
+use crate::connection::frame::FrameLimits;
+use crate::connection::{frame, pool};
+use crate::segment::context::DecodeContext;
+use crate::protocol::{Direction, Protocol, State};
+use std::io::{Error, ErrorKind, Read};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits{
+    /// The largest packet body that is decoded, excluding the packet id.
+    pub max_body_len: usize,
+    /// The longest string in bytes that crate-native segments read.
+    pub max_string_len: usize
+}
+
+impl Default for DecodeLimits{
+    fn default() -> Self {
+        DecodeLimits{
+            max_body_len: frame::MAX_FRAME_LEN as usize,
+            // Vanilla allows 32767 characters of up to 4 bytes each.
+            max_string_len: 32767 * 4
+        }
+    }
+}
+
+/// Decodes a packet body received from an untrusted peer, see the module documentation.
+/// Unknown packets are returned as `P::Raw`.
+pub fn decode_untrusted<P: Protocol>(state: State, direction: Direction, id: i32, body: &[u8], limits: &DecodeLimits) -> std::io::Result<P> {
+    decode_with(state, direction, id, body, &DecodeContext::for_protocol::<P>().with_limits(*limits))
+}
+
+/// Like `decode_untrusted`, with the body decoded within the given context.
+pub fn decode_with<P: Protocol>(state: State, direction: Direction, id: i32, body: &[u8], context: &DecodeContext) -> std::io::Result<P> {
+    let limits = &context.limits;
+    if body.len() > limits.max_body_len {
+        return Err(Error::new(ErrorKind::InvalidData, format!("packet body of {} bytes exceeds the limit of {}", body.len(), limits.max_body_len)));
+    }
+    let decode = || P::decode_body(state, direction, id, body);
+    match catch_unwind(AssertUnwindSafe(|| context.clone().enter(decode))) {
+        Ok(result) => result,
+        Err(panic) => {
+            let message = panic.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(Error::new(ErrorKind::InvalidData, format!("decoder of packet 0x{:02X} panicked: {}", id, message)))
+        }
+    }
+}
+
+/// Reads an uncompressed frame and decodes it with `decode_untrusted`. Frames
+/// over the vanilla `FrameLimits` of `state` are refused before they are read.
+pub fn read_untrusted<P: Protocol, R: Read>(reader: &mut R, state: State, direction: Direction, limits: &DecodeLimits) -> std::io::Result<P> {
+    read_with(reader, state, direction, &DecodeContext::for_protocol::<P>().with_limits(*limits))
+}
+
+/// Reads an uncompressed frame and decodes it with `decode_with`. The frame
+/// header is read within the context as well, and checked against the
+/// vanilla `FrameLimits` of `state`.
+pub fn read_with<P: Protocol, R: Read>(reader: &mut R, state: State, direction: Direction, context: &DecodeContext) -> std::io::Result<P> {
+    let (id, body) = context.clone().enter(|| frame::read_frame(reader, &FrameLimits::default(), state))?;
+    let packet = decode_with(state, direction, id, &body, context);
+    pool::recycle(body);
+    packet
+}
@@ -0,0 +1,177 @@
+//! Protocol version numbers of releases and snapshots, and picking the
+//! definition to speak with a client.
+//!
+//! Snapshots, pre-releases and release candidates since 1.16.4 set bit 30,
+//! `0x40000000`, and count up from there, e.g. `0x40000001`. Snapshot numbers
+//! say nothing about the release they lead up to, so comparing them with
+//! release numbers needs that release: `VersionRegistry::register_snapshot`
+//! orders a snapshot right before its target release.
+//!
+//! Definitions of snapshots use their number as `PROTOCOL`:
+//!
+//! ```ignore
+//! define_protocol!(pub Proto_1_21_pre1, "1.21-pre1", 0x400000C6 { ... });
+//! ```
+use crate::protocol::Protocol;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+pub const SNAPSHOT_BIT: i32 = 0x4000_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProtocolVersion(pub i32);
+
+impl ProtocolVersion{
+    pub fn release(number: i32) -> Self {
+        ProtocolVersion(number)
+    }
+
+    /// The version of the `number`th snapshot, counting from 1.16.4.
+    pub fn snapshot(number: i32) -> Self {
+        ProtocolVersion(SNAPSHOT_BIT | number)
+    }
+
+    pub fn is_snapshot(&self) -> bool {
+        self.0 & SNAPSHOT_BIT != 0
+    }
+
+    /// The number without the snapshot bit.
+    pub fn number(&self) -> i32 {
+        self.0 & !SNAPSHOT_BIT
+    }
+
+    /// Compares versions of the same kind. Releases and snapshots can only be
+    /// ordered knowing which release a snapshot belongs to, see
+    /// `VersionRegistry::compare`.
+    pub fn compare(&self, other: &ProtocolVersion) -> Option<Ordering> {
+        match self.is_snapshot() == other.is_snapshot() {
+            true => Some(self.0.cmp(&other.0)),
+            false => None
+        }
+    }
+}
+
+impl Display for ProtocolVersion{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.is_snapshot() {
+            true => write!(f, "snapshot {} (0x{:08X})", self.number(), self.0),
+            false => write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl From<i32> for ProtocolVersion{
+    fn from(version: i32) -> Self {
+        ProtocolVersion(version)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionInfo{
+    pub version: ProtocolVersion,
+    pub name: String,
+    /// The release a snapshot leads up to.
+    pub target: Option<i32>
+}
+
+impl VersionInfo{
+    /// Releases sort by number, snapshots right before their target release
+    /// and by number among each other.
+    fn sort_key(&self) -> (i32, bool, i32) {
+        match self.target {
+            Some(target) => (target, false, self.version.number()),
+            None => (self.version.0, true, 0)
+        }
+    }
+}
+
+/// How a server can talk to a client announcing a protocol version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Negotiation<'a>{
+    Supported(&'a VersionInfo),
+    /// The client is older than every registered version.
+    OutdatedClient,
+    /// The client is newer than every registered version, or an unknown
+    /// snapshot, which vanilla reports the same way.
+    OutdatedServer
+}
+
+/// The versions a server or proxy speaks.
+#[derive(Debug, Clone, Default)]
+pub struct VersionRegistry{
+    versions: BTreeMap<i32, VersionInfo>
+}
+
+impl VersionRegistry{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a definition. Snapshot definitions need a target, see `register_snapshot`.
+    pub fn register<P: Protocol>(&mut self) -> &mut Self {
+        self.register_version(P::PROTOCOL, P::NAME)
+    }
+
+    /// Registers a release, or a snapshot ordered after every release.
+    pub fn register_version(&mut self, version: i32, name: &str) -> &mut Self {
+        self.versions.insert(version, VersionInfo{ version: ProtocolVersion(version), name: name.to_string(), target: None });
+        self
+    }
+
+    /// Registers a snapshot leading up to the release with protocol `target`.
+    pub fn register_snapshot(&mut self, version: i32, name: &str, target: i32) -> &mut Self {
+        self.versions.insert(version, VersionInfo{ version: ProtocolVersion(version), name: name.to_string(), target: Some(target) });
+        self
+    }
+
+    pub fn get(&self, version: i32) -> Option<&VersionInfo> {
+        self.versions.get(&version)
+    }
+
+    pub fn contains(&self, version: i32) -> bool {
+        self.versions.contains_key(&version)
+    }
+
+    /// The registered versions, oldest first.
+    pub fn versions(&self) -> Vec<&VersionInfo> {
+        let mut versions: Vec<&VersionInfo> = self.versions.values().collect();
+        versions.sort_by_key(|info| info.sort_key());
+        versions
+    }
+
+    /// Orders two versions. A registered snapshot is compared by its target
+    /// release, others only with other snapshots.
+    pub fn compare(&self, a: i32, b: i32) -> Option<Ordering> {
+        match (self.get(a), self.get(b)) {
+            (Some(a), Some(b)) => Some(a.sort_key().cmp(&b.sort_key())),
+            (None, Some(b)) => compare_to(ProtocolVersion(a), b),
+            (Some(a), None) => compare_to(ProtocolVersion(b), a).map(Ordering::reverse),
+            (None, None) => ProtocolVersion(a).compare(&ProtocolVersion(b))
+        }
+    }
+
+    /// Picks the registered version matching the client, or tells which side
+    /// is outdated.
+    pub fn negotiate(&self, client: i32) -> Negotiation<'_> {
+        if let Some(info) = self.get(client) {
+            return Negotiation::Supported(info);
+        }
+        let older = self.versions.values().all(|info| compare_to(ProtocolVersion(client), info) == Some(Ordering::Less));
+        match !self.versions.is_empty() && older {
+            true => Negotiation::OutdatedClient,
+            false => Negotiation::OutdatedServer
+        }
+    }
+}
+
+/// Orders an unregistered version relative to a registered one.
+fn compare_to(version: ProtocolVersion, info: &VersionInfo) -> Option<Ordering> {
+    match (version.is_snapshot(), info.target) {
+        (false, Some(target)) if version.0 < target => Some(Ordering::Less),
+        (false, Some(_)) => Some(Ordering::Greater),
+        _ => version.compare(&info.version)
+    }
+}
@@ -0,0 +1,110 @@
+//! BungeeCord IP forwarding ("legacy forwarding").
+//!
+//! With `ip_forward` enabled, BungeeCord replaces the host of the Handshake
+//! with `host\0client ip\0uuid\0properties`, where the uuid is 32 hex digits
+//! without dashes and the properties are the JSON array of the game profile.
+//! The properties are left out when the player has none. Backend servers
+//! have to run in offline mode and should only be reachable through the proxy,
+//! as any client can send this data.
+use std::io::{Error, ErrorKind};
+use std::net::IpAddr;
+
+/// The data BungeeCord forwards in the host field of the Handshake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardedHandshake{
+    /// The host the client connected to the proxy with.
+    pub host: String,
+    pub client_ip: IpAddr,
+    pub uuid: u128,
+    /// The JSON array of profile properties, such as skins. Empty when none were forwarded.
+    pub properties: String
+}
+
+impl ForwardedHandshake{
+    /// Parses the host field of a forwarded Handshake.
+    pub fn decode(host: &str) -> std::io::Result<Self> {
+        let mut parts = host.split('\0');
+        let host = parts.next().unwrap_or_default();
+        let client_ip = parts.next()
+            .ok_or_else(|| invalid("the connection was not forwarded, is ip_forward enabled on the proxy?"))?;
+        let client_ip = client_ip.parse()
+            .map_err(|_| invalid("forwarded client address is invalid"))?;
+        let uuid = parts.next()
+            .and_then(parse_uuid)
+            .ok_or_else(|| invalid("forwarded UUID is missing or invalid"))?;
+        let properties = parts.next().unwrap_or_default().to_string();
+        if parts.next().is_some() {
+            return Err(invalid("forwarded host has too many parts"));
+        }
+        Ok(ForwardedHandshake{ host: host.to_string(), client_ip, uuid, properties })
+    }
+
+    /// Formats the host field the way BungeeCord sends it.
+    pub fn encode(&self) -> String {
+        let mut host = format!("{}\0{}\0{:032x}", self.host, self.client_ip, self.uuid);
+        if !self.properties.is_empty() {
+            host.push('\0');
+            host.push_str(&self.properties);
+        }
+        host
+    }
+
+    /// The forwarded profile properties.
+    #[cfg(feature = "serde_json")]
+    pub fn properties(&self) -> std::io::Result<Vec<ProfileProperty>> {
+        if self.properties.is_empty() {
+            return Ok(Vec::new());
+        }
+        let value: serde_json::Value = serde_json::from_str(&self.properties)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let entries = value.as_array().ok_or_else(|| invalid("forwarded properties are not an array"))?;
+        entries.iter().map(|entry| {
+            let field = |name: &str| entry.get(name).and_then(serde_json::Value::as_str).map(str::to_string);
+            Ok(ProfileProperty{
+                name: field("name").ok_or_else(|| invalid("forwarded property has no name"))?,
+                value: field("value").ok_or_else(|| invalid("forwarded property has no value"))?,
+                signature: field("signature")
+            })
+        }).collect()
+    }
+}
+
+/// A property of a game profile, such as the skin of a player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileProperty{
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>
+}
+
+/// Parses a UUID written as 32 hex digits, with or without dashes.
+pub fn parse_uuid(uuid: &str) -> Option<u128> {
+    let digits: String = uuid.chars().filter(|c| *c != '-').collect();
+    if digits.len() != 32 {
+        return None;
+    }
+    u128::from_str_radix(&digits, 16).ok()
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::ForwardedHandshake;
+    use crate::protocol::implementation::steven::v1_17::Handshake;
+
+    impl ForwardedHandshake{
+        /// Reads the forwarded data from a Handshake.
+        pub fn from_handshake(handshake: &Handshake) -> std::io::Result<Self> {
+            Self::decode(&handshake.host)
+        }
+
+        /// Replaces the host of a Handshake with the forwarded data, as the proxy does.
+        pub fn apply(&self, handshake: &mut Handshake) {
+            handshake.host = self.encode();
+        }
+    }
+}
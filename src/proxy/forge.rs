@@ -0,0 +1,770 @@
+//! Forge (FML) handshakes, exchanging mod lists before a modded client plays.
+//!
+//! Forge clients append a marker to the host of the Handshake: `\0FML\0` up
+//! to 1.12.2, `\0FML2\0` from 1.13 and `\0FML3\0` since 1.18.
+//!
+//! Up to 1.12.2 the handshake follows LoginSuccess as play state plugin
+//! messages on `FML|HS`, whose first byte tells the message. Both sides
+//! acknowledge the steps with the phase they reached.
+//!
+//! Since 1.13 it runs during login. Every LoginPluginRequest on
+//! `fml:loginwrapper` wraps a message of `fml:handshake` as
+//! `[String channel][VarInt length][message]`, and the client answers each
+//! with a wrapped reply in the LoginPluginResponse.
+//!
+//! A proxy passes modded clients through by keeping the marker in the host
+//! it forwards and by letting `FORGE_CHANNELS` through. A server runs
+//! `LegacyServerHandshake` or `ModernServerHandshake`, which turn clients
+//! away when their mods do not meet the `ModRequirements`.
+use crate::io::borrowed::{read_str, read_var_int};
+use crate::proxy::plugin_channel::{ChannelPolicy, PluginChannelFilter};
+use crate::segment::implementation::var::write_var_int;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Error, ErrorKind};
+
+pub const LEGACY_CHANNEL: &str = "FML|HS";
+pub const LOGIN_WRAPPER: &str = "fml:loginwrapper";
+pub const HANDSHAKE_CHANNEL: &str = "fml:handshake";
+
+/// Channels Forge needs, for proxies filtering plugin messages.
+pub const FORGE_CHANNELS: &[&str] = &["FML|HS", "FML", "FML|MP", "FORGE", "fml:loginwrapper", "fml:handshake", "fml:play"];
+
+/// The version of the `FML|HS` protocol of 1.12.2.
+pub const LEGACY_PROTOCOL: u8 = 2;
+
+/// Lets the Forge channels through a filter with a whitelist.
+pub fn allow_forge(filter: &mut PluginChannelFilter) {
+    for channel in FORGE_CHANNELS {
+        filter.set_policy(*channel, ChannelPolicy::Allow);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForgeMarker{
+    /// 1.7 up to 1.12.2.
+    Fml,
+    /// 1.13 up to 1.17.1.
+    Fml2,
+    /// Since 1.18.
+    Fml3
+}
+
+impl ForgeMarker{
+    pub fn marker(&self) -> &'static str {
+        match self {
+            ForgeMarker::Fml => "\0FML\0",
+            ForgeMarker::Fml2 => "\0FML2\0",
+            ForgeMarker::Fml3 => "\0FML3\0"
+        }
+    }
+
+    /// Whether the handshake runs during login rather than in play.
+    pub fn is_modern(&self) -> bool {
+        *self != ForgeMarker::Fml
+    }
+
+    /// Removes the marker from the host of a Handshake, returning the host
+    /// without it and the marker, `None` for vanilla clients.
+    pub fn strip(host: &str) -> (String, Option<ForgeMarker>) {
+        for marker in [ForgeMarker::Fml, ForgeMarker::Fml2, ForgeMarker::Fml3] {
+            if let Some(position) = host.find(marker.marker()) {
+                let mut stripped = host[..position].to_string();
+                // The marker ends with the NUL separating forwarded data.
+                stripped.push_str(&host[position + marker.marker().len() - 1..]);
+                if stripped.ends_with('\0') {
+                    stripped.pop();
+                }
+                return (stripped, Some(marker));
+            }
+        }
+        (host.to_string(), None)
+    }
+
+    /// The host a Forge client of this generation sends.
+    pub fn append(&self, host: &str) -> String {
+        format!("{}{}", host, self.marker())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModInfo{
+    pub id: String,
+    /// Empty when unknown, as in the mod list of 1.13+ clients.
+    pub version: String
+}
+
+impl ModInfo{
+    pub fn new(id: impl Into<String>, version: impl Into<String>) -> Self {
+        ModInfo{ id: id.into(), version: version.into() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModMismatch{
+    Missing(String),
+    WrongVersion{ id: String, expected: String, found: String },
+    Blocked(String)
+}
+
+impl ModMismatch{
+    pub fn description(&self) -> String {
+        match self {
+            ModMismatch::Missing(id) => format!("mod {} is required", id),
+            ModMismatch::WrongVersion{ id, expected, found } => format!("mod {} {} is required, found {}", id, expected, found),
+            ModMismatch::Blocked(id) => format!("mod {} is not allowed", id)
+        }
+    }
+}
+
+/// Mods a client has to have, or must not have.
+#[derive(Debug, Clone, Default)]
+pub struct ModRequirements{
+    required: BTreeMap<String, Option<String>>,
+    blocked: BTreeSet<String>
+}
+
+impl ModRequirements{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Requires the mod, in exactly `version` if given.
+    pub fn require(&mut self, id: impl Into<String>, version: Option<&str>) -> &mut Self {
+        self.required.insert(id.into(), version.map(str::to_string));
+        self
+    }
+
+    pub fn block(&mut self, id: impl Into<String>) -> &mut Self {
+        self.blocked.insert(id.into());
+        self
+    }
+
+    /// Checks the mods of a client. Versions are only compared when the
+    /// client sent them.
+    pub fn check(&self, mods: &[ModInfo]) -> Result<(), ModMismatch> {
+        if let Some(blocked) = mods.iter().find(|info| self.blocked.contains(&info.id)) {
+            return Err(ModMismatch::Blocked(blocked.id.clone()));
+        }
+        for (id, version) in &self.required {
+            let info = mods.iter().find(|info| &info.id == id)
+                .ok_or_else(|| ModMismatch::Missing(id.clone()))?;
+            if let Some(expected) = version {
+                if !info.version.is_empty() && &info.version != expected {
+                    return Err(ModMismatch::WrongVersion{ id: id.clone(), expected: expected.clone(), found: info.version.clone() });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The phases a 1.12.2 client acknowledges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientPhase{
+    Start,
+    Hello,
+    WaitingServerData,
+    WaitingServerComplete,
+    PendingComplete,
+    Complete,
+    Done,
+    Error
+}
+
+/// The phases a 1.12.2 server acknowledges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerPhase{
+    Start,
+    Hello,
+    WaitingCAck,
+    Complete,
+    Done,
+    Error
+}
+
+/// The ids of a registry sent to 1.12.2 clients.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryData{
+    /// Whether more registries follow, set by `LegacyServerHandshake`.
+    pub has_more: bool,
+    pub name: String,
+    pub ids: Vec<(String, i32)>,
+    pub substitutions: Vec<String>,
+    pub dummies: Vec<String>
+}
+
+/// A message on `FML|HS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LegacyMessage{
+    /// `dimension` overrides the dimension of JoinGame since protocol 2.
+    ServerHello{ protocol: u8, dimension: Option<i32> },
+    ClientHello{ protocol: u8 },
+    ModList(Vec<ModInfo>),
+    RegistryData(RegistryData),
+    /// The phase the sender reached, a `ClientPhase` or `ServerPhase`.
+    Ack(u8),
+    Reset
+}
+
+impl LegacyMessage{
+    pub fn decode(mut data: &[u8]) -> std::io::Result<Self> {
+        let buffer = &mut data;
+        Ok(match read_u8(buffer)? {
+            0 => {
+                let protocol = read_u8(buffer)?;
+                let dimension = if protocol > 1 { Some(read_i32(buffer)?) } else { None };
+                LegacyMessage::ServerHello{ protocol, dimension }
+            }
+            1 => LegacyMessage::ClientHello{ protocol: read_u8(buffer)? },
+            2 => {
+                let mut mods = Vec::new();
+                for _ in 0..read_count(buffer)? {
+                    mods.push(ModInfo::new(read_str(buffer)?, read_str(buffer)?));
+                }
+                LegacyMessage::ModList(mods)
+            }
+            3 => {
+                let has_more = read_u8(buffer)? != 0;
+                let name = read_str(buffer)?.to_string();
+                let mut ids = Vec::new();
+                for _ in 0..read_count(buffer)? {
+                    ids.push((read_str(buffer)?.to_string(), read_var_int(buffer)?));
+                }
+                let substitutions = read_strings(buffer)?;
+                // Added in later 1.12.2 builds.
+                let dummies = if buffer.is_empty() { Vec::new() } else { read_strings(buffer)? };
+                LegacyMessage::RegistryData(RegistryData{ has_more, name, ids, substitutions, dummies })
+            }
+            0xFF => LegacyMessage::Ack(read_u8(buffer)?),
+            0xFE => LegacyMessage::Reset,
+            discriminator => return Err(Error::new(ErrorKind::InvalidData, format!("unknown FML handshake message {}", discriminator)))
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            LegacyMessage::ServerHello{ protocol, dimension } => {
+                out.extend_from_slice(&[0, *protocol]);
+                if *protocol > 1 {
+                    out.extend_from_slice(&dimension.unwrap_or(0).to_be_bytes());
+                }
+            }
+            LegacyMessage::ClientHello{ protocol } => out.extend_from_slice(&[1, *protocol]),
+            LegacyMessage::ModList(mods) => {
+                out.push(2);
+                let _ = write_var_int(mods.len() as i32, &mut out);
+                for info in mods {
+                    write_str(&info.id, &mut out);
+                    write_str(&info.version, &mut out);
+                }
+            }
+            LegacyMessage::RegistryData(registry) => {
+                out.extend_from_slice(&[3, registry.has_more as u8]);
+                write_str(&registry.name, &mut out);
+                let _ = write_var_int(registry.ids.len() as i32, &mut out);
+                for (name, id) in &registry.ids {
+                    write_str(name, &mut out);
+                    let _ = write_var_int(*id, &mut out);
+                }
+                write_strings(&registry.substitutions, &mut out);
+                write_strings(&registry.dummies, &mut out);
+            }
+            LegacyMessage::Ack(phase) => out.extend_from_slice(&[0xFF, *phase]),
+            LegacyMessage::Reset => out.push(0xFE)
+        }
+        out
+    }
+}
+
+/// A message on `fml:handshake`, 1.13 and later.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModernMessage{
+    /// `data_pack_registries` is sent since 1.18.
+    ModList{ mods: Vec<String>, channels: Vec<(String, String)>, registries: Vec<String>, data_pack_registries: Option<Vec<String>> },
+    /// The registries map to the marker of their snapshot.
+    ModListReply{ mods: Vec<String>, channels: Vec<(String, String)>, registries: Vec<(String, String)> },
+    /// The snapshot is kept in Forge's encoding.
+    Registry{ name: String, snapshot: Option<Vec<u8>> },
+    ConfigData{ file_name: String, data: Vec<u8> },
+    Acknowledge,
+    /// Messages of later Forge versions, like the mod data of 1.18.
+    Other{ index: i32, data: Vec<u8> }
+}
+
+impl ModernMessage{
+    pub fn decode(mut data: &[u8]) -> std::io::Result<Self> {
+        let buffer = &mut data;
+        Ok(match read_var_int(buffer)? {
+            1 => {
+                let mods = read_strings(buffer)?;
+                let channels = read_pairs(buffer)?;
+                let registries = read_strings(buffer)?;
+                let data_pack_registries = if buffer.is_empty() { None } else { Some(read_strings(buffer)?) };
+                ModernMessage::ModList{ mods, channels, registries, data_pack_registries }
+            }
+            2 => ModernMessage::ModListReply{ mods: read_strings(buffer)?, channels: read_pairs(buffer)?, registries: read_pairs(buffer)? },
+            3 => {
+                let name = read_str(buffer)?.to_string();
+                let snapshot = if read_u8(buffer)? != 0 { Some(buffer.to_vec()) } else { None };
+                ModernMessage::Registry{ name, snapshot }
+            }
+            4 => {
+                let file_name = read_str(buffer)?.to_string();
+                let len = read_count(buffer)?;
+                ModernMessage::ConfigData{ file_name, data: take(buffer, len)?.to_vec() }
+            }
+            99 => ModernMessage::Acknowledge,
+            index => ModernMessage::Other{ index, data: buffer.to_vec() }
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            ModernMessage::ModList{ mods, channels, registries, data_pack_registries } => {
+                let _ = write_var_int(1, &mut out);
+                write_strings(mods, &mut out);
+                write_pairs(channels, &mut out);
+                write_strings(registries, &mut out);
+                if let Some(data_pack_registries) = data_pack_registries {
+                    write_strings(data_pack_registries, &mut out);
+                }
+            }
+            ModernMessage::ModListReply{ mods, channels, registries } => {
+                let _ = write_var_int(2, &mut out);
+                write_strings(mods, &mut out);
+                write_pairs(channels, &mut out);
+                write_pairs(registries, &mut out);
+            }
+            ModernMessage::Registry{ name, snapshot } => {
+                let _ = write_var_int(3, &mut out);
+                write_str(name, &mut out);
+                out.push(snapshot.is_some() as u8);
+                if let Some(snapshot) = snapshot {
+                    out.extend_from_slice(snapshot);
+                }
+            }
+            ModernMessage::ConfigData{ file_name, data } => {
+                let _ = write_var_int(4, &mut out);
+                write_str(file_name, &mut out);
+                let _ = write_var_int(data.len() as i32, &mut out);
+                out.extend_from_slice(data);
+            }
+            ModernMessage::Acknowledge => {
+                let _ = write_var_int(99, &mut out);
+            }
+            ModernMessage::Other{ index, data } => {
+                let _ = write_var_int(*index, &mut out);
+                out.extend_from_slice(data);
+            }
+        }
+        out
+    }
+
+    /// The data of a LoginPluginRequest or LoginPluginResponse on `fml:loginwrapper`.
+    pub fn wrap(&self) -> Vec<u8> {
+        wrap(HANDSHAKE_CHANNEL, &self.encode())
+    }
+
+    /// Reads a message wrapped for `fml:handshake`.
+    pub fn unwrap(data: &[u8]) -> std::io::Result<Self> {
+        match unwrap(data)? {
+            (HANDSHAKE_CHANNEL, message) => ModernMessage::decode(message),
+            (channel, _) => Err(Error::new(ErrorKind::InvalidData, format!("unexpected wrapped channel {}", channel)))
+        }
+    }
+}
+
+/// Wraps a message for `fml:loginwrapper`.
+pub fn wrap(channel: &str, message: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(channel.len() + message.len() + 6);
+    write_str(channel, &mut out);
+    let _ = write_var_int(message.len() as i32, &mut out);
+    out.extend_from_slice(message);
+    out
+}
+
+/// The channel and message wrapped for `fml:loginwrapper`.
+pub fn unwrap(mut data: &[u8]) -> std::io::Result<(&str, &[u8])> {
+    let buffer = &mut data;
+    let channel = read_str(buffer)?;
+    let len = read_count(buffer)?;
+    Ok((channel, take(buffer, len)?))
+}
+
+/// The server side of the 1.12.2 handshake for a single client, run after
+/// LoginSuccess. Every message goes out on `FML|HS`, apart from the
+/// `REGISTER` sent first.
+#[derive(Debug, Clone)]
+pub struct LegacyServerHandshake{
+    mods: Vec<ModInfo>,
+    registries: Vec<RegistryData>,
+    requirements: ModRequirements,
+    phase: ServerPhase,
+    client_mods: Vec<ModInfo>
+}
+
+impl LegacyServerHandshake{
+    pub fn new(mods: Vec<ModInfo>, requirements: ModRequirements) -> Self {
+        LegacyServerHandshake{ mods, registries: Vec::new(), requirements, phase: ServerPhase::Start, client_mods: Vec::new() }
+    }
+
+    /// The registries to send. Clients wait for at least one.
+    pub fn with_registries(mut self, registries: Vec<RegistryData>) -> Self {
+        self.registries = registries;
+        self
+    }
+
+    pub fn phase(&self) -> ServerPhase {
+        self.phase
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.phase == ServerPhase::Done
+    }
+
+    /// The mods the client sent.
+    pub fn client_mods(&self) -> &[ModInfo] {
+        &self.client_mods
+    }
+
+    /// The plugin messages opening the handshake, as channel and data.
+    pub fn start(&mut self) -> Vec<(&'static str, Vec<u8>)> {
+        self.phase = ServerPhase::Hello;
+        vec![
+            ("REGISTER", b"FML|HS\0FML\0FML|MP\0FML\0FORGE".to_vec()),
+            (LEGACY_CHANNEL, LegacyMessage::ServerHello{ protocol: LEGACY_PROTOCOL, dimension: Some(0) }.encode())
+        ]
+    }
+
+    /// Handles a serverbound plugin message, returning the messages to send
+    /// on `FML|HS`. Messages on other channels are ignored. Clients with
+    /// mods not meeting the requirements fail with `PermissionDenied`.
+    pub fn handle(&mut self, channel: &str, data: &[u8]) -> std::io::Result<Vec<Vec<u8>>> {
+        if channel != LEGACY_CHANNEL {
+            return Ok(Vec::new());
+        }
+        let message = LegacyMessage::decode(data)?;
+        let ack = |phase: ClientPhase| LegacyMessage::Ack(phase as u8);
+        Ok(match (self.phase, message) {
+            (ServerPhase::Hello, LegacyMessage::ClientHello{ .. }) => Vec::new(),
+            (ServerPhase::Hello, LegacyMessage::ModList(mods)) => {
+                if let Err(mismatch) = self.requirements.check(&mods) {
+                    self.phase = ServerPhase::Error;
+                    return Err(Error::new(ErrorKind::PermissionDenied, mismatch.description()));
+                }
+                self.client_mods = mods;
+                self.phase = ServerPhase::WaitingCAck;
+                vec![LegacyMessage::ModList(self.mods.clone()).encode()]
+            }
+            (ServerPhase::WaitingCAck, message) if message == ack(ClientPhase::WaitingServerData) => {
+                let last = self.registries.len().saturating_sub(1);
+                self.registries.iter().enumerate()
+                    .map(|(index, registry)| LegacyMessage::RegistryData(RegistryData{ has_more: index != last, ..registry.clone() }).encode())
+                    .collect()
+            }
+            (ServerPhase::WaitingCAck, message) if message == ack(ClientPhase::WaitingServerComplete) => {
+                self.phase = ServerPhase::Complete;
+                vec![LegacyMessage::Ack(ServerPhase::WaitingCAck as u8).encode()]
+            }
+            (ServerPhase::Complete, message) if message == ack(ClientPhase::PendingComplete) => {
+                self.phase = ServerPhase::Done;
+                vec![LegacyMessage::Ack(ServerPhase::Complete as u8).encode()]
+            }
+            (ServerPhase::Done, LegacyMessage::Ack(_)) => Vec::new(),
+            (phase, message) => {
+                self.phase = ServerPhase::Error;
+                return Err(Error::new(ErrorKind::InvalidData, format!("unexpected FML handshake message {:?} in phase {:?}", message, phase)));
+            }
+        })
+    }
+}
+
+/// The server side of the 1.13+ handshake for a single client, run between
+/// LoginStart and LoginSuccess.
+#[derive(Debug, Clone)]
+pub struct ModernServerHandshake{
+    mods: Vec<String>,
+    channels: Vec<(String, String)>,
+    registries: Vec<(String, Option<Vec<u8>>)>,
+    configs: Vec<(String, Vec<u8>)>,
+    requirements: ModRequirements,
+    mod_list_id: Option<i32>,
+    pending: BTreeSet<i32>,
+    client_mods: Vec<ModInfo>
+}
+
+impl ModernServerHandshake{
+    /// `channels` are the play channels of the mods with their versions.
+    pub fn new(mods: Vec<String>, channels: Vec<(String, String)>, requirements: ModRequirements) -> Self {
+        ModernServerHandshake{
+            mods,
+            channels,
+            registries: Vec::new(),
+            configs: Vec::new(),
+            requirements,
+            mod_list_id: None,
+            pending: BTreeSet::new(),
+            client_mods: Vec::new()
+        }
+    }
+
+    pub fn with_registry(mut self, name: impl Into<String>, snapshot: Option<Vec<u8>>) -> Self {
+        self.registries.push((name.into(), snapshot));
+        self
+    }
+
+    pub fn with_config(mut self, file_name: impl Into<String>, data: Vec<u8>) -> Self {
+        self.configs.push((file_name.into(), data));
+        self
+    }
+
+    /// The LoginPluginRequests to send on `fml:loginwrapper`, as message id
+    /// and data, numbered from `first_message_id`.
+    pub fn requests(&mut self, first_message_id: i32) -> Vec<(i32, Vec<u8>)> {
+        let mut messages = vec![ModernMessage::ModList{
+            mods: self.mods.clone(),
+            channels: self.channels.clone(),
+            registries: self.registries.iter().map(|(name, _)| name.clone()).collect(),
+            data_pack_registries: None
+        }];
+        messages.extend(self.registries.iter().map(|(name, snapshot)| ModernMessage::Registry{ name: name.clone(), snapshot: snapshot.clone() }));
+        messages.extend(self.configs.iter().map(|(file_name, data)| ModernMessage::ConfigData{ file_name: file_name.clone(), data: data.clone() }));
+        self.mod_list_id = Some(first_message_id);
+        messages.into_iter().enumerate()
+            .map(|(index, message)| {
+                let message_id = first_message_id.wrapping_add(index as i32);
+                self.pending.insert(message_id);
+                (message_id, message.wrap())
+            })
+            .collect()
+    }
+
+    /// Handles a LoginPluginResponse, returning `Ok(false)` if it answers
+    /// another request. Vanilla clients fail with `PermissionDenied`, as do
+    /// clients with mods not meeting the requirements.
+    pub fn response(&mut self, message_id: i32, successful: bool, data: &[u8]) -> std::io::Result<bool> {
+        if !self.pending.remove(&message_id) {
+            return Ok(false);
+        }
+        if !successful {
+            return Err(Error::new(ErrorKind::PermissionDenied, "the client does not run Forge"));
+        }
+        match (Some(message_id) == self.mod_list_id, ModernMessage::unwrap(data)?) {
+            (true, ModernMessage::ModListReply{ mods, .. }) => {
+                let mods: Vec<ModInfo> = mods.into_iter().map(|id| ModInfo::new(id, "")).collect();
+                self.requirements.check(&mods)
+                    .map_err(|mismatch| Error::new(ErrorKind::PermissionDenied, mismatch.description()))?;
+                self.client_mods = mods;
+            }
+            (false, ModernMessage::Acknowledge) => {}
+            (_, message) => return Err(Error::new(ErrorKind::InvalidData, format!("unexpected FML handshake reply {:?}", message)))
+        }
+        Ok(true)
+    }
+
+    /// Whether every request was answered, so LoginSuccess can follow.
+    pub fn is_done(&self) -> bool {
+        self.mod_list_id.is_some() && self.pending.is_empty()
+    }
+
+    /// The mods the client sent, without versions.
+    pub fn client_mods(&self) -> &[ModInfo] {
+        &self.client_mods
+    }
+}
+
+fn take<'a>(buffer: &mut &'a [u8], len: usize) -> std::io::Result<&'a [u8]> {
+    if buffer.len() < len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "FML handshake message is truncated"));
+    }
+    let (bytes, rest) = buffer.split_at(len);
+    *buffer = rest;
+    Ok(bytes)
+}
+
+fn read_u8(buffer: &mut &[u8]) -> std::io::Result<u8> {
+    Ok(take(buffer, 1)?[0])
+}
+
+fn read_i32(buffer: &mut &[u8]) -> std::io::Result<i32> {
+    let bytes = take(buffer, 4)?;
+    Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_count(buffer: &mut &[u8]) -> std::io::Result<usize> {
+    let count = read_var_int(buffer)?;
+    // Every entry takes at least a byte.
+    if count < 0 || count as usize > buffer.len() {
+        return Err(Error::new(ErrorKind::InvalidData, format!("invalid count {} in FML handshake message", count)));
+    }
+    Ok(count as usize)
+}
+
+fn read_strings(buffer: &mut &[u8]) -> std::io::Result<Vec<String>> {
+    (0..read_count(buffer)?).map(|_| read_str(buffer).map(str::to_string)).collect()
+}
+
+fn read_pairs(buffer: &mut &[u8]) -> std::io::Result<Vec<(String, String)>> {
+    (0..read_count(buffer)?).map(|_| Ok((read_str(buffer)?.to_string(), read_str(buffer)?.to_string()))).collect()
+}
+
+fn write_str(value: &str, out: &mut Vec<u8>) {
+    let _ = write_var_int(value.len() as i32, out);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn write_strings(values: &[String], out: &mut Vec<u8>) {
+    let _ = write_var_int(values.len() as i32, out);
+    for value in values {
+        write_str(value, out);
+    }
+}
+
+fn write_pairs(pairs: &[(String, String)], out: &mut Vec<u8>) {
+    let _ = write_var_int(pairs.len() as i32, out);
+    for (key, value) in pairs {
+        write_str(key, out);
+        write_str(value, out);
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::{ModernServerHandshake, LOGIN_WRAPPER};
+    use crate::protocol::implementation::steven::v1_17::{LoginPluginRequest, LoginPluginResponse};
+    use steven_protocol::protocol::VarInt;
+
+    impl ModernServerHandshake{
+        pub fn request_packets(&mut self, first_message_id: i32) -> Vec<LoginPluginRequest> {
+            self.requests(first_message_id).into_iter()
+                .map(|(message_id, data)| LoginPluginRequest{ message_id: VarInt(message_id), channel: LOGIN_WRAPPER.to_string(), data })
+                .collect()
+        }
+
+        pub fn handle(&mut self, response: &LoginPluginResponse) -> std::io::Result<bool> {
+            self.response(response.message_id.0, response.successful, &response.data)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What a 1.12.2 Forge client sends on `FML|HS`, in order: its hello, its
+    /// mods and the acknowledgements of the phases it reaches.
+    const CLIENT_HELLO: &[u8] = b"\x01\x02";
+    const CLIENT_MOD_LIST: &[u8] = b"\x02\x04\x09minecraft\x061.12.2\x03mcp\x049.42\x03FML\x098.0.99.99\x05forge\x0c14.23.5.2860";
+    const ACK_WAITING_SERVER_DATA: &[u8] = b"\xff\x02";
+    const ACK_WAITING_SERVER_COMPLETE: &[u8] = b"\xff\x03";
+    const ACK_PENDING_COMPLETE: &[u8] = b"\xff\x04";
+
+    const SERVER_HELLO: &[u8] = b"\x00\x02\x00\x00\x00\x00";
+    const SERVER_MOD_LIST: &[u8] = b"\x02\x02\x09minecraft\x061.12.2\x05forge\x0c14.23.5.2860";
+    const SERVER_REGISTRY: &[u8] = b"\x03\x00\x10minecraft:blocks\x02\x0dminecraft:air\x00\x0fminecraft:stone\x01\x00\x00";
+
+    /// The 1.16.5 handshake on `fml:loginwrapper`: the requests of a server
+    /// with one mod and one registry, and the replies of a client.
+    const MOD_LIST_REQUEST: &[u8] = b"\x0dfml:handshake\x30\x01\x01\x05forge\x01\x12forge:tier_sorting\x031.0\x01\x0eminecraft:item";
+    const REGISTRY_REQUEST: &[u8] = b"\x0dfml:handshake\x11\x03\x0eminecraft:item\x00";
+    const MOD_LIST_REPLY: &[u8] = b"\x0dfml:handshake\x3b\x02\x02\x09minecraft\x05forge\x01\x12forge:tier_sorting\x031.0\x01\x0eminecraft:item\x00";
+    const ACKNOWLEDGE: &[u8] = b"\x0dfml:handshake\x01\x63";
+
+    fn legacy_server() -> LegacyServerHandshake {
+        let mods = vec![ModInfo::new("minecraft", "1.12.2"), ModInfo::new("forge", "14.23.5.2860")];
+        let mut requirements = ModRequirements::new();
+        requirements.require("forge", None);
+        LegacyServerHandshake::new(mods, requirements).with_registries(vec![RegistryData{
+            has_more: true,
+            name: "minecraft:blocks".to_string(),
+            ids: vec![("minecraft:air".to_string(), 0), ("minecraft:stone".to_string(), 1)],
+            ..Default::default()
+        }])
+    }
+
+    #[test]
+    fn markers() {
+        assert_eq!(ForgeMarker::strip("localhost\0FML\0"), ("localhost".to_string(), Some(ForgeMarker::Fml)));
+        assert_eq!(ForgeMarker::strip("localhost\0FML2\0"), ("localhost".to_string(), Some(ForgeMarker::Fml2)));
+        assert_eq!(ForgeMarker::strip("localhost\0FML3\0"), ("localhost".to_string(), Some(ForgeMarker::Fml3)));
+        assert_eq!(ForgeMarker::strip("localhost"), ("localhost".to_string(), None));
+        assert_eq!(ForgeMarker::Fml2.append("localhost"), "localhost\0FML2\0");
+    }
+
+    #[test]
+    fn legacy_messages_round_trip() {
+        for data in [CLIENT_HELLO, CLIENT_MOD_LIST, ACK_WAITING_SERVER_DATA, SERVER_HELLO, SERVER_MOD_LIST, SERVER_REGISTRY] {
+            assert_eq!(LegacyMessage::decode(data).unwrap().encode(), data);
+        }
+        assert_eq!(LegacyMessage::decode(CLIENT_MOD_LIST).unwrap(), LegacyMessage::ModList(vec![
+            ModInfo::new("minecraft", "1.12.2"),
+            ModInfo::new("mcp", "9.42"),
+            ModInfo::new("FML", "8.0.99.99"),
+            ModInfo::new("forge", "14.23.5.2860")
+        ]));
+    }
+
+    #[test]
+    fn legacy_handshake() {
+        let mut server = legacy_server();
+        let start = server.start();
+        assert_eq!(start[1], (LEGACY_CHANNEL, SERVER_HELLO.to_vec()));
+        assert!(server.handle(LEGACY_CHANNEL, CLIENT_HELLO).unwrap().is_empty());
+        assert_eq!(server.handle(LEGACY_CHANNEL, CLIENT_MOD_LIST).unwrap(), vec![SERVER_MOD_LIST.to_vec()]);
+        assert_eq!(server.client_mods().len(), 4);
+        assert_eq!(server.handle(LEGACY_CHANNEL, ACK_WAITING_SERVER_DATA).unwrap(), vec![SERVER_REGISTRY.to_vec()]);
+        assert_eq!(server.handle(LEGACY_CHANNEL, ACK_WAITING_SERVER_COMPLETE).unwrap(), vec![b"\xff\x02".to_vec()]);
+        assert_eq!(server.handle(LEGACY_CHANNEL, ACK_PENDING_COMPLETE).unwrap(), vec![b"\xff\x03".to_vec()]);
+        assert!(server.is_done());
+    }
+
+    #[test]
+    fn legacy_handshake_refuses_missing_mods() {
+        let mut server = legacy_server();
+        server.start();
+        let vanilla_mods = b"\x02\x01\x09minecraft\x061.12.2";
+        assert_eq!(server.handle(LEGACY_CHANNEL, vanilla_mods).unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(server.phase(), ServerPhase::Error);
+    }
+
+    #[test]
+    fn modern_handshake() {
+        let mut server = ModernServerHandshake::new(
+            vec!["forge".to_string()],
+            vec![("forge:tier_sorting".to_string(), "1.0".to_string())],
+            ModRequirements::new()
+        ).with_registry("minecraft:item", None);
+        assert_eq!(server.requests(0), vec![(0, MOD_LIST_REQUEST.to_vec()), (1, REGISTRY_REQUEST.to_vec())]);
+        assert!(server.response(0, true, MOD_LIST_REPLY).unwrap());
+        assert!(!server.is_done());
+        assert!(server.response(1, true, ACKNOWLEDGE).unwrap());
+        assert!(server.is_done());
+        let mods: Vec<&str> = server.client_mods().iter().map(|info| info.id.as_str()).collect();
+        assert_eq!(mods, ["minecraft", "forge"]);
+    }
+
+    #[test]
+    fn modern_handshake_refuses_vanilla_clients() {
+        let mut server = ModernServerHandshake::new(Vec::new(), Vec::new(), ModRequirements::new());
+        server.requests(7);
+        assert!(!server.response(6, true, ACKNOWLEDGE).unwrap());
+        assert_eq!(server.response(7, false, &[]).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn truncated_messages_are_errors() {
+        // A prefix may happen to be a shorter message, but must never panic.
+        for data in [MOD_LIST_REPLY, CLIENT_MOD_LIST] {
+            for len in 1..data.len() - 1 {
+                let _ = ModernMessage::unwrap(&data[..len]);
+                let _ = LegacyMessage::decode(&data[..len]);
+            }
+        }
+        assert!(ModernMessage::unwrap(&MOD_LIST_REPLY[..MOD_LIST_REPLY.len() - 1]).is_err());
+        assert!(LegacyMessage::decode(&CLIENT_MOD_LIST[..CLIENT_MOD_LIST.len() - 1]).is_err());
+    }
+}
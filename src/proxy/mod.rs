@@ -0,0 +1,5 @@
+pub mod plugin_channel;
+pub mod bungeecord;
+pub mod forge;
+#[cfg(feature = "velocity")]
+pub mod velocity;
@@ -0,0 +1,141 @@
+//! Per-channel policies for plugin messages passing through a proxy.
+use std::collections::HashMap;
+
+/// Rewrites a plugin message. Returning `None` drops it.
+pub type Rewrite = Box<dyn Fn(&str, Vec<u8>) -> Option<(String, Vec<u8>)> + Send + Sync>;
+
+pub enum ChannelPolicy{
+    Allow,
+    Block,
+    /// Allow messages with a payload of at most this many bytes.
+    SizeCap(usize),
+    Rewrite(Rewrite)
+}
+
+impl std::fmt::Debug for ChannelPolicy{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelPolicy::Allow => f.write_str("Allow"),
+            ChannelPolicy::Block => f.write_str("Block"),
+            ChannelPolicy::SizeCap(cap) => f.debug_tuple("SizeCap").field(cap).finish(),
+            ChannelPolicy::Rewrite(_) => f.write_str("Rewrite")
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelCounters{
+    pub forwarded: u64,
+    pub forwarded_bytes: u64,
+    pub dropped: u64,
+    pub dropped_bytes: u64
+}
+
+impl ChannelCounters{
+    fn count(&mut self, forwarded: bool, bytes: usize) {
+        if forwarded {
+            self.forwarded += 1;
+            self.forwarded_bytes += bytes as u64;
+        }else{
+            self.dropped += 1;
+            self.dropped_bytes += bytes as u64;
+        }
+    }
+}
+
+/// Decides per channel whether a plugin message is forwarded.
+///
+/// Channels without a policy of their own fall back to `default_policy`, and
+/// share a single set of counters so clients can not grow the counter map by
+/// making up channel names.
+#[derive(Debug)]
+pub struct PluginChannelFilter{
+    policies: HashMap<String, ChannelPolicy>,
+    default_policy: ChannelPolicy,
+    counters: HashMap<String, ChannelCounters>,
+    unlisted: ChannelCounters
+}
+
+impl PluginChannelFilter{
+    /// A filter that only forwards channels which were explicitly allowed.
+    pub fn whitelist() -> Self {
+        Self::with_default(ChannelPolicy::Block)
+    }
+
+    pub fn with_default(default_policy: ChannelPolicy) -> Self {
+        PluginChannelFilter{
+            policies: HashMap::new(),
+            default_policy,
+            counters: HashMap::new(),
+            unlisted: ChannelCounters::default()
+        }
+    }
+
+    pub fn set_policy<S: Into<String>>(&mut self, channel: S, policy: ChannelPolicy) -> &mut Self {
+        self.policies.insert(channel.into(), policy);
+        self
+    }
+
+    /// Applies the policy of the channel. Returns the message to forward, which
+    /// may have been rewritten, or `None` if it has to be dropped.
+    pub fn filter(&mut self, channel: &str, data: Vec<u8>) -> Option<(String, Vec<u8>)> {
+        let len = data.len();
+        let (policy, counters) = match self.policies.get(channel) {
+            Some(policy) => (policy, self.counters.entry(channel.to_string()).or_default()),
+            None => (&self.default_policy, &mut self.unlisted)
+        };
+        let result = match policy {
+            ChannelPolicy::Allow => Some((channel.to_string(), data)),
+            ChannelPolicy::Block => None,
+            ChannelPolicy::SizeCap(cap) if len <= *cap => Some((channel.to_string(), data)),
+            ChannelPolicy::SizeCap(_) => None,
+            ChannelPolicy::Rewrite(rewrite) => rewrite(channel, data)
+        };
+        counters.count(result.is_some(), len);
+        result
+    }
+
+    /// Counters of a channel with its own policy.
+    pub fn counters(&self, channel: &str) -> Option<&ChannelCounters> {
+        self.counters.get(channel)
+    }
+
+    /// Counters of all channels that fell back to the default policy.
+    pub fn unlisted_counters(&self) -> &ChannelCounters {
+        &self.unlisted
+    }
+
+    pub fn all_counters(&self) -> impl Iterator<Item = (&str, &ChannelCounters)> {
+        self.counters.iter().map(|(channel, counters)| (channel.as_str(), counters))
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::PluginChannelFilter;
+    use crate::protocol::implementation::steven::v1_17::Proto_1_17;
+
+    impl PluginChannelFilter{
+        /// Filters plugin messages in either direction and lets every other packet
+        /// through. Returns `None` if the packet has to be dropped.
+        pub fn filter_packet(&mut self, packet: Proto_1_17) -> Option<Proto_1_17> {
+            match packet {
+                Proto_1_17::PluginMessageServerbound(mut message) => {
+                    let data = std::mem::take(&mut message.data);
+                    let (channel, data) = self.filter(&message.channel, data)?;
+                    message.channel = channel;
+                    message.data = data;
+                    Some(Proto_1_17::PluginMessageServerbound(message))
+                }
+                Proto_1_17::PluginMessageClientbound(mut message) => {
+                    let data = std::mem::take(&mut message.data);
+                    let (channel, data) = self.filter(&message.channel, data)?;
+                    message.channel = channel;
+                    message.data = data;
+                    Some(Proto_1_17::PluginMessageClientbound(message))
+                }
+                packet => Some(packet)
+            }
+        }
+    }
+}
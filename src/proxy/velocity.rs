@@ -0,0 +1,322 @@
+//! Velocity modern forwarding.
+//!
+//! During login the backend server sends a LoginPluginRequest on the
+//! `velocity:player_info` channel, optionally carrying the highest forwarding
+//! version it understands as a single byte. Velocity answers with a
+//! LoginPluginResponse holding an HMAC-SHA256 signature of the payload, keyed
+//! with the forwarding secret, followed by the payload:
+//!
+//! `[VarInt version][String address][UUID][String username][VarInt count]`
+//! and per profile property `[String name][String value][bool signed][String signature if signed]`.
+//!
+//! Versions above `MODERN_DEFAULT` append the chat signing key of the player,
+//! which is kept as `ForwardedPlayer::extra`.
+//!
+//! `VelocityLogin` handles the exchange of a single login. With the
+//! `ServerListener`, wrap the handler in `VelocityForwarding` to have every
+//! login forwarded.
+use crate::io::borrowed::{read_str, read_var_int};
+use crate::proxy::bungeecord::ProfileProperty;
+use crate::segment::implementation::var::write_var_int;
+use hmac::{Hmac, Mac};
+use std::io::{Error, ErrorKind};
+use std::net::IpAddr;
+
+pub const CHANNEL: &str = "velocity:player_info";
+
+pub const MODERN_DEFAULT: u8 = 1;
+pub const MODERN_WITH_KEY: u8 = 2;
+pub const MODERN_WITH_KEY_V2: u8 = 3;
+pub const MODERN_LAZY_SESSION: u8 = 4;
+
+const SIGNATURE_LEN: usize = 32;
+
+type HmacSha256 = Hmac<sha2::Sha256>;
+
+/// The player information Velocity forwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardedPlayer{
+    pub version: i32,
+    pub address: IpAddr,
+    pub uuid: u128,
+    pub username: String,
+    pub properties: Vec<ProfileProperty>,
+    /// Data of newer forwarding versions following the properties, unparsed.
+    pub extra: Vec<u8>
+}
+
+impl ForwardedPlayer{
+    /// Checks the signature of a response and parses its payload. A wrong
+    /// signature results in `PermissionDenied`.
+    pub fn verify(secret: &[u8], data: &[u8]) -> std::io::Result<Self> {
+        if data.len() < SIGNATURE_LEN {
+            return Err(Error::new(ErrorKind::InvalidData, "forwarding data is too short"));
+        }
+        let (signature, payload) = data.split_at(SIGNATURE_LEN);
+        let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        mac.update(payload);
+        mac.verify_slice(signature)
+            .map_err(|_| Error::new(ErrorKind::PermissionDenied, "forwarding data has an invalid signature, do the secrets match?"))?;
+        Self::decode(payload)
+    }
+
+    /// Parses an unsigned payload.
+    pub fn decode(mut payload: &[u8]) -> std::io::Result<Self> {
+        let buffer = &mut payload;
+        let version = read_var_int(buffer)?;
+        let address = read_str(buffer)?.parse()
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "forwarded address is invalid"))?;
+        let uuid = u128::from_be_bytes(take::<16>(buffer)?);
+        let username = read_str(buffer)?.to_string();
+        let count = read_var_int(buffer)?;
+        if count < 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "negative property count"));
+        }
+        let mut properties = Vec::new();
+        for _ in 0..count {
+            let name = read_str(buffer)?.to_string();
+            let value = read_str(buffer)?.to_string();
+            let signature = match take::<1>(buffer)?[0] {
+                0 => None,
+                _ => Some(read_str(buffer)?.to_string())
+            };
+            properties.push(ProfileProperty{ name, value, signature });
+        }
+        Ok(ForwardedPlayer{ version, address, uuid, username, properties, extra: buffer.to_vec() })
+    }
+
+    /// The unsigned payload, as written by the proxy.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        let _ = write_var_int(self.version, &mut payload);
+        write_str(&self.address.to_string(), &mut payload);
+        payload.extend_from_slice(&self.uuid.to_be_bytes());
+        write_str(&self.username, &mut payload);
+        let _ = write_var_int(self.properties.len() as i32, &mut payload);
+        for property in &self.properties {
+            write_str(&property.name, &mut payload);
+            write_str(&property.value, &mut payload);
+            match &property.signature {
+                Some(signature) => {
+                    payload.push(1);
+                    write_str(signature, &mut payload);
+                }
+                None => payload.push(0)
+            }
+        }
+        payload.extend_from_slice(&self.extra);
+        payload
+    }
+
+    /// The signed response data, as sent by the proxy.
+    pub fn sign(&self, secret: &[u8]) -> Vec<u8> {
+        let payload = self.encode();
+        let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        mac.update(&payload);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.extend_from_slice(&payload);
+        data
+    }
+}
+
+/// The backend side of modern forwarding for a single login.
+#[derive(Debug, Clone)]
+pub struct VelocityLogin{
+    secret: Vec<u8>,
+    message_id: i32,
+    max_version: u8
+}
+
+impl VelocityLogin{
+    /// `message_id` has to be unique among the plugin requests of the login.
+    pub fn new(secret: impl Into<Vec<u8>>, message_id: i32) -> Self {
+        VelocityLogin{ secret: secret.into(), message_id, max_version: MODERN_DEFAULT }
+    }
+
+    /// Asks for a newer forwarding version. Velocity falls back to an older
+    /// one when the client does not support it.
+    pub fn with_max_version(mut self, max_version: u8) -> Self {
+        self.max_version = max_version;
+        self
+    }
+
+    pub fn message_id(&self) -> i32 {
+        self.message_id
+    }
+
+    /// The channel and data of the LoginPluginRequest to send after LoginStart.
+    pub fn request(&self) -> (&'static str, Vec<u8>) {
+        (CHANNEL, vec![self.max_version])
+    }
+
+    /// Handles the LoginPluginResponse, returning `Ok(None)` if it answers another request.
+    /// A forwarding version newer than the one asked for is `InvalidData`.
+    pub fn response(&self, message_id: i32, successful: bool, data: &[u8]) -> std::io::Result<Option<ForwardedPlayer>> {
+        if message_id != self.message_id {
+            return Ok(None);
+        }
+        if !successful {
+            return Err(Error::new(ErrorKind::PermissionDenied, "the connection was not forwarded, is the proxy using modern forwarding?"));
+        }
+        let player = ForwardedPlayer::verify(&self.secret, data)?;
+        if player.version > i32::from(self.max_version) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("forwarding version {} is newer than the requested {}", player.version, self.max_version)));
+        }
+        Ok(Some(player))
+    }
+}
+
+fn take<const N: usize>(buffer: &mut &[u8]) -> std::io::Result<[u8; N]> {
+    if buffer.len() < N {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "forwarding data is truncated"));
+    }
+    let mut bytes = [0; N];
+    bytes.copy_from_slice(&buffer[..N]);
+    *buffer = &buffer[N..];
+    Ok(bytes)
+}
+
+fn write_str(value: &str, out: &mut Vec<u8>) {
+    let _ = write_var_int(value.len() as i32, out);
+    out.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(feature = "steven_shared")]
+pub use self::v1_17::VelocityForwarding;
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::{ForwardedPlayer, VelocityLogin};
+    use crate::chat::Component;
+    use crate::protocol::implementation::steven::v1_17::{Handshake, LoginPluginRequest, LoginPluginResponse};
+    use crate::server::listener::{PluginResponse, Profile, ServerHandler};
+    use std::net::SocketAddr;
+    use steven_protocol::protocol::VarInt;
+
+    impl VelocityLogin{
+        pub fn request_packet(&self) -> LoginPluginRequest {
+            let (channel, data) = self.request();
+            LoginPluginRequest{ message_id: VarInt(self.message_id), channel: channel.to_string(), data }
+        }
+
+        pub fn handle(&self, response: &LoginPluginResponse) -> std::io::Result<Option<ForwardedPlayer>> {
+            self.response(response.message_id.0, response.successful, &response.data)
+        }
+    }
+
+    /// A `ServerHandler` for backend servers behind Velocity. Every login has
+    /// to be forwarded; `inner` decides whether the forwarded player may join,
+    /// with the address of the client rather than that of the proxy, and the
+    /// forwarded profile is the one the player joins with.
+    #[derive(Debug)]
+    pub struct VelocityForwarding<H>{
+        login: VelocityLogin,
+        inner: H
+    }
+
+    impl<H> VelocityForwarding<H>{
+        pub fn new(login: VelocityLogin, inner: H) -> Self {
+            VelocityForwarding{ login, inner }
+        }
+    }
+
+    impl<H: ServerHandler> ServerHandler for VelocityForwarding<H>{
+        fn status(&self, handshake: &Handshake, address: SocketAddr) -> String {
+            self.inner.status(handshake, address)
+        }
+
+        fn login(&self, _handshake: &Handshake, _username: &str, _address: SocketAddr) -> Result<Profile, String> {
+            Err(Component::text("This server is only reachable through a Velocity proxy").to_json())
+        }
+
+        fn login_plugin_requests(&self, handshake: &Handshake, username: &str) -> Vec<(i32, String, Vec<u8>)> {
+            let (channel, data) = self.login.request();
+            let mut requests = vec![(self.login.message_id(), channel.to_string(), data)];
+            requests.extend(self.inner.login_plugin_requests(handshake, username));
+            requests
+        }
+
+        fn login_with_plugins(&self, handshake: &Handshake, username: &str, address: SocketAddr, responses: &[PluginResponse]) -> Result<Profile, String> {
+            let response = responses.iter().find(|response| response.message_id == self.login.message_id());
+            let forwarded = match response {
+                Some(PluginResponse{ message_id, data }) => self.login.response(*message_id, data.is_some(), data.as_deref().unwrap_or_default()),
+                None => return self.login(handshake, username, address)
+            };
+            let player = match forwarded {
+                Ok(Some(player)) => player,
+                Ok(None) => return self.login(handshake, username, address),
+                Err(e) => return Err(Component::text(e.to_string()).to_json())
+            };
+            let address = SocketAddr::new(player.address, address.port());
+            self.inner.login_with_plugins(handshake, &player.username, address, responses)?;
+            Ok(Profile{ uuid: player.uuid, username: player.username, properties: player.properties })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(version: i32) -> ForwardedPlayer {
+        ForwardedPlayer{
+            version,
+            address: "203.0.113.7".parse().unwrap(),
+            uuid: 0x069a79f4_44e9_4726_a5be_fca90e38aaf5,
+            username: "Notch".to_string(),
+            properties: vec![ProfileProperty{ name: "textures".to_string(), value: "e30=".to_string(), signature: Some("c2ln".to_string()) }],
+            extra: Vec::new()
+        }
+    }
+
+    /// The response of `player(1)` with the secret `secret`: the HMAC-SHA256 of
+    /// the payload, computed with an independent implementation, followed by
+    /// the payload.
+    const SIGNATURE: [u8; 32] = [
+        0xb0, 0x58, 0x78, 0x66, 0x70, 0x46, 0xbe, 0x98, 0xa9, 0x52, 0x45, 0x7a, 0x90, 0x99, 0x4d, 0x86,
+        0x37, 0x96, 0x73, 0x00, 0xf1, 0xe1, 0xc7, 0xd0, 0x17, 0x64, 0xde, 0x59, 0x8f, 0xa0, 0x08, 0x99
+    ];
+    const PAYLOAD: [u8; 56] = [
+        0x01, 0x0b, 0x32, 0x30, 0x33, 0x2e, 0x30, 0x2e, 0x31, 0x31, 0x33, 0x2e, 0x37, 0x06, 0x9a, 0x79,
+        0xf4, 0x44, 0xe9, 0x47, 0x26, 0xa5, 0xbe, 0xfc, 0xa9, 0x0e, 0x38, 0xaa, 0xf5, 0x05, 0x4e, 0x6f,
+        0x74, 0x63, 0x68, 0x01, 0x08, 0x74, 0x65, 0x78, 0x74, 0x75, 0x72, 0x65, 0x73, 0x04, 0x65, 0x33,
+        0x30, 0x3d, 0x01, 0x04, 0x63, 0x32, 0x6c, 0x6e
+    ];
+
+    #[test]
+    fn known_answer() {
+        let data = [&SIGNATURE[..], &PAYLOAD[..]].concat();
+        assert_eq!(player(1).encode(), PAYLOAD);
+        assert_eq!(player(1).sign(b"secret"), data);
+        assert_eq!(ForwardedPlayer::verify(b"secret", &data).unwrap(), player(1));
+        let mut tampered = data.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(ForwardedPlayer::verify(b"secret", &tampered).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn signed_players_round_trip() {
+        let login = VelocityLogin::new(b"secret".to_vec(), 7);
+        assert_eq!(login.request(), (CHANNEL, vec![MODERN_DEFAULT]));
+        let data = player(1).sign(b"secret");
+        assert_eq!(login.response(7, true, &data).unwrap(), Some(player(1)));
+        assert_eq!(login.response(8, true, &data).unwrap(), None);
+    }
+
+    #[test]
+    fn bad_responses_are_rejected() {
+        let login = VelocityLogin::new(b"secret".to_vec(), 7);
+        let data = player(1).sign(b"other secret");
+        assert_eq!(login.response(7, true, &data).unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(login.response(7, false, &[]).unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(login.response(7, true, &data[..16]).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn newer_versions_than_requested_are_rejected() {
+        let login = VelocityLogin::new(b"secret".to_vec(), 0).with_max_version(MODERN_WITH_KEY);
+        assert!(login.response(0, true, &player(2).sign(b"secret")).is_ok());
+        let error = login.response(0, true, &player(3).sign(b"secret")).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,228 @@
+//! The Source RCON protocol Minecraft servers use for remote administration.
+//!
+//! Every packet is `[i32 length][i32 request id][i32 type][payload]\0\0` with
+//! all integers in little endian, where the length counts everything after
+//! itself. Authentication failures are answered with a request id of -1.
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+pub const DEFAULT_PORT: u16 = 25575;
+
+pub const TYPE_RESPONSE: i32 = 0;
+pub const TYPE_COMMAND: i32 = 2;
+/// The auth response shares its type with commands, the direction tells them apart.
+pub const TYPE_AUTH_RESPONSE: i32 = 2;
+pub const TYPE_AUTH: i32 = 3;
+
+/// The largest payload a vanilla server accepts.
+pub const MAX_REQUEST_PAYLOAD: usize = 1446;
+/// Responses are split into packets with payloads of at most this many bytes.
+pub const MAX_RESPONSE_PAYLOAD: usize = 4096;
+
+/// Request id of the response to a failed authentication.
+const AUTH_FAILED: i32 = -1;
+/// Bytes of a packet besides the payload, excluding the length itself.
+const OVERHEAD: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RconPacket{
+    pub request_id: i32,
+    pub kind: i32,
+    pub payload: String
+}
+
+impl RconPacket{
+    pub fn new(request_id: i32, kind: i32, payload: impl Into<String>) -> Self {
+        RconPacket{ request_id, kind, payload: payload.into() }
+    }
+
+    /// Reads a packet, rejecting payloads longer than `max_payload` bytes.
+    pub fn read_from<R: Read>(reader: &mut R, max_payload: usize) -> std::io::Result<Self> {
+        let len = reader.read_i32::<LittleEndian>()?;
+        if len < OVERHEAD as i32 || len as usize > max_payload + OVERHEAD {
+            return Err(Error::new(ErrorKind::InvalidData, format!("invalid RCON packet length {}", len)));
+        }
+        let request_id = reader.read_i32::<LittleEndian>()?;
+        let kind = reader.read_i32::<LittleEndian>()?;
+        let mut payload = vec![0u8; len as usize - 8];
+        reader.read_exact(&mut payload)?;
+        if payload.pop() != Some(0) || payload.pop() != Some(0) {
+            return Err(Error::new(ErrorKind::InvalidData, "RCON payload is not terminated"));
+        }
+        let payload = String::from_utf8(payload).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(RconPacket{ request_id, kind, payload })
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let mut buffer = Vec::with_capacity(self.payload.len() + OVERHEAD + 4);
+        buffer.write_i32::<LittleEndian>((self.payload.len() + OVERHEAD) as i32)?;
+        buffer.write_i32::<LittleEndian>(self.request_id)?;
+        buffer.write_i32::<LittleEndian>(self.kind)?;
+        buffer.extend_from_slice(self.payload.as_bytes());
+        buffer.extend_from_slice(&[0, 0]);
+        writer.write_all(&buffer)?;
+        writer.flush()
+    }
+}
+
+/// Connection to the RCON port of a server.
+#[derive(Debug)]
+pub struct RconClient<S = TcpStream>{
+    stream: S,
+    next_id: i32
+}
+
+impl RconClient<TcpStream>{
+    pub fn connect<A: ToSocketAddrs>(address: A) -> std::io::Result<Self> {
+        Ok(Self::new(TcpStream::connect(address)?))
+    }
+}
+
+impl<S: Read + Write> RconClient<S>{
+    pub fn new(stream: S) -> Self {
+        RconClient{ stream, next_id: 1 }
+    }
+
+    /// Logs in, failing with `PermissionDenied` on a wrong password.
+    pub fn auth(&mut self, password: &str) -> std::io::Result<()> {
+        let id = self.send(TYPE_AUTH, password)?;
+        loop {
+            let response = RconPacket::read_from(&mut self.stream, MAX_RESPONSE_PAYLOAD)?;
+            // Some servers send an empty response ahead of the auth response.
+            if response.kind != TYPE_AUTH_RESPONSE {
+                continue;
+            }
+            return match response.request_id {
+                AUTH_FAILED => Err(Error::new(ErrorKind::PermissionDenied, "RCON authentication failed")),
+                response_id if response_id == id => Ok(()),
+                _ => Err(Error::new(ErrorKind::InvalidData, "RCON auth response for another request"))
+            };
+        }
+    }
+
+    /// Runs a command and returns its output.
+    ///
+    /// Long output is split over multiple packets, so the command is followed
+    /// by a request of an invalid type. The server answers requests in order,
+    /// so the answer to it marks the end of the output.
+    pub fn command(&mut self, command: &str) -> std::io::Result<String> {
+        if command.len() > MAX_REQUEST_PAYLOAD {
+            return Err(Error::new(ErrorKind::InvalidInput, "RCON command is too long"));
+        }
+        let id = self.send(TYPE_COMMAND, command)?;
+        let marker = self.send(TYPE_RESPONSE, "")?;
+        let mut output = String::new();
+        loop {
+            let response = RconPacket::read_from(&mut self.stream, MAX_RESPONSE_PAYLOAD)?;
+            match response.request_id {
+                AUTH_FAILED => return Err(Error::new(ErrorKind::PermissionDenied, "not authenticated")),
+                response_id if response_id == id => output.push_str(&response.payload),
+                response_id if response_id == marker => return Ok(output),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    fn send(&mut self, kind: i32, payload: &str) -> std::io::Result<i32> {
+        let id = self.next_id;
+        // Never hand out the id used for failed authentication.
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        RconPacket::new(id, kind, payload).write_to(&mut self.stream)?;
+        Ok(id)
+    }
+}
+
+/// Accepts RCON connections on behalf of a server.
+#[derive(Debug)]
+pub struct RconListener{
+    listener: TcpListener,
+    password: String
+}
+
+impl RconListener{
+    pub fn bind<A: ToSocketAddrs>(address: A, password: impl Into<String>) -> std::io::Result<Self> {
+        Ok(RconListener{
+            listener: TcpListener::bind(address)?,
+            password: password.into()
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn accept(&self) -> std::io::Result<RconSession<TcpStream>> {
+        let (stream, _) = self.listener.accept()?;
+        Ok(RconSession::new(stream, self.password.clone()))
+    }
+}
+
+/// The server side of a single RCON connection.
+#[derive(Debug)]
+pub struct RconSession<S>{
+    stream: S,
+    password: String,
+    authenticated: bool
+}
+
+impl<S: Read + Write> RconSession<S>{
+    pub fn new(stream: S, password: impl Into<String>) -> Self {
+        RconSession{ stream, password: password.into(), authenticated: false }
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    /// Answers requests until the client disconnects, passing every command
+    /// of an authenticated client to `handler` and sending back its output.
+    pub fn serve<F: FnMut(&str) -> String>(&mut self, mut handler: F) -> std::io::Result<()> {
+        loop {
+            let request = match RconPacket::read_from(&mut self.stream, MAX_REQUEST_PAYLOAD) {
+                Ok(request) => request,
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e)
+            };
+            match request.kind {
+                TYPE_AUTH => {
+                    self.authenticated = request.payload == self.password;
+                    let id = if self.authenticated { request.request_id } else { AUTH_FAILED };
+                    RconPacket::new(id, TYPE_AUTH_RESPONSE, "").write_to(&mut self.stream)?;
+                }
+                _ if !self.authenticated => {
+                    RconPacket::new(AUTH_FAILED, TYPE_RESPONSE, "").write_to(&mut self.stream)?;
+                }
+                TYPE_COMMAND => {
+                    let output = handler(&request.payload);
+                    self.respond(request.request_id, &output)?;
+                }
+                other => self.respond(request.request_id, &format!("Unknown request {:x}", other))?
+            }
+        }
+    }
+
+    /// Sends `output` split into packets, without splitting characters.
+    fn respond(&mut self, request_id: i32, output: &str) -> std::io::Result<()> {
+        let mut rest = output;
+        loop {
+            let mut end = std::cmp::min(rest.len(), MAX_RESPONSE_PAYLOAD);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            RconPacket::new(request_id, TYPE_RESPONSE, &rest[..end]).write_to(&mut self.stream)?;
+            rest = &rest[end..];
+            if rest.is_empty() {
+                return Ok(());
+            }
+        }
+    }
+}
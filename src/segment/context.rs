@@ -0,0 +1,133 @@
+//! Settings for the segments being decoded on the current thread.
+//!
+//! `Segment::read_from_stream` only gets a reader, so segments that depend on
+//! the protocol version, on limits or on registry data look them up here:
+//!
+//! ```ignore
+//! let max = context::current(|context| context.limits.max_string_len);
+//! ```
+//!
+//! A context is active while `DecodeContext::enter` runs, `decode_with` enters
+//! one for every packet. Outside of it segments see `DecodeContext::default()`.
+//! Writing consults the protocol version of the active context as well.
+use crate::protocol::trailing::{TrailingBytes, TrailingBytesPolicy};
+use crate::protocol::untrusted::DecodeLimits;
+use crate::protocol::Protocol;
+use crate::segment::implementation::var;
+use std::any::Any;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness{
+    /// Accept everything vanilla accepts.
+    #[default]
+    Lenient,
+    /// Also reject encodings vanilla tolerates, like overlong VarInts.
+    Strict
+}
+
+#[derive(Clone, Default)]
+pub struct DecodeContext{
+    /// `None` if not known, segments then use their newest layout.
+    pub protocol_version: Option<i32>,
+    pub strictness: Strictness,
+    pub limits: DecodeLimits,
+    /// What `Protocol::decode_body` does with bytes a packet left unread.
+    pub trailing_bytes: TrailingBytesPolicy,
+    /// Called with the protocol name and the bytes left unread under
+    /// `TrailingBytesPolicy::Warn`, along with installed `metrics`.
+    pub trailing_bytes_hook: Option<Arc<dyn Fn(&'static str, &TrailingBytes) + Send + Sync>>,
+    /// Registry data received earlier, like a `DimensionCodec` or `TagSet`.
+    pub registry: Option<Arc<dyn Any + Send + Sync>>
+}
+
+impl std::fmt::Debug for DecodeContext{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecodeContext")
+            .field("protocol_version", &self.protocol_version)
+            .field("strictness", &self.strictness)
+            .field("limits", &self.limits)
+            .field("trailing_bytes", &self.trailing_bytes)
+            .field("trailing_bytes_hook", &self.trailing_bytes_hook.is_some())
+            .field("registry", &self.registry.is_some())
+            .finish()
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<DecodeContext> = RefCell::new(DecodeContext::default());
+}
+
+impl DecodeContext{
+    pub fn new(protocol_version: i32) -> Self {
+        DecodeContext{ protocol_version: Some(protocol_version), ..DecodeContext::default() }
+    }
+
+    pub fn for_protocol<P: Protocol>() -> Self {
+        Self::new(P::PROTOCOL)
+    }
+
+    pub fn with_strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_trailing_bytes(mut self, trailing_bytes: TrailingBytesPolicy) -> Self {
+        self.trailing_bytes = trailing_bytes;
+        self
+    }
+
+    pub fn with_trailing_bytes_hook<F: Fn(&'static str, &TrailingBytes) + Send + Sync + 'static>(mut self, hook: F) -> Self {
+        self.trailing_bytes_hook = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn with_registry<T: Any + Send + Sync>(mut self, registry: Arc<T>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// The registry data, if it is of type `T`.
+    pub fn registry<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.registry.as_ref()?.downcast_ref()
+    }
+
+    /// Whether the protocol version is known to be at least `version`.
+    pub fn is_at_least(&self, version: i32) -> bool {
+        self.protocol_version.is_some_and(|protocol_version| protocol_version >= version)
+    }
+
+    /// Runs `f` with this context active on the current thread. Contexts can
+    /// be nested, the previous one is restored afterwards, also on panic.
+    pub fn enter<T>(self, f: impl FnOnce() -> T) -> T {
+        struct Restore(Option<DecodeContext>);
+
+        impl Drop for Restore{
+            fn drop(&mut self) {
+                if let Some(previous) = self.0.take() {
+                    CURRENT.with(|current| *current.borrow_mut() = previous);
+                }
+            }
+        }
+
+        let strict = self.strictness == Strictness::Strict;
+        let _restore = Restore(Some(CURRENT.with(|current| current.replace(self))));
+        var::with_canonical(strict, f)
+    }
+}
+
+/// The active context with another protocol version.
+pub fn versioned(protocol_version: i32) -> DecodeContext {
+    DecodeContext{ protocol_version: Some(protocol_version), ..current(DecodeContext::clone) }
+}
+
+/// Calls `f` with the active context.
+pub fn current<T>(f: impl FnOnce(&DecodeContext) -> T) -> T {
+    CURRENT.with(|current| f(&current.borrow()))
+}
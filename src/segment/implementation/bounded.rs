@@ -0,0 +1,120 @@
+//! Numbers that must be within a range, like a view distance of 2 to 32.
+//!
+//! `Bounded<T, MIN, MAX>` is sent as `T`. Reading or deserializing a value
+//! outside of `MIN..=MAX` fails, so corrupt packets are rejected before their
+//! values are used.
+use crate::io::{Error, ErrorKind};
+use crate::segment::{Discriminant, Segment};
+use alloc::format;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Unchecked<T>", bound(deserialize = "T: Discriminant + serde::Deserialize<'de>")))]
+pub struct Bounded<T, const MIN: i64, const MAX: i64>(T);
+
+/// A `Bounded` as deserialized, before `Bounded::new` checks its range.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Bounded")]
+struct Unchecked<T>(T);
+
+#[cfg(feature = "serde")]
+impl<T: Discriminant, const MIN: i64, const MAX: i64> core::convert::TryFrom<Unchecked<T>> for Bounded<T, MIN, MAX>{
+    type Error = alloc::string::String;
+
+    fn try_from(value: Unchecked<T>) -> Result<Self, Self::Error> {
+        let discriminant = value.0.to_discriminant();
+        Self::new(value.0).ok_or_else(|| out_of_bounds(discriminant, MIN, MAX))
+    }
+}
+
+fn out_of_bounds(value: i64, min: i64, max: i64) -> alloc::string::String {
+    format!("{} is outside of {}..={}", value, min, max)
+}
+
+impl<T: Discriminant, const MIN: i64, const MAX: i64> Bounded<T, MIN, MAX>{
+    /// Returns `None` if the value is out of range.
+    pub fn new(value: T) -> Option<Self> {
+        if (MIN..=MAX).contains(&value.to_discriminant()) {
+            Some(Bounded(value))
+        }else{
+            None
+        }
+    }
+
+    /// Clamps the value into range. `None` if the clamped value does not fit
+    /// `T`, which only happens for bounds beyond what `T` holds.
+    pub fn saturating(value: i64) -> Option<Self> {
+        T::from_discriminant(value.clamp(MIN, MAX)).map(Bounded)
+    }
+
+    pub fn get(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Discriminant, const MIN: i64, const MAX: i64> Default for Bounded<T, MIN, MAX>{
+    /// Zero, or the bound closest to it. If that does not fit `T`, no value
+    /// of `T` is within the bounds and `T::default()` is used.
+    fn default() -> Self {
+        Self::saturating(0).unwrap_or_else(|| Bounded(T::default()))
+    }
+}
+
+impl<T: Discriminant, const MIN: i64, const MAX: i64> Segment for Bounded<T, MIN, MAX>{
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()> {
+        let mut value = T::default();
+        value.read_from_stream(reader)?;
+        let discriminant = value.to_discriminant();
+        *self = Self::new(value).ok_or_else(|| Error::new(ErrorKind::InvalidData, out_of_bounds(discriminant, MIN, MAX)))?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        self.0.write_to_stream(writer)
+    }
+
+    fn serialized_len(&self) -> usize {
+        self.0.serialized_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type ViewDistance = Bounded<u8, 2, 32>;
+
+    #[test]
+    fn reading_checks_the_range() {
+        let mut distance = ViewDistance::default();
+        distance.read_from_stream(&mut &[12][..]).unwrap();
+        assert_eq!(*distance.get(), 12);
+        let error = distance.read_from_stream(&mut &[33][..]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn saturating_clamps() {
+        assert_eq!(ViewDistance::saturating(-5).map(Bounded::into_inner), Some(2));
+        assert_eq!(ViewDistance::saturating(1000).map(Bounded::into_inner), Some(32));
+        assert_eq!(*ViewDistance::default().get(), 2);
+        // Bounds beyond u8 do not panic.
+        assert!(Bounded::<u8, 300, 400>::saturating(0).is_none());
+        assert_eq!(*Bounded::<u8, 300, 400>::default().get(), 0);
+        assert_eq!(*Bounded::<i8, -1000, 1000>::default().get(), 0);
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "serde_json"))]
+    fn deserializing_checks_the_range() {
+        let distance: ViewDistance = serde_json::from_str("12").unwrap();
+        assert_eq!(*distance.get(), 12);
+        assert!(serde_json::from_str::<ViewDistance>("33").is_err());
+        assert_eq!(serde_json::to_string(&distance).unwrap(), "12");
+    }
+}
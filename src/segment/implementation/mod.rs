@@ -1,34 +1,57 @@
 use crate::segment::Segment;
-use std::ops::{DerefMut, Deref};
+use alloc::boxed::Box;
+use core::ops::{DerefMut, Deref};
 
+pub mod bounded;
 pub mod num;
+pub mod var;
+#[cfg(feature = "std")]
+pub mod nbt;
+#[cfg(feature = "std")]
 pub mod mojang;
+pub mod types;
 #[cfg(feature = "steven_protocol")]
-pub mod steven;
+pub(crate) mod steven;
 
 impl<T: Segment> Segment for Box<T>{
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()> {
         self.deref_mut().read_from_stream(reader)
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
         self.deref().write_to_stream(writer)
     }
+
+    fn serialized_len(&self) -> usize {
+        self.deref().serialized_len()
+    }
+
+    fn try_serialized_len(&self) -> crate::io::Result<usize> {
+        self.deref().try_serialized_len()
+    }
 }
 
 impl<T: Segment> Segment for Option<T>{
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()> {
         let mut t = Default::default();
         T::read_from_stream(&mut t, reader)?;
         *self = Some(t);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
         if let Some(inner) = self{
             inner.write_to_stream(writer)
         }else{
             Ok(())
         }
     }
+
+    fn serialized_len(&self) -> usize {
+        self.as_ref().map_or(0, Segment::serialized_len)
+    }
+
+    fn try_serialized_len(&self) -> crate::io::Result<usize> {
+        self.as_ref().map_or(Ok(0), Segment::try_serialized_len)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+//! The payload of the Advancements packet.
+//!
+//! `[bool reset][VarInt count][String id][Advancement]...` followed by the
+//! removed ids and `[VarInt count][String id][AdvancementProgress]...`.
+//!
+//! Modded servers are known to send advancements vanilla would never produce,
+//! such as icons with unusual NBT or unknown frame types. Those are kept as
+//! they were sent instead of being rejected, and every length is read lazily
+//! so a bogus count runs into the end of the packet instead of allocating.
+use super::{read_count, read_string, write_string};
+use crate::segment::implementation::mojang::slot::Slot;
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::Error;
+
+/// The display has a background texture, which only root advancements use.
+pub const FLAG_BACKGROUND: i32 = 0x01;
+pub const FLAG_SHOW_TOAST: i32 = 0x02;
+pub const FLAG_HIDDEN: i32 = 0x04;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdvancementDisplay{
+    /// JSON chat component.
+    pub title: String,
+    pub description: String,
+    pub icon: Slot,
+    /// 0 for a task, 1 for a challenge and 2 for a goal. Other values are kept as sent.
+    pub frame: i32,
+    /// Flags other than `FLAG_BACKGROUND`, which follows from `background`.
+    pub flags: i32,
+    pub background: Option<String>,
+    pub x: f32,
+    pub y: f32
+}
+
+impl Segment for AdvancementDisplay{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.title = read_string(reader)?;
+        self.description = read_string(reader)?;
+        self.icon.read_from_stream(reader)?;
+        self.frame = read_var_int(reader)?;
+        let flags = reader.read_i32::<BigEndian>()?;
+        self.flags = flags & !FLAG_BACKGROUND;
+        self.background = if flags & FLAG_BACKGROUND != 0 { Some(read_string(reader)?) }else{ None };
+        self.x = reader.read_f32::<BigEndian>()?;
+        self.y = reader.read_f32::<BigEndian>()?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_string(&self.title, writer)?;
+        write_string(&self.description, writer)?;
+        self.icon.write_to_stream(writer)?;
+        write_var_int(self.frame, writer)?;
+        let background = if self.background.is_some() { FLAG_BACKGROUND }else{ 0 };
+        writer.write_i32::<BigEndian>((self.flags & !FLAG_BACKGROUND) | background)?;
+        if let Some(background) = &self.background {
+            write_string(background, writer)?;
+        }
+        writer.write_f32::<BigEndian>(self.x)?;
+        writer.write_f32::<BigEndian>(self.y)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Advancement{
+    pub parent: Option<String>,
+    pub display: Option<AdvancementDisplay>,
+    pub criteria: Vec<String>,
+    /// Groups of criteria; the advancement is done when every group has at
+    /// least one achieved criterion.
+    pub requirements: Vec<Vec<String>>
+}
+
+impl Segment for Advancement{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.parent = if reader.read_u8()? != 0 { Some(read_string(reader)?) }else{ None };
+        self.display = if reader.read_u8()? != 0 {
+            let mut display = AdvancementDisplay::default();
+            display.read_from_stream(reader)?;
+            Some(display)
+        }else{
+            None
+        };
+        self.criteria = read_strings(reader)?;
+        let count = read_count(reader)?;
+        self.requirements.clear();
+        for _ in 0..count {
+            self.requirements.push(read_strings(reader)?);
+        }
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match &self.parent {
+            Some(parent) => {
+                writer.write_u8(1)?;
+                write_string(parent, writer)?;
+            }
+            None => writer.write_u8(0)?
+        }
+        match &self.display {
+            Some(display) => {
+                writer.write_u8(1)?;
+                display.write_to_stream(writer)?;
+            }
+            None => writer.write_u8(0)?
+        }
+        write_strings(&self.criteria, writer)?;
+        write_var_int(self.requirements.len() as i32, writer)?;
+        self.requirements.iter().try_for_each(|group| write_strings(group, writer))
+    }
+}
+
+/// Progress of a single advancement: per criterion the time it was achieved,
+/// in milliseconds since the epoch, or `None` if it was not.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdvancementProgress{
+    pub criteria: Vec<(String, Option<i64>)>
+}
+
+impl AdvancementProgress{
+    pub fn achieved(&self, criterion: &str) -> bool {
+        self.criteria.iter().any(|(name, achieved)| name == criterion && achieved.is_some())
+    }
+}
+
+impl Segment for AdvancementProgress{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        let count = read_count(reader)?;
+        self.criteria.clear();
+        for _ in 0..count {
+            let name = read_string(reader)?;
+            let achieved = if reader.read_u8()? != 0 { Some(reader.read_i64::<BigEndian>()?) }else{ None };
+            self.criteria.push((name, achieved));
+        }
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_var_int(self.criteria.len() as i32, writer)?;
+        for (name, achieved) in &self.criteria {
+            write_string(name, writer)?;
+            match achieved {
+                Some(date) => {
+                    writer.write_u8(1)?;
+                    writer.write_i64::<BigEndian>(*date)?;
+                }
+                None => writer.write_u8(0)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a VarInt prefixed list of `[String id][T]` entries. Errors name the
+/// entry that failed, which helps tracking down modded advancements.
+pub fn read_keyed<T: Segment, R: std::io::Read>(reader: &mut R) -> std::io::Result<Vec<(String, T)>> {
+    let count = read_count(reader)?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let id = read_string(reader)?;
+        let mut value = T::default();
+        value.read_from_stream(reader)
+            .map_err(|e| Error::new(e.kind(), format!("{}: {}", id, e)))?;
+        entries.push((id, value));
+    }
+    Ok(entries)
+}
+
+pub fn write_keyed<T: Segment, W: std::io::Write>(entries: &[(String, T)], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(entries.len() as i32, writer)?;
+    for (id, value) in entries {
+        write_string(id, writer)?;
+        value.write_to_stream(writer)?;
+    }
+    Ok(())
+}
+
+fn read_strings<R: std::io::Read>(reader: &mut R) -> std::io::Result<Vec<String>> {
+    let count = read_count(reader)?;
+    let mut strings = Vec::new();
+    for _ in 0..count {
+        strings.push(read_string(reader)?);
+    }
+    Ok(strings)
+}
+
+fn write_strings<W: std::io::Write>(strings: &[String], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(strings.len() as i32, writer)?;
+    strings.iter().try_for_each(|string| write_string(string, writer))
+}
@@ -0,0 +1,44 @@
+//! Block entities as ChunkData sends them since 1.18: `[u8 packed x and z]
+//! [i16 y][VarInt type][NBT]`, the position relative to the column.
+use crate::segment::implementation::nbt::NetworkNbt;
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkBlockEntity{
+    /// `x << 4 | z` within the column.
+    pub packed_xz: u8,
+    pub y: i16,
+    pub ty: i32,
+    /// Without a root name, as sent since 1.20.2.
+    pub data: NetworkNbt
+}
+
+impl ChunkBlockEntity{
+    pub fn x(&self) -> u8 {
+        self.packed_xz >> 4
+    }
+
+    pub fn z(&self) -> u8 {
+        self.packed_xz & 0x0f
+    }
+}
+
+impl Segment for ChunkBlockEntity{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.packed_xz = reader.read_u8()?;
+        self.y = reader.read_i16::<BigEndian>()?;
+        self.ty = read_var_int(reader)?;
+        self.data.read_from_stream(reader)
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_u8(self.packed_xz)?;
+        writer.write_i16::<BigEndian>(self.y)?;
+        write_var_int(self.ty, writer)?;
+        self.data.write_to_stream(writer)
+    }
+}
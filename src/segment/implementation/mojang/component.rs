@@ -0,0 +1,351 @@
+//! Item components, which replaced the item NBT of slots in 1.20.5.
+//!
+//! A slot lists the components it adds to the defaults of its item and the
+//! ones it removes: `[VarInt added][VarInt removed]`, then `[VarInt type]`
+//! with the data per added component and `[VarInt type]` per removed one.
+//! Component data is not length prefixed, so a slot holding a component that
+//! is not decoded here can not be read at all and fails with `InvalidData`.
+//!
+//! Component types are numbered by their position in `COMPONENT_TYPES`. 1.21
+//! inserted `jukebox_playable`, which moved the types after it up by one.
+use super::identifier::Identifier;
+use super::slot::Slot;
+use super::{read_count, read_string, read_var_list, write_string, write_var_list};
+use crate::segment::implementation::nbt::NetworkNbt;
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::cell::Cell;
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// The component types of 1.21 in the order of their ids, without the
+/// `minecraft` namespace.
+pub const COMPONENT_TYPES: [&str; 57] = [
+    "custom_data", "max_stack_size", "max_damage", "damage", "unbreakable", "custom_name", "item_name", "lore",
+    "rarity", "enchantments", "can_place_on", "can_break", "attribute_modifiers", "custom_model_data",
+    "hide_additional_tooltip", "hide_tooltip", "repair_cost", "creative_slot_lock", "enchantment_glint_override",
+    "intangible_projectile", "food", "fire_resistant", "tool", "stored_enchantments", "dyed_color", "map_color",
+    "map_id", "map_decorations", "map_post_processing", "charged_projectiles", "bundle_contents", "potion_contents",
+    "suspicious_stew_effects", "writable_book_content", "written_book_content", "trim", "debug_stick_state",
+    "entity_data", "bucket_entity_data", "block_entity_data", "instrument", "ominous_bottle_amplifier",
+    "jukebox_playable", "recipes", "lodestone_tracker", "firework_explosion", "fireworks", "profile",
+    "note_block_sound", "banner_patterns", "base_color", "pot_decorations", "container", "block_state", "bees",
+    "lock", "container_loot"
+];
+
+/// The first protocol version with `jukebox_playable`, 1.21.
+pub const JUKEBOX_PLAYABLE_PROTOCOL: i32 = 767;
+const JUKEBOX_PLAYABLE_ID: i32 = 42;
+
+/// Slots nested in components, like a shulker box in a bundle, are refused
+/// beyond this depth.
+pub const MAX_NESTING: usize = 16;
+
+thread_local! {
+    static NESTING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The name of a component type, like `custom_name`.
+pub fn component_name(protocol: i32, id: i32) -> Option<&'static str> {
+    let index = if protocol < JUKEBOX_PLAYABLE_PROTOCOL && id >= JUKEBOX_PLAYABLE_ID { id + 1 } else { id };
+    if index < 0 {
+        return None;
+    }
+    COMPONENT_TYPES.get(index as usize).copied()
+}
+
+/// The id of a component type, with or without namespace.
+pub fn component_id(protocol: i32, name: &str) -> Option<i32> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let index = COMPONENT_TYPES.iter().position(|candidate| *candidate == name)? as i32;
+    if protocol >= JUKEBOX_PLAYABLE_PROTOCOL || index < JUKEBOX_PLAYABLE_ID {
+        Some(index)
+    }else if index == JUKEBOX_PLAYABLE_ID {
+        None
+    }else{
+        Some(index - 1)
+    }
+}
+
+/// Enchantments and their levels, by their id in the enchantment registry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Enchantments{
+    pub levels: Vec<(i32, i32)>,
+    pub show_in_tooltip: bool
+}
+
+/// A component with its data. Text is sent as NBT, like `NetworkNbt`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DataComponent{
+    CustomData(NetworkNbt),
+    MaxStackSize(i32),
+    MaxDamage(i32),
+    Damage(i32),
+    Unbreakable{ show_in_tooltip: bool },
+    CustomName(NetworkNbt),
+    ItemName(NetworkNbt),
+    Lore(Vec<NetworkNbt>),
+    /// 0 common, 1 uncommon, 2 rare, 3 epic.
+    Rarity(i32),
+    Enchantments(Enchantments),
+    CustomModelData(i32),
+    HideAdditionalTooltip,
+    HideTooltip,
+    RepairCost(i32),
+    CreativeSlotLock,
+    EnchantmentGlintOverride(bool),
+    FireResistant,
+    StoredEnchantments(Enchantments),
+    DyedColor{ color: i32, show_in_tooltip: bool },
+    MapColor(i32),
+    MapId(i32),
+    MapDecorations(NetworkNbt),
+    MapPostProcessing(i32),
+    ChargedProjectiles(Vec<Slot>),
+    BundleContents(Vec<Slot>),
+    DebugStickState(NetworkNbt),
+    EntityData(NetworkNbt),
+    BucketEntityData(NetworkNbt),
+    BlockEntityData(NetworkNbt),
+    OminousBottleAmplifier(i32),
+    Recipes(NetworkNbt),
+    NoteBlockSound(Identifier),
+    /// A dye color id.
+    BaseColor(i32),
+    /// Item ids of the sherds, back, left, right and front.
+    PotDecorations(Vec<i32>),
+    Container(Vec<Slot>),
+    /// Block state properties and their values.
+    BlockState(Vec<(String, String)>),
+    Lock(NetworkNbt),
+    ContainerLoot(NetworkNbt)
+}
+
+impl DataComponent{
+    /// The component type, without namespace.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataComponent::CustomData(_) => "custom_data",
+            DataComponent::MaxStackSize(_) => "max_stack_size",
+            DataComponent::MaxDamage(_) => "max_damage",
+            DataComponent::Damage(_) => "damage",
+            DataComponent::Unbreakable{ .. } => "unbreakable",
+            DataComponent::CustomName(_) => "custom_name",
+            DataComponent::ItemName(_) => "item_name",
+            DataComponent::Lore(_) => "lore",
+            DataComponent::Rarity(_) => "rarity",
+            DataComponent::Enchantments(_) => "enchantments",
+            DataComponent::CustomModelData(_) => "custom_model_data",
+            DataComponent::HideAdditionalTooltip => "hide_additional_tooltip",
+            DataComponent::HideTooltip => "hide_tooltip",
+            DataComponent::RepairCost(_) => "repair_cost",
+            DataComponent::CreativeSlotLock => "creative_slot_lock",
+            DataComponent::EnchantmentGlintOverride(_) => "enchantment_glint_override",
+            DataComponent::FireResistant => "fire_resistant",
+            DataComponent::StoredEnchantments(_) => "stored_enchantments",
+            DataComponent::DyedColor{ .. } => "dyed_color",
+            DataComponent::MapColor(_) => "map_color",
+            DataComponent::MapId(_) => "map_id",
+            DataComponent::MapDecorations(_) => "map_decorations",
+            DataComponent::MapPostProcessing(_) => "map_post_processing",
+            DataComponent::ChargedProjectiles(_) => "charged_projectiles",
+            DataComponent::BundleContents(_) => "bundle_contents",
+            DataComponent::DebugStickState(_) => "debug_stick_state",
+            DataComponent::EntityData(_) => "entity_data",
+            DataComponent::BucketEntityData(_) => "bucket_entity_data",
+            DataComponent::BlockEntityData(_) => "block_entity_data",
+            DataComponent::OminousBottleAmplifier(_) => "ominous_bottle_amplifier",
+            DataComponent::Recipes(_) => "recipes",
+            DataComponent::NoteBlockSound(_) => "note_block_sound",
+            DataComponent::BaseColor(_) => "base_color",
+            DataComponent::PotDecorations(_) => "pot_decorations",
+            DataComponent::Container(_) => "container",
+            DataComponent::BlockState(_) => "block_state",
+            DataComponent::Lock(_) => "lock",
+            DataComponent::ContainerLoot(_) => "container_loot"
+        }
+    }
+
+    /// Reads the data of a component of the given type.
+    pub fn read<R: Read>(protocol: i32, id: i32, reader: &mut R) -> std::io::Result<Self> {
+        let name = component_name(protocol, id).ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("unknown item component type {}", id)))?;
+        Ok(match name {
+            "custom_data" => DataComponent::CustomData(read_segment(reader)?),
+            "max_stack_size" => DataComponent::MaxStackSize(read_var_int(reader)?),
+            "max_damage" => DataComponent::MaxDamage(read_var_int(reader)?),
+            "damage" => DataComponent::Damage(read_var_int(reader)?),
+            "unbreakable" => DataComponent::Unbreakable{ show_in_tooltip: reader.read_u8()? != 0 },
+            "custom_name" => DataComponent::CustomName(read_segment(reader)?),
+            "item_name" => DataComponent::ItemName(read_segment(reader)?),
+            "lore" => DataComponent::Lore(read_var_list(reader)?),
+            "rarity" => DataComponent::Rarity(read_var_int(reader)?),
+            "enchantments" => DataComponent::Enchantments(read_enchantments(reader)?),
+            "custom_model_data" => DataComponent::CustomModelData(read_var_int(reader)?),
+            "hide_additional_tooltip" => DataComponent::HideAdditionalTooltip,
+            "hide_tooltip" => DataComponent::HideTooltip,
+            "repair_cost" => DataComponent::RepairCost(read_var_int(reader)?),
+            "creative_slot_lock" => DataComponent::CreativeSlotLock,
+            "enchantment_glint_override" => DataComponent::EnchantmentGlintOverride(reader.read_u8()? != 0),
+            "fire_resistant" => DataComponent::FireResistant,
+            "stored_enchantments" => DataComponent::StoredEnchantments(read_enchantments(reader)?),
+            "dyed_color" => DataComponent::DyedColor{ color: reader.read_i32::<BigEndian>()?, show_in_tooltip: reader.read_u8()? != 0 },
+            "map_color" => DataComponent::MapColor(reader.read_i32::<BigEndian>()?),
+            "map_id" => DataComponent::MapId(read_var_int(reader)?),
+            "map_decorations" => DataComponent::MapDecorations(read_segment(reader)?),
+            "map_post_processing" => DataComponent::MapPostProcessing(read_var_int(reader)?),
+            "charged_projectiles" => DataComponent::ChargedProjectiles(read_slots(reader)?),
+            "bundle_contents" => DataComponent::BundleContents(read_slots(reader)?),
+            "debug_stick_state" => DataComponent::DebugStickState(read_segment(reader)?),
+            "entity_data" => DataComponent::EntityData(read_segment(reader)?),
+            "bucket_entity_data" => DataComponent::BucketEntityData(read_segment(reader)?),
+            "block_entity_data" => DataComponent::BlockEntityData(read_segment(reader)?),
+            "ominous_bottle_amplifier" => DataComponent::OminousBottleAmplifier(read_var_int(reader)?),
+            "recipes" => DataComponent::Recipes(read_segment(reader)?),
+            "note_block_sound" => DataComponent::NoteBlockSound(read_segment(reader)?),
+            "base_color" => DataComponent::BaseColor(read_var_int(reader)?),
+            "pot_decorations" => {
+                let count = read_count(reader)?;
+                DataComponent::PotDecorations((0..count).map(|_| read_var_int(reader)).collect::<std::io::Result<_>>()?)
+            }
+            "container" => DataComponent::Container(read_slots(reader)?),
+            "block_state" => {
+                let count = read_count(reader)?;
+                DataComponent::BlockState((0..count).map(|_| Ok((read_string(reader)?, read_string(reader)?))).collect::<std::io::Result<_>>()?)
+            }
+            "lock" => DataComponent::Lock(read_segment(reader)?),
+            "container_loot" => DataComponent::ContainerLoot(read_segment(reader)?),
+            _ => return Err(Error::new(ErrorKind::InvalidData, format!("item component minecraft:{} is not supported", name)))
+        })
+    }
+
+    /// Writes the data of the component, without its type.
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            DataComponent::CustomData(nbt) | DataComponent::CustomName(nbt) | DataComponent::ItemName(nbt)
+            | DataComponent::MapDecorations(nbt) | DataComponent::DebugStickState(nbt) | DataComponent::EntityData(nbt)
+            | DataComponent::BucketEntityData(nbt) | DataComponent::BlockEntityData(nbt) | DataComponent::Recipes(nbt)
+            | DataComponent::Lock(nbt) | DataComponent::ContainerLoot(nbt) => nbt.write_to_stream(writer),
+            DataComponent::MaxStackSize(value) | DataComponent::MaxDamage(value) | DataComponent::Damage(value)
+            | DataComponent::Rarity(value) | DataComponent::CustomModelData(value) | DataComponent::RepairCost(value)
+            | DataComponent::MapId(value) | DataComponent::MapPostProcessing(value) | DataComponent::OminousBottleAmplifier(value)
+            | DataComponent::BaseColor(value) => write_var_int(*value, writer),
+            DataComponent::Unbreakable{ show_in_tooltip } | DataComponent::EnchantmentGlintOverride(show_in_tooltip) => writer.write_u8(*show_in_tooltip as u8),
+            DataComponent::Lore(lines) => write_var_list(lines, writer),
+            DataComponent::Enchantments(enchantments) | DataComponent::StoredEnchantments(enchantments) => {
+                write_var_int(enchantments.levels.len() as i32, writer)?;
+                for (id, level) in &enchantments.levels {
+                    write_var_int(*id, writer)?;
+                    write_var_int(*level, writer)?;
+                }
+                writer.write_u8(enchantments.show_in_tooltip as u8)
+            }
+            DataComponent::HideAdditionalTooltip | DataComponent::HideTooltip | DataComponent::CreativeSlotLock | DataComponent::FireResistant => Ok(()),
+            DataComponent::DyedColor{ color, show_in_tooltip } => {
+                writer.write_i32::<BigEndian>(*color)?;
+                writer.write_u8(*show_in_tooltip as u8)
+            }
+            DataComponent::MapColor(color) => writer.write_i32::<BigEndian>(*color),
+            DataComponent::ChargedProjectiles(slots) | DataComponent::BundleContents(slots) | DataComponent::Container(slots) => write_var_list(slots, writer),
+            DataComponent::NoteBlockSound(sound) => sound.write_to_stream(writer),
+            DataComponent::PotDecorations(items) => {
+                write_var_int(items.len() as i32, writer)?;
+                items.iter().try_for_each(|&item| write_var_int(item, writer))
+            }
+            DataComponent::BlockState(properties) => {
+                write_var_int(properties.len() as i32, writer)?;
+                for (name, value) in properties {
+                    write_string(name, writer)?;
+                    write_string(value, writer)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The components a slot adds to and removes from the defaults of its item.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemComponents{
+    pub added: Vec<DataComponent>,
+    /// Names of the removed component types, without namespace.
+    pub removed: Vec<String>
+}
+
+impl ItemComponents{
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// The added component of the given type, e.g. `get("custom_name")`.
+    pub fn get(&self, name: &str) -> Option<&DataComponent> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        self.added.iter().find(|component| component.name() == name)
+    }
+
+    pub fn read<R: Read>(protocol: i32, reader: &mut R) -> std::io::Result<Self> {
+        let added = read_count(reader)?;
+        let removed = read_count(reader)?;
+        let added = (0..added).map(|_| {
+            let id = read_var_int(reader)?;
+            DataComponent::read(protocol, id, reader)
+        }).collect::<std::io::Result<_>>()?;
+        let removed = (0..removed).map(|_| {
+            let id = read_var_int(reader)?;
+            component_name(protocol, id).map(str::to_string).ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("unknown item component type {}", id)))
+        }).collect::<std::io::Result<_>>()?;
+        Ok(ItemComponents{ added, removed })
+    }
+
+    pub fn write<W: Write>(&self, protocol: i32, writer: &mut W) -> std::io::Result<()> {
+        write_var_int(self.added.len() as i32, writer)?;
+        write_var_int(self.removed.len() as i32, writer)?;
+        for component in &self.added {
+            write_var_int(type_id(protocol, component.name())?, writer)?;
+            component.write(writer)?;
+        }
+        for name in &self.removed {
+            write_var_int(type_id(protocol, name)?, writer)?;
+        }
+        Ok(())
+    }
+}
+
+fn type_id(protocol: i32, name: &str) -> std::io::Result<i32> {
+    component_id(protocol, name).ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("item component {} does not exist in protocol {}", name, protocol)))
+}
+
+fn read_segment<S: Segment, R: Read>(reader: &mut R) -> std::io::Result<S> {
+    let mut segment = S::default();
+    segment.read_from_stream(reader)?;
+    Ok(segment)
+}
+
+fn read_enchantments<R: Read>(reader: &mut R) -> std::io::Result<Enchantments> {
+    let count = read_count(reader)?;
+    let levels = (0..count).map(|_| Ok((read_var_int(reader)?, read_var_int(reader)?))).collect::<std::io::Result<_>>()?;
+    Ok(Enchantments{ levels, show_in_tooltip: reader.read_u8()? != 0 })
+}
+
+/// Reads slots held by a component, refusing to nest deeper than `MAX_NESTING`.
+fn read_slots<R: Read>(reader: &mut R) -> std::io::Result<Vec<Slot>> {
+    struct Leave;
+
+    impl Drop for Leave{
+        fn drop(&mut self) {
+            NESTING.with(|nesting| nesting.set(nesting.get() - 1));
+        }
+    }
+
+    let depth = NESTING.with(|nesting| {
+        nesting.set(nesting.get() + 1);
+        nesting.get()
+    });
+    let _leave = Leave;
+    if depth > MAX_NESTING {
+        return Err(Error::new(ErrorKind::InvalidData, format!("item components nest deeper than {}", MAX_NESTING)));
+    }
+    read_var_list(reader)
+}
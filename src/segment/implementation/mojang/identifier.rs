@@ -0,0 +1,85 @@
+//! Resource locations like `minecraft:brand`, a namespace and a path.
+//!
+//! Namespaces may contain `[a-z0-9_.-]`, paths also `/`. Without a
+//! namespace `minecraft` is implied. On the wire an identifier is a String.
+use super::{read_string, write_string};
+use crate::segment::Segment;
+use std::fmt::{Display, Formatter};
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+pub const DEFAULT_NAMESPACE: &str = "minecraft";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Identifier{
+    namespace: String,
+    path: String
+}
+
+impl Identifier{
+    pub fn new(namespace: &str, path: &str) -> std::io::Result<Self> {
+        if namespace.is_empty() || !namespace.bytes().all(|b| valid_char(b, false)) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("invalid namespace {:?}", namespace)));
+        }
+        if !path.bytes().all(|b| valid_char(b, true)) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("invalid path {:?}", path)));
+        }
+        Ok(Identifier{ namespace: namespace.to_string(), path: path.to_string() })
+    }
+
+    /// An identifier in the `minecraft` namespace.
+    pub fn minecraft(path: &str) -> std::io::Result<Self> {
+        Identifier::new(DEFAULT_NAMESPACE, path)
+    }
+
+    pub fn parse(identifier: &str) -> std::io::Result<Self> {
+        match identifier.split_once(':') {
+            Some((namespace, path)) => Identifier::new(namespace, path),
+            None => Identifier::minecraft(identifier)
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+fn valid_char(b: u8, path: bool) -> bool {
+    matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'.' | b'-') || (path && b == b'/')
+}
+
+impl Default for Identifier{
+    fn default() -> Self {
+        Identifier{ namespace: DEFAULT_NAMESPACE.to_string(), path: String::new() }
+    }
+}
+
+impl Display for Identifier{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
+
+impl FromStr for Identifier{
+    type Err = Error;
+
+    fn from_str(identifier: &str) -> std::io::Result<Self> {
+        Identifier::parse(identifier)
+    }
+}
+
+impl Segment for Identifier{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        *self = Identifier::parse(&read_string(reader)?)?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_string(&self.to_string(), writer)
+    }
+}
@@ -0,0 +1,268 @@
+//! Structures of the 1.7 protocol that later versions replaced.
+//!
+//! 1.7 prefixes many lists and byte arrays with fixed size integers instead of
+//! VarInts, sends item and block entity NBT gzip compressed, and writes slots
+//! as `[i16 item id, -1 if empty][i8 count][i16 damage][NBT]`.
+use crate::segment::implementation::mojang::{read_count, read_string, write_string};
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// A string whose length is limited to `MAX` UTF-16 code units, like Java's
+/// `String::length`, while the prefix counts UTF-8 bytes. Like vanilla the
+/// prefix may be up to four bytes per unit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JavaString<const MAX: usize>(pub String);
+
+/// The length of a string as Java counts it.
+pub fn java_len(value: &str) -> usize {
+    value.encode_utf16().count()
+}
+
+impl<const MAX: usize> JavaString<MAX>{
+    pub fn new(value: impl Into<String>) -> std::io::Result<Self> {
+        let value = value.into();
+        match java_len(&value) > MAX {
+            true => Err(too_long(java_len(&value), MAX)),
+            false => Ok(JavaString(value))
+        }
+    }
+}
+
+impl<const MAX: usize> Segment for JavaString<MAX>{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        let len = read_count(reader)?;
+        if len > MAX * 4 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("string of {} bytes exceeds the limit of {} characters", len, MAX)));
+        }
+        let mut value = String::new();
+        if reader.take(len as u64).read_to_string(&mut value)? != len {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "string ended prematurely"));
+        }
+        if java_len(&value) > MAX {
+            return Err(too_long(java_len(&value), MAX));
+        }
+        self.0 = value;
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        if java_len(&self.0) > MAX {
+            return Err(too_long(java_len(&self.0), MAX));
+        }
+        write_string(&self.0, writer)
+    }
+}
+
+fn too_long(len: usize, max: usize) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("string of {} characters exceeds the limit of {}", len, max))
+}
+
+/// Gzip compressed NBT prefixed with an `i16` length, -1 if absent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacyNbt(pub Option<Vec<u8>>);
+
+impl LegacyNbt{
+    /// Decompresses the root compound.
+    #[cfg(feature = "compression")]
+    pub fn decode(&self) -> std::io::Result<Option<crate::segment::implementation::nbt::Tag>> {
+        match &self.0 {
+            Some(data) => {
+                let mut decoder = flate2::read::GzDecoder::new(&data[..]);
+                Ok(crate::segment::implementation::nbt::Tag::read_named(&mut decoder)?.map(|(_, tag)| tag))
+            }
+            None => Ok(None)
+        }
+    }
+}
+
+impl Segment for LegacyNbt{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.0 = match reader.read_i16::<BigEndian>()? {
+            -1 => None,
+            len if len < 0 => return Err(Error::new(ErrorKind::InvalidData, "negative NBT length")),
+            len => {
+                let mut data = vec![0; len as usize];
+                reader.read_exact(&mut data)?;
+                Some(data)
+            }
+        };
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match &self.0 {
+            Some(data) => {
+                if data.len() > i16::MAX as usize {
+                    return Err(Error::new(ErrorKind::InvalidInput, "NBT does not fit an i16 length"));
+                }
+                writer.write_i16::<BigEndian>(data.len() as i16)?;
+                writer.write_all(data)
+            }
+            None => writer.write_i16::<BigEndian>(-1)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacyItemStack{
+    pub item_id: i16,
+    pub count: i8,
+    pub damage: i16,
+    pub nbt: LegacyNbt
+}
+
+/// A slot as 1.7 sends it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacySlot(pub Option<LegacyItemStack>);
+
+impl Segment for LegacySlot{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.0 = match reader.read_i16::<BigEndian>()? {
+            -1 => None,
+            item_id => {
+                let count = reader.read_i8()?;
+                let damage = reader.read_i16::<BigEndian>()?;
+                let mut nbt = LegacyNbt::default();
+                nbt.read_from_stream(reader)?;
+                Some(LegacyItemStack{ item_id, count, damage, nbt })
+            }
+        };
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match &self.0 {
+            Some(stack) => {
+                writer.write_i16::<BigEndian>(stack.item_id)?;
+                writer.write_i8(stack.count)?;
+                writer.write_i16::<BigEndian>(stack.damage)?;
+                stack.nbt.write_to_stream(writer)
+            }
+            None => writer.write_i16::<BigEndian>(-1)
+        }
+    }
+}
+
+/// A property of a player's profile, like its skin, in SpawnPlayer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacyProperty{
+    pub name: String,
+    pub value: String,
+    pub signature: String
+}
+
+impl Segment for LegacyProperty{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.name = read_string(reader)?;
+        self.value = read_string(reader)?;
+        self.signature = read_string(reader)?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_string(&self.name, writer)?;
+        write_string(&self.value, writer)?;
+        write_string(&self.signature, writer)
+    }
+}
+
+/// A statistic by name, like `stat.walkOneCm`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacyStatistic{
+    pub name: String,
+    pub value: i32
+}
+
+impl Segment for LegacyStatistic{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.name = read_string(reader)?;
+        self.value = read_var_int(reader)?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_string(&self.name, writer)?;
+        write_var_int(self.value, writer)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacyModifier{
+    pub uuid: u128,
+    pub amount: f64,
+    pub operation: i8
+}
+
+/// An entity attribute with an `i16` count of modifiers.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacyAttribute{
+    pub key: String,
+    pub value: f64,
+    pub modifiers: Vec<LegacyModifier>
+}
+
+impl Segment for LegacyAttribute{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.key = read_string(reader)?;
+        self.value = reader.read_f64::<BigEndian>()?;
+        let count = reader.read_i16::<BigEndian>()?;
+        if count < 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "negative element count"));
+        }
+        self.modifiers = (0..count).map(|_| Ok(LegacyModifier{
+            uuid: reader.read_u128::<BigEndian>()?,
+            amount: reader.read_f64::<BigEndian>()?,
+            operation: reader.read_i8()?
+        })).collect::<std::io::Result<_>>()?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_string(&self.key, writer)?;
+        writer.write_f64::<BigEndian>(self.value)?;
+        writer.write_i16::<BigEndian>(self.modifiers.len() as i16)?;
+        for modifier in &self.modifiers {
+            writer.write_u128::<BigEndian>(modifier.uuid)?;
+            writer.write_f64::<BigEndian>(modifier.amount)?;
+            writer.write_i8(modifier.operation)?;
+        }
+        Ok(())
+    }
+}
+
+/// The position and sections of a column in MapChunkBulk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkMeta{
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub primary_bitmask: u16,
+    pub add_bitmask: u16
+}
+
+impl Segment for ChunkMeta{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.chunk_x = reader.read_i32::<BigEndian>()?;
+        self.chunk_z = reader.read_i32::<BigEndian>()?;
+        self.primary_bitmask = reader.read_u16::<BigEndian>()?;
+        self.add_bitmask = reader.read_u16::<BigEndian>()?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_i32::<BigEndian>(self.chunk_x)?;
+        writer.write_i32::<BigEndian>(self.chunk_z)?;
+        writer.write_u16::<BigEndian>(self.primary_bitmask)?;
+        writer.write_u16::<BigEndian>(self.add_bitmask)
+    }
+}
@@ -0,0 +1,49 @@
+//! Icons of the Maps packet as sent since 1.20.3, where their names are NBT
+//! text components.
+use crate::segment::implementation::nbt::NetworkNbt;
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use crate::segment::Segment;
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// A marker on a map, like a player or a banner.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapIcon{
+    pub ty: i32,
+    /// From -128 for the left to 127 for the right edge of the map.
+    pub x: i8,
+    pub z: i8,
+    /// 0 to 15, in steps of 22.5 degrees clockwise from north.
+    pub direction: i8,
+    /// Sent after a bool saying whether there is one.
+    pub display_name: NetworkNbt
+}
+
+impl Segment for MapIcon{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.ty = read_var_int(reader)?;
+        self.x = reader.read_i8()?;
+        self.z = reader.read_i8()?;
+        self.direction = reader.read_i8()?;
+        self.display_name = NetworkNbt::default();
+        if reader.read_u8()? != 0 {
+            self.display_name.read_from_stream(reader)?;
+        }
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_var_int(self.ty, writer)?;
+        writer.write_i8(self.x)?;
+        writer.write_i8(self.z)?;
+        writer.write_i8(self.direction)?;
+        match &self.display_name.0 {
+            Some(_) => {
+                writer.write_u8(1)?;
+                self.display_name.write_to_stream(writer)
+            }
+            None => writer.write_u8(0)
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Crate-native segments for structures of the vanilla protocol.
+use crate::segment::context;
+use crate::segment::Segment;
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use std::io::{Error, ErrorKind, Read, Write};
+
+pub mod advancement;
+pub mod block_entity;
+pub mod component;
+pub mod identifier;
+pub mod legacy;
+pub mod map;
+pub mod profile_key;
+pub mod recipe;
+pub mod registry;
+pub mod slot;
+pub mod tags;
+
+pub(crate) fn read_string<R: Read>(reader: &mut R) -> std::io::Result<String> {
+    let len = read_var_int(reader)?;
+    if len < 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "negative string length"));
+    }
+    let max = context::current(|context| context.limits.max_string_len);
+    if len as usize > max {
+        return Err(Error::new(ErrorKind::InvalidData, format!("string of {} bytes exceeds the limit of {}", len, max)));
+    }
+    let mut value = String::new();
+    let read = reader.take(len as u64).read_to_string(&mut value)?;
+    if read != len as usize {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "string ended prematurely"));
+    }
+    Ok(value)
+}
+
+pub(crate) fn write_string<W: Write>(value: &str, writer: &mut W) -> std::io::Result<()> {
+    write_var_int(value.len() as i32, writer)?;
+    writer.write_all(value.as_bytes())
+}
+
+/// Reads a VarInt element count, refusing negative ones.
+pub(crate) fn read_count<R: Read>(reader: &mut R) -> std::io::Result<usize> {
+    let count = read_var_int(reader)?;
+    if count < 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "negative element count"));
+    }
+    Ok(count as usize)
+}
+
+/// Reads `count` segments, for lists whose count is sent apart from them.
+pub fn read_segments<S: Segment, R: Read>(count: usize, reader: &mut R) -> std::io::Result<Vec<S>> {
+    (0..count).map(|_| {
+        let mut segment = S::default();
+        segment.read_from_stream(reader)?;
+        Ok(segment)
+    }).collect()
+}
+
+pub fn write_segments<S: Segment, W: Write>(segments: &[S], writer: &mut W) -> std::io::Result<()> {
+    segments.iter().try_for_each(|segment| segment.write_to_stream(writer))
+}
+
+/// Reads a list prefixed with a VarInt count.
+pub fn read_var_list<S: Segment, R: Read>(reader: &mut R) -> std::io::Result<Vec<S>> {
+    let count = read_count(reader)?;
+    read_segments(count, reader)
+}
+
+pub fn write_var_list<S: Segment, W: Write>(segments: &[S], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(segments.len() as i32, writer)?;
+    write_segments(segments, writer)
+}
@@ -0,0 +1,234 @@
+//! The key a player signs chat with since 1.19, certified by Mojang.
+//!
+//! On the wire `[i64 expiry][VarInt length][key][VarInt length][signature]`,
+//! sent in LoginStart by 1.19 up to 1.19.2 and in PlayerSession since 1.19.3.
+//!
+//! With the `chat-signing` feature the certificate can be checked against
+//! Mojang's keys, served as JSON at `https://api.minecraftservices.com/publickeys`.
+//! Like the session server, fetching them is left to the application through
+//! a `KeyFetcher`, `MojangKeyCache` keeps them around:
+//!
+//! ```ignore
+//! let cache = MojangKeyCache::new(|| http_get("https://api.minecraftservices.com/publickeys"));
+//! session.public_key.verify(&*cache.get()?, Some(player_uuid), now_millis)?;
+//! ```
+use super::read_count;
+use crate::segment::implementation::var::write_var_int;
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind, Read};
+
+/// Longest DER encoded key the vanilla server accepts.
+pub const MAX_KEY_LEN: usize = 512;
+
+/// Longest signature the vanilla server accepts.
+pub const MAX_SIGNATURE_LEN: usize = 4096;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfilePublicKey{
+    /// Milliseconds since the Unix epoch.
+    pub expires_at: i64,
+    /// The RSA public key in X.509 DER encoding.
+    pub key: Vec<u8>,
+    /// Mojang's signature of the key, see `signed_data`.
+    pub signature: Vec<u8>
+}
+
+impl ProfilePublicKey{
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expires_at
+    }
+
+    /// What Mojang signed. Since 1.19.1 this is `[UUID player][i64 expiry][key]`,
+    /// 1.19 signed the expiry and PEM encoded key as text, without the player.
+    pub fn signed_data(&self, player: Option<u128>) -> Vec<u8> {
+        match player {
+            Some(uuid) => {
+                let mut data = Vec::with_capacity(24 + self.key.len());
+                data.extend_from_slice(&uuid.to_be_bytes());
+                data.extend_from_slice(&self.expires_at.to_be_bytes());
+                data.extend_from_slice(&self.key);
+                data
+            }
+            None => format!("{}{}", self.expires_at, self.pem()).into_bytes()
+        }
+    }
+
+    /// The key in PEM, wrapped at 76 characters as Java's MIME encoder does.
+    pub fn pem(&self) -> String {
+        let encoded = crate::base64::encode(&self.key);
+        let lines: Vec<&str> = encoded.as_bytes().chunks(76)
+            .map(|line| std::str::from_utf8(line).unwrap())
+            .collect();
+        format!("-----BEGIN RSA PUBLIC KEY-----\n{}\n-----END RSA PUBLIC KEY-----\n", lines.join("\n"))
+    }
+}
+
+impl Segment for ProfilePublicKey{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.expires_at = reader.read_i64::<BigEndian>()?;
+        self.key = read_bytes(reader, MAX_KEY_LEN)?;
+        self.signature = read_bytes(reader, MAX_SIGNATURE_LEN)?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_i64::<BigEndian>(self.expires_at)?;
+        write_var_int(self.key.len() as i32, writer)?;
+        writer.write_all(&self.key)?;
+        write_var_int(self.signature.len() as i32, writer)?;
+        writer.write_all(&self.signature)
+    }
+}
+
+fn read_bytes<R: Read>(reader: &mut R, max: usize) -> std::io::Result<Vec<u8>> {
+    let len = read_count(reader)?;
+    if len > max {
+        return Err(Error::new(ErrorKind::InvalidData, format!("{} bytes exceed the limit of {}", len, max)));
+    }
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(feature = "chat-signing")]
+pub use self::verification::*;
+
+#[cfg(feature = "chat-signing")]
+mod verification{
+    use super::ProfilePublicKey;
+    use rsa::pkcs8::DecodePublicKey;
+    use rsa::{Pkcs1v15Sign, RsaPublicKey};
+    use sha1::{Digest, Sha1};
+    use std::io::{Error, ErrorKind};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    impl ProfilePublicKey{
+        pub fn rsa_key(&self) -> std::io::Result<RsaPublicKey> {
+            RsaPublicKey::from_public_key_der(&self.key)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("invalid profile public key: {}", e)))
+        }
+
+        /// Checks the key has not expired at `now`, in milliseconds since the
+        /// Unix epoch, and is signed by Mojang. `player` is the UUID of the
+        /// player since 1.19.1, and `None` for 1.19.
+        pub fn verify(&self, keys: &MojangKeys, player: Option<u128>, now: i64) -> std::io::Result<()> {
+            if self.is_expired(now) {
+                return Err(Error::new(ErrorKind::PermissionDenied, "expired profile public key"));
+            }
+            if !keys.verify_certificate(&self.signed_data(player), &self.signature) {
+                return Err(Error::new(ErrorKind::PermissionDenied, "profile public key is not signed by Mojang"));
+            }
+            self.rsa_key().map(|_| ())
+        }
+    }
+
+    /// The keys Mojang signs with.
+    #[derive(Debug, Clone, Default)]
+    pub struct MojangKeys{
+        /// Keys signing player certificates.
+        pub certificate_keys: Vec<RsaPublicKey>,
+        /// Keys signing profile properties, like skins.
+        pub property_keys: Vec<RsaPublicKey>
+    }
+
+    impl MojangKeys{
+        /// Reads the response of `https://api.minecraftservices.com/publickeys`,
+        /// `{"profilePropertyKeys":[{"publicKey":"<base64 DER>"}],"playerCertificateKeys":[...]}`.
+        pub fn from_json(json: &str) -> std::io::Result<Self> {
+            let value: serde_json::Value = serde_json::from_str(json)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("invalid public keys JSON: {}", e)))?;
+            let keys = |name: &str| -> std::io::Result<Vec<RsaPublicKey>> {
+                value.get(name).and_then(serde_json::Value::as_array)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("public keys JSON has no {}", name)))?
+                    .iter()
+                    .map(|key| {
+                        let encoded = key.get("publicKey").and_then(serde_json::Value::as_str)
+                            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("entry of {} has no publicKey", name)))?;
+                        let der = crate::base64::decode(encoded)?;
+                        RsaPublicKey::from_public_key_der(&der)
+                            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("invalid key in {}: {}", name, e)))
+                    })
+                    .collect()
+            };
+            Ok(MojangKeys{ certificate_keys: keys("playerCertificateKeys")?, property_keys: keys("profilePropertyKeys")? })
+        }
+
+        /// Whether any of the certificate keys signed `data` with SHA1withRSA.
+        pub fn verify_certificate(&self, data: &[u8], signature: &[u8]) -> bool {
+            verify_any(&self.certificate_keys, data, signature)
+        }
+
+        /// Whether the base64 `signature` of a profile property, like the
+        /// textures of the session server, is valid for its `value`.
+        pub fn verify_property(&self, value: &str, signature: &str) -> bool {
+            crate::base64::decode(signature)
+                .is_ok_and(|signature| verify_any(&self.property_keys, value.as_bytes(), &signature))
+        }
+    }
+
+    fn verify_any(keys: &[RsaPublicKey], data: &[u8], signature: &[u8]) -> bool {
+        let hashed = Sha1::digest(data);
+        keys.iter().any(|key| key.verify(Pkcs1v15Sign::new::<Sha1>(), &hashed, signature).is_ok())
+    }
+
+    /// Gets the JSON of Mojang's public keys.
+    pub trait KeyFetcher{
+        fn fetch(&self) -> std::io::Result<String>;
+    }
+
+    impl<F: Fn() -> std::io::Result<String>> KeyFetcher for F{
+        fn fetch(&self) -> std::io::Result<String> {
+            self()
+        }
+    }
+
+    /// Keeps the keys for `ttl`, a day by default. When fetching fails the
+    /// previous keys are used, as Mojang rotates them rarely.
+    pub struct MojangKeyCache<F>{
+        fetcher: F,
+        ttl: Duration,
+        cached: Mutex<Option<(Instant, Arc<MojangKeys>)>>
+    }
+
+    impl<F> std::fmt::Debug for MojangKeyCache<F>{
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("MojangKeyCache").field("ttl", &self.ttl).field("cached", &self.cached).finish()
+        }
+    }
+
+    impl<F: KeyFetcher> MojangKeyCache<F>{
+        pub fn new(fetcher: F) -> Self {
+            MojangKeyCache{ fetcher, ttl: Duration::from_secs(24 * 60 * 60), cached: Mutex::new(None) }
+        }
+
+        pub fn with_ttl(mut self, ttl: Duration) -> Self {
+            self.ttl = ttl;
+            self
+        }
+
+        pub fn get(&self) -> std::io::Result<Arc<MojangKeys>> {
+            let mut cached = self.cached.lock().unwrap();
+            if let Some((fetched, keys)) = &*cached {
+                if fetched.elapsed() < self.ttl {
+                    return Ok(keys.clone());
+                }
+            }
+            match self.fetcher.fetch().and_then(|json| MojangKeys::from_json(&json)) {
+                Ok(keys) => {
+                    let keys = Arc::new(keys);
+                    *cached = Some((Instant::now(), keys.clone()));
+                    Ok(keys)
+                }
+                Err(e) => cached.as_ref().map(|(_, keys)| keys.clone()).ok_or(e)
+            }
+        }
+
+        /// Drops the keys, so the next `get` fetches them.
+        pub fn invalidate(&self) {
+            *self.cached.lock().unwrap() = None;
+        }
+    }
+}
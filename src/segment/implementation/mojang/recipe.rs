@@ -0,0 +1,243 @@
+//! Recipes as sent in DeclareRecipes.
+//!
+//! Every recipe is `[String serializer][String id]` followed by the data of
+//! its serializer. Special recipes, like dyeing armor, are implemented by the
+//! client itself and carry no data.
+use super::{read_count, read_string, write_string};
+use crate::segment::implementation::mojang::slot::Slot;
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind};
+
+const SPECIAL_PREFIX: &str = "crafting_special_";
+
+/// Items any of which satisfy a recipe slot.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ingredient(pub Vec<Slot>);
+
+impl Segment for Ingredient{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        let count = read_count(reader)?;
+        self.0.clear();
+        for _ in 0..count {
+            self.0.push(read(reader)?);
+        }
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_var_int(self.0.len() as i32, writer)?;
+        self.0.iter().try_for_each(|slot| slot.write_to_stream(writer))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CookingMethod{
+    Smelting,
+    Blasting,
+    Smoking,
+    CampfireCooking
+}
+
+impl CookingMethod{
+    pub fn serializer(&self) -> &'static str {
+        match self {
+            CookingMethod::Smelting => "smelting",
+            CookingMethod::Blasting => "blasting",
+            CookingMethod::Smoking => "smoking",
+            CookingMethod::CampfireCooking => "campfire_cooking"
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecipeKind{
+    Shapeless{
+        group: String,
+        ingredients: Vec<Ingredient>,
+        result: Slot
+    },
+    /// `ingredients` holds `width * height` entries, row by row.
+    Shaped{
+        width: i32,
+        height: i32,
+        group: String,
+        ingredients: Vec<Ingredient>,
+        result: Slot
+    },
+    Cooking{
+        method: CookingMethod,
+        group: String,
+        ingredient: Ingredient,
+        result: Slot,
+        experience: f32,
+        /// Ticks.
+        cooking_time: i32
+    },
+    Stonecutting{
+        group: String,
+        ingredient: Ingredient,
+        result: Slot
+    },
+    Smithing{
+        base: Ingredient,
+        addition: Ingredient,
+        result: Slot
+    },
+    /// A recipe the client implements itself, with the serializer name
+    /// without namespace, like `crafting_special_armordye`.
+    Special(String)
+}
+
+impl Default for RecipeKind{
+    fn default() -> Self {
+        RecipeKind::Special(String::new())
+    }
+}
+
+impl RecipeKind{
+    /// The serializer name without namespace.
+    pub fn serializer(&self) -> &str {
+        match self {
+            RecipeKind::Shapeless{..} => "crafting_shapeless",
+            RecipeKind::Shaped{..} => "crafting_shaped",
+            RecipeKind::Cooking{ method, .. } => method.serializer(),
+            RecipeKind::Stonecutting{..} => "stonecutting",
+            RecipeKind::Smithing{..} => "smithing",
+            RecipeKind::Special(serializer) => serializer
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recipe{
+    pub id: String,
+    pub kind: RecipeKind
+}
+
+impl Segment for Recipe{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        let serializer = read_string(reader)?;
+        self.id = read_string(reader)?;
+        let name = serializer.strip_prefix("minecraft:").unwrap_or(&serializer);
+        self.kind = match name {
+            "crafting_shapeless" => {
+                let group = read_string(reader)?;
+                let count = read_count(reader)?;
+                let mut ingredients = Vec::new();
+                for _ in 0..count {
+                    ingredients.push(read(reader)?);
+                }
+                RecipeKind::Shapeless{ group, ingredients, result: read(reader)? }
+            }
+            "crafting_shaped" => {
+                let width = read_var_int(reader)?;
+                let height = read_var_int(reader)?;
+                if width < 1 || height < 1 {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("shaped recipe of {}x{} is invalid", width, height)));
+                }
+                let group = read_string(reader)?;
+                let mut ingredients = Vec::new();
+                for _ in 0..width as i64 * height as i64 {
+                    ingredients.push(read(reader)?);
+                }
+                RecipeKind::Shaped{ width, height, group, ingredients, result: read(reader)? }
+            }
+            "smelting" => read_cooking(CookingMethod::Smelting, reader)?,
+            "blasting" => read_cooking(CookingMethod::Blasting, reader)?,
+            "smoking" => read_cooking(CookingMethod::Smoking, reader)?,
+            "campfire_cooking" => read_cooking(CookingMethod::CampfireCooking, reader)?,
+            "stonecutting" => RecipeKind::Stonecutting{
+                group: read_string(reader)?,
+                ingredient: read(reader)?,
+                result: read(reader)?
+            },
+            "smithing" => RecipeKind::Smithing{
+                base: read(reader)?,
+                addition: read(reader)?,
+                result: read(reader)?
+            },
+            special if special.starts_with(SPECIAL_PREFIX) => RecipeKind::Special(special.to_string()),
+            unknown => return Err(Error::new(ErrorKind::InvalidData, format!("unknown recipe serializer {}", unknown)))
+        };
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_string(&format!("minecraft:{}", self.kind.serializer()), writer)?;
+        write_string(&self.id, writer)?;
+        match &self.kind {
+            RecipeKind::Shapeless{ group, ingredients, result } => {
+                write_string(group, writer)?;
+                write_var_int(ingredients.len() as i32, writer)?;
+                ingredients.iter().try_for_each(|ingredient| ingredient.write_to_stream(writer))?;
+                result.write_to_stream(writer)
+            }
+            RecipeKind::Shaped{ width, height, group, ingredients, result } => {
+                if ingredients.len() as i64 != *width as i64 * *height as i64 {
+                    return Err(Error::new(ErrorKind::InvalidInput, format!("shaped recipe of {}x{} has {} ingredients", width, height, ingredients.len())));
+                }
+                write_var_int(*width, writer)?;
+                write_var_int(*height, writer)?;
+                write_string(group, writer)?;
+                ingredients.iter().try_for_each(|ingredient| ingredient.write_to_stream(writer))?;
+                result.write_to_stream(writer)
+            }
+            RecipeKind::Cooking{ group, ingredient, result, experience, cooking_time, .. } => {
+                write_string(group, writer)?;
+                ingredient.write_to_stream(writer)?;
+                result.write_to_stream(writer)?;
+                writer.write_f32::<BigEndian>(*experience)?;
+                write_var_int(*cooking_time, writer)
+            }
+            RecipeKind::Stonecutting{ group, ingredient, result } => {
+                write_string(group, writer)?;
+                ingredient.write_to_stream(writer)?;
+                result.write_to_stream(writer)
+            }
+            RecipeKind::Smithing{ base, addition, result } => {
+                base.write_to_stream(writer)?;
+                addition.write_to_stream(writer)?;
+                result.write_to_stream(writer)
+            }
+            RecipeKind::Special(_) => Ok(())
+        }
+    }
+}
+
+/// Reads the recipes of DeclareRecipes, prefixed by their VarInt count.
+pub fn read_recipes<R: std::io::Read>(reader: &mut R) -> std::io::Result<Vec<Recipe>> {
+    let count = read_count(reader)?;
+    let mut recipes = Vec::new();
+    for _ in 0..count {
+        recipes.push(read(reader)?);
+    }
+    Ok(recipes)
+}
+
+pub fn write_recipes<W: std::io::Write>(recipes: &[Recipe], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(recipes.len() as i32, writer)?;
+    recipes.iter().try_for_each(|recipe| recipe.write_to_stream(writer))
+}
+
+fn read_cooking<R: std::io::Read>(method: CookingMethod, reader: &mut R) -> std::io::Result<RecipeKind> {
+    Ok(RecipeKind::Cooking{
+        method,
+        group: read_string(reader)?,
+        ingredient: read(reader)?,
+        result: read(reader)?,
+        experience: reader.read_f32::<BigEndian>()?,
+        cooking_time: read_var_int(reader)?
+    })
+}
+
+fn read<T: Segment, R: std::io::Read>(reader: &mut R) -> std::io::Result<T> {
+    let mut value = T::default();
+    value.read_from_stream(reader)?;
+    Ok(value)
+}
@@ -0,0 +1,51 @@
+//! Entries of the RegistryData packet sent in the Configuration state since
+//! 1.20.5, one packet per registry.
+//!
+//! An entry is `[Identifier id][bool has data][NBT data]`. Entries of data
+//! packs both sides know, see KnownPacks, are sent without their data.
+use crate::segment::implementation::mojang::identifier::Identifier;
+use crate::segment::implementation::nbt::{NetworkNbt, Tag};
+use crate::segment::Segment;
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// An entry of a synchronized registry, like a dimension type or a biome.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryDataEntry{
+    pub id: Identifier,
+    pub data: NetworkNbt
+}
+
+impl RegistryDataEntry{
+    pub fn new(id: Identifier, data: Option<Tag>) -> Self {
+        RegistryDataEntry{ id, data: NetworkNbt(data) }
+    }
+
+    /// The data of the entry, if it was sent.
+    pub fn data(&self) -> Option<&Tag> {
+        self.data.0.as_ref()
+    }
+}
+
+impl Segment for RegistryDataEntry{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.id.read_from_stream(reader)?;
+        self.data = NetworkNbt::default();
+        if reader.read_u8()? != 0 {
+            self.data.read_from_stream(reader)?;
+        }
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.id.write_to_stream(writer)?;
+        match &self.data.0 {
+            Some(_) => {
+                writer.write_u8(1)?;
+                self.data.write_to_stream(writer)
+            }
+            None => writer.write_u8(0)
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Item slots: `[bool present]` followed by `[VarInt item id][i8 count][NBT]` when present.
+//!
+//! Since 1.20.5 a slot is `[VarInt count]`, followed by `[VarInt item id]` and
+//! its components if the count is positive, see `component`. `Slot` uses this
+//! layout when the active `DecodeContext` is at least protocol 766.
+use crate::segment::context;
+use crate::segment::implementation::mojang::component::ItemComponents;
+use crate::segment::implementation::nbt::Tag;
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use crate::segment::Segment;
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind};
+
+/// The first protocol version whose slots carry components instead of NBT, 1.20.5.
+pub const COMPONENTS_PROTOCOL: i32 = 766;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemStack{
+    pub item_id: i32,
+    pub count: i8,
+    /// The root compound of the item NBT, its name is always empty. Not sent
+    /// since 1.20.5.
+    pub nbt: Option<Tag>,
+    /// Only sent since 1.20.5.
+    pub components: ItemComponents
+}
+
+/// A slot that may hold an item.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Slot(pub Option<ItemStack>);
+
+impl Slot{
+    pub fn new(item_id: i32, count: i8) -> Self {
+        Slot(Some(ItemStack{ item_id, count, nbt: None, components: ItemComponents::default() }))
+    }
+
+    pub fn empty() -> Self {
+        Slot(None)
+    }
+}
+
+impl Segment for Slot{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        if let Some(protocol) = components_protocol() {
+            self.0 = read_component_stack(protocol, reader)?;
+            return Ok(());
+        }
+        self.0 = if reader.read_u8()? != 0 {
+            let item_id = read_var_int(reader)?;
+            let count = reader.read_i8()?;
+            let nbt = Tag::read_named(reader)?.map(|(_, tag)| tag);
+            Some(ItemStack{ item_id, count, nbt, components: ItemComponents::default() })
+        }else{
+            None
+        };
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        if let Some(protocol) = components_protocol() {
+            return write_component_stack(protocol, self.0.as_ref(), writer);
+        }
+        match &self.0 {
+            Some(stack) => {
+                refuse_components(stack)?;
+                writer.write_u8(1)?;
+                write_var_int(stack.item_id, writer)?;
+                writer.write_i8(stack.count)?;
+                match &stack.nbt {
+                    Some(nbt) => nbt.write_named("", writer),
+                    None => writer.write_u8(0)
+                }
+            }
+            None => writer.write_u8(0)
+        }
+    }
+}
+
+/// A slot as sent from 1.20.2 until item components replaced NBT in 1.20.5,
+/// its NBT has no root name.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkSlot(pub Option<ItemStack>);
+
+impl Segment for NetworkSlot{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.0 = if reader.read_u8()? != 0 {
+            let item_id = read_var_int(reader)?;
+            let count = reader.read_i8()?;
+            let nbt = Tag::read_network(reader)?;
+            Some(ItemStack{ item_id, count, nbt, components: ItemComponents::default() })
+        }else{
+            None
+        };
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match &self.0 {
+            Some(stack) => {
+                refuse_components(stack)?;
+                writer.write_u8(1)?;
+                write_var_int(stack.item_id, writer)?;
+                writer.write_i8(stack.count)?;
+                match &stack.nbt {
+                    Some(nbt) => nbt.write_network(writer),
+                    None => writer.write_u8(0)
+                }
+            }
+            None => writer.write_u8(0)
+        }
+    }
+}
+
+impl From<Slot> for NetworkSlot{
+    fn from(slot: Slot) -> Self {
+        NetworkSlot(slot.0)
+    }
+}
+
+/// The protocol version of the active context if its slots carry components.
+fn components_protocol() -> Option<i32> {
+    context::current(|context| context.protocol_version).filter(|&protocol| protocol >= COMPONENTS_PROTOCOL)
+}
+
+fn read_component_stack<R: std::io::Read>(protocol: i32, reader: &mut R) -> std::io::Result<Option<ItemStack>> {
+    let count = read_var_int(reader)?;
+    if count <= 0 {
+        return Ok(None);
+    }
+    if count > i8::MAX as i32 {
+        return Err(Error::new(ErrorKind::InvalidData, format!("stack of {} items", count)));
+    }
+    let item_id = read_var_int(reader)?;
+    let components = ItemComponents::read(protocol, reader)?;
+    Ok(Some(ItemStack{ item_id, count: count as i8, nbt: None, components }))
+}
+
+fn write_component_stack<W: std::io::Write>(protocol: i32, stack: Option<&ItemStack>, writer: &mut W) -> std::io::Result<()> {
+    match stack {
+        Some(stack) if stack.count > 0 => {
+            if stack.nbt.is_some() {
+                return Err(Error::new(ErrorKind::InvalidInput, "item NBT can not be sent since 1.20.5, use components"));
+            }
+            write_var_int(stack.count as i32, writer)?;
+            write_var_int(stack.item_id, writer)?;
+            stack.components.write(protocol, writer)
+        }
+        _ => write_var_int(0, writer)
+    }
+}
+
+fn refuse_components(stack: &ItemStack) -> std::io::Result<()> {
+    if stack.components.is_empty() {
+        Ok(())
+    }else{
+        Err(Error::new(ErrorKind::InvalidInput, "item components can not be sent before 1.20.5, use NBT"))
+    }
+}
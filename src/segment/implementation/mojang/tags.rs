@@ -0,0 +1,223 @@
+//! The Tags packet: named groups of registry entries, like `minecraft:logs`.
+//!
+//! A tag list is `[VarInt tag count]` and per tag `[String name][VarInt count][VarInt id]...`.
+//! Older versions send one tag list for blocks, items, fluids and entity
+//! types, in that order. Newer versions send `[VarInt registry count]` with
+//! `[String registry][tag list]` per registry, which also carries registries
+//! like game events.
+use super::{read_count, read_string, write_string};
+use crate::segment::implementation::var::{read_var_int, write_var_int};
+use crate::segment::Segment;
+use std::io::{Error, ErrorKind};
+
+pub const BLOCK: &str = "minecraft:block";
+pub const ITEM: &str = "minecraft:item";
+pub const FLUID: &str = "minecraft:fluid";
+pub const ENTITY_TYPE: &str = "minecraft:entity_type";
+pub const GAME_EVENT: &str = "minecraft:game_event";
+
+/// The registries of the fixed layout, in the order they are sent.
+pub const FIXED_REGISTRIES: [&str; 4] = [BLOCK, ITEM, FLUID, ENTITY_TYPE];
+
+/// The tags of one registry, by name.
+type Tags = Vec<(String, Vec<i32>)>;
+
+/// Tags per registry. Registry and tag names are stored with their namespace.
+/// Registries and tags keep the order they were inserted or read in, so a
+/// decoded packet encodes to the bytes it was read from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TagSet{
+    registries: Vec<(String, Tags)>
+}
+
+impl TagSet{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the entries of a tag, replacing its previous entries.
+    pub fn insert(&mut self, registry: &str, tag: &str, entries: Vec<i32>) -> &mut Self {
+        insert_tag(self.registry_mut(namespaced(registry)), namespaced(tag), entries);
+        self
+    }
+
+    /// The entries of a tag. Names without namespace are looked up in `minecraft`.
+    pub fn tag(&self, registry: &str, tag: &str) -> Option<&[i32]> {
+        let tag = namespaced(tag);
+        self.registry(registry)?.iter().find(|(name, _)| *name == tag).map(|(_, entries)| entries.as_slice())
+    }
+
+    /// Whether the registry entry with the given id is part of the tag, e.g.
+    /// `tags.contains(BLOCK, "logs", state_block_id)`.
+    pub fn contains(&self, registry: &str, tag: &str, id: i32) -> bool {
+        self.tag(registry, tag).is_some_and(|entries| entries.contains(&id))
+    }
+
+    /// The tags of a registry an entry is part of.
+    pub fn tags_of(&self, registry: &str, id: i32) -> impl Iterator<Item = &str> {
+        self.registry(registry).into_iter()
+            .flat_map(move |tags| tags.iter().filter(move |(_, entries)| entries.contains(&id)).map(|(tag, _)| tag.as_str()))
+    }
+
+    pub fn registries(&self) -> impl Iterator<Item = &str> {
+        self.registries.iter().map(|(registry, _)| registry.as_str())
+    }
+
+    pub fn tags(&self, registry: &str) -> impl Iterator<Item = (&str, &[i32])> {
+        self.registry(registry).into_iter()
+            .flat_map(|tags| tags.iter().map(|(tag, entries)| (tag.as_str(), entries.as_slice())))
+    }
+
+    /// Merges another set in, with its tags replacing tags of the same name.
+    pub fn extend(&mut self, other: TagSet) {
+        for (registry, tags) in other.registries {
+            let own = self.registry_mut(registry);
+            for (tag, entries) in tags {
+                insert_tag(own, tag, entries);
+            }
+        }
+    }
+
+    fn registry(&self, registry: &str) -> Option<&Tags> {
+        let registry = namespaced(registry);
+        self.registries.iter().find(|(name, _)| *name == registry).map(|(_, tags)| tags)
+    }
+
+    fn registry_mut(&mut self, registry: String) -> &mut Tags {
+        let index = match self.registries.iter().position(|(name, _)| *name == registry) {
+            Some(index) => index,
+            None => {
+                self.registries.push((registry, Tags::new()));
+                self.registries.len() - 1
+            }
+        };
+        &mut self.registries[index].1
+    }
+
+    /// Reads the fixed layout of block, item, fluid and entity type tags.
+    pub fn read_fixed<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut set = TagSet::new();
+        for registry in FIXED_REGISTRIES.iter() {
+            set.registries.push((registry.to_string(), read_tag_list(reader)?));
+        }
+        Ok(set)
+    }
+
+    /// Writes the fixed layout. Registries the layout can not carry are refused.
+    pub fn write_fixed<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        if let Some(registry) = self.registries().find(|registry| !FIXED_REGISTRIES.contains(registry)) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("registry {} can not be sent in the fixed tag layout", registry)));
+        }
+        for registry in FIXED_REGISTRIES.iter() {
+            write_tag_list(self.registry(registry).map_or(&[][..], Vec::as_slice), writer)?;
+        }
+        Ok(())
+    }
+}
+
+fn insert_tag(tags: &mut Tags, tag: String, entries: Vec<i32>) {
+    match tags.iter_mut().find(|(name, _)| *name == tag) {
+        Some((_, existing)) => *existing = entries,
+        None => tags.push((tag, entries))
+    }
+}
+
+/// The layout of newer versions, a list of registries.
+impl Segment for TagSet{
+    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        let count = read_count(reader)?;
+        self.registries.clear();
+        for _ in 0..count {
+            let registry = read_string(reader)?;
+            let tags = read_tag_list(reader)?;
+            self.registries.push((registry, tags));
+        }
+        Ok(())
+    }
+
+    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_var_int(self.registries.len() as i32, writer)?;
+        for (registry, tags) in &self.registries {
+            write_string(registry, writer)?;
+            write_tag_list(tags, writer)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_tag_list<R: std::io::Read>(reader: &mut R) -> std::io::Result<Tags> {
+    let count = read_count(reader)?;
+    let mut tags = Tags::new();
+    for _ in 0..count {
+        let name = read_string(reader)?;
+        let len = read_count(reader)?;
+        let mut entries = Vec::new();
+        for _ in 0..len {
+            entries.push(read_var_int(reader)?);
+        }
+        tags.push((name, entries));
+    }
+    Ok(tags)
+}
+
+fn write_tag_list<W: std::io::Write>(tags: &[(String, Vec<i32>)], writer: &mut W) -> std::io::Result<()> {
+    write_var_int(tags.len() as i32, writer)?;
+    for (name, entries) in tags {
+        write_string(name, writer)?;
+        write_var_int(entries.len() as i32, writer)?;
+        entries.iter().try_for_each(|&id| write_var_int(id, writer))?;
+    }
+    Ok(())
+}
+
+fn namespaced(name: &str) -> String {
+    if name.contains(':') {
+        name.to_string()
+    }else{
+        format!("minecraft:{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The 1.17 Tags packet of `fixtures/1.17`, in the registry order of vanilla.
+    const TAGS_1_17: &[u8] = include_bytes!("../../../../fixtures/1.17/play-clientbound-0x66-Tags.bin");
+
+    #[test]
+    fn registry_keyed_layout_round_trips() {
+        let mut set = TagSet::new();
+        let mut reader = TAGS_1_17;
+        set.read_from_stream(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(set.registries().collect::<Vec<_>>(), [BLOCK, ITEM, FLUID, ENTITY_TYPE, GAME_EVENT]);
+        assert_eq!(set.tag(FLUID, "water"), Some(&[2, 1][..]));
+        assert_eq!(set.tags_of(FLUID, 4).collect::<Vec<_>>(), ["minecraft:lava"]);
+        let mut written = Vec::new();
+        set.write_to_stream(&mut written).unwrap();
+        assert_eq!(written, TAGS_1_17);
+    }
+
+    #[test]
+    fn fixed_layout_refuses_other_registries() {
+        let mut set = TagSet::new();
+        set.insert(FLUID, "water", vec![2, 1]);
+        let mut written = Vec::new();
+        set.write_fixed(&mut written).unwrap();
+        assert_eq!(TagSet::read_fixed(&mut &written[..]).unwrap().tag(FLUID, "water"), Some(&[2, 1][..]));
+        set.insert(GAME_EVENT, "vibrations", Vec::new());
+        assert_eq!(set.write_fixed(&mut Vec::new()).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn insert_and_extend_replace_tags_in_place() {
+        let mut set = TagSet::new();
+        set.insert("block", "logs", vec![1]).insert("block", "planks", vec![2]);
+        let mut other = TagSet::new();
+        other.insert(BLOCK, "minecraft:logs", vec![3]);
+        set.extend(other);
+        assert_eq!(set.tags(BLOCK).collect::<Vec<_>>(), [("minecraft:logs", &[3][..]), ("minecraft:planks", &[2][..])]);
+    }
+}
@@ -0,0 +1,450 @@
+//! Structural validation of NBT before it is handed to a full decoder.
+//!
+//! The decoders copy one tag from the reader while checking its nesting depth
+//! and lengths, so a hostile peer can neither overflow the stack with deeply
+//! nested compounds nor make the decoder allocate more than it actually sent.
+//! `Tag` is a decoder built on top of that, for the NBT the crate interprets
+//! itself.
+//!
+//! Since 1.20.2 the root tag of NBT sent over the network has no name, see
+//! `NetworkNbt`.
+
+use crate::segment::Segment;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// The nesting limit of the vanilla NBT reader.
+pub const MAX_DEPTH: usize = 512;
+
+const TAG_END: u8 = 0;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+
+/// Copies a named tag, or the lone `TAG_End` of an absent one, from `reader` to `out`.
+pub fn copy_named_tag<R: Read>(reader: &mut R, out: &mut Vec<u8>, max_depth: usize) -> std::io::Result<()> {
+    let tag_type = reader.read_u8()?;
+    out.push(tag_type);
+    if tag_type == TAG_END {
+        return Ok(());
+    }
+    copy_string(reader, out)?;
+    copy_payload(reader, out, tag_type, max_depth)
+}
+
+/// Copies a tag without a name, or a lone `TAG_End`, as sent since 1.20.2.
+pub fn copy_network_tag<R: Read>(reader: &mut R, out: &mut Vec<u8>, max_depth: usize) -> std::io::Result<()> {
+    let tag_type = reader.read_u8()?;
+    out.push(tag_type);
+    if tag_type == TAG_END {
+        return Ok(());
+    }
+    copy_payload(reader, out, tag_type, max_depth)
+}
+
+fn copy_payload<R: Read>(reader: &mut R, out: &mut Vec<u8>, tag_type: u8, depth: usize) -> std::io::Result<()> {
+    match tag_type {
+        1 => copy_exact(reader, out, 1),
+        2 => copy_exact(reader, out, 2),
+        3 | 5 => copy_exact(reader, out, 4),
+        4 | 6 => copy_exact(reader, out, 8),
+        7 => copy_array(reader, out, 1),
+        8 => copy_string(reader, out),
+        TAG_LIST => {
+            let depth = enter(depth)?;
+            let element_type = reader.read_u8()?;
+            out.push(element_type);
+            let len = copy_len(reader, out)?;
+            if element_type == TAG_END && len > 0 {
+                return Err(Error::new(ErrorKind::InvalidData, "NBT list of TAG_End is not empty"));
+            }
+            for _ in 0..len {
+                copy_payload(reader, out, element_type, depth)?;
+            }
+            Ok(())
+        }
+        TAG_COMPOUND => {
+            let depth = enter(depth)?;
+            loop {
+                let child_type = reader.read_u8()?;
+                out.push(child_type);
+                if child_type == TAG_END {
+                    return Ok(());
+                }
+                copy_string(reader, out)?;
+                copy_payload(reader, out, child_type, depth)?;
+            }
+        }
+        11 => copy_array(reader, out, 4),
+        12 => copy_array(reader, out, 8),
+        _ => Err(Error::new(ErrorKind::InvalidData, format!("unknown NBT tag type {}", tag_type)))
+    }
+}
+
+/// Uses up one level of the remaining depth.
+fn enter(depth: usize) -> std::io::Result<usize> {
+    depth.checked_sub(1).ok_or_else(|| Error::new(ErrorKind::InvalidData, "NBT is nested too deeply"))
+}
+
+fn copy_string<R: Read>(reader: &mut R, out: &mut Vec<u8>) -> std::io::Result<()> {
+    let len = reader.read_u16::<BigEndian>()?;
+    out.extend_from_slice(&len.to_be_bytes());
+    copy_exact(reader, out, len as u64)
+}
+
+fn copy_len<R: Read>(reader: &mut R, out: &mut Vec<u8>) -> std::io::Result<u64> {
+    let len = reader.read_i32::<BigEndian>()?;
+    if len < 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "negative NBT length"));
+    }
+    out.extend_from_slice(&len.to_be_bytes());
+    Ok(len as u64)
+}
+
+fn copy_array<R: Read>(reader: &mut R, out: &mut Vec<u8>, element_len: u64) -> std::io::Result<()> {
+    let len = copy_len(reader, out)?;
+    copy_exact(reader, out, len * element_len)
+}
+
+/// Copies exactly `len` bytes. The buffer only grows as the bytes actually arrive.
+fn copy_exact<R: Read>(reader: &mut R, out: &mut Vec<u8>, len: u64) -> std::io::Result<()> {
+    let copied = reader.by_ref().take(len).read_to_end(out)?;
+    if (copied as u64) < len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "NBT is truncated"));
+    }
+    Ok(())
+}
+
+/// A decoded NBT tag.
+///
+/// Compounds keep the order their entries were read in. Strings are decoded
+/// as UTF-8; Java's modified UTF-8 only differs for NUL and characters outside
+/// the basic multilingual plane, which are replaced.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tag{
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>)
+}
+
+impl Tag{
+    pub fn type_id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12
+        }
+    }
+
+    /// Looks up an entry of a compound.
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(key, _)| key == name).map(|(_, tag)| tag),
+            _ => None
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(value) => Some(value as i64),
+            Tag::Short(value) => Some(value as i64),
+            Tag::Int(value) => Some(value as i64),
+            Tag::Long(value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Tag::Float(value) => Some(value as f64),
+            Tag::Double(value) => Some(value),
+            _ => self.as_i64().map(|value| value as f64)
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(values) => Some(values),
+            _ => None
+        }
+    }
+
+    /// Reads a named tag, returning `None` for the lone `TAG_End` of an absent one.
+    pub fn read_named<R: Read>(reader: &mut R) -> std::io::Result<Option<(String, Tag)>> {
+        let mut validated = Vec::new();
+        copy_named_tag(reader, &mut validated, MAX_DEPTH)?;
+        let mut reader = &validated[..];
+        let tag_type = reader.read_u8()?;
+        if tag_type == TAG_END {
+            return Ok(None);
+        }
+        let name = read_string(&mut reader)?;
+        Ok(Some((name, read_payload(&mut reader, tag_type)?)))
+    }
+
+    /// Reads a tag without a name, returning `None` for a lone `TAG_End`.
+    pub fn read_network<R: Read>(reader: &mut R) -> std::io::Result<Option<Tag>> {
+        let mut validated = Vec::new();
+        copy_network_tag(reader, &mut validated, MAX_DEPTH)?;
+        let mut reader = &validated[..];
+        match reader.read_u8()? {
+            TAG_END => Ok(None),
+            tag_type => read_payload(&mut reader, tag_type).map(Some)
+        }
+    }
+
+    /// Writes this tag without a name, as the root of network NBT.
+    pub fn write_network<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_u8(self.type_id())?;
+        self.write_payload(writer)
+    }
+
+    /// Writes this tag with a name, as the root of an NBT blob.
+    pub fn write_named<W: Write>(&self, name: &str, writer: &mut W) -> std::io::Result<()> {
+        writer.write_u8(self.type_id())?;
+        write_string(name, writer)?;
+        self.write_payload(writer)
+    }
+
+    fn write_payload<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            Tag::Byte(value) => writer.write_i8(*value),
+            Tag::Short(value) => writer.write_i16::<BigEndian>(*value),
+            Tag::Int(value) => writer.write_i32::<BigEndian>(*value),
+            Tag::Long(value) => writer.write_i64::<BigEndian>(*value),
+            Tag::Float(value) => writer.write_f32::<BigEndian>(*value),
+            Tag::Double(value) => writer.write_f64::<BigEndian>(*value),
+            Tag::ByteArray(values) => {
+                writer.write_i32::<BigEndian>(values.len() as i32)?;
+                values.iter().try_for_each(|value| writer.write_i8(*value))
+            }
+            Tag::String(value) => write_string(value, writer),
+            Tag::List(values) => {
+                writer.write_u8(values.first().map_or(TAG_END, Tag::type_id))?;
+                writer.write_i32::<BigEndian>(values.len() as i32)?;
+                for value in values {
+                    if value.type_id() != values[0].type_id() {
+                        return Err(Error::new(ErrorKind::InvalidInput, "NBT list elements differ in type"));
+                    }
+                    value.write_payload(writer)?;
+                }
+                Ok(())
+            }
+            Tag::Compound(entries) => {
+                for (name, value) in entries {
+                    writer.write_u8(value.type_id())?;
+                    write_string(name, writer)?;
+                    value.write_payload(writer)?;
+                }
+                writer.write_u8(TAG_END)
+            }
+            Tag::IntArray(values) => {
+                writer.write_i32::<BigEndian>(values.len() as i32)?;
+                values.iter().try_for_each(|value| writer.write_i32::<BigEndian>(*value))
+            }
+            Tag::LongArray(values) => {
+                writer.write_i32::<BigEndian>(values.len() as i32)?;
+                values.iter().try_for_each(|value| writer.write_i64::<BigEndian>(*value))
+            }
+        }
+    }
+}
+
+/// NBT as sent since 1.20.2: a root tag of any type without a name, or a
+/// lone `TAG_End` if absent. Text components are sent this way since 1.20.3.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkNbt(pub Option<Tag>);
+
+impl Segment for NetworkNbt{
+    fn read_from_stream<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        self.0 = Tag::read_network(reader)?;
+        Ok(())
+    }
+
+    fn write_to_stream<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match &self.0 {
+            Some(tag) => tag.write_network(writer),
+            None => writer.write_u8(TAG_END)
+        }
+    }
+}
+
+/// Parses a payload that `copy_named_tag` already validated.
+fn read_payload(reader: &mut &[u8], tag_type: u8) -> std::io::Result<Tag> {
+    Ok(match tag_type {
+        1 => Tag::Byte(reader.read_i8()?),
+        2 => Tag::Short(reader.read_i16::<BigEndian>()?),
+        3 => Tag::Int(reader.read_i32::<BigEndian>()?),
+        4 => Tag::Long(reader.read_i64::<BigEndian>()?),
+        5 => Tag::Float(reader.read_f32::<BigEndian>()?),
+        6 => Tag::Double(reader.read_f64::<BigEndian>()?),
+        7 => {
+            let len = reader.read_i32::<BigEndian>()?;
+            Tag::ByteArray((0..len).map(|_| reader.read_i8()).collect::<std::io::Result<_>>()?)
+        }
+        8 => Tag::String(read_string(reader)?),
+        TAG_LIST => {
+            let element_type = reader.read_u8()?;
+            let len = reader.read_i32::<BigEndian>()?;
+            Tag::List((0..len).map(|_| read_payload(reader, element_type)).collect::<std::io::Result<_>>()?)
+        }
+        TAG_COMPOUND => {
+            let mut entries = Vec::new();
+            loop {
+                let child_type = reader.read_u8()?;
+                if child_type == TAG_END {
+                    break;
+                }
+                let name = read_string(reader)?;
+                entries.push((name, read_payload(reader, child_type)?));
+            }
+            Tag::Compound(entries)
+        }
+        11 => {
+            let len = reader.read_i32::<BigEndian>()?;
+            Tag::IntArray((0..len).map(|_| reader.read_i32::<BigEndian>()).collect::<std::io::Result<_>>()?)
+        }
+        12 => {
+            let len = reader.read_i32::<BigEndian>()?;
+            Tag::LongArray((0..len).map(|_| reader.read_i64::<BigEndian>()).collect::<std::io::Result<_>>()?)
+        }
+        _ => return Err(Error::new(ErrorKind::InvalidData, format!("unknown NBT tag type {}", tag_type)))
+    })
+}
+
+fn read_string(reader: &mut &[u8]) -> std::io::Result<String> {
+    let len = reader.read_u16::<BigEndian>()? as usize;
+    if reader.len() < len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "NBT is truncated"));
+    }
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn write_string<W: Write>(value: &str, writer: &mut W) -> std::io::Result<()> {
+    if value.len() > u16::MAX as usize {
+        return Err(Error::new(ErrorKind::InvalidInput, "NBT string is too long"));
+    }
+    writer.write_u16::<BigEndian>(value.len() as u16)?;
+    writer.write_all(value.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A named root compound with `levels - 1` compounds nested inside it.
+    fn nested_compounds(levels: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for _ in 0..levels {
+            bytes.extend_from_slice(&[TAG_COMPOUND, 0, 0]);
+        }
+        bytes.resize(bytes.len() + levels, TAG_END);
+        bytes
+    }
+
+    /// A network root list holding lists `levels - 1` deep.
+    fn nested_lists(levels: usize) -> Vec<u8> {
+        let mut bytes = vec![TAG_LIST];
+        for _ in 1..levels {
+            bytes.extend_from_slice(&[TAG_LIST, 0, 0, 0, 1]);
+        }
+        bytes.extend_from_slice(&[TAG_END, 0, 0, 0, 0]);
+        bytes
+    }
+
+    fn copy(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        copy_named_tag(&mut &bytes[..], &mut out, MAX_DEPTH)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn nesting_up_to_the_limit_is_accepted() {
+        let bytes = nested_compounds(MAX_DEPTH);
+        assert_eq!(copy(&bytes).unwrap(), bytes);
+        assert!(Tag::read_network(&mut &nested_lists(MAX_DEPTH)[..]).unwrap().is_some());
+    }
+
+    #[test]
+    fn nesting_past_the_limit_is_refused() {
+        let error = copy(&nested_compounds(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let error = Tag::read_network(&mut &nested_lists(MAX_DEPTH + 1)[..]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        // Far deeper than the stack would allow without the limit.
+        assert!(copy(&nested_compounds(100_000)).is_err());
+    }
+
+    #[test]
+    fn lengths_past_the_data_are_truncated_not_allocated() {
+        // A long array announcing i32::MAX longs, 16 GiB, with one present.
+        let mut bytes = vec![12, 0, 1, b'a', 0x7F, 0xFF, 0xFF, 0xFF];
+        bytes.extend_from_slice(&[0; 8]);
+        let error = copy(&bytes).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        // A list of i32::MAX compounds with none present.
+        let error = copy(&[TAG_LIST, 0, 0, TAG_COMPOUND, 0x7F, 0xFF, 0xFF, 0xFF]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        // A name longer than what follows.
+        let error = copy(&[8, 0xFF, 0xFF, b'a']).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn invalid_lengths_and_types_are_refused() {
+        let error = copy(&[7, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let error = copy(&[TAG_LIST, 0, 0, TAG_END, 0, 0, 0, 1]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let error = copy(&[13, 0, 0]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn tags_round_trip() {
+        let tag = Tag::Compound(vec![
+            ("byte".to_string(), Tag::Byte(-1)),
+            ("name".to_string(), Tag::String("mc".to_string())),
+            ("list".to_string(), Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+            ("longs".to_string(), Tag::LongArray(vec![i64::MIN, 3])),
+            ("empty".to_string(), Tag::List(Vec::new()))
+        ]);
+        let mut named = Vec::new();
+        tag.write_named("root", &mut named).unwrap();
+        assert_eq!(Tag::read_named(&mut &named[..]).unwrap(), Some(("root".to_string(), tag.clone())));
+        let mut network = Vec::new();
+        NetworkNbt(Some(tag.clone())).write_to_stream(&mut network).unwrap();
+        let mut read = NetworkNbt::default();
+        read.read_from_stream(&mut &network[..]).unwrap();
+        assert_eq!(read, NetworkNbt(Some(tag)));
+        assert_eq!(Tag::read_named(&mut &[TAG_END][..]).unwrap(), None);
+    }
+}
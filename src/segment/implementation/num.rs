@@ -1,143 +1,239 @@
-use crate::segment::Segment;
-use byteorder::{ReadBytesExt, BigEndian, WriteBytesExt};
+use crate::io::read_array;
+use crate::segment::{Discriminant, Segment};
 
 impl Segment for bool {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
-        *self = reader.read_u8()? != 0;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()> {
+        *self = read_array::<1, _>(reader)?[0] != 0;
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_u8(if *self { 1 } else { 0 })?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&[if *self { 1 } else { 0 }])?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<bool>()
+    }
 }
 
 /*
     Unsigned integers
  */
 impl Segment for u8 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_u8()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = u8::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_u8(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<u8>()
+    }
 }
 
 impl Segment for u16 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_u16::<BigEndian>()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = u16::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_u16::<BigEndian>(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<u16>()
+    }
 }
 
 impl Segment for u32 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_u32::<BigEndian>()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = u32::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_u32::<BigEndian>(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<u32>()
+    }
 }
 
 impl Segment for u64 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_u64::<BigEndian>()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = u64::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_u64::<BigEndian>(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<u64>()
+    }
 }
 
 /*
     Signed integers
  */
 impl Segment for i8 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_i8()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = i8::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_i8(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<i8>()
+    }
 }
 
 impl Segment for i16 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_i16::<BigEndian>()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = i16::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_i16::<BigEndian>(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<i16>()
+    }
 }
 
 impl Segment for i32 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_i32::<BigEndian>()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = i32::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_i32::<BigEndian>(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<i32>()
+    }
 }
 
 impl Segment for i64 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_i64::<BigEndian>()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = i64::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_i64::<BigEndian>(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<i64>()
+    }
 }
 
 /*
     FLOATS
  */
 impl Segment for f32 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_f32::<BigEndian>()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = f32::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_f32::<BigEndian>(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<f32>()
+    }
 }
 
 impl Segment for f64 {
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>{
-        *self = reader.read_f64::<BigEndian>()?;
+    #[inline]
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>{
+        *self = f64::from_be_bytes(read_array(reader)?);
         Ok(())
     }
 
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_f64::<BigEndian>(*self)?;
+    #[inline]
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+        writer.write_all(&self.to_be_bytes())?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[inline]
+    fn serialized_len(&self) -> usize {
+        core::mem::size_of::<f64>()
+    }
+}
+
+/*
+    Discriminants
+ */
+macro_rules! impl_discriminant {
+    ($($int:ty),+) => {
+        $(impl Discriminant for $int {
+            fn to_discriminant(&self) -> i64 {
+                *self as i64
+            }
+
+            fn from_discriminant(value: i64) -> Option<Self> {
+                core::convert::TryFrom::try_from(value).ok()
+            }
+        })+
+    };
+}
+
+impl_discriminant!(u8, u16, u32, i8, i16, i32, i64);
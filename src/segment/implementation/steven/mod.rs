@@ -6,6 +6,8 @@ mod private {
     }
 
     /// This macro is a workaround because sealed traits do not exist yet.
+    /// Types with a known size pass it as a closure, so `serialized_len` does
+    /// not have to serialize them.
     macro_rules! impl_serialize {
         ($struct_name:path) => {
             impl crate::segment::Segment for $struct_name{
@@ -20,8 +22,8 @@ mod private {
                 }
             }
         };
-        (optional $struct_name:path) => {
-            impl crate::segment::Segment for Option<$struct_name>{
+        ($struct_name:path, |$value:ident| $len:expr) => {
+            impl crate::segment::Segment for $struct_name{
                 fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
                     *self = steven_protocol::protocol::Serializable::read_from(reader).map_err(convert_error)?;
                     Ok(())
@@ -31,10 +33,15 @@ mod private {
                     steven_protocol::protocol::Serializable::write_to(self, writer).map_err(convert_error)?;
                     Ok(())
                 }
+
+                fn serialized_len(&self) -> usize {
+                    let $value = self;
+                    $len
+                }
             }
         };
-        ($target_name:ident, $generic_trait:path) => {
-            impl<T: $generic_trait + Default> crate::segment::Segment for $target_name<T>{
+        (optional $struct_name:path) => {
+            impl crate::segment::Segment for Option<$struct_name>{
                 fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
                     *self = steven_protocol::protocol::Serializable::read_from(reader).map_err(convert_error)?;
                     Ok(())
@@ -46,8 +53,8 @@ mod private {
                 }
             }
         };
-        ($target_name:ident, $generic_trait:path, $generic_trait2:path) => {
-            impl<T: $generic_trait, TT: $generic_trait2 + Default> crate::segment::Segment for $target_name<T,TT>{
+        ($target_name:ident, $generic_trait:path) => {
+            impl<T: $generic_trait + Default> crate::segment::Segment for $target_name<T>{
                 fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
                     *self = steven_protocol::protocol::Serializable::read_from(reader).map_err(convert_error)?;
                     Ok(())
@@ -61,36 +68,217 @@ mod private {
         }
     }
 
-    impl_serialize!(steven_protocol::protocol::VarInt);
     impl_serialize!(steven_protocol::protocol::VarShort);
-    impl_serialize!(steven_protocol::protocol::VarLong);
+
+    /// Read with the crate's own reader, so they honour `var::canonical`.
+    impl crate::segment::Segment for steven_protocol::protocol::VarInt{
+        fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+            self.0 = crate::segment::implementation::var::read_var_int(reader)?;
+            Ok(())
+        }
+
+        fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+            crate::segment::implementation::var::write_var_int(self.0, writer)
+        }
+
+        fn serialized_len(&self) -> usize {
+            crate::segment::implementation::var::var_int_len(self.0)
+        }
+    }
+
+    impl crate::segment::Segment for steven_protocol::protocol::VarLong{
+        fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+            self.0 = crate::segment::implementation::var::read_var_long(reader)?;
+            Ok(())
+        }
+
+        fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+            crate::segment::implementation::var::write_var_long(self.0, writer)
+        }
+
+        fn serialized_len(&self) -> usize {
+            crate::segment::implementation::var::var_long_len(self.0)
+        }
+    }
     impl_serialize!(steven_protocol::format::Component);
-    impl_serialize!(steven_protocol::protocol::UUID);
-    impl_serialize!(optional steven_protocol::nbt::NamedTag);
+    impl_serialize!(steven_protocol::protocol::UUID, |_uuid| 16);
     impl_serialize!(optional steven_protocol::item::Stack);
     impl_serialize!(optional steven_protocol::nbt::Tag);
     impl_serialize!(optional steven_protocol::types::ParticleData);
     impl_serialize!(optional steven_protocol::types::VillagerData);
-    impl_serialize!(steven_protocol::protocol::packet::PlayerInfoData);
     impl_serialize!(steven_protocol::types::Metadata);
     impl_serialize!(steven_protocol::protocol::packet::EntityEquipments);
 
     #[cfg(feature = "steven_shared")]
-    impl_serialize!(steven_shared::Position);
+    impl_serialize!(steven_shared::Position, |_position| 8);
 
     #[cfg(feature = "serde_json")]
     impl_serialize!(serde_json::Value);
 
-    impl_serialize!(std::string::String);
+    /// Read with the crate's own reader, which checks the length against the
+    /// `DecodeLimits` of the active context and only allocates what arrives.
+    impl crate::segment::Segment for std::string::String{
+        fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+            *self = crate::segment::implementation::mojang::read_string(reader)?;
+            Ok(())
+        }
+
+        fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+            steven_protocol::protocol::Serializable::write_to(self, writer).map_err(convert_error)?;
+            Ok(())
+        }
+
+        fn serialized_len(&self) -> usize {
+            crate::segment::implementation::var::var_int_len(self.len() as i32) + self.len()
+        }
+    }
+
+    /// Reads the length prefix of a `LenPrefixed` or `LenPrefixedBytes`.
+    /// stevenarella reserves that much up front, a hostile peer could make it
+    /// abort the process by sending a large one.
+    fn read_len<T: steven_protocol::protocol::Lengthable, R: std::io::Read>(reader: &mut R) -> std::io::Result<usize> {
+        let len: T = steven_protocol::protocol::Serializable::read_from(reader).map_err(convert_error)?;
+        Ok(len.into_len())
+    }
 
     use steven_protocol::protocol::LenPrefixedBytes;
-    impl_serialize!(LenPrefixedBytes, steven_protocol::protocol::Lengthable);
+    /// The bytes are read as they arrive instead of reserving the length prefix.
+    impl<T: steven_protocol::protocol::Lengthable + Default> crate::segment::Segment for LenPrefixedBytes<T>{
+        fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+            use std::io::Read;
+            let len = read_len::<T, R>(reader)?;
+            let mut data = Vec::new();
+            reader.by_ref().take(len as u64).read_to_end(&mut data)?;
+            if data.len() != len {
+                return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "byte array ended prematurely"));
+            }
+            *self = LenPrefixedBytes::new(data);
+            Ok(())
+        }
+
+        fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+            steven_protocol::protocol::Serializable::write_to(self, writer).map_err(convert_error)?;
+            Ok(())
+        }
+
+        /// Only the length prefix is counted by writing it.
+        fn serialized_len(&self) -> usize {
+            let mut counter = crate::segment::ByteCounter::default();
+            let _ = steven_protocol::protocol::Serializable::write_to(&T::from_len(self.data.len()), &mut counter);
+            counter.0 + self.data.len()
+        }
+    }
     use steven_protocol::protocol::LenPrefixed;
-    impl_serialize!(LenPrefixed, steven_protocol::protocol::Lengthable, steven_protocol::protocol::Serializable);
+    /// The elements are read one by one instead of reserving the length prefix.
+    impl<T: steven_protocol::protocol::Lengthable, TT: steven_protocol::protocol::Serializable + Default> crate::segment::Segment for LenPrefixed<T, TT>{
+        fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+            let len = read_len::<T, R>(reader)?;
+            let mut data = Vec::new();
+            for _ in 0..len {
+                data.push(steven_protocol::protocol::Serializable::read_from(reader).map_err(convert_error)?);
+            }
+            *self = LenPrefixed::new(data);
+            Ok(())
+        }
+
+        fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+            steven_protocol::protocol::Serializable::write_to(self, writer).map_err(convert_error)?;
+            Ok(())
+        }
+    }
     use steven_protocol::protocol::{FixedPoint12,FixedPoint5};
     impl_serialize!(FixedPoint12, steven_protocol::protocol::Serializable);
     impl_serialize!(FixedPoint5, steven_protocol::protocol::Serializable);
-    impl_serialize!(Vec<u8>);
+    impl_serialize!(Vec<u8>, |bytes| bytes.len());
+
+    impl crate::segment::Discriminant for steven_protocol::protocol::VarInt{
+        fn to_discriminant(&self) -> i64 {
+            self.0 as i64
+        }
+
+        fn from_discriminant(value: i64) -> Option<Self> {
+            std::convert::TryFrom::try_from(value).ok().map(steven_protocol::protocol::VarInt)
+        }
+    }
+
+    impl crate::segment::Discriminant for steven_protocol::protocol::VarLong{
+        fn to_discriminant(&self) -> i64 {
+            self.0
+        }
 
+        fn from_discriminant(value: i64) -> Option<Self> {
+            Some(steven_protocol::protocol::VarLong(value))
+        }
+    }
+
+    /// NBT is checked for depth and length before being decoded, as the
+    /// decoder of stevenarella recurses and allocates without limits.
+    impl crate::segment::Segment for Option<steven_protocol::nbt::NamedTag>{
+        fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+            let mut buffer = Vec::new();
+            crate::segment::implementation::nbt::copy_named_tag(reader, &mut buffer, crate::segment::implementation::nbt::MAX_DEPTH)?;
+            *self = steven_protocol::protocol::Serializable::read_from(&mut &buffer[..]).map_err(convert_error)?;
+            Ok(())
+        }
+
+        fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+            steven_protocol::protocol::Serializable::write_to(self, writer).map_err(convert_error)?;
+            Ok(())
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::segment::Segment;
+
+    fn written_len<S: Segment>(segment: &S) -> usize {
+        let mut written = Vec::new();
+        segment.write_to_stream(&mut written).unwrap();
+        written.len()
+    }
+
+    #[test]
+    fn known_sizes_match_the_written_bytes() {
+        let string = "é".repeat(100);
+        assert_eq!(string.serialized_len(), written_len(&string));
+        let bytes = vec![7u8; 300];
+        assert_eq!(bytes.serialized_len(), written_len(&bytes));
+        let uuid = steven_protocol::protocol::UUID::default();
+        assert_eq!(uuid.serialized_len(), written_len(&uuid));
+        let prefixed = steven_protocol::protocol::LenPrefixedBytes::<steven_protocol::protocol::VarInt>::new(vec![1u8; 200]);
+        assert_eq!(prefixed.serialized_len(), written_len(&prefixed));
+    }
+
+    /// A VarInt length prefix of `i32::MAX` followed by three bytes.
+    const HUGE_PREFIX: [u8; 8] = [0xFF, 0xFF, 0xFF, 0xFF, 0x07, 1, 2, 3];
+
+    #[test]
+    fn length_prefixes_past_the_data_are_errors() {
+        use steven_protocol::protocol::{LenPrefixed, LenPrefixedBytes, VarInt};
+        let mut string = String::new();
+        assert_eq!(string.read_from_stream(&mut &HUGE_PREFIX[..]).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        let mut bytes = LenPrefixedBytes::<VarInt>::default();
+        assert_eq!(bytes.read_from_stream(&mut &HUGE_PREFIX[..]).unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+        let mut list = LenPrefixed::<VarInt, u8>::default();
+        assert!(list.read_from_stream(&mut &HUGE_PREFIX[..]).is_err());
+    }
+
+    #[test]
+    fn length_prefixed_values_round_trip() {
+        use steven_protocol::protocol::{LenPrefixed, LenPrefixedBytes, VarInt};
+        let mut bytes = Vec::new();
+        "hello".to_string().write_to_stream(&mut bytes).unwrap();
+        LenPrefixedBytes::<VarInt>::new(vec![4, 5]).write_to_stream(&mut bytes).unwrap();
+        LenPrefixed::<VarInt, u8>::new(vec![6, 7, 8]).write_to_stream(&mut bytes).unwrap();
+        let mut reader = &bytes[..];
+        let mut string = String::new();
+        string.read_from_stream(&mut reader).unwrap();
+        let mut prefixed = LenPrefixedBytes::<VarInt>::default();
+        prefixed.read_from_stream(&mut reader).unwrap();
+        let mut list = LenPrefixed::<VarInt, u8>::default();
+        list.read_from_stream(&mut reader).unwrap();
+        assert_eq!((string.as_str(), &prefixed.data[..], &list.data[..]), ("hello", &[4, 5][..], &[6, 7, 8][..]));
+        assert!(reader.is_empty());
+    }
+}
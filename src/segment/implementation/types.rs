@@ -0,0 +1,253 @@
+//! Enums and bit flags for fields that vanilla sends as plain numbers.
+//!
+//! Each enum implements `Segment` with the wire type it is sent as most of the
+//! time, and `SegmentEnum` so fields with another wire type can be declared as
+//! `field: Direction6 as u8`. Unknown values and unknown flag bits are
+//! rejected when reading.
+use crate::io::{Error, ErrorKind};
+use crate::segment::implementation::var::{read_var_int, var_int_len, write_var_int};
+use crate::segment::{Segment, SegmentEnum};
+use alloc::format;
+
+macro_rules! wire_type {
+    ($name:ident, VarInt) => {
+        impl Segment for $name{
+            fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()> {
+                *self = decode(read_var_int(reader)? as i64)?;
+                Ok(())
+            }
+
+            fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+                write_var_int(self.discriminant() as i32, writer)
+            }
+
+            fn serialized_len(&self) -> usize {
+                var_int_len(self.discriminant() as i32)
+            }
+        }
+    };
+    ($name:ident, u8) => {
+        impl Segment for $name{
+            fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()> {
+                let mut value = 0u8;
+                value.read_from_stream(reader)?;
+                *self = decode(value as i64)?;
+                Ok(())
+            }
+
+            fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()> {
+                (self.discriminant() as u8).write_to_stream(writer)
+            }
+
+            fn serialized_len(&self) -> usize {
+                1
+            }
+        }
+    };
+}
+
+crate::segment_enum!{
+    pub enum GameMode {
+        Survival = 0,
+        Creative = 1,
+        Adventure = 2,
+        Spectator = 3,
+    }
+}
+wire_type!(GameMode, u8);
+
+crate::segment_enum!{
+    pub enum Difficulty {
+        Peaceful = 0,
+        Easy = 1,
+        Normal = 2,
+        Hard = 3,
+    }
+}
+wire_type!(Difficulty, u8);
+
+crate::segment_enum!{
+    pub enum Hand {
+        MainHand = 0,
+        OffHand = 1,
+    }
+}
+wire_type!(Hand, VarInt);
+
+crate::segment_enum!{
+    /// Where a chat message is shown.
+    pub enum ChatPosition {
+        Chat = 0,
+        System = 1,
+        /// Above the hotbar.
+        GameInfo = 2,
+    }
+}
+wire_type!(ChatPosition, u8);
+
+crate::segment_enum!{
+    /// The face of a block.
+    pub enum Direction6 {
+        Down = 0,
+        Up = 1,
+        North = 2,
+        South = 3,
+        West = 4,
+        East = 5,
+    }
+}
+wire_type!(Direction6, VarInt);
+
+impl Direction6{
+    /// The unit offset towards the neighbouring block on this side.
+    pub fn offset(&self) -> (i32, i32, i32) {
+        match self {
+            Direction6::Down => (0, -1, 0),
+            Direction6::Up => (0, 1, 0),
+            Direction6::North => (0, 0, -1),
+            Direction6::South => (0, 0, 1),
+            Direction6::West => (-1, 0, 0),
+            Direction6::East => (1, 0, 0)
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction6::Down => Direction6::Up,
+            Direction6::Up => Direction6::Down,
+            Direction6::North => Direction6::South,
+            Direction6::South => Direction6::North,
+            Direction6::West => Direction6::East,
+            Direction6::East => Direction6::West
+        }
+    }
+}
+
+crate::segment_enum!{
+    pub enum DiggingStatus {
+        Started = 0,
+        Cancelled = 1,
+        Finished = 2,
+        DropItemStack = 3,
+        DropItem = 4,
+        /// Shooting a bow or finishing to eat.
+        ReleaseUseItem = 5,
+        SwapItemInHand = 6,
+    }
+}
+wire_type!(DiggingStatus, VarInt);
+
+crate::segment_enum!{
+    pub enum ClientStatusAction {
+        PerformRespawn = 0,
+        RequestStats = 1,
+    }
+}
+wire_type!(ClientStatusAction, VarInt);
+
+crate::segment_flags!{
+    /// Abilities of PlayerAbilities. ClientAbilities only carries `FLYING`.
+    pub struct AbilityFlags: u8 {
+        const INVULNERABLE = 0x01;
+        const FLYING = 0x02;
+        const ALLOW_FLYING = 0x04;
+        /// Blocks break instantly.
+        const CREATIVE_MODE = 0x08;
+    }
+}
+
+crate::segment_flags!{
+    pub struct SteerFlags: u8 {
+        const JUMP = 0x01;
+        const UNMOUNT = 0x02;
+    }
+}
+
+crate::segment_flags!{
+    /// The coordinates of a TeleportPlayer that are relative to the current position.
+    pub struct RelativeTeleportFlags: u8 {
+        const X = 0x01;
+        const Y = 0x02;
+        const Z = 0x04;
+        const YAW = 0x08;
+        const PITCH = 0x10;
+    }
+}
+
+crate::segment_flags!{
+    /// The enabled layers of a player skin. Vanilla clients also send the
+    /// unused bit 0x80, which is kept.
+    pub struct SkinParts: u8, retain_unknown {
+        const CAPE = 0x01;
+        const JACKET = 0x02;
+        const LEFT_SLEEVE = 0x04;
+        const RIGHT_SLEEVE = 0x08;
+        const LEFT_PANTS_LEG = 0x10;
+        const RIGHT_PANTS_LEG = 0x20;
+        const HAT = 0x40;
+    }
+}
+
+crate::segment_flags!{
+    pub struct CommandBlockFlags: u8 {
+        const TRACK_OUTPUT = 0x01;
+        const CONDITIONAL = 0x02;
+        const AUTOMATIC = 0x04;
+    }
+}
+
+crate::segment_flags!{
+    pub struct StructureBlockFlags: u8 {
+        const IGNORE_ENTITIES = 0x01;
+        const SHOW_AIR = 0x02;
+        const SHOW_BOUNDING_BOX = 0x04;
+    }
+}
+
+crate::segment_flags!{
+    /// Which of the optional fields of StopSound are present.
+    pub struct StopSoundFlags: u8 {
+        const SOURCE = 0x01;
+        const SOUND = 0x02;
+    }
+}
+
+crate::segment_flags!{
+    pub struct BossBarFlags: u8 {
+        const DARKEN_SKY = 0x01;
+        const DRAGON_BAR = 0x02;
+        const CREATE_FOG = 0x04;
+    }
+}
+
+fn decode<E: SegmentEnum>(value: i64) -> crate::io::Result<E> {
+    E::from_discriminant(value).ok_or_else(|| Error::new(
+        ErrorKind::InvalidData,
+        format!("{} is not a valid {}", value, core::any::type_name::<E>())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn skin_parts_keep_unknown_bits() {
+        let mut parts = SkinParts::default();
+        parts.read_from_stream(&mut &[0xFF][..]).unwrap();
+        assert_eq!(parts.bits(), 0xFF);
+        assert!(parts.contains(SkinParts::HAT));
+        assert!(format!("{:?}", parts).ends_with("HAT | 0x80)"));
+        let mut written = Vec::new();
+        parts.write_to_stream(&mut written).unwrap();
+        assert_eq!(written, [0xFF]);
+    }
+
+    #[test]
+    fn other_flags_reject_unknown_bits() {
+        let mut flags = SteerFlags::default();
+        let error = flags.read_from_stream(&mut &[0x04][..]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,343 @@
+//! Variable length integers as used by the Minecraft protocol. These do not
+//! depend on any protocol implementation so the framing layer can use them.
+//!
+//! Like vanilla, the readers accept overlong encodings such as `[0x80, 0x00]`
+//! for zero. Proxies that re-encode such numbers produce different bytes than
+//! the backend sees, which exploit tooling uses to desync them. Within
+//! `canonical` only the shortest encoding of every number is accepted.
+use crate::io::{read_array, Error, ErrorKind};
+use alloc::format;
+#[cfg(feature = "std")]
+use std::cell::Cell;
+
+const SEGMENT_BITS: u8 = 0x7F;
+const CONTINUE_BIT: u8 = 0x80;
+
+#[cfg(feature = "std")]
+thread_local! {
+    static CANONICAL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f` with non-canonical VarInts and VarLongs rejected on this thread.
+#[cfg(feature = "std")]
+pub fn canonical<T>(f: impl FnOnce() -> T) -> T {
+    with_canonical(true, f)
+}
+
+/// Runs `f` with non-canonical encodings rejected or accepted on this thread.
+#[cfg(feature = "std")]
+pub fn with_canonical<T>(canonical: bool, f: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+
+    impl Drop for Restore{
+        fn drop(&mut self) {
+            CANONICAL.with(|canonical| canonical.set(self.0));
+        }
+    }
+
+    let _restore = Restore(CANONICAL.with(|current| current.replace(canonical)));
+    f()
+}
+
+/// Whether the readers currently reject non-canonical encodings, see `canonical`.
+/// Always `false` without the `std` feature.
+pub fn is_canonical() -> bool {
+    #[cfg(feature = "std")]
+    return CANONICAL.with(Cell::get);
+    #[cfg(not(feature = "std"))]
+    return false;
+}
+
+/// Checks the last byte of an encoding: it may only be zero if it is the only
+/// byte, and may not carry bits beyond the width of the number.
+fn check_canonical(byte: u8, index: usize, last_index: usize, unused_bits: u8, name: &str) -> crate::io::Result<()> {
+    if index > 0 && byte == 0 {
+        return Err(Error::new(ErrorKind::InvalidData, format!("overlong {}", name)));
+    }
+    if index == last_index && byte & unused_bits != 0 {
+        return Err(Error::new(ErrorKind::InvalidData, format!("{} too big", name)));
+    }
+    Ok(())
+}
+
+/// Amount of bytes the VarInt encoding of `value` takes.
+#[inline]
+pub fn var_int_len(value: i32) -> usize {
+    var_long_len(value as u32 as i64)
+}
+
+/// Amount of bytes the VarLong encoding of `value` takes.
+#[inline]
+pub fn var_long_len(value: i64) -> usize {
+    let bits = 64 - (value as u64).leading_zeros() as usize;
+    core::cmp::max(1, bits.div_ceil(7))
+}
+
+/// Decodes a VarInt from the front of `bytes`, returning it with the amount of
+/// bytes it took. Works on the slice directly, without going through `Read`.
+#[inline]
+pub fn decode_var_int(bytes: &[u8]) -> crate::io::Result<(i32, usize)> {
+    match bytes.first() {
+        Some(&byte) if byte & CONTINUE_BIT == 0 => Ok((byte as i32, 1)),
+        _ => decode_var_long_bytes(bytes, 5, 0x70, "VarInt").map(|(value, len)| (value as u32 as i32, len))
+    }
+}
+
+/// Decodes a VarLong from the front of `bytes`, see `decode_var_int`.
+#[inline]
+pub fn decode_var_long(bytes: &[u8]) -> crate::io::Result<(i64, usize)> {
+    match bytes.first() {
+        Some(&byte) if byte & CONTINUE_BIT == 0 => Ok((byte as i64, 1)),
+        _ => decode_var_long_bytes(bytes, 10, 0x7E, "VarLong").map(|(value, len)| (value as i64, len))
+    }
+}
+
+fn decode_var_long_bytes(bytes: &[u8], max_len: usize, unused_bits: u8, name: &str) -> crate::io::Result<(u64, usize)> {
+    let mut value: u64 = 0;
+    for (i, &byte) in bytes.iter().take(max_len).enumerate() {
+        value |= ((byte & SEGMENT_BITS) as u64) << (7 * i);
+        if byte & CONTINUE_BIT == 0 {
+            if is_canonical() {
+                check_canonical(byte, i, max_len - 1, unused_bits, name)?;
+            }
+            return Ok((value, i + 1));
+        }
+    }
+    if bytes.len() < max_len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, format!("{} ended prematurely", name)));
+    }
+    Err(Error::new(ErrorKind::InvalidData, format!("{} too big", name)))
+}
+
+/// Encodes a VarInt into a stack buffer, returning it with the amount of bytes used.
+#[inline]
+pub fn encode_var_int(value: i32) -> ([u8; 5], usize) {
+    let mut bytes = [0; 5];
+    let len = encode_into(value as u32 as u64, &mut bytes);
+    (bytes, len)
+}
+
+/// Encodes a VarLong into a stack buffer, see `encode_var_int`.
+#[inline]
+pub fn encode_var_long(value: i64) -> ([u8; 10], usize) {
+    let mut bytes = [0; 10];
+    let len = encode_into(value as u64, &mut bytes);
+    (bytes, len)
+}
+
+#[inline]
+fn encode_into(mut value: u64, bytes: &mut [u8]) -> usize {
+    for (i, slot) in bytes.iter_mut().enumerate() {
+        let byte = (value & SEGMENT_BITS as u64) as u8;
+        value >>= 7;
+        if value == 0 {
+            *slot = byte;
+            return i + 1;
+        }
+        *slot = byte | CONTINUE_BIT;
+    }
+    bytes.len()
+}
+
+/// Reads a VarInt. `Read` does not allow reading past the end of the number,
+/// so its bytes are read onto the stack one at a time and decoded by the same
+/// code as `decode_var_int`. Readers over a slice at hand are faster with
+/// `decode_var_int`.
+#[inline]
+pub fn read_var_int<R: crate::io::Read>(reader: &mut R) -> crate::io::Result<i32> {
+    let byte = read_array::<1, _>(reader)?[0];
+    if byte & CONTINUE_BIT == 0 {
+        return Ok(byte as i32);
+    }
+    let mut bytes = [0; 5];
+    let len = read_encoding(reader, byte, &mut bytes)?;
+    decode_var_int(&bytes[..len]).map(|(value, _)| value)
+}
+
+/// Reads a VarLong, see `read_var_int`.
+#[inline]
+pub fn read_var_long<R: crate::io::Read>(reader: &mut R) -> crate::io::Result<i64> {
+    let byte = read_array::<1, _>(reader)?[0];
+    if byte & CONTINUE_BIT == 0 {
+        return Ok(byte as i64);
+    }
+    let mut bytes = [0; 10];
+    let len = read_encoding(reader, byte, &mut bytes)?;
+    decode_var_long(&bytes[..len]).map(|(value, _)| value)
+}
+
+/// Reads the bytes after `first` into `bytes` up to the last one of the
+/// number, or until `bytes` is full, returning how many it holds. Kept out of
+/// line so the common single byte case inlines small.
+fn read_encoding<R: crate::io::Read>(reader: &mut R, first: u8, bytes: &mut [u8]) -> crate::io::Result<usize> {
+    bytes[0] = first;
+    for i in 1..bytes.len() {
+        reader.read_exact(&mut bytes[i..=i])?;
+        if bytes[i] & CONTINUE_BIT == 0 {
+            return Ok(i + 1);
+        }
+    }
+    Ok(bytes.len())
+}
+
+/// Writes the encoding with a single `write_all`.
+#[inline]
+pub fn write_var_int<W: crate::io::Write>(value: i32, writer: &mut W) -> crate::io::Result<()> {
+    let (bytes, len) = encode_var_int(value);
+    writer.write_all(&bytes[..len])
+}
+
+#[inline]
+pub fn write_var_long<W: crate::io::Write>(value: i64, writer: &mut W) -> crate::io::Result<()> {
+    let (bytes, len) = encode_var_long(value);
+    writer.write_all(&bytes[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Reads `bytes` with both the `Read` based and the slice based decoder,
+    /// which have to agree on the value, the length and the kind of error.
+    fn read_both(bytes: &[u8]) -> (crate::io::Result<(i32, usize)>, crate::io::Result<(i64, usize)>) {
+        let mut reader = bytes;
+        let int = read_var_int(&mut reader).map(|value| (value, bytes.len() - reader.len()));
+        let decoded = decode_var_int(bytes);
+        assert_eq!(int.as_ref().ok(), decoded.as_ref().ok(), "VarInt {:02X?}", bytes);
+        assert_eq!(int.as_ref().err().map(Error::kind), decoded.as_ref().err().map(Error::kind), "VarInt {:02X?}", bytes);
+        let mut reader = bytes;
+        let long = read_var_long(&mut reader).map(|value| (value, bytes.len() - reader.len()));
+        let decoded = decode_var_long(bytes);
+        assert_eq!(long.as_ref().ok(), decoded.as_ref().ok(), "VarLong {:02X?}", bytes);
+        assert_eq!(long.as_ref().err().map(Error::kind), decoded.as_ref().err().map(Error::kind), "VarLong {:02X?}", bytes);
+        (int, long)
+    }
+
+    #[test]
+    fn reader_agrees_with_slice_decoder() {
+        let mut inputs: Vec<Vec<u8>> = Vec::new();
+        for value in [0, 1, 127, 128, 255, 300, 2_097_151, i32::MAX, -1, i32::MIN] {
+            let (bytes, len) = encode_var_int(value);
+            inputs.push(bytes[..len].to_vec());
+        }
+        for value in [i64::MAX, i64::MIN, -1, 1 << 35] {
+            let (bytes, len) = encode_var_long(value);
+            inputs.push(bytes[..len].to_vec());
+        }
+        inputs.push(alloc::vec![0x80, 0x00]);
+        inputs.push(alloc::vec![0x80, 0x80]);
+        inputs.push(alloc::vec![0xFF; 11]);
+        inputs.push(alloc::vec![0x01, 0xFF]);
+        for input in &inputs {
+            read_both(input);
+        }
+    }
+
+    #[test]
+    fn reader_stops_after_the_number() {
+        let mut reader = &[0xAC, 0x02, 0x07][..];
+        assert_eq!(read_var_int(&mut reader).unwrap(), 300);
+        assert_eq!(reader, &[0x07]);
+    }
+
+    /// `value` encoded with `extra` needless continuation bytes.
+    fn overlong(value: u64, extra: usize) -> Vec<u8> {
+        let mut bytes = [0; 10];
+        let len = encode_into(value, &mut bytes);
+        let mut bytes = bytes[..len].to_vec();
+        for _ in 0..extra {
+            *bytes.last_mut().unwrap() |= CONTINUE_BIT;
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    #[test]
+    fn every_length_boundary() {
+        for len in 1..5 {
+            let last = (1i64 << (7 * len)) - 1;
+            assert_eq!(var_int_len(last as i32), len);
+            assert_eq!(var_int_len((last + 1) as i32), len + 1);
+        }
+        assert_eq!(var_int_len(i32::MAX), 5);
+        for len in 1..10 {
+            let last = (1u64 << (7 * len)) - 1;
+            assert_eq!(var_long_len(last as i64), len);
+            assert_eq!(var_long_len((last + 1) as i64), len + 1);
+        }
+        canonical(|| {
+            for len in 1..5 {
+                for value in [(1i64 << (7 * len)) - 1, 1i64 << (7 * len)] {
+                    let (bytes, written) = encode_var_int(value as i32);
+                    assert_eq!(written, var_int_len(value as i32));
+                    assert_eq!(read_both(&bytes[..written]).0.unwrap(), (value as i32, written));
+                }
+            }
+            for len in 1..10 {
+                for value in [(1u64 << (7 * len)) - 1, 1u64 << (7 * len)] {
+                    let (bytes, written) = encode_var_long(value as i64);
+                    assert_eq!(written, var_long_len(value as i64));
+                    assert_eq!(read_both(&bytes[..written]).1.unwrap(), (value as i64, written));
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn overlong_encodings() {
+        for value in [0u64, 1, 5, 127, 128, 300] {
+            let len = var_long_len(value as i64);
+            for extra in 1..=(10 - len) {
+                let bytes = overlong(value, extra);
+                let (int, long) = read_both(&bytes);
+                if len + extra <= 5 {
+                    assert_eq!(int.unwrap(), (value as i32, len + extra));
+                }
+                assert_eq!(long.unwrap(), (value as i64, len + extra));
+                let (int, long) = canonical(|| read_both(&bytes));
+                if len + extra <= 5 {
+                    assert_eq!(int.unwrap_err().kind(), ErrorKind::InvalidData);
+                }
+                assert_eq!(long.unwrap_err().kind(), ErrorKind::InvalidData);
+            }
+        }
+    }
+
+    #[test]
+    fn unused_bits_of_the_last_byte() {
+        // -1 and i64::MIN with bits set beyond the width of the number.
+        for last in [0x1F, 0x2F, 0x4F, 0x7F] {
+            let bytes = [0xFF, 0xFF, 0xFF, 0xFF, last];
+            assert_eq!(read_both(&bytes).0.unwrap(), (-1, 5));
+            assert_eq!(canonical(|| read_both(&bytes)).0.unwrap_err().kind(), ErrorKind::InvalidData);
+        }
+        for last in [0x03, 0x41, 0x7F] {
+            let mut bytes = [0x80; 10];
+            bytes[9] = last;
+            assert_eq!(read_both(&bytes).1.unwrap().1, 10);
+            assert_eq!(canonical(|| read_both(&bytes)).1.unwrap_err().kind(), ErrorKind::InvalidData);
+        }
+        let mut bytes = [0x80; 10];
+        bytes[9] = 0x01;
+        assert_eq!(canonical(|| read_both(&bytes)).1.unwrap(), (i64::MIN, 10));
+    }
+
+    #[test]
+    fn negative_round_trips() {
+        canonical(|| {
+            for value in [-1, i32::MIN, i32::MIN + 1, i32::MAX] {
+                let (bytes, len) = encode_var_int(value);
+                assert_eq!(len, 5);
+                assert_eq!(read_both(&bytes[..len]).0.unwrap(), (value, 5));
+            }
+            for value in [-1, i64::MIN, i64::MIN + 1, i64::MAX] {
+                let (bytes, len) = encode_var_long(value);
+                let expected = if value == i64::MAX { 9 } else { 10 };
+                assert_eq!(len, expected);
+                assert_eq!(read_both(&bytes[..len]).1.unwrap(), (value, expected));
+            }
+        });
+    }
+}
+
@@ -1,6 +1,467 @@
+#[cfg(feature = "std")]
+pub mod context;
 pub mod implementation;
 
+// The common segment types under a path that stays put when their
+// implementation moves, e.g. from steven_protocol to this crate.
+#[cfg(feature = "steven_protocol")]
+pub use steven_protocol::protocol::{LenPrefixed, LenPrefixedBytes, VarInt, VarLong};
+#[cfg(feature = "std")]
+pub use self::implementation::mojang::{identifier::Identifier, slot::Slot};
+#[cfg(feature = "std")]
+pub use self::implementation::nbt::{NetworkNbt, Tag};
+
+use alloc::format;
+
 pub trait Segment: Default{
-    fn read_from_stream<R: std::io::Read>(&mut self, reader: &mut R) -> std::io::Result<()>;
-    fn write_to_stream<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()>;
-}
\ No newline at end of file
+    fn read_from_stream<R: crate::io::Read>(&mut self, reader: &mut R) -> crate::io::Result<()>;
+    fn write_to_stream<W: crate::io::Write>(&self, writer: &mut W) -> crate::io::Result<()>;
+
+    /// The exact amount of bytes `write_to_stream` will produce.
+    ///
+    /// A segment that fails to serialize counts as empty here, use
+    /// `try_serialized_len` where the length ends up on the wire. Implementors
+    /// that know their size upfront should override it.
+    fn serialized_len(&self) -> usize {
+        self.try_serialized_len().unwrap_or(0)
+    }
+
+    /// Like `serialized_len`, but fails with the error `write_to_stream` would
+    /// fail with.
+    ///
+    /// The default implementation serializes into a counting sink, segments
+    /// containing fallible segments should override it if they override
+    /// `serialized_len`.
+    fn try_serialized_len(&self) -> crate::io::Result<usize> {
+        let mut counter = ByteCounter::default();
+        // ByteCounter never fails, so an error can only originate from the segment itself.
+        self.write_to_stream(&mut counter)?;
+        Ok(counter.0)
+    }
+
+    /// Reads the segment as sent by the given protocol version, for segments
+    /// that differ between versions. See `context::DecodeContext`.
+    #[cfg(feature = "std")]
+    fn read_versioned<R: crate::io::Read>(&mut self, reader: &mut R, version: i32) -> crate::io::Result<()> {
+        context::versioned(version).enter(|| self.read_from_stream(reader))
+    }
+
+    /// Writes the segment as the given protocol version expects it.
+    #[cfg(feature = "std")]
+    fn write_versioned<W: crate::io::Write>(&self, writer: &mut W, version: i32) -> crate::io::Result<()> {
+        context::versioned(version).enter(|| self.write_to_stream(writer))
+    }
+}
+
+/// A numeric segment that can carry the discriminant of a `SegmentEnum`.
+pub trait Discriminant: Segment{
+    fn to_discriminant(&self) -> i64;
+    /// Returns `None` if the value does not fit.
+    fn from_discriminant(value: i64) -> Option<Self>;
+}
+
+/// An enum that is sent as a number on the wire, see `segment_enum!`.
+///
+/// Fields of such a type are declared as `field: MyEnum as VarInt` in
+/// `define_protocol!`, which rejects unknown discriminants when reading.
+pub trait SegmentEnum: Sized + Default{
+    fn from_discriminant(value: i64) -> Option<Self>;
+    fn discriminant(&self) -> i64;
+}
+
+pub fn read_enum<E: SegmentEnum, D: Discriminant, R: crate::io::Read>(reader: &mut R) -> crate::io::Result<E> {
+    let mut repr = D::default();
+    repr.read_from_stream(reader)?;
+    let value = repr.to_discriminant();
+    E::from_discriminant(value).ok_or_else(|| crate::io::Error::new(
+        crate::io::ErrorKind::InvalidData,
+        format!("{} is not a valid {}", value, core::any::type_name::<E>())
+    ))
+}
+
+pub fn write_enum<E: SegmentEnum, D: Discriminant, W: crate::io::Write>(value: &E, writer: &mut W) -> crate::io::Result<()> {
+    enum_repr::<E, D>(value)?.write_to_stream(writer)
+}
+
+pub fn enum_len<E: SegmentEnum, D: Discriminant>(value: &E) -> usize {
+    try_enum_len::<E, D>(value).unwrap_or(0)
+}
+
+pub fn try_enum_len<E: SegmentEnum, D: Discriminant>(value: &E) -> crate::io::Result<usize> {
+    enum_repr::<E, D>(value)?.try_serialized_len()
+}
+
+fn enum_repr<E: SegmentEnum, D: Discriminant>(value: &E) -> crate::io::Result<D> {
+    D::from_discriminant(value.discriminant()).ok_or_else(|| crate::io::Error::new(
+        crate::io::ErrorKind::InvalidInput,
+        format!("{} does not fit the wire type of {}", value.discriminant(), core::any::type_name::<E>())
+    ))
+}
+
+/// Puts the attributes on the item, along with the serde derives when this crate
+/// has the `serde` feature. Defined per feature, as a `cfg` inside an exported
+/// macro would check the features of the calling crate.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serde_item {
+    ([$(#[$meta:meta])*] $item:item) => {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        $(#[$meta])*
+        $item
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serde_item {
+    ([$(#[$meta:meta])*] $item:item) => {
+        $(#[$meta])*
+        $item
+    };
+}
+
+/// Declares an enum that implements `SegmentEnum`. The first variant is the default.
+///
+/// ```ignore
+/// segment_enum!{
+///     pub enum RecipeBookType {
+///         Crafting = 0,
+///         Furnace = 1,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! segment_enum {
+    ($(#[$enum_meta:meta])* $vis:vis enum $name:ident {$(#[$first_meta:meta])* $first:ident = $first_value:literal $(, $(#[$variant_meta:meta])* $variant:ident = $value:literal)*$(,)?}) => {
+        $crate::__serde_item!{[]
+            $(#[$enum_meta])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            $vis enum $name {
+                $(#[$first_meta])*
+                $first,
+                $($(#[$variant_meta])* $variant),*
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::$first
+            }
+        }
+
+        impl $crate::segment::SegmentEnum for $name {
+            fn from_discriminant(value: i64) -> Option<Self> {
+                match value {
+                    $first_value => Some($name::$first),
+                    $($value => Some($name::$variant),)*
+                    _ => None
+                }
+            }
+
+            fn discriminant(&self) -> i64 {
+                match self {
+                    $name::$first => $first_value,
+                    $($name::$variant => $value),*
+                }
+            }
+        }
+    };
+}
+
+/// Declares an enum whose variant is selected by a leading tag on the wire,
+/// followed by the fields of that variant. The first variant is the default.
+///
+/// ```ignore
+/// tagged_union!{
+///     pub enum BossBarAction: VarInt {
+///         0 => Add { title: Component, health: f32 },
+///         1 => Remove,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! tagged_union {
+    ($(#[$enum_meta:meta])* $vis:vis enum $name:ident: $tag_type:ty {
+        $(#[$first_meta:meta])* $first_tag:literal => $first:ident $({$($(#[$first_field_meta:meta])* $first_field:ident: $first_type:ty),*$(,)?})?
+        $(, $(#[$variant_meta:meta])* $tag:literal => $variant:ident $({$($(#[$field_meta:meta])* $field:ident: $value_type:ty),*$(,)?})?)*$(,)?
+    }) => {
+        $crate::__serde_item!{[]
+            $(#[$enum_meta])*
+            #[derive(Debug, Clone, PartialEq)]
+            $vis enum $name {
+                $(#[$first_meta])*
+                $first $({$($(#[$first_field_meta])* $first_field: $first_type),*})?,
+                $($(#[$variant_meta])* $variant $({$($(#[$field_meta])* $field: $value_type),*})?),*
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::$first $({$($first_field: Default::default()),*})?
+            }
+        }
+
+        impl $name {
+            /// The tag that selects this variant on the wire.
+            pub fn tag(&self) -> i64 {
+                match self {
+                    $name::$first {..} => $first_tag,
+                    $($name::$variant {..} => $tag),*
+                }
+            }
+        }
+
+        impl $crate::segment::Segment for $name {
+            fn read_from_stream<R: $crate::io::Read>(&mut self, reader: &mut R) -> $crate::io::Result<()> {
+                let mut tag: $tag_type = Default::default();
+                $crate::segment::Segment::read_from_stream(&mut tag, reader)?;
+                *self = match $crate::segment::Discriminant::to_discriminant(&tag) {
+                    $first_tag => $name::$first $({$($first_field: {
+                        let mut field: $first_type = Default::default();
+                        $crate::segment::Segment::read_from_stream(&mut field, reader)?;
+                        field
+                    }),*})?,
+                    $($tag => $name::$variant $({$($field: {
+                        let mut field: $value_type = Default::default();
+                        $crate::segment::Segment::read_from_stream(&mut field, reader)?;
+                        field
+                    }),*})?,)*
+                    tag => return Err($crate::io::Error::new(
+                        $crate::io::ErrorKind::InvalidData,
+                        $crate::__private::format!("{} is not a valid {} tag", tag, stringify!($name))
+                    ))
+                };
+                Ok(())
+            }
+
+            fn write_to_stream<W: $crate::io::Write>(&self, writer: &mut W) -> $crate::io::Result<()> {
+                let tag: $tag_type = $crate::segment::Discriminant::from_discriminant(self.tag()).ok_or_else(|| $crate::io::Error::new(
+                    $crate::io::ErrorKind::InvalidInput,
+                    $crate::__private::format!("{} tag does not fit its wire type", stringify!($name))
+                ))?;
+                $crate::segment::Segment::write_to_stream(&tag, writer)?;
+                match self {
+                    $name::$first $({$($first_field),*})? => {
+                        $($($crate::segment::Segment::write_to_stream($first_field, writer)?;)*)?
+                    }
+                    $($name::$variant $({$($field),*})? => {
+                        $($($crate::segment::Segment::write_to_stream($field, writer)?;)*)?
+                    })*
+                }
+                Ok(())
+            }
+
+            fn serialized_len(&self) -> usize {
+                let tag: Option<$tag_type> = $crate::segment::Discriminant::from_discriminant(self.tag());
+                let mut len = tag.map_or(0, |tag| $crate::segment::Segment::serialized_len(&tag));
+                match self {
+                    $name::$first $({$($first_field),*})? => {
+                        $($(len += $crate::segment::Segment::serialized_len($first_field);)*)?
+                    }
+                    $($name::$variant $({$($field),*})? => {
+                        $($(len += $crate::segment::Segment::serialized_len($field);)*)?
+                    })*
+                }
+                len
+            }
+
+            fn try_serialized_len(&self) -> $crate::io::Result<usize> {
+                let tag: $tag_type = $crate::segment::Discriminant::from_discriminant(self.tag()).ok_or_else(|| $crate::io::Error::new(
+                    $crate::io::ErrorKind::InvalidInput,
+                    $crate::__private::format!("{} tag does not fit its wire type", stringify!($name))
+                ))?;
+                let mut len = $crate::segment::Segment::try_serialized_len(&tag)?;
+                match self {
+                    $name::$first $({$($first_field),*})? => {
+                        $($(len += $crate::segment::Segment::try_serialized_len($first_field)?;)*)?
+                    }
+                    $($name::$variant $({$($field),*})? => {
+                        $($(len += $crate::segment::Segment::try_serialized_len($field)?;)*)?
+                    })*
+                }
+                Ok(len)
+            }
+        }
+    };
+}
+
+/// Declares a set of bit flags sent as an integer. Bits without a declared
+/// flag are rejected when reading, and only `from_bits_retain` sets them.
+/// With `retain_unknown` after the integer type they are kept instead, for
+/// flags vanilla sends undeclared bits of. `bits` returns them, and they are
+/// written back as read.
+///
+/// ```ignore
+/// segment_flags!{
+///     pub struct SteerFlags: u8 {
+///         const JUMP = 0x01;
+///         const UNMOUNT = 0x02;
+///     }
+/// }
+///
+/// segment_flags!{
+///     pub struct SkinParts: u8, retain_unknown {
+///         const CAPE = 0x01;
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! segment_flags {
+    ($(#[$struct_meta:meta])* $vis:vis struct $name:ident: $repr:ty {$($(#[$flag_meta:meta])* const $flag:ident = $value:literal;)+}) => {
+        $crate::segment_flags!(@define reject, $(#[$struct_meta])* $vis struct $name: $repr {$($(#[$flag_meta])* const $flag = $value;)+});
+    };
+    ($(#[$struct_meta:meta])* $vis:vis struct $name:ident: $repr:ty, retain_unknown {$($(#[$flag_meta:meta])* const $flag:ident = $value:literal;)+}) => {
+        $crate::segment_flags!(@define retain, $(#[$struct_meta])* $vis struct $name: $repr {$($(#[$flag_meta])* const $flag = $value;)+});
+    };
+    (@read reject, $name:ident, $bits:ident) => {
+        $name::from_bits($bits).ok_or_else(|| $crate::io::Error::new(
+            $crate::io::ErrorKind::InvalidData,
+            $crate::__private::format!("{:#x} sets unknown {} bits", $bits, stringify!($name))
+        ))
+    };
+    (@read retain, $name:ident, $bits:ident) => {
+        $crate::io::Result::Ok($name::from_bits_retain($bits))
+    };
+    (@define $mode:ident, $(#[$struct_meta:meta])* $vis:vis struct $name:ident: $repr:ty {$($(#[$flag_meta:meta])* const $flag:ident = $value:literal;)+}) => {
+        $crate::__serde_item!{[]
+            $(#[$struct_meta])*
+            #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+            $vis struct $name($repr);
+        }
+
+        impl $name {
+            $($(#[$flag_meta])* pub const $flag: $name = $name($value);)+
+
+            pub const fn empty() -> Self {
+                $name(0)
+            }
+
+            pub const fn all() -> Self {
+                $name(0 $(| $value)+)
+            }
+
+            pub const fn bits(&self) -> $repr {
+                self.0
+            }
+
+            /// Returns `None` if any bit without a flag is set.
+            pub fn from_bits(bits: $repr) -> Option<Self> {
+                if bits & !Self::all().0 == 0 {
+                    Some($name(bits))
+                }else{
+                    None
+                }
+            }
+
+            /// Drops the bits without a flag.
+            pub const fn from_bits_truncate(bits: $repr) -> Self {
+                $name(bits & Self::all().0)
+            }
+
+            /// Keeps the bits without a flag, which are written as they are.
+            pub const fn from_bits_retain(bits: $repr) -> Self {
+                $name(bits)
+            }
+
+            pub const fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+
+            /// Whether all flags of `other` are set.
+            pub const fn contains(&self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+
+            pub fn set(&mut self, other: Self, value: bool) {
+                if value {
+                    self.insert(other)
+                }else{
+                    self.remove(other)
+                }
+            }
+        }
+
+        impl core::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, other: Self) -> Self {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl core::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+        }
+
+        impl core::ops::BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, other: Self) -> Self {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, "{}(", stringify!($name))?;
+                let names = [$((stringify!($flag), $name::$flag)),+];
+                let mut separator = "";
+                for (name, _) in names.iter().filter(|(_, flag)| self.contains(*flag)) {
+                    write!(f, "{}{}", separator, name)?;
+                    separator = " | ";
+                }
+                let unknown = self.0 & !Self::all().0;
+                if unknown != 0 {
+                    write!(f, "{}{:#x}", separator, unknown)?;
+                }
+                f.write_str(")")
+            }
+        }
+
+        impl $crate::segment::Segment for $name {
+            fn read_from_stream<R: $crate::io::Read>(&mut self, reader: &mut R) -> $crate::io::Result<()> {
+                let mut bits: $repr = 0;
+                $crate::segment::Segment::read_from_stream(&mut bits, reader)?;
+                *self = $crate::segment_flags!(@read $mode, $name, bits)?;
+                Ok(())
+            }
+
+            fn write_to_stream<W: $crate::io::Write>(&self, writer: &mut W) -> $crate::io::Result<()> {
+                $crate::segment::Segment::write_to_stream(&self.0, writer)
+            }
+
+            fn serialized_len(&self) -> usize {
+                core::mem::size_of::<$repr>()
+            }
+        }
+    };
+}
+
+/// A writer that discards everything but the amount of bytes written to it.
+#[derive(Debug, Default)]
+pub struct ByteCounter(pub usize);
+
+impl crate::io::Write for ByteCounter{
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> crate::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> crate::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,447 @@
+//! Accepting connections: the server side of the handshake, status ping and
+//! login, handing out a `Session` for every player that made it into play.
+//!
+//! The listener speaks the protocol of 1.17.1, clients of other versions are
+//! told that they are outdated. It runs in offline mode. Behind BungeeCord,
+//! `ServerHandler::login` can read the forwarded player from the Handshake;
+//! behind Velocity, wrap the handler in `proxy::velocity::VelocityForwarding`.
+//! Accepted logins go through a `LoginGuard`, which throttles addresses and
+//! handles players logging in twice as configured in `ListenerOptions`.
+use crate::chat::Component;
+use crate::connection::disconnect::HalfClose;
+use crate::connection::keep_alive::{KeepAliveTracker, Role};
+use crate::connection::stream::PacketStream;
+use crate::protocol::implementation::steven::v1_17::*;
+use crate::protocol::version::ProtocolVersion;
+use crate::protocol::{Direction, Packet, Protocol, State};
+use crate::proxy::bungeecord::ProfileProperty;
+use crate::segment::implementation::var::write_var_int;
+use crate::segment::Segment;
+use crate::server::login::{offline_uuid, validate_username, Admission, DuplicateLoginPolicy, LoginGuard, LoginRejection, LoginThrottle, DUPLICATE_LOGIN_JSON};
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use steven_protocol::protocol::{VarInt, UUID};
+
+/// The player a login is accepted for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile{
+    pub uuid: u128,
+    pub username: String,
+    /// Profile properties such as the skin, when a proxy forwarded them.
+    pub properties: Vec<ProfileProperty>
+}
+
+/// The answer to a LoginPluginRequest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginResponse{
+    pub message_id: i32,
+    /// `None` when the client did not understand the channel.
+    pub data: Option<Vec<u8>>
+}
+
+/// Callbacks deciding what the listener answers.
+pub trait ServerHandler{
+    /// The JSON status shown in the server list, see `status::Status`.
+    fn status(&self, handshake: &Handshake, address: SocketAddr) -> String;
+
+    /// Decides whether a player may join. `Err` holds the disconnect reason as a
+    /// chat component. By default everyone joins with their offline mode UUID.
+    fn login(&self, handshake: &Handshake, username: &str, address: SocketAddr) -> Result<Profile, String> {
+        let _ = (handshake, address);
+        Ok(Profile{ uuid: offline_uuid(username), username: username.to_string(), properties: Vec::new() })
+    }
+
+    /// LoginPluginRequests to send after LoginStart, as message id, channel
+    /// and data. The ids have to be unique. By default none are sent.
+    fn login_plugin_requests(&self, handshake: &Handshake, username: &str) -> Vec<(i32, String, Vec<u8>)> {
+        let _ = (handshake, username);
+        Vec::new()
+    }
+
+    /// Like `login`, once every request of `login_plugin_requests` has been
+    /// answered. By default the responses are ignored and `login` decides.
+    fn login_with_plugins(&self, handshake: &Handshake, username: &str, address: SocketAddr, responses: &[PluginResponse]) -> Result<Profile, String> {
+        let _ = responses;
+        self.login(handshake, username, address)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerOptions{
+    /// Packets of at least this many bytes are compressed, `None` disables compression.
+    pub compression_threshold: Option<usize>,
+    /// Minimum time between logins from one address, `None` disables the throttle.
+    pub login_throttle: Option<Duration>,
+    pub duplicate_login: DuplicateLoginPolicy
+}
+
+impl Default for ListenerOptions{
+    fn default() -> Self {
+        ListenerOptions{
+            // The default of server.properties
+            compression_threshold: Some(256),
+            login_throttle: None,
+            duplicate_login: DuplicateLoginPolicy::default()
+        }
+    }
+}
+
+/// The players online through one listener, shared by its connections.
+#[derive(Debug)]
+struct Logins{
+    guard: LoginGuard<u64>,
+    /// Sessions replaced by a newer login of the same player. They are
+    /// disconnected on their next `Session::tick`.
+    kicked: HashSet<u64>,
+    next_id: u64
+}
+
+#[derive(Debug, Clone)]
+struct SharedLogins(Arc<Mutex<Logins>>);
+
+impl SharedLogins{
+    fn new(options: &ListenerOptions) -> Self {
+        let guard = LoginGuard::new(options.login_throttle.map(LoginThrottle::new), options.duplicate_login);
+        SharedLogins(Arc::new(Mutex::new(Logins{ guard, kicked: HashSet::new(), next_id: 0 })))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Logins> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Registers the player as online, or returns why it may not join.
+    fn admit(&self, address: SocketAddr, profile: &Profile) -> Result<Admitted, LoginRejection> {
+        let now = Instant::now();
+        let mut logins = self.lock();
+        let id = logins.next_id;
+        logins.next_id += 1;
+        if let Some(throttle) = &mut logins.guard.throttle {
+            throttle.purge(now);
+        }
+        match logins.guard.admit(address.ip(), &profile.username, profile.uuid, id, now) {
+            Admission::Allowed{ kick } => {
+                logins.kicked.extend(kick);
+                Ok(Admitted{ logins: self.clone(), id, username: profile.username.clone() })
+            }
+            Admission::Rejected(rejection) => Err(rejection)
+        }
+    }
+}
+
+/// Keeps a player registered as online until its session is dropped.
+#[derive(Debug)]
+struct Admitted{
+    logins: SharedLogins,
+    id: u64,
+    username: String
+}
+
+impl Admitted{
+    fn is_kicked(&self) -> bool {
+        self.logins.lock().kicked.contains(&self.id)
+    }
+}
+
+impl Drop for Admitted{
+    fn drop(&mut self) {
+        let mut logins = self.logins.lock();
+        logins.guard.logout(&self.username, &self.id);
+        logins.kicked.remove(&self.id);
+    }
+}
+
+#[derive(Debug)]
+pub struct ServerListener{
+    listener: TcpListener,
+    options: ListenerOptions,
+    logins: SharedLogins
+}
+
+impl ServerListener{
+    pub fn bind<A: ToSocketAddrs>(address: A, options: ListenerOptions) -> std::io::Result<Self> {
+        let logins = SharedLogins::new(&options);
+        Ok(ServerListener{ listener: TcpListener::bind(address)?, options, logins })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Waits for the next connection. Run `PendingConnection::establish` on it,
+    /// preferably off the accepting thread.
+    pub fn accept(&self) -> std::io::Result<PendingConnection<TcpStream>> {
+        let (stream, address) = self.listener.accept()?;
+        stream.set_nodelay(true)?;
+        Ok(PendingConnection{ stream, address, options: self.options.clone(), logins: self.logins.clone() })
+    }
+
+    /// Accepts connections forever, establishing each on its own thread and
+    /// passing the sessions that reach play to `on_session` on that thread.
+    pub fn serve<H, F>(&self, handler: Arc<H>, on_session: F) -> std::io::Result<()>
+        where H: ServerHandler + Send + Sync + 'static, F: Fn(Session<TcpStream>) + Send + Sync + 'static {
+        let on_session = Arc::new(on_session);
+        loop {
+            let pending = self.accept()?;
+            let handler = handler.clone();
+            let on_session = on_session.clone();
+            std::thread::spawn(move || {
+                if let Ok(Some(session)) = pending.establish(&*handler) {
+                    on_session(session);
+                }
+            });
+        }
+    }
+}
+
+/// A connection that has not sent its Handshake yet.
+#[derive(Debug)]
+pub struct PendingConnection<S>{
+    stream: S,
+    address: SocketAddr,
+    options: ListenerOptions,
+    logins: SharedLogins
+}
+
+impl<S: Read + Write> PendingConnection<S>{
+    /// A connection that is not accepted by a `ServerListener`, so its login
+    /// is not checked against the players online there.
+    pub fn new(stream: S, address: SocketAddr, options: ListenerOptions) -> Self {
+        let logins = SharedLogins::new(&options);
+        PendingConnection{ stream, address, options, logins }
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Runs the handshake, and either the status ping or the login. Returns
+    /// `None` when the connection was a status ping or the login was rejected.
+    pub fn establish<H: ServerHandler + ?Sized>(self, handler: &H) -> std::io::Result<Option<Session<S>>> {
+        let address = self.address;
+        let mut stream = PacketStream::new(self.stream);
+        let handshake = match stream.read_packet::<Proto_1_17>(State::Handshaking, Direction::ServerBound)? {
+            Proto_1_17::Handshake(handshake) => *handshake,
+            other => return Err(unexpected(&other))
+        };
+        match handshake.next.0 {
+            1 => {
+                answer_status(&mut stream, handler.status(&handshake, address))?;
+                Ok(None)
+            }
+            2 => login(stream, handshake, handler, address, &self.options, &self.logins),
+            next => Err(Error::new(ErrorKind::InvalidData, format!("handshake asks for unknown state {}", next)))
+        }
+    }
+}
+
+fn answer_status<S: Read + Write>(stream: &mut PacketStream<S>, json: String) -> std::io::Result<()> {
+    match stream.read_packet::<Proto_1_17>(State::Status, Direction::ServerBound)? {
+        Proto_1_17::StatusRequest(_) => stream.write_packet(&StatusResponse{ status: json })?,
+        other => return Err(unexpected(&other))
+    }
+    match stream.read_packet::<Proto_1_17>(State::Status, Direction::ServerBound) {
+        Ok(Proto_1_17::StatusPing(ping)) => stream.write_packet(&StatusPong{ ping: ping.ping }),
+        // Clients may close the connection without pinging.
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(()),
+        Ok(other) => Err(unexpected(&other)),
+        Err(e) => Err(e)
+    }
+}
+
+fn login<S: Read + Write, H: ServerHandler + ?Sized>(mut stream: PacketStream<S>, handshake: Handshake, handler: &H, address: SocketAddr, options: &ListenerOptions, logins: &SharedLogins) -> std::io::Result<Option<Session<S>>> {
+    let username = match stream.read_packet::<Proto_1_17>(State::Login, Direction::ServerBound)? {
+        Proto_1_17::LoginStart(start) => start.username,
+        other => return Err(unexpected(&other))
+    };
+    if handshake.protocol_version.0 != Proto_1_17::PROTOCOL {
+        let older = ProtocolVersion(handshake.protocol_version.0).compare(&ProtocolVersion(Proto_1_17::PROTOCOL));
+        let reason = if older == Some(std::cmp::Ordering::Less) {
+            format!(r#"{{"translate":"multiplayer.disconnect.outdated_client","with":["{}"]}}"#, Proto_1_17::NAME)
+        }else{
+            format!(r#"{{"translate":"multiplayer.disconnect.outdated_server","with":["{}"]}}"#, Proto_1_17::NAME)
+        };
+        login_disconnect(&mut stream, &reason)?;
+        return Ok(None);
+    }
+    if let Err(error) = validate_username(&username) {
+        login_disconnect(&mut stream, &LoginRejection::InvalidUsername(error).reason_json())?;
+        return Ok(None);
+    }
+    let responses = plugin_exchange(&mut stream, handler.login_plugin_requests(&handshake, &username))?;
+    let profile = match handler.login_with_plugins(&handshake, &username, address, &responses) {
+        Ok(profile) => profile,
+        Err(reason) => {
+            login_disconnect(&mut stream, &reason)?;
+            return Ok(None);
+        }
+    };
+    let admitted = match logins.admit(address, &profile) {
+        Ok(admitted) => admitted,
+        Err(rejection) => {
+            login_disconnect(&mut stream, &rejection.reason_json())?;
+            return Ok(None);
+        }
+    };
+    if let Some(threshold) = options.compression_threshold {
+        stream.write_packet(&SetInitialCompression{ threshold: VarInt(threshold as i32) })?;
+        stream.set_compression(Some(threshold))?;
+    }
+    let mut uuid = UUID::default();
+    uuid.read_from_stream(&mut &profile.uuid.to_be_bytes()[..])?;
+    stream.write_packet(&LoginSuccess{ uuid, username: profile.username.clone() })?;
+    Ok(Some(Session{ stream, profile, handshake, address, keep_alive: KeepAliveTracker::new(Role::Server, Instant::now()), admitted }))
+}
+
+/// Sends the plugin requests and waits until the client answered all of them.
+fn plugin_exchange<S: Read + Write>(stream: &mut PacketStream<S>, requests: Vec<(i32, String, Vec<u8>)>) -> std::io::Result<Vec<PluginResponse>> {
+    let ids: Vec<i32> = requests.iter().map(|(message_id, ..)| *message_id).collect();
+    for (message_id, channel, data) in requests {
+        stream.write_packet(&LoginPluginRequest{ message_id: VarInt(message_id), channel, data })?;
+    }
+    let mut responses: Vec<PluginResponse> = Vec::with_capacity(ids.len());
+    while responses.len() < ids.len() {
+        let response = match stream.read_packet::<Proto_1_17>(State::Login, Direction::ServerBound)? {
+            Proto_1_17::LoginPluginResponse(response) => *response,
+            other => return Err(unexpected(&other))
+        };
+        let message_id = response.message_id.0;
+        if !ids.contains(&message_id) || responses.iter().any(|r| r.message_id == message_id) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("unexpected LoginPluginResponse {}", message_id)));
+        }
+        responses.push(PluginResponse{ message_id, data: if response.successful { Some(response.data) } else { None } });
+    }
+    Ok(responses)
+}
+
+/// Sends LoginDisconnect. The reason is written as-is rather than through
+/// the chat component type, so any JSON can be passed.
+fn login_disconnect<S: Read + Write>(stream: &mut PacketStream<S>, reason_json: &str) -> std::io::Result<()> {
+    write_reason(stream, LoginDisconnect::PACKET_ID, reason_json)
+}
+
+fn write_reason<S: Read + Write>(stream: &mut PacketStream<S>, packet_id: i32, reason_json: &str) -> std::io::Result<()> {
+    let mut body = Vec::with_capacity(reason_json.len() + 3);
+    write_var_int(reason_json.len() as i32, &mut body)?;
+    body.extend_from_slice(reason_json.as_bytes());
+    stream.write_raw(packet_id, &body)
+}
+
+fn unexpected(packet: &Proto_1_17) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("unexpected packet 0x{:02X}", packet.id()))
+}
+
+/// A player in the play state.
+#[derive(Debug)]
+pub struct Session<S>{
+    stream: PacketStream<S>,
+    profile: Profile,
+    handshake: Handshake,
+    address: SocketAddr,
+    keep_alive: KeepAliveTracker,
+    admitted: Admitted
+}
+
+impl<S: Read + Write> Session<S>{
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// The Handshake the client sent, e.g. for the host it connected with.
+    pub fn handshake(&self) -> &Handshake {
+        &self.handshake
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Reads the next packet. Keep-alive answers are checked before they are returned.
+    pub fn next_packet(&mut self) -> std::io::Result<Proto_1_17> {
+        let packet = self.stream.read_packet(State::Play, Direction::ServerBound)?;
+        self.keep_alive.handle(&packet, Instant::now())?;
+        Ok(packet)
+    }
+
+    /// Sends a keep-alive when one is due, failing when the client stopped
+    /// answering or the player logged in again elsewhere and was kicked. Call
+    /// this regularly, e.g. every tick.
+    pub fn tick(&mut self) -> std::io::Result<()> {
+        if self.admitted.is_kicked() {
+            write_reason(&mut self.stream, Disconnect::PACKET_ID, DUPLICATE_LOGIN_JSON)?;
+            return Err(Error::new(ErrorKind::ConnectionAborted, "the player logged in from another location"));
+        }
+        if let Some(keep_alive) = self.keep_alive.poll_packet(Instant::now())? {
+            self.stream.write_packet(&keep_alive)?;
+        }
+        Ok(())
+    }
+
+    /// The round trip time measured with the last keep-alive.
+    pub fn latency(&self) -> Option<Duration> {
+        self.keep_alive.latency()
+    }
+
+    /// Sends a play packet. Serverbound packets and those of other states do
+    /// not compile, use `stream` to send anything else.
+    pub fn send<P: Packet + crate::protocol::ClientBound + crate::protocol::PlayState>(&mut self, packet: &P) -> std::io::Result<()> {
+        self.stream.write_packet(packet)
+    }
+
+    pub fn stream(&mut self) -> &mut PacketStream<S> {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> PacketStream<S> {
+        self.stream
+    }
+}
+
+impl<S: Read + Write + HalfClose> Session<S>{
+    /// Kicks the player with `reason`, see `PacketStream::disconnect`.
+    pub fn disconnect(&mut self, reason: &Component) -> std::io::Result<()> {
+        self.stream.disconnect::<Proto_1_17>(State::Play, reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(username: &str) -> Profile {
+        Profile{ uuid: offline_uuid(username), username: username.to_string(), properties: Vec::new() }
+    }
+
+    #[test]
+    fn logging_in_twice_kicks_the_first_session() {
+        let logins = SharedLogins::new(&ListenerOptions::default());
+        let address: SocketAddr = "127.0.0.1:25565".parse().unwrap();
+        let first = logins.admit(address, &profile("Notch")).unwrap();
+        assert!(!first.is_kicked());
+        let second = logins.admit(address, &profile("notch")).unwrap();
+        assert!(first.is_kicked());
+        // The replaced session leaving does not log the new one out.
+        drop(first);
+        assert!(logins.lock().guard.is_online("Notch"));
+        assert!(!second.is_kicked());
+        drop(second);
+        assert_eq!(logins.lock().guard.online(), 0);
+    }
+
+    #[test]
+    fn options_configure_the_guard() {
+        let options = ListenerOptions{
+            login_throttle: Some(Duration::from_secs(60)),
+            duplicate_login: DuplicateLoginPolicy::DenyNew,
+            ..ListenerOptions::default()
+        };
+        let logins = SharedLogins::new(&options);
+        let address: SocketAddr = "127.0.0.1:25565".parse().unwrap();
+        let _first = logins.admit(address, &profile("Notch")).unwrap();
+        assert_eq!(logins.admit(address, &profile("jeb_")).unwrap_err(), LoginRejection::Throttled);
+        let other: SocketAddr = "127.0.0.2:25565".parse().unwrap();
+        assert_eq!(logins.admit(other, &profile("Notch")).unwrap_err(), LoginRejection::AlreadyOnline);
+    }
+}
@@ -0,0 +1,272 @@
+//! Checks a server runs between LoginStart and LoginSuccess.
+use crate::chat::Component;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+pub const MIN_USERNAME_LEN: usize = 3;
+pub const MAX_USERNAME_LEN: usize = 16;
+
+/// Why a login was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginRejection{
+    /// The address connected again before the throttle interval passed.
+    Throttled,
+    InvalidUsername(UsernameError),
+    /// The player is already online and the policy is `DenyNew`.
+    AlreadyOnline
+}
+
+impl LoginRejection{
+    /// The disconnect reason as a chat component.
+    pub fn reason_json(&self) -> String {
+        match self {
+            LoginRejection::Throttled => r#"{"text":"Connection throttled! Please wait before reconnecting."}"#.to_string(),
+            // The description quotes a character the client chose, so it has to be escaped.
+            LoginRejection::InvalidUsername(error) => Component::text(format!("Invalid username: {}", error.description())).to_json(),
+            LoginRejection::AlreadyOnline => DUPLICATE_LOGIN_JSON.to_string()
+        }
+    }
+}
+
+/// Reason to disconnect a player with, when the same account logs in elsewhere.
+pub const DUPLICATE_LOGIN_JSON: &str = r#"{"translate":"multiplayer.disconnect.duplicate_login"}"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsernameError{
+    TooShort,
+    TooLong,
+    IllegalCharacter(char)
+}
+
+impl UsernameError{
+    pub fn description(&self) -> String {
+        match self {
+            UsernameError::TooShort => format!("shorter than {} characters", MIN_USERNAME_LEN),
+            UsernameError::TooLong => format!("longer than {} characters", MAX_USERNAME_LEN),
+            UsernameError::IllegalCharacter(c) => format!("contains illegal character {:?}", c)
+        }
+    }
+}
+
+/// Checks a username against the rules of the vanilla client: 3 to 16
+/// characters, consisting of letters, digits and underscores.
+pub fn validate_username(username: &str) -> Result<(), UsernameError> {
+    if let Some(c) = username.chars().find(|c| !(c.is_ascii_alphanumeric() || *c == '_')) {
+        return Err(UsernameError::IllegalCharacter(c));
+    }
+    if username.len() < MIN_USERNAME_LEN {
+        return Err(UsernameError::TooShort);
+    }
+    if username.len() > MAX_USERNAME_LEN {
+        return Err(UsernameError::TooLong);
+    }
+    Ok(())
+}
+
+/// Refuses logins from an address that logged in less than `interval` ago,
+/// like vanilla's `connection-throttle` setting.
+#[derive(Debug, Clone)]
+pub struct LoginThrottle{
+    interval: Duration,
+    last_attempt: HashMap<IpAddr, Instant>
+}
+
+impl LoginThrottle{
+    pub fn new(interval: Duration) -> Self {
+        LoginThrottle{
+            interval,
+            last_attempt: HashMap::new()
+        }
+    }
+
+    /// Records an attempt and returns whether it is allowed. Throttled attempts
+    /// do not extend the wait.
+    pub fn attempt(&mut self, ip: IpAddr, now: Instant) -> bool {
+        match self.last_attempt.get(&ip) {
+            Some(&last) if now.saturating_duration_since(last) < self.interval => false,
+            _ => {
+                self.last_attempt.insert(ip, now);
+                true
+            }
+        }
+    }
+
+    /// Forgets addresses whose throttle expired. Call this now and then to
+    /// keep memory bounded.
+    pub fn purge(&mut self, now: Instant) {
+        let interval = self.interval;
+        self.last_attempt.retain(|_, last| now.saturating_duration_since(*last) < interval);
+    }
+}
+
+/// What to do when a player logs in while already being online.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateLoginPolicy{
+    /// Disconnect the existing session with `multiplayer.disconnect.duplicate_login`.
+    /// This is what vanilla does.
+    #[default]
+    KickExisting,
+    /// Refuse the new login.
+    DenyNew
+}
+
+/// Outcome of `LoginGuard::admit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission<C>{
+    Allowed{
+        /// Sessions that have to be disconnected with `DUPLICATE_LOGIN_JSON`
+        /// before the new one is let in.
+        kick: Vec<C>
+    },
+    Rejected(LoginRejection)
+}
+
+/// Tracks online players and decides whether a new login may proceed.
+///
+/// `C` identifies a connection, e.g. a connection id or a channel to its task.
+#[derive(Debug)]
+pub struct LoginGuard<C>{
+    pub throttle: Option<LoginThrottle>,
+    pub duplicate_policy: DuplicateLoginPolicy,
+    /// Online sessions by lowercase username.
+    by_name: HashMap<String, (u128, C)>,
+    by_uuid: HashMap<u128, String>
+}
+
+impl<C: Clone + Eq + Hash> LoginGuard<C>{
+    pub fn new(throttle: Option<LoginThrottle>, duplicate_policy: DuplicateLoginPolicy) -> Self {
+        LoginGuard{
+            throttle,
+            duplicate_policy,
+            by_name: HashMap::new(),
+            by_uuid: HashMap::new()
+        }
+    }
+
+    /// Runs all checks for a login. An allowed login is registered as online
+    /// right away; call `logout` when the connection closes.
+    pub fn admit(&mut self, ip: IpAddr, username: &str, uuid: u128, connection: C, now: Instant) -> Admission<C> {
+        if let Some(throttle) = &mut self.throttle {
+            if !throttle.attempt(ip, now) {
+                return Admission::Rejected(LoginRejection::Throttled);
+            }
+        }
+        if let Err(e) = validate_username(username) {
+            return Admission::Rejected(LoginRejection::InvalidUsername(e));
+        }
+
+        let name = username.to_ascii_lowercase();
+        // The same account may be online under an old name, or another
+        // account may hold the name in offline mode. Both count as duplicate.
+        let mut existing: Vec<String> = self.by_uuid.get(&uuid).cloned().into_iter().collect();
+        if self.by_name.contains_key(&name) && !existing.contains(&name) {
+            existing.push(name.clone());
+        }
+        if !existing.is_empty() && self.duplicate_policy == DuplicateLoginPolicy::DenyNew {
+            return Admission::Rejected(LoginRejection::AlreadyOnline);
+        }
+        let kick = existing.iter().filter_map(|existing| self.remove(existing)).collect();
+        self.by_uuid.insert(uuid, name.clone());
+        self.by_name.insert(name, (uuid, connection));
+        Admission::Allowed{ kick }
+    }
+
+    /// Marks the session as offline, if it is still the registered one.
+    pub fn logout(&mut self, username: &str, connection: &C) {
+        let name = username.to_ascii_lowercase();
+        if matches!(self.by_name.get(&name), Some((_, c)) if c == connection) {
+            self.remove(&name);
+        }
+    }
+
+    pub fn is_online(&self, username: &str) -> bool {
+        self.by_name.contains_key(&username.to_ascii_lowercase())
+    }
+
+    pub fn online(&self) -> usize {
+        self.by_name.len()
+    }
+
+    fn remove(&mut self, name: &str) -> Option<C> {
+        let (uuid, connection) = self.by_name.remove(name)?;
+        self.by_uuid.remove(&uuid);
+        Some(connection)
+    }
+}
+
+/// The UUID offline mode servers assign: a version 3 UUID of `OfflinePlayer:<username>`.
+pub fn offline_uuid(username: &str) -> u128 {
+    let mut digest = md5(format!("OfflinePlayer:{}", username).as_bytes());
+    digest[6] = (digest[6] & 0x0F) | 0x30;
+    digest[8] = (digest[8] & 0x3F) | 0x80;
+    u128::from_be_bytes(digest)
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+        5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+        4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+        6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21
+    ];
+    let constants: Vec<u32> = (0..64).map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32).collect();
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+    for block in message.chunks(64) {
+        let words: Vec<u32> = block.chunks(4).map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16)
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(constants[i]).wrapping_add(words[g]).rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        for (word, value) in state.iter_mut().zip([a, b, c, d].iter()) {
+            *word = word.wrapping_add(*value);
+        }
+    }
+    let mut digest = [0u8; 16];
+    for (chunk, word) in digest.chunks_mut(4).zip(state.iter()) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuid_matches_vanilla() {
+        assert_eq!(offline_uuid("Notch"), 0xb50ad385_829d_3141_a216_7e7d7539ba7f);
+    }
+
+    #[test]
+    fn md5_known_answers() {
+        assert_eq!(u128::from_be_bytes(md5(b"")), 0xd41d8cd9_8f00b204_e9800998_ecf8427e);
+        // Spans two blocks.
+        assert_eq!(u128::from_be_bytes(md5(&[b'a'; 100])), 0x36a92cc9_4a9e0fa2_1f625f8b_fb007adf);
+    }
+
+    #[test]
+    fn illegal_characters_are_escaped_in_the_reason() {
+        let reason = |username: &str| LoginRejection::InvalidUsername(validate_username(username).unwrap_err()).reason_json();
+        assert_eq!(reason("ab\\c"), r#"{"text":"Invalid username: contains illegal character '\\\\'"}"#);
+        assert_eq!(reason("ab\"c"), r#"{"text":"Invalid username: contains illegal character '\"'"}"#);
+        assert_eq!(reason("ab\u{7}c"), r#"{"text":"Invalid username: contains illegal character '\\u{7}'"}"#);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod login;
+pub mod motd;
+pub mod status;
+#[cfg(feature = "steven_shared")]
+pub mod listener;
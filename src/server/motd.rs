@@ -0,0 +1,330 @@
+//! Building the description of the server list, the message of the day.
+//!
+//! A `Motd` is written once and turned into either a legacy string with `§`
+//! codes or a chat component. Lines can be centered, using the widths of the
+//! default client font:
+//!
+//! ```ignore
+//! let motd = Motd::new()
+//!     .text("My Server").color(Color::Gold).bold().centered()
+//!     .line()
+//!     .text("Now on 1.17!").color(Color::Gray).centered();
+//! let status = Status::new("1.17.1", 756).description(motd.to_component());
+//! ```
+use crate::chat::Component;
+
+/// Width of a line of the server list in pixels.
+pub const LINE_WIDTH: u32 = 270;
+
+/// The server list shows two lines of description.
+pub const MAX_LINES: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Color{
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White
+}
+
+impl Color{
+    const ALL: [Color; 16] = [
+        Color::Black, Color::DarkBlue, Color::DarkGreen, Color::DarkAqua,
+        Color::DarkRed, Color::DarkPurple, Color::Gold, Color::Gray,
+        Color::DarkGray, Color::Blue, Color::Green, Color::Aqua,
+        Color::Red, Color::LightPurple, Color::Yellow, Color::White
+    ];
+
+    /// The character following `§` for this color.
+    pub fn code(&self) -> char {
+        std::char::from_digit(*self as u32, 16).unwrap()
+    }
+
+    pub fn from_code(code: char) -> Option<Color> {
+        code.to_digit(16).map(|digit| Color::ALL[digit as usize])
+    }
+
+    /// The name used by chat components.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Color::Black => "black",
+            Color::DarkBlue => "dark_blue",
+            Color::DarkGreen => "dark_green",
+            Color::DarkAqua => "dark_aqua",
+            Color::DarkRed => "dark_red",
+            Color::DarkPurple => "dark_purple",
+            Color::Gold => "gold",
+            Color::Gray => "gray",
+            Color::DarkGray => "dark_gray",
+            Color::Blue => "blue",
+            Color::Green => "green",
+            Color::Aqua => "aqua",
+            Color::Red => "red",
+            Color::LightPurple => "light_purple",
+            Color::Yellow => "yellow",
+            Color::White => "white"
+        }
+    }
+}
+
+/// Pixels the default font advances for `c`, the gap to the next character
+/// included. Bold text is one pixel wider per character.
+pub fn char_width(c: char) -> u32 {
+    match c {
+        '!' | '\'' | ',' | '.' | ':' | ';' | 'i' | '|' => 2,
+        '`' | 'l' => 3,
+        ' ' | 'I' | '[' | ']' | 't' => 4,
+        '"' | '(' | ')' | '*' | '<' | '>' | 'f' | 'k' | '{' | '}' => 5,
+        '@' | '~' => 7,
+        _ => 6
+    }
+}
+
+pub fn text_width(text: &str, bold: bool) -> u32 {
+    text.chars().map(|c| char_width(c) + bold as u32).sum()
+}
+
+/// Width of a legacy string, skipping `§` codes and following `§l` for bold.
+pub fn legacy_width(text: &str) -> u32 {
+    let mut width = 0;
+    let mut bold = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            match chars.next().map(|code| code.to_ascii_lowercase()) {
+                Some('l') => bold = true,
+                Some(code) if code == 'r' || Color::from_code(code).is_some() => bold = false,
+                _ => {}
+            }
+        }else{
+            width += char_width(c) + bold as u32;
+        }
+    }
+    width
+}
+
+/// Removes the `§` codes of a legacy string.
+pub fn strip_legacy(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        }else{
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Spaces that center text of `width` pixels on a line of `line_width`.
+pub fn centering_padding(width: u32, line_width: u32) -> String {
+    let spaces = line_width.saturating_sub(width) / 2 / char_width(' ');
+    " ".repeat(spaces as usize)
+}
+
+/// A piece of text with one style.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span{
+    pub text: String,
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub strikethrough: bool,
+    pub obfuscated: bool
+}
+
+impl Span{
+    pub fn width(&self) -> u32 {
+        text_width(&self.text, self.bold)
+    }
+
+    fn legacy(&self, out: &mut String) {
+        out.push('§');
+        out.push(self.color.map_or('r', |color| color.code()));
+        for (set, code) in [(self.bold, 'l'), (self.italic, 'o'), (self.underlined, 'n'), (self.strikethrough, 'm'), (self.obfuscated, 'k')] {
+            if set {
+                out.push('§');
+                out.push(code);
+            }
+        }
+        out.push_str(&self.text);
+    }
+
+    fn component(&self) -> Component {
+        let flag = |set: bool| if set { Some(true) } else { None };
+        Component{
+            color: self.color.map(|color| color.name().to_string()),
+            bold: flag(self.bold),
+            italic: flag(self.italic),
+            underlined: flag(self.underlined),
+            strikethrough: flag(self.strikethrough),
+            obfuscated: flag(self.obfuscated),
+            ..Component::text(self.text.clone())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Line{
+    pub spans: Vec<Span>,
+    pub centered: bool
+}
+
+impl Line{
+    pub fn width(&self) -> u32 {
+        self.spans.iter().map(Span::width).sum()
+    }
+
+    fn padding(&self) -> String {
+        if self.centered {
+            centering_padding(self.width(), LINE_WIDTH)
+        }else{
+            String::new()
+        }
+    }
+}
+
+/// Builder of a server list description. Style methods apply to the text
+/// added last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Motd{
+    pub lines: Vec<Line>
+}
+
+impl Default for Motd{
+    fn default() -> Self {
+        Motd{ lines: vec![Line::default()] }
+    }
+}
+
+impl Motd{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.current_line().spans.push(Span{ text: text.into(), ..Default::default() });
+        self
+    }
+
+    /// Continues on the next line. Lines past `MAX_LINES` are not shown by
+    /// the client.
+    pub fn line(mut self) -> Self {
+        self.lines.push(Line::default());
+        self
+    }
+
+    /// Centers the current line.
+    pub fn centered(mut self) -> Self {
+        self.current_line().centered = true;
+        self
+    }
+
+    pub fn color(self, color: Color) -> Self {
+        self.style(|span| span.color = Some(color))
+    }
+
+    pub fn bold(self) -> Self {
+        self.style(|span| span.bold = true)
+    }
+
+    pub fn italic(self) -> Self {
+        self.style(|span| span.italic = true)
+    }
+
+    pub fn underlined(self) -> Self {
+        self.style(|span| span.underlined = true)
+    }
+
+    pub fn strikethrough(self) -> Self {
+        self.style(|span| span.strikethrough = true)
+    }
+
+    pub fn obfuscated(self) -> Self {
+        self.style(|span| span.obfuscated = true)
+    }
+
+    fn style(mut self, apply: impl FnOnce(&mut Span)) -> Self {
+        if let Some(span) = self.current_line().spans.last_mut() {
+            apply(span);
+        }
+        self
+    }
+
+    /// The line text goes to, started if `lines` was emptied.
+    fn current_line(&mut self) -> &mut Line {
+        if self.lines.is_empty() {
+            self.lines.push(Line::default());
+        }
+        let last = self.lines.len() - 1;
+        &mut self.lines[last]
+    }
+
+    /// The description as a legacy string, for clients before 1.7 and plugins
+    /// that only take `§` codes.
+    pub fn to_legacy(&self) -> String {
+        let mut out = String::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&line.padding());
+            for span in &line.spans {
+                span.legacy(&mut out);
+            }
+        }
+        out
+    }
+
+    pub fn to_component(&self) -> Component {
+        let mut component = Component::text("");
+        for (i, line) in self.lines.iter().enumerate() {
+            let padding = line.padding();
+            let text = if i > 0 { format!("\n{}", padding) } else { padding };
+            if !text.is_empty() {
+                component.extra.push(Component::text(text));
+            }
+            component.extra.extend(line.spans.iter().map(Span::component));
+        }
+        component
+    }
+}
+
+impl From<Motd> for Component{
+    fn from(motd: Motd) -> Self {
+        motd.to_component()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_motds_start_a_line() {
+        let motd = Motd{ lines: Vec::new() };
+        assert_eq!(motd.to_legacy(), "");
+        let motd = Motd{ lines: Vec::new() }.bold().centered().text("A Minecraft Server").color(Color::Gold);
+        assert_eq!(motd.lines.len(), 1);
+        assert_eq!(motd.lines[0].spans[0].color, Some(Color::Gold));
+        assert!(motd.lines[0].centered);
+    }
+}
@@ -0,0 +1,168 @@
+//! The status shown in the server list, built instead of written as JSON by
+//! hand, and the favicon it can carry.
+//!
+//! ```ignore
+//! let status = Status::new("1.17.1", 756)
+//!     .players(3, 20)
+//!     .description(Component::text("A Minecraft Server").color("gold"))
+//!     .favicon_png(&std::fs::read("server-icon.png")?)?;
+//! // return status.to_json() from ServerHandler::status
+//! ```
+use crate::chat::{write_json_string, Component};
+use std::fmt::Write;
+use std::io::{Error, ErrorKind};
+
+/// Width and height of a favicon in pixels.
+pub const FAVICON_SIZE: u32 = 64;
+
+pub const FAVICON_PREFIX: &str = "data:image/png;base64,";
+
+/// Largest PNG accepted as favicon. The client reads at most 32767 characters
+/// of status, this leaves room for the rest once base64 encoded.
+pub const MAX_FAVICON_LEN: usize = 16 * 1024;
+
+const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Width and height of a PNG, read from its header.
+pub fn png_dimensions(png: &[u8]) -> std::io::Result<(u32, u32)> {
+    if png.len() < 24 || &png[..8] != PNG_SIGNATURE {
+        return Err(Error::new(ErrorKind::InvalidData, "not a PNG image"));
+    }
+    // The IHDR chunk comes first: length, type, then width and height.
+    if &png[12..16] != b"IHDR" {
+        return Err(Error::new(ErrorKind::InvalidData, "PNG image does not start with IHDR"));
+    }
+    let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+    Ok((width, height))
+}
+
+/// Checks `png` can be used as favicon: a PNG of 64×64 pixels that is not
+/// too large. Only the header is checked, the image data is not decoded.
+pub fn validate_favicon(png: &[u8]) -> std::io::Result<()> {
+    if png.len() > MAX_FAVICON_LEN {
+        return Err(Error::new(ErrorKind::InvalidData, format!("favicon of {} bytes is larger than {} bytes", png.len(), MAX_FAVICON_LEN)));
+    }
+    let (width, height) = png_dimensions(png)?;
+    if (width, height) != (FAVICON_SIZE, FAVICON_SIZE) {
+        return Err(Error::new(ErrorKind::InvalidData, format!("favicon is {}×{} pixels rather than {}×{}", width, height, FAVICON_SIZE, FAVICON_SIZE)));
+    }
+    Ok(())
+}
+
+/// Turns a 64×64 PNG into the `data:image/png;base64,...` string of the status.
+pub fn favicon_from_png(png: &[u8]) -> std::io::Result<String> {
+    validate_favicon(png)?;
+    Ok(format!("{}{}", FAVICON_PREFIX, crate::base64::encode(png)))
+}
+
+/// Reads the PNG back out of the favicon of a status, checking it the same
+/// way as `favicon_from_png`.
+pub fn favicon_to_png(favicon: &str) -> std::io::Result<Vec<u8>> {
+    let data = favicon.strip_prefix(FAVICON_PREFIX)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "favicon is not a base64 PNG data URI"))?;
+    // Servers used to send line breaks in the base64, which clients still accept.
+    let png = crate::base64::decode(&data.replace(['\n', '\r'], ""))?;
+    validate_favicon(&png)?;
+    Ok(png)
+}
+
+/// A player shown when hovering the player count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SamplePlayer{
+    pub name: String,
+    pub uuid: u128
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Status{
+    /// Shown in place of the ping when `protocol` differs from the client's.
+    pub version_name: String,
+    pub protocol: i32,
+    pub online_players: i32,
+    pub max_players: i32,
+    pub sample: Vec<SamplePlayer>,
+    pub description: Component,
+    /// The favicon as data URI, see `favicon_from_png`.
+    pub favicon: Option<String>,
+    /// Whether the server requires signed chat, sent since 1.19.1.
+    pub enforces_secure_chat: Option<bool>
+}
+
+impl Status{
+    pub fn new(version_name: impl Into<String>, protocol: i32) -> Self {
+        Status{
+            version_name: version_name.into(),
+            protocol,
+            online_players: 0,
+            max_players: 20,
+            sample: Vec::new(),
+            description: Component::text("A Minecraft Server"),
+            favicon: None,
+            enforces_secure_chat: None
+        }
+    }
+
+    pub fn players(mut self, online: i32, max: i32) -> Self {
+        self.online_players = online;
+        self.max_players = max;
+        self
+    }
+
+    pub fn sample_player(mut self, name: impl Into<String>, uuid: u128) -> Self {
+        self.sample.push(SamplePlayer{ name: name.into(), uuid });
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<Component>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Sets the favicon from a 64×64 PNG.
+    pub fn favicon_png(mut self, png: &[u8]) -> std::io::Result<Self> {
+        self.favicon = Some(favicon_from_png(png)?);
+        Ok(self)
+    }
+
+    pub fn enforces_secure_chat(mut self, enforces_secure_chat: bool) -> Self {
+        self.enforces_secure_chat = Some(enforces_secure_chat);
+        self
+    }
+
+    /// The status as sent in StatusResponse.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"version\":{\"name\":");
+        let _ = write_json_string(&mut json, &self.version_name);
+        let _ = write!(json, ",\"protocol\":{}}},\"players\":{{\"max\":{},\"online\":{}", self.protocol, self.max_players, self.online_players);
+        if !self.sample.is_empty() {
+            json.push_str(",\"sample\":[");
+            for (i, player) in self.sample.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                json.push_str("{\"name\":");
+                let _ = write_json_string(&mut json, &player.name);
+                let _ = write!(json, ",\"id\":\"{}\"}}", hyphenated(player.uuid));
+            }
+            json.push(']');
+        }
+        let _ = write!(json, "}},\"description\":{}", self.description);
+        if let Some(favicon) = &self.favicon {
+            json.push_str(",\"favicon\":");
+            let _ = write_json_string(&mut json, favicon);
+        }
+        if let Some(enforces_secure_chat) = self.enforces_secure_chat {
+            let _ = write!(json, ",\"enforcesSecureChat\":{}", enforces_secure_chat);
+        }
+        json.push('}');
+        json
+    }
+}
+
+fn hyphenated(uuid: u128) -> String {
+    let hex = format!("{:032x}", uuid);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
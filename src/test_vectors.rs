@@ -0,0 +1,162 @@
+//! Canonical encodings of segments that are easy to get wrong.
+//!
+//! Every entry pairs a value with the exact bytes the vanilla server produces
+//! for it. Both this crate and other implementations can check their encoders
+//! and decoders against these.
+
+/// VarInt boundaries, including the five byte encodings of negative numbers.
+pub const VAR_INTS: &[(i32, &[u8])] = &[
+    (0, &[0x00]),
+    (1, &[0x01]),
+    (2, &[0x02]),
+    (127, &[0x7f]),
+    (128, &[0x80, 0x01]),
+    (255, &[0xff, 0x01]),
+    (25565, &[0xdd, 0xc7, 0x01]),
+    (2097151, &[0xff, 0xff, 0x7f]),
+    (2147483647, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+    (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+    (-2147483648, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+];
+
+pub const VAR_LONGS: &[(i64, &[u8])] = &[
+    (0, &[0x00]),
+    (1, &[0x01]),
+    (2, &[0x02]),
+    (127, &[0x7f]),
+    (128, &[0x80, 0x01]),
+    (255, &[0xff, 0x01]),
+    (2147483647, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+    (9223372036854775807, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]),
+    (-1, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+    (-2147483648, &[0x80, 0x80, 0x80, 0x80, 0xf8, 0xff, 0xff, 0xff, 0xff, 0x01]),
+    (-9223372036854775808, &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]),
+];
+
+/// A block position as `(x, y, z)`.
+pub type BlockPosition = (i32, i32, i32);
+
+/// Block positions packed as 26 bits x, 26 bits z and 12 bits y.
+pub const POSITIONS: &[(BlockPosition, [u8; 8])] = &[
+    ((0, 0, 0), [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ((1, 2, 3), [0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x30, 0x02]),
+    ((-1, -1, -1), [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+    ((33554431, 2047, 33554431), [0x7f, 0xff, 0xff, 0xdf, 0xff, 0xff, 0xf7, 0xff]),
+    ((-33554432, -2048, -33554432), [0x80, 0x00, 0x00, 0x20, 0x00, 0x00, 0x08, 0x00]),
+    ((18357644, 831, -20882616), [0x46, 0x07, 0x63, 0x2c, 0x15, 0xb4, 0x83, 0x3f]),
+];
+
+/// UUIDs in their textual form, sent as two big endian 64 bit integers.
+pub const UUIDS: &[(&str, [u8; 16])] = &[
+    ("00000000-0000-0000-0000-000000000000", [0; 16]),
+    ("069a79f4-44e9-4726-a5be-fca90e38aaf5", [
+        0x06, 0x9a, 0x79, 0xf4, 0x44, 0xe9, 0x47, 0x26,
+        0xa5, 0xbe, 0xfc, 0xa9, 0x0e, 0x38, 0xaa, 0xf5
+    ]),
+    ("ffffffff-ffff-ffff-ffff-ffffffffffff", [0xff; 16]),
+];
+
+/// Strings, prefixed with their length in bytes (not characters) as a VarInt.
+pub const STRINGS: &[(&str, &[u8])] = &[
+    ("", &[0x00]),
+    ("hello", &[0x05, b'h', b'e', b'l', b'l', b'o']),
+    ("\u{00e9}", &[0x02, 0xc3, 0xa9]),
+];
+
+/// An empty VarInt length prefixed byte array.
+pub const EMPTY_LEN_PREFIXED: &[u8] = &[0x00];
+
+/// A VarInt length prefixed byte array of 300 bytes counting up from 0 and
+/// wrapping at 256, which needs a two byte length prefix.
+pub fn large_len_prefixed() -> (Vec<u8>, Vec<u8>) {
+    let data: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+    let mut encoded = vec![0xac, 0x02];
+    encoded.extend_from_slice(&data);
+    (data, encoded)
+}
+
+/// The `hello_world.nbt` sample of the NBT specification: a compound named
+/// `hello world` holding the string `name` set to `Bananrama`.
+pub const NBT_HELLO_WORLD: &[u8] = &[
+    0x0a, 0x00, 0x0b, b'h', b'e', b'l', b'l', b'o', b' ', b'w', b'o', b'r', b'l', b'd',
+    0x08, 0x00, 0x04, b'n', b'a', b'm', b'e',
+    0x00, 0x09, b'B', b'a', b'n', b'a', b'n', b'r', b'a', b'm', b'a',
+    0x00,
+];
+
+/// A lone TAG_End, which is how an absent optional NBT compound is sent.
+pub const NBT_ABSENT: &[u8] = &[0x00];
+
+#[cfg(all(test, feature = "steven_shared"))]
+mod tests {
+    use super::*;
+    use crate::segment::Segment;
+    use steven_protocol::protocol::{VarInt, VarLong, UUID};
+    use steven_shared::Position;
+
+    fn written<S: Segment>(segment: &S) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        segment.write_to_stream(&mut bytes).unwrap();
+        assert_eq!(segment.serialized_len(), bytes.len());
+        bytes
+    }
+
+    fn read<S: Segment + Default>(bytes: &[u8]) -> S {
+        let mut reader = bytes;
+        let mut segment = S::default();
+        segment.read_from_stream(&mut reader).unwrap();
+        assert!(reader.is_empty(), "{} bytes of {:?} were not read", reader.len(), bytes);
+        segment
+    }
+
+    #[test]
+    fn var_ints() {
+        for (value, bytes) in VAR_INTS {
+            assert_eq!(written(&VarInt(*value)), *bytes, "{}", value);
+            assert_eq!(read::<VarInt>(bytes).0, *value);
+        }
+    }
+
+    #[test]
+    fn var_longs() {
+        for (value, bytes) in VAR_LONGS {
+            assert_eq!(written(&VarLong(*value)), *bytes, "{}", value);
+            assert_eq!(read::<VarLong>(bytes).0, *value);
+        }
+    }
+
+    #[test]
+    fn positions() {
+        for ((x, y, z), bytes) in POSITIONS {
+            assert_eq!(written(&Position::new(*x, *y, *z)), bytes, "{:?}", (x, y, z));
+            let position = read::<Position>(bytes);
+            assert_eq!((position.x, position.y, position.z), (*x, *y, *z));
+        }
+    }
+
+    #[test]
+    fn uuids() {
+        for (text, bytes) in UUIDS {
+            let uuid: UUID = text.parse().unwrap();
+            assert_eq!(written(&uuid), bytes, "{}", text);
+            assert_eq!(written(&read::<UUID>(bytes)), bytes);
+        }
+    }
+
+    #[test]
+    fn strings() {
+        for (value, bytes) in STRINGS {
+            assert_eq!(written(&value.to_string()), *bytes, "{:?}", value);
+            assert_eq!(read::<String>(bytes), *value);
+        }
+    }
+
+    #[test]
+    fn len_prefixed_bytes() {
+        use steven_protocol::protocol::LenPrefixedBytes;
+        assert!(read::<LenPrefixedBytes<VarInt>>(EMPTY_LEN_PREFIXED).data.is_empty());
+        let (data, encoded) = large_len_prefixed();
+        assert_eq!(written(&LenPrefixedBytes::<VarInt>::new(data.clone())), encoded);
+        assert_eq!(read::<LenPrefixedBytes<VarInt>>(&encoded).data, data);
+    }
+}
@@ -0,0 +1,134 @@
+//! Round-trip checks for protocol definitions.
+//!
+//! Values are produced by decoding arbitrary bytes rather than by generating
+//! every field type, so every packet can be checked without each segment
+//! having to know how to produce a random instance of itself.
+use crate::protocol::{PacketDescriptor, PacketKind, Protocol};
+use crate::segment::Segment;
+use std::io::{Error, ErrorKind};
+
+/// Decodes a segment from arbitrary bytes. Bytes left over are ignored.
+pub fn arbitrary<S: Segment>(data: &[u8]) -> std::io::Result<S> {
+    let mut segment = S::default();
+    segment.read_from_stream(&mut &data[..])?;
+    Ok(segment)
+}
+
+/// Writes the segment, reads it back and writes it again. Both writes have to
+/// produce the same bytes, the read has to consume all of them and
+/// `serialized_len` has to match.
+pub fn roundtrip<S: Segment>(segment: &S) -> std::io::Result<()> {
+    let mut first = Vec::new();
+    segment.write_to_stream(&mut first)?;
+    if segment.serialized_len() != first.len() {
+        return Err(mismatch(format!("serialized_len is {}, but {} bytes were written", segment.serialized_len(), first.len())));
+    }
+    let mut reader = &first[..];
+    let mut read = S::default();
+    read.read_from_stream(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(mismatch(format!("{} of {} bytes were not read back", reader.len(), first.len())));
+    }
+    let mut second = Vec::new();
+    read.write_to_stream(&mut second)?;
+    if first != second {
+        return Err(mismatch(format!("wrote {:?}, after reading it back wrote {:?}", first, second)));
+    }
+    Ok(())
+}
+
+fn mismatch(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Small xorshift generator, good enough to produce test input.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng{
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn fill(&mut self, buffer: &mut [u8]) {
+        for chunk in buffer.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+const MAX_INPUT_LEN: usize = 256;
+
+/// A packet of the kind decoded from random bytes, for benchmarks and fuzzers
+/// that need varied packets. Falls back to the packet with default fields if
+/// none of `attempts` random inputs decode.
+pub fn random_packet<P: Protocol>(kind: &PacketKind<P>, rng: &mut Rng, attempts: usize) -> P {
+    let mut input = Vec::with_capacity(MAX_INPUT_LEN);
+    for _ in 0..attempts {
+        input.resize((rng.next_u64() % MAX_INPUT_LEN as u64) as usize, 0);
+        rng.fill(&mut input);
+        if let Ok(packet) = kind.read(&mut &input[..]) {
+            return packet;
+        }
+    }
+    kind.create()
+}
+
+/// Round-trips every packet of the protocol, decoded from all zeroes and from
+/// `iterations` random byte strings. Inputs that do not decode are skipped.
+///
+/// Returns how many packets were checked, or the first failure along with the
+/// name of the packet.
+pub fn verify_protocol<P: Protocol>(iterations: usize, seed: u64) -> std::io::Result<usize> {
+    let mut rng = Rng::new(seed);
+    let mut checked = 0;
+    let mut input = vec![0u8; MAX_INPUT_LEN];
+    for descriptor in P::packets() {
+        for i in 0..=iterations {
+            if i > 0 {
+                let len = (rng.next_u64() % MAX_INPUT_LEN as u64) as usize;
+                input.resize(len, 0);
+                rng.fill(&mut input);
+            }
+            let packet = match P::packet_by_id(descriptor.state, descriptor.direction, descriptor.id, &mut &input[..]) {
+                Ok(Some(packet)) => packet,
+                _ => continue
+            };
+            protocol_roundtrip(descriptor, &packet).map_err(|e| Error::new(e.kind(), format!(
+                "{} {}: {}", P::NAME, descriptor.name, e
+            )))?;
+            checked += 1;
+        }
+        input.clear();
+        input.resize(MAX_INPUT_LEN, 0);
+    }
+    Ok(checked)
+}
+
+fn protocol_roundtrip<P: Protocol>(descriptor: &PacketDescriptor, packet: &P) -> std::io::Result<()> {
+    let mut first = Vec::new();
+    packet.write_to_stream(&mut first)?;
+    if packet.serialized_len() != first.len() {
+        return Err(mismatch(format!("serialized_len is {}, but {} bytes were written", packet.serialized_len(), first.len())));
+    }
+    let mut reader = &first[..];
+    let read = P::packet_by_id(descriptor.state, descriptor.direction, descriptor.id, &mut reader)?
+        .ok_or_else(|| mismatch("packet could not be read back".to_string()))?;
+    if !reader.is_empty() {
+        return Err(mismatch(format!("{} of {} bytes were not read back", reader.len(), first.len())));
+    }
+    let mut second = Vec::new();
+    read.write_to_stream(&mut second)?;
+    if first != second {
+        return Err(mismatch(format!("wrote {:?}, after reading it back wrote {:?}", first, second)));
+    }
+    Ok(())
+}
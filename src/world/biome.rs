@@ -0,0 +1,224 @@
+//! Biomes as sent in ChunkData: one biome per cell of 4x4x4 blocks.
+use std::io::{Error, ErrorKind};
+
+macro_rules! biomes {
+    ($($variant:ident => $name:literal = $id_1_17:literal),+$(,)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum Biome{
+            $($variant,)+
+            /// A biome the registry does not know, e.g. a custom biome of a datapack.
+            Unknown(i32)
+        }
+
+        impl Biome{
+            /// The namespaced identifier, or `None` for unknown biomes.
+            pub fn name(&self) -> Option<&'static str> {
+                match self {
+                    $(Biome::$variant => Some($name),)+
+                    Biome::Unknown(_) => None
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Biome> {
+                match name {
+                    $($name => Some(Biome::$variant),)+
+                    _ => None
+                }
+            }
+        }
+
+        /// Vanilla biome registry of 1.17.
+        #[derive(Debug, Default, Clone, Copy)]
+        pub struct Biomes1_17;
+
+        impl BiomeRegistry for Biomes1_17{
+            fn biome(id: i32) -> Biome {
+                match id {
+                    $($id_1_17 => Biome::$variant,)+
+                    id => Biome::Unknown(id)
+                }
+            }
+
+            fn id(biome: Biome) -> i32 {
+                match biome {
+                    $(Biome::$variant => $id_1_17,)+
+                    Biome::Unknown(id) => id
+                }
+            }
+        }
+    };
+}
+
+/// Maps between biomes and the numeric ids of a protocol version.
+pub trait BiomeRegistry{
+    fn biome(id: i32) -> Biome;
+    fn id(biome: Biome) -> i32;
+}
+
+biomes!(
+    Ocean => "minecraft:ocean" = 0,
+    Plains => "minecraft:plains" = 1,
+    Desert => "minecraft:desert" = 2,
+    Mountains => "minecraft:mountains" = 3,
+    Forest => "minecraft:forest" = 4,
+    Taiga => "minecraft:taiga" = 5,
+    Swamp => "minecraft:swamp" = 6,
+    River => "minecraft:river" = 7,
+    NetherWastes => "minecraft:nether_wastes" = 8,
+    TheEnd => "minecraft:the_end" = 9,
+    FrozenOcean => "minecraft:frozen_ocean" = 10,
+    FrozenRiver => "minecraft:frozen_river" = 11,
+    SnowyTundra => "minecraft:snowy_tundra" = 12,
+    SnowyMountains => "minecraft:snowy_mountains" = 13,
+    MushroomFields => "minecraft:mushroom_fields" = 14,
+    MushroomFieldShore => "minecraft:mushroom_field_shore" = 15,
+    Beach => "minecraft:beach" = 16,
+    DesertHills => "minecraft:desert_hills" = 17,
+    WoodedHills => "minecraft:wooded_hills" = 18,
+    TaigaHills => "minecraft:taiga_hills" = 19,
+    MountainEdge => "minecraft:mountain_edge" = 20,
+    Jungle => "minecraft:jungle" = 21,
+    JungleHills => "minecraft:jungle_hills" = 22,
+    JungleEdge => "minecraft:jungle_edge" = 23,
+    DeepOcean => "minecraft:deep_ocean" = 24,
+    StoneShore => "minecraft:stone_shore" = 25,
+    SnowyBeach => "minecraft:snowy_beach" = 26,
+    BirchForest => "minecraft:birch_forest" = 27,
+    BirchForestHills => "minecraft:birch_forest_hills" = 28,
+    DarkForest => "minecraft:dark_forest" = 29,
+    SnowyTaiga => "minecraft:snowy_taiga" = 30,
+    SnowyTaigaHills => "minecraft:snowy_taiga_hills" = 31,
+    GiantTreeTaiga => "minecraft:giant_tree_taiga" = 32,
+    GiantTreeTaigaHills => "minecraft:giant_tree_taiga_hills" = 33,
+    WoodedMountains => "minecraft:wooded_mountains" = 34,
+    Savanna => "minecraft:savanna" = 35,
+    SavannaPlateau => "minecraft:savanna_plateau" = 36,
+    Badlands => "minecraft:badlands" = 37,
+    WoodedBadlandsPlateau => "minecraft:wooded_badlands_plateau" = 38,
+    BadlandsPlateau => "minecraft:badlands_plateau" = 39,
+    SmallEndIslands => "minecraft:small_end_islands" = 40,
+    EndMidlands => "minecraft:end_midlands" = 41,
+    EndHighlands => "minecraft:end_highlands" = 42,
+    EndBarrens => "minecraft:end_barrens" = 43,
+    WarmOcean => "minecraft:warm_ocean" = 44,
+    LukewarmOcean => "minecraft:lukewarm_ocean" = 45,
+    ColdOcean => "minecraft:cold_ocean" = 46,
+    DeepWarmOcean => "minecraft:deep_warm_ocean" = 47,
+    DeepLukewarmOcean => "minecraft:deep_lukewarm_ocean" = 48,
+    DeepColdOcean => "minecraft:deep_cold_ocean" = 49,
+    DeepFrozenOcean => "minecraft:deep_frozen_ocean" = 50,
+    TheVoid => "minecraft:the_void" = 127,
+    SunflowerPlains => "minecraft:sunflower_plains" = 129,
+    DesertLakes => "minecraft:desert_lakes" = 130,
+    GravellyMountains => "minecraft:gravelly_mountains" = 131,
+    FlowerForest => "minecraft:flower_forest" = 132,
+    TaigaMountains => "minecraft:taiga_mountains" = 133,
+    SwampHills => "minecraft:swamp_hills" = 134,
+    IceSpikes => "minecraft:ice_spikes" = 140,
+    ModifiedJungle => "minecraft:modified_jungle" = 149,
+    ModifiedJungleEdge => "minecraft:modified_jungle_edge" = 151,
+    TallBirchForest => "minecraft:tall_birch_forest" = 155,
+    TallBirchHills => "minecraft:tall_birch_hills" = 156,
+    DarkForestHills => "minecraft:dark_forest_hills" = 157,
+    SnowyTaigaMountains => "minecraft:snowy_taiga_mountains" = 158,
+    GiantSpruceTaiga => "minecraft:giant_spruce_taiga" = 160,
+    GiantSpruceTaigaHills => "minecraft:giant_spruce_taiga_hills" = 161,
+    ModifiedGravellyMountains => "minecraft:modified_gravelly_mountains" = 162,
+    ShatteredSavanna => "minecraft:shattered_savanna" = 163,
+    ShatteredSavannaPlateau => "minecraft:shattered_savanna_plateau" = 164,
+    ErodedBadlands => "minecraft:eroded_badlands" = 165,
+    ModifiedWoodedBadlandsPlateau => "minecraft:modified_wooded_badlands_plateau" = 166,
+    ModifiedBadlandsPlateau => "minecraft:modified_badlands_plateau" = 167,
+    BambooJungle => "minecraft:bamboo_jungle" = 168,
+    BambooJungleHills => "minecraft:bamboo_jungle_hills" = 169,
+    SoulSandValley => "minecraft:soul_sand_valley" = 170,
+    CrimsonForest => "minecraft:crimson_forest" = 171,
+    WarpedForest => "minecraft:warped_forest" = 172,
+    BasaltDeltas => "minecraft:basalt_deltas" = 173,
+    DripstoneCaves => "minecraft:dripstone_caves" = 174,
+    LushCaves => "minecraft:lush_caves" = 175,
+);
+
+/// Biomes of a chunk column, one per 4x4x4 cell.
+///
+/// Cells are stored the way ChunkData sends them: ordered by y, then z, then x.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BiomeGrid{
+    cells: Vec<Biome>
+}
+
+impl BiomeGrid{
+    /// A grid for a column of `height` blocks filled with a single biome.
+    pub fn filled(height: usize, biome: Biome) -> Self {
+        BiomeGrid{
+            cells: vec![biome; height / 4 * 16]
+        }
+    }
+
+    /// Interprets the raw ids of ChunkData. The amount of ids must be a multiple of 16.
+    pub fn from_ids<R: BiomeRegistry>(ids: &[i32]) -> std::io::Result<Self> {
+        if !ids.len().is_multiple_of(16) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} biome cells do not form whole layers", ids.len())));
+        }
+        Ok(BiomeGrid{
+            cells: ids.iter().map(|&id| R::biome(id)).collect()
+        })
+    }
+
+    pub fn to_ids<R: BiomeRegistry>(&self) -> Vec<i32> {
+        self.cells.iter().map(|&biome| R::id(biome)).collect()
+    }
+
+    /// Height of the column in blocks.
+    pub fn height(&self) -> usize {
+        self.cells.len() / 16 * 4
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> Option<usize> {
+        if y >= self.height() {
+            return None;
+        }
+        Some(((y >> 2) << 4) | (((z & 0xF) >> 2) << 2) | ((x & 0xF) >> 2))
+    }
+
+    /// The biome at the given block, relative to the bottom of the column.
+    /// `x` and `z` wrap around at the chunk border.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<Biome> {
+        self.index(x, y, z).map(|index| self.cells[index])
+    }
+
+    /// Sets the biome of the whole cell the block is in. Returns `false` if `y` is out of bounds.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, biome: Biome) -> bool {
+        match self.index(x, y, z) {
+            Some(index) => {
+                self.cells[index] = biome;
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn cells(&self) -> &[Biome] {
+        &self.cells
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod chunk_data{
+    use super::{BiomeGrid, BiomeRegistry};
+    use crate::protocol::implementation::steven::v1_17::ChunkData;
+    use steven_protocol::protocol::{LenPrefixed, VarInt};
+
+    impl BiomeGrid{
+        pub fn from_chunk_data<R: BiomeRegistry>(chunk: &ChunkData) -> std::io::Result<Self> {
+            let ids: Vec<i32> = chunk.biomes.data.iter().map(|id| id.0).collect();
+            Self::from_ids::<R>(&ids)
+        }
+
+        pub fn to_chunk_data<R: BiomeRegistry>(&self) -> LenPrefixed<VarInt, VarInt> {
+            LenPrefixed::new(self.to_ids::<R>().into_iter().map(VarInt).collect())
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Typed views of the block entity NBT of ChunkData and UpdateBlockEntity for
+//! the block entities tools look at most: signs, containers, banners, skulls
+//! and spawners. Everything else, and NBT that does not fit the expected
+//! layout, is kept as `BlockEntity::Other`.
+//!
+//! Layouts of older versions are understood as well: signs before 1.20 with
+//! `Text1` to `Text4`, banner patterns with numeric colors before 1.20.5 and
+//! skull owners before 1.20.5 as `SkullOwner`. Text stays the JSON it is sent as.
+use crate::segment::implementation::nbt::Tag;
+
+/// Dye colors by their id, as banners named them before 1.20.5.
+pub const DYE_COLORS: [&str; 16] = [
+    "white", "orange", "magenta", "light_blue", "yellow", "lime", "pink", "gray",
+    "light_gray", "cyan", "purple", "blue", "brown", "green", "red", "black"
+];
+
+/// Block entity types that hold items and may be named, without namespace.
+pub const CONTAINERS: [&str; 14] = [
+    "chest", "trapped_chest", "barrel", "shulker_box", "hopper", "dispenser", "dropper", "furnace",
+    "blast_furnace", "smoker", "brewing_stand", "crafter", "chiseled_bookshelf", "decorated_pot"
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignText{
+    /// The four lines as JSON text.
+    pub messages: [String; 4],
+    /// A dye color, `black` by default.
+    pub color: String,
+    pub glowing: bool
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sign{
+    pub front: SignText,
+    /// Empty before 1.20, when signs only had a front.
+    pub back: SignText,
+    pub waxed: bool
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Container{
+    /// JSON text given with an anvil.
+    pub custom_name: Option<String>,
+    pub lock: Option<String>,
+    /// The loot table filling the container when it is first opened.
+    pub loot_table: Option<String>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BannerPattern{
+    /// `minecraft:border` since 1.20.5, a short code like `bo` before.
+    pub pattern: String,
+    /// A dye color, see `DYE_COLORS`.
+    pub color: String
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Banner{
+    pub custom_name: Option<String>,
+    pub patterns: Vec<BannerPattern>
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Skull{
+    pub owner_name: Option<String>,
+    pub owner_id: Option<u128>,
+    /// The base64 `textures` property of the owner's profile.
+    pub texture: Option<String>,
+    /// The sound a note block plays with the skull on top, since 1.20.
+    pub note_block_sound: Option<String>
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spawner{
+    /// The entity type spawned next, like `minecraft:zombie`.
+    pub entity: Option<String>,
+    /// Ticks until the next spawn.
+    pub delay: i16,
+    pub min_spawn_delay: i16,
+    pub max_spawn_delay: i16,
+    pub spawn_count: i16,
+    pub max_nearby_entities: i16,
+    pub required_player_range: i16,
+    pub spawn_range: i16
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlockEntity{
+    /// Also hanging signs.
+    Sign(Sign),
+    Container(Container),
+    Banner(Banner),
+    Skull(Skull),
+    Spawner(Spawner),
+    Other(Tag)
+}
+
+impl BlockEntity{
+    /// Decodes the NBT of a block entity of the given type, like
+    /// `minecraft:sign` or `sign`. Types are sent apart from the NBT since 1.18.
+    pub fn from_nbt(kind: &str, tag: Tag) -> Self {
+        let kind = kind.strip_prefix("minecraft:").unwrap_or(kind);
+        let typed = match kind {
+            "sign" | "hanging_sign" => sign(&tag).map(BlockEntity::Sign),
+            "banner" => banner(&tag).map(BlockEntity::Banner),
+            "skull" => Some(BlockEntity::Skull(skull(&tag))),
+            "mob_spawner" => spawner(&tag).map(BlockEntity::Spawner),
+            kind if CONTAINERS.contains(&kind) => Some(BlockEntity::Container(Container{
+                custom_name: string(&tag, "CustomName"),
+                lock: string(&tag, "Lock"),
+                loot_table: string(&tag, "LootTable")
+            })),
+            _ => None
+        };
+        typed.unwrap_or(BlockEntity::Other(tag))
+    }
+
+    /// Decodes NBT that names its type in an `id` entry, as sent before 1.18.
+    pub fn from_tagged(tag: Tag) -> Self {
+        match string(&tag, "id") {
+            Some(kind) => Self::from_nbt(&kind, tag),
+            None => BlockEntity::Other(tag)
+        }
+    }
+}
+
+fn sign(tag: &Tag) -> Option<Sign> {
+    if tag.get("front_text").is_none() {
+        let line = |name: &str| string(tag, name).unwrap_or_default();
+        return Some(Sign{
+            front: SignText{
+                messages: [line("Text1"), line("Text2"), line("Text3"), line("Text4")],
+                color: string(tag, "Color").unwrap_or_else(|| String::from("black")),
+                glowing: int(tag, "GlowingText").is_some_and(|glowing| glowing != 0)
+            },
+            back: SignText::default(),
+            waxed: false
+        });
+    }
+    Some(Sign{
+        front: sign_text(tag.get("front_text")?)?,
+        back: tag.get("back_text").map_or(Some(SignText::default()), sign_text)?,
+        waxed: int(tag, "is_waxed").is_some_and(|waxed| waxed != 0)
+    })
+}
+
+fn sign_text(tag: &Tag) -> Option<SignText> {
+    let lines = tag.get("messages")?.as_list()?;
+    if lines.len() != 4 {
+        return None;
+    }
+    let line = |i: usize| lines[i].as_str().map(str::to_string);
+    Some(SignText{
+        messages: [line(0)?, line(1)?, line(2)?, line(3)?],
+        color: string(tag, "color").unwrap_or_else(|| String::from("black")),
+        glowing: int(tag, "has_glowing_text").is_some_and(|glowing| glowing != 0)
+    })
+}
+
+fn banner(tag: &Tag) -> Option<Banner> {
+    let patterns = match (tag.get("patterns"), tag.get("Patterns")) {
+        (Some(patterns), _) => patterns.as_list()?.iter().map(|pattern| Some(BannerPattern{
+            pattern: string(pattern, "pattern")?,
+            color: string(pattern, "color")?
+        })).collect::<Option<_>>()?,
+        (None, Some(patterns)) => patterns.as_list()?.iter().map(|pattern| Some(BannerPattern{
+            pattern: string(pattern, "Pattern")?,
+            color: DYE_COLORS.get(int(pattern, "Color")? as usize)?.to_string()
+        })).collect::<Option<_>>()?,
+        (None, None) => Vec::new()
+    };
+    Some(Banner{ custom_name: string(tag, "CustomName"), patterns })
+}
+
+fn skull(tag: &Tag) -> Skull {
+    let note_block_sound = string(tag, "note_block_sound");
+    let owner = tag.get("profile").or_else(|| tag.get("SkullOwner")).or_else(|| tag.get("Owner"));
+    match owner {
+        // A bare name, as commands and old worlds store it.
+        Some(Tag::String(name)) => Skull{ owner_name: Some(name.clone()), note_block_sound, ..Skull::default() },
+        Some(owner) => Skull{
+            owner_name: string(owner, "name").or_else(|| string(owner, "Name")),
+            owner_id: owner.get("id").or_else(|| owner.get("Id")).and_then(uuid),
+            texture: texture(owner),
+            note_block_sound
+        },
+        None => Skull{ note_block_sound, ..Skull::default() }
+    }
+}
+
+/// The textures property, a list of `{name, value}` since 1.20.5 and
+/// `{textures: [{Value}]}` before.
+fn texture(owner: &Tag) -> Option<String> {
+    match owner.get("properties") {
+        Some(properties) => properties.as_list()?.iter()
+            .find(|property| string(property, "name").as_deref() == Some("textures"))
+            .and_then(|property| string(property, "value")),
+        None => string(owner.get("Properties")?.get("textures")?.as_list()?.first()?, "Value")
+    }
+}
+
+/// A UUID as four ints, or as a string before 1.16.
+fn uuid(tag: &Tag) -> Option<u128> {
+    match tag {
+        Tag::IntArray(ints) if ints.len() == 4 => Some(ints.iter().fold(0, |uuid, &int| uuid << 32 | int as u32 as u128)),
+        Tag::String(uuid) => u128::from_str_radix(&uuid.replace('-', ""), 16).ok(),
+        _ => None
+    }
+}
+
+fn spawner(tag: &Tag) -> Option<Spawner> {
+    let short = |name: &str| int(tag, name).map(|value| value as i16);
+    // Since 1.18 the entity is nested within SpawnData.
+    let spawn_data = tag.get("SpawnData");
+    let entity = spawn_data.and_then(|data| data.get("entity")).or(spawn_data).and_then(|entity| string(entity, "id"));
+    Some(Spawner{
+        entity,
+        delay: short("Delay")?,
+        min_spawn_delay: short("MinSpawnDelay")?,
+        max_spawn_delay: short("MaxSpawnDelay")?,
+        spawn_count: short("SpawnCount")?,
+        max_nearby_entities: short("MaxNearbyEntities")?,
+        required_player_range: short("RequiredPlayerRange")?,
+        spawn_range: short("SpawnRange")?
+    })
+}
+
+fn string(tag: &Tag, name: &str) -> Option<String> {
+    tag.get(name).and_then(Tag::as_str).map(str::to_string)
+}
+
+fn int(tag: &Tag, name: &str) -> Option<i64> {
+    tag.get(name).and_then(Tag::as_i64)
+}
@@ -0,0 +1,471 @@
+//! Block states of chunk columns in the 1.17 ChunkData format.
+//!
+//! The data of a ChunkData packet holds one section of 16x16x16 blocks for
+//! every bit set in its bitmask, from the bottom of the world up:
+//!
+//! `[i16 non air blocks][u8 bits per block][palette][VarInt long count][longs]`
+//!
+//! With up to 8 bits per block the palette is a VarInt length followed by the
+//! global ids of the states, and the longs hold indices into it. Above that
+//! there is no palette and the longs hold global ids directly. Entries do not
+//! span longs, the leftover high bits of each long are unused. Blocks are
+//! ordered by y, then z, then x.
+use crate::segment::implementation::var::{read_var_int, var_int_len, write_var_int};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind, Write};
+
+pub const SECTION_BLOCKS: usize = 4096;
+
+/// Bits per block of sections without a palette in 1.17.
+pub const GLOBAL_BITS: u8 = 15;
+
+const MIN_PALETTE_BITS: u8 = 4;
+const MAX_PALETTE_BITS: u8 = 8;
+
+/// Longs `ChunkSection::write_into` writes at once.
+const LONG_BATCH: usize = 64;
+
+/// Global ids of air, void air and cave air in 1.17, which do not count
+/// towards the non air blocks of a section.
+pub const AIR_STATES: [i32; 3] = [0, 9915, 9916];
+
+pub fn is_air(state: i32) -> bool {
+    AIR_STATES.contains(&state)
+}
+
+/// 16x16x16 block states stored the way they are sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSection{
+    non_air: i16,
+    bits: u8,
+    /// `None` when the data holds global ids.
+    palette: Option<Vec<i32>>,
+    data: Vec<u64>
+}
+
+impl Default for ChunkSection{
+    /// A section filled with air.
+    fn default() -> Self {
+        Self::filled(0)
+    }
+}
+
+impl ChunkSection{
+    pub fn filled(state: i32) -> Self {
+        ChunkSection{
+            non_air: if is_air(state) { 0 }else{ SECTION_BLOCKS as i16 },
+            bits: MIN_PALETTE_BITS,
+            palette: Some(vec![state]),
+            data: vec![0; longs_needed(MIN_PALETTE_BITS)]
+        }
+    }
+
+    pub fn decode(reader: &mut &[u8]) -> std::io::Result<Self> {
+        let non_air = reader.read_i16::<BigEndian>()?;
+        let sent_bits = reader.read_u8()?;
+        if sent_bits == 0 || sent_bits > 32 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} bits per block is not supported", sent_bits)));
+        }
+        let (bits, palette) = if sent_bits <= MAX_PALETTE_BITS {
+            let len = read_var_int(reader)?;
+            if !(1..=1 << MAX_PALETTE_BITS).contains(&len) {
+                return Err(Error::new(ErrorKind::InvalidData, format!("invalid palette length {}", len)));
+            }
+            let mut palette = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                palette.push(read_var_int(reader)?);
+            }
+            (sent_bits.max(MIN_PALETTE_BITS), Some(palette))
+        }else{
+            (sent_bits, None)
+        };
+        let len = read_var_int(reader)?;
+        if len as usize != longs_needed(sent_bits) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} longs do not hold a section at {} bits per block", len, sent_bits)));
+        }
+        let mut data = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            data.push(reader.read_u64::<BigEndian>()?);
+        }
+        let mut section = ChunkSection{ non_air, bits: sent_bits, palette, data };
+        if bits != sent_bits {
+            section.repack(bits);
+        }
+        if let Some(palette) = &section.palette {
+            if (0..SECTION_BLOCKS).any(|index| section.raw(index) as usize >= palette.len()) {
+                return Err(Error::new(ErrorKind::InvalidData, "block refers past the end of the palette"));
+            }
+        }
+        Ok(section)
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.reserve(self.serialized_len());
+        let _ = self.write_into(out);
+    }
+
+    /// Writes the section straight to `writer`, without building it up in a
+    /// buffer first. The longs go out in batches from the stack.
+    pub fn write_into<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_i16::<BigEndian>(self.non_air)?;
+        writer.write_u8(self.bits)?;
+        if let Some(palette) = &self.palette {
+            write_var_int(palette.len() as i32, writer)?;
+            for &state in palette {
+                write_var_int(state, writer)?;
+            }
+        }
+        write_var_int(self.data.len() as i32, writer)?;
+        let mut batch = [0u8; 8 * LONG_BATCH];
+        for longs in self.data.chunks(LONG_BATCH) {
+            for (bytes, long) in batch.chunks_exact_mut(8).zip(longs) {
+                bytes.copy_from_slice(&long.to_be_bytes());
+            }
+            writer.write_all(&batch[..longs.len() * 8])?;
+        }
+        Ok(())
+    }
+
+    /// Amount of bytes `write_into` writes.
+    pub fn serialized_len(&self) -> usize {
+        let palette = self.palette.as_ref().map_or(0, |palette| {
+            var_int_len(palette.len() as i32) + palette.iter().map(|&state| var_int_len(state)).sum::<usize>()
+        });
+        2 + 1 + palette + var_int_len(self.data.len() as i32) + self.data.len() * 8
+    }
+
+    /// The state at the given coordinates, which wrap around at 16.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> i32 {
+        let raw = self.raw(index(x, y, z));
+        match &self.palette {
+            Some(palette) => palette[raw as usize],
+            None => raw as i32
+        }
+    }
+
+    /// Sets a state and returns the previous one.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, state: i32) -> i32 {
+        let index = index(x, y, z);
+        let previous = self.get(x, y, z);
+        if previous == state {
+            return previous;
+        }
+        if let Some(palette) = &mut self.palette {
+            if !palette.contains(&state) {
+                palette.push(state);
+                if palette.len() > 1 << self.bits {
+                    self.repack(self.bits + 1);
+                }
+            }
+        }
+        // Repacking may have dropped the palette, in which case the global id is stored.
+        let raw = match &self.palette {
+            Some(palette) => palette.iter().position(|&entry| entry == state).expect("added above") as u64,
+            None => state as u64
+        };
+        self.set_raw(index, raw);
+        match (is_air(previous), is_air(state)) {
+            (true, false) => self.non_air += 1,
+            (false, true) => self.non_air -= 1,
+            _ => {}
+        }
+        previous
+    }
+
+    /// Amount of blocks that are not air.
+    pub fn non_air(&self) -> i16 {
+        self.non_air
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// The distinct states of the section if it uses a palette.
+    pub fn palette(&self) -> Option<&[i32]> {
+        self.palette.as_deref()
+    }
+
+    /// Stores the blocks with a different amount of bits. Going past
+    /// `MAX_PALETTE_BITS` resolves the palette into global ids.
+    fn repack(&mut self, bits: u8) {
+        let values: Vec<u64> = (0..SECTION_BLOCKS).map(|index| self.raw(index)).collect();
+        let (bits, values) = match &self.palette {
+            Some(palette) if bits > MAX_PALETTE_BITS => {
+                let values = values.iter().map(|&raw| palette[raw as usize] as u64).collect();
+                self.palette = None;
+                (GLOBAL_BITS.max(bits), values)
+            }
+            _ => (bits, values)
+        };
+        self.bits = bits;
+        self.data = vec![0; longs_needed(bits)];
+        for (index, value) in values.into_iter().enumerate() {
+            self.set_raw(index, value);
+        }
+    }
+
+    fn raw(&self, index: usize) -> u64 {
+        let per_long = 64 / self.bits as usize;
+        let shift = (index % per_long) * self.bits as usize;
+        (self.data[index / per_long] >> shift) & mask(self.bits)
+    }
+
+    fn set_raw(&mut self, index: usize, value: u64) {
+        let per_long = 64 / self.bits as usize;
+        let shift = (index % per_long) * self.bits as usize;
+        let long = &mut self.data[index / per_long];
+        *long = (*long & !(mask(self.bits) << shift)) | ((value & mask(self.bits)) << shift);
+    }
+}
+
+fn index(x: usize, y: usize, z: usize) -> usize {
+    ((y & 0xF) << 8) | ((z & 0xF) << 4) | (x & 0xF)
+}
+
+fn mask(bits: u8) -> u64 {
+    (1 << bits) - 1
+}
+
+fn longs_needed(bits: u8) -> usize {
+    SECTION_BLOCKS.div_ceil(64 / bits as usize)
+}
+
+/// The block states of a chunk column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkColumn{
+    pub x: i32,
+    pub z: i32,
+    /// Section y of the lowest section, the minimum y of the world divided by 16.
+    min_section: i32,
+    /// From the bottom up, `None` for sections that were not sent.
+    sections: Vec<Option<ChunkSection>>
+}
+
+impl ChunkColumn{
+    /// An empty column of `section_count` sections.
+    pub fn new(x: i32, z: i32, min_section: i32, section_count: usize) -> Self {
+        ChunkColumn{ x, z, min_section, sections: vec![None; section_count] }
+    }
+
+    /// Decodes the data of ChunkData, with `bitmask` holding the longs of its bitmask.
+    pub fn decode(x: i32, z: i32, min_section: i32, section_count: usize, bitmask: &[i64], mut data: &[u8]) -> std::io::Result<Self> {
+        let mut column = Self::new(x, z, min_section, section_count);
+        for (index, section) in column.sections.iter_mut().enumerate() {
+            let sent = bitmask.get(index / 64).is_some_and(|long| long >> (index % 64) & 1 == 1);
+            if sent {
+                *section = Some(ChunkSection::decode(&mut data)?);
+            }
+        }
+        let beyond = (section_count..bitmask.len() * 64).any(|index| bitmask[index / 64] >> (index % 64) & 1 == 1);
+        if beyond {
+            return Err(Error::new(ErrorKind::InvalidData, "chunk bitmask has sections above the world height"));
+        }
+        if !data.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "chunk data has trailing bytes"));
+        }
+        Ok(column)
+    }
+
+    /// The bitmask and data of ChunkData. Sections holding only air are left out.
+    pub fn encode(&self) -> (Vec<i64>, Vec<u8>) {
+        let mut data = Vec::with_capacity(self.data_len());
+        for section in self.sent_sections() {
+            let _ = section.write_into(&mut data);
+        }
+        (self.bitmask(), data)
+    }
+
+    /// The bitmask of ChunkData, see `encode`.
+    pub fn bitmask(&self) -> Vec<i64> {
+        let mut bitmask = vec![0i64; self.sections.len().div_ceil(64)];
+        for (index, section) in self.sections.iter().enumerate() {
+            if section.as_ref().is_some_and(|section| section.non_air() > 0) {
+                bitmask[index / 64] |= 1 << (index % 64);
+            }
+        }
+        bitmask
+    }
+
+    /// Length of the data of ChunkData, without its VarInt length prefix.
+    pub fn data_len(&self) -> usize {
+        self.sent_sections().map(ChunkSection::serialized_len).sum()
+    }
+
+    /// Writes the data of ChunkData with its VarInt length prefix straight to
+    /// `writer`, for servers sending many chunks. The bytes are the same as
+    /// `encode` returns, without the intermediate buffer.
+    pub fn write_into<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_var_int(self.data_len() as i32, writer)?;
+        for section in self.sent_sections() {
+            section.write_into(writer)?;
+        }
+        Ok(())
+    }
+
+    fn sent_sections(&self) -> impl Iterator<Item = &ChunkSection> {
+        self.sections.iter().flatten().filter(|section| section.non_air() > 0)
+    }
+
+    fn section_index(&self, y: i32) -> Option<usize> {
+        let index = (y >> 4) - self.min_section;
+        if index < 0 || index as usize >= self.sections.len() {
+            None
+        }else{
+            Some(index as usize)
+        }
+    }
+
+    /// The state at an absolute y and x, z within the column. `None` if y is
+    /// outside the world, sections that were not sent are air.
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Option<i32> {
+        let index = self.section_index(y)?;
+        Some(self.sections[index].as_ref().map_or(0, |section| section.get(x as usize, y as usize, z as usize)))
+    }
+
+    /// Sets a state, returning the previous one or `None` if y is outside the world.
+    pub fn set(&mut self, x: i32, y: i32, z: i32, state: i32) -> Option<i32> {
+        let index = self.section_index(y)?;
+        let section = match &mut self.sections[index] {
+            Some(section) => section,
+            None if is_air(state) => return Some(0),
+            empty => empty.insert(ChunkSection::default())
+        };
+        Some(section.set(x as usize, y as usize, z as usize, state))
+    }
+
+    pub fn section(&self, section_y: i32) -> Option<&ChunkSection> {
+        self.section_index(section_y << 4).and_then(|index| self.sections[index].as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Coordinates of the block at `index`, so distinct indices never wrap onto each other.
+    fn position(index: usize) -> (usize, usize, usize) {
+        (index & 0xF, index >> 8, (index >> 4) & 0xF)
+    }
+
+    fn round_trip(section: &ChunkSection) -> ChunkSection {
+        let mut bytes = Vec::new();
+        section.encode(&mut bytes);
+        assert_eq!(bytes.len(), section.serialized_len());
+        let mut reader = &bytes[..];
+        let decoded = ChunkSection::decode(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        decoded
+    }
+
+    #[test]
+    fn filled_section_round_trips() {
+        let section = ChunkSection::filled(1);
+        assert_eq!(section.non_air(), SECTION_BLOCKS as i16);
+        assert_eq!(round_trip(&section), section);
+        assert_eq!(ChunkSection::default().non_air(), 0);
+    }
+
+    #[test]
+    fn palette_grows_a_bit_at_a_time() {
+        let mut section = ChunkSection::default();
+        for state in 1..=15 {
+            let (x, y, z) = position(state as usize);
+            section.set(x, y, z, state);
+        }
+        assert_eq!(section.bits(), 4);
+        section.set(0, 0, 0, 16);
+        assert_eq!(section.bits(), 5);
+        assert_eq!(section.palette().map(<[i32]>::len), Some(17));
+        for state in 1..=15 {
+            let (x, y, z) = position(state as usize);
+            assert_eq!(section.get(x, y, z), state);
+        }
+        assert_eq!(section.get(0, 0, 0), 16);
+        assert_eq!(section.non_air(), 16);
+        assert_eq!(round_trip(&section), section);
+    }
+
+    #[test]
+    fn large_palettes_become_global_ids() {
+        let mut section = ChunkSection::default();
+        for state in 1..=256 {
+            let (x, y, z) = position(state as usize);
+            section.set(x, y, z, state);
+        }
+        assert_eq!(section.bits(), GLOBAL_BITS);
+        assert_eq!(section.palette(), None);
+        for state in 1..=256 {
+            let (x, y, z) = position(state as usize);
+            assert_eq!(section.get(x, y, z), state);
+        }
+        assert_eq!(section.get(0, 0, 0), 0);
+        assert_eq!(round_trip(&section), section);
+    }
+
+    #[test]
+    fn setting_air_counts_down() {
+        let mut section = ChunkSection::filled(1);
+        assert_eq!(section.set(3, 4, 5, AIR_STATES[2]), 1);
+        assert_eq!(section.set(3, 4, 5, AIR_STATES[2]), AIR_STATES[2]);
+        assert_eq!(section.non_air(), SECTION_BLOCKS as i16 - 1);
+    }
+
+    #[test]
+    fn narrow_sections_are_repacked_on_decode() {
+        // One bit per block, alternating between the two palette entries.
+        let mut bytes = vec![0x10, 0x00, 1, 2, 1, 2];
+        write_var_int(64, &mut bytes).unwrap();
+        for _ in 0..64 {
+            bytes.extend_from_slice(&0xAAAA_AAAA_AAAA_AAAAu64.to_be_bytes());
+        }
+        let section = ChunkSection::decode(&mut &bytes[..]).unwrap();
+        assert_eq!(section.bits(), MIN_PALETTE_BITS);
+        for index in 0..SECTION_BLOCKS {
+            let (x, y, z) = position(index);
+            assert_eq!(section.get(x, y, z), if index % 2 == 0 { 1 } else { 2 });
+        }
+    }
+
+    #[test]
+    fn indices_past_the_palette_are_invalid() {
+        let mut bytes = vec![0x10, 0x00, 4, 1, 1];
+        write_var_int(256, &mut bytes).unwrap();
+        bytes.extend_from_slice(&1u64.to_be_bytes());
+        bytes.resize(bytes.len() + 255 * 8, 0);
+        let error = ChunkSection::decode(&mut &bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn column_round_trips() {
+        let mut column = ChunkColumn::new(2, -3, -4, 24);
+        assert_eq!(column.set(1, -64, 2, 1), Some(0));
+        assert_eq!(column.set(15, 319, 15, 2), Some(0));
+        assert_eq!(column.set(0, 320, 0, 1), None);
+        // Air in a section that was never sent does not create it.
+        column.set(0, 0, 0, 0);
+        let (bitmask, data) = column.encode();
+        assert_eq!(bitmask, vec![1 | 1 << 23]);
+        assert_eq!(data.len(), column.data_len());
+        let decoded = ChunkColumn::decode(2, -3, -4, 24, &bitmask, &data).unwrap();
+        assert_eq!(decoded, column);
+        assert_eq!(decoded.get(15, 319, 15), Some(2));
+
+        let mut prefixed = Vec::new();
+        column.write_into(&mut prefixed).unwrap();
+        assert_eq!(prefixed[var_int_len(data.len() as i32)..], data[..]);
+    }
+
+    #[test]
+    fn column_refuses_trailing_bytes_and_sections_above_the_world() {
+        let (bitmask, mut data) = {
+            let mut column = ChunkColumn::new(0, 0, 0, 16);
+            column.set(0, 0, 0, 1);
+            column.encode()
+        };
+        assert!(ChunkColumn::decode(0, 0, 0, 1, &[0b11], &data).is_err());
+        data.push(0);
+        assert!(ChunkColumn::decode(0, 0, 0, 16, &bitmask, &data).is_err());
+    }
+}
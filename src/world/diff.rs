@@ -0,0 +1,109 @@
+//! Turns block mutations on a chunk into the cheapest set of packets that
+//! brings clients up to date.
+use crate::protocol::implementation::steven::v1_17::{BlockChange, ChunkData, MultiBlockChange, Proto_1_17};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use steven_protocol::protocol::{LenPrefixed, VarInt, VarLong};
+use steven_shared::Position;
+
+/// A chunk column as kept by the server.
+pub trait TrackedChunk{
+    /// Chunk coordinates, i.e. block coordinates divided by 16.
+    fn chunk_position(&self) -> (i32, i32);
+    /// Sets a block using absolute block coordinates.
+    fn set_block(&mut self, x: i32, y: i32, z: i32, block_state: i32);
+    /// Encodes the complete chunk column.
+    fn chunk_data(&self) -> std::io::Result<ChunkData>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockMutation{
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub block_state: i32
+}
+
+/// Decides between BlockChange, MultiBlockChange and a full ChunkData resend.
+#[derive(Debug, Clone)]
+pub struct ChunkDiff{
+    /// Minimum amount of changed blocks in a single section before they are
+    /// batched into a MultiBlockChange instead of separate BlockChanges.
+    pub multi_block_threshold: usize,
+    /// Amount of changed blocks in the whole column from which on the chunk
+    /// is resent entirely.
+    pub resend_threshold: usize
+}
+
+impl Default for ChunkDiff{
+    fn default() -> Self {
+        ChunkDiff{
+            multi_block_threshold: 2,
+            resend_threshold: 4096
+        }
+    }
+}
+
+impl ChunkDiff{
+    /// Applies the mutations to the chunk and returns the packets to send.
+    ///
+    /// When a block is mutated more than once only the last mutation is sent.
+    /// Mutations outside of the chunk are rejected before anything is applied.
+    pub fn apply<C: TrackedChunk>(&self, chunk: &mut C, mutations: &[BlockMutation]) -> std::io::Result<Vec<Proto_1_17>> {
+        let (chunk_x, chunk_z) = chunk.chunk_position();
+        // Keyed on section, then on block, so the output is ordered and deduplicated.
+        let mut sections: BTreeMap<(i32, i32, i32), BTreeMap<(i32, i32, i32), i32>> = BTreeMap::new();
+        for mutation in mutations {
+            if mutation.x >> 4 != chunk_x || mutation.z >> 4 != chunk_z {
+                return Err(Error::new(ErrorKind::InvalidInput, format!(
+                    "block {} {} {} is not part of chunk {} {}",
+                    mutation.x, mutation.y, mutation.z, chunk_x, chunk_z
+                )));
+            }
+            sections.entry((mutation.x >> 4, mutation.y >> 4, mutation.z >> 4))
+                .or_default()
+                .insert((mutation.x, mutation.y, mutation.z), mutation.block_state);
+        }
+        for blocks in sections.values() {
+            for (&(x, y, z), &block_state) in blocks {
+                chunk.set_block(x, y, z, block_state);
+            }
+        }
+
+        let changed: usize = sections.values().map(BTreeMap::len).sum();
+        if changed == 0 {
+            return Ok(Vec::new());
+        }
+        if changed >= self.resend_threshold {
+            return Ok(vec![Proto_1_17::ChunkData(Box::new(chunk.chunk_data()?))]);
+        }
+
+        let mut packets = Vec::new();
+        for (&(section_x, section_y, section_z), blocks) in &sections {
+            if blocks.len() < self.multi_block_threshold {
+                packets.extend(blocks.iter().map(|(&(x, y, z), &block_state)| {
+                    Proto_1_17::BlockChange(Box::new(BlockChange{
+                        location: Position::new(x, y, z),
+                        block_id: VarInt(block_state)
+                    }))
+                }));
+            }else{
+                let records = blocks.iter().map(|(&(x, y, z), &block_state)| {
+                    let local = ((x & 0xF) << 8) | ((z & 0xF) << 4) | (y & 0xF);
+                    VarLong(((block_state as i64) << 12) | local as i64)
+                }).collect();
+                packets.push(Proto_1_17::MultiBlockChange(Box::new(MultiBlockChange{
+                    chunk_section_pos: section_position(section_x, section_y, section_z),
+                    no_trust_edges: false,
+                    records: LenPrefixed::new(records)
+                })));
+            }
+        }
+        Ok(packets)
+    }
+}
+
+/// Packs section coordinates the way MultiBlockChange expects them.
+fn section_position(x: i32, y: i32, z: i32) -> u64 {
+    ((x as u64 & 0x3F_FFFF) << 42) | ((z as u64 & 0x3F_FFFF) << 20) | (y as u64 & 0xF_FFFF)
+}
@@ -0,0 +1,461 @@
+//! The dimension codec of JoinGame: the registries of dimension types and
+//! biomes the client needs before it can show a world.
+//!
+//! The codec is a compound holding `minecraft:dimension_type` and
+//! `minecraft:worldgen/biome`, each of them `{type, value}` where `value`
+//! lists `{name, id, element}` entries. The `dimension` field of JoinGame and
+//! Respawn is a single dimension type element.
+use crate::segment::implementation::nbt::Tag;
+use std::io::{Error, ErrorKind};
+
+pub const DIMENSION_TYPE_REGISTRY: &str = "minecraft:dimension_type";
+pub const BIOME_REGISTRY: &str = "minecraft:worldgen/biome";
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DimensionType{
+    pub piglin_safe: bool,
+    pub natural: bool,
+    pub ambient_light: f32,
+    /// Time of day the dimension is stuck at, if any.
+    pub fixed_time: Option<i64>,
+    /// Block tag of the blocks fire burns on forever.
+    pub infiniburn: String,
+    pub respawn_anchor_works: bool,
+    pub has_skylight: bool,
+    pub bed_works: bool,
+    /// Sky and fog rendering, `minecraft:overworld`, `minecraft:the_nether` or `minecraft:the_end`.
+    pub effects: String,
+    pub has_raids: bool,
+    pub min_y: i32,
+    pub height: i32,
+    pub logical_height: i32,
+    pub coordinate_scale: f64,
+    pub ultrawarm: bool,
+    pub has_ceiling: bool
+}
+
+impl DimensionType{
+    pub fn overworld() -> Self {
+        DimensionType{
+            piglin_safe: false,
+            natural: true,
+            ambient_light: 0.0,
+            fixed_time: None,
+            infiniburn: String::from("minecraft:infiniburn_overworld"),
+            respawn_anchor_works: false,
+            has_skylight: true,
+            bed_works: true,
+            effects: String::from("minecraft:overworld"),
+            has_raids: true,
+            min_y: 0,
+            height: 256,
+            logical_height: 256,
+            coordinate_scale: 1.0,
+            ultrawarm: false,
+            has_ceiling: false
+        }
+    }
+
+    pub fn the_nether() -> Self {
+        DimensionType{
+            piglin_safe: true,
+            natural: false,
+            ambient_light: 0.1,
+            fixed_time: Some(18000),
+            infiniburn: String::from("minecraft:infiniburn_nether"),
+            respawn_anchor_works: true,
+            has_skylight: false,
+            bed_works: false,
+            effects: String::from("minecraft:the_nether"),
+            has_raids: false,
+            min_y: 0,
+            height: 256,
+            logical_height: 128,
+            coordinate_scale: 8.0,
+            ultrawarm: true,
+            has_ceiling: true
+        }
+    }
+
+    pub fn the_end() -> Self {
+        DimensionType{
+            piglin_safe: false,
+            natural: false,
+            ambient_light: 0.0,
+            fixed_time: Some(6000),
+            infiniburn: String::from("minecraft:infiniburn_end"),
+            respawn_anchor_works: false,
+            has_skylight: false,
+            bed_works: false,
+            effects: String::from("minecraft:the_end"),
+            has_raids: true,
+            min_y: 0,
+            height: 256,
+            logical_height: 256,
+            coordinate_scale: 1.0,
+            ultrawarm: false,
+            has_ceiling: false
+        }
+    }
+
+    pub fn from_nbt(tag: &Tag) -> std::io::Result<Self> {
+        Ok(DimensionType{
+            piglin_safe: boolean(tag, "piglin_safe")?,
+            natural: boolean(tag, "natural")?,
+            ambient_light: float(tag, "ambient_light")? as f32,
+            fixed_time: tag.get("fixed_time").and_then(Tag::as_i64),
+            infiniburn: string(tag, "infiniburn")?,
+            respawn_anchor_works: boolean(tag, "respawn_anchor_works")?,
+            has_skylight: boolean(tag, "has_skylight")?,
+            bed_works: boolean(tag, "bed_works")?,
+            effects: string(tag, "effects")?,
+            has_raids: boolean(tag, "has_raids")?,
+            min_y: int(tag, "min_y")?,
+            height: int(tag, "height")?,
+            logical_height: int(tag, "logical_height")?,
+            coordinate_scale: float(tag, "coordinate_scale")?,
+            ultrawarm: boolean(tag, "ultrawarm")?,
+            has_ceiling: boolean(tag, "has_ceiling")?
+        })
+    }
+
+    pub fn to_nbt(&self) -> Tag {
+        let mut entries = vec![
+            entry("piglin_safe", Tag::Byte(self.piglin_safe as i8)),
+            entry("natural", Tag::Byte(self.natural as i8)),
+            entry("ambient_light", Tag::Float(self.ambient_light))
+        ];
+        if let Some(fixed_time) = self.fixed_time {
+            entries.push(entry("fixed_time", Tag::Long(fixed_time)));
+        }
+        entries.extend(vec![
+            entry("infiniburn", Tag::String(self.infiniburn.clone())),
+            entry("respawn_anchor_works", Tag::Byte(self.respawn_anchor_works as i8)),
+            entry("has_skylight", Tag::Byte(self.has_skylight as i8)),
+            entry("bed_works", Tag::Byte(self.bed_works as i8)),
+            entry("effects", Tag::String(self.effects.clone())),
+            entry("has_raids", Tag::Byte(self.has_raids as i8)),
+            entry("min_y", Tag::Int(self.min_y)),
+            entry("height", Tag::Int(self.height)),
+            entry("logical_height", Tag::Int(self.logical_height)),
+            entry("coordinate_scale", Tag::Double(self.coordinate_scale)),
+            entry("ultrawarm", Tag::Byte(self.ultrawarm as i8)),
+            entry("has_ceiling", Tag::Byte(self.has_ceiling as i8))
+        ]);
+        Tag::Compound(entries)
+    }
+}
+
+/// Colors and sounds of a biome.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BiomeEffects{
+    pub sky_color: i32,
+    pub water_fog_color: i32,
+    pub fog_color: i32,
+    pub water_color: i32,
+    pub foliage_color: Option<i32>,
+    pub grass_color: Option<i32>,
+    pub grass_color_modifier: Option<String>,
+    /// Music, ambient sounds and particles, kept as they were sent.
+    pub extra: Vec<(String, Tag)>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BiomeProperties{
+    /// `none`, `rain` or `snow`.
+    pub precipitation: String,
+    pub depth: f32,
+    pub temperature: f32,
+    pub scale: f32,
+    pub downfall: f32,
+    pub category: String,
+    pub temperature_modifier: Option<String>,
+    pub effects: BiomeEffects
+}
+
+impl BiomeProperties{
+    /// The vanilla plains biome, which the client falls back to for unknown biome ids.
+    pub fn plains() -> Self {
+        BiomeProperties{
+            precipitation: String::from("rain"),
+            depth: 0.125,
+            temperature: 0.8,
+            scale: 0.05,
+            downfall: 0.4,
+            category: String::from("plains"),
+            temperature_modifier: None,
+            effects: BiomeEffects{
+                sky_color: 7907327,
+                water_fog_color: 329011,
+                fog_color: 12638463,
+                water_color: 4159204,
+                foliage_color: None,
+                grass_color: None,
+                grass_color_modifier: None,
+                extra: vec![entry("mood_sound", Tag::Compound(vec![
+                    entry("tick_delay", Tag::Int(6000)),
+                    entry("offset", Tag::Double(2.0)),
+                    entry("sound", Tag::String(String::from("minecraft:ambient.cave"))),
+                    entry("block_search_extent", Tag::Int(8))
+                ]))]
+            }
+        }
+    }
+
+    pub fn from_nbt(tag: &Tag) -> std::io::Result<Self> {
+        let effects = tag.get("effects").ok_or_else(|| missing("effects"))?;
+        const KNOWN_EFFECTS: [&str; 7] = ["sky_color", "water_fog_color", "fog_color", "water_color", "foliage_color", "grass_color", "grass_color_modifier"];
+        let extra = match effects {
+            Tag::Compound(entries) => entries.iter().filter(|(name, _)| !KNOWN_EFFECTS.contains(&name.as_str())).cloned().collect(),
+            _ => return Err(invalid("biome effects are not a compound"))
+        };
+        Ok(BiomeProperties{
+            precipitation: string(tag, "precipitation")?,
+            depth: float(tag, "depth")? as f32,
+            temperature: float(tag, "temperature")? as f32,
+            scale: float(tag, "scale")? as f32,
+            downfall: float(tag, "downfall")? as f32,
+            category: string(tag, "category")?,
+            temperature_modifier: tag.get("temperature_modifier").and_then(Tag::as_str).map(str::to_string),
+            effects: BiomeEffects{
+                sky_color: int(effects, "sky_color")?,
+                water_fog_color: int(effects, "water_fog_color")?,
+                fog_color: int(effects, "fog_color")?,
+                water_color: int(effects, "water_color")?,
+                foliage_color: effects.get("foliage_color").and_then(Tag::as_i64).map(|color| color as i32),
+                grass_color: effects.get("grass_color").and_then(Tag::as_i64).map(|color| color as i32),
+                grass_color_modifier: effects.get("grass_color_modifier").and_then(Tag::as_str).map(str::to_string),
+                extra
+            }
+        })
+    }
+
+    pub fn to_nbt(&self) -> Tag {
+        let mut effects = vec![
+            entry("sky_color", Tag::Int(self.effects.sky_color)),
+            entry("water_fog_color", Tag::Int(self.effects.water_fog_color)),
+            entry("fog_color", Tag::Int(self.effects.fog_color)),
+            entry("water_color", Tag::Int(self.effects.water_color))
+        ];
+        if let Some(color) = self.effects.foliage_color {
+            effects.push(entry("foliage_color", Tag::Int(color)));
+        }
+        if let Some(color) = self.effects.grass_color {
+            effects.push(entry("grass_color", Tag::Int(color)));
+        }
+        if let Some(modifier) = &self.effects.grass_color_modifier {
+            effects.push(entry("grass_color_modifier", Tag::String(modifier.clone())));
+        }
+        effects.extend(self.effects.extra.iter().cloned());
+        let mut entries = vec![
+            entry("precipitation", Tag::String(self.precipitation.clone())),
+            entry("depth", Tag::Float(self.depth)),
+            entry("temperature", Tag::Float(self.temperature)),
+            entry("scale", Tag::Float(self.scale)),
+            entry("downfall", Tag::Float(self.downfall)),
+            entry("category", Tag::String(self.category.clone()))
+        ];
+        if let Some(modifier) = &self.temperature_modifier {
+            entries.push(entry("temperature_modifier", Tag::String(modifier.clone())));
+        }
+        entries.push(entry("effects", Tag::Compound(effects)));
+        Tag::Compound(entries)
+    }
+}
+
+/// An entry of a registry.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryEntry<T>{
+    pub name: String,
+    pub id: i32,
+    pub element: T
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DimensionCodec{
+    pub dimension_types: Vec<RegistryEntry<DimensionType>>,
+    pub biomes: Vec<RegistryEntry<BiomeProperties>>
+}
+
+impl DimensionCodec{
+    /// An empty codec, to be filled with `with_dimension_type` and `with_biome`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The vanilla dimension types with plains as the only biome, which is
+    /// enough for a client to join any of the vanilla dimensions.
+    pub fn vanilla() -> Self {
+        Self::new()
+            .with_dimension_type("minecraft:overworld", DimensionType::overworld())
+            .with_dimension_type("minecraft:the_nether", DimensionType::the_nether())
+            .with_dimension_type("minecraft:the_end", DimensionType::the_end())
+            .with_biome("minecraft:plains", BiomeProperties::plains())
+    }
+
+    /// Adds a dimension type with the next free id, or replaces the one with the same name.
+    pub fn with_dimension_type<S: Into<String>>(mut self, name: S, dimension_type: DimensionType) -> Self {
+        insert(&mut self.dimension_types, name.into(), dimension_type);
+        self
+    }
+
+    /// Adds a biome with the next free id, or replaces the one with the same
+    /// name. The id is what ChunkData refers to the biome with.
+    pub fn with_biome<S: Into<String>>(mut self, name: S, biome: BiomeProperties) -> Self {
+        insert(&mut self.biomes, name.into(), biome);
+        self
+    }
+
+    pub fn dimension_type(&self, name: &str) -> Option<&DimensionType> {
+        self.dimension_types.iter().find(|entry| entry.name == name).map(|entry| &entry.element)
+    }
+
+    pub fn biome(&self, name: &str) -> Option<&RegistryEntry<BiomeProperties>> {
+        self.biomes.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn biome_by_id(&self, id: i32) -> Option<&RegistryEntry<BiomeProperties>> {
+        self.biomes.iter().find(|entry| entry.id == id)
+    }
+
+    pub fn from_nbt(tag: &Tag) -> std::io::Result<Self> {
+        Ok(DimensionCodec{
+            dimension_types: read_registry(tag, DIMENSION_TYPE_REGISTRY, DimensionType::from_nbt)?,
+            biomes: read_registry(tag, BIOME_REGISTRY, BiomeProperties::from_nbt)?
+        })
+    }
+
+    pub fn to_nbt(&self) -> Tag {
+        Tag::Compound(vec![
+            entry(DIMENSION_TYPE_REGISTRY, write_registry(DIMENSION_TYPE_REGISTRY, &self.dimension_types, DimensionType::to_nbt)),
+            entry(BIOME_REGISTRY, write_registry(BIOME_REGISTRY, &self.biomes, BiomeProperties::to_nbt))
+        ])
+    }
+
+    /// Reads the codec as sent, a named compound.
+    pub fn decode(mut bytes: &[u8]) -> std::io::Result<Self> {
+        let (_, tag) = Tag::read_named(&mut bytes)?.ok_or_else(|| invalid("dimension codec is missing"))?;
+        Self::from_nbt(&tag)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        // Names, lists and compounds of the codec are built by this module and always valid.
+        let _ = self.to_nbt().write_named("", &mut bytes);
+        bytes
+    }
+}
+
+fn insert<T>(entries: &mut Vec<RegistryEntry<T>>, name: String, element: T) {
+    match entries.iter_mut().find(|entry| entry.name == name) {
+        Some(entry) => entry.element = element,
+        None => {
+            let id = entries.iter().map(|entry| entry.id + 1).max().unwrap_or(0);
+            entries.push(RegistryEntry{ name, id, element });
+        }
+    }
+}
+
+fn read_registry<T>(codec: &Tag, registry: &str, element: fn(&Tag) -> std::io::Result<T>) -> std::io::Result<Vec<RegistryEntry<T>>> {
+    let values = codec.get(registry)
+        .and_then(|registry| registry.get("value"))
+        .and_then(Tag::as_list)
+        .ok_or_else(|| invalid(&format!("dimension codec has no {} registry", registry)))?;
+    values.iter().map(|value| {
+        let name = string(value, "name")?;
+        let id = int(value, "id")?;
+        let element = element(value.get("element").ok_or_else(|| missing("element"))?)
+            .map_err(|e| Error::new(e.kind(), format!("{} {}: {}", registry, name, e)))?;
+        Ok(RegistryEntry{ name, id, element })
+    }).collect()
+}
+
+fn write_registry<T>(registry: &str, entries: &[RegistryEntry<T>], element: fn(&T) -> Tag) -> Tag {
+    Tag::Compound(vec![
+        entry("type", Tag::String(registry.to_string())),
+        entry("value", Tag::List(entries.iter().map(|registry_entry| Tag::Compound(vec![
+            entry("name", Tag::String(registry_entry.name.clone())),
+            entry("id", Tag::Int(registry_entry.id)),
+            entry("element", element(&registry_entry.element))
+        ])).collect()))
+    ])
+}
+
+fn entry(name: &str, tag: Tag) -> (String, Tag) {
+    (name.to_string(), tag)
+}
+
+fn boolean(tag: &Tag, name: &str) -> std::io::Result<bool> {
+    tag.get(name).and_then(Tag::as_i64).map(|value| value != 0).ok_or_else(|| missing(name))
+}
+
+fn int(tag: &Tag, name: &str) -> std::io::Result<i32> {
+    tag.get(name).and_then(Tag::as_i64).map(|value| value as i32).ok_or_else(|| missing(name))
+}
+
+fn float(tag: &Tag, name: &str) -> std::io::Result<f64> {
+    tag.get(name).and_then(Tag::as_f64).ok_or_else(|| missing(name))
+}
+
+fn string(tag: &Tag, name: &str) -> std::io::Result<String> {
+    tag.get(name).and_then(Tag::as_str).map(str::to_string).ok_or_else(|| missing(name))
+}
+
+fn missing(name: &str) -> Error {
+    invalid(&format!("{} is missing or has the wrong type", name))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::{DimensionCodec, DimensionType};
+    use crate::protocol::implementation::steven::v1_17::JoinGame;
+    use crate::segment::implementation::nbt::Tag;
+    use crate::segment::Segment;
+    use steven_protocol::nbt::NamedTag;
+
+    impl DimensionCodec{
+        pub fn from_join_game(join: &JoinGame) -> std::io::Result<Self> {
+            Self::decode(&to_bytes(&join.dimension_codec)?)
+        }
+
+        pub fn to_named_tag(&self) -> std::io::Result<Option<NamedTag>> {
+            from_bytes(&self.encode())
+        }
+    }
+
+    impl DimensionType{
+        /// The dimension the client joins, from the `dimension` field of JoinGame.
+        pub fn from_join_game(join: &JoinGame) -> std::io::Result<Self> {
+            let mut bytes = &to_bytes(&join.dimension)?[..];
+            let (_, tag) = Tag::read_named(&mut bytes)?.ok_or_else(|| super::invalid("dimension is missing"))?;
+            Self::from_nbt(&tag)
+        }
+
+        pub fn to_named_tag(&self) -> std::io::Result<Option<NamedTag>> {
+            let mut bytes = Vec::new();
+            self.to_nbt().write_named("", &mut bytes)?;
+            from_bytes(&bytes)
+        }
+    }
+
+    fn to_bytes(tag: &Option<NamedTag>) -> std::io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(tag.serialized_len());
+        tag.write_to_stream(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn from_bytes(mut bytes: &[u8]) -> std::io::Result<Option<NamedTag>> {
+        let mut tag = None;
+        tag.read_from_stream(&mut bytes)?;
+        Ok(tag)
+    }
+}
@@ -0,0 +1,122 @@
+//! Map items as the Maps packet draws them: a 128×128 canvas of color indices
+//! that the server fills with rectangular patches.
+//!
+//! A color index is a base color times four plus a shade. Base color 0 is
+//! transparent, the others are listed in `BASE_COLORS` and shaded with the
+//! multipliers of `SHADES`, which is how `rgba` renders them.
+use std::io::{Error, ErrorKind};
+
+/// The width and height of a map in pixels.
+pub const MAP_SIZE: usize = 128;
+
+/// The base colors of maps as RGB, by their id, as of 1.17.
+pub const BASE_COLORS: [u32; 62] = [
+    0x000000, 0x7fb238, 0xf7e9a3, 0xc7c7c7, 0xff0000, 0xa0a0ff, 0xa7a7a7, 0x007c00,
+    0xffffff, 0xa4a8b8, 0x976d4d, 0x707070, 0x4040ff, 0x8f7748, 0xfffcf5, 0xd87f33,
+    0xb24cd8, 0x6699d8, 0xe5e533, 0x7fcc19, 0xf27fa5, 0x4c4c4c, 0x999999, 0x4c7f99,
+    0x7f3fb2, 0x334cb2, 0x664c33, 0x667f33, 0x993333, 0x191919, 0xfaee4d, 0x5cdbd5,
+    0x4a80ff, 0x00d93a, 0x815631, 0x700200, 0xd1b1a1, 0x9f5224, 0x95576c, 0x706c8a,
+    0xba8524, 0x677535, 0xa04d4e, 0x392923, 0x876b62, 0x575c5c, 0x7a4958, 0x4c3e5c,
+    0x4c3223, 0x4c522a, 0x8e3c2e, 0x251610, 0xbd3031, 0x943f61, 0x5c191d, 0x167e86,
+    0x3a8e8c, 0x562c3e, 0x14b485, 0x646464, 0xd8af93, 0x7fa796
+];
+
+/// The multipliers of the four shades, out of 255.
+pub const SHADES: [u32; 4] = [180, 220, 255, 135];
+
+/// Renders a color index as RGBA. Transparent and unknown colors give
+/// `[0, 0, 0, 0]`.
+pub fn rgba(index: u8) -> [u8; 4] {
+    let base = (index / 4) as usize;
+    if base == 0 || base >= BASE_COLORS.len() {
+        return [0; 4];
+    }
+    let color = BASE_COLORS[base];
+    let shade = SHADES[(index % 4) as usize];
+    let channel = |shift: u32| ((color >> shift & 0xff) * shade / 255) as u8;
+    [channel(16), channel(8), channel(0), 0xff]
+}
+
+/// The pixels of one map, transparent until patches arrive.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MapCanvas{
+    colors: Box<[u8; MAP_SIZE * MAP_SIZE]>
+}
+
+impl Default for MapCanvas{
+    fn default() -> Self {
+        MapCanvas{ colors: Box::new([0; MAP_SIZE * MAP_SIZE]) }
+    }
+}
+
+impl std::fmt::Debug for MapCanvas{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapCanvas").finish_non_exhaustive()
+    }
+}
+
+impl MapCanvas{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draws a patch of `columns` by `rows` pixels at `x`, `z`, with `data`
+    /// holding the patch row by row. Patches that do not fit the canvas or
+    /// their data are refused without drawing anything.
+    pub fn apply(&mut self, columns: u8, rows: u8, x: u8, z: u8, data: &[u8]) -> std::io::Result<()> {
+        let (columns, rows, x, z) = (columns as usize, rows as usize, x as usize, z as usize);
+        if x + columns > MAP_SIZE || z + rows > MAP_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, format!("map patch of {}x{} at {}, {} exceeds the map", columns, rows, x, z)));
+        }
+        if data.len() != columns * rows {
+            return Err(Error::new(ErrorKind::InvalidData, format!("map patch of {}x{} has {} bytes", columns, rows, data.len())));
+        }
+        if columns == 0 {
+            return Ok(());
+        }
+        for (row, patch) in data.chunks(columns).enumerate() {
+            let start = (z + row) * MAP_SIZE + x;
+            self.colors[start..start + columns].copy_from_slice(patch);
+        }
+        Ok(())
+    }
+
+    /// The color index at `x`, `z`, or None outside of the map.
+    pub fn get(&self, x: usize, z: usize) -> Option<u8> {
+        if x >= MAP_SIZE || z >= MAP_SIZE {
+            return None;
+        }
+        Some(self.colors[z * MAP_SIZE + x])
+    }
+
+    /// The color indices row by row.
+    pub fn colors(&self) -> &[u8] {
+        &self.colors[..]
+    }
+
+    /// The map as RGBA pixels row by row, 4 bytes each.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.colors.iter().flat_map(|&index| rgba(index)).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.colors.fill(0);
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_21{
+    use super::MapCanvas;
+    use crate::protocol::implementation::steven::v1_21::Maps;
+
+    impl MapCanvas{
+        /// Draws the patch of a Maps packet, if it has one. The packet is
+        /// expected to be about this map.
+        pub fn handle(&mut self, packet: &Maps) -> std::io::Result<()> {
+            if packet.columns == 0 {
+                return Ok(());
+            }
+            self.apply(packet.columns, packet.rows, packet.x, packet.z, &packet.data.data)
+        }
+    }
+}
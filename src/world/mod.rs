@@ -0,0 +1,11 @@
+pub mod biome;
+pub mod block_entity;
+pub mod chunk;
+#[cfg(feature = "steven_shared")]
+pub mod diff;
+pub mod dimension;
+pub mod map;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod registry;
+pub mod store;
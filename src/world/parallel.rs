@@ -0,0 +1,61 @@
+//! Decoding the data of many ChunkData packets at once on a rayon thread pool.
+//!
+//! Decoding chunks dominates the time a bot spends logging into a busy server,
+//! which sends hundreds of columns right away. The columns do not depend on
+//! each other, so they are decoded in parallel and handed back keyed by their
+//! chunk coordinates.
+use crate::world::chunk::ChunkColumn;
+use crate::world::store::{ChunkExtras, World};
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::collections::HashMap;
+
+/// The parts of a ChunkData packet `ChunkColumn::decode` needs.
+#[derive(Debug, Clone)]
+pub struct ChunkPayload<'a>{
+    pub x: i32,
+    pub z: i32,
+    /// The longs of the bitmask.
+    pub bitmask: &'a [i64],
+    pub data: &'a [u8]
+}
+
+/// Decodes every payload on the global rayon pool. A payload that fails to
+/// decode only fails its own entry, and later payloads for the same
+/// coordinates replace earlier ones like they do in `World::load`.
+pub fn decode_chunks(min_section: i32, section_count: usize, payloads: &[ChunkPayload]) -> HashMap<(i32, i32), std::io::Result<ChunkColumn>> {
+    payloads.par_iter()
+        .map(|payload| ((payload.x, payload.z), ChunkColumn::decode(payload.x, payload.z, min_section, section_count, payload.bitmask, payload.data)))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// `decode_chunks` on a pool of its own, to keep chunk decoding from
+/// starving other work on the global pool.
+pub fn decode_chunks_in(pool: &ThreadPool, min_section: i32, section_count: usize, payloads: &[ChunkPayload]) -> HashMap<(i32, i32), std::io::Result<ChunkColumn>> {
+    pool.install(|| decode_chunks(min_section, section_count, payloads))
+}
+
+impl World{
+    /// Decodes a batch of chunks in parallel with the bounds of this world
+    /// and loads the ones that decoded. Returns the errors of the others,
+    /// which leave a chunk already loaded at their coordinates untouched.
+    pub fn load_all(&mut self, payloads: &[(ChunkPayload, ChunkExtras)]) -> HashMap<(i32, i32), std::io::Error> {
+        let chunks: Vec<ChunkPayload> = payloads.iter().map(|(payload, _)| payload.clone()).collect();
+        let mut extras: HashMap<(i32, i32), &ChunkExtras> = payloads.iter().map(|(payload, extras)| ((payload.x, payload.z), extras)).collect();
+        let mut errors = HashMap::new();
+        for (position, decoded) in decode_chunks(self.min_y() >> 4, self.section_count(), &chunks) {
+            match decoded {
+                Ok(blocks) => {
+                    let extras = extras.remove(&position).cloned().unwrap_or_default();
+                    self.insert(blocks, extras);
+                }
+                Err(error) => {
+                    errors.insert(position, error);
+                }
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,98 @@
+//! The synchronized registries of 1.20.5 and later, gathered from the
+//! RegistryData packets of the Configuration state.
+//!
+//! Packets of the Play state refer to registry entries by their network id,
+//! which is the position of the entry in its RegistryData packet. Slots and
+//! particles look up enchantments, banner patterns and the like this way.
+use crate::segment::implementation::mojang::identifier::Identifier;
+use crate::segment::implementation::mojang::registry::RegistryDataEntry;
+use crate::segment::implementation::nbt::Tag;
+use crate::world::dimension::{DimensionType, DIMENSION_TYPE_REGISTRY};
+use std::collections::BTreeMap;
+
+pub const CHAT_TYPE_REGISTRY: &str = "minecraft:chat_type";
+pub const DAMAGE_TYPE_REGISTRY: &str = "minecraft:damage_type";
+pub const BANNER_PATTERN_REGISTRY: &str = "minecraft:banner_pattern";
+pub const ENCHANTMENT_REGISTRY: &str = "minecraft:enchantment";
+pub const PAINTING_VARIANT_REGISTRY: &str = "minecraft:painting_variant";
+pub const WOLF_VARIANT_REGISTRY: &str = "minecraft:wolf_variant";
+pub const JUKEBOX_SONG_REGISTRY: &str = "minecraft:jukebox_song";
+
+/// The entries of each registry received so far, in the order they were
+/// sent. Registry names are stored with their namespace.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryStore{
+    registries: BTreeMap<String, Vec<RegistryDataEntry>>
+}
+
+impl RegistryStore{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the entries of a registry, replacing the ones of an earlier
+    /// configuration.
+    pub fn insert(&mut self, registry: Identifier, entries: Vec<RegistryDataEntry>) -> &mut Self {
+        self.registries.insert(registry.to_string(), entries);
+        self
+    }
+
+    /// The entries of a registry. Names without namespace are looked up in `minecraft`.
+    pub fn entries(&self, registry: &str) -> Option<&[RegistryDataEntry]> {
+        self.registries.get(&Identifier::parse(registry).ok()?.to_string()).map(Vec::as_slice)
+    }
+
+    /// The entry with the given network id.
+    pub fn entry(&self, registry: &str, id: i32) -> Option<&RegistryDataEntry> {
+        if id < 0 {
+            return None;
+        }
+        self.entries(registry)?.get(id as usize)
+    }
+
+    /// The network id of an entry, e.g. `registries.id_of(ENCHANTMENT_REGISTRY, "sharpness")`.
+    pub fn id_of(&self, registry: &str, entry: &str) -> Option<i32> {
+        let entry = Identifier::parse(entry).ok()?;
+        self.entries(registry)?.iter().position(|candidate| candidate.id == entry).map(|id| id as i32)
+    }
+
+    /// The data of an entry, if the server sent it rather than relying on a known pack.
+    pub fn data(&self, registry: &str, entry: &str) -> Option<&Tag> {
+        let entry = Identifier::parse(entry).ok()?;
+        self.entries(registry)?.iter().find(|candidate| candidate.id == entry)?.data()
+    }
+
+    /// A dimension type by name. JoinGame and Respawn send its network id since
+    /// 1.20.5, see `entry`.
+    pub fn dimension_type(&self, name: &str) -> Option<std::io::Result<DimensionType>> {
+        self.data(DIMENSION_TYPE_REGISTRY, name).map(DimensionType::from_nbt)
+    }
+
+    pub fn registries(&self) -> impl Iterator<Item = &str> {
+        self.registries.keys().map(String::as_str)
+    }
+
+    pub fn clear(&mut self) {
+        self.registries.clear();
+    }
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_21{
+    use super::RegistryStore;
+    use crate::protocol::implementation::steven::v1_21::Proto_1_21;
+
+    impl RegistryStore{
+        /// Stores RegistryData, returning whether the packet was one.
+        pub fn handle(&mut self, packet: &Proto_1_21) -> bool {
+            match packet {
+                Proto_1_21::RegistryData(data) => {
+                    self.insert(data.registry_id.clone(), data.entries.clone());
+                    true
+                }
+                _ => false
+            }
+        }
+    }
+}
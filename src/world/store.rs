@@ -0,0 +1,249 @@
+//! The loaded chunks of a world, kept up to date with ChunkData, ChunkUnload,
+//! BlockChange and MultiBlockChange.
+use crate::world::chunk::ChunkColumn;
+use std::collections::HashMap;
+
+/// The parts of a chunk that `World` does not interpret, kept so the chunk
+/// can be sent again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkExtras{
+    /// The heightmaps NBT as sent, a single TAG_End byte for none.
+    pub heightmaps: Vec<u8>,
+    /// Global biome ids, see `BiomeGrid::from_ids`.
+    pub biomes: Vec<i32>,
+    /// Each block entity NBT as sent.
+    pub block_entities: Vec<Vec<u8>>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk{
+    pub blocks: ChunkColumn,
+    pub extras: ChunkExtras
+}
+
+#[derive(Debug, Clone)]
+pub struct World{
+    min_y: i32,
+    height: u32,
+    chunks: HashMap<(i32, i32), Chunk>
+}
+
+impl Default for World{
+    /// The 1.17 overworld, from y 0 up to 256.
+    fn default() -> Self {
+        Self::new(0, 256)
+    }
+}
+
+impl World{
+    /// A world with the given dimension bounds, which are multiples of 16.
+    pub fn new(min_y: i32, height: u32) -> Self {
+        World{ min_y, height, chunks: HashMap::new() }
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Amount of sections of each column.
+    pub fn section_count(&self) -> usize {
+        (self.height / 16) as usize
+    }
+
+    /// Decodes and stores a chunk, replacing a loaded one at the same position.
+    pub fn load(&mut self, x: i32, z: i32, bitmask: &[i64], data: &[u8], extras: ChunkExtras) -> std::io::Result<&Chunk> {
+        let blocks = ChunkColumn::decode(x, z, self.min_y >> 4, self.section_count(), bitmask, data)?;
+        self.chunks.insert((x, z), Chunk{ blocks, extras });
+        Ok(&self.chunks[&(x, z)])
+    }
+
+    /// Stores an already decoded column at its own position, replacing a loaded one.
+    pub fn insert(&mut self, blocks: ChunkColumn, extras: ChunkExtras) -> &Chunk {
+        let position = (blocks.x, blocks.z);
+        self.chunks.insert(position, Chunk{ blocks, extras });
+        &self.chunks[&position]
+    }
+
+    /// Stores an empty chunk, for building a world instead of receiving one.
+    pub fn insert_empty(&mut self, x: i32, z: i32) -> &mut Chunk {
+        let blocks = ChunkColumn::new(x, z, self.min_y >> 4, self.section_count());
+        let extras = ChunkExtras{
+            heightmaps: vec![0],
+            biomes: vec![0; self.section_count() * 64],
+            block_entities: Vec::new()
+        };
+        self.chunks.insert((x, z), Chunk{ blocks, extras });
+        self.chunks.get_mut(&(x, z)).expect("inserted above")
+    }
+
+    pub fn unload(&mut self, x: i32, z: i32) -> Option<Chunk> {
+        self.chunks.remove(&(x, z))
+    }
+
+    /// Forgets all chunks, as a client does on Respawn.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    pub fn chunk(&self, x: i32, z: i32) -> Option<&Chunk> {
+        self.chunks.get(&(x, z))
+    }
+
+    pub fn chunk_mut(&mut self, x: i32, z: i32) -> Option<&mut Chunk> {
+        self.chunks.get_mut(&(x, z))
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    pub fn is_loaded(&self, x: i32, z: i32) -> bool {
+        self.chunks.contains_key(&(x, z))
+    }
+
+    /// The block state at absolute coordinates, `None` if its chunk is not
+    /// loaded or y is outside the world.
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<i32> {
+        self.chunks.get(&(x >> 4, z >> 4))?.blocks.get(x, y, z)
+    }
+
+    /// Sets a block state, returning the previous one. Blocks in chunks that
+    /// are not loaded are ignored, like the vanilla client does.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: i32) -> Option<i32> {
+        self.chunks.get_mut(&(x >> 4, z >> 4))?.blocks.set(x, y, z, state)
+    }
+
+    /// Applies the records of a MultiBlockChange to the section at the packed position.
+    pub fn apply_multi_block_change(&mut self, section_position: u64, records: &[i64]) {
+        let (section_x, section_y, section_z) = unpack_section_position(section_position);
+        for &record in records {
+            let state = (record >> 12) as i32;
+            let x = (section_x << 4) | ((record >> 8) & 0xF) as i32;
+            let z = (section_z << 4) | ((record >> 4) & 0xF) as i32;
+            let y = (section_y << 4) | (record & 0xF) as i32;
+            self.set_block(x, y, z, state);
+        }
+    }
+}
+
+/// Section coordinates of a MultiBlockChange: 22 bits x, 22 bits z and 20 bits y.
+pub fn unpack_section_position(position: u64) -> (i32, i32, i32) {
+    let position = position as i64;
+    ((position >> 42) as i32, (position << 44 >> 44) as i32, (position << 22 >> 42) as i32)
+}
+
+#[cfg(feature = "steven_shared")]
+mod v1_17{
+    use super::{Chunk, ChunkExtras, World};
+    use crate::protocol::implementation::steven::v1_17::{ChunkData, Proto_1_17};
+    use crate::segment::Segment;
+    use crate::world::diff::TrackedChunk;
+    use steven_protocol::nbt::NamedTag;
+    use steven_protocol::protocol::{LenPrefixed, LenPrefixedBytes, VarInt, VarLong};
+
+    impl World{
+        /// Applies ChunkData, ChunkUnload, BlockChange, MultiBlockChange and
+        /// Respawn, returning whether the packet was one of them.
+        pub fn handle(&mut self, packet: &Proto_1_17) -> std::io::Result<bool> {
+            match packet {
+                Proto_1_17::ChunkData(chunk) => {
+                    let bitmask: Vec<i64> = chunk.bitmask.data.iter().map(|long| long.0).collect();
+                    let extras = ChunkExtras{
+                        heightmaps: encode(&chunk.heightmaps)?,
+                        biomes: chunk.biomes.data.iter().map(|id| id.0).collect(),
+                        block_entities: chunk.block_entities.data.iter().map(encode).collect::<std::io::Result<_>>()?
+                    };
+                    self.load(chunk.chunk_x, chunk.chunk_z, &bitmask, &chunk.data.data, extras)?;
+                }
+                Proto_1_17::ChunkUnload(unload) => {
+                    self.unload(unload.x, unload.z);
+                }
+                Proto_1_17::BlockChange(change) => {
+                    self.set_block(change.location.x, change.location.y, change.location.z, change.block_id.0);
+                }
+                Proto_1_17::MultiBlockChange(change) => {
+                    let records: Vec<i64> = change.records.data.iter().map(|record| record.0).collect();
+                    self.apply_multi_block_change(change.chunk_section_pos, &records);
+                }
+                Proto_1_17::Respawn(_) => self.clear(),
+                _ => return Ok(false)
+            }
+            Ok(true)
+        }
+
+        /// Encodes a loaded chunk with its current blocks.
+        pub fn chunk_data(&self, x: i32, z: i32) -> Option<std::io::Result<ChunkData>> {
+            self.chunk(x, z).map(Chunk::to_chunk_data)
+        }
+    }
+
+    impl Chunk{
+        pub fn to_chunk_data(&self) -> std::io::Result<ChunkData> {
+            let (bitmask, data) = self.blocks.encode();
+            let mut block_entities = Vec::with_capacity(self.extras.block_entities.len());
+            for nbt in &self.extras.block_entities {
+                block_entities.push(decode::<Option<NamedTag>>(nbt)?);
+            }
+            Ok(ChunkData{
+                chunk_x: self.blocks.x,
+                chunk_z: self.blocks.z,
+                bitmask: LenPrefixed::new(bitmask.into_iter().map(VarLong).collect()),
+                heightmaps: decode(&self.extras.heightmaps)?,
+                biomes: LenPrefixed::new(self.extras.biomes.iter().copied().map(VarInt).collect()),
+                data: LenPrefixedBytes::new(data),
+                block_entities: LenPrefixed::new(block_entities)
+            })
+        }
+    }
+
+    impl TrackedChunk for Chunk{
+        fn chunk_position(&self) -> (i32, i32) {
+            (self.blocks.x, self.blocks.z)
+        }
+
+        fn set_block(&mut self, x: i32, y: i32, z: i32, block_state: i32) {
+            self.blocks.set(x, y, z, block_state);
+        }
+
+        fn chunk_data(&self) -> std::io::Result<ChunkData> {
+            self.to_chunk_data()
+        }
+    }
+
+    fn encode<T: Segment>(value: &T) -> std::io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(value.serialized_len());
+        value.write_to_stream(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn decode<T: Segment>(mut bytes: &[u8]) -> std::io::Result<T> {
+        let mut value = T::default();
+        value.read_from_stream(&mut bytes)?;
+        Ok(value)
+    }
+}
+
+#[cfg(all(test, feature = "steven_shared"))]
+mod tests {
+    use super::*;
+    use crate::protocol::implementation::steven::v1_17::Proto_1_17;
+    use crate::world::diff::{BlockMutation, ChunkDiff};
+
+    #[test]
+    fn empty_chunks_are_resent_whole() {
+        let mut world = World::default();
+        let diff = ChunkDiff{ resend_threshold: 1, ..ChunkDiff::default() };
+        let mutation = BlockMutation{ x: 1, y: 70, z: 2, block_state: 1 };
+        let packets = diff.apply(world.insert_empty(0, 0), &[mutation]).unwrap();
+        assert!(matches!(packets.as_slice(), [Proto_1_17::ChunkData(_)]));
+
+        let mut client = World::default();
+        assert!(client.handle(&packets[0]).unwrap());
+        assert_eq!(client.block_at(1, 70, 2), Some(1));
+        assert_eq!(client.chunk(0, 0), world.chunk(0, 0));
+    }
+}